@@ -0,0 +1,855 @@
+//! GFF3/GTF parsing and a gene → transcript → exon/CDS model on top of it.
+//!
+//! All coordinates are converted to 0-based, half-open ranges on the forward strand,
+//! the strand is kept separately.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{self, BufRead},
+    marker::PhantomData,
+    ops::Range,
+    str::FromStr,
+};
+
+use resource::{RawResource, RawResourceExt};
+
+use crate::location::{ContigPosition, ContigRange, orientation::SequenceOrientation};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AnnotationFormat {
+    Gff3,
+    Gtf,
+}
+
+/// A single line of a GFF3/GTF file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Feature<C = String> {
+    /// Columns 1, 4, and 5.
+    pub range: ContigRange<C>,
+    pub source: String,
+    /// The `type` column (`gene`, `mRNA`, `exon`, `CDS`, ...).
+    pub kind: String,
+    pub score: Option<f64>,
+    pub strand: Option<SequenceOrientation>,
+    pub phase: Option<u8>,
+    /// In file order, values are unescaped.
+    pub attributes: Vec<(String, String)>,
+}
+
+pub struct FeatureReader<R, C = String> {
+    reader: R,
+    format: AnnotationFormat,
+    buf: String,
+    line: usize,
+    _contig: PhantomData<fn() -> C>,
+}
+
+/// Genes with an interval index for position-to-feature queries.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeneAnnotation<C = String> {
+    genes: Vec<Gene<C>>,
+    ids: HashMap<String, usize>,
+    index: BTreeMap<C, Vec<IndexEntry>>,
+}
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct IndexEntry {
+    at: Range<u64>,
+    /// The maximum end of this and all previous entries.
+    max: u64,
+    gene: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Gene<C = String> {
+    pub id: String,
+    pub name: Option<String>,
+    pub biotype: Option<String>,
+    pub range: ContigRange<C>,
+    pub strand: Option<SequenceOrientation>,
+    pub transcripts: Vec<Transcript<C>>,
+}
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transcript<C = String> {
+    pub id: String,
+    pub name: Option<String>,
+    pub biotype: Option<String>,
+    pub range: ContigRange<C>,
+    pub strand: Option<SequenceOrientation>,
+    /// Sorted by genomic position (regardless of strand).
+    pub exons: Vec<Range<u64>>,
+    /// Sorted by genomic position (regardless of strand).
+    pub cds: Vec<CdsSegment>,
+}
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CdsSegment {
+    pub at: Range<u64>,
+    /// Number of bases to skip from the 5' end of the segment to reach the next codon.
+    pub phase: u8,
+}
+
+/// Where a position falls within a transcript.
+///
+/// Indices refer to [Transcript::exons] and [Transcript::cds].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TranscriptRegion {
+    Cds {
+        exon: usize,
+        segment: usize,
+    },
+    /// Exonic, but outside of the CDS (or in a non-coding transcript).
+    Untranslated {
+        exon: usize,
+    },
+    /// Between `exons[after]` and `exons[after + 1]`.
+    Intron {
+        after: usize,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum AnnotationError {
+    #[error("Line {line}: expected 9 columns, found {found}.")]
+    ColumnCount { line: usize, found: usize },
+    #[error("Line {line}: invalid contig {value:?}.")]
+    InvalidContig { line: usize, value: String },
+    #[error("Line {line}: invalid value {value:?} in column {column}.")]
+    InvalidField {
+        line: usize,
+        column: usize,
+        value: String,
+    },
+    #[error("A {kind} feature is missing the {attribute:?} attribute.")]
+    MissingAttribute { kind: String, attribute: String },
+    #[error("Unknown parent {parent:?} referenced by {child:?}.")]
+    UnknownParent { parent: String, child: String },
+}
+impl From<AnnotationError> for io::Error {
+    fn from(e: AnnotationError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, e)
+    }
+}
+
+impl AnnotationFormat {
+    /// Infers the format from a file name, ignoring compression extensions.
+    pub fn infer(filename: &str) -> Option<Self> {
+        let filename = filename
+            .strip_suffix(".gz")
+            .or_else(|| filename.strip_suffix(".bgz"))
+            .unwrap_or(filename);
+        if filename.ends_with(".gff3") || filename.ends_with(".gff") {
+            Some(Self::Gff3)
+        } else if filename.ends_with(".gtf") {
+            Some(Self::Gtf)
+        } else {
+            None
+        }
+    }
+}
+
+impl<C> Feature<C> {
+    pub fn attribute(&self, key: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| &**v)
+    }
+    /// GFF3 attributes like `Parent` can hold several comma-separated values.
+    pub fn attribute_values(&self, key: &str) -> impl Iterator<Item = &str> {
+        self.attribute(key).into_iter().flat_map(|v| v.split(','))
+    }
+
+    fn first_attribute(&self, keys: &[&str]) -> Option<&str> {
+        keys.iter().find_map(|k| self.attribute(k))
+    }
+}
+
+impl<R: BufRead, C> FeatureReader<R, C> {
+    pub fn new(reader: R, format: AnnotationFormat) -> Self {
+        Self {
+            reader,
+            format,
+            buf: String::new(),
+            line: 0,
+            _contig: PhantomData,
+        }
+    }
+}
+impl<R, C> Iterator for FeatureReader<R, C>
+where
+    R: BufRead,
+    C: FromStr,
+{
+    type Item = io::Result<Feature<C>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.buf.clear();
+            match self.reader.read_line(&mut self.buf) {
+                Ok(0) => return None,
+                Ok(_) => {}
+                Err(e) => return Some(Err(e)),
+            }
+            self.line += 1;
+
+            let line = self.buf.trim_end_matches(['\n', '\r']);
+            if line == "##FASTA" {
+                // GFF3 files can embed sequences at the end.
+                return None;
+            }
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            return Some(parse_feature(line, self.line, self.format).map_err(Into::into));
+        }
+    }
+}
+
+impl<C> GeneAnnotation<C> {
+    pub fn genes(&self) -> &[Gene<C>] {
+        &self.genes
+    }
+    pub fn gene(&self, id: &str) -> Option<&Gene<C>> {
+        self.ids.get(id).map(|&i| &self.genes[i])
+    }
+}
+impl<C: Ord + Clone> GeneAnnotation<C> {
+    pub fn new(mut genes: Vec<Gene<C>>) -> Self {
+        genes.sort_by(|a, b| Ord::cmp(&a.range, &b.range).then_with(|| Ord::cmp(&a.id, &b.id)));
+
+        let ids = genes
+            .iter()
+            .enumerate()
+            .map(|(i, g)| (g.id.clone(), i))
+            .collect();
+
+        let mut index: BTreeMap<C, Vec<IndexEntry>> = BTreeMap::new();
+        for (i, gene) in genes.iter().enumerate() {
+            index
+                .entry(gene.range.contig.clone())
+                .or_default()
+                .push(IndexEntry {
+                    at: gene.range.at.clone(),
+                    max: 0,
+                    gene: i,
+                });
+        }
+        for entries in index.values_mut() {
+            // Already sorted by start, as genes are.
+            let mut max = 0;
+            for entry in entries {
+                max = u64::max(max, entry.at.end);
+                entry.max = max;
+            }
+        }
+
+        Self { genes, ids, index }
+    }
+
+    pub fn read(reader: impl BufRead, format: AnnotationFormat) -> io::Result<Self>
+    where
+        C: FromStr,
+    {
+        let features = FeatureReader::<_, C>::new(reader, format).try_collect::<Vec<_>>()?;
+        Ok(Self::from_features(features, format)?)
+    }
+    /// Reads a (possibly compressed) GFF3/GTF file from a resource.
+    pub fn load(resource: impl RawResource, format: AnnotationFormat) -> io::Result<Self>
+    where
+        C: FromStr,
+    {
+        Self::read(resource.decompressed().buffered().read()?, format)
+    }
+
+    /// Assembles gene models.
+    ///
+    /// GFF3 is resolved through `ID`/`Parent`: anything that is the parent of an exon or CDS
+    /// is a transcript, and the parent of a transcript is its gene.
+    /// GTF is resolved through the `gene_id` and `transcript_id` attributes.
+    pub fn from_features(
+        features: impl IntoIterator<Item = Feature<C>>,
+        format: AnnotationFormat,
+    ) -> Result<Self, AnnotationError> {
+        let genes = match format {
+            AnnotationFormat::Gff3 => group_gff3(features)?,
+            AnnotationFormat::Gtf => group_gtf(features)?,
+        };
+        let genes = genes
+            .into_iter()
+            .filter_map(|(id, parts)| parts.build(id))
+            .collect();
+        Ok(Self::new(genes))
+    }
+
+    /// Genes overlapping the range, in order.
+    pub fn genes_overlapping<'a>(
+        &'a self,
+        range: &ContigRange<C>,
+    ) -> impl Iterator<Item = &'a Gene<C>> + use<'a, C> {
+        let Range { start, end } = range.at.clone();
+        let entries = self.index.get(&range.contig).map_or(&[][..], |v| &v[..]);
+
+        let from = entries.partition_point(|e| e.max <= start);
+        let to = entries.partition_point(|e| e.at.start < end);
+
+        entries[from..usize::max(from, to)]
+            .iter()
+            .filter(move |e| e.at.end > start && !(start..end).is_empty())
+            .map(|e| &self.genes[e.gene])
+    }
+    pub fn genes_at<'a>(
+        &'a self,
+        pos: &ContigPosition<C>,
+    ) -> impl Iterator<Item = &'a Gene<C>> + use<'a, C> {
+        self.genes_overlapping(&pos.clone().into())
+    }
+    pub fn transcripts_at<'a>(
+        &'a self,
+        pos: &ContigPosition<C>,
+    ) -> impl Iterator<Item = (&'a Gene<C>, &'a Transcript<C>)> + use<'a, C> {
+        let at = pos.at;
+        self.genes_at(pos).flat_map(move |g| {
+            g.transcripts
+                .iter()
+                .filter(move |t| t.range.at.contains(&at))
+                .map(move |t| (g, t))
+        })
+    }
+}
+
+impl<C> Transcript<C> {
+    pub fn is_coding(&self) -> bool {
+        !self.cds.is_empty()
+    }
+    /// From the first to the last coding base (in genomic order).
+    pub fn coding_range(&self) -> Option<Range<u64>> {
+        let first = self.cds.first()?;
+        let last = self.cds.last()?;
+        Some(first.at.start..last.at.end)
+    }
+    /// The length of the spliced transcript.
+    pub fn exonic_len(&self) -> u64 {
+        self.exons.iter().map(|e| e.end - e.start).sum()
+    }
+    pub fn coding_len(&self) -> u64 {
+        self.cds.iter().map(|c| c.at.end - c.at.start).sum()
+    }
+
+    /// Returns [None] if the position is outside the transcript.
+    pub fn region_at(&self, at: u64) -> Option<TranscriptRegion> {
+        if !self.range.at.contains(&at) {
+            return None;
+        }
+
+        let exon = self.exons.partition_point(|e| e.end <= at);
+        if self.exons.get(exon).is_none_or(|e| !e.contains(&at)) {
+            return Some(TranscriptRegion::Intron {
+                after: exon.checked_sub(1)?,
+            });
+        }
+
+        let segment = self.cds.partition_point(|c| c.at.end <= at);
+        match self.cds.get(segment) {
+            Some(c) if c.at.contains(&at) => Some(TranscriptRegion::Cds { exon, segment }),
+            _ => Some(TranscriptRegion::Untranslated { exon }),
+        }
+    }
+}
+
+struct GeneParts<C> {
+    feature: Option<Feature<C>>,
+    transcripts: BTreeMap<String, TranscriptParts<C>>,
+}
+struct TranscriptParts<C> {
+    feature: Option<Feature<C>>,
+    exons: Vec<Feature<C>>,
+    cds: Vec<Feature<C>>,
+}
+impl<C> Default for GeneParts<C> {
+    fn default() -> Self {
+        Self {
+            feature: None,
+            transcripts: BTreeMap::new(),
+        }
+    }
+}
+impl<C> Default for TranscriptParts<C> {
+    fn default() -> Self {
+        Self {
+            feature: None,
+            exons: vec![],
+            cds: vec![],
+        }
+    }
+}
+impl<C: Ord + Clone> GeneParts<C> {
+    fn build(self, id: String) -> Option<Gene<C>> {
+        let transcripts: Vec<_> = self
+            .transcripts
+            .into_iter()
+            .filter_map(|(id, t)| t.build(id))
+            .collect();
+
+        let (range, strand) = match &self.feature {
+            Some(f) => (f.range.clone(), f.strand),
+            None => {
+                let first = transcripts.first()?;
+                (span(transcripts.iter().map(|t| &t.range))?, first.strand)
+            }
+        };
+        let attribute = |keys: &[&str]| {
+            let f = self.feature.as_ref()?;
+            f.first_attribute(keys).map(|v| v.to_owned())
+        };
+
+        Some(Gene {
+            name: attribute(&["Name", "gene_name"]),
+            biotype: attribute(&["biotype", "gene_biotype", "gene_type"]),
+            id,
+            range,
+            strand,
+            transcripts,
+        })
+    }
+}
+impl<C: Ord + Clone> TranscriptParts<C> {
+    fn build(self, id: String) -> Option<Transcript<C>> {
+        let mut exons: Vec<_> = self.exons.iter().map(|e| e.range.at.clone()).collect();
+        let mut cds: Vec<_> = self
+            .cds
+            .iter()
+            .map(|c| CdsSegment {
+                at: c.range.at.clone(),
+                phase: c.phase.unwrap_or(0),
+            })
+            .collect();
+        exons.sort_by_key(|e| (e.start, e.end));
+        cds.sort_by_key(|c| (c.at.start, c.at.end));
+        if exons.is_empty() {
+            // Some files only list the CDS for coding transcripts.
+            exons = cds.iter().map(|c| c.at.clone()).collect();
+        }
+
+        let (range, strand) = match &self.feature {
+            Some(f) => (f.range.clone(), f.strand),
+            None => {
+                let first = self.exons.first().or(self.cds.first())?;
+                let children = self.exons.iter().chain(&self.cds).map(|f| &f.range);
+                (span(children)?, first.strand)
+            }
+        };
+        let attribute = |keys: &[&str]| {
+            let f = self.feature.as_ref()?;
+            f.first_attribute(keys).map(|v| v.to_owned())
+        };
+
+        Some(Transcript {
+            name: attribute(&["Name", "transcript_name"]),
+            biotype: attribute(&["biotype", "transcript_biotype", "transcript_type"]),
+            id,
+            range,
+            strand,
+            exons,
+            cds,
+        })
+    }
+}
+
+fn group_gff3<C: Ord + Clone>(
+    features: impl IntoIterator<Item = Feature<C>>,
+) -> Result<BTreeMap<String, GeneParts<C>>, AnnotationError> {
+    let mut by_id: HashMap<String, Feature<C>> = HashMap::new();
+    let mut transcripts: BTreeMap<String, TranscriptParts<C>> = BTreeMap::new();
+    let mut genes: BTreeMap<String, GeneParts<C>> = BTreeMap::new();
+
+    for feature in features {
+        match &*feature.kind {
+            "exon" | "CDS" => {
+                let parents: Vec<_> = feature
+                    .attribute_values("Parent")
+                    .map(str::to_owned)
+                    .collect();
+                if parents.is_empty() {
+                    return Err(AnnotationError::MissingAttribute {
+                        kind: feature.kind,
+                        attribute: "Parent".to_owned(),
+                    });
+                }
+                for parent in parents {
+                    let parts = transcripts.entry(parent).or_default();
+                    match &*feature.kind {
+                        "exon" => parts.exons.push(feature.clone()),
+                        _ => parts.cds.push(feature.clone()),
+                    }
+                }
+            }
+            kind => {
+                let is_gene = kind == "gene" || kind.ends_with("_gene") || kind == "pseudogene";
+                if let Some(id) = feature.attribute("ID").map(str::to_owned) {
+                    if is_gene {
+                        genes.entry(id.clone()).or_default();
+                    }
+                    by_id.insert(id, feature);
+                }
+            }
+        }
+    }
+
+    for (id, mut parts) in transcripts {
+        let Some(feature) = by_id.get(&id) else {
+            return Err(AnnotationError::UnknownParent {
+                parent: id,
+                child: "exon".to_owned(),
+            });
+        };
+        parts.feature = Some(feature.clone());
+
+        // Transcripts without a parent stand in for their own gene.
+        let gene = feature
+            .attribute_values("Parent")
+            .next()
+            .unwrap_or(&id)
+            .to_owned();
+        let transcript_id = feature.attribute("transcript_id").unwrap_or(&id).to_owned();
+
+        if !by_id.contains_key(&gene) {
+            return Err(AnnotationError::UnknownParent {
+                parent: gene,
+                child: id,
+            });
+        }
+        genes
+            .entry(gene)
+            .or_default()
+            .transcripts
+            .insert(transcript_id, parts);
+    }
+
+    genes
+        .into_iter()
+        .map(|(id, mut parts)| {
+            let feature = by_id.get(&id).cloned();
+            let id = feature
+                .as_ref()
+                .and_then(|f| f.attribute("gene_id"))
+                .map(str::to_owned)
+                .unwrap_or(id);
+            parts.feature = feature;
+            Ok((id, parts))
+        })
+        .collect()
+}
+
+fn group_gtf<C: Ord + Clone>(
+    features: impl IntoIterator<Item = Feature<C>>,
+) -> Result<BTreeMap<String, GeneParts<C>>, AnnotationError> {
+    let mut genes: BTreeMap<String, GeneParts<C>> = BTreeMap::new();
+
+    for feature in features {
+        let kind = &*feature.kind;
+        if !matches!(kind, "gene" | "transcript" | "exon" | "CDS") {
+            continue;
+        }
+
+        let Some(gene_id) = feature.attribute("gene_id").map(str::to_owned) else {
+            return Err(AnnotationError::MissingAttribute {
+                kind: feature.kind,
+                attribute: "gene_id".to_owned(),
+            });
+        };
+        let gene = genes.entry(gene_id).or_default();
+
+        if kind == "gene" {
+            gene.feature = Some(feature);
+            continue;
+        }
+
+        let Some(transcript_id) = feature.attribute("transcript_id").map(str::to_owned) else {
+            return Err(AnnotationError::MissingAttribute {
+                kind: feature.kind,
+                attribute: "transcript_id".to_owned(),
+            });
+        };
+        let transcript = gene.transcripts.entry(transcript_id).or_default();
+
+        match kind {
+            "transcript" => transcript.feature = Some(feature),
+            "exon" => transcript.exons.push(feature),
+            "CDS" => transcript.cds.push(feature),
+            _ => unreachable!(),
+        }
+    }
+
+    Ok(genes)
+}
+
+fn span<'a, C: Ord + Clone + 'a>(
+    ranges: impl IntoIterator<Item = &'a ContigRange<C>>,
+) -> Option<ContigRange<C>> {
+    let mut ranges = ranges.into_iter();
+    let mut span = ranges.next()?.clone();
+    for range in ranges {
+        if range.contig != span.contig {
+            return None;
+        }
+        span.at = u64::min(span.at.start, range.at.start)..u64::max(span.at.end, range.at.end);
+    }
+    Some(span)
+}
+
+fn parse_feature<C: FromStr>(
+    line: &str,
+    line_number: usize,
+    format: AnnotationFormat,
+) -> Result<Feature<C>, AnnotationError> {
+    let columns: Vec<&str> = line.split('\t').collect();
+    if columns.len() != 9 {
+        return Err(AnnotationError::ColumnCount {
+            line: line_number,
+            found: columns.len(),
+        });
+    }
+
+    let invalid = |column: usize| AnnotationError::InvalidField {
+        line: line_number,
+        column: column + 1,
+        value: columns[column].to_owned(),
+    };
+    let optional = |column: usize| Some(columns[column]).filter(|&v| v != ".");
+
+    let contig = C::from_str(columns[0]).map_err(|_| AnnotationError::InvalidContig {
+        line: line_number,
+        value: columns[0].to_owned(),
+    })?;
+    // 1-based, inclusive.
+    let start: u64 = columns[3].parse().map_err(|_| invalid(3))?;
+    let end: u64 = columns[4].parse().map_err(|_| invalid(4))?;
+    if start == 0 || end < start {
+        return Err(invalid(3));
+    }
+
+    let score = match optional(5) {
+        None => None,
+        Some(v) => Some(v.parse().map_err(|_| invalid(5))?),
+    };
+    let strand = match optional(6) {
+        None | Some("?") => None,
+        Some("+") => Some(SequenceOrientation::Forward),
+        Some("-") => Some(SequenceOrientation::Reverse),
+        Some(_) => return Err(invalid(6)),
+    };
+    let phase = match optional(7) {
+        None => None,
+        Some(v) => Some(
+            v.parse()
+                .ok()
+                .filter(|&p: &u8| p < 3)
+                .ok_or_else(|| invalid(7))?,
+        ),
+    };
+    let attributes = match format {
+        AnnotationFormat::Gff3 => parse_gff3_attributes(columns[8]),
+        AnnotationFormat::Gtf => parse_gtf_attributes(columns[8]),
+    }
+    .ok_or_else(|| invalid(8))?;
+
+    Ok(Feature {
+        range: ContigRange {
+            contig,
+            at: (start - 1)..end,
+        },
+        source: columns[1].to_owned(),
+        kind: columns[2].to_owned(),
+        score,
+        strand,
+        phase,
+        attributes,
+    })
+}
+
+/// `key=value;key=value`, with percent-encoding.
+fn parse_gff3_attributes(s: &str) -> Option<Vec<(String, String)>> {
+    if s == "." {
+        return Some(vec![]);
+    }
+    s.split(';')
+        .map(str::trim)
+        .filter(|kv| !kv.is_empty())
+        .map(|kv| {
+            let (k, v) = kv.split_once('=')?;
+            Some((percent_decode(k)?, percent_decode(v)?))
+        })
+        .collect()
+}
+/// `key "value"; key "value";`
+fn parse_gtf_attributes(s: &str) -> Option<Vec<(String, String)>> {
+    if s == "." {
+        return Some(vec![]);
+    }
+    s.split(';')
+        .map(str::trim)
+        .filter(|kv| !kv.is_empty())
+        .map(|kv| {
+            let (k, v) = kv.split_once(' ')?;
+            let v = v.trim();
+            let v = v
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .unwrap_or(v);
+            Some((k.to_owned(), v.to_owned()))
+        })
+        .collect()
+}
+fn percent_decode(s: &str) -> Option<String> {
+    if !s.contains('%') {
+        return Some(s.to_owned());
+    }
+    let mut out = Vec::with_capacity(s.len());
+    let mut bytes = s.bytes();
+    while let Some(b) = bytes.next() {
+        if b == b'%' {
+            let hex = [bytes.next()?, bytes.next()?];
+            out.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            out.push(b);
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::location::{ContigPosition, ContigRange, orientation::SequenceOrientation};
+
+    use super::{AnnotationFormat, CdsSegment, GeneAnnotation, TranscriptRegion};
+
+    const GFF3: &str = "\
+##gff-version 3
+chr1\tsrc\tgene\t1000\t9000\t.\t+\t.\tID=gene:G1;Name=ONE;biotype=protein_coding;gene_id=G1
+chr1\tsrc\tmRNA\t1000\t9000\t.\t+\t.\tID=transcript:T1;Parent=gene:G1;transcript_id=T1
+chr1\tsrc\texon\t1000\t2000\t.\t+\t.\tParent=transcript:T1
+chr1\tsrc\texon\t5000\t9000\t.\t+\t.\tParent=transcript:T1
+chr1\tsrc\tCDS\t1500\t2000\t.\t+\t0\tParent=transcript:T1
+chr1\tsrc\tCDS\t5000\t6000\t.\t+\t2\tParent=transcript:T1
+chr1\tsrc\tncRNA_gene\t8000\t8500\t.\t-\t.\tID=gene:G2;Name=TW%3BO
+chr1\tsrc\tlnc_RNA\t8000\t8500\t.\t-\t.\tID=transcript:T2;Parent=gene:G2
+chr1\tsrc\texon\t8000\t8500\t.\t-\t.\tParent=transcript:T2
+##FASTA
+>chr1
+ACGT
+";
+
+    const GTF: &str = "\
+chr1\tsrc\tgene\t1000\t9000\t.\t+\t.\tgene_id \"G1\"; gene_name \"ONE\";
+chr1\tsrc\texon\t1000\t2000\t.\t+\t.\tgene_id \"G1\"; transcript_id \"T1\";
+chr1\tsrc\texon\t5000\t9000\t.\t+\t.\tgene_id \"G1\"; transcript_id \"T1\";
+chr1\tsrc\tCDS\t1500\t2000\t.\t+\t0\tgene_id \"G1\"; transcript_id \"T1\";
+chr1\tsrc\tCDS\t5000\t6000\t.\t+\t2\tgene_id \"G1\"; transcript_id \"T1\";
+chr1\tsrc\tstart_codon\t1500\t1502\t.\t+\t0\tgene_id \"G1\"; transcript_id \"T1\";
+";
+
+    fn pos(at: u64) -> ContigPosition {
+        ContigPosition {
+            contig: "chr1".to_owned(),
+            at,
+        }
+    }
+
+    #[test]
+    fn gff3() {
+        let annotation =
+            GeneAnnotation::<String>::read(Cursor::new(GFF3), AnnotationFormat::Gff3).unwrap();
+
+        assert_eq!(annotation.genes().len(), 2);
+
+        let g1 = annotation.gene("G1").unwrap();
+        assert_eq!(g1.name.as_deref(), Some("ONE"));
+        assert_eq!(g1.biotype.as_deref(), Some("protein_coding"));
+        assert_eq!(g1.range.at, 999..9000);
+        let t1 = &g1.transcripts[0];
+        assert_eq!(t1.id, "T1");
+        assert_eq!(t1.exons, vec![999..2000, 4999..9000]);
+        assert_eq!(
+            t1.cds,
+            vec![
+                CdsSegment {
+                    at: 1499..2000,
+                    phase: 0
+                },
+                CdsSegment {
+                    at: 4999..6000,
+                    phase: 2
+                },
+            ]
+        );
+
+        let g2 = annotation.gene("gene:G2").unwrap();
+        assert_eq!(g2.name.as_deref(), Some("TW;O"));
+        assert_eq!(g2.strand, Some(SequenceOrientation::Reverse));
+    }
+
+    #[test]
+    fn gtf_matches_gff3() {
+        let gff3 =
+            GeneAnnotation::<String>::read(Cursor::new(GFF3), AnnotationFormat::Gff3).unwrap();
+        let gtf = GeneAnnotation::<String>::read(Cursor::new(GTF), AnnotationFormat::Gtf).unwrap();
+
+        let a = gff3.gene("G1").unwrap();
+        let b = gtf.gene("G1").unwrap();
+        assert_eq!(a.range, b.range);
+        assert_eq!(a.name, b.name);
+        assert_eq!(a.transcripts[0].exons, b.transcripts[0].exons);
+        assert_eq!(a.transcripts[0].cds, b.transcripts[0].cds);
+    }
+
+    #[test]
+    fn queries() {
+        let annotation =
+            GeneAnnotation::<String>::read(Cursor::new(GFF3), AnnotationFormat::Gff3).unwrap();
+
+        let ids = |at| {
+            annotation
+                .genes_at(&pos(at))
+                .map(|g| g.id.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ids(998), Vec::<String>::new());
+        assert_eq!(ids(999), vec!["G1"]);
+        assert_eq!(ids(8200), vec!["G1", "gene:G2"]);
+        assert_eq!(ids(8999), vec!["G1"]);
+        assert_eq!(ids(9000), Vec::<String>::new());
+
+        let overlapping = annotation.genes_overlapping(&ContigRange {
+            contig: "chr1".to_owned(),
+            at: 8500..10_000,
+        });
+        assert_eq!(overlapping.count(), 1);
+
+        let (_, t1) = annotation.transcripts_at(&pos(1200)).next().unwrap();
+        assert_eq!(
+            t1.region_at(1200),
+            Some(TranscriptRegion::Untranslated { exon: 0 })
+        );
+        assert_eq!(
+            t1.region_at(1600),
+            Some(TranscriptRegion::Cds {
+                exon: 0,
+                segment: 0
+            })
+        );
+        assert_eq!(
+            t1.region_at(3000),
+            Some(TranscriptRegion::Intron { after: 0 })
+        );
+        assert_eq!(
+            t1.region_at(5500),
+            Some(TranscriptRegion::Cds {
+                exon: 1,
+                segment: 1
+            })
+        );
+        assert_eq!(t1.region_at(9000), None);
+    }
+}
@@ -0,0 +1,665 @@
+//! BAM alignments, decoded into typed records with 0-based, half-open coordinates.
+
+pub mod pileup;
+
+use std::{
+    fmt,
+    io::{self, Read, Seek},
+    ops::Range,
+    str::FromStr,
+};
+
+use noodles::{
+    core::region::Interval,
+    csi::BinningIndex,
+    sam::alignment::record::{
+        cigar::op::Kind,
+        data::field::{Value, value::Array},
+    },
+};
+use resource::RawResource;
+use utile::range::RangeExt;
+
+use crate::location::{ContigPosition, ContigRange, orientation::SequenceOrientation};
+
+pub struct BamReader<R, C = String> {
+    header: noodles::sam::Header,
+    reader: noodles::bam::io::Reader<noodles::bgzf::io::Reader<R>>,
+    record: noodles::bam::Record,
+    contigs: Contigs<C>,
+}
+
+/// A [BamReader] with a `.bai` or `.csi` index, for region queries.
+pub struct IndexedBamReader<R, C = String> {
+    reader: BamReader<R, C>,
+    index: BamIndex,
+}
+
+#[derive(Debug)]
+pub enum BamIndex {
+    Bai(noodles::bam::bai::Index),
+    Csi(noodles::csi::Index),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlignmentRecord<C = String> {
+    /// The read name (`QNAME`).
+    pub name: Option<String>,
+    pub flags: Flags,
+    /// The first aligned base (0-based), [None] for unplaced reads.
+    pub at: Option<ContigPosition<C>>,
+    /// [None] if unavailable (255).
+    pub mapping_quality: Option<u8>,
+    pub cigar: Vec<CigarOp>,
+    /// The first aligned base of the mate (0-based).
+    pub mate: Option<ContigPosition<C>>,
+    pub template_length: i32,
+    /// ASCII bases, as stored (on the forward strand).
+    pub sequence: Vec<u8>,
+    /// Phred scores, without the ASCII offset.
+    pub quality_scores: Vec<u8>,
+    pub tags: Vec<([u8; 2], TagValue)>,
+}
+
+/// The `FLAG` bitset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Flags(pub u16);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CigarOp {
+    pub kind: CigarKind,
+    pub len: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CigarKind {
+    /// `M`
+    Match,
+    /// `I`
+    Insertion,
+    /// `D`
+    Deletion,
+    /// `N`
+    Skip,
+    /// `S`
+    SoftClip,
+    /// `H`
+    HardClip,
+    /// `P`
+    Pad,
+    /// `=`
+    SequenceMatch,
+    /// `X`
+    SequenceMismatch,
+}
+
+/// Integer types are widened, the original width isn't kept.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TagValue {
+    Character(u8),
+    Int(i64),
+    Float(f32),
+    String(String),
+    Hex(String),
+    IntArray(Vec<i64>),
+    FloatArray(Vec<f32>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum BamError {
+    #[error("Invalid contig {0:?}.")]
+    InvalidContig(String),
+    #[error("Reference sequence {0} is missing from the header.")]
+    UnknownReference(usize),
+}
+impl From<BamError> for io::Error {
+    fn from(e: BamError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, e)
+    }
+}
+
+/// Contigs in header order, [Err] if the name couldn't be parsed.
+pub(crate) struct Contigs<C>(Vec<Result<C, String>>);
+
+impl<R: Read, C: FromStr> BamReader<R, C> {
+    pub fn new(reader: R) -> io::Result<Self> {
+        let mut reader = noodles::bam::io::Reader::new(reader);
+        let header = reader.read_header()?;
+        let contigs = Contigs::new(&header);
+        Ok(Self {
+            header,
+            reader,
+            record: noodles::bam::Record::default(),
+            contigs,
+        })
+    }
+}
+impl<R, C> BamReader<R, C> {
+    pub fn header(&self) -> &noodles::sam::Header {
+        &self.header
+    }
+    /// Contig names and lengths, in header order.
+    pub fn contigs(&self) -> impl Iterator<Item = (&str, u64)> {
+        contig_lengths(&self.header)
+    }
+}
+impl<R, C> Iterator for BamReader<R, C>
+where
+    R: Read,
+    C: Clone,
+{
+    type Item = io::Result<AlignmentRecord<C>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.reader.read_record(&mut self.record) {
+            Ok(0) => None,
+            Ok(_) => Some(self.contigs.decode(&self.header, &self.record)),
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+/// Opens a BAM file from a resource (BAM is always BGZF-compressed).
+pub fn load<C: FromStr>(resource: impl RawResource) -> io::Result<BamReader<impl Read, C>> {
+    BamReader::new(resource.read()?)
+}
+
+impl<R: Read, C: FromStr> IndexedBamReader<R, C> {
+    pub fn new(reader: R, index: BamIndex) -> io::Result<Self> {
+        Ok(Self {
+            reader: BamReader::new(reader)?,
+            index,
+        })
+    }
+    pub fn new_bai(reader: R, index: impl Read) -> io::Result<Self> {
+        Self::new(reader, BamIndex::read_bai(index)?)
+    }
+    pub fn new_csi(reader: R, index: impl Read) -> io::Result<Self> {
+        Self::new(reader, BamIndex::read_csi(index)?)
+    }
+}
+impl<R, C> IndexedBamReader<R, C> {
+    pub fn header(&self) -> &noodles::sam::Header {
+        &self.reader.header
+    }
+    pub fn index(&self) -> &BamIndex {
+        &self.index
+    }
+    /// Returns the underlying reader, for sequential access.
+    pub fn reader_mut(&mut self) -> &mut BamReader<R, C> {
+        &mut self.reader
+    }
+    pub fn into_reader(self) -> BamReader<R, C> {
+        self.reader
+    }
+
+    /// Records overlapping the range, in file order.
+    ///
+    /// Unmapped reads placed next to their mate are skipped.
+    /// Empty ranges are an [io::ErrorKind::InvalidInput] error.
+    pub fn query<Q: AsRef<str>>(&mut self, at: &ContigRange<Q>) -> io::Result<Query<'_, R, C>>
+    where
+        R: Read + Seek,
+    {
+        let name = at.contig.as_ref();
+        let reference_sequence_id = self
+            .reader
+            .header
+            .reference_sequences()
+            .get_index_of(name.as_bytes())
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("region reference sequence does not exist in the header: {name:?}"),
+                )
+            })?;
+        let interval = query_interval(at)?;
+        let chunks = match &self.index {
+            BamIndex::Bai(index) => index.query(reference_sequence_id, interval)?,
+            BamIndex::Csi(index) => index.query(reference_sequence_id, interval)?,
+        };
+
+        let BamReader {
+            header,
+            reader,
+            contigs,
+            ..
+        } = &mut self.reader;
+        Ok(Query {
+            reader: noodles::bam::io::Reader::from(noodles::csi::io::Query::new(
+                reader.get_mut(),
+                chunks,
+            )),
+            record: noodles::bam::Record::default(),
+            header,
+            contigs,
+            reference_sequence_id,
+            range: at.at.clone(),
+        })
+    }
+}
+
+fn query_interval<Q: AsRef<str>>(at: &ContigRange<Q>) -> io::Result<Interval> {
+    Interval::try_from(at).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+}
+
+impl BamIndex {
+    pub fn read_bai(index: impl Read) -> io::Result<Self> {
+        Ok(Self::Bai(
+            noodles::bam::bai::io::Reader::new(index).read_index()?,
+        ))
+    }
+    pub fn read_csi(index: impl Read) -> io::Result<Self> {
+        Ok(Self::Csi(
+            noodles::csi::io::Reader::new(index).read_index()?,
+        ))
+    }
+    /// Picks the index format from the file name (`.bai` or `.csi`).
+    pub fn load(resource: impl RawResource) -> io::Result<Self> {
+        let key = resource.key();
+        if key.ends_with(".bai") {
+            Self::read_bai(resource.read()?)
+        } else if key.ends_with(".csi") {
+            Self::read_csi(resource.read()?)
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Unknown BAM index format: {key:?}."),
+            ))
+        }
+    }
+}
+
+pub struct Query<'r, R, C> {
+    reader: noodles::bam::io::Reader<noodles::csi::io::Query<'r, noodles::bgzf::io::Reader<R>>>,
+    record: noodles::bam::Record,
+    header: &'r noodles::sam::Header,
+    contigs: &'r Contigs<C>,
+
+    reference_sequence_id: usize,
+    range: Range<u64>,
+}
+impl<R, C> Iterator for Query<'_, R, C>
+where
+    R: Read + Seek,
+    C: Clone,
+{
+    type Item = io::Result<AlignmentRecord<C>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.reader.read_record(&mut self.record) {
+                Ok(0) => return None,
+                Ok(_) => {}
+                Err(e) => return Some(Err(e)),
+            }
+
+            match self.record.reference_sequence_id().transpose() {
+                Ok(Some(id)) if id == self.reference_sequence_id => {}
+                Ok(_) => continue,
+                Err(e) => return Some(Err(e)),
+            }
+
+            let record = match self.contigs.decode(self.header, &self.record) {
+                Ok(record) => record,
+                Err(e) => return Some(Err(e)),
+            };
+            if record
+                .range()
+                .is_some_and(|range| range.at.overlaps(&self.range))
+            {
+                return Some(Ok(record));
+            }
+        }
+    }
+}
+
+impl<C> AlignmentRecord<C> {
+    pub fn is_mapped(&self) -> bool {
+        !self.flags.is_unmapped() && self.at.is_some()
+    }
+    pub fn strand(&self) -> SequenceOrientation {
+        if self.flags.is_reverse() {
+            SequenceOrientation::Reverse
+        } else {
+            SequenceOrientation::Forward
+        }
+    }
+    /// The number of reference bases covered by the alignment.
+    pub fn reference_len(&self) -> u64 {
+        self.cigar
+            .iter()
+            .filter(|op| op.kind.consumes_reference())
+            .map(|op| u64::from(op.len))
+            .sum()
+    }
+    /// The reference range covered by the alignment, [None] for unmapped reads.
+    pub fn range(&self) -> Option<ContigRange<C>>
+    where
+        C: Clone,
+    {
+        if !self.is_mapped() {
+            return None;
+        }
+        let ContigPosition { contig, at } = self.at.clone()?;
+        Some(ContigRange {
+            contig,
+            at: at..at + self.reference_len().max(1),
+        })
+    }
+    pub fn tag(&self, tag: &[u8; 2]) -> Option<&TagValue> {
+        self.tags.iter().find(|(t, _)| t == tag).map(|(_, v)| v)
+    }
+
+    pub fn map_contig<NewContig>(
+        self,
+        mut f: impl FnMut(C) -> NewContig,
+    ) -> AlignmentRecord<NewContig> {
+        AlignmentRecord {
+            name: self.name,
+            flags: self.flags,
+            at: self.at.map(|at| at.map_contig(&mut f)),
+            mapping_quality: self.mapping_quality,
+            cigar: self.cigar,
+            mate: self.mate.map(|at| at.map_contig(&mut f)),
+            template_length: self.template_length,
+            sequence: self.sequence,
+            quality_scores: self.quality_scores,
+            tags: self.tags,
+        }
+    }
+}
+
+impl Flags {
+    pub const PAIRED: u16 = 0x1;
+    pub const PROPERLY_PAIRED: u16 = 0x2;
+    pub const UNMAPPED: u16 = 0x4;
+    pub const MATE_UNMAPPED: u16 = 0x8;
+    pub const REVERSE: u16 = 0x10;
+    pub const MATE_REVERSE: u16 = 0x20;
+    pub const FIRST_SEGMENT: u16 = 0x40;
+    pub const LAST_SEGMENT: u16 = 0x80;
+    pub const SECONDARY: u16 = 0x100;
+    pub const QC_FAIL: u16 = 0x200;
+    pub const DUPLICATE: u16 = 0x400;
+    pub const SUPPLEMENTARY: u16 = 0x800;
+
+    pub fn contains(self, flags: u16) -> bool {
+        self.0 & flags == flags
+    }
+    pub fn is_paired(self) -> bool {
+        self.contains(Self::PAIRED)
+    }
+    pub fn is_unmapped(self) -> bool {
+        self.contains(Self::UNMAPPED)
+    }
+    pub fn is_reverse(self) -> bool {
+        self.contains(Self::REVERSE)
+    }
+    pub fn is_secondary(self) -> bool {
+        self.contains(Self::SECONDARY)
+    }
+    pub fn is_supplementary(self) -> bool {
+        self.contains(Self::SUPPLEMENTARY)
+    }
+    pub fn is_duplicate(self) -> bool {
+        self.contains(Self::DUPLICATE)
+    }
+    pub fn is_qc_fail(self) -> bool {
+        self.contains(Self::QC_FAIL)
+    }
+    /// Not secondary nor supplementary.
+    pub fn is_primary(self) -> bool {
+        !self.is_secondary() && !self.is_supplementary()
+    }
+}
+
+impl CigarKind {
+    pub fn consumes_reference(self) -> bool {
+        matches!(
+            self,
+            Self::Match
+                | Self::Deletion
+                | Self::Skip
+                | Self::SequenceMatch
+                | Self::SequenceMismatch
+        )
+    }
+    pub fn consumes_read(self) -> bool {
+        matches!(
+            self,
+            Self::Match
+                | Self::Insertion
+                | Self::SoftClip
+                | Self::SequenceMatch
+                | Self::SequenceMismatch
+        )
+    }
+    pub fn to_char(self) -> char {
+        match self {
+            Self::Match => 'M',
+            Self::Insertion => 'I',
+            Self::Deletion => 'D',
+            Self::Skip => 'N',
+            Self::SoftClip => 'S',
+            Self::HardClip => 'H',
+            Self::Pad => 'P',
+            Self::SequenceMatch => '=',
+            Self::SequenceMismatch => 'X',
+        }
+    }
+}
+impl From<Kind> for CigarKind {
+    fn from(kind: Kind) -> Self {
+        match kind {
+            Kind::Match => Self::Match,
+            Kind::Insertion => Self::Insertion,
+            Kind::Deletion => Self::Deletion,
+            Kind::Skip => Self::Skip,
+            Kind::SoftClip => Self::SoftClip,
+            Kind::HardClip => Self::HardClip,
+            Kind::Pad => Self::Pad,
+            Kind::SequenceMatch => Self::SequenceMatch,
+            Kind::SequenceMismatch => Self::SequenceMismatch,
+        }
+    }
+}
+impl fmt::Display for CigarOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.len, self.kind.to_char())
+    }
+}
+
+impl<C: FromStr> Contigs<C> {
+    pub(crate) fn new(header: &noodles::sam::Header) -> Self {
+        let contigs = contig_lengths(header)
+            .map(|(name, _)| C::from_str(name).map_err(|_| name.to_owned()))
+            .collect();
+        Self(contigs)
+    }
+}
+impl<C: Clone> Contigs<C> {
+    fn get(&self, id: usize) -> Result<C, BamError> {
+        match self.0.get(id) {
+            Some(Ok(contig)) => Ok(contig.clone()),
+            Some(Err(name)) => Err(BamError::InvalidContig(name.clone())),
+            None => Err(BamError::UnknownReference(id)),
+        }
+    }
+    fn position(
+        &self,
+        id: Option<io::Result<usize>>,
+        start: Option<io::Result<noodles::core::Position>>,
+    ) -> io::Result<Option<ContigPosition<C>>> {
+        let (Some(id), Some(start)) = (id, start) else {
+            return Ok(None);
+        };
+        let at = u64::try_from(usize::from(start?)).unwrap() - 1;
+        Ok(Some(ContigPosition {
+            contig: self.get(id?)?,
+            at,
+        }))
+    }
+
+    /// Decodes any SAM-like record (BAM, CRAM).
+    pub(crate) fn decode(
+        &self,
+        header: &noodles::sam::Header,
+        record: &impl noodles::sam::alignment::Record,
+    ) -> io::Result<AlignmentRecord<C>> {
+        let cigar = record
+            .cigar()
+            .iter()
+            .map(|op| {
+                let op = op?;
+                Ok(CigarOp {
+                    kind: op.kind().into(),
+                    len: u32::try_from(op.len()).unwrap(),
+                })
+            })
+            .try_collect::<Vec<_>>()?;
+        let tags = record
+            .data()
+            .iter()
+            .map(|field| {
+                let (tag, value) = field?;
+                Ok((*tag.as_ref(), TagValue::decode(value)?))
+            })
+            .try_collect::<Vec<_>>()?;
+
+        Ok(AlignmentRecord {
+            name: record
+                .name()
+                .map(|name| String::from_utf8_lossy(name).into_owned()),
+            flags: Flags(record.flags()?.bits()),
+            at: self.position(
+                record.reference_sequence_id(header),
+                record.alignment_start(),
+            )?,
+            mapping_quality: record.mapping_quality().transpose()?.map(|q| q.get()),
+            cigar,
+            mate: self.position(
+                record.mate_reference_sequence_id(header),
+                record.mate_alignment_start(),
+            )?,
+            template_length: record.template_length()?,
+            sequence: record.sequence().iter().collect(),
+            quality_scores: record.quality_scores().iter().try_collect::<Vec<_>>()?,
+            tags,
+        })
+    }
+}
+
+impl TagValue {
+    fn decode(value: Value<'_>) -> io::Result<Self> {
+        fn ints<T: Into<i64>>(values: impl Iterator<Item = io::Result<T>>) -> io::Result<TagValue> {
+            let values = values.map(|v| v.map(Into::into));
+            Ok(TagValue::IntArray(values.try_collect::<Vec<_>>()?))
+        }
+
+        Ok(match value {
+            Value::Character(c) => Self::Character(c),
+            Value::Int8(v) => Self::Int(v.into()),
+            Value::UInt8(v) => Self::Int(v.into()),
+            Value::Int16(v) => Self::Int(v.into()),
+            Value::UInt16(v) => Self::Int(v.into()),
+            Value::Int32(v) => Self::Int(v.into()),
+            Value::UInt32(v) => Self::Int(v.into()),
+            Value::Float(v) => Self::Float(v),
+            Value::String(s) => Self::String(String::from_utf8_lossy(s).into_owned()),
+            Value::Hex(s) => Self::Hex(String::from_utf8_lossy(s).into_owned()),
+            Value::Array(Array::Int8(values)) => ints(values.iter())?,
+            Value::Array(Array::UInt8(values)) => ints(values.iter())?,
+            Value::Array(Array::Int16(values)) => ints(values.iter())?,
+            Value::Array(Array::UInt16(values)) => ints(values.iter())?,
+            Value::Array(Array::Int32(values)) => ints(values.iter())?,
+            Value::Array(Array::UInt32(values)) => ints(values.iter())?,
+            Value::Array(Array::Float(values)) => {
+                Self::FloatArray(values.iter().try_collect::<Vec<_>>()?)
+            }
+        })
+    }
+}
+
+fn contig_lengths(header: &noodles::sam::Header) -> impl Iterator<Item = (&str, u64)> {
+    header.reference_sequences().iter().map(|(name, sequence)| {
+        let name = std::str::from_utf8(name).unwrap_or_default();
+        let len = usize::from(sequence.length());
+        (name, u64::try_from(len).unwrap())
+    })
+}
+
+impl<R, C> fmt::Debug for BamReader<R, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BamReader")
+            .field("header", &self.header)
+            .finish_non_exhaustive()
+    }
+}
+impl<R, C> fmt::Debug for IndexedBamReader<R, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IndexedBamReader")
+            .field("header", &self.reader.header)
+            .field("index", &self.index)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::location::{ContigPosition, ContigRange, orientation::SequenceOrientation};
+
+    use super::{AlignmentRecord, CigarKind, CigarOp, Flags, TagValue, query_interval};
+
+    #[test]
+    fn invalid_query() {
+        let range = |at| ContigRange { contig: "chr1", at };
+        assert!(query_interval(&range(10..20)).is_ok());
+        for at in [10..10, std::ops::Range { start: 20, end: 10 }] {
+            let error = query_interval(&range(at)).unwrap_err();
+            assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+        }
+    }
+
+    #[test]
+    fn alignment_range() {
+        let op = |kind, len| CigarOp { kind, len };
+        let record = AlignmentRecord {
+            name: Some("read1".to_owned()),
+            flags: Flags(Flags::PAIRED | Flags::REVERSE),
+            at: Some(ContigPosition {
+                contig: "chr1".to_owned(),
+                at: 100,
+            }),
+            mapping_quality: Some(60),
+            cigar: vec![
+                op(CigarKind::SoftClip, 5),
+                op(CigarKind::Match, 10),
+                op(CigarKind::Insertion, 2),
+                op(CigarKind::Deletion, 3),
+                op(CigarKind::Match, 10),
+            ],
+            mate: None,
+            template_length: 0,
+            sequence: b"ACGTACGTACGTACGTACGTACGTACG".to_vec(),
+            quality_scores: vec![30; 27],
+            tags: vec![(*b"NM", TagValue::Int(5))],
+        };
+
+        assert_eq!(record.reference_len(), 23);
+        assert_eq!(
+            record.range(),
+            Some(ContigRange {
+                contig: "chr1".to_owned(),
+                at: 100..123
+            })
+        );
+        assert_eq!(record.strand(), SequenceOrientation::Reverse);
+        assert!(record.flags.is_primary());
+        assert_eq!(record.tag(b"NM"), Some(&TagValue::Int(5)));
+        assert_eq!(record.tag(b"MD"), None);
+
+        let cigar: String = record.cigar.iter().map(|op| op.to_string()).collect();
+        assert_eq!(cigar, "5S10M2I3D10M");
+    }
+}
@@ -0,0 +1,284 @@
+//! Per-position depth and base counts over a region.
+
+use std::io::{self, Read, Seek};
+
+use utile::num::TryU64;
+
+use crate::{
+    bam::{AlignmentRecord, CigarKind, IndexedBamReader},
+    dna::DnaBase,
+    location::{ContigPosition, ContigRange},
+};
+
+/// Which reads and bases are counted.
+///
+/// The default counts everything except duplicates, secondary, supplementary
+/// and QC-failed reads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct PileupOptions {
+    pub min_mapping_quality: u8,
+    /// Bases below this (Phred) quality are skipped, deletions are always counted.
+    pub min_base_quality: u8,
+    pub include_duplicates: bool,
+    pub include_secondary: bool,
+    pub include_supplementary: bool,
+    pub include_qc_fail: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct BaseCounts {
+    pub a: u32,
+    pub c: u32,
+    pub g: u32,
+    pub t: u32,
+    /// Any other base (usually `N`).
+    pub other: u32,
+    pub deletions: u32,
+}
+
+/// Counts for each position of `range`.
+///
+/// Note: the two reads of an overlapping pair are counted separately.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pileup<C = String> {
+    pub range: ContigRange<C>,
+    pub counts: Vec<BaseCounts>,
+}
+
+impl PileupOptions {
+    pub fn accepts<C>(&self, record: &AlignmentRecord<C>) -> bool {
+        let flags = record.flags;
+        record.is_mapped()
+            && record.mapping_quality.unwrap_or(0) >= self.min_mapping_quality
+            && (self.include_duplicates || !flags.is_duplicate())
+            && (self.include_secondary || !flags.is_secondary())
+            && (self.include_supplementary || !flags.is_supplementary())
+            && (self.include_qc_fail || !flags.is_qc_fail())
+    }
+}
+
+impl BaseCounts {
+    /// Bases observed, excluding deletions.
+    pub fn depth(&self) -> u32 {
+        self.a + self.c + self.g + self.t + self.other
+    }
+    pub fn get(&self, base: DnaBase) -> u32 {
+        match base {
+            DnaBase::A => self.a,
+            DnaBase::C => self.c,
+            DnaBase::G => self.g,
+            DnaBase::T => self.t,
+        }
+    }
+    /// The most common base, if any was observed.
+    pub fn consensus(&self) -> Option<DnaBase> {
+        [DnaBase::A, DnaBase::C, DnaBase::G, DnaBase::T]
+            .into_iter()
+            .filter(|b| self.get(*b) > 0)
+            .max_by_key(|b| self.get(*b))
+    }
+
+    fn add(&mut self, base: u8) {
+        match base.to_ascii_uppercase() {
+            b'A' => self.a += 1,
+            b'C' => self.c += 1,
+            b'G' => self.g += 1,
+            b'T' => self.t += 1,
+            _ => self.other += 1,
+        }
+    }
+}
+
+impl<C> Pileup<C> {
+    pub fn new(range: ContigRange<C>) -> Self {
+        let len = usize::try_from(range.at.end - range.at.start).unwrap();
+        Self {
+            range,
+            counts: vec![BaseCounts::default(); len],
+        }
+    }
+
+    /// Adds a record, if accepted by `options`.
+    pub fn add(&mut self, record: &AlignmentRecord<C>, options: &PileupOptions)
+    where
+        C: PartialEq,
+    {
+        if !options.accepts(record) {
+            return;
+        }
+        let Some(start) = &record.at else { return };
+        if start.contig != self.range.contig {
+            return;
+        }
+
+        let mut reference = start.at;
+        let mut read = 0;
+        for op in &record.cigar {
+            let len = u64::from(op.len);
+            match op.kind {
+                CigarKind::Match | CigarKind::SequenceMatch | CigarKind::SequenceMismatch => {
+                    for i in 0..len {
+                        let Some(counts) = self.get_mut(reference + i) else {
+                            continue;
+                        };
+                        let read = read + usize::try_from(i).unwrap();
+                        let quality = record.quality_scores.get(read).copied();
+                        // A missing quality string (all `0xFF`) is never filtered.
+                        if quality.is_some_and(|q| q != 0xFF && q < options.min_base_quality) {
+                            continue;
+                        }
+                        if let Some(base) = record.sequence.get(read) {
+                            counts.add(*base);
+                        }
+                    }
+                }
+                CigarKind::Deletion => {
+                    for i in 0..len {
+                        if let Some(counts) = self.get_mut(reference + i) {
+                            counts.deletions += 1;
+                        }
+                    }
+                }
+                CigarKind::Insertion
+                | CigarKind::SoftClip
+                | CigarKind::Skip
+                | CigarKind::HardClip
+                | CigarKind::Pad => {}
+            }
+            if op.kind.consumes_reference() {
+                reference += len;
+            }
+            if op.kind.consumes_read() {
+                read += usize::try_from(len).unwrap();
+            }
+        }
+    }
+
+    /// Returns [None] outside of the range.
+    pub fn get(&self, at: u64) -> Option<&BaseCounts> {
+        let i = at.checked_sub(self.range.at.start)?;
+        self.counts.get(usize::try_from(i).ok()?)
+    }
+    fn get_mut(&mut self, at: u64) -> Option<&mut BaseCounts> {
+        let i = at.checked_sub(self.range.at.start)?;
+        self.counts.get_mut(usize::try_from(i).ok()?)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (ContigPosition<C>, &BaseCounts)>
+    where
+        C: Clone,
+    {
+        self.counts.iter().enumerate().map(|(i, counts)| {
+            let at = ContigPosition {
+                contig: self.range.contig.clone(),
+                at: self.range.at.start + i.u64_unwrap(),
+            };
+            (at, counts)
+        })
+    }
+    pub fn depths(&self) -> impl Iterator<Item = u32> {
+        self.counts.iter().map(BaseCounts::depth)
+    }
+}
+
+/// Piles up the records overlapping `range`, others are ignored.
+pub fn pileup<C: PartialEq>(
+    range: ContigRange<C>,
+    records: impl IntoIterator<Item = io::Result<AlignmentRecord<C>>>,
+    options: &PileupOptions,
+) -> io::Result<Pileup<C>> {
+    let mut pileup = Pileup::new(range);
+    for record in records {
+        pileup.add(&record?, options);
+    }
+    Ok(pileup)
+}
+
+impl<R, C> IndexedBamReader<R, C>
+where
+    R: Read + Seek,
+    C: AsRef<str> + Clone + PartialEq,
+{
+    pub fn pileup(
+        &mut self,
+        range: ContigRange<C>,
+        options: &PileupOptions,
+    ) -> io::Result<Pileup<C>> {
+        let records = self.query(&range)?;
+        pileup(range.clone(), records, options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bam::{AlignmentRecord, CigarKind, CigarOp, Flags},
+        dna::DnaBase,
+        location::{ContigPosition, ContigRange},
+    };
+
+    use super::{Pileup, PileupOptions};
+
+    fn record(at: u64, cigar: &[(CigarKind, u32)], sequence: &str, mapq: u8) -> AlignmentRecord {
+        AlignmentRecord {
+            name: None,
+            flags: Flags::default(),
+            at: Some(ContigPosition {
+                contig: "chr1".to_owned(),
+                at,
+            }),
+            mapping_quality: Some(mapq),
+            cigar: cigar
+                .iter()
+                .map(|&(kind, len)| CigarOp { kind, len })
+                .collect(),
+            mate: None,
+            template_length: 0,
+            sequence: sequence.as_bytes().to_vec(),
+            quality_scores: vec![30; sequence.len()],
+            tags: vec![],
+        }
+    }
+
+    #[test]
+    fn counts() {
+        let mut pileup = Pileup::new(ContigRange {
+            contig: "chr1".to_owned(),
+            at: 10..15,
+        });
+        let options = PileupOptions {
+            min_mapping_quality: 20,
+            ..Default::default()
+        };
+
+        // Covers 8..16, with a deletion at 12 and an insertion after 13.
+        let cigar = [
+            (CigarKind::SoftClip, 1),
+            (CigarKind::Match, 4),
+            (CigarKind::Deletion, 1),
+            (CigarKind::Match, 1),
+            (CigarKind::Insertion, 2),
+            (CigarKind::Match, 2),
+        ];
+        pileup.add(&record(8, &cigar, "NACGTAGGCA", 60), &options);
+        pileup.add(&record(11, &[(CigarKind::Match, 3)], "TTT", 60), &options);
+        pileup.add(&record(11, &[(CigarKind::Match, 3)], "CCC", 10), &options);
+
+        let mut duplicate = record(10, &[(CigarKind::Match, 1)], "C", 60);
+        duplicate.flags = Flags(Flags::DUPLICATE);
+        pileup.add(&duplicate, &options);
+
+        let depths: Vec<_> = pileup.depths().collect();
+        assert_eq!(depths, [1, 2, 1, 2, 1]);
+
+        assert_eq!(pileup.get(10).unwrap().g, 1);
+        assert_eq!(pileup.get(11).unwrap().consensus(), Some(DnaBase::T));
+        assert_eq!(pileup.get(12).unwrap().deletions, 1);
+        assert_eq!(
+            (pileup.get(13).unwrap().a, pileup.get(13).unwrap().t),
+            (1, 1)
+        );
+        assert_eq!(pileup.get(14).unwrap().get(DnaBase::C), 1);
+        assert_eq!(pileup.get(15), None);
+    }
+}
@@ -0,0 +1,475 @@
+use std::{
+    fmt,
+    io::{self, BufRead, Write},
+    marker::PhantomData,
+    ops::Range,
+    str::FromStr,
+};
+
+use resource::{RawResource, RawResourceExt};
+
+use crate::{
+    genome::alias::ContigAliases,
+    location::{ContigRange, orientation::SequenceOrientation},
+};
+
+/// A BED3 to BED12 record.
+///
+/// Optional columns are only written up to the last one that is set,
+/// earlier missing columns are filled in with placeholders.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BedRecord<C = String> {
+    /// Columns 1-3: `chrom`, `chromStart`, `chromEnd` (0-based, half-open).
+    pub range: ContigRange<C>,
+    /// Column 4. A `.` is read as [None].
+    pub name: Option<String>,
+    /// Column 5. Nominally in `0..=1000`.
+    pub score: Option<u32>,
+    /// Column 6.
+    pub strand: Option<SequenceOrientation>,
+    /// Columns 7-8: `thickStart`, `thickEnd`.
+    pub thick: Option<Range<u64>>,
+    /// Column 9: `itemRgb`.
+    pub item_rgb: Option<[u8; 3]>,
+    /// Columns 10-12: `blockCount`, `blockSizes`, `blockStarts`.
+    ///
+    /// Stored as absolute ranges on the contig rather than sizes and relative offsets.
+    pub blocks: Option<Vec<Range<u64>>>,
+}
+
+pub struct BedReader<R, C = String> {
+    reader: R,
+    buf: String,
+    line: usize,
+    _contig: PhantomData<fn() -> C>,
+}
+
+pub struct BedWriter<W> {
+    writer: W,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum BedError {
+    #[error("Line {line}: expected between 3 and 12 columns, found {found}.")]
+    ColumnCount { line: usize, found: usize },
+    #[error("Line {line}: invalid contig {value:?}.")]
+    InvalidContig { line: usize, value: String },
+    #[error("Line {line}: invalid value {value:?} in column {column}.")]
+    InvalidField {
+        line: usize,
+        column: usize,
+        value: String,
+    },
+    #[error("Line {line}: block {block} ({at:?}) is outside of the record.")]
+    InvalidBlock {
+        line: usize,
+        block: usize,
+        at: Range<u64>,
+    },
+}
+impl From<BedError> for io::Error {
+    fn from(e: BedError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, e)
+    }
+}
+
+impl<C> BedRecord<C> {
+    pub fn new(range: ContigRange<C>) -> Self {
+        Self {
+            range,
+            name: None,
+            score: None,
+            strand: None,
+            thick: None,
+            item_rgb: None,
+            blocks: None,
+        }
+    }
+
+    /// The number of columns this record is written with.
+    pub fn column_count(&self) -> usize {
+        let Self {
+            range: _,
+            name,
+            score,
+            strand,
+            thick,
+            item_rgb,
+            blocks,
+        } = self;
+
+        if blocks.is_some() {
+            12
+        } else if item_rgb.is_some() {
+            9
+        } else if thick.is_some() {
+            8
+        } else if strand.is_some() {
+            6
+        } else if score.is_some() {
+            5
+        } else if name.is_some() {
+            4
+        } else {
+            3
+        }
+    }
+
+    pub fn map_contig<NewContig>(self, f: impl FnOnce(C) -> NewContig) -> BedRecord<NewContig> {
+        BedRecord {
+            range: self.range.map_contig(f),
+            name: self.name,
+            score: self.score,
+            strand: self.strand,
+            thick: self.thick,
+            item_rgb: self.item_rgb,
+            blocks: self.blocks,
+        }
+    }
+}
+
+impl<R: BufRead, C> BedReader<R, C> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buf: String::new(),
+            line: 0,
+            _contig: PhantomData,
+        }
+    }
+}
+impl<R: BufRead> BedReader<R, String> {
+    /// Resolves contig names through `aliases`, unknown contigs are reported as errors.
+    pub fn with_aliases<C: Clone>(
+        self,
+        aliases: &ContigAliases<C>,
+    ) -> impl Iterator<Item = io::Result<BedRecord<C>>> + use<'_, R, C> {
+        self.map(|record| {
+            let record = record?;
+            let contig = aliases.resolve(&record.range.contig)?;
+            Ok(record.map_contig(|_| contig))
+        })
+    }
+}
+impl<R, C> Iterator for BedReader<R, C>
+where
+    R: BufRead,
+    C: FromStr,
+{
+    type Item = io::Result<BedRecord<C>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.buf.clear();
+            match self.reader.read_line(&mut self.buf) {
+                Ok(0) => return None,
+                Ok(_) => {}
+                Err(e) => return Some(Err(e)),
+            }
+            self.line += 1;
+
+            let line = self.buf.trim_end_matches(['\n', '\r']);
+            if line.trim().is_empty()
+                || line.starts_with('#')
+                || line.starts_with("track")
+                || line.starts_with("browser")
+            {
+                continue;
+            }
+
+            return Some(parse_record(line, self.line).map_err(Into::into));
+        }
+    }
+}
+
+impl<W: Write> BedWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    pub fn write_record<C: AsRef<str>>(&mut self, record: &BedRecord<C>) -> io::Result<()> {
+        writeln!(self.writer, "{}", DisplayRecord(record))
+    }
+    pub fn write_records<'a, C: AsRef<str> + 'a>(
+        &mut self,
+        records: impl IntoIterator<Item = &'a BedRecord<C>>,
+    ) -> io::Result<()> {
+        for record in records {
+            self.write_record(record)?;
+        }
+        Ok(())
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+impl<W: Write> BedWriter<noodles::bgzf::io::Writer<W>> {
+    /// Writes BGZF-compressed output, readable as [resource::Compression::MultiGzip]
+    /// and indexable with tabix.
+    pub fn new_bgzf(writer: W) -> Self {
+        Self::new(noodles::bgzf::io::Writer::new(writer))
+    }
+    /// Writes the BGZF EOF marker, this must be called to produce a valid file.
+    pub fn finish(self) -> io::Result<W> {
+        self.writer.finish()
+    }
+}
+
+/// Reads a (possibly compressed) BED file from a resource.
+pub fn load<C>(resource: impl RawResource) -> io::Result<BedReader<impl BufRead, C>> {
+    Ok(BedReader::new(resource.decompressed().buffered().read()?))
+}
+
+fn parse_record<C: FromStr>(line: &str, line_number: usize) -> Result<BedRecord<C>, BedError> {
+    // The spec requires tabs, but plenty of files in the wild use spaces.
+    let columns: Vec<&str> = if line.contains('\t') {
+        line.split('\t').collect()
+    } else {
+        line.split_ascii_whitespace().collect()
+    };
+
+    if !(3..=12).contains(&columns.len()) {
+        return Err(BedError::ColumnCount {
+            line: line_number,
+            found: columns.len(),
+        });
+    }
+
+    let invalid = |column: usize| BedError::InvalidField {
+        line: line_number,
+        column: column + 1,
+        value: columns[column].to_owned(),
+    };
+    let get = |column: usize| columns.get(column).copied().filter(|v| !v.is_empty());
+    let parse_u64 = |column: usize| -> Result<u64, BedError> {
+        columns[column].trim().parse().map_err(|_| invalid(column))
+    };
+
+    let contig = C::from_str(columns[0]).map_err(|_| BedError::InvalidContig {
+        line: line_number,
+        value: columns[0].to_owned(),
+    })?;
+    let start = parse_u64(1)?;
+    let end = parse_u64(2)?;
+    if end < start {
+        return Err(invalid(2));
+    }
+
+    let name = get(3).filter(|&v| v != ".").map(|v| v.to_owned());
+    let score = match get(4) {
+        None | Some(".") => None,
+        Some(v) => Some(v.parse().map_err(|_| invalid(4))?),
+    };
+    let strand = match get(5) {
+        None | Some(".") => None,
+        Some("+") => Some(SequenceOrientation::Forward),
+        Some("-") => Some(SequenceOrientation::Reverse),
+        Some(_) => return Err(invalid(5)),
+    };
+    let thick = match (get(6), get(7)) {
+        (None, None) => None,
+        (Some(_), Some(_)) => Some(parse_u64(6)?..parse_u64(7)?),
+        (Some(_), None) => return Err(invalid(6)),
+        (None, Some(_)) => return Err(invalid(7)),
+    };
+    let item_rgb = match get(8) {
+        None | Some("0") => None,
+        Some(v) => {
+            let rgb = v
+                .split(',')
+                .map(|c| c.trim().parse::<u8>())
+                .try_collect::<Vec<_>>()
+                .map_err(|_| invalid(8))?;
+            Some(<[u8; 3]>::try_from(rgb).map_err(|_| invalid(8))?)
+        }
+    };
+    let blocks = match (get(9), get(10), get(11)) {
+        (None, None, None) => None,
+        (Some(_), Some(sizes), Some(starts)) => {
+            let count: usize = columns[9].trim().parse().map_err(|_| invalid(9))?;
+            let list = |column: usize, v: &str| -> Result<Vec<u64>, BedError> {
+                v.trim_end_matches(',')
+                    .split(',')
+                    .map(|c| c.trim().parse::<u64>())
+                    .try_collect::<Vec<_>>()
+                    .map_err(|_| invalid(column))
+            };
+            let sizes = list(10, sizes)?;
+            let starts = list(11, starts)?;
+            if sizes.len() != count {
+                return Err(invalid(10));
+            }
+            if starts.len() != count {
+                return Err(invalid(11));
+            }
+
+            let blocks: Vec<_> = std::iter::zip(starts, sizes)
+                .map(|(block_start, size)| (start + block_start)..(start + block_start + size))
+                .collect();
+            for (block, at) in blocks.iter().enumerate() {
+                if at.end > end {
+                    return Err(BedError::InvalidBlock {
+                        line: line_number,
+                        block,
+                        at: at.clone(),
+                    });
+                }
+            }
+            Some(blocks)
+        }
+        _ => return Err(invalid(9)),
+    };
+
+    Ok(BedRecord {
+        range: ContigRange {
+            contig,
+            at: start..end,
+        },
+        name,
+        score,
+        strand,
+        thick,
+        item_rgb,
+        blocks,
+    })
+}
+
+struct DisplayRecord<'a, C>(&'a BedRecord<C>);
+impl<C: AsRef<str>> fmt::Display for DisplayRecord<'_, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let record = self.0;
+        let columns = record.column_count();
+        let Range { start, end } = record.range.at;
+
+        write!(f, "{}\t{start}\t{end}", record.range.contig.as_ref())?;
+        if columns >= 4 {
+            write!(f, "\t{}", record.name.as_deref().unwrap_or("."))?;
+        }
+        if columns >= 5 {
+            write!(f, "\t{}", record.score.unwrap_or(0))?;
+        }
+        if columns >= 6 {
+            let strand = match record.strand {
+                None => '.',
+                Some(SequenceOrientation::Forward) => '+',
+                Some(SequenceOrientation::Reverse) => '-',
+            };
+            write!(f, "\t{strand}")?;
+        }
+        if columns >= 8 {
+            let thick = record.thick.clone().unwrap_or(start..start);
+            write!(f, "\t{}\t{}", thick.start, thick.end)?;
+        }
+        if columns >= 9 {
+            match record.item_rgb {
+                None => write!(f, "\t0")?,
+                Some([r, g, b]) => write!(f, "\t{r},{g},{b}")?,
+            }
+        }
+        if columns >= 12 {
+            let blocks = record.blocks.as_deref().unwrap_or_default();
+            write!(f, "\t{}\t", blocks.len())?;
+            for block in blocks {
+                write!(f, "{},", block.end - block.start)?;
+            }
+            write!(f, "\t")?;
+            for block in blocks {
+                write!(f, "{},", block.start - start)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::location::{ContigRange, orientation::SequenceOrientation};
+
+    use super::{BedError, BedReader, BedRecord, BedWriter};
+
+    const BED12: &str = "\
+track name=test
+chr22\t1000\t5000\tcloneA\t960\t+\t1000\t5000\t0\t2\t567,488,\t0,3512,
+chr22\t2000\t6000\tcloneB\t900\t-\t2000\t6000\t255,0,0\t2\t433,399,\t0,3601,
+";
+
+    #[test]
+    fn bed12_round_trip() {
+        let records = BedReader::<_, String>::new(Cursor::new(BED12))
+            .try_collect::<Vec<_>>()
+            .unwrap();
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].range.at, 1000..5000);
+        assert_eq!(records[0].item_rgb, None);
+        assert_eq!(records[1].strand, Some(SequenceOrientation::Reverse));
+        assert_eq!(records[1].item_rgb, Some([255, 0, 0]));
+        assert_eq!(
+            records[0].blocks.as_deref(),
+            Some(&[1000..1567, 4512..5000][..])
+        );
+
+        let mut writer = BedWriter::new(vec![]);
+        writer.write_records(&records).unwrap();
+        let written = String::from_utf8(writer.into_inner()).unwrap();
+
+        assert_eq!(written, BED12.strip_prefix("track name=test\n").unwrap());
+    }
+
+    #[test]
+    fn minimal_columns() {
+        let mut record = BedRecord::new(ContigRange {
+            contig: "chr1".to_owned(),
+            at: 10..20,
+        });
+        let mut writer = BedWriter::new(vec![]);
+        writer.write_record(&record).unwrap();
+
+        record.strand = Some(SequenceOrientation::Forward);
+        writer.write_record(&record).unwrap();
+
+        let written = String::from_utf8(writer.into_inner()).unwrap();
+        assert_eq!(written, "chr1\t10\t20\nchr1\t10\t20\t.\t0\t+\n");
+
+        let read = BedReader::<_, String>::new(Cursor::new(written))
+            .try_collect::<Vec<_>>()
+            .unwrap();
+        assert_eq!(read[0], BedRecord::new(record.range.clone()));
+        assert_eq!(read[1].name, None);
+        assert_eq!(read[1].score, Some(0));
+        assert_eq!(read[1].strand, Some(SequenceOrientation::Forward));
+    }
+
+    #[test]
+    fn invalid() {
+        let read = |s: &'static str| {
+            BedReader::<_, String>::new(Cursor::new(s))
+                .next()
+                .unwrap()
+                .unwrap_err()
+                .into_inner()
+                .unwrap()
+                .downcast::<BedError>()
+                .unwrap()
+        };
+
+        assert!(matches!(
+            *read("chr1\t10\n"),
+            BedError::ColumnCount { line: 1, found: 2 }
+        ));
+        assert!(matches!(
+            *read("chr1\t20\t10\n"),
+            BedError::InvalidField { column: 3, .. }
+        ));
+        assert!(matches!(
+            *read("chr1\t0\t10\tx\t0\t*\n"),
+            BedError::InvalidField { column: 6, .. }
+        ));
+    }
+}
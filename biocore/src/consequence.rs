@@ -0,0 +1,451 @@
+//! Predicts the effect of variants on transcripts (synonymous, missense, frameshift, ...).
+//!
+//! Terms and thresholds follow the Sequence Ontology terms used by VEP,
+//! but only the common coding and splicing consequences are covered.
+
+use std::{fmt, io, ops::Range};
+
+use utile::{collections::complete_map::CompleteHashMap, num::TryU64, range::RangeExt};
+
+use crate::{
+    aminoacid::{AminoAcid, codons::map::STANDARD},
+    annotation::{GeneAnnotation, Transcript},
+    dna::DnaBase,
+    location::{ContigRange, orientation::SequenceOrientation},
+    mutation::normalize::{ReferenceSequence, Variant},
+    sequence::Sequence,
+};
+
+/// Translates codons (5'->3') to amino acids, [None] for stop codons.
+pub type CodonTable = CompleteHashMap<[DnaBase; 3], Option<AminoAcid>>;
+
+/// Ordered from most to least severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Consequence {
+    /// One of the two intronic bases at either end of an intron.
+    SpliceSite,
+    StopGained,
+    Frameshift,
+    StopLost,
+    StartLost,
+    InframeInsertion,
+    InframeDeletion,
+    Missense,
+    /// Within 3 exonic or 8 intronic bases of an exon boundary.
+    SpliceRegion,
+    /// The coding sequence changes, but the protein change can't be predicted
+    /// (e.g. the variant spans a CDS boundary).
+    CodingSequence,
+    Synonymous,
+    FivePrimeUtr,
+    ThreePrimeUtr,
+    NonCodingExon,
+    Intron,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TranscriptConsequence {
+    pub transcript: String,
+    /// Sorted, most severe first.
+    pub consequences: Vec<Consequence>,
+    pub protein_change: Option<ProteinChange>,
+}
+
+/// The affected stretch of protein, e.g. `A2D` or `M1fs`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ProteinChange {
+    /// The first affected codon (1-based).
+    pub position: u64,
+    /// [None] stands for a stop codon.
+    pub reference: Vec<Option<AminoAcid>>,
+    /// Empty for frameshifts.
+    pub alternate: Vec<Option<AminoAcid>>,
+    pub frameshift: bool,
+}
+
+impl TranscriptConsequence {
+    pub fn most_severe(&self) -> Option<Consequence> {
+        self.consequences.first().copied()
+    }
+}
+
+/// Predicts consequences for all the transcripts overlapping the variant,
+/// using the standard genetic code.
+pub fn predict_all<C>(
+    variant: &Variant<C>,
+    annotation: &GeneAnnotation<C>,
+    genome: &mut impl ReferenceSequence<C>,
+) -> io::Result<Vec<TranscriptConsequence>>
+where
+    C: AsRef<str> + Ord + Clone,
+{
+    let span = ContigRange {
+        contig: variant.at.contig.clone(),
+        at: affected(variant),
+    };
+    let mut consequences = vec![];
+    for gene in annotation.genes_overlapping(&span) {
+        for transcript in &gene.transcripts {
+            if transcript.range.at.overlaps(&span.at) {
+                consequences.push(predict(variant, transcript, genome)?);
+            }
+        }
+    }
+    Ok(consequences)
+}
+
+/// See [predict_with_codons], uses the standard genetic code.
+pub fn predict<C>(
+    variant: &Variant<C>,
+    transcript: &Transcript<C>,
+    genome: &mut impl ReferenceSequence<C>,
+) -> io::Result<TranscriptConsequence>
+where
+    C: AsRef<str> + Clone,
+{
+    predict_with_codons(variant, transcript, genome, &STANDARD)
+}
+
+/// Predicts the consequences of a (normalized) variant on a transcript.
+///
+/// The variant is assumed to be on the transcript's contig.
+pub fn predict_with_codons<C>(
+    variant: &Variant<C>,
+    transcript: &Transcript<C>,
+    genome: &mut impl ReferenceSequence<C>,
+    codons: &CodonTable,
+) -> io::Result<TranscriptConsequence>
+where
+    C: AsRef<str> + Clone,
+{
+    let span = affected(variant);
+    let mut consequences = splicing(transcript, &span);
+    let mut protein_change = None;
+
+    let exonic = transcript.exons.iter().any(|e| e.overlaps(&span));
+    let coding = transcript.cds.iter().any(|c| c.at.overlaps(&span));
+
+    if coding {
+        match coding_change(variant, transcript, genome, codons)? {
+            Some((consequence, change)) => {
+                consequences.push(consequence);
+                protein_change = Some(change);
+            }
+            None => consequences.push(Consequence::CodingSequence),
+        }
+    } else if exonic {
+        consequences.push(match transcript.coding_range() {
+            None => Consequence::NonCodingExon,
+            Some(coding) => {
+                let upstream = span.end <= coding.start;
+                match (upstream, reverse(transcript)) {
+                    (true, false) | (false, true) => Consequence::FivePrimeUtr,
+                    (true, true) | (false, false) => Consequence::ThreePrimeUtr,
+                }
+            }
+        });
+    }
+    if !transcript.exons.iter().any(|e| e.contains_range(&span)) {
+        consequences.push(Consequence::Intron);
+    }
+
+    consequences.sort();
+    consequences.dedup();
+
+    Ok(TranscriptConsequence {
+        transcript: transcript.id.clone(),
+        consequences,
+        protein_change,
+    })
+}
+
+/// The reference bases touched by the variant (the anchor base for insertions).
+fn affected<C>(variant: &Variant<C>) -> Range<u64> {
+    let len = variant.reference.len().max(1).u64_unwrap();
+    variant.at.at..variant.at.at + len
+}
+fn reverse<C>(transcript: &Transcript<C>) -> bool {
+    transcript.strand == Some(SequenceOrientation::Reverse)
+}
+
+fn splicing<C>(transcript: &Transcript<C>, span: &Range<u64>) -> Vec<Consequence> {
+    let mut consequences = vec![];
+    for pair in transcript.exons.windows(2) {
+        let (before, after) = (&pair[0], &pair[1]);
+        let intron = before.end..after.start;
+
+        let essential = [
+            intron.start..(intron.start + 2).min(intron.end),
+            intron.end.saturating_sub(2).max(intron.start)..intron.end,
+        ];
+        let region = [
+            before.end.saturating_sub(3).max(before.start)..before.end,
+            intron.start..(intron.start + 8).min(intron.end),
+            intron.end.saturating_sub(8).max(intron.start)..intron.end,
+            after.start..(after.start + 3).min(after.end),
+        ];
+
+        if essential.iter().any(|r| r.overlaps(span)) {
+            consequences.push(Consequence::SpliceSite);
+        } else if region.iter().any(|r| r.overlaps(span)) {
+            consequences.push(Consequence::SpliceRegion);
+        }
+    }
+    consequences
+}
+
+/// Returns [None] if the change can't be predicted from the CDS alone.
+fn coding_change<C>(
+    variant: &Variant<C>,
+    transcript: &Transcript<C>,
+    genome: &mut impl ReferenceSequence<C>,
+    codons: &CodonTable,
+) -> io::Result<Option<(Consequence, ProteinChange)>>
+where
+    C: AsRef<str> + Clone,
+{
+    let reference_len = variant.reference.len();
+    let alternate_len = variant.alternate.len();
+    let reference_range = variant.at.at..variant.at.at + reference_len.u64_unwrap();
+
+    let Some(segment) = transcript
+        .cds
+        .iter()
+        .position(|c| c.at.contains_range(&reference_range))
+    else {
+        return Ok(None);
+    };
+
+    // Splice the CDS together in genomic order, then apply the variant.
+    let mut reference: Vec<DnaBase> = vec![];
+    let mut offset = 0;
+    for (i, cds) in transcript.cds.iter().enumerate() {
+        if i == segment {
+            offset = reference.len() + usize::try_from(variant.at.at - cds.at.start).unwrap();
+        }
+        let range = ContigRange {
+            contig: variant.at.contig.clone(),
+            at: cds.at.clone(),
+        };
+        reference.extend(genome.fetch(&range)?.iter().copied());
+    }
+    let mut alternate = reference.clone();
+    alternate.splice(
+        offset..offset + reference_len,
+        variant.alternate.iter().copied(),
+    );
+
+    let phase = if reverse(transcript) {
+        let complement = |bases: Vec<DnaBase>| Sequence::new(bases).reverse_complement().to_vec();
+        reference = complement(reference);
+        alternate = complement(alternate);
+        offset = reference.len() - offset - reference_len;
+        transcript.cds.last().unwrap().phase
+    } else {
+        transcript.cds.first().unwrap().phase
+    };
+    let phase = usize::from(phase);
+    if offset < phase {
+        return Ok(None);
+    }
+    let offset = offset - phase;
+    let (reference, alternate) = (&reference[phase..], &alternate[phase..]);
+
+    let translate = |bases: &[DnaBase]| -> Vec<Option<AminoAcid>> {
+        bases
+            .chunks_exact(3)
+            .map(|codon| *codons.get(&[codon[0], codon[1], codon[2]]))
+            .collect()
+    };
+    let reference_aas = translate(reference);
+    let alternate_aas = translate(alternate);
+
+    let first = offset / 3;
+    let position = first.u64_unwrap() + 1;
+    let Some(&first_reference) = reference_aas.get(first) else {
+        return Ok(None);
+    };
+
+    if reference_len.abs_diff(alternate_len) % 3 != 0 {
+        let change = ProteinChange {
+            position,
+            reference: vec![first_reference],
+            alternate: vec![],
+            frameshift: true,
+        };
+        return Ok(Some((Consequence::Frameshift, change)));
+    }
+
+    let reference_end = (offset + reference_len).div_ceil(3).max(first + 1);
+    let alternate_end = (offset + alternate_len).div_ceil(3).max(first + 1);
+    let reference_aas = &reference_aas[first..reference_end.min(reference_aas.len())];
+    let alternate_aas = &alternate_aas[first..alternate_end.min(alternate_aas.len())];
+
+    let consequence = if reference_aas == alternate_aas {
+        Consequence::Synonymous
+    } else if first == 0 && first_reference == Some(AminoAcid::M) {
+        Consequence::StartLost
+    } else if alternate_aas.contains(&None) && !reference_aas.contains(&None) {
+        Consequence::StopGained
+    } else if reference_aas.contains(&None) && !alternate_aas.contains(&None) {
+        Consequence::StopLost
+    } else if alternate_len > reference_len {
+        Consequence::InframeInsertion
+    } else if alternate_len < reference_len {
+        Consequence::InframeDeletion
+    } else {
+        Consequence::Missense
+    };
+
+    let change = ProteinChange {
+        position,
+        reference: reference_aas.to_vec(),
+        alternate: alternate_aas.to_vec(),
+        frameshift: false,
+    };
+    Ok(Some((consequence, change)))
+}
+
+impl fmt::Display for ProteinChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn write_aas(f: &mut fmt::Formatter<'_>, aas: &[Option<AminoAcid>]) -> fmt::Result {
+            for aa in aas {
+                match aa {
+                    Some(aa) => write!(f, "{}", aa.to_char())?,
+                    None => write!(f, "*")?,
+                }
+            }
+            Ok(())
+        }
+
+        write_aas(f, &self.reference)?;
+        write!(f, "{}", self.position)?;
+        if self.frameshift {
+            write!(f, "fs")
+        } else if self.alternate.is_empty() {
+            write!(f, "del")
+        } else {
+            write_aas(f, &self.alternate)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        annotation::{CdsSegment, Transcript},
+        dna::{DnaBase, DnaSequence},
+        genome::InMemoryGenome,
+        location::{ContigPosition, ContigRange, orientation::SequenceOrientation},
+        mutation::normalize::Variant,
+        sequence::AsciiChar,
+    };
+
+    use super::{Consequence, predict};
+
+    fn seq(s: &str) -> DnaSequence {
+        DnaBase::decode(s.as_bytes().to_vec()).unwrap()
+    }
+    fn genome() -> InMemoryGenome<String> {
+        // 5' UTR, exon 1 CDS (M A W), intron, exon 2 CDS (R K *), 3' UTR.
+        let sequence = ["TT", "ATGGCTTGG", "GTAG", "CGTAAATAA", "CCCCCC"].concat();
+        InMemoryGenome::new([("chr1".to_owned(), seq(&sequence))])
+    }
+    fn transcript(strand: SequenceOrientation) -> Transcript {
+        Transcript {
+            id: "T1".to_owned(),
+            name: None,
+            biotype: None,
+            range: ContigRange {
+                contig: "chr1".to_owned(),
+                at: 0..30,
+            },
+            strand: Some(strand),
+            exons: vec![0..11, 15..30],
+            cds: vec![
+                CdsSegment {
+                    at: 2..11,
+                    phase: 0,
+                },
+                CdsSegment {
+                    at: 15..24,
+                    phase: 0,
+                },
+            ],
+        }
+    }
+    fn variant(at: u64, reference: &str, alternate: &str) -> Variant {
+        Variant {
+            at: ContigPosition {
+                contig: "chr1".to_owned(),
+                at,
+            },
+            reference: seq(reference),
+            alternate: seq(alternate),
+        }
+    }
+
+    #[test]
+    fn coding() {
+        let mut genome = genome();
+        let transcript = transcript(SequenceOrientation::Forward);
+        let predict = |genome: &mut _, v| predict(&v, &transcript, genome).unwrap();
+
+        let c = predict(&mut genome, variant(6, "C", "A"));
+        assert_eq!(c.consequences, [Consequence::Missense]);
+        assert_eq!(c.protein_change.unwrap().to_string(), "A2D");
+
+        let c = predict(&mut genome, variant(7, "T", "C"));
+        assert_eq!(c.consequences, [Consequence::Synonymous]);
+
+        let c = predict(&mut genome, variant(9, "G", "A"));
+        assert_eq!(
+            c.consequences,
+            [Consequence::StopGained, Consequence::SpliceRegion]
+        );
+        assert_eq!(c.protein_change.unwrap().to_string(), "W3*");
+
+        let c = predict(&mut genome, variant(3, "TG", "T"));
+        assert_eq!(c.consequences, [Consequence::Frameshift]);
+        assert_eq!(c.protein_change.unwrap().to_string(), "M1fs");
+
+        let c = predict(&mut genome, variant(21, "T", "C"));
+        assert_eq!(c.consequences, [Consequence::StopLost]);
+    }
+
+    #[test]
+    fn non_coding() {
+        let mut genome = genome();
+        let transcript = transcript(SequenceOrientation::Forward);
+        let predict = |genome: &mut _, v| predict(&v, &transcript, genome).unwrap();
+
+        let c = predict(&mut genome, variant(12, "T", "C"));
+        assert_eq!(
+            c.consequences,
+            [Consequence::SpliceSite, Consequence::Intron]
+        );
+        assert_eq!(c.protein_change, None);
+
+        let c = predict(&mut genome, variant(0, "T", "C"));
+        assert_eq!(c.consequences, [Consequence::FivePrimeUtr]);
+
+        let c = predict(&mut genome, variant(26, "C", "G"));
+        assert_eq!(c.consequences, [Consequence::ThreePrimeUtr]);
+    }
+
+    #[test]
+    fn reverse_strand() {
+        let mut genome = genome();
+        let mut transcript = transcript(SequenceOrientation::Reverse);
+        transcript.exons = vec![2..11];
+        transcript.cds = vec![CdsSegment {
+            at: 2..11,
+            phase: 0,
+        }];
+
+        // The CDS reads CCA AGC CAT (P S H) on the reverse strand.
+        let c = predict(&variant(10, "G", "C"), &transcript, &mut genome).unwrap();
+        assert_eq!(c.consequences, [Consequence::Missense]);
+        assert_eq!(c.protein_change.unwrap().to_string(), "P1A");
+    }
+}
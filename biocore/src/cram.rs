@@ -0,0 +1,167 @@
+//! CRAM 3.x alignments, decoded against an indexed FASTA reference.
+
+use std::{
+    fmt,
+    io::{self, BufRead, Read, Seek},
+    str::FromStr,
+};
+
+use crate::{
+    bam::{AlignmentRecord, Contigs},
+    fasta::IndexedFastaReader,
+};
+
+pub struct CramReader<R, C = String> {
+    header: noodles::sam::Header,
+    reader: noodles::cram::io::Reader<R>,
+    contigs: Contigs<C>,
+}
+
+impl<R: Read, C: FromStr> CramReader<R, C> {
+    /// The reference must be the one the file was encoded against
+    /// (see the `M5`/`UR` fields of the header's `@SQ` lines).
+    pub fn new<F>(reader: R, reference: IndexedFastaReader<F>) -> io::Result<Self>
+    where
+        F: BufRead + Seek + Send + Sync + 'static,
+    {
+        let repository = noodles::fasta::Repository::new(reference);
+        let mut reader = noodles::cram::io::reader::Builder::default()
+            .set_reference_sequence_repository(repository)
+            .build_from_reader(reader);
+        let header = reader.read_header()?;
+        let contigs = Contigs::new(&header);
+        Ok(Self {
+            header,
+            reader,
+            contigs,
+        })
+    }
+}
+impl<R, C> CramReader<R, C> {
+    pub fn header(&self) -> &noodles::sam::Header {
+        &self.header
+    }
+}
+impl<R: Read, C: Clone> CramReader<R, C> {
+    pub fn records(&mut self) -> impl Iterator<Item = io::Result<AlignmentRecord<C>>> + '_ {
+        let Self {
+            header,
+            reader,
+            contigs,
+        } = self;
+        let (header, contigs) = (&*header, &*contigs);
+        reader
+            .records(header)
+            .map(move |record| contigs.decode(header, &record?))
+    }
+}
+
+impl<R, C> fmt::Debug for CramReader<R, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CramReader")
+            .field("header", &self.header)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Cursor, num::NonZero};
+
+    use noodles::{
+        core::Position,
+        sam::{
+            self,
+            alignment::{
+                RecordBuf,
+                io::Write as _,
+                record::{
+                    Flags, MappingQuality,
+                    cigar::{Op, op::Kind},
+                },
+                record_buf::{Cigar, QualityScores, Sequence},
+            },
+            header::record::value::{Map, map::ReferenceSequence},
+        },
+    };
+
+    use crate::{
+        bam::{CigarKind, CigarOp},
+        fasta::IndexedFastaReader,
+        location::ContigPosition,
+    };
+
+    use super::CramReader;
+
+    const REFERENCE: &str = "ACGTACGTACGT";
+
+    fn reference(sequence: &str) -> IndexedFastaReader<Cursor<Vec<u8>>> {
+        let fasta = format!(">chr1\n{sequence}\n");
+        let fai = format!("chr1\t{}\t6\t{0}\t{}\n", sequence.len(), sequence.len() + 1);
+        IndexedFastaReader::new(Cursor::new(fasta.into_bytes()), fai.as_bytes()).unwrap()
+    }
+
+    /// One read at 1-based position 3, with a substitution at position 5.
+    fn cram() -> Vec<u8> {
+        let header = sam::Header::builder()
+            .add_reference_sequence(
+                "chr1",
+                Map::<ReferenceSequence>::new(NonZero::new(REFERENCE.len()).unwrap()),
+            )
+            .build();
+        let record = RecordBuf::builder()
+            .set_name("r0")
+            .set_flags(Flags::empty())
+            .set_reference_sequence_id(0)
+            .set_alignment_start(Position::try_from(3).unwrap())
+            .set_mapping_quality(MappingQuality::new(60).unwrap())
+            .set_cigar(Cigar::from(vec![Op::new(Kind::Match, 6)]))
+            .set_sequence(Sequence::from(b"GTTCGT".to_vec()))
+            .set_quality_scores(QualityScores::from(vec![30; 6]))
+            .build();
+
+        let repository = noodles::fasta::Repository::new(reference(REFERENCE));
+        let mut writer = noodles::cram::io::writer::Builder::default()
+            .set_reference_sequence_repository(repository)
+            .build_from_writer(Vec::new());
+        writer.write_header(&header).unwrap();
+        writer.write_alignment_record(&header, &record).unwrap();
+        writer.try_finish(&header).unwrap();
+        writer.get_ref().clone()
+    }
+
+    #[test]
+    fn records() {
+        let mut reader: CramReader<_> =
+            CramReader::new(Cursor::new(cram()), reference(REFERENCE)).unwrap();
+        let records = reader.records().collect::<Result<Vec<_>, _>>().unwrap();
+
+        assert_eq!(records.len(), 1);
+        let record = &records[0];
+        assert_eq!(record.name.as_deref(), Some("r0"));
+        assert_eq!(
+            record.at,
+            Some(ContigPosition {
+                contig: "chr1".to_owned(),
+                at: 2
+            })
+        );
+        assert_eq!(
+            record.cigar,
+            [CigarOp {
+                kind: CigarKind::Match,
+                len: 6
+            }]
+        );
+        // Matching bases are stored as references to the FASTA, so this checks they resolve.
+        assert_eq!(record.sequence, b"GTTCGT");
+        assert_eq!(record.quality_scores, [30; 6]);
+    }
+
+    #[test]
+    fn wrong_reference() {
+        let mut reader: CramReader<_> =
+            CramReader::new(Cursor::new(cram()), reference("ACGTACGTACGA")).unwrap();
+        assert!(reader.records().any(|record| record.is_err()));
+    }
+}
@@ -0,0 +1,130 @@
+//! Restriction enzymes and their recognition sites.
+
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+use utile::num::TryU64;
+
+use crate::{
+    dna::{DnaBase, IupacDnaSequence},
+    sequence::MatchesChar,
+};
+
+/// Commercially available enzymes, as `(name, recognition site)`, sites are 5' → 3'.
+///
+/// See http://rebase.neb.com/ for the full list.
+const TABLE: &[(&str, &str)] = &[
+    ("AluI", "AGCT"),
+    ("AvaII", "GGWCC"),
+    ("BamHI", "GGATCC"),
+    ("BbsI", "GAAGAC"),
+    ("BglII", "AGATCT"),
+    ("BsaI", "GGTCTC"),
+    ("BsmBI", "CGTCTC"),
+    ("BsrI", "ACTGG"),
+    ("BstNI", "CCWGG"),
+    ("ClaI", "ATCGAT"),
+    ("DdeI", "CTNAG"),
+    ("DpnII", "GATC"),
+    ("EcoRI", "GAATTC"),
+    ("EcoRV", "GATATC"),
+    ("HaeIII", "GGCC"),
+    ("HhaI", "GCGC"),
+    ("HindIII", "AAGCTT"),
+    ("HinfI", "GANTC"),
+    ("HpyCH4IV", "ACGT"),
+    ("KpnI", "GGTACC"),
+    ("MseI", "TTAA"),
+    ("MspI", "CCGG"),
+    ("NcoI", "CCATGG"),
+    ("NdeI", "CATATG"),
+    ("NheI", "GCTAGC"),
+    ("NlaIII", "CATG"),
+    ("NotI", "GCGGCCGC"),
+    ("PstI", "CTGCAG"),
+    ("PvuII", "CAGCTG"),
+    ("RsaI", "GTAC"),
+    ("SacI", "GAGCTC"),
+    ("SalI", "GTCGAC"),
+    ("Sau96I", "GGNCC"),
+    ("SmaI", "CCCGGG"),
+    ("SpeI", "ACTAGT"),
+    ("StyI", "CCWWGG"),
+    ("TaqI", "TCGA"),
+    ("XbaI", "TCTAGA"),
+    ("XhoI", "CTCGAG"),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize)]
+pub struct RestrictionEnzyme {
+    pub name: String,
+    /// The recognition site on the top strand, 5' → 3'.
+    pub site: IupacDnaSequence,
+}
+
+impl RestrictionEnzyme {
+    /// A table of common enzymes, sorted by name.
+    pub fn common() -> Vec<Self> {
+        TABLE
+            .iter()
+            .map(|(name, site)| Self {
+                name: (*name).to_owned(),
+                site: site.parse().unwrap(),
+            })
+            .collect()
+    }
+    /// See [Self::common], the name is case-insensitive.
+    pub fn named(name: &str) -> Option<Self> {
+        Self::common()
+            .into_iter()
+            .find(|enzyme| enzyme.name.eq_ignore_ascii_case(name))
+    }
+
+    /// The recognition sites in `sequence`, on either strand, as sorted forward ranges.
+    pub fn find(&self, sequence: &[DnaBase]) -> Vec<Range<u64>> {
+        let reverse = self.site.clone().reverse_complement();
+        let len = self.site.len();
+        if len == 0 || sequence.len() < len {
+            return vec![];
+        }
+        (0..=sequence.len() - len)
+            .filter(|&start| {
+                let window = &sequence[start..start + len];
+                matches(&self.site, window) || matches(&reverse, window)
+            })
+            .map(|start| start.u64_unwrap()..(start + len).u64_unwrap())
+            .collect()
+    }
+}
+
+fn matches(site: &IupacDnaSequence, window: &[DnaBase]) -> bool {
+    site.iter()
+        .zip(window)
+        .all(|(s, b)| MatchesChar::<DnaBase>::matches(*s, *b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::dna::DnaSequence;
+
+    #[test]
+    fn test_find() {
+        let sequence: DnaSequence = "TTGAATTCAAGGTCTCAGAGACCA".parse().unwrap();
+
+        let eco_ri = RestrictionEnzyme::named("ecori").unwrap();
+        assert_eq!(eco_ri.find(&sequence), vec![2..8]);
+
+        // BsaI isn't palindromic, so it's also found on the reverse strand.
+        let bsa_i = RestrictionEnzyme::named("BsaI").unwrap();
+        assert_eq!(bsa_i.find(&sequence), vec![10..16, 17..23]);
+
+        // With ambiguous bases.
+        let dde_i = RestrictionEnzyme::named("DdeI").unwrap();
+        assert_eq!(dde_i.find(&sequence), vec![13..18]);
+
+        assert!(RestrictionEnzyme::named("FooI").is_none());
+    }
+}
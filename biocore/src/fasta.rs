@@ -62,6 +62,16 @@ impl<R: BufRead> IndexedFastaReader<R> {
     }
 }
 
+/// Lets CRAM decoding pull reference sequences by contig name.
+impl<R: BufRead + Seek> noodles::fasta::repository::Adapter for IndexedFastaReader<R> {
+    fn get(&mut self, name: &[u8]) -> Option<io::Result<noodles::fasta::Record>> {
+        let name = std::str::from_utf8(name).ok()?;
+        self.index.as_ref().iter().find(|r| r.name() == name)?;
+        let region = noodles::core::Region::new(name, ..);
+        Some(self.reader.query(&self.index, &region))
+    }
+}
+
 pub struct IntoRecords<R> {
     inner: Reader<R>,
     line_buf: String,
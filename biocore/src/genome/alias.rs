@@ -0,0 +1,220 @@
+//! Contig names across naming schemes (UCSC `chr1`, Ensembl `1`, RefSeq `NC_000001.11`).
+
+use std::{collections::HashMap, io};
+
+/// Human GRCh38 (hg38) primary assembly names, as `[UCSC, Ensembl, RefSeq]`.
+pub const HUMAN_GRCH38: &[[&str; 3]] = &[
+    ["chr1", "1", "NC_000001.11"],
+    ["chr2", "2", "NC_000002.12"],
+    ["chr3", "3", "NC_000003.12"],
+    ["chr4", "4", "NC_000004.12"],
+    ["chr5", "5", "NC_000005.10"],
+    ["chr6", "6", "NC_000006.12"],
+    ["chr7", "7", "NC_000007.14"],
+    ["chr8", "8", "NC_000008.11"],
+    ["chr9", "9", "NC_000009.12"],
+    ["chr10", "10", "NC_000010.11"],
+    ["chr11", "11", "NC_000011.10"],
+    ["chr12", "12", "NC_000012.12"],
+    ["chr13", "13", "NC_000013.11"],
+    ["chr14", "14", "NC_000014.9"],
+    ["chr15", "15", "NC_000015.10"],
+    ["chr16", "16", "NC_000016.10"],
+    ["chr17", "17", "NC_000017.11"],
+    ["chr18", "18", "NC_000018.10"],
+    ["chr19", "19", "NC_000019.10"],
+    ["chr20", "20", "NC_000020.11"],
+    ["chr21", "21", "NC_000021.9"],
+    ["chr22", "22", "NC_000022.11"],
+    ["chrX", "X", "NC_000023.11"],
+    ["chrY", "Y", "NC_000024.10"],
+    ["chrM", "MT", "NC_012920.1"],
+];
+/// Human GRCh37 (hg19) primary assembly names, as `[UCSC, Ensembl, RefSeq]`.
+///
+/// The mitochondrion is left out on purpose: hg19's `chrM` (NC_001807.4) is a
+/// different sequence from GRCh37's `MT` (the rCRS, NC_012920.1).
+pub const HUMAN_GRCH37: &[[&str; 3]] = &[
+    ["chr1", "1", "NC_000001.10"],
+    ["chr2", "2", "NC_000002.11"],
+    ["chr3", "3", "NC_000003.11"],
+    ["chr4", "4", "NC_000004.11"],
+    ["chr5", "5", "NC_000005.9"],
+    ["chr6", "6", "NC_000006.11"],
+    ["chr7", "7", "NC_000007.13"],
+    ["chr8", "8", "NC_000008.10"],
+    ["chr9", "9", "NC_000009.11"],
+    ["chr10", "10", "NC_000010.10"],
+    ["chr11", "11", "NC_000011.9"],
+    ["chr12", "12", "NC_000012.11"],
+    ["chr13", "13", "NC_000013.10"],
+    ["chr14", "14", "NC_000014.8"],
+    ["chr15", "15", "NC_000015.9"],
+    ["chr16", "16", "NC_000016.9"],
+    ["chr17", "17", "NC_000017.10"],
+    ["chr18", "18", "NC_000018.9"],
+    ["chr19", "19", "NC_000019.9"],
+    ["chr20", "20", "NC_000020.10"],
+    ["chr21", "21", "NC_000021.8"],
+    ["chr22", "22", "NC_000022.10"],
+    ["chrX", "X", "NC_000023.10"],
+    ["chrY", "Y", "NC_000024.9"],
+];
+
+/// Human T2T-CHM13v2.0 (hs1) names, as `[UCSC, Ensembl, RefSeq]`.
+///
+/// The mitochondrion is left out on purpose: CHM13's `chrM` (CP068254.1) is not the rCRS.
+pub const HUMAN_T2T_CHM13: &[[&str; 3]] = &[
+    ["chr1", "1", "NC_060925.1"],
+    ["chr2", "2", "NC_060926.1"],
+    ["chr3", "3", "NC_060927.1"],
+    ["chr4", "4", "NC_060928.1"],
+    ["chr5", "5", "NC_060929.1"],
+    ["chr6", "6", "NC_060930.1"],
+    ["chr7", "7", "NC_060931.1"],
+    ["chr8", "8", "NC_060932.1"],
+    ["chr9", "9", "NC_060933.1"],
+    ["chr10", "10", "NC_060934.1"],
+    ["chr11", "11", "NC_060935.1"],
+    ["chr12", "12", "NC_060936.1"],
+    ["chr13", "13", "NC_060937.1"],
+    ["chr14", "14", "NC_060938.1"],
+    ["chr15", "15", "NC_060939.1"],
+    ["chr16", "16", "NC_060940.1"],
+    ["chr17", "17", "NC_060941.1"],
+    ["chr18", "18", "NC_060942.1"],
+    ["chr19", "19", "NC_060943.1"],
+    ["chr20", "20", "NC_060944.1"],
+    ["chr21", "21", "NC_060945.1"],
+    ["chr22", "22", "NC_060946.1"],
+    ["chrX", "X", "NC_060947.1"],
+    ["chrY", "Y", "NC_060948.1"],
+];
+
+/// Maps alternative names to typed contigs.
+///
+/// Build one with the contigs you work with (e.g. a typed contig's `CHROMOSOMES`)
+/// and use [Self::resolve] wherever names come from an external file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContigAliases<C> {
+    aliases: HashMap<String, C>,
+}
+
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("Unknown contig {0:?}.")]
+pub struct UnknownContigError(pub String);
+impl From<UnknownContigError> for io::Error {
+    fn from(e: UnknownContigError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, e)
+    }
+}
+
+impl<C> Default for ContigAliases<C> {
+    fn default() -> Self {
+        Self {
+            aliases: HashMap::new(),
+        }
+    }
+}
+impl<C: Clone> ContigAliases<C> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Every contig is reachable by its own name, and by all the names in the
+    /// synonym row that contains it (if any).
+    pub fn from_synonyms<const N: usize>(
+        synonyms: &[[&str; N]],
+        contigs: impl IntoIterator<Item = C>,
+    ) -> Self
+    where
+        C: AsRef<str>,
+    {
+        let mut aliases = Self::new();
+        for contig in contigs {
+            let name = contig.as_ref().to_owned();
+            if let Some(row) = synonyms.iter().find(|row| row.contains(&&name[..])) {
+                for alias in row {
+                    aliases.insert(*alias, contig.clone());
+                }
+            }
+            aliases.insert(name, contig);
+        }
+        aliases
+    }
+    /// See [HUMAN_GRCH38].
+    pub fn human_grch38(contigs: impl IntoIterator<Item = C>) -> Self
+    where
+        C: AsRef<str>,
+    {
+        Self::from_synonyms(HUMAN_GRCH38, contigs)
+    }
+    /// See [HUMAN_GRCH37].
+    pub fn human_grch37(contigs: impl IntoIterator<Item = C>) -> Self
+    where
+        C: AsRef<str>,
+    {
+        Self::from_synonyms(HUMAN_GRCH37, contigs)
+    }
+    /// See [HUMAN_T2T_CHM13].
+    pub fn human_t2t_chm13(contigs: impl IntoIterator<Item = C>) -> Self
+    where
+        C: AsRef<str>,
+    {
+        Self::from_synonyms(HUMAN_T2T_CHM13, contigs)
+    }
+
+    /// Returns the contig previously mapped to this alias, if any.
+    pub fn insert(&mut self, alias: impl Into<String>, contig: C) -> Option<C> {
+        self.aliases.insert(alias.into(), contig)
+    }
+    pub fn get(&self, name: &str) -> Option<&C> {
+        self.aliases.get(name)
+    }
+    pub fn resolve(&self, name: &str) -> Result<C, UnknownContigError> {
+        self.get(name)
+            .cloned()
+            .ok_or_else(|| UnknownContigError(name.to_owned()))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &C)> {
+        self.aliases.iter().map(|(alias, c)| (&alias[..], c))
+    }
+    pub fn len(&self) -> usize {
+        self.aliases.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.aliases.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ContigAliases, HUMAN_GRCH37, HUMAN_GRCH38, HUMAN_T2T_CHM13};
+
+    #[test]
+    fn synonym_tables_are_unique() {
+        for table in [HUMAN_GRCH38, HUMAN_GRCH37, HUMAN_T2T_CHM13] {
+            let mut names: Vec<_> = table.iter().flatten().collect();
+            let len = names.len();
+            names.sort();
+            names.dedup();
+            assert_eq!(names.len(), len);
+        }
+    }
+
+    #[test]
+    fn resolve_across_schemes() {
+        let aliases = ContigAliases::human_grch38(["chr1", "chrM", "chrUn_KI270302v1"]);
+
+        assert_eq!(aliases.resolve("chr1").unwrap(), "chr1");
+        assert_eq!(aliases.resolve("1").unwrap(), "chr1");
+        assert_eq!(aliases.resolve("NC_000001.11").unwrap(), "chr1");
+        assert_eq!(aliases.resolve("MT").unwrap(), "chrM");
+        assert_eq!(
+            aliases.resolve("chrUn_KI270302v1").unwrap(),
+            "chrUn_KI270302v1"
+        );
+        assert!(aliases.resolve("2").is_err());
+        assert!(aliases.resolve("NC_000001.10").is_err());
+    }
+}
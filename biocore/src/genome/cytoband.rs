@@ -0,0 +1,403 @@
+//! Cytogenetic bands, as found in UCSC's `cytoBand.txt`.
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    io::{self, BufRead},
+    ops::Range,
+    str::FromStr,
+};
+
+use resource::{Compression, RawResource, RawResourceExt, UrlResource};
+
+use crate::location::{ContigPosition, ContigRange};
+
+/// A UCSC `cytoBand.txt.gz` file, e.g. [CytobandResource::new_ucsc]`("hg38")`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CytobandResource {
+    pub key: String,
+}
+impl CytobandResource {
+    pub fn new(key: String) -> Self {
+        Self { key }
+    }
+    pub fn new_ucsc(assembly: &str) -> Self {
+        Self::new(format!("goldenPath/{assembly}/database/cytoBand.txt.gz"))
+    }
+
+    pub fn url(&self) -> String {
+        let key = &self.key;
+        format!("https://hgdownload.soe.ucsc.edu/{key}")
+    }
+
+    fn url_resource(&self) -> UrlResource {
+        UrlResource::new(self.url()).unwrap()
+    }
+}
+impl RawResource for CytobandResource {
+    const NAMESPACE: &'static str = "ucsc";
+    fn key(&self) -> String {
+        self.key.clone()
+    }
+
+    fn compression(&self) -> Option<Compression> {
+        if self.key.ends_with(".gz") {
+            Some(Compression::MultiGzip)
+        } else {
+            None
+        }
+    }
+
+    type Reader = <UrlResource as RawResource>::Reader;
+    fn size(&self) -> io::Result<u64> {
+        self.url_resource().size()
+    }
+    fn read(&self) -> io::Result<Self::Reader> {
+        self.url_resource().read()
+    }
+
+    type AsyncReader = <UrlResource as RawResource>::AsyncReader;
+    async fn size_async(&self) -> io::Result<u64> {
+        self.url_resource().size_async().await
+    }
+    async fn read_async(&self) -> io::Result<Self::AsyncReader> {
+        self.url_resource().read_async().await
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Cytoband<C = String> {
+    pub range: ContigRange<C>,
+    /// The band name without the chromosome, e.g. `p21.33`.
+    /// Empty for unplaced and alt contigs.
+    pub name: String,
+    pub stain: Stain,
+}
+/// The Giemsa stain result of a band, the `gieStain` column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Stain {
+    /// `gneg`
+    Negative,
+    /// `gpos25`, `gpos50`, `gpos75`, `gpos100`: positive with the given density.
+    Positive(u8),
+    /// `acen`: centromeric.
+    Centromere,
+    /// `gvar`: variable-length heterochromatin.
+    Variable,
+    /// `stalk`: the short arm stalks of acrocentric chromosomes.
+    Stalk,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Arm {
+    /// The short arm.
+    P,
+    /// The long arm.
+    Q,
+}
+
+/// All the bands of a genome, sorted by position within each contig.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Karyotype<C = String> {
+    bands: BTreeMap<C, Vec<Cytoband<C>>>,
+}
+
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum CytobandError {
+    #[error("Line {line}: expected 5 columns, found {found}.")]
+    ColumnCount { line: usize, found: usize },
+    #[error("Line {line}: invalid contig {value:?}.")]
+    InvalidContig { line: usize, value: String },
+    #[error("Line {line}: invalid value {value:?} in column {column}.")]
+    InvalidField {
+        line: usize,
+        column: usize,
+        value: String,
+    },
+}
+impl From<CytobandError> for io::Error {
+    fn from(e: CytobandError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, e)
+    }
+}
+
+impl<C> Cytoband<C> {
+    pub fn arm(&self) -> Option<Arm> {
+        match self.name.as_bytes().first()? {
+            b'p' => Some(Arm::P),
+            b'q' => Some(Arm::Q),
+            _ => None,
+        }
+    }
+    /// The name including the chromosome, e.g. `6p21.33`.
+    pub fn full_name(&self) -> String
+    where
+        C: AsRef<str>,
+    {
+        format!("{}{}", chromosome(self.range.contig.as_ref()), self.name)
+    }
+}
+
+impl Stain {
+    /// How dark the band is drawn on an ideogram, between 0 and 100.
+    ///
+    /// [None] for centromeres, stalks and variable regions,
+    /// which are usually drawn with a distinct style.
+    pub fn density(self) -> Option<u8> {
+        match self {
+            Stain::Negative => Some(0),
+            Stain::Positive(density) => Some(density),
+            Stain::Centromere | Stain::Variable | Stain::Stalk => None,
+        }
+    }
+}
+impl FromStr for Stain {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "gneg" => Stain::Negative,
+            "acen" => Stain::Centromere,
+            "gvar" => Stain::Variable,
+            "stalk" => Stain::Stalk,
+            _ => {
+                let density = s.strip_prefix("gpos").ok_or(())?;
+                Stain::Positive(density.parse().map_err(|_| ())?)
+            }
+        })
+    }
+}
+impl fmt::Display for Stain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Stain::Negative => write!(f, "gneg"),
+            Stain::Positive(density) => write!(f, "gpos{density}"),
+            Stain::Centromere => write!(f, "acen"),
+            Stain::Variable => write!(f, "gvar"),
+            Stain::Stalk => write!(f, "stalk"),
+        }
+    }
+}
+
+impl<C: Ord + Clone> Karyotype<C> {
+    pub fn new(bands: impl IntoIterator<Item = Cytoband<C>>) -> Self {
+        let mut map: BTreeMap<C, Vec<Cytoband<C>>> = BTreeMap::new();
+        for band in bands {
+            map.entry(band.range.contig.clone()).or_default().push(band);
+        }
+        for bands in map.values_mut() {
+            bands.sort_by_key(|b| (b.range.at.start, b.range.at.end));
+        }
+        Self { bands: map }
+    }
+    pub fn read(reader: impl BufRead) -> io::Result<Self>
+    where
+        C: FromStr,
+    {
+        let mut bands = vec![];
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            bands.push(parse_band(&line, i + 1)?);
+        }
+        Ok(Self::new(bands))
+    }
+    /// Loads a (possibly compressed) `cytoBand.txt` file from a resource.
+    pub fn load(resource: impl RawResource) -> io::Result<Self>
+    where
+        C: FromStr,
+    {
+        Self::read(resource.decompressed().buffered().read()?)
+    }
+
+    pub fn contigs(&self) -> impl Iterator<Item = &C> {
+        self.bands.keys()
+    }
+    /// The bands of a contig, sorted by position.
+    pub fn bands(&self, contig: &C) -> &[Cytoband<C>] {
+        self.bands.get(contig).map(|b| &b[..]).unwrap_or_default()
+    }
+    pub fn band_at(&self, position: &ContigPosition<C>) -> Option<&Cytoband<C>> {
+        let bands = self.bands.get(&position.contig)?;
+        let i = bands.partition_point(|b| b.range.at.end <= position.at);
+        bands.get(i).filter(|b| b.range.at.contains(&position.at))
+    }
+    /// The span of the `acen` bands of a contig.
+    pub fn centromere(&self, contig: &C) -> Option<Range<u64>> {
+        span(
+            self.bands(contig)
+                .iter()
+                .filter(|b| b.stain == Stain::Centromere),
+        )
+    }
+
+    /// Resolves a region like `6p21.33`, `6p21`, `Xq` or `6p22.1-p21.33` to the range it covers.
+    ///
+    /// A band matches all of its sub-bands, so `6p21` covers `6p21.1` to `6p21.33`.
+    /// The chromosome is matched ignoring any `chr` prefix on the contig names.
+    pub fn resolve(&self, region: &str) -> Option<ContigRange<C>>
+    where
+        C: AsRef<str>,
+    {
+        let region = region.trim();
+        let (start, end) = match region.split_once('-') {
+            Some((start, end)) => (start.trim(), end.trim()),
+            None => (region, region),
+        };
+
+        let (chrom, start) = split_region(start)?;
+        let end = match split_region(end) {
+            Some((end_chrom, end)) if end_chrom.is_empty() || end_chrom == chrom => end,
+            _ => return None,
+        };
+
+        let (contig, bands) = self
+            .bands
+            .iter()
+            .find(|(c, _)| chromosome(c.as_ref()) == chrom)?;
+
+        let start = span(bands.iter().filter(|b| b.name.starts_with(start)))?;
+        let end = span(bands.iter().filter(|b| b.name.starts_with(end)))?;
+
+        Some(ContigRange {
+            contig: contig.clone(),
+            at: start.start.min(end.start)..start.end.max(end.end),
+        })
+    }
+}
+
+fn chromosome(contig: &str) -> &str {
+    contig.strip_prefix("chr").unwrap_or(contig)
+}
+/// Splits `6p21.33` into `6` and `p21.33`.
+fn split_region(region: &str) -> Option<(&str, &str)> {
+    let region = chromosome(region);
+    let i = region.find(['p', 'q'])?;
+    let (chrom, band) = region.split_at(i);
+    Some((chrom, band))
+}
+fn span<'a, C: 'a>(bands: impl Iterator<Item = &'a Cytoband<C>>) -> Option<Range<u64>> {
+    bands
+        .map(|b| b.range.at.clone())
+        .reduce(|a, b| a.start.min(b.start)..a.end.max(b.end))
+}
+
+fn parse_band<C: FromStr>(line: &str, line_number: usize) -> Result<Cytoband<C>, CytobandError> {
+    let columns: Vec<&str> = line.split('\t').collect();
+    let &[contig, start, end, name, stain] = &columns[..] else {
+        return Err(CytobandError::ColumnCount {
+            line: line_number,
+            found: columns.len(),
+        });
+    };
+
+    let invalid = |column: usize, value: &str| CytobandError::InvalidField {
+        line: line_number,
+        column,
+        value: value.to_owned(),
+    };
+
+    Ok(Cytoband {
+        range: ContigRange {
+            contig: contig.parse().map_err(|_| CytobandError::InvalidContig {
+                line: line_number,
+                value: contig.to_owned(),
+            })?,
+            at: start.parse().map_err(|_| invalid(2, start))?
+                ..end.parse().map_err(|_| invalid(3, end))?,
+        },
+        name: name.to_owned(),
+        stain: stain.parse().map_err(|_| invalid(5, stain))?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::location::{ContigPosition, ContigRange};
+
+    use super::{Arm, Karyotype, Stain};
+
+    const CYTOBANDS: &str = "\
+chr6\t30500000\t31100000\tp21.33\tgneg
+chr6\t31100000\t31800000\tp21.33\tgpos25
+chr6\t31800000\t33100000\tp21.32\tgneg
+chr6\t33100000\t36500000\tp21.31\tgpos25
+chr6\t36500000\t40600000\tp21.2\tgneg
+chr6\t58500000\t59800000\tp11.1\tacen
+chr6\t59800000\t62600000\tq11.1\tacen
+chr6\t62600000\t62700000\tq11.2\tgneg
+chrUn_KI270302v1\t0\t2274\t\tgneg
+";
+
+    fn karyotype() -> Karyotype {
+        Karyotype::read(Cursor::new(CYTOBANDS)).unwrap()
+    }
+
+    #[test]
+    fn band_at() {
+        let karyotype = karyotype();
+
+        let band = karyotype
+            .band_at(&ContigPosition {
+                contig: "chr6".to_owned(),
+                at: 31_000_000,
+            })
+            .unwrap();
+        assert_eq!(band.full_name(), "6p21.33");
+        assert_eq!(band.arm(), Some(Arm::P));
+        assert_eq!(band.stain, Stain::Negative);
+
+        let band = karyotype
+            .band_at(&ContigPosition {
+                contig: "chr6".to_owned(),
+                at: 31_100_000,
+            })
+            .unwrap();
+        assert_eq!(band.stain, Stain::Positive(25));
+
+        assert!(
+            karyotype
+                .band_at(&ContigPosition {
+                    contig: "chr6".to_owned(),
+                    at: 45_000_000,
+                })
+                .is_none()
+        );
+        assert_eq!(
+            karyotype.centromere(&"chr6".to_owned()),
+            Some(58_500_000..62_600_000)
+        );
+    }
+
+    #[test]
+    fn resolve() {
+        let karyotype = karyotype();
+        let chr6 = |at| ContigRange {
+            contig: "chr6".to_owned(),
+            at,
+        };
+
+        assert_eq!(
+            karyotype.resolve("6p21.33"),
+            Some(chr6(30_500_000..31_800_000))
+        );
+        assert_eq!(
+            karyotype.resolve("6p21.3"),
+            Some(chr6(30_500_000..36_500_000))
+        );
+        assert_eq!(
+            karyotype.resolve("6p21"),
+            Some(chr6(30_500_000..40_600_000))
+        );
+        assert_eq!(
+            karyotype.resolve("6p21.2-p21.32"),
+            Some(chr6(31_800_000..40_600_000))
+        );
+        assert_eq!(karyotype.resolve("6q22"), None);
+        assert_eq!(karyotype.resolve("7p21"), None);
+        assert_eq!(karyotype.resolve("6p21-7p21"), None);
+    }
+}
@@ -1,3 +1,7 @@
+pub mod alias;
+pub mod cytoband;
+pub mod t2t;
+
 use serde::{Deserialize, Serialize};
 use std::{
     collections::BTreeMap,
@@ -0,0 +1,155 @@
+//! The T2T-CHM13v2.0 assembly (UCSC `hs1`), which has no alternate or unplaced contigs.
+
+use std::{
+    cmp::Ordering,
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    genome::Contig,
+    location::build::{self, BuildContig},
+};
+
+/// A T2T-CHM13v2.0 contig, with UCSC names (`chr1`, ..., `chrX`, `chrY`, `chrM`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct T2TContig {
+    contig: &'static str,
+}
+
+/// Names and sizes, in assembly order.
+const CONTIGS: [(&str, u64); 25] = [
+    ("chr1", 248387328),
+    ("chr2", 242696752),
+    ("chr3", 201105948),
+    ("chr4", 193574945),
+    ("chr5", 182045439),
+    ("chr6", 172126628),
+    ("chr7", 160567428),
+    ("chr8", 146259331),
+    ("chr9", 150617247),
+    ("chr10", 134758134),
+    ("chr11", 135127769),
+    ("chr12", 133324548),
+    ("chr13", 113566686),
+    ("chr14", 101161492),
+    ("chr15", 99753195),
+    ("chr16", 96330374),
+    ("chr17", 84276897),
+    ("chr18", 80542538),
+    ("chr19", 61707364),
+    ("chr20", 66210255),
+    ("chr21", 45090682),
+    ("chr22", 51324926),
+    ("chrX", 154259566),
+    ("chrY", 62460029),
+    ("chrM", 16569),
+];
+
+impl T2TContig {
+    pub const X: Self = Self { contig: "chrX" };
+    pub const Y: Self = Self { contig: "chrY" };
+    pub const M: Self = Self { contig: "chrM" };
+
+    pub const CHROMOSOMES: [Self; 25] = {
+        let mut chromosomes = [Self::M; 25];
+        let mut i = 0;
+        while i < 25 {
+            chromosomes[i] = Self {
+                contig: CONTIGS[i].0,
+            };
+            i += 1;
+        }
+        chromosomes
+    };
+
+    pub fn new(v: &str) -> Option<Self> {
+        let (contig, _) = CONTIGS.iter().find(|(name, _)| *name == v)?;
+        Some(Self { contig })
+    }
+    pub fn new_chr(number: usize) -> Option<Self> {
+        Self::new(&format!("chr{number}"))
+    }
+    pub fn static_value(self) -> &'static str {
+        self.contig
+    }
+
+    fn index(self) -> usize {
+        CONTIGS
+            .iter()
+            .position(|(name, _)| *name == self.contig)
+            .unwrap()
+    }
+}
+impl PartialOrd for T2TContig {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for T2TContig {
+    fn cmp(&self, other: &Self) -> Ordering {
+        Ord::cmp(&self.index(), &other.index())
+    }
+}
+impl Contig for T2TContig {
+    fn size(&self) -> u64 {
+        CONTIGS[self.index()].1
+    }
+}
+impl BuildContig for T2TContig {
+    type Build = build::T2TCHM13;
+}
+impl AsRef<str> for T2TContig {
+    fn as_ref(&self) -> &str {
+        self.contig
+    }
+}
+impl Display for T2TContig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.contig)
+    }
+}
+impl FromStr for T2TContig {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(s).ok_or_else(|| s.to_owned())
+    }
+}
+impl Serialize for T2TContig {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.contig.serialize(serializer)
+    }
+}
+impl<'de> Deserialize<'de> for T2TContig {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let name = String::deserialize(deserializer)?;
+        Self::new(&name).ok_or_else(|| {
+            serde::de::Error::invalid_value(serde::de::Unexpected::Str(&name), &"a T2T contig")
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::genome::Contig;
+
+    use super::T2TContig;
+
+    #[test]
+    fn contigs() {
+        assert_eq!(T2TContig::new_chr(1), Some(T2TContig::CHROMOSOMES[0]));
+        assert_eq!(T2TContig::new("chrX"), Some(T2TContig::X));
+        assert_eq!(T2TContig::new("1"), None);
+        assert_eq!(T2TContig::M.size(), 16569);
+        assert!(T2TContig::new_chr(2).unwrap() < T2TContig::new_chr(10).unwrap());
+    }
+}
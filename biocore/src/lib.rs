@@ -3,12 +3,19 @@
 #![feature(map_try_insert)]
 
 pub mod aminoacid;
+pub mod annotation;
+pub mod bam;
 pub mod bcf;
+pub mod bed;
+pub mod consequence;
+pub mod cram;
 pub mod dna;
+pub mod enzyme;
 pub mod fasta;
 pub mod genome;
 pub mod location;
 pub mod mutation;
 pub mod rna;
 pub mod sequence;
+pub mod track;
 pub mod vcf;
@@ -496,6 +496,752 @@ pub mod orientation {
     }
 }
 
+pub mod build {
+    use std::fmt;
+
+    use crate::genome::Contig;
+
+    /// A reference genome build, used as a type-level tag.
+    pub trait Build: fmt::Debug + Copy + Eq + Ord + Send + Sync + 'static {
+        /// The GRC name, e.g. `GRCh38`.
+        const NAME: &'static str;
+        /// The UCSC name, e.g. `hg38`.
+        const UCSC_NAME: &'static str;
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub enum GRCh37 {}
+    impl Build for GRCh37 {
+        const NAME: &'static str = "GRCh37";
+        const UCSC_NAME: &'static str = "hg19";
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub enum GRCh38 {}
+    impl Build for GRCh38 {
+        const NAME: &'static str = "GRCh38";
+        const UCSC_NAME: &'static str = "hg38";
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub enum T2TCHM13 {}
+    impl Build for T2TCHM13 {
+        const NAME: &'static str = "T2T-CHM13v2.0";
+        const UCSC_NAME: &'static str = "hs1";
+    }
+
+    /// A contig that belongs to a specific [Build].
+    ///
+    /// Positions on these contigs carry their build in the type, so
+    /// `ContigPosition<C>` for a GRCh37 contig cannot be passed where a GRCh38 one
+    /// is expected. Moving between builds is left to the liftover crate.
+    pub trait BuildContig: Contig {
+        type Build: Build;
+    }
+    impl<C: BuildContig + ?Sized> BuildContig for &C {
+        type Build = C::Build;
+    }
+}
+
+pub mod set {
+    use std::{cmp::Ordering, ops::Range};
+
+    use serde::{Deserialize, Serialize};
+
+    use crate::genome::Contig;
+
+    use super::{
+        ContigPosition, ContigRange,
+        orientation::{SequenceOrientation, Stranded},
+    };
+
+    /// A set of genome positions, stored as sorted, non-overlapping, non-adjacent ranges.
+    ///
+    /// Deserialized ranges are normalized like [ContigRangeSet::from_iter].
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    #[derive(Serialize, Deserialize)]
+    #[serde(from = "RawContigRangeSet<C>")]
+    #[serde(bound(deserialize = "C: Deserialize<'de> + Ord"))]
+    pub struct ContigRangeSet<C = String> {
+        ranges: Vec<ContigRange<C>>,
+    }
+    /// The serialized form of [ContigRangeSet], with ranges in any order.
+    #[derive(Deserialize)]
+    struct RawContigRangeSet<C> {
+        ranges: Vec<ContigRange<C>>,
+    }
+
+    /// Like [ContigRangeSet], but ranges only interact with ranges on the same strand.
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    #[derive(Serialize, Deserialize)]
+    #[serde(bound(deserialize = "C: Deserialize<'de> + Ord"))]
+    pub struct StrandedContigRangeSet<C = String> {
+        forward: ContigRangeSet<C>,
+        reverse: ContigRangeSet<C>,
+    }
+
+    impl<C> ContigRangeSet<C> {
+        pub fn new() -> Self {
+            Self { ranges: vec![] }
+        }
+
+        pub fn ranges(&self) -> &[ContigRange<C>] {
+            &self.ranges
+        }
+        pub fn into_ranges(self) -> Vec<ContigRange<C>> {
+            self.ranges
+        }
+        pub fn iter(&self) -> std::slice::Iter<'_, ContigRange<C>> {
+            self.ranges.iter()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.ranges.is_empty()
+        }
+        /// The number of disjoint ranges.
+        pub fn len(&self) -> usize {
+            self.ranges.len()
+        }
+        /// The number of positions covered.
+        pub fn covered_len(&self) -> u64 {
+            self.ranges.iter().map(|r| r.len()).sum()
+        }
+    }
+    impl<C: Ord> ContigRangeSet<C> {
+        pub fn insert(&mut self, range: ContigRange<C>) {
+            if range.is_empty() {
+                return;
+            }
+            // The ranges overlapping or adjacent to the new one, which are merged into it.
+            let first = self.ranges.partition_point(|r| {
+                Ord::cmp(&r.contig, &range.contig).then(Ord::cmp(&r.at.end, &range.at.start))
+                    == Ordering::Less
+            });
+            let last = self.ranges.partition_point(|r| {
+                Ord::cmp(&r.contig, &range.contig).then(Ord::cmp(&r.at.start, &range.at.end))
+                    != Ordering::Greater
+            });
+
+            let mut at = range.at;
+            if first < last {
+                at.start = u64::min(at.start, self.ranges[first].at.start);
+                at.end = u64::max(at.end, self.ranges[last - 1].at.end);
+            }
+            let range = ContigRange {
+                contig: range.contig,
+                at,
+            };
+            self.ranges.splice(first..last, [range]);
+        }
+
+        pub fn contains(&self, pos: &ContigPosition<C>) -> bool {
+            let i = self.first_ending_after(&pos.contig, pos.at);
+            self.ranges.get(i).is_some_and(|r| r.contains(pos))
+        }
+        pub fn contains_range(&self, range: &ContigRange<C>) -> bool {
+            if range.is_empty() {
+                return true;
+            }
+            let i = self.first_ending_after(&range.contig, range.at.start);
+            self.ranges.get(i).is_some_and(|r| r.contains_range(range))
+        }
+        pub fn overlaps(&self, range: &ContigRange<C>) -> bool {
+            let i = self.first_ending_after(&range.contig, range.at.start);
+            self.ranges.get(i).is_some_and(|r| r.overlaps(range))
+        }
+
+        pub fn union(&self, other: &Self) -> Self
+        where
+            C: Clone,
+        {
+            Self {
+                ranges: merge_within(self.iter().chain(other.iter()).cloned(), 0),
+            }
+        }
+        pub fn intersection(&self, other: &Self) -> Self
+        where
+            C: Clone,
+        {
+            let (mut a, mut b) = (
+                self.ranges.iter().peekable(),
+                other.ranges.iter().peekable(),
+            );
+            let mut ranges = vec![];
+            while let (Some(x), Some(y)) = (a.peek(), b.peek()) {
+                match Ord::cmp(&x.contig, &y.contig) {
+                    Ordering::Less => {
+                        a.next();
+                    }
+                    Ordering::Greater => {
+                        b.next();
+                    }
+                    Ordering::Equal => {
+                        let at = u64::max(x.at.start, y.at.start)..u64::min(x.at.end, y.at.end);
+                        if !at.is_empty() {
+                            ranges.push(ContigRange {
+                                contig: x.contig.clone(),
+                                at,
+                            });
+                        }
+                        if x.at.end <= y.at.end {
+                            a.next();
+                        } else {
+                            b.next();
+                        }
+                    }
+                }
+            }
+            Self { ranges }
+        }
+        /// All positions in `self` that are not in `other`.
+        pub fn subtract(&self, other: &Self) -> Self
+        where
+            C: Clone,
+        {
+            let mut ranges = vec![];
+            let mut j = 0;
+            for range in &self.ranges {
+                // Anything ending before this range also ends before the following ones.
+                while other.ranges.get(j).is_some_and(|r| {
+                    Ord::cmp(&r.contig, &range.contig).then(Ord::cmp(&r.at.end, &range.at.start))
+                        != Ordering::Greater
+                }) {
+                    j += 1;
+                }
+
+                let mut start = range.at.start;
+                for cut in other.ranges[j..]
+                    .iter()
+                    .take_while(|r| r.contig == range.contig && r.at.start < range.at.end)
+                {
+                    if start < cut.at.start {
+                        ranges.push(ContigRange {
+                            contig: range.contig.clone(),
+                            at: start..cut.at.start,
+                        });
+                    }
+                    start = u64::max(start, cut.at.end);
+                }
+                if start < range.at.end {
+                    ranges.push(ContigRange {
+                        contig: range.contig.clone(),
+                        at: start..range.at.end,
+                    });
+                }
+            }
+            Self { ranges }
+        }
+        /// Merges ranges separated by gaps of at most `distance`.
+        ///
+        /// Adjacent ranges are always merged, so a `distance` of `0` is a no-op.
+        pub fn merge_within(self, distance: u64) -> Self {
+            Self {
+                ranges: merge_within(self.ranges, distance),
+            }
+        }
+
+        fn first_ending_after(&self, contig: &C, at: u64) -> usize {
+            self.ranges.partition_point(|r| {
+                Ord::cmp(&r.contig, contig).then(Ord::cmp(&r.at.end, &at)) != Ordering::Greater
+            })
+        }
+    }
+    impl<C> Default for ContigRangeSet<C> {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+    impl<C: Ord> From<RawContigRangeSet<C>> for ContigRangeSet<C> {
+        fn from(raw: RawContigRangeSet<C>) -> Self {
+            raw.ranges.into_iter().collect()
+        }
+    }
+    impl<C: Ord> FromIterator<ContigRange<C>> for ContigRangeSet<C> {
+        fn from_iter<T: IntoIterator<Item = ContigRange<C>>>(iter: T) -> Self {
+            Self {
+                ranges: merge_within(iter, 0),
+            }
+        }
+    }
+    impl<C> IntoIterator for ContigRangeSet<C> {
+        type Item = ContigRange<C>;
+        type IntoIter = std::vec::IntoIter<ContigRange<C>>;
+        fn into_iter(self) -> Self::IntoIter {
+            self.ranges.into_iter()
+        }
+    }
+    impl<'a, C> IntoIterator for &'a ContigRangeSet<C> {
+        type Item = &'a ContigRange<C>;
+        type IntoIter = std::slice::Iter<'a, ContigRange<C>>;
+        fn into_iter(self) -> Self::IntoIter {
+            self.ranges.iter()
+        }
+    }
+
+    impl<C> StrandedContigRangeSet<C> {
+        pub fn new() -> Self {
+            Self {
+                forward: ContigRangeSet::new(),
+                reverse: ContigRangeSet::new(),
+            }
+        }
+
+        pub fn strand(&self, orientation: SequenceOrientation) -> &ContigRangeSet<C> {
+            match orientation {
+                SequenceOrientation::Forward => &self.forward,
+                SequenceOrientation::Reverse => &self.reverse,
+            }
+        }
+        pub fn iter(&self) -> impl Iterator<Item = Stranded<&ContigRange<C>>> {
+            let forward = self.forward.iter().map(Stranded::new_forward);
+            let reverse = self.reverse.iter().map(Stranded::new_reverse);
+            forward.chain(reverse)
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.forward.is_empty() && self.reverse.is_empty()
+        }
+        /// The number of disjoint ranges, across both strands.
+        pub fn len(&self) -> usize {
+            self.forward.len() + self.reverse.len()
+        }
+    }
+    impl<C: Ord> StrandedContigRangeSet<C> {
+        pub fn insert(&mut self, range: Stranded<ContigRange<C>>) {
+            match range.orientation {
+                SequenceOrientation::Forward => self.forward.insert(range.v),
+                SequenceOrientation::Reverse => self.reverse.insert(range.v),
+            }
+        }
+
+        pub fn contains(&self, pos: &Stranded<ContigPosition<C>>) -> bool {
+            self.strand(pos.orientation).contains(&pos.v)
+        }
+        pub fn overlaps(&self, range: &Stranded<ContigRange<C>>) -> bool {
+            self.strand(range.orientation).overlaps(&range.v)
+        }
+
+        pub fn union(&self, other: &Self) -> Self
+        where
+            C: Clone,
+        {
+            Self {
+                forward: self.forward.union(&other.forward),
+                reverse: self.reverse.union(&other.reverse),
+            }
+        }
+        pub fn intersection(&self, other: &Self) -> Self
+        where
+            C: Clone,
+        {
+            Self {
+                forward: self.forward.intersection(&other.forward),
+                reverse: self.reverse.intersection(&other.reverse),
+            }
+        }
+        pub fn subtract(&self, other: &Self) -> Self
+        where
+            C: Clone,
+        {
+            Self {
+                forward: self.forward.subtract(&other.forward),
+                reverse: self.reverse.subtract(&other.reverse),
+            }
+        }
+        pub fn merge_within(self, distance: u64) -> Self {
+            Self {
+                forward: self.forward.merge_within(distance),
+                reverse: self.reverse.merge_within(distance),
+            }
+        }
+
+        /// Moves everything onto the forward strand, discarding strand information.
+        pub fn into_unstranded(self) -> ContigRangeSet<C>
+        where
+            C: Contig,
+        {
+            let reverse = self
+                .reverse
+                .into_iter()
+                .map(|v| Stranded::new_reverse(v).flip_orientation().v);
+            self.forward.into_iter().chain(reverse).collect()
+        }
+    }
+    impl<C> Default for StrandedContigRangeSet<C> {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+    impl<C: Ord> FromIterator<Stranded<ContigRange<C>>> for StrandedContigRangeSet<C> {
+        fn from_iter<T: IntoIterator<Item = Stranded<ContigRange<C>>>>(iter: T) -> Self {
+            let (forward, reverse): (Vec<_>, Vec<_>) =
+                iter.into_iter().partition(|r| r.is_forward());
+            Self {
+                forward: forward.into_iter().map(|r| r.v).collect(),
+                reverse: reverse.into_iter().map(|r| r.v).collect(),
+            }
+        }
+    }
+
+    fn merge_within<C: Ord>(
+        ranges: impl IntoIterator<Item = ContigRange<C>>,
+        distance: u64,
+    ) -> Vec<ContigRange<C>> {
+        let mut ranges: Vec<_> = ranges.into_iter().filter(|r| !r.is_empty()).collect();
+        ranges.sort();
+
+        let mut merged: Vec<ContigRange<C>> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match merged.last_mut() {
+                Some(ContigRange {
+                    contig,
+                    at: Range { start: _, end },
+                }) if *contig == range.contig && range.at.start <= end.saturating_add(distance) => {
+                    *end = u64::max(*end, range.at.end);
+                }
+                _ => merged.push(range),
+            }
+        }
+        merged
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use crate::location::{ContigPosition, ContigRange, orientation::Stranded};
+
+        use super::{ContigRangeSet, StrandedContigRangeSet};
+
+        fn set(ranges: &[(&str, u64, u64)]) -> ContigRangeSet<String> {
+            ranges
+                .iter()
+                .map(|&(contig, start, end)| ContigRange {
+                    contig: contig.to_owned(),
+                    at: start..end,
+                })
+                .collect()
+        }
+
+        #[test]
+        fn test_normalization() {
+            let s = set(&[
+                ("b", 0, 5),
+                ("a", 10, 20),
+                ("a", 5, 10),
+                ("a", 15, 25),
+                ("a", 30, 30),
+            ]);
+            assert_eq!(s, set(&[("a", 5, 25), ("b", 0, 5)]));
+            assert_eq!(s.len(), 2);
+            assert_eq!(s.covered_len(), 25);
+        }
+
+        #[test]
+        fn test_insert() {
+            let ranges = [
+                ("b", 0, 5),
+                ("a", 10, 20),
+                ("a", 30, 40),
+                ("a", 20, 30),
+                ("a", 0, 5),
+                ("a", 45, 50),
+                ("a", 6, 7),
+                ("a", 3, 12),
+                ("a", 60, 60),
+            ];
+            let mut s = ContigRangeSet::new();
+            for (i, &(contig, start, end)) in ranges.iter().enumerate() {
+                s.insert(ContigRange {
+                    contig: contig.to_owned(),
+                    at: start..end,
+                });
+                assert_eq!(s, set(&ranges[..=i]));
+            }
+            assert_eq!(s, set(&[("a", 0, 40), ("a", 45, 50), ("b", 0, 5)]));
+        }
+
+        #[test]
+        fn test_deserialize_normalizes() {
+            let ranges: Vec<_> = [("b", 0, 5), ("a", 10, 20), ("a", 5, 10), ("a", 30, 30)]
+                .map(|(contig, start, end)| ContigRange {
+                    contig: contig.to_owned(),
+                    at: start..end,
+                })
+                .into();
+            let json = serde_json::json!({ "ranges": ranges });
+            let s: ContigRangeSet = serde_json::from_value(json).unwrap();
+            assert_eq!(s, set(&[("a", 5, 20), ("b", 0, 5)]));
+            assert_eq!(
+                serde_json::from_value::<ContigRangeSet>(serde_json::to_value(&s).unwrap())
+                    .unwrap(),
+                s
+            );
+        }
+
+        #[test]
+        fn test_union() {
+            let a = set(&[("a", 0, 10), ("a", 20, 30)]);
+            let b = set(&[("a", 5, 15), ("a", 30, 40), ("b", 0, 1)]);
+            assert_eq!(
+                a.union(&b),
+                set(&[("a", 0, 15), ("a", 20, 40), ("b", 0, 1)])
+            );
+        }
+
+        #[test]
+        fn test_intersection() {
+            let a = set(&[("a", 0, 10), ("a", 20, 30), ("c", 0, 10)]);
+            let b = set(&[("a", 5, 25), ("b", 0, 10), ("c", 10, 20)]);
+            assert_eq!(a.intersection(&b), set(&[("a", 5, 10), ("a", 20, 25)]));
+            assert_eq!(a.intersection(&b), b.intersection(&a));
+        }
+
+        #[test]
+        fn test_subtract() {
+            let a = set(&[("a", 0, 100), ("b", 0, 10)]);
+            let b = set(&[("a", 10, 20), ("a", 30, 40), ("a", 90, 110), ("b", 0, 10)]);
+            assert_eq!(
+                a.subtract(&b),
+                set(&[("a", 0, 10), ("a", 20, 30), ("a", 40, 90)])
+            );
+            assert_eq!(b.subtract(&a), set(&[("a", 100, 110)]));
+        }
+
+        #[test]
+        fn test_merge_within() {
+            let a = set(&[("a", 0, 10), ("a", 12, 20), ("a", 25, 30), ("b", 31, 40)]);
+            assert_eq!(
+                a.merge_within(2),
+                set(&[("a", 0, 20), ("a", 25, 30), ("b", 31, 40)])
+            );
+        }
+
+        #[test]
+        fn test_queries() {
+            let a = set(&[("a", 10, 20), ("b", 0, 5)]);
+            let pos = |contig: &str, at| ContigPosition {
+                contig: contig.to_owned(),
+                at,
+            };
+            let range = |contig: &str, at| ContigRange {
+                contig: contig.to_owned(),
+                at,
+            };
+
+            assert!(!a.contains(&pos("a", 9)));
+            assert!(a.contains(&pos("a", 10)));
+            assert!(a.contains(&pos("a", 19)));
+            assert!(!a.contains(&pos("a", 20)));
+            assert!(a.contains(&pos("b", 0)));
+
+            assert!(a.contains_range(&range("a", 12..20)));
+            assert!(!a.contains_range(&range("a", 12..21)));
+            assert!(a.overlaps(&range("a", 19..30)));
+            assert!(!a.overlaps(&range("a", 20..30)));
+        }
+
+        #[test]
+        fn test_stranded() {
+            let forward = |at| {
+                Stranded::new_forward(ContigRange {
+                    contig: "a".to_owned(),
+                    at,
+                })
+            };
+            let reverse = |at| {
+                Stranded::new_reverse(ContigRange {
+                    contig: "a".to_owned(),
+                    at,
+                })
+            };
+
+            let a: StrandedContigRangeSet = [forward(0..10), reverse(0..10)].into_iter().collect();
+            let b: StrandedContigRangeSet = [forward(5..15)].into_iter().collect();
+
+            assert_eq!(
+                a.subtract(&b),
+                [forward(0..5), reverse(0..10)]
+                    .into_iter()
+                    .collect::<StrandedContigRangeSet>()
+            );
+            assert_eq!(
+                a.intersection(&b),
+                [forward(5..10)]
+                    .into_iter()
+                    .collect::<StrandedContigRangeSet>()
+            );
+        }
+    }
+}
+
+pub mod window {
+    use std::cmp::Ordering;
+
+    use crate::genome::Contig;
+
+    use super::{ContigPosition, ContigRange};
+
+    /// Windows of `size` bases, starting every `step` bases over a range.
+    ///
+    /// Windows are truncated at the end of the range, so the last ones can be shorter.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Windows<C> {
+        contig: C,
+        next: u64,
+        end: u64,
+        size: u64,
+        step: u64,
+    }
+
+    impl<C> Windows<C> {
+        #[track_caller]
+        pub fn new(range: ContigRange<C>, size: u64, step: u64) -> Self {
+            assert!(size > 0, "window size must be positive");
+            assert!(step > 0, "window step must be positive");
+            Self {
+                contig: range.contig,
+                next: range.at.start,
+                end: range.at.end,
+                size,
+                step,
+            }
+        }
+    }
+    impl<C: Clone> Iterator for Windows<C> {
+        type Item = ContigRange<C>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            if self.next >= self.end {
+                return None;
+            }
+            let start = self.next;
+            self.next = start.saturating_add(self.step);
+            Some(ContigRange {
+                contig: self.contig.clone(),
+                at: start..start.saturating_add(self.size).min(self.end),
+            })
+        }
+
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            let len = usize::try_from(self.end.saturating_sub(self.next).div_ceil(self.step)).ok();
+            (len.unwrap_or(usize::MAX), len)
+        }
+    }
+
+    impl<C> ContigRange<C> {
+        /// Non-overlapping windows of `size` bases tiling the range.
+        pub fn windows(self, size: u64) -> Windows<C> {
+            Windows::new(self, size, size)
+        }
+        /// Windows of `size` bases starting every `step` bases, overlapping if `step < size`.
+        pub fn sliding_windows(self, size: u64, step: u64) -> Windows<C> {
+            Windows::new(self, size, step)
+        }
+    }
+
+    /// Windows over the whole of a contig.
+    pub fn contig_windows<C: Contig>(contig: C, size: u64, step: u64) -> Windows<C> {
+        let end = contig.size();
+        Windows::new(ContigRange { contig, at: 0..end }, size, step)
+    }
+    /// Windows over each of the contigs, in order.
+    pub fn genome_windows<C: Contig + Clone>(
+        contigs: impl IntoIterator<Item = C>,
+        size: u64,
+        step: u64,
+    ) -> impl Iterator<Item = ContigRange<C>> {
+        contigs
+            .into_iter()
+            .flat_map(move |contig| contig_windows(contig, size, step))
+    }
+
+    /// Folds values into every window containing their position.
+    ///
+    /// Windows are sorted by contig and start first, and values can come in any order.
+    /// Values outside of all windows are ignored.
+    pub fn aggregate<C, T, A>(
+        windows: impl IntoIterator<Item = ContigRange<C>>,
+        values: impl IntoIterator<Item = (ContigPosition<C>, T)>,
+        mut init: impl FnMut(&ContigRange<C>) -> A,
+        mut fold: impl FnMut(&mut A, &ContigPosition<C>, &T),
+    ) -> Vec<(ContigRange<C>, A)>
+    where
+        C: Ord,
+    {
+        let mut windows: Vec<_> = windows
+            .into_iter()
+            .map(|w| {
+                let acc = init(&w);
+                (w, acc)
+            })
+            .collect();
+        windows.sort_by(|(a, _), (b, _)| a.cmp(b));
+        let max_len = windows.iter().map(|(w, _)| w.len()).max().unwrap_or(0);
+
+        for (position, value) in values {
+            let i = windows.partition_point(|(w, _)| match w.contig.cmp(&position.contig) {
+                Ordering::Less => true,
+                Ordering::Equal => w.at.start <= position.at,
+                Ordering::Greater => false,
+            });
+            // Everything before `i` starts at or before the position, so walk back
+            // until no window could be long enough to reach it.
+            for (window, acc) in windows[..i].iter_mut().rev() {
+                if window.contig != position.contig || window.at.start + max_len <= position.at {
+                    break;
+                }
+                if window.at.contains(&position.at) {
+                    fold(acc, &position, &value);
+                }
+            }
+        }
+
+        windows
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use crate::{genome::ContigRef, location::ContigPosition};
+
+        use super::{aggregate, contig_windows};
+
+        #[test]
+        fn tiling() {
+            let contig = ContigRef::new("chr1", 25);
+
+            let windows: Vec<_> = contig_windows(contig, 10, 10).map(|w| w.at).collect();
+            assert_eq!(windows, [0..10, 10..20, 20..25]);
+
+            let windows: Vec<_> = contig_windows(contig, 10, 5).map(|w| w.at).collect();
+            assert_eq!(windows, [0..10, 5..15, 10..20, 15..25, 20..25]);
+            assert_eq!(contig_windows(contig, 10, 5).size_hint(), (5, Some(5)));
+        }
+
+        #[test]
+        fn aggregate_counts() {
+            let contig = ContigRef::new("chr1", 25);
+            let at = |at| (ContigPosition { contig, at }, ());
+
+            let counts = aggregate(
+                contig_windows(contig, 10, 5),
+                [at(0), at(7), at(12), at(24), at(24)],
+                |_| 0,
+                |count, _, _| *count += 1,
+            );
+            let counts: Vec<_> = counts.into_iter().map(|(w, c)| (w.at, c)).collect();
+            assert_eq!(
+                counts,
+                [
+                    (0..10, 2),
+                    (5..15, 2),
+                    (10..20, 1),
+                    (15..25, 2),
+                    (20..25, 2)
+                ]
+            );
+        }
+    }
+}
+
 mod math {
     use std::ops::{Add, AddAssign, Sub, SubAssign};
 
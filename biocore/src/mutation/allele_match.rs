@@ -0,0 +1,196 @@
+//! Matching the alleles of a variant from a score or summary stats against the same variant in a
+//! genotype dataset, which may report them swapped or on the opposite strand.
+
+use serde::{Deserialize, Serialize};
+
+use crate::dna::DnaSequenceSlice;
+
+/// The two alleles of a biallelic variant.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Alleles<'a> {
+    /// The allele that is counted: the effect allele of a score or summary stats,
+    /// the alternate allele of genotypes.
+    pub counted: &'a DnaSequenceSlice,
+    pub other: &'a DnaSequenceSlice,
+    /// Frequency of [Self::counted], used for [AlleleMatcher::resolve_ambiguous_by_af].
+    pub af: Option<f64>,
+}
+
+/// How the alleles of a variant relate to those of the dataset, see [AlleleMatcher::classify].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Serialize, Deserialize)]
+pub enum AlleleMatch {
+    Exact,
+    /// The counted allele is the other allele in the dataset.
+    Swapped,
+    /// The same alleles, on the opposite strand.
+    StrandFlipped,
+    /// Both [Self::Swapped] and [Self::StrandFlipped].
+    StrandFlippedSwapped,
+    /// A palindromic variant (A/T, C/G), the strand can't be told from the alleles.
+    Ambiguous,
+    Mismatch,
+}
+impl AlleleMatch {
+    pub fn is_match(&self) -> bool {
+        !matches!(self, Self::Ambiguous | Self::Mismatch)
+    }
+    /// Whether effects must have their sign flipped (or dosages be `2 - d`) to apply to the dataset.
+    pub fn is_swapped(&self) -> bool {
+        matches!(self, Self::Swapped | Self::StrandFlippedSwapped)
+    }
+}
+
+/// Classifies variants against a dataset, see [AlleleMatch].
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct AlleleMatcher {
+    max_ambiguous_maf: Option<f64>,
+}
+
+impl AlleleMatcher {
+    /// Palindromic variants are always [AlleleMatch::Ambiguous].
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Resolves palindromic variants by comparing allele frequencies, when both are known
+    /// and both minor allele frequencies are at most `max_maf`.
+    ///
+    /// Frequencies closer to 0.5 can't tell the alleles apart, 0.4 is a common cut-off.
+    pub fn resolve_ambiguous_by_af(mut self, max_maf: f64) -> Self {
+        self.max_ambiguous_maf = Some(max_maf);
+        self
+    }
+
+    /// Classifies `variant` against the same variant in the dataset.
+    pub fn classify(&self, variant: Alleles, dataset: Alleles) -> AlleleMatch {
+        let complement = |a: &DnaSequenceSlice, b: &DnaSequenceSlice| *a.reverse_complement() == *b;
+
+        if is_palindromic(variant) {
+            if !is_palindromic(dataset) || !same_set(variant, dataset) {
+                return AlleleMatch::Mismatch;
+            }
+            return self.resolve_ambiguous(variant, dataset);
+        }
+
+        if variant.counted == dataset.counted && variant.other == dataset.other {
+            AlleleMatch::Exact
+        } else if variant.counted == dataset.other && variant.other == dataset.counted {
+            AlleleMatch::Swapped
+        } else if complement(variant.counted, dataset.counted)
+            && complement(variant.other, dataset.other)
+        {
+            AlleleMatch::StrandFlipped
+        } else if complement(variant.counted, dataset.other)
+            && complement(variant.other, dataset.counted)
+        {
+            AlleleMatch::StrandFlippedSwapped
+        } else {
+            AlleleMatch::Mismatch
+        }
+    }
+
+    /// On the opposite strand, the counted allele of a palindromic variant is the other allele,
+    /// so only the frequencies tell whether it's the same allele.
+    fn resolve_ambiguous(&self, variant: Alleles, dataset: Alleles) -> AlleleMatch {
+        let (Some(max_maf), Some(variant_af), Some(dataset_af)) =
+            (self.max_ambiguous_maf, variant.af, dataset.af)
+        else {
+            return AlleleMatch::Ambiguous;
+        };
+        let maf = |af: f64| af.min(1. - af);
+        if maf(variant_af) > max_maf || maf(dataset_af) > max_maf {
+            return AlleleMatch::Ambiguous;
+        }
+
+        let same_name = variant.counted == dataset.counted;
+        let same_allele = (variant_af > 0.5) == (dataset_af > 0.5);
+        match (same_name, same_allele) {
+            (true, true) => AlleleMatch::Exact,
+            (true, false) => AlleleMatch::StrandFlippedSwapped,
+            (false, true) => AlleleMatch::StrandFlipped,
+            (false, false) => AlleleMatch::Swapped,
+        }
+    }
+}
+
+/// The alleles are each other's reverse complement.
+fn is_palindromic(alleles: Alleles) -> bool {
+    *alleles.counted.reverse_complement() == *alleles.other
+}
+
+fn same_set(a: Alleles, b: Alleles) -> bool {
+    (a.counted == b.counted && a.other == b.other) || (a.counted == b.other && a.other == b.counted)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dna::DnaSequence;
+
+    use super::{AlleleMatch, AlleleMatcher, Alleles};
+
+    fn classify(
+        matcher: AlleleMatcher,
+        variant: (&str, &str, Option<f64>),
+        dataset: (&str, &str, Option<f64>),
+    ) -> AlleleMatch {
+        let parse = |s: &str| -> DnaSequence { s.parse().unwrap() };
+        let (variant_counted, variant_other) = (parse(variant.0), parse(variant.1));
+        let (dataset_counted, dataset_other) = (parse(dataset.0), parse(dataset.1));
+        matcher.classify(
+            Alleles {
+                counted: &variant_counted,
+                other: &variant_other,
+                af: variant.2,
+            },
+            Alleles {
+                counted: &dataset_counted,
+                other: &dataset_other,
+                af: dataset.2,
+            },
+        )
+    }
+
+    #[test]
+    fn test_classify_alleles() {
+        let matcher = AlleleMatcher::new();
+        let c = |v: (&str, &str), d: (&str, &str)| {
+            classify(matcher, (v.0, v.1, None), (d.0, d.1, None))
+        };
+        assert_eq!(c(("A", "G"), ("A", "G")), AlleleMatch::Exact);
+        assert_eq!(c(("A", "G"), ("G", "A")), AlleleMatch::Swapped);
+        assert_eq!(c(("A", "G"), ("T", "C")), AlleleMatch::StrandFlipped);
+        assert_eq!(c(("A", "G"), ("C", "T")), AlleleMatch::StrandFlippedSwapped);
+        assert_eq!(c(("A", "G"), ("A", "C")), AlleleMatch::Mismatch);
+        assert_eq!(c(("AT", "A"), ("T", "AT")), AlleleMatch::Swapped);
+        assert_eq!(c(("AT", "A"), ("AT", "T")), AlleleMatch::StrandFlipped);
+
+        assert_eq!(c(("A", "T"), ("A", "T")), AlleleMatch::Ambiguous);
+        assert_eq!(c(("C", "G"), ("G", "C")), AlleleMatch::Ambiguous);
+        assert_eq!(c(("A", "T"), ("C", "G")), AlleleMatch::Mismatch);
+        assert!(!AlleleMatch::Ambiguous.is_match());
+    }
+
+    #[test]
+    fn test_resolve_by_af() {
+        let matcher = AlleleMatcher::new().resolve_ambiguous_by_af(0.4);
+        let c = |v: (&str, &str, f64), d: (&str, &str, f64)| {
+            classify(matcher, (v.0, v.1, Some(v.2)), (d.0, d.1, Some(d.2)))
+        };
+        assert_eq!(c(("A", "T", 0.1), ("A", "T", 0.15)), AlleleMatch::Exact);
+        assert_eq!(c(("A", "T", 0.1), ("T", "A", 0.85)), AlleleMatch::Swapped);
+        assert_eq!(
+            c(("A", "T", 0.1), ("T", "A", 0.15)),
+            AlleleMatch::StrandFlipped
+        );
+        assert_eq!(
+            c(("A", "T", 0.1), ("A", "T", 0.85)),
+            AlleleMatch::StrandFlippedSwapped
+        );
+        assert!(AlleleMatch::StrandFlippedSwapped.is_swapped());
+        assert_eq!(c(("A", "T", 0.1), ("A", "T", 0.45)), AlleleMatch::Ambiguous);
+        assert_eq!(
+            classify(matcher, ("A", "T", None), ("A", "T", Some(0.1))),
+            AlleleMatch::Ambiguous
+        );
+    }
+}
@@ -0,0 +1,272 @@
+//! Multi-nucleotide variants (MNVs) and complex substitutions.
+//!
+//! Callers usually report nearby changes on the same haplotype as separate records,
+//! even when they hit the same codon and only make sense together.
+
+use std::io;
+
+use utile::num::TryU64;
+
+use crate::{
+    dna::{DnaBase, DnaSequence},
+    location::{ContigPosition, ContigRange},
+    mutation::normalize::{NormalizationError, ReferenceSequence, Variant},
+    sequence::Sequence,
+};
+
+/// Replaces `reference` with `alternate`, starting at `at` (0-based).
+///
+/// Alleles of the same length make a MNV, otherwise it's a complex substitution.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MultiNucleotideVariant<C = String> {
+    pub at: ContigPosition<C>,
+    pub reference: DnaSequence,
+    pub alternate: DnaSequence,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum MnvError {
+    #[error("No variants to recompose.")]
+    Empty,
+    #[error("Variants to recompose must be on the same contig.")]
+    DifferentContigs,
+    #[error("Variants to recompose must be sorted and must not overlap.")]
+    Overlapping,
+    #[error(transparent)]
+    Normalization(#[from] NormalizationError),
+}
+impl From<MnvError> for io::Error {
+    fn from(e: MnvError) -> Self {
+        match e {
+            MnvError::Normalization(e) => e.into(),
+            e => io::Error::new(io::ErrorKind::InvalidInput, e),
+        }
+    }
+}
+
+impl<C: AsRef<str> + Clone> MultiNucleotideVariant<C> {
+    /// Merges variants from the same haplotype into a single change,
+    /// filling the gaps between them from the reference.
+    ///
+    /// The variants must be sorted, on the same contig, and not overlap.
+    pub fn recompose(
+        variants: &[Variant<C>],
+        genome: &mut impl ReferenceSequence<C>,
+    ) -> Result<Self, MnvError> {
+        let (first, last) = match variants {
+            [] => return Err(MnvError::Empty),
+            [first, .., last] => (first, last),
+            [single] => (single, single),
+        };
+        let contig = first.at.contig.as_ref();
+        if variants.iter().any(|v| v.at.contig.as_ref() != contig) {
+            return Err(MnvError::DifferentContigs);
+        }
+        if variants.windows(2).any(|w| w[1].at.at < end(&w[0])) {
+            return Err(MnvError::Overlapping);
+        }
+        for variant in variants {
+            variant.validate(genome)?;
+        }
+
+        let start = first.at.at;
+        let range = ContigRange {
+            contig: first.at.contig.clone(),
+            at: start..end(last),
+        };
+        let reference = genome.fetch(&range)?;
+
+        let mut alternate: Vec<DnaBase> = vec![];
+        let mut cursor = start;
+        for variant in variants {
+            let gap = offset(start, cursor)..offset(start, variant.at.at);
+            alternate.extend_from_slice(&reference[gap]);
+            alternate.extend_from_slice(&variant.alternate);
+            cursor = end(variant);
+        }
+        alternate.extend_from_slice(&reference[offset(start, cursor)..]);
+
+        Ok(Self {
+            at: first.at.clone(),
+            reference,
+            alternate: Sequence::new(alternate),
+        })
+    }
+
+    /// Whether the alleles have different lengths.
+    pub fn is_complex(&self) -> bool {
+        self.reference.len() != self.alternate.len()
+    }
+    pub fn range(&self) -> ContigRange<C> {
+        ContigRange {
+            contig: self.at.contig.clone(),
+            at: self.at.at..self.at.at + self.reference.len().u64_unwrap(),
+        }
+    }
+
+    /// Splits the change into its SNVs, skipping unchanged bases.
+    ///
+    /// Returns [None] for complex substitutions.
+    pub fn decompose(&self) -> Option<Vec<Variant<C>>> {
+        if self.is_complex() {
+            return None;
+        }
+        let snvs = self
+            .reference
+            .iter()
+            .zip(self.alternate.iter())
+            .enumerate()
+            .filter(|(_, (r, a))| r != a)
+            .map(|(i, (r, a))| Variant {
+                at: ContigPosition {
+                    contig: self.at.contig.clone(),
+                    at: self.at.at + i.u64_unwrap(),
+                },
+                reference: Sequence::new(vec![*r]),
+                alternate: Sequence::new(vec![*a]),
+            })
+            .collect();
+        Some(snvs)
+    }
+}
+
+impl<C> From<MultiNucleotideVariant<C>> for Variant<C> {
+    fn from(v: MultiNucleotideVariant<C>) -> Self {
+        Variant {
+            at: v.at,
+            reference: v.reference,
+            alternate: v.alternate,
+        }
+    }
+}
+impl<C> From<Variant<C>> for MultiNucleotideVariant<C> {
+    fn from(v: Variant<C>) -> Self {
+        MultiNucleotideVariant {
+            at: v.at,
+            reference: v.reference,
+            alternate: v.alternate,
+        }
+    }
+}
+
+/// Groups sorted variants from a single haplotype, starting a new group whenever
+/// the gap to the previous variant is larger than `max_distance` bases.
+///
+/// `max_distance = 0` only groups directly adjacent variants, `2` groups all changes
+/// that could share a codon.
+pub fn group_phased<C: AsRef<str>>(
+    variants: impl IntoIterator<Item = Variant<C>>,
+    max_distance: u64,
+) -> Vec<Vec<Variant<C>>> {
+    let mut groups: Vec<Vec<Variant<C>>> = vec![];
+    for variant in variants {
+        let extends = groups.last().and_then(|g| g.last()).is_some_and(|last| {
+            last.at.contig.as_ref() == variant.at.contig.as_ref()
+                && end(last) <= variant.at.at
+                && variant.at.at - end(last) <= max_distance
+        });
+        match groups.last_mut() {
+            Some(group) if extends => group.push(variant),
+            _ => groups.push(vec![variant]),
+        }
+    }
+    groups
+}
+
+/// Recomposes the MNVs in a single haplotype, see [group_phased].
+///
+/// Isolated variants are skipped.
+pub fn recompose_phased<C: AsRef<str> + Clone>(
+    variants: impl IntoIterator<Item = Variant<C>>,
+    max_distance: u64,
+    genome: &mut impl ReferenceSequence<C>,
+) -> Result<Vec<MultiNucleotideVariant<C>>, MnvError> {
+    group_phased(variants, max_distance)
+        .into_iter()
+        .filter(|group| group.len() > 1)
+        .map(|group| MultiNucleotideVariant::recompose(&group, genome))
+        .collect()
+}
+
+fn end<C>(variant: &Variant<C>) -> u64 {
+    variant.at.at + variant.reference.len().u64_unwrap()
+}
+fn offset(start: u64, at: u64) -> usize {
+    usize::try_from(at - start).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        dna::{DnaBase, DnaSequence},
+        genome::InMemoryGenome,
+        location::ContigPosition,
+        mutation::normalize::Variant,
+        sequence::AsciiChar,
+    };
+
+    use super::{MnvError, MultiNucleotideVariant, group_phased, recompose_phased};
+
+    fn seq(s: &str) -> DnaSequence {
+        DnaBase::decode(s.as_bytes().to_vec()).unwrap()
+    }
+    fn variant(at: u64, reference: &str, alternate: &str) -> Variant {
+        Variant {
+            at: ContigPosition {
+                contig: "chr1".to_owned(),
+                at,
+            },
+            reference: seq(reference),
+            alternate: seq(alternate),
+        }
+    }
+    fn genome() -> InMemoryGenome<String> {
+        InMemoryGenome::new([("chr1".to_owned(), seq("ATGGCTTGGCGT"))])
+    }
+
+    #[test]
+    fn recompose_and_decompose() {
+        let mut genome = genome();
+
+        let snvs = [variant(3, "G", "A"), variant(5, "T", "C")];
+        let mnv = MultiNucleotideVariant::recompose(&snvs, &mut genome).unwrap();
+        assert_eq!(Variant::from(mnv.clone()), variant(3, "GCT", "ACC"));
+        assert!(!mnv.is_complex());
+        assert_eq!(mnv.decompose().unwrap(), snvs);
+
+        let parts = [variant(3, "G", "A"), variant(5, "TT", "T")];
+        let complex = MultiNucleotideVariant::recompose(&parts, &mut genome).unwrap();
+        assert_eq!(Variant::from(complex.clone()), variant(3, "GCTT", "ACT"));
+        assert!(complex.is_complex());
+        assert_eq!(complex.decompose(), None);
+
+        let overlapping = [variant(3, "GC", "AA"), variant(4, "C", "T")];
+        assert!(matches!(
+            MultiNucleotideVariant::recompose(&overlapping, &mut genome),
+            Err(MnvError::Overlapping)
+        ));
+        assert!(matches!(
+            MultiNucleotideVariant::recompose(&[variant(3, "T", "A")], &mut genome),
+            Err(MnvError::Normalization(_))
+        ));
+    }
+
+    #[test]
+    fn groups_neighbours() {
+        let mut genome = genome();
+        let haplotype = [
+            variant(0, "A", "G"),
+            variant(3, "G", "A"),
+            variant(4, "C", "G"),
+            variant(9, "C", "A"),
+        ];
+
+        let groups = group_phased(haplotype.clone(), 0);
+        let sizes: Vec<_> = groups.iter().map(|g| g.len()).collect();
+        assert_eq!(sizes, [1, 2, 1]);
+
+        let mnvs = recompose_phased(haplotype, 2, &mut genome).unwrap();
+        let mnvs: Vec<Variant> = mnvs.into_iter().map(Variant::from).collect();
+        assert_eq!(mnvs, [variant(0, "ATGGC", "GTGAG")]);
+    }
+}
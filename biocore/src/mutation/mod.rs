@@ -1,3 +1,8 @@
+pub mod allele_match;
+pub mod mnv;
+pub mod normalize;
+pub mod structural;
+
 use std::{fmt, iter, ops::Range};
 
 use either::Either;
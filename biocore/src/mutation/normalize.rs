@@ -0,0 +1,245 @@
+//! Reference-checked variant normalization (left-alignment and trimming).
+//!
+//! Follows the definition in Tan et al. (2015), "Unified representation of genetic variants":
+//! a normalized variant is parsimonious (no redundant shared bases) and left-aligned,
+//! keeping a single anchor base for indels as in VCF.
+
+use std::io::{self, BufRead, Seek};
+
+use utile::num::TryU64;
+
+use crate::{
+    dna::{DnaBase, DnaSequence},
+    fasta::IndexedFastaReader,
+    genome::InMemoryGenome,
+    location::{ContigPosition, ContigRange},
+    sequence::Sequence,
+};
+
+/// A variant replacing `reference` with `alternate`, starting at `at` (0-based).
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Variant<C = String> {
+    pub at: ContigPosition<C>,
+    pub reference: DnaSequence,
+    pub alternate: DnaSequence,
+}
+
+/// Random access to the bases of a reference genome.
+pub trait ReferenceSequence<C> {
+    fn fetch(&mut self, range: &ContigRange<C>) -> io::Result<DnaSequence>;
+}
+impl<R, C> ReferenceSequence<C> for IndexedFastaReader<R>
+where
+    R: BufRead + Seek,
+    C: AsRef<str> + Clone,
+{
+    fn fetch(&mut self, range: &ContigRange<C>) -> io::Result<DnaSequence> {
+        self.query(range)
+    }
+}
+impl<C: Ord + AsRef<str>> ReferenceSequence<C> for InMemoryGenome<C, DnaBase> {
+    fn fetch(&mut self, range: &ContigRange<C>) -> io::Result<DnaSequence> {
+        let sequence = self.get_range(range).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!(
+                    "{}:{:?} is outside of the genome.",
+                    range.contig.as_ref(),
+                    range.at
+                ),
+            )
+        })?;
+        Ok(sequence.to_owned())
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum NormalizationError {
+    #[error(
+        "Reference mismatch at {contig}:{at}: the variant states {stated:?} but the reference has {found:?}."
+    )]
+    ReferenceMismatch {
+        contig: String,
+        /// 0-based.
+        at: u64,
+        stated: String,
+        found: String,
+    },
+    #[error("The reference and alternate alleles are identical.")]
+    IdenticalAlleles,
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+impl From<NormalizationError> for io::Error {
+    fn from(e: NormalizationError) -> Self {
+        match e {
+            NormalizationError::Io(e) => e,
+            e => io::Error::new(io::ErrorKind::InvalidData, e),
+        }
+    }
+}
+
+impl<C: AsRef<str> + Clone> Variant<C> {
+    /// Checks that the reference allele matches the genome.
+    pub fn validate(
+        &self,
+        genome: &mut impl ReferenceSequence<C>,
+    ) -> Result<(), NormalizationError> {
+        let range = ContigRange {
+            contig: self.at.contig.clone(),
+            at: self.at.at..self.at.at + self.reference.len().u64_unwrap(),
+        };
+        let found = genome.fetch(&range)?;
+        if found != self.reference {
+            return Err(NormalizationError::ReferenceMismatch {
+                contig: self.at.contig.as_ref().to_owned(),
+                at: self.at.at,
+                stated: self.reference.encode(),
+                found: found.encode(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Validates the reference allele, then left-aligns and trims the variant.
+    ///
+    /// Indels keep one anchor base before the event (as in VCF), unless the
+    /// event is at the very start of the contig.
+    pub fn normalize(
+        self,
+        genome: &mut impl ReferenceSequence<C>,
+    ) -> Result<Self, NormalizationError> {
+        if self.reference == self.alternate {
+            return Err(NormalizationError::IdenticalAlleles);
+        }
+        self.validate(genome)?;
+
+        let Variant {
+            at: ContigPosition { contig, mut at },
+            reference,
+            alternate,
+        } = self;
+        let mut reference: Vec<DnaBase> = reference.to_vec();
+        let mut alternate: Vec<DnaBase> = alternate.to_vec();
+
+        // Trim shared trailing bases, extending to the left whenever an allele runs out.
+        loop {
+            let mut changed = false;
+
+            if !reference.is_empty()
+                && !alternate.is_empty()
+                && reference.last() == alternate.last()
+            {
+                reference.pop();
+                alternate.pop();
+                changed = true;
+            }
+
+            if (reference.is_empty() || alternate.is_empty()) && at > 0 {
+                let range = ContigRange {
+                    contig: contig.clone(),
+                    at: at - 1..at,
+                };
+                let base = genome.fetch(&range)?[0];
+                reference.insert(0, base);
+                alternate.insert(0, base);
+                at -= 1;
+                changed = true;
+            }
+
+            if !changed {
+                break;
+            }
+        }
+
+        // Trim shared leading bases, keeping at least one base in each allele.
+        let shared = reference
+            .iter()
+            .zip(&alternate)
+            .take_while(|(r, a)| r == a)
+            .count()
+            .min(reference.len().saturating_sub(1))
+            .min(alternate.len().saturating_sub(1));
+        reference.drain(..shared);
+        alternate.drain(..shared);
+        at += shared.u64_unwrap();
+
+        Ok(Variant {
+            at: ContigPosition { contig, at },
+            reference: Sequence::new(reference),
+            alternate: Sequence::new(alternate),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        dna::{DnaBase, DnaSequence},
+        genome::InMemoryGenome,
+        location::ContigPosition,
+        sequence::AsciiChar,
+    };
+
+    use super::{NormalizationError, Variant};
+
+    fn seq(s: &str) -> DnaSequence {
+        DnaBase::decode(s.as_bytes().to_vec()).unwrap()
+    }
+    fn variant(at: u64, reference: &str, alternate: &str) -> Variant {
+        Variant {
+            at: ContigPosition {
+                contig: "chr1".to_owned(),
+                at,
+            },
+            reference: seq(reference),
+            alternate: seq(alternate),
+        }
+    }
+    fn genome() -> InMemoryGenome<String> {
+        InMemoryGenome::new([("chr1".to_owned(), seq("GGCACACAGT"))])
+    }
+
+    #[test]
+    fn left_aligns_indels() {
+        let mut genome = genome();
+
+        // Deleting the last `CA` of the repeat is the same as deleting the first.
+        let normalized = variant(5, "ACA", "A").normalize(&mut genome).unwrap();
+        assert_eq!(normalized, variant(1, "GCA", "G"));
+
+        // Same for insertions.
+        let normalized = variant(7, "A", "ACA").normalize(&mut genome).unwrap();
+        assert_eq!(normalized, variant(1, "G", "GCA"));
+    }
+
+    #[test]
+    fn trims_shared_bases() {
+        let mut genome = genome();
+
+        let normalized = variant(2, "CAC", "CGC").normalize(&mut genome).unwrap();
+        assert_eq!(normalized, variant(3, "A", "G"));
+
+        let normalized = variant(8, "G", "T").normalize(&mut genome).unwrap();
+        assert_eq!(normalized, variant(8, "G", "T"));
+    }
+
+    #[test]
+    fn reports_mismatches() {
+        let mut genome = genome();
+
+        let error = variant(2, "G", "T").normalize(&mut genome).unwrap_err();
+        let NormalizationError::ReferenceMismatch {
+            at, stated, found, ..
+        } = error
+        else {
+            panic!("expected a reference mismatch");
+        };
+        assert_eq!((at, &*stated, &*found), (2, "G", "C"));
+
+        assert!(matches!(
+            variant(2, "C", "C").normalize(&mut genome),
+            Err(NormalizationError::IdenticalAlleles)
+        ));
+    }
+}
@@ -0,0 +1,413 @@
+//! Structural variants, as represented by symbolic and breakend VCF ALT alleles.
+
+use std::{fmt, io, ops::Range, str::FromStr};
+
+use crate::location::{ContigPosition, ContigRange};
+
+/// A structural ALT allele: either symbolic (`<DEL>`, `<CN0>`, ...) or a breakend (`G]17:198982]`).
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum StructuralAllele<C = String> {
+    Symbolic(SymbolicAllele),
+    Breakend(Breakend<C>),
+}
+
+/// A symbolic ALT allele, e.g. `<DEL>`, `<DUP:TANDEM>`, `<INS:ME:ALU>` or `<CN0>`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SymbolicAllele {
+    pub kind: StructuralVariantKind,
+    /// Colon-separated subtypes after the kind, e.g. `["ME", "ALU"]` for `<INS:ME:ALU>`.
+    pub subtypes: Vec<String>,
+}
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum StructuralVariantKind {
+    /// `DEL`
+    Deletion,
+    /// `DUP`
+    Duplication,
+    /// `INS`
+    Insertion,
+    /// `INV`
+    Inversion,
+    /// `CNV` ([None]) or `CN<n>` (a specific copy number).
+    CopyNumber(Option<u32>),
+    /// Any other identifier, these can be defined in the VCF header.
+    Other(String),
+}
+
+/// A breakend ALT allele, one side of a novel adjacency.
+///
+/// See section 5.4 of the VCF specification.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Breakend<C = String> {
+    /// The bases at this end, usually the reference base, possibly followed by inserted bases.
+    pub bases: String,
+    /// Whether [Self::bases] come before the joined sequence (`t[p[`, `t]p]`, `t.`)
+    /// rather than after it (`]p]t`, `[p[t`, `.t`).
+    pub bases_first: bool,
+    /// [None] for single breakends (`t.` and `.t`).
+    pub mate: Option<Mate<C>>,
+}
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Mate<C = String> {
+    /// 0-based.
+    pub at: ContigPosition<C>,
+    /// Whether the joined sequence extends to the right of the mate position (`[`),
+    /// or to the left of it (`]`).
+    pub extends_right: bool,
+}
+
+/// A structural variant anchored on the reference.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StructuralVariant<C = String> {
+    /// The (0-based) position of the VCF record, for symbolic alleles this is the
+    /// padding base just before the event.
+    pub at: ContigPosition<C>,
+    /// The (0-based, exclusive) end of the affected region, from the `END` INFO field.
+    pub end: Option<u64>,
+    pub allele: StructuralAllele<C>,
+}
+
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum StructuralAlleleError {
+    #[error("Invalid symbolic allele {0:?}.")]
+    InvalidSymbolic(String),
+    #[error("Invalid breakend {0:?}.")]
+    InvalidBreakend(String),
+    #[error("Invalid mate contig in breakend {0:?}.")]
+    InvalidContig(String),
+}
+impl From<StructuralAlleleError> for io::Error {
+    fn from(e: StructuralAlleleError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, e)
+    }
+}
+
+/// Whether an ALT allele looks structural (symbolic or breakend), without fully parsing it.
+pub fn is_structural(allele: &str) -> bool {
+    allele.starts_with('<')
+        || allele.contains(['[', ']'])
+        || (allele.len() > 1 && (allele.starts_with('.') || allele.ends_with('.')))
+}
+
+impl<C> StructuralAllele<C> {
+    pub fn kind(&self) -> Option<&StructuralVariantKind> {
+        match self {
+            StructuralAllele::Symbolic(symbolic) => Some(&symbolic.kind),
+            StructuralAllele::Breakend(_) => None,
+        }
+    }
+    pub fn map_contig<NewContig>(
+        self,
+        f: impl FnOnce(C) -> NewContig,
+    ) -> StructuralAllele<NewContig> {
+        match self {
+            StructuralAllele::Symbolic(symbolic) => StructuralAllele::Symbolic(symbolic),
+            StructuralAllele::Breakend(breakend) => StructuralAllele::Breakend(Breakend {
+                bases: breakend.bases,
+                bases_first: breakend.bases_first,
+                mate: breakend.mate.map(|mate| Mate {
+                    at: mate.at.map_contig(f),
+                    extends_right: mate.extends_right,
+                }),
+            }),
+        }
+    }
+}
+
+impl SymbolicAllele {
+    pub fn new(kind: StructuralVariantKind) -> Self {
+        Self {
+            kind,
+            subtypes: vec![],
+        }
+    }
+    pub fn copy_number(&self) -> Option<u32> {
+        match self.kind {
+            StructuralVariantKind::CopyNumber(n) => n,
+            _ => None,
+        }
+    }
+}
+
+impl<C> StructuralVariant<C> {
+    /// The reference bases affected by the event, excluding the padding base.
+    ///
+    /// [None] for breakends, or if the end is unknown.
+    pub fn affected_range(&self) -> Option<ContigRange<C>>
+    where
+        C: Clone,
+    {
+        if let StructuralAllele::Breakend(_) = self.allele {
+            return None;
+        }
+        let end = self.end?;
+        Some(ContigRange {
+            contig: self.at.contig.clone(),
+            at: Range {
+                start: (self.at.at + 1).min(end),
+                end,
+            },
+        })
+    }
+}
+
+impl FromStr for SymbolicAllele {
+    type Err = StructuralAlleleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || StructuralAlleleError::InvalidSymbolic(s.to_owned());
+
+        let inner = s
+            .strip_prefix('<')
+            .and_then(|s| s.strip_suffix('>'))
+            .filter(|s| !s.is_empty() && !s.contains(['<', '>']))
+            .ok_or_else(invalid)?;
+
+        let mut parts = inner.split(':');
+        let id = parts.next().ok_or_else(invalid)?;
+        let subtypes: Vec<String> = parts.map(str::to_owned).collect();
+        if subtypes.iter().any(|s| s.is_empty()) {
+            return Err(invalid());
+        }
+
+        let kind = match id {
+            "DEL" => StructuralVariantKind::Deletion,
+            "DUP" => StructuralVariantKind::Duplication,
+            "INS" => StructuralVariantKind::Insertion,
+            "INV" => StructuralVariantKind::Inversion,
+            "CNV" => StructuralVariantKind::CopyNumber(None),
+            _ => match id.strip_prefix("CN").map(str::parse) {
+                Some(Ok(n)) => StructuralVariantKind::CopyNumber(Some(n)),
+                _ => StructuralVariantKind::Other(id.to_owned()),
+            },
+        };
+
+        Ok(Self { kind, subtypes })
+    }
+}
+impl<C: FromStr> FromStr for Breakend<C> {
+    type Err = StructuralAlleleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || StructuralAlleleError::InvalidBreakend(s.to_owned());
+
+        let Some(open) = s.find(['[', ']']) else {
+            // Single breakend.
+            return if let Some(bases) = s.strip_suffix('.').filter(|b| !b.is_empty()) {
+                Ok(Self {
+                    bases: bases.to_owned(),
+                    bases_first: true,
+                    mate: None,
+                })
+            } else if let Some(bases) = s.strip_prefix('.').filter(|b| !b.is_empty()) {
+                Ok(Self {
+                    bases: bases.to_owned(),
+                    bases_first: false,
+                    mate: None,
+                })
+            } else {
+                Err(invalid())
+            };
+        };
+
+        let bracket = s[open..].chars().next().unwrap();
+        let rest = &s[open + 1..];
+        let close = rest.find(bracket).ok_or_else(invalid)?;
+        let (mate, after) = (&rest[..close], &rest[close + 1..]);
+        let before = &s[..open];
+
+        let (bases, bases_first) = match (before.is_empty(), after.is_empty()) {
+            (false, true) => (before, true),
+            (true, false) => (after, false),
+            _ => return Err(invalid()),
+        };
+        if bases.contains(['[', ']']) {
+            return Err(invalid());
+        }
+
+        let (contig, position) = mate.rsplit_once(':').ok_or_else(invalid)?;
+        // Contigs can be written as `<ctg>` when they are not in the assembly.
+        let contig = contig
+            .strip_prefix('<')
+            .and_then(|c| c.strip_suffix('>'))
+            .unwrap_or(contig);
+        let position: u64 = position.parse().map_err(|_| invalid())?;
+
+        Ok(Self {
+            bases: bases.to_owned(),
+            bases_first,
+            mate: Some(Mate {
+                at: ContigPosition {
+                    contig: contig
+                        .parse()
+                        .map_err(|_| StructuralAlleleError::InvalidContig(s.to_owned()))?,
+                    at: position.checked_sub(1).ok_or_else(invalid)?,
+                },
+                extends_right: bracket == '[',
+            }),
+        })
+    }
+}
+impl<C: FromStr> FromStr for StructuralAllele<C> {
+    type Err = StructuralAlleleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.starts_with('<') {
+            Ok(Self::Symbolic(s.parse()?))
+        } else {
+            Ok(Self::Breakend(s.parse()?))
+        }
+    }
+}
+
+impl fmt::Display for StructuralVariantKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StructuralVariantKind::Deletion => write!(f, "DEL"),
+            StructuralVariantKind::Duplication => write!(f, "DUP"),
+            StructuralVariantKind::Insertion => write!(f, "INS"),
+            StructuralVariantKind::Inversion => write!(f, "INV"),
+            StructuralVariantKind::CopyNumber(None) => write!(f, "CNV"),
+            StructuralVariantKind::CopyNumber(Some(n)) => write!(f, "CN{n}"),
+            StructuralVariantKind::Other(id) => write!(f, "{id}"),
+        }
+    }
+}
+impl fmt::Display for SymbolicAllele {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<{}", self.kind)?;
+        for subtype in &self.subtypes {
+            write!(f, ":{subtype}")?;
+        }
+        write!(f, ">")
+    }
+}
+impl<C: AsRef<str>> fmt::Display for Breakend<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(mate) = &self.mate else {
+            return if self.bases_first {
+                write!(f, "{}.", self.bases)
+            } else {
+                write!(f, ".{}", self.bases)
+            };
+        };
+
+        let bracket = if mate.extends_right { '[' } else { ']' };
+        let mate = format!(
+            "{bracket}{}:{}{bracket}",
+            mate.at.contig.as_ref(),
+            mate.at.at + 1
+        );
+        if self.bases_first {
+            write!(f, "{}{mate}", self.bases)
+        } else {
+            write!(f, "{mate}{}", self.bases)
+        }
+    }
+}
+impl<C: AsRef<str>> fmt::Display for StructuralAllele<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StructuralAllele::Symbolic(symbolic) => fmt::Display::fmt(symbolic, f),
+            StructuralAllele::Breakend(breakend) => fmt::Display::fmt(breakend, f),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::location::{ContigPosition, ContigRange};
+
+    use super::{
+        Breakend, Mate, StructuralAllele, StructuralVariant, StructuralVariantKind, SymbolicAllele,
+        is_structural,
+    };
+
+    #[test]
+    fn symbolic_round_trip() {
+        for s in [
+            "<DEL>",
+            "<DUP:TANDEM>",
+            "<INS:ME:ALU>",
+            "<CN0>",
+            "<CNV>",
+            "<INS:MT>",
+        ] {
+            let allele: SymbolicAllele = s.parse().unwrap();
+            assert_eq!(allele.to_string(), s);
+        }
+
+        let allele: SymbolicAllele = "<CN3>".parse().unwrap();
+        assert_eq!(allele.copy_number(), Some(3));
+        let allele: SymbolicAllele = "<INS:ME:LINE1>".parse().unwrap();
+        assert_eq!(allele.kind, StructuralVariantKind::Insertion);
+        assert_eq!(allele.subtypes, ["ME", "LINE1"]);
+
+        assert!("DEL".parse::<SymbolicAllele>().is_err());
+        assert!("<>".parse::<SymbolicAllele>().is_err());
+        assert!("<DEL:>".parse::<SymbolicAllele>().is_err());
+    }
+
+    #[test]
+    fn breakend_round_trip() {
+        for (s, bases_first, extends_right) in [
+            ("G]17:198982]", true, false),
+            ("]13:123456]T", false, false),
+            ("C[2:321682[", true, true),
+            ("[17:198983[A", false, true),
+        ] {
+            let breakend: Breakend = s.parse().unwrap();
+            assert_eq!(breakend.bases_first, bases_first, "{s}");
+            assert_eq!(
+                breakend.mate.as_ref().unwrap().extends_right,
+                extends_right,
+                "{s}"
+            );
+            assert_eq!(breakend.to_string(), s);
+        }
+
+        let breakend: Breakend = "G]17:198982]".parse().unwrap();
+        assert_eq!(
+            breakend.mate,
+            Some(Mate {
+                at: ContigPosition {
+                    contig: "17".to_owned(),
+                    at: 198981,
+                },
+                extends_right: false,
+            })
+        );
+
+        let single: Breakend = "G.".parse().unwrap();
+        assert_eq!(single.mate, None);
+        assert_eq!(single.to_string(), "G.");
+
+        assert!("G]17:198982".parse::<Breakend>().is_err());
+        assert!("G]17:198982]A".parse::<Breakend>().is_err());
+        assert!(".".parse::<Breakend>().is_err());
+    }
+
+    #[test]
+    fn affected_range() {
+        let variant = StructuralVariant {
+            at: ContigPosition {
+                contig: "1".to_owned(),
+                at: 99,
+            },
+            end: Some(200),
+            allele: "<DEL>".parse::<StructuralAllele>().unwrap(),
+        };
+        assert_eq!(
+            variant.affected_range(),
+            Some(ContigRange {
+                contig: "1".to_owned(),
+                at: 100..200,
+            })
+        );
+
+        assert!(is_structural("<CN0>"));
+        assert!(is_structural("A[1:10["));
+        assert!(!is_structural("ACGT"));
+        assert!(!is_structural("."));
+    }
+}
@@ -0,0 +1,183 @@
+//! bedGraph and wiggle writers, for exporting per-position or per-window values
+//! as genome browser tracks.
+//!
+//! Both formats are described at <https://genome.ucsc.edu/goldenPath/help/wiggle.html>.
+
+use std::{
+    fmt,
+    io::{self, Write},
+};
+
+use crate::location::{ContigPosition, ContigRange};
+
+/// Writes `chrom start end value` lines (0-based, half-open, like BED).
+///
+/// Pairs with [crate::location::window::aggregate].
+pub struct BedGraphWriter<W> {
+    writer: W,
+}
+
+/// Writes `fixedStep` and `variableStep` wiggle sections.
+///
+/// Positions are given 0-based and written 1-based, as the format requires.
+pub struct WigWriter<W> {
+    writer: W,
+}
+
+/// Optional header line for either format.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct TrackLine {
+    pub name: Option<String>,
+    pub description: Option<String>,
+}
+
+impl<W: Write> BedGraphWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    pub fn write_track_line(&mut self, track: &TrackLine) -> io::Result<()> {
+        writeln!(self.writer, "{}", DisplayTrack("bedGraph", track))
+    }
+    pub fn write_record<C: AsRef<str>>(
+        &mut self,
+        range: &ContigRange<C>,
+        value: impl fmt::Display,
+    ) -> io::Result<()> {
+        let ContigRange { contig, at } = range;
+        writeln!(
+            self.writer,
+            "{}\t{}\t{}\t{value}",
+            contig.as_ref(),
+            at.start,
+            at.end
+        )
+    }
+    pub fn write_records<'a, C: AsRef<str> + 'a, V: fmt::Display + 'a>(
+        &mut self,
+        records: impl IntoIterator<Item = &'a (ContigRange<C>, V)>,
+    ) -> io::Result<()> {
+        for (range, value) in records {
+            self.write_record(range, value)?;
+        }
+        Ok(())
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> WigWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    pub fn write_track_line(&mut self, track: &TrackLine) -> io::Result<()> {
+        writeln!(self.writer, "{}", DisplayTrack("wiggle_0", track))
+    }
+    /// Consecutive values, each covering `span` bases, `step` bases apart.
+    pub fn write_fixed_step<C: AsRef<str>>(
+        &mut self,
+        start: &ContigPosition<C>,
+        step: u64,
+        span: u64,
+        values: impl IntoIterator<Item = impl fmt::Display>,
+    ) -> io::Result<()> {
+        assert!(step > 0 && span > 0);
+        writeln!(
+            self.writer,
+            "fixedStep chrom={} start={} step={step} span={span}",
+            start.contig.as_ref(),
+            start.at + 1,
+        )?;
+        for value in values {
+            writeln!(self.writer, "{value}")?;
+        }
+        Ok(())
+    }
+    /// Values at the given positions (0-based) of a single contig, each covering `span` bases.
+    pub fn write_variable_step(
+        &mut self,
+        contig: &str,
+        span: u64,
+        values: impl IntoIterator<Item = (u64, impl fmt::Display)>,
+    ) -> io::Result<()> {
+        assert!(span > 0);
+        writeln!(self.writer, "variableStep chrom={contig} span={span}")?;
+        for (at, value) in values {
+            writeln!(self.writer, "{}\t{value}", at + 1)?;
+        }
+        Ok(())
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+struct DisplayTrack<'a>(&'a str, &'a TrackLine);
+impl fmt::Display for DisplayTrack<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let DisplayTrack(kind, TrackLine { name, description }) = self;
+        write!(f, "track type={kind}")?;
+        if let Some(name) = name {
+            write!(f, " name=\"{name}\"")?;
+        }
+        if let Some(description) = description {
+            write!(f, " description=\"{description}\"")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::location::{ContigPosition, ContigRange};
+
+    use super::{BedGraphWriter, TrackLine, WigWriter};
+
+    #[test]
+    fn bedgraph() {
+        let track = TrackLine {
+            name: Some("coverage".to_owned()),
+            description: None,
+        };
+        let range = |at| ContigRange { contig: "chr1", at };
+
+        let mut writer = BedGraphWriter::new(vec![]);
+        writer.write_track_line(&track).unwrap();
+        writer
+            .write_records(&[(range(0..10), 1.5), (range(10..20), 0.0)])
+            .unwrap();
+
+        let written = String::from_utf8(writer.into_inner()).unwrap();
+        assert_eq!(
+            written,
+            "track type=bedGraph name=\"coverage\"\nchr1\t0\t10\t1.5\nchr1\t10\t20\t0\n"
+        );
+    }
+
+    #[test]
+    fn wiggle() {
+        let mut writer = WigWriter::new(vec![]);
+        let start = ContigPosition {
+            contig: "chr2",
+            at: 99,
+        };
+        writer.write_fixed_step(&start, 10, 5, [1, 2]).unwrap();
+        writer.write_variable_step("chr3", 1, [(0, 0.5)]).unwrap();
+
+        let written = String::from_utf8(writer.into_inner()).unwrap();
+        assert_eq!(
+            written,
+            "fixedStep chrom=chr2 start=100 step=10 span=5\n1\n2\nvariableStep chrom=chr3 span=1\n1\t0.5\n"
+        );
+    }
+}
@@ -8,7 +8,10 @@ use std::{
 use serde::{Deserialize, Serialize, de::Unexpected};
 use utile::io::FromUtf8Bytes;
 
-use biocore::genome::Contig;
+use biocore::{
+    genome::Contig,
+    location::build::{self, BuildContig},
+};
 
 mod grch37_meta;
 mod grch38_meta;
@@ -155,6 +158,9 @@ impl Contig for GRCh38Contig {
         self.meta().len
     }
 }
+impl BuildContig for GRCh38Contig {
+    type Build = build::GRCh38;
+}
 impl Display for GRCh38Contig {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.contig)
@@ -363,6 +369,9 @@ impl Contig for GRCh37Contig {
         self.meta().len
     }
 }
+impl BuildContig for GRCh37Contig {
+    type Build = build::GRCh37;
+}
 impl Display for GRCh37Contig {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.contig)
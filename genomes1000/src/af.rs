@@ -0,0 +1,304 @@
+//! Alternate allele frequencies and genotype counts, per population.
+//!
+//! Denominators follow the expected ploidy of each sample (see [GRCh38Contig::ploidy_at]):
+//! males count once outside the pseudoautosomal regions of X and Y, and females are left out on Y.
+
+use std::collections::BTreeMap;
+
+use biocore::location::ContigPosition;
+
+use crate::{
+    DiploidGenotype, GRCh38Contig, Genotype, HaploidGenotype, Record,
+    pedigree::{Pedigree, Sex},
+    simplified::SimplifiedRecord,
+};
+
+/// How samples are grouped, using their [Pedigree].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Stratification {
+    Population,
+    Superpopulation,
+}
+
+/// Tallies genotypes per group, for records with the given sample order.
+#[derive(Debug, Clone)]
+pub struct AlleleCounter {
+    groups: Vec<String>,
+    /// Per sample (in record order), [None] for samples without a pedigree.
+    samples: Vec<Option<(Sex, usize)>>,
+}
+
+/// The counts for one alternate allele, see [AlleleCounter].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AlleleCounts {
+    /// Called alleles, the denominator of [Self::frequency].
+    pub allele_number: u64,
+    /// Copies of the alternate allele.
+    pub allele_count: u64,
+
+    /// Diploid calls without the alternate allele (other alternate alleles count as reference).
+    pub hom_ref: u64,
+    pub het: u64,
+    pub hom_alt: u64,
+    /// Haploid calls without the alternate allele.
+    pub hemi_ref: u64,
+    pub hemi_alt: u64,
+
+    /// Samples without a call, or with a call that doesn't match their expected ploidy.
+    pub missing: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct AlleleFrequencies {
+    pub all: AlleleCounts,
+    pub groups: BTreeMap<String, AlleleCounts>,
+}
+
+impl Stratification {
+    fn group(self, pedigree: &Pedigree) -> &'static str {
+        match self {
+            Self::Population => pedigree.population.code(),
+            Self::Superpopulation => pedigree.superpopulation.code(),
+        }
+    }
+}
+
+impl AlleleCounter {
+    /// Samples without a pedigree are skipped, as their sex (so ploidy) is unknown.
+    pub fn new<'a>(
+        sample_names: &[String],
+        pedigrees: impl IntoIterator<Item = &'a Pedigree>,
+        by: Stratification,
+    ) -> Self {
+        let pedigrees: BTreeMap<&str, &Pedigree> =
+            pedigrees.into_iter().map(|p| (&*p.id, p)).collect();
+
+        let mut groups: Vec<String> = pedigrees.values().map(|p| by.group(p).to_owned()).collect();
+        groups.sort();
+        groups.dedup();
+
+        let samples = sample_names
+            .iter()
+            .map(|name| {
+                let Some(pedigree) = pedigrees.get(&**name) else {
+                    log::warn!("[1000 Genomes][AF] No pedigree for sample {name}, skipping it");
+                    return None;
+                };
+                let group = groups.binary_search_by(|g| g.as_str().cmp(by.group(pedigree)));
+                Some((pedigree.sex, group.unwrap()))
+            })
+            .collect();
+
+        Self { groups, samples }
+    }
+    pub fn groups(&self) -> &[String] {
+        &self.groups
+    }
+    /// Only counts the given samples (indices into the sample names),
+    /// e.g. from [crate::Genomes1000Fs::samples_in].
+    pub fn retain_samples(mut self, samples: &[usize]) -> Self {
+        for (i, sample) in self.samples.iter_mut().enumerate() {
+            if !samples.contains(&i) {
+                *sample = None;
+            }
+        }
+        self
+    }
+
+    /// Counts allele `alt` (`1` for the first alternate allele) of a record.
+    pub fn count_record(&self, record: &Record<Genotype>, alt: u8) -> AlleleFrequencies {
+        self.count(record.at(), &record.samples, alt)
+    }
+    pub fn count_simplified(&self, record: &SimplifiedRecord) -> AlleleFrequencies {
+        self.count(record.at(), &record.samples, 1)
+    }
+    /// Counts allele `alt` in `genotypes`, which are in the same order as the sample names.
+    pub fn count(
+        &self,
+        at: ContigPosition<GRCh38Contig>,
+        genotypes: &[Genotype],
+        alt: u8,
+    ) -> AlleleFrequencies {
+        assert_eq!(genotypes.len(), self.samples.len());
+
+        let mut groups = vec![AlleleCounts::default(); self.groups.len()];
+        for (genotype, sample) in genotypes.iter().zip(&self.samples) {
+            let Some((sex, group)) = *sample else {
+                continue;
+            };
+            let ploidy = at.contig.ploidy_at(at.at, sex);
+            groups[group].tally(*genotype, ploidy, alt);
+        }
+
+        AlleleFrequencies {
+            all: groups.iter().copied().sum(),
+            groups: self.groups.iter().cloned().zip(groups).collect(),
+        }
+    }
+}
+
+impl AlleleCounts {
+    /// [None] if no allele was called.
+    pub fn frequency(&self) -> Option<f64> {
+        if self.allele_number == 0 {
+            return None;
+        }
+        Some(self.allele_count as f64 / self.allele_number as f64)
+    }
+    /// Diploid genotypes, as `[hom_ref, het, hom_alt]`.
+    pub fn diploid(&self) -> [u64; 3] {
+        [self.hom_ref, self.het, self.hom_alt]
+    }
+    /// The diploid genotype counts expected under Hardy-Weinberg equilibrium, given the
+    /// allele frequency among diploid calls. [None] if there are none.
+    pub fn hwe_expected(&self) -> Option<[f64; 3]> {
+        let n = (self.hom_ref + self.het + self.hom_alt) as f64;
+        if n == 0.0 {
+            return None;
+        }
+        let p = (2 * self.hom_alt + self.het) as f64 / (2.0 * n);
+        let q = 1.0 - p;
+        Some([n * q * q, 2.0 * n * p * q, n * p * p])
+    }
+
+    fn tally(&mut self, genotype: Genotype, ploidy: u8, alt: u8) {
+        match (ploidy, genotype) {
+            (0, _) => {}
+            (_, Genotype::Missing) => self.missing += 1,
+            (1, Genotype::Haploid(HaploidGenotype { value })) => self.tally_haploid(value, alt),
+            // Haploid regions are sometimes called as homozygous diploid.
+            (1, Genotype::Diploid(DiploidGenotype { left, right, .. })) if left == right => {
+                self.tally_haploid(left, alt)
+            }
+            (2, Genotype::Diploid(_)) => {
+                let dosage = genotype.dosage(alt);
+                self.allele_number += 2;
+                self.allele_count += u64::from(dosage);
+                match dosage {
+                    0 => self.hom_ref += 1,
+                    1 => self.het += 1,
+                    _ => self.hom_alt += 1,
+                }
+            }
+            _ => self.missing += 1,
+        }
+    }
+    fn tally_haploid(&mut self, value: u8, alt: u8) {
+        self.allele_number += 1;
+        if value == alt {
+            self.allele_count += 1;
+            self.hemi_alt += 1;
+        } else {
+            self.hemi_ref += 1;
+        }
+    }
+}
+impl std::ops::Add for AlleleCounts {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            allele_number: self.allele_number + other.allele_number,
+            allele_count: self.allele_count + other.allele_count,
+            hom_ref: self.hom_ref + other.hom_ref,
+            het: self.het + other.het,
+            hom_alt: self.hom_alt + other.hom_alt,
+            hemi_ref: self.hemi_ref + other.hemi_ref,
+            hemi_alt: self.hemi_alt + other.hemi_alt,
+            missing: self.missing + other.missing,
+        }
+    }
+}
+impl std::iter::Sum for AlleleCounts {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), |a, b| a + b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use biocore::location::ContigPosition;
+
+    use crate::{
+        DiploidGenotype, GRCh38Contig, Genotype, GenotypePhasing, HaploidGenotype,
+        pedigree::{Pedigree, Population, Sex},
+    };
+
+    use super::{AlleleCounter, AlleleCounts, Stratification};
+
+    fn pedigree(id: &str, sex: Sex, population: Population) -> Pedigree {
+        Pedigree {
+            id: id.to_owned(),
+            family_id: id.to_owned(),
+            father_id: "0".to_owned(),
+            mother_id: "0".to_owned(),
+            sex,
+            population,
+            superpopulation: population.superpopulation(),
+        }
+    }
+    fn diploid(left: u8, right: u8) -> Genotype {
+        Genotype::Diploid(DiploidGenotype {
+            left,
+            phasing: GenotypePhasing::Phased,
+            right,
+        })
+    }
+
+    #[test]
+    fn test_counts() {
+        let names = ["A", "B", "C", "D"].map(str::to_owned);
+        let pedigrees = [
+            pedigree("A", Sex::Male, Population::GBR),
+            pedigree("B", Sex::Female, Population::GBR),
+            pedigree("C", Sex::Female, Population::YRI),
+        ];
+        let counter = AlleleCounter::new(&names, &pedigrees, Stratification::Superpopulation);
+        assert_eq!(counter.groups(), ["AFR", "EUR"]);
+
+        let at = |contig, at| ContigPosition { contig, at };
+        let genotypes = [diploid(0, 1), diploid(1, 1), diploid(0, 0), diploid(1, 1)];
+
+        let autosome = counter.count(at(GRCh38Contig::CHR1, 100_000_000), &genotypes, 1);
+        assert_eq!(autosome.all.allele_number, 6);
+        assert_eq!(autosome.all.allele_count, 3);
+        assert_eq!(autosome.all.diploid(), [1, 1, 1]);
+        assert_eq!(autosome.groups["EUR"].frequency(), Some(0.75));
+        assert_eq!(autosome.groups["AFR"].frequency(), Some(0.0));
+        assert_eq!(autosome.all.hwe_expected(), Some([0.75, 1.5, 0.75]));
+
+        let only_b = counter.clone().retain_samples(&[1]);
+        let autosome = only_b.count(at(GRCh38Contig::CHR1, 100_000_000), &genotypes, 1);
+        assert_eq!(autosome.all.diploid(), [0, 0, 1]);
+
+        // The male is haploid here, so his heterozygous call is inconsistent.
+        let x = counter.count(at(GRCh38Contig::X, 100_000_000), &genotypes, 1);
+        assert_eq!(
+            x.groups["EUR"],
+            AlleleCounts {
+                allele_number: 2,
+                allele_count: 2,
+                hom_alt: 1,
+                missing: 1,
+                ..Default::default()
+            }
+        );
+
+        let genotypes = [
+            Genotype::Haploid(HaploidGenotype { value: 1 }),
+            Genotype::Missing,
+            Genotype::Missing,
+            Genotype::Missing,
+        ];
+        let y = counter.count(at(GRCh38Contig::Y, 10_000_000), &genotypes, 1);
+        assert_eq!(
+            y.all,
+            AlleleCounts {
+                allele_number: 1,
+                allele_count: 1,
+                hemi_alt: 1,
+                ..Default::default()
+            }
+        );
+    }
+}
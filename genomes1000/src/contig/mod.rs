@@ -2,13 +2,17 @@ use std::{
     borrow::Borrow,
     cmp::Ordering,
     fmt::{self, Display},
+    ops::Range,
     str::FromStr,
 };
 
 use serde::{Deserialize, Serialize, de::Unexpected};
 use utile::io::FromUtf8Bytes;
 
-use biocore::genome::Contig;
+use biocore::{
+    genome::Contig,
+    location::build::{self, BuildContig},
+};
 
 use crate::pedigree::Sex;
 
@@ -56,6 +60,11 @@ impl GRCh38Contig {
     pub const Y: Self = Self { contig: "chrY" };
     pub const MT: Self = Self { contig: "chrM" };
 
+    /// The pseudoautosomal regions (PAR1, PAR2) of [Self::X] (0-based, half-open).
+    pub const X_PARS: [Range<u64>; 2] = [10_000..2_781_479, 155_701_382..156_030_895];
+    /// The pseudoautosomal regions (PAR1, PAR2) of [Self::Y] (0-based, half-open).
+    pub const Y_PARS: [Range<u64>; 2] = [10_000..2_781_479, 56_887_902..57_217_415];
+
     pub fn new(v: &str) -> Option<Self> {
         let contig = grch38_meta::META.get_entry(v)?.0;
         Some(Self { contig })
@@ -152,6 +161,7 @@ impl GRCh38Contig {
                 | Self::Y
         )
     }
+    /// The ploidy outside of the pseudoautosomal regions, see [Self::ploidy_at].
     pub fn ploidy(self, sex: Sex) -> u8 {
         match (self, sex) {
             (Self::Y, Sex::Male) => 1,
@@ -172,6 +182,28 @@ impl GRCh38Contig {
         self.ploidy(sex) == 1
     }
 
+    /// The pseudoautosomal regions on this contig, empty unless it is [Self::X] or [Self::Y].
+    pub fn pars(self) -> &'static [Range<u64>] {
+        match self {
+            Self::X => &Self::X_PARS,
+            Self::Y => &Self::Y_PARS,
+            _ => &[],
+        }
+    }
+    pub fn is_par(self, at: u64) -> bool {
+        self.pars().iter().any(|par| par.contains(&at))
+    }
+    /// Like [Self::ploidy], but accounts for the pseudoautosomal regions.
+    ///
+    /// Males carry a copy of the PARs on both X and Y, so they are diploid there.
+    pub fn ploidy_at(self, at: u64, sex: Sex) -> u8 {
+        if self.is_par(at) && sex == Sex::Male {
+            2
+        } else {
+            self.ploidy(sex)
+        }
+    }
+
     fn new_from_bytes(bytes: &[u8]) -> Option<Self> {
         Self::new(bytes.as_ascii()?.as_str())
     }
@@ -185,6 +217,9 @@ impl Contig for GRCh38Contig {
         self.meta().len
     }
 }
+impl BuildContig for GRCh38Contig {
+    type Build = build::GRCh38;
+}
 impl Display for GRCh38Contig {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.contig)
@@ -313,6 +348,11 @@ impl GRCh37Contig {
     pub const Y: Self = Self { contig: "Y" };
     pub const MT: Self = Self { contig: "MT" };
 
+    /// The pseudoautosomal regions (PAR1, PAR2) of [Self::X] (0-based, half-open).
+    pub const X_PARS: [Range<u64>; 2] = [60_000..2_699_520, 154_931_043..155_260_560];
+    /// The pseudoautosomal regions (PAR1, PAR2) of [Self::Y] (0-based, half-open).
+    pub const Y_PARS: [Range<u64>; 2] = [10_000..2_649_520, 59_034_049..59_363_566];
+
     pub fn new(v: &str) -> Option<Self> {
         let contig = grch37_meta::META.get_entry(v)?.0;
         Some(Self { contig })
@@ -379,6 +419,41 @@ impl GRCh37Contig {
         )
     }
 
+    /// The ploidy outside of the pseudoautosomal regions, see [Self::ploidy_at].
+    pub fn ploidy(self, sex: Sex) -> u8 {
+        match (self, sex) {
+            (Self::Y, Sex::Male) => 1,
+            (Self::Y, Sex::Female) => 0,
+
+            (Self::X, Sex::Male) => 1,
+            (Self::X, Sex::Female) => 2,
+
+            (Self::MT, _) => 1,
+
+            _ => 2,
+        }
+    }
+
+    /// The pseudoautosomal regions on this contig, empty unless it is [Self::X] or [Self::Y].
+    pub fn pars(self) -> &'static [Range<u64>] {
+        match self {
+            Self::X => &Self::X_PARS,
+            Self::Y => &Self::Y_PARS,
+            _ => &[],
+        }
+    }
+    pub fn is_par(self, at: u64) -> bool {
+        self.pars().iter().any(|par| par.contains(&at))
+    }
+    /// Like [Self::ploidy], but accounts for the pseudoautosomal regions.
+    pub fn ploidy_at(self, at: u64, sex: Sex) -> u8 {
+        if self.is_par(at) && sex == Sex::Male {
+            2
+        } else {
+            self.ploidy(sex)
+        }
+    }
+
     fn new_from_bytes(bytes: &[u8]) -> Option<Self> {
         Self::new(bytes.as_ascii()?.as_str())
     }
@@ -392,6 +467,9 @@ impl Contig for GRCh37Contig {
         self.meta().len
     }
 }
+impl BuildContig for GRCh37Contig {
+    type Build = build::GRCh37;
+}
 impl Display for GRCh37Contig {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.contig)
@@ -494,6 +572,27 @@ mod tests {
         GRCh38Contig::new(GRCh38Contig::MT.contig).unwrap();
     }
 
+    #[test]
+    fn test_grch38_pars() {
+        for par in &GRCh38Contig::X_PARS {
+            assert!(par.end <= GRCh38Contig::X.size());
+        }
+        for par in &GRCh38Contig::Y_PARS {
+            assert!(par.end <= GRCh38Contig::Y.size());
+        }
+
+        assert!(GRCh38Contig::X.is_par(10_000));
+        assert!(!GRCh38Contig::X.is_par(9_999));
+        assert!(!GRCh38Contig::X.is_par(2_781_479));
+        assert!(!GRCh38Contig::CHR1.is_par(10_000));
+
+        assert_eq!(GRCh38Contig::X.ploidy_at(1_000_000, Sex::Male), 2);
+        assert_eq!(GRCh38Contig::X.ploidy_at(50_000_000, Sex::Male), 1);
+        assert_eq!(GRCh38Contig::X.ploidy_at(1_000_000, Sex::Female), 2);
+        assert_eq!(GRCh38Contig::Y.ploidy_at(1_000_000, Sex::Male), 2);
+        assert_eq!(GRCh38Contig::Y.ploidy_at(1_000_000, Sex::Female), 0);
+    }
+
     #[test]
     fn test_grch37_contig_chromosomes_sorted() {
         assert!(GRCh37Contig::CHROMOSOMES.iter().is_sorted());
@@ -506,4 +605,19 @@ mod tests {
         }
         GRCh37Contig::new(GRCh37Contig::MT.contig).unwrap();
     }
+
+    #[test]
+    fn test_grch37_pars() {
+        for par in &GRCh37Contig::X_PARS {
+            assert!(par.end <= GRCh37Contig::X.size());
+        }
+        for par in &GRCh37Contig::Y_PARS {
+            assert!(par.end <= GRCh37Contig::Y.size());
+        }
+
+        assert_eq!(GRCh37Contig::X.ploidy_at(1_000_000, Sex::Male), 2);
+        assert_eq!(GRCh37Contig::X.ploidy_at(50_000_000, Sex::Male), 1);
+        assert_eq!(GRCh37Contig::Y.ploidy_at(50_000_000, Sex::Female), 0);
+        assert_eq!(GRCh37Contig::MT.ploidy_at(1_000, Sex::Female), 1);
+    }
 }
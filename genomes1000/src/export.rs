@@ -0,0 +1,269 @@
+//! Arrow and Parquet export of simplified records and dosage matrices.
+//!
+//! Both are written one row per variant:
+//!
+//! | column          | type                   | notes                                   |
+//! |-----------------|------------------------|-----------------------------------------|
+//! | `contig`        | `Utf8`                 | e.g. `chr1`                             |
+//! | `position`      | `UInt64`               | 1-based, as in the VCF                  |
+//! | `reference`     | `Utf8`                 |                                         |
+//! | `alternate`     | `Utf8`                 |                                         |
+//! | `quality`       | `Float64`, nullable    | simplified records only                 |
+//! | `filter`        | `Utf8`                 | simplified records only                 |
+//! | one per sample  | `UInt8`/`Float32`, nullable | copies of the alternate allele, null if missing |
+//!
+//! Sample columns are named after the samples. Simplified records always use `UInt8`,
+//! dosage matrices use their own value type.
+
+use std::{io, sync::Arc};
+
+use arrow::{
+    array::{ArrayRef, Float32Array, Float64Array, StringArray, UInt8Array, UInt64Array},
+    datatypes::{DataType, Field, Schema, SchemaRef},
+    error::ArrowError,
+    record_batch::RecordBatch,
+};
+use parquet::{arrow::ArrowWriter, errors::ParquetError};
+
+use crate::{
+    Genotype,
+    matrix::{Dosage, DosageMatrix},
+    simplified::SimplifiedRecord,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ExportError {
+    #[error(transparent)]
+    Arrow(#[from] ArrowError),
+    #[error(transparent)]
+    Parquet(#[from] ParquetError),
+    #[error("The schema has {expected} sample columns, but a record has {found} samples")]
+    SampleCount { expected: usize, found: usize },
+    #[error("Not a simplified record schema, it has {0} columns")]
+    Schema(usize),
+}
+impl From<ExportError> for io::Error {
+    fn from(e: ExportError) -> Self {
+        match e {
+            ExportError::Arrow(ArrowError::IoError(_, e)) => e,
+            e => io::Error::other(e),
+        }
+    }
+}
+
+/// A [Dosage] that can be stored in an Arrow column.
+pub trait ArrowDosage: Dosage {
+    const DATA_TYPE: DataType;
+    /// [Dosage::MISSING] values become nulls.
+    fn array(values: impl Iterator<Item = Self>) -> ArrayRef;
+}
+impl ArrowDosage for u8 {
+    const DATA_TYPE: DataType = DataType::UInt8;
+    fn array(values: impl Iterator<Item = Self>) -> ArrayRef {
+        Arc::new(UInt8Array::from_iter(
+            values.map(|v| (v != Self::MISSING).then_some(v)),
+        ))
+    }
+}
+impl ArrowDosage for f32 {
+    const DATA_TYPE: DataType = DataType::Float32;
+    fn array(values: impl Iterator<Item = Self>) -> ArrayRef {
+        Arc::new(Float32Array::from_iter(
+            values.map(|v| (!v.is_nan()).then_some(v)),
+        ))
+    }
+}
+
+pub fn simplified_schema(sample_names: &[String]) -> SchemaRef {
+    let mut fields = variant_fields();
+    fields.push(Field::new("quality", DataType::Float64, true));
+    fields.push(Field::new("filter", DataType::Utf8, false));
+    fields.extend(sample_fields(sample_names, DataType::UInt8));
+    Arc::new(Schema::new(fields))
+}
+pub fn simplified_batch(
+    schema: SchemaRef,
+    records: &[SimplifiedRecord],
+) -> Result<RecordBatch, ExportError> {
+    let sample_count = schema
+        .fields()
+        .len()
+        .checked_sub(6)
+        .ok_or(ExportError::Schema(schema.fields().len()))?;
+    if let Some(record) = records.iter().find(|r| r.samples.len() != sample_count) {
+        return Err(ExportError::SampleCount {
+            expected: sample_count,
+            found: record.samples.len(),
+        });
+    }
+
+    let mut columns: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from_iter_values(
+            records.iter().map(|r| r.contig.as_ref()),
+        )),
+        Arc::new(UInt64Array::from_iter_values(
+            records.iter().map(|r| r.position),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            records.iter().map(|r| r.reference_allele.to_string()),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            records.iter().map(|r| r.alternate_allele.to_string()),
+        )),
+        Arc::new(Float64Array::from_iter(records.iter().map(|r| r.quality))),
+        Arc::new(StringArray::from_iter_values(
+            records.iter().map(|r| &*r.filter),
+        )),
+    ];
+    for sample in 0..sample_count {
+        columns.push(u8::array(records.iter().map(|r| match r.samples[sample] {
+            Genotype::Missing => u8::MISSING,
+            genotype => genotype.dosage(1),
+        })));
+    }
+
+    Ok(RecordBatch::try_new(schema, columns)?)
+}
+/// Writes records to Parquet, `batch_size` rows at a time.
+pub fn write_simplified_parquet(
+    writer: impl io::Write + Send,
+    sample_names: &[String],
+    records: impl IntoIterator<Item = SimplifiedRecord>,
+    batch_size: usize,
+) -> Result<(), ExportError> {
+    let schema = simplified_schema(sample_names);
+    let mut writer = ArrowWriter::try_new(writer, schema.clone(), None)?;
+
+    let mut batch = Vec::with_capacity(batch_size);
+    for record in records {
+        batch.push(record);
+        if batch.len() >= batch_size {
+            writer.write(&simplified_batch(schema.clone(), &batch)?)?;
+            batch.clear();
+        }
+    }
+    if !batch.is_empty() {
+        writer.write(&simplified_batch(schema, &batch)?)?;
+    }
+    writer.close()?;
+    Ok(())
+}
+
+pub fn dosage_schema<T: ArrowDosage>(sample_names: &[String]) -> SchemaRef {
+    let mut fields = variant_fields();
+    fields.extend(sample_fields(sample_names, T::DATA_TYPE));
+    Arc::new(Schema::new(fields))
+}
+/// `sample_names` are all the samples, the matrix columns are looked up by index.
+pub fn dosage_batch<T: ArrowDosage>(
+    matrix: &DosageMatrix<T>,
+    sample_names: &[String],
+) -> Result<RecordBatch, ExportError> {
+    let names: Vec<_> = matrix
+        .samples
+        .iter()
+        .map(|&i| sample_names[i].clone())
+        .collect();
+    let variants = &matrix.variants;
+
+    let mut columns: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from_iter_values(
+            variants.iter().map(|v| v.at.contig.as_ref()),
+        )),
+        Arc::new(UInt64Array::from_iter_values(
+            variants.iter().map(|v| v.at.at + 1),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            variants.iter().map(|v| v.reference.to_string()),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            variants.iter().map(|v| v.alternate.to_string()),
+        )),
+    ];
+    for sample in 0..names.len() {
+        columns.push(T::array(
+            (0..variants.len()).map(|variant| matrix.get(variant, sample)),
+        ));
+    }
+
+    Ok(RecordBatch::try_new(dosage_schema::<T>(&names), columns)?)
+}
+pub fn write_dosage_parquet<T: ArrowDosage>(
+    writer: impl io::Write + Send,
+    matrix: &DosageMatrix<T>,
+    sample_names: &[String],
+) -> Result<(), ExportError> {
+    let batch = dosage_batch(matrix, sample_names)?;
+    let mut writer = ArrowWriter::try_new(writer, batch.schema(), None)?;
+    writer.write(&batch)?;
+    writer.close()?;
+    Ok(())
+}
+
+fn variant_fields() -> Vec<Field> {
+    vec![
+        Field::new("contig", DataType::Utf8, false),
+        Field::new("position", DataType::UInt64, false),
+        Field::new("reference", DataType::Utf8, false),
+        Field::new("alternate", DataType::Utf8, false),
+    ]
+}
+fn sample_fields(sample_names: &[String], data_type: DataType) -> impl Iterator<Item = Field> {
+    sample_names
+        .iter()
+        .map(move |name| Field::new(name, data_type.clone(), true))
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::array::{Array, AsArray, UInt8Array};
+    use biocore::dna::DnaSequence;
+
+    use crate::{
+        DiploidGenotype, GRCh38Contig, Genotype, GenotypePhasing, simplified::SimplifiedRecord,
+    };
+
+    use super::{ExportError, simplified_batch, simplified_schema, write_simplified_parquet};
+
+    #[test]
+    fn simplified() {
+        let names = ["A".to_owned(), "B".to_owned()];
+        let record = SimplifiedRecord {
+            contig: GRCh38Contig::CHR1,
+            position: 100,
+            reference_allele: "A".parse::<DnaSequence>().unwrap(),
+            alternate_allele: "G".parse::<DnaSequence>().unwrap(),
+            quality: None,
+            filter: "PASS".to_owned(),
+            samples: vec![
+                Genotype::Diploid(DiploidGenotype {
+                    left: 1,
+                    phasing: GenotypePhasing::Phased,
+                    right: 1,
+                }),
+                Genotype::Missing,
+            ],
+        };
+
+        let batch = simplified_batch(simplified_schema(&names), &[record.clone()]).unwrap();
+        assert_eq!(batch.num_columns(), 8);
+        assert_eq!(batch.column(0).as_string::<i32>().value(0), "chr1");
+        assert!(batch.column(4).is_null(0));
+        let a = batch.column_by_name("A").unwrap();
+        assert_eq!(a.as_any().downcast_ref::<UInt8Array>().unwrap().value(0), 2);
+        assert!(batch.column_by_name("B").unwrap().is_null(0));
+
+        let one_sample = simplified_schema(&names[..1]);
+        assert!(matches!(
+            simplified_batch(one_sample, &[record.clone()]),
+            Err(ExportError::SampleCount {
+                expected: 1,
+                found: 2
+            })
+        ));
+
+        let mut parquet = vec![];
+        write_simplified_parquet(&mut parquet, &names, [record.clone(), record], 1).unwrap();
+        assert!(parquet.starts_with(b"PAR1"));
+    }
+}
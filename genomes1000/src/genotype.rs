@@ -3,6 +3,7 @@ use std::str::FromStr;
 
 use biocore::{
     dna::{DnaBase, DnaSequence},
+    mutation::structural::{StructuralVariantKind, SymbolicAllele},
     sequence::{AsciiChar, SequenceSlice},
 };
 use utile::io::FromUtf8Bytes;
@@ -41,6 +42,22 @@ impl fmt::Display for AltGenotype {
         f.write_str(v)
     }
 }
+impl AltGenotype {
+    /// The symbolic allele, if this is not a plain sequence.
+    pub fn structural(&self) -> Option<SymbolicAllele> {
+        let kind = match self {
+            Self::Sequence(_) => return None,
+            Self::DEL => StructuralVariantKind::Deletion,
+            Self::DUP => StructuralVariantKind::Duplication,
+            Self::INS => StructuralVariantKind::Insertion,
+            Self::INV => StructuralVariantKind::Inversion,
+            Self::CN(n) => StructuralVariantKind::CopyNumber(Some(u32::from(*n))),
+            Self::Other(v) => return v.parse().ok(),
+            Self::Unknown(v) => return v.parse().ok(),
+        };
+        Some(SymbolicAllele::new(kind))
+    }
+}
 impl FromStr for AltGenotype {
     type Err = std::io::Error;
 
@@ -0,0 +1,227 @@
+//! The haplotype sequences of a single sample, from its phased genotypes applied onto the reference.
+
+use std::io;
+
+use biocore::{
+    dna::{DnaBase, DnaSequence},
+    location::ContigRange,
+    mutation::normalize::{ReferenceSequence, Variant},
+};
+use utile::num::TryU64;
+
+use crate::{
+    DiploidGenotype, GRCh38Contig, Genomes1000Fs, Genotype, GenotypePhasing, HaploidGenotype,
+    Record,
+};
+
+/// See [Genomes1000Fs::haplotypes].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SampleHaplotypes {
+    /// One sequence per copy of the range (by the sample's ploidy), in genotype order.
+    pub haplotypes: Vec<DnaSequence>,
+    /// Alternate alleles carried by the sample that were left out: unphased heterozygous calls,
+    /// calls that don't match the expected ploidy, and variants crossing the range edges,
+    /// overlapping one already applied, or disagreeing with the reference.
+    ///
+    /// Alleles that can't be resolved to a sequence (e.g. `<INS>`) are dropped without a trace.
+    pub skipped: Vec<Variant<GRCh38Contig>>,
+}
+
+impl Genomes1000Fs {
+    /// Reconstructs the sequence of each haplotype of `sample` (an index into [Self::sample_names])
+    /// over `range`. Missing genotypes are read as reference.
+    ///
+    /// The ploidy comes from the sample's sex at the start of the range, see
+    /// [GRCh38Contig::ploidy_at] (diploid if the pedigree is unknown).
+    pub fn haplotypes(
+        &mut self,
+        sample: usize,
+        range: &ContigRange<GRCh38Contig>,
+        genome: &mut impl ReferenceSequence<GRCh38Contig>,
+    ) -> io::Result<SampleHaplotypes> {
+        let ploidy = match self.pedigree(&self.sample_names[sample]) {
+            Some(pedigree) => range.contig.ploidy_at(range.at.start, pedigree.sex),
+            None => 2,
+        };
+        let reference = genome.fetch(range)?;
+
+        let mut variants = vec![vec![]; ploidy.into()];
+        let mut skipped = vec![];
+        for record in self.query(range)? {
+            let record = record?;
+            if !range.at.contains(&record.at().at) {
+                continue;
+            }
+            let Some(alleles) = sample_alleles(record.samples[sample], ploidy) else {
+                skipped.extend(carried(&record, record.samples[sample]));
+                continue;
+            };
+            for (haplotype, allele) in variants.iter_mut().zip(alleles) {
+                if allele == 0 {
+                    continue;
+                }
+                if let Some(variant) = allele_variant(&record, allele) {
+                    haplotype.push(variant);
+                }
+            }
+        }
+
+        let haplotypes = variants
+            .into_iter()
+            .map(|variants| apply(&reference, range, variants, &mut skipped))
+            .collect();
+        skipped.sort();
+        skipped.dedup();
+
+        Ok(SampleHaplotypes {
+            haplotypes,
+            skipped,
+        })
+    }
+}
+
+/// The allele on each of the `ploidy` haplotypes, [None] if that can't be told from the call.
+fn sample_alleles(genotype: Genotype, ploidy: u8) -> Option<Vec<u8>> {
+    Some(match (ploidy, genotype) {
+        (_, Genotype::Missing) => vec![0; ploidy.into()],
+        (1, Genotype::Haploid(HaploidGenotype { value })) => vec![value],
+        (
+            2,
+            Genotype::Diploid(DiploidGenotype {
+                left,
+                phasing: GenotypePhasing::Phased,
+                right,
+            }),
+        ) => vec![left, right],
+        // Homozygous calls don't need phasing.
+        (_, Genotype::Diploid(DiploidGenotype { left, right, .. })) if left == right => {
+            vec![left; ploidy.into()]
+        }
+        _ => return None,
+    })
+}
+/// The alternate alleles of a record carried by a genotype, that can be resolved to a sequence.
+fn carried(record: &Record<Genotype>, genotype: Genotype) -> Vec<Variant<GRCh38Contig>> {
+    (1..=record.alternate_alleles.len())
+        .map(|i| u8::try_from(i).unwrap())
+        .filter(|&i| genotype.dosage(i) > 0)
+        .filter_map(|i| allele_variant(record, i))
+        .collect()
+}
+fn allele_variant(record: &Record<Genotype>, allele: u8) -> Option<Variant<GRCh38Contig>> {
+    let reference: DnaSequence = record.reference_allele.iter().copied().try_collect()?;
+    let alternate = record
+        .alternate_alleles
+        .get(usize::from(allele) - 1)?
+        .unpack(&reference)?;
+    Some(Variant {
+        at: record.at(),
+        reference,
+        alternate,
+    })
+}
+
+/// Applies sorted variants onto the reference bases of `range`, skipping those that don't fit.
+fn apply(
+    reference: &DnaSequence,
+    range: &ContigRange<GRCh38Contig>,
+    variants: Vec<Variant<GRCh38Contig>>,
+    skipped: &mut Vec<Variant<GRCh38Contig>>,
+) -> DnaSequence {
+    let mut haplotype: Vec<DnaBase> = Vec::with_capacity(reference.len());
+    // Offset into `reference` of the first base not yet copied.
+    let mut cursor = 0;
+    for variant in variants {
+        let start = (variant.at.at - range.at.start) as usize;
+        let end = start + variant.reference.len();
+        let fits = start >= cursor
+            && end.u64_unwrap() <= range.at.end - range.at.start
+            && reference[start..end].iter().eq(variant.reference.iter());
+        if !fits {
+            skipped.push(variant);
+            continue;
+        }
+        haplotype.extend(reference[cursor..start].iter().copied());
+        haplotype.extend(variant.alternate.iter().copied());
+        cursor = end;
+    }
+    haplotype.extend(reference[cursor..].iter().copied());
+    DnaSequence::new(haplotype)
+}
+
+#[cfg(test)]
+mod tests {
+    use biocore::{
+        dna::DnaSequence,
+        location::{ContigPosition, ContigRange},
+        mutation::normalize::Variant,
+    };
+
+    use crate::{DiploidGenotype, GRCh38Contig, Genotype, GenotypePhasing, HaploidGenotype};
+
+    use super::{apply, sample_alleles};
+
+    #[test]
+    fn alleles() {
+        let diploid = |left, phasing, right| {
+            Genotype::Diploid(DiploidGenotype {
+                left,
+                phasing,
+                right,
+            })
+        };
+        let haploid = Genotype::Haploid(HaploidGenotype { value: 1 });
+        assert_eq!(
+            sample_alleles(diploid(0, GenotypePhasing::Phased, 1), 2),
+            Some(vec![0, 1])
+        );
+        assert_eq!(
+            sample_alleles(diploid(0, GenotypePhasing::Unphased, 1), 2),
+            None
+        );
+        assert_eq!(
+            sample_alleles(diploid(1, GenotypePhasing::Unphased, 1), 1),
+            Some(vec![1])
+        );
+        assert_eq!(sample_alleles(haploid, 1), Some(vec![1]));
+        assert_eq!(sample_alleles(haploid, 2), None);
+        assert_eq!(sample_alleles(Genotype::Missing, 2), Some(vec![0, 0]));
+    }
+
+    #[test]
+    fn application() {
+        let sequence = |s: &str| s.parse::<DnaSequence>().unwrap();
+        let range = ContigRange {
+            contig: GRCh38Contig::CHR1,
+            at: 100..110,
+        };
+        let variant = |at, reference, alternate| Variant {
+            at: ContigPosition {
+                contig: GRCh38Contig::CHR1,
+                at,
+            },
+            reference: sequence(reference),
+            alternate: sequence(alternate),
+        };
+        let reference = sequence("ACGTACGTAC");
+
+        let mut skipped = vec![];
+        let haplotype = apply(
+            &reference,
+            &range,
+            vec![
+                variant(101, "CG", "C"),
+                // Overlaps the deletion.
+                variant(102, "G", "T"),
+                variant(104, "A", "AGG"),
+                // Disagrees with the reference.
+                variant(105, "G", "T"),
+                // Crosses the end of the range.
+                variant(109, "CA", "C"),
+            ],
+            &mut skipped,
+        );
+        assert_eq!(haplotype, sequence("ACTAGGCGTAC"));
+        assert_eq!(skipped.len(), 3);
+    }
+}
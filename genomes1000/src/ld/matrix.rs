@@ -0,0 +1,531 @@
+//! A block-sparse, banded on-disk format for LD matrices.
+//!
+//! Variants are split into consecutive blocks of `block_size` variants, and only blocks
+//! within `bandwidth` blocks of the diagonal are stored (upper triangle only, the matrix
+//! is symmetric). Blocks whose values are all below a threshold can be omitted entirely
+//! and read back as zeroes.
+//!
+//! Layout (all integers and floats are little-endian):
+//! ```text
+//! header:  magic (8) | version u32 | block_size u32 | bandwidth u32
+//!          | contig length u32 | contig (utf8) | variant count u64 | positions (u64 each)
+//! blocks:  row-major f32 values, one block after the other
+//! index:   (row block u32 | column block u32 | offset u64) per block, sorted
+//! trailer: index offset u64 | block count u64 | magic (8)
+//! ```
+//!
+//! Nothing needs to be parsed past the header and index to answer a query, so the reader
+//! works over any `AsRef<[u8]>` and is happy with a memory-mapped file.
+
+use std::{
+    io::{self, BufWriter, Write},
+    ops::Range,
+};
+
+use resource::{
+    RawResource, RawResourceExt,
+    fs::{FsCache, FsCacheEntry},
+};
+use utile::io::invalid_data;
+
+use biocore::{genome::Contig, location::ContigRange};
+
+use crate::GRCh38Contig;
+
+const MAGIC: &[u8; 8] = b"LDMATRIX";
+const VERSION: u32 = 1;
+const TRAILER_LEN: usize = 8 + 8 + MAGIC.len();
+const INDEX_ENTRY_LEN: usize = 4 + 4 + 8;
+
+/// Describes which variants a matrix covers and how it is split into blocks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LdMatrixLayout {
+    pub contig: GRCh38Contig,
+    /// 0-based, sorted.
+    pub positions: Vec<u64>,
+    /// Number of variants per block (along each side).
+    pub block_size: u32,
+    /// How many blocks away from the diagonal are stored.
+    pub bandwidth: u32,
+}
+
+/// A read-only view of an LD matrix in the on-disk format.
+///
+/// Values are signed correlations (r), square them for r².
+#[derive(Debug, Clone)]
+pub struct LdMatrix<D> {
+    data: D,
+    layout: LdMatrixLayout,
+    /// Sorted by `(row, column)`.
+    index: Vec<BlockIndexEntry>,
+}
+
+/// Streams an LD matrix to disk one block at a time.
+///
+/// Blocks must be written in `(row, column)` order.
+#[derive(Debug)]
+pub struct LdMatrixWriter<W> {
+    inner: W,
+    layout: LdMatrixLayout,
+    offset: u64,
+    index: Vec<BlockIndexEntry>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct BlockIndexEntry {
+    row: u32,
+    column: u32,
+    offset: u64,
+}
+
+impl LdMatrixLayout {
+    pub fn variant_count(&self) -> usize {
+        self.positions.len()
+    }
+    pub fn block_count(&self) -> u32 {
+        self.variant_count()
+            .div_ceil(self.block_size as usize)
+            .try_into()
+            .unwrap()
+    }
+    /// The variant indices covered by a block along one side.
+    pub fn block_variants(&self, block: u32) -> Range<usize> {
+        let block_size = self.block_size as usize;
+        let start = block as usize * block_size;
+        start..usize::min(start + block_size, self.variant_count())
+    }
+    /// Whether the block is within the stored band.
+    pub fn is_in_band(&self, row: u32, column: u32) -> bool {
+        let (row, column) = (u32::min(row, column), u32::max(row, column));
+        column - row <= self.bandwidth
+    }
+    /// All the blocks within the band, in the order they are expected by [LdMatrixWriter].
+    pub fn blocks(&self) -> impl Iterator<Item = (u32, u32)> + use<> {
+        let block_count = self.block_count();
+        let bandwidth = self.bandwidth;
+        (0..block_count).flat_map(move |row| {
+            let end = u32::min(row.saturating_add(bandwidth).saturating_add(1), block_count);
+            (row..end).map(move |column| (row, column))
+        })
+    }
+    /// The range of variant indices whose positions fall within `range`.
+    pub fn variants_in(&self, range: &ContigRange<GRCh38Contig>) -> Range<usize> {
+        if range.contig != self.contig {
+            return 0..0;
+        }
+        let start = self.positions.partition_point(|&p| p < range.at.start);
+        let end = self.positions.partition_point(|&p| p < range.at.end);
+        start..end
+    }
+
+    fn block_len(&self, row: u32, column: u32) -> usize {
+        self.block_variants(row).len() * self.block_variants(column).len()
+    }
+    fn check(&self) -> io::Result<()> {
+        if self.block_size == 0 {
+            return Err(invalid_data("LD matrix block size must be positive"));
+        }
+        if !self.positions.is_sorted() {
+            return Err(invalid_data("LD matrix positions must be sorted"));
+        }
+        if let Some(&last) = self.positions.last()
+            && last >= self.contig.size()
+        {
+            return Err(invalid_data(format!(
+                "LD matrix position {last} is out of bounds for {}",
+                self.contig
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl<D: AsRef<[u8]>> LdMatrix<D> {
+    pub fn new(data: D) -> io::Result<Self> {
+        let bytes = data.as_ref();
+
+        let layout = read_header(bytes)?;
+
+        let (index_offset, block_count) = {
+            let Some(trailer) = bytes.len().checked_sub(TRAILER_LEN).map(|at| &bytes[at..]) else {
+                return Err(invalid_data("LD matrix is truncated"));
+            };
+            if &trailer[16..] != MAGIC {
+                return Err(invalid_data("Invalid LD matrix trailer"));
+            }
+            (read_u64(trailer, 0)?, read_u64(trailer, 8)?)
+        };
+
+        let index_len = usize::try_from(block_count)
+            .ok()
+            .and_then(|c| c.checked_mul(INDEX_ENTRY_LEN))
+            .ok_or_else(|| invalid_data("LD matrix index is too large"))?;
+        let index_bytes = usize::try_from(index_offset)
+            .ok()
+            .and_then(|start| bytes.get(start..start.checked_add(index_len)?))
+            .ok_or_else(|| invalid_data("LD matrix index is out of bounds"))?;
+
+        let index: Vec<_> = index_bytes
+            .chunks_exact(INDEX_ENTRY_LEN)
+            .map(|entry| {
+                io::Result::Ok(BlockIndexEntry {
+                    row: read_u32(entry, 0)?,
+                    column: read_u32(entry, 4)?,
+                    offset: read_u64(entry, 8)?,
+                })
+            })
+            .try_collect::<Vec<_>>()?;
+
+        if !index.is_sorted() {
+            return Err(invalid_data("LD matrix index is not sorted"));
+        }
+        for entry in &index {
+            if entry.row > entry.column
+                || entry.column >= layout.block_count()
+                || !layout.is_in_band(entry.row, entry.column)
+            {
+                return Err(invalid_data(format!(
+                    "LD matrix block ({}, {}) is outside the band",
+                    entry.row, entry.column
+                )));
+            }
+            let len = layout.block_len(entry.row, entry.column) * 4;
+            let end = entry.offset.checked_add(len as u64);
+            if end.is_none_or(|end| end > index_offset) {
+                return Err(invalid_data("LD matrix block is out of bounds"));
+            }
+        }
+
+        Ok(Self {
+            data,
+            layout,
+            index,
+        })
+    }
+
+    pub fn layout(&self) -> &LdMatrixLayout {
+        &self.layout
+    }
+    pub fn contig(&self) -> GRCh38Contig {
+        self.layout.contig
+    }
+    pub fn positions(&self) -> &[u64] {
+        &self.layout.positions
+    }
+    pub fn variant_count(&self) -> usize {
+        self.layout.variant_count()
+    }
+
+    /// The correlation between variants `i` and `j`.
+    ///
+    /// Returns [None] if the pair falls outside of the stored band.
+    /// Pairs in omitted blocks are reported as `0.0`.
+    pub fn get(&self, i: usize, j: usize) -> Option<f32> {
+        let (i, j) = (usize::min(i, j), usize::max(i, j));
+        assert!(j < self.variant_count());
+
+        let block_size = self.layout.block_size as usize;
+        let (row, column) = ((i / block_size) as u32, (j / block_size) as u32);
+
+        if !self.layout.is_in_band(row, column) {
+            return None;
+        }
+
+        let Some(block) = self.block(row, column) else {
+            return Some(0.0);
+        };
+
+        let columns = self.layout.block_variants(column).len();
+        Some(block[(i % block_size) * columns + (j % block_size)])
+    }
+
+    /// The raw values of a stored block, row-major.
+    ///
+    /// Returns [None] if the block was omitted or is outside the band.
+    pub fn block(&self, row: u32, column: u32) -> Option<Vec<f32>> {
+        let entry = self
+            .index
+            .binary_search_by(|e| Ord::cmp(&(e.row, e.column), &(row, column)))
+            .ok()
+            .map(|i| self.index[i])?;
+
+        let start = entry.offset as usize;
+        let len = self.layout.block_len(row, column);
+        let bytes = &self.data.as_ref()[start..start + len * 4];
+
+        Some(
+            bytes
+                .chunks_exact(4)
+                .map(|v| f32::from_le_bytes(v.try_into().unwrap()))
+                .collect(),
+        )
+    }
+
+    /// A dense, square sub-matrix for the given variant indices, row-major.
+    ///
+    /// Pairs outside of the stored band are set to [f32::NAN].
+    pub fn dense(&self, variants: Range<usize>) -> Vec<f32> {
+        let n = variants.len();
+        let mut out = vec![f32::NAN; n * n];
+        for (a, i) in variants.clone().enumerate() {
+            for (b, j) in variants.clone().enumerate().skip(a) {
+                if let Some(v) = self.get(i, j) {
+                    out[a * n + b] = v;
+                    out[b * n + a] = v;
+                }
+            }
+        }
+        out
+    }
+    /// A dense sub-matrix for the variants within `range`, see [Self::dense].
+    pub fn dense_in(&self, range: &ContigRange<GRCh38Contig>) -> (Range<usize>, Vec<f32>) {
+        let variants = self.layout.variants_in(range);
+        let dense = self.dense(variants.clone());
+        (variants, dense)
+    }
+}
+
+impl LdMatrix<Vec<u8>> {
+    pub fn load(resource: impl RawResource) -> io::Result<Self> {
+        Self::new(resource.read_vec()?)
+    }
+
+    /// Loads the matrix from the cache, computing and storing it first if needed.
+    ///
+    /// `compute` is handed a writer for the given layout and should fill in the blocks.
+    pub fn load_or_compute(
+        entry: &FsCacheEntry,
+        layout: LdMatrixLayout,
+        compute: impl FnOnce(&mut LdMatrixWriter<BufWriter<&mut dyn Write>>) -> io::Result<()>,
+    ) -> io::Result<Self> {
+        if !entry.try_exists()? {
+            log::info!("[1000 Genomes][LD] Computing LD matrix at {entry}");
+            entry.write_file_with(|file| {
+                let mut writer =
+                    LdMatrixWriter::new(BufWriter::new(file as &mut dyn Write), layout)?;
+                compute(&mut writer)?;
+                writer.finish()?.flush()
+            })?;
+        }
+
+        Self::load(entry.clone())
+    }
+    /// The conventional cache location for the LD matrix of a contig.
+    pub fn cache_entry(cache: &FsCache, name: &str, contig: GRCh38Contig) -> FsCacheEntry {
+        cache.entry(format!("genomes1000/ld/{name}/{contig}.ldm"))
+    }
+}
+
+impl<W: Write> LdMatrixWriter<W> {
+    pub fn new(mut inner: W, layout: LdMatrixLayout) -> io::Result<Self> {
+        layout.check()?;
+        let offset = write_header(&mut inner, &layout)?;
+        Ok(Self {
+            inner,
+            layout,
+            offset,
+            index: vec![],
+        })
+    }
+
+    pub fn layout(&self) -> &LdMatrixLayout {
+        &self.layout
+    }
+
+    /// Writes a block of row-major values.
+    pub fn write_block(&mut self, row: u32, column: u32, values: &[f32]) -> io::Result<()> {
+        assert!(row <= column, "only the upper triangle is stored");
+        assert!(column < self.layout.block_count());
+        assert!(self.layout.is_in_band(row, column));
+        assert_eq!(self.layout.block_len(row, column), values.len());
+        if let Some(last) = self.index.last() {
+            assert!(
+                (last.row, last.column) < (row, column),
+                "blocks out of order"
+            );
+        }
+
+        self.index.push(BlockIndexEntry {
+            row,
+            column,
+            offset: self.offset,
+        });
+        for v in values {
+            self.inner.write_all(&v.to_le_bytes())?;
+        }
+        self.offset += values.len() as u64 * 4;
+
+        Ok(())
+    }
+
+    /// Computes and writes every block in the band from a pairwise function.
+    ///
+    /// Blocks where no value reaches `threshold` in absolute value are omitted.
+    pub fn write_all_blocks(
+        &mut self,
+        threshold: f32,
+        mut f: impl FnMut(usize, usize) -> io::Result<f32>,
+    ) -> io::Result<()> {
+        let mut values = vec![];
+        for (row, column) in self.layout.blocks() {
+            values.clear();
+            for i in self.layout.block_variants(row) {
+                for j in self.layout.block_variants(column) {
+                    values.push(if i == j { 1.0 } else { f(i, j)? });
+                }
+            }
+            if values.iter().any(|v| v.abs() >= threshold) {
+                self.write_block(row, column, &values)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the index and trailer, returning the inner writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        let index_offset = self.offset;
+        for entry in &self.index {
+            self.inner.write_all(&entry.row.to_le_bytes())?;
+            self.inner.write_all(&entry.column.to_le_bytes())?;
+            self.inner.write_all(&entry.offset.to_le_bytes())?;
+        }
+        self.inner.write_all(&index_offset.to_le_bytes())?;
+        self.inner
+            .write_all(&(self.index.len() as u64).to_le_bytes())?;
+        self.inner.write_all(MAGIC)?;
+        Ok(self.inner)
+    }
+}
+
+fn write_header(w: &mut impl Write, layout: &LdMatrixLayout) -> io::Result<u64> {
+    let contig = layout.contig.as_ref().as_bytes();
+
+    w.write_all(MAGIC)?;
+    w.write_all(&VERSION.to_le_bytes())?;
+    w.write_all(&layout.block_size.to_le_bytes())?;
+    w.write_all(&layout.bandwidth.to_le_bytes())?;
+    w.write_all(&u32::try_from(contig.len()).unwrap().to_le_bytes())?;
+    w.write_all(contig)?;
+    w.write_all(&(layout.positions.len() as u64).to_le_bytes())?;
+    for p in &layout.positions {
+        w.write_all(&p.to_le_bytes())?;
+    }
+
+    Ok((MAGIC.len() + 4 * 4 + contig.len() + 8 + layout.positions.len() * 8) as u64)
+}
+fn read_header(bytes: &[u8]) -> io::Result<LdMatrixLayout> {
+    if bytes.get(..MAGIC.len()) != Some(MAGIC) {
+        return Err(invalid_data("Not an LD matrix"));
+    }
+    let mut at = MAGIC.len();
+
+    let version = read_u32(bytes, at)?;
+    if version != VERSION {
+        return Err(invalid_data(format!(
+            "Unsupported LD matrix version: {version}"
+        )));
+    }
+    let block_size = read_u32(bytes, at + 4)?;
+    let bandwidth = read_u32(bytes, at + 8)?;
+    let contig_len = read_u32(bytes, at + 12)? as usize;
+    at += 16;
+
+    let contig = bytes
+        .get(at..at + contig_len)
+        .ok_or_else(|| invalid_data("LD matrix is truncated"))?;
+    let contig = std::str::from_utf8(contig)
+        .ok()
+        .and_then(GRCh38Contig::new)
+        .ok_or_else(|| invalid_data(format!("Unknown contig in LD matrix: {contig:?}")))?;
+    at += contig_len;
+
+    let variant_count = usize::try_from(read_u64(bytes, at)?)
+        .map_err(|_| invalid_data("LD matrix is too large"))?;
+    at += 8;
+
+    let positions = bytes
+        .get(at..at.saturating_add(variant_count.saturating_mul(8)))
+        .ok_or_else(|| invalid_data("LD matrix is truncated"))?
+        .chunks_exact(8)
+        .map(|v| u64::from_le_bytes(v.try_into().unwrap()))
+        .collect();
+
+    let layout = LdMatrixLayout {
+        contig,
+        positions,
+        block_size,
+        bandwidth,
+    };
+    layout.check()?;
+
+    Ok(layout)
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> io::Result<u32> {
+    let v = bytes
+        .get(at..at + 4)
+        .ok_or_else(|| invalid_data("LD matrix is truncated"))?;
+    Ok(u32::from_le_bytes(v.try_into().unwrap()))
+}
+fn read_u64(bytes: &[u8], at: usize) -> io::Result<u64> {
+    let v = bytes
+        .get(at..at + 8)
+        .ok_or_else(|| invalid_data("LD matrix is truncated"))?;
+    Ok(u64::from_le_bytes(v.try_into().unwrap()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LdMatrix, LdMatrixLayout, LdMatrixWriter};
+    use crate::GRCh38Contig;
+
+    fn layout() -> LdMatrixLayout {
+        LdMatrixLayout {
+            contig: GRCh38Contig::CHR22,
+            positions: (0..10).map(|i| 1_000 + i * 10).collect(),
+            block_size: 3,
+            bandwidth: 1,
+        }
+    }
+    fn r(i: usize, j: usize) -> f32 {
+        1.0 / (1.0 + i.abs_diff(j) as f32)
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut writer = LdMatrixWriter::new(vec![], layout()).unwrap();
+        writer.write_all_blocks(0.0, |i, j| Ok(r(i, j))).unwrap();
+        let matrix = LdMatrix::new(writer.finish().unwrap()).unwrap();
+
+        assert_eq!(matrix.layout(), &layout());
+        for i in 0..10 {
+            for j in 0..10 {
+                let in_band = (i / 3).abs_diff(j / 3) <= 1;
+                assert_eq!(matrix.get(i, j), in_band.then(|| r(i, j)), "{i} {j}");
+            }
+        }
+    }
+
+    #[test]
+    fn omitted_blocks() {
+        let mut writer = LdMatrixWriter::new(vec![], layout()).unwrap();
+        writer
+            .write_all_blocks(0.5, |i, j| Ok(r(i, j) / 10.0))
+            .unwrap();
+        let matrix = LdMatrix::new(writer.finish().unwrap()).unwrap();
+
+        assert_eq!(matrix.get(0, 0), Some(1.0));
+        assert_eq!(matrix.get(0, 4), Some(0.0)); // Off-diagonal block was dropped.
+        assert_eq!(matrix.get(0, 9), None);
+    }
+
+    #[test]
+    fn truncated() {
+        let mut writer = LdMatrixWriter::new(vec![], layout()).unwrap();
+        writer.write_all_blocks(0.0, |i, j| Ok(r(i, j))).unwrap();
+        let data = writer.finish().unwrap();
+
+        assert!(LdMatrix::new(&data[..data.len() - 1]).is_err());
+        assert!(LdMatrix::new(&data[..10]).is_err());
+    }
+}
@@ -0,0 +1,9 @@
+//! Linkage disequilibrium.
+
+pub mod matrix;
+pub mod pairwise;
+
+pub use self::{
+    matrix::{LdMatrix, LdMatrixLayout, LdMatrixWriter},
+    pairwise::{LdEstimator, LdStats, ld, ld_window},
+};
@@ -0,0 +1,235 @@
+//! Pairwise LD (r², D′) between biallelic variants, over a subset of samples.
+//!
+//! Allele `1` is the alternate allele (see [crate::Record::split]), anything else counts as
+//! reference. Phased genotypes give the haplotypes directly, unphased ones go through the usual
+//! two-locus EM, where only double heterozygotes are ambiguous.
+
+use std::io;
+
+use biocore::location::ContigRange;
+
+use crate::{GRCh38Contig, Genomes1000Fs, Genotype, simplified::SimplifiedRecord};
+
+const EM_MAX_ITERATIONS: usize = 1000;
+const EM_TOLERANCE: f64 = 1e-10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LdEstimator {
+    /// Counts haplotypes, skipping samples with unphased genotypes at either variant.
+    Phased,
+    /// Estimates haplotype frequencies by expectation-maximisation, ignoring phasing.
+    Em,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LdStats {
+    /// `p(AB) - p(A)p(B)`, for the alternate alleles `A` and `B`.
+    pub d: f64,
+    /// [Self::d] scaled by its maximum given the allele frequencies, in `-1..=1`.
+    pub d_prime: f64,
+    /// The signed correlation between alleles.
+    pub r: f64,
+}
+
+impl LdStats {
+    pub fn r2(&self) -> f64 {
+        self.r * self.r
+    }
+
+    /// From haplotype counts, indexed by `[allele at a][allele at b]` (`0` ref, `1` alt).
+    /// [None] if there are no haplotypes or either variant is monomorphic.
+    pub fn from_haplotype_counts(counts: [[f64; 2]; 2]) -> Option<Self> {
+        let total: f64 = counts.iter().flatten().sum();
+        if total == 0.0 {
+            return None;
+        }
+        let p11 = counts[1][1] / total;
+        let pa = (counts[1][0] + counts[1][1]) / total;
+        let pb = (counts[0][1] + counts[1][1]) / total;
+        let variance = pa * (1.0 - pa) * pb * (1.0 - pb);
+        if variance <= 0.0 {
+            return None;
+        }
+
+        let d = p11 - pa * pb;
+        let d_max = if d >= 0.0 {
+            f64::min(pa * (1.0 - pb), (1.0 - pa) * pb)
+        } else {
+            f64::min(pa * pb, (1.0 - pa) * (1.0 - pb))
+        };
+        Some(Self {
+            d,
+            d_prime: d / d_max,
+            r: d / variance.sqrt(),
+        })
+    }
+}
+
+/// LD between two variants, from the genotypes of `samples` (indices into the genotypes).
+pub fn ld(
+    a: &[Genotype],
+    b: &[Genotype],
+    samples: &[usize],
+    estimator: LdEstimator,
+) -> Option<LdStats> {
+    let pairs = samples.iter().map(|&i| (a[i], b[i]));
+    let counts = match estimator {
+        LdEstimator::Phased => phased_counts(pairs),
+        LdEstimator::Em => em_counts(pairs),
+    };
+    LdStats::from_haplotype_counts(counts)
+}
+
+/// LD between all pairs of variants on the same contig at most `window` bases apart,
+/// as `(i, j, stats)` with `i < j`. Records must be sorted.
+pub fn ld_window(
+    records: &[SimplifiedRecord],
+    window: u64,
+    samples: &[usize],
+    estimator: LdEstimator,
+) -> Vec<(usize, usize, LdStats)> {
+    let mut pairs = vec![];
+    for (i, a) in records.iter().enumerate() {
+        for (j, b) in records.iter().enumerate().skip(i + 1) {
+            if a.contig != b.contig || b.position - a.position > window {
+                break;
+            }
+            if let Some(stats) = ld(&a.samples, &b.samples, samples, estimator) {
+                pairs.push((i, j, stats));
+            }
+        }
+    }
+    pairs
+}
+
+impl Genomes1000Fs {
+    /// The records within `range` and the LD between them (see [ld_window]),
+    /// over [Self::unrelated_samples]. The records only keep the genotypes of those samples.
+    pub fn ld_window(
+        &mut self,
+        range: &ContigRange<GRCh38Contig>,
+        window: u64,
+        estimator: LdEstimator,
+    ) -> io::Result<(Vec<SimplifiedRecord>, Vec<(usize, usize, LdStats)>)> {
+        let samples = self.unrelated_samples();
+        let records: Vec<_> = self
+            .query_simplified_samples(range, &samples)?
+            .filter(|r| range.at.contains(&r.at().at))
+            .collect();
+        let all: Vec<_> = (0..samples.len()).collect();
+        let pairs = ld_window(&records, window, &all, estimator);
+        Ok((records, pairs))
+    }
+}
+
+fn phased_counts(pairs: impl Iterator<Item = (Genotype, Genotype)>) -> [[f64; 2]; 2] {
+    let mut counts = [[0.0; 2]; 2];
+    for (a, b) in pairs {
+        let (Some(a), Some(b)) = (a.haplotypes(), b.haplotypes()) else {
+            continue;
+        };
+        if a.len() != b.len() {
+            continue;
+        }
+        for (a, b) in a.into_iter().zip(b) {
+            counts[usize::from(a == 1)][usize::from(b == 1)] += 1.0;
+        }
+    }
+    counts
+}
+
+fn em_counts(pairs: impl Iterator<Item = (Genotype, Genotype)>) -> [[f64; 2]; 2] {
+    // Haplotypes that are known without phasing, and the number of double heterozygotes.
+    let mut known = [[0.0; 2]; 2];
+    let mut double_hets = 0.0;
+    for (a, b) in pairs {
+        match (a.ploidy(), b.ploidy()) {
+            (Some(1), Some(1)) => {
+                known[usize::from(a.dosage(1))][usize::from(b.dosage(1))] += 1.0;
+            }
+            (Some(2), Some(2)) => match (a.dosage(1), b.dosage(1)) {
+                (1, 1) => double_hets += 1.0,
+                // One side is homozygous, so it pairs with both alleles of the other.
+                (da, db) => {
+                    for h in 0..2 {
+                        let a = if da == 1 { h } else { usize::from(da / 2) };
+                        let b = if db == 1 { h } else { usize::from(db / 2) };
+                        known[a][b] += 1.0;
+                    }
+                }
+            },
+            _ => {}
+        }
+    }
+
+    // Double heterozygotes are either `11/00` (cis) or `10/01` (trans).
+    let mut cis = 0.5;
+    let mut counts = known;
+    for _ in 0..EM_MAX_ITERATIONS {
+        counts = known;
+        counts[1][1] += double_hets * cis;
+        counts[0][0] += double_hets * cis;
+        counts[1][0] += double_hets * (1.0 - cis);
+        counts[0][1] += double_hets * (1.0 - cis);
+        if double_hets == 0.0 {
+            break;
+        }
+
+        let cis_weight = counts[1][1] * counts[0][0];
+        let trans_weight = counts[1][0] * counts[0][1];
+        if cis_weight + trans_weight == 0.0 {
+            break;
+        }
+        let next = cis_weight / (cis_weight + trans_weight);
+        let converged = (next - cis).abs() < EM_TOLERANCE;
+        cis = next;
+        if converged {
+            break;
+        }
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DiploidGenotype, Genotype, GenotypePhasing};
+
+    use super::{LdEstimator, LdStats, ld};
+
+    fn genotype(left: u8, right: u8, phasing: GenotypePhasing) -> Genotype {
+        Genotype::Diploid(DiploidGenotype {
+            left,
+            phasing,
+            right,
+        })
+    }
+
+    #[test]
+    fn test_estimators() {
+        let stats = LdStats::from_haplotype_counts([[50.0, 0.0], [0.0, 50.0]]).unwrap();
+        assert_eq!((stats.r2(), stats.d_prime, stats.d), (1.0, 1.0, 0.25));
+        let stats = LdStats::from_haplotype_counts([[25.0, 25.0], [25.0, 25.0]]).unwrap();
+        assert_eq!(stats.r2(), 0.0);
+        assert_eq!(
+            LdStats::from_haplotype_counts([[10.0, 0.0], [10.0, 0.0]]),
+            None
+        );
+
+        let phased = GenotypePhasing::Phased;
+        let a = [(0, 1), (1, 1), (0, 0), (1, 0)].map(|(l, r)| genotype(l, r, phased));
+        let b = [(0, 1), (1, 1), (0, 0), (0, 0)].map(|(l, r)| genotype(l, r, phased));
+        let samples = [0, 1, 2, 3];
+
+        let stats = ld(&a, &b, &samples, LdEstimator::Phased).unwrap();
+        assert!((stats.d_prime - 1.0).abs() < 1e-12);
+        assert!((stats.r2() - 0.6).abs() < 1e-12);
+
+        // Only the first sample is a double heterozygote, and the rest of the data
+        // has the alternate alleles together.
+        let stats = ld(&a, &b, &samples, LdEstimator::Em).unwrap();
+        assert!((stats.d_prime - 1.0).abs() < 1e-6);
+        assert!((stats.r2() - 0.6).abs() < 1e-6);
+
+        assert_eq!(ld(&a, &b, &[2], LdEstimator::Phased), None);
+    }
+}
@@ -1,3 +1,11 @@
+//! The 1000 Genomes high-coverage (GRCh38) and phase 3 (GRCh37, see [phase3]) releases.
+//!
+//! # Stability
+//!
+//! [Genomes1000Fs] and its queries are the stable API, its internals are only reachable through
+//! accessors (e.g. [Genomes1000Fs::pedigrees] and [Genomes1000Fs::contigs]) and it is
+//! `#[non_exhaustive]`. The other modules may change in minor releases.
+
 #![feature(never_type)]
 #![feature(ascii_char)]
 #![feature(iterator_try_collect)]
@@ -7,13 +15,25 @@ mod info;
 mod parse;
 mod slow;
 
+pub mod af;
 pub mod contig;
+#[cfg(feature = "parquet")]
+pub mod export;
+pub mod haplotype;
+pub mod ld;
+pub mod matrix;
 pub mod pedigree;
+pub mod phase3;
+pub mod rsid;
 pub mod simplified;
 pub mod source;
 
 use either::Either;
-use std::{cmp::Ordering, collections::BTreeMap, io};
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet},
+    io::{self, BufRead},
+};
 
 use biocore::{
     dna::{DnaBase, DnaSequence},
@@ -23,7 +43,13 @@ use biocore::{
 use resource::{RawResource, RawResourceExt, fs::FsCache};
 use utile::{io::FromUtf8Bytes, iter::IteratorExt};
 
-use self::{pedigree::Pedigree, simplified::SimplifiedRecord, source::Genomes1000Resource};
+use self::{
+    af::{AlleleCounter, Stratification},
+    pedigree::{Pedigree, Population, Sex, Superpopulation},
+    rsid::RsidIndex,
+    simplified::SimplifiedRecord,
+    source::Genomes1000Resource,
+};
 
 pub use self::{contig::GRCh38Contig, genotype::AltGenotype, info::RecordInfo};
 
@@ -37,9 +63,11 @@ pub struct VcfFile<S> {
     records: Vec<Record<S>>,
 }
 
+/// A VCF record, from the high-coverage GRCh38 release unless `C` says otherwise
+/// (see [phase3]).
 #[derive(Debug, Clone)]
-pub struct Record<S> {
-    pub contig: GRCh38Contig,
+pub struct Record<S, C = GRCh38Contig> {
+    pub contig: C,
     /// 1-based! 0 and n+1 means telomere (where n is length of contig).
     pub position: u64,
     pub id: String,
@@ -114,15 +142,15 @@ pub struct ExtendedSample<GT> {
     /// ##FORMAT=<ID=SB,Number=4,Type=Integer,Description="Per-sample component statistics which comprise the Fisher's Exact Test to detect strand bias.">
     SB: Option<Vec<u64>>,
 }
-impl<S> Record<S> {
-    pub fn at(&self) -> ContigPosition<GRCh38Contig> {
+impl<S, C: Copy> Record<S, C> {
+    pub fn at(&self) -> ContigPosition<C> {
         ContigPosition {
             contig: self.contig,
             at: self.position - 1,
         }
     }
 }
-impl Record<Genotype> {
+impl<C: Clone> Record<Genotype, C> {
     /// Splits a multi-allelic variant into multiple bi-allelic variants.
     ///
     /// Note: invalidates info.
@@ -206,6 +234,23 @@ impl Record<Genotype> {
 
         Some(self)
     }
+}
+impl Record<Genotype> {
+    /// Matches each call to the ploidy expected for the sample at this position
+    /// (see [Genotype::with_ploidy]), given the sex of each sample in order.
+    ///
+    /// Only affects chrX and chrY, where males are haploid outside the pseudoautosomal regions.
+    /// Samples of unknown sex are left as they are.
+    pub fn with_sample_ploidy(mut self, sexes: &[Option<Sex>]) -> Self {
+        if self.contig != GRCh38Contig::X && self.contig != GRCh38Contig::Y {
+            return self;
+        }
+        let at = self.at();
+        match_sample_ploidy(&mut self.samples, sexes, |sex| {
+            at.contig.ploidy_at(at.at, sex)
+        });
+        self
+    }
     pub fn simplified(self) -> Option<SimplifiedRecord> {
         let Some(reference_allele) = self
             .reference_allele
@@ -242,7 +287,24 @@ impl Record<Genotype> {
         })
     }
 }
+fn match_sample_ploidy(
+    samples: &mut [Genotype],
+    sexes: &[Option<Sex>],
+    ploidy: impl Fn(Sex) -> u8,
+) {
+    assert_eq!(samples.len(), sexes.len());
+    for (genotype, sex) in samples.iter_mut().zip(sexes) {
+        if let Some(sex) = *sex {
+            *genotype = genotype.with_ploidy(ploidy(sex));
+        }
+    }
+}
+
 impl Genotype {
+    /// Copies of allele `variant`, so at most `1` for haploid calls.
+    ///
+    /// On sex chromosomes this depends on the calls matching the sample's ploidy,
+    /// see [Record::with_sample_ploidy].
     pub fn dosage(&self, variant: u8) -> u8 {
         let dose = |v: &u8| (*v == variant).into();
         match self {
@@ -258,6 +320,32 @@ impl Genotype {
             Genotype::Diploid(_) => Some(2),
         }
     }
+    /// Reconciles the call with the expected `ploidy` (see [GRCh38Contig::ploidy_at]).
+    ///
+    /// Homozygous diploid calls in haploid regions become haploid, and other mismatches
+    /// (including any call where the ploidy is `0`) become [Genotype::Missing].
+    pub fn with_ploidy(self, ploidy: u8) -> Self {
+        match (ploidy, self) {
+            (1, Self::Haploid(_)) | (2, Self::Diploid(_)) => self,
+            (1, Self::Diploid(DiploidGenotype { left, right, .. })) if left == right => {
+                Self::Haploid(HaploidGenotype { value: left })
+            }
+            _ => Self::Missing,
+        }
+    }
+    /// The allele on each haplotype, [None] if missing or unphased.
+    pub fn haplotypes(&self) -> Option<Vec<u8>> {
+        match self {
+            Genotype::Missing => None,
+            Genotype::Haploid(HaploidGenotype { value }) => Some(vec![*value]),
+            Genotype::Diploid(DiploidGenotype {
+                left,
+                phasing: GenotypePhasing::Phased,
+                right,
+            }) => Some(vec![*left, *right]),
+            Genotype::Diploid(_) => None,
+        }
+    }
     fn visit_values_mut(&mut self, mut f: impl FnMut(&mut u8)) {
         match self {
             Genotype::Missing => {}
@@ -271,10 +359,16 @@ impl Genotype {
 }
 
 #[derive(Debug)]
+#[non_exhaustive]
 pub struct Genomes1000Fs {
     sample_names: Vec<String>,
     pedigrees: BTreeMap<String, Pedigree>,
+    /// The phase 3 samples, see [Self::unrelated_samples].
+    unrelated: BTreeSet<String>,
     readers: BTreeMap<GRCh38Contig, IndexedVcfReader<std::fs::File>>,
+    cache: FsCache,
+    /// Loaded on first use, see [Self::rsid_index].
+    rsids: Option<RsidIndex>,
 }
 
 impl Genomes1000Fs {
@@ -287,6 +381,57 @@ impl Genomes1000Fs {
     pub fn pedigree(&self, id: &str) -> Option<&Pedigree> {
         self.pedigrees.get(id)
     }
+    /// The pedigree of every sample, ordered by sample name.
+    pub fn pedigrees(&self) -> impl Iterator<Item = &Pedigree> {
+        self.pedigrees.values()
+    }
+    /// The indices (into [Self::sample_names]) of the samples from `population`.
+    pub fn samples_in(&self, population: Population) -> Vec<usize> {
+        self.samples_where(|p| p.population == population)
+    }
+    pub fn samples_in_superpopulation(&self, superpopulation: Superpopulation) -> Vec<usize> {
+        self.samples_where(|p| p.superpopulation == superpopulation)
+    }
+    /// The 2504 unrelated samples of phase 3, leaving out the 698 relatives added for the
+    /// high-coverage release.
+    ///
+    /// These should be used for allele frequencies and LD, which relatives would bias.
+    /// Not all relatives are the children of trios (see [Pedigree::has_parents]), some are
+    /// siblings or more distant relatives without parents in the pedigree.
+    pub fn unrelated_samples(&self) -> Vec<usize> {
+        self.sample_names
+            .iter()
+            .enumerate()
+            .filter(|(_, name)| self.unrelated.contains(*name))
+            .map(|(i, _)| i)
+            .collect()
+    }
+    /// The sex of each of `samples` (indices into [Self::sample_names]), if known.
+    fn sample_sexes(&self, samples: impl Iterator<Item = usize>) -> Vec<Option<Sex>> {
+        samples
+            .map(|i| self.pedigree(&self.sample_names[i]).map(|p| p.sex))
+            .collect()
+    }
+    fn samples_where(&self, f: impl Fn(&Pedigree) -> bool) -> Vec<usize> {
+        self.sample_names
+            .iter()
+            .enumerate()
+            .filter(|(_, name)| self.pedigree(name).is_some_and(&f))
+            .map(|(i, _)| i)
+            .collect()
+    }
+    /// Counts alleles of the records from [Self::query] per population or superpopulation,
+    /// over the [Self::unrelated_samples].
+    ///
+    /// Use [AlleleCounter::new] directly to include related samples.
+    pub fn allele_counter(&self, by: Stratification) -> AlleleCounter {
+        AlleleCounter::new(&self.sample_names, self.pedigrees.values(), by)
+            .retain_samples(&self.unrelated_samples())
+    }
+    /// The contigs for which a VCF is available.
+    pub fn contigs(&self) -> impl Iterator<Item = GRCh38Contig> + '_ {
+        self.readers.keys().copied()
+    }
     pub async fn new_with_cache(cache: &FsCache) -> io::Result<Self> {
         let mut sample_names = None;
 
@@ -329,10 +474,20 @@ impl Genomes1000Fs {
         .map(|p| (p.id.clone(), p))
         .collect();
 
+        let unrelated = Genomes1000Resource::old_phase_3_panel()
+            .log_progress()
+            .with_fs_cache(cache)
+            .ensure_cached_async()
+            .await?;
+        let unrelated = read_panel_samples(unrelated.buffered().read()?)?;
+
         Ok(Self {
             sample_names: sample_names.unwrap(),
             pedigrees,
+            unrelated,
             readers,
+            cache: cache.clone(),
+            rsids: None,
         })
     }
     pub fn query(
@@ -362,29 +517,146 @@ impl Genomes1000Fs {
             .unwrap())
         }))
     }
+    /// Like [Self::query], but skips the genotypes.
+    pub fn query_sites(
+        &mut self,
+        at: &ContigRange<GRCh38Contig>,
+    ) -> io::Result<impl Iterator<Item = io::Result<Record<!>>> + use<'_>> {
+        let entry_c = if at.contig.is_core() {
+            at.contig
+        } else {
+            GRCh38Contig::MT
+        };
+        let reader = self.readers.get_mut(&entry_c).unwrap();
+
+        let mut buf = vec![];
+        Ok(reader.query_raw(at)?.map(move |r| {
+            let r = r?;
+            Ok(parse::read_site(&mut buf, &mut std::io::Cursor::new(r))?.unwrap())
+        }))
+    }
+    /// The records at the position dbSNP gives for `rsid` (e.g. `rs42`), empty if it is not in
+    /// dbSNP or in the VCFs.
+    ///
+    /// The VCFs don't list rsIDs, so records are matched by position only.
+    /// The first call loads the [RsidIndex], see [Self::rsid_index].
+    pub fn query_rsid(&mut self, rsid: &str) -> io::Result<Vec<Record<Genotype>>> {
+        let positions: Vec<_> = self.rsid_index()?.get(rsid).collect();
+
+        let mut records = vec![];
+        for at in positions {
+            let range = ContigRange {
+                contig: at.contig,
+                at: at.at..at.at + 1,
+            };
+            for record in self.query(&range)? {
+                let record = record?;
+                if record.at() == at {
+                    records.push(record);
+                }
+            }
+        }
+        Ok(records)
+    }
+    /// The dbSNP rsID index, loaded from the cache.
+    ///
+    /// If it isn't cached yet, it is built first, see [RsidIndex::build_from_dbsnp].
+    pub fn rsid_index(&mut self) -> io::Result<&RsidIndex> {
+        if self.rsids.is_none() {
+            let cache = &self.cache;
+            let index = RsidIndex::load_or_build(&RsidIndex::cache_entry(cache), || {
+                RsidIndex::build_from_dbsnp(cache)
+            })?;
+            self.rsids = Some(index);
+        }
+        Ok(self.rsids.as_ref().unwrap())
+    }
+    /// Like [Self::query], but only parses the genotypes of `samples` (indices into
+    /// [Self::sample_names], see [Self::sample_indices]), which are returned in that order.
+    /// Repeated or out of range samples are an [io::ErrorKind::InvalidInput] error.
+    pub fn query_samples(
+        &mut self,
+        at: &ContigRange<GRCh38Contig>,
+        samples: &[usize],
+    ) -> io::Result<impl Iterator<Item = io::Result<Record<Genotype>>> + use<'_>> {
+        let entry_c = if at.contig.is_core() {
+            at.contig
+        } else {
+            GRCh38Contig::MT
+        };
+
+        let sample_count = self.sample_names.len();
+        let subset = parse::SampleSubset::new(samples, sample_count)?;
+        let reader = self.readers.get_mut(&entry_c).unwrap();
+
+        let mut buf = vec![];
+        let read_sample = sample_reading_function(entry_c);
+
+        Ok(reader.query_raw(at)?.map(move |r| {
+            let r = r?;
+            Ok(parse::read_record_subset(
+                &mut buf,
+                sample_count,
+                &subset,
+                &mut std::io::Cursor::new(r),
+                read_sample,
+            )?
+            .unwrap())
+        }))
+    }
+    /// The indices of the given samples in [Self::sample_names], [None] if any is unknown.
+    pub fn sample_indices<'a>(
+        &self,
+        names: impl IntoIterator<Item = &'a str>,
+    ) -> Option<Vec<usize>> {
+        names
+            .into_iter()
+            .map(|name| self.sample_names.iter().position(|n| n == name))
+            .collect()
+    }
+    /// Like [Self::query], split into biallelic records with sequence alleles.
+    ///
+    /// On chrX and chrY, calls are matched to each sample's ploidy,
+    /// see [Record::with_sample_ploidy].
     pub fn query_simplified(
         &mut self,
         at: &ContigRange<GRCh38Contig>,
     ) -> io::Result<impl Iterator<Item = SimplifiedRecord> + use<'_>> {
-        Ok(self
-            .query(at)?
-            .filter_map(|r: io::Result<Record<Genotype>>| match r {
-                Ok(r) => Some(Ok(r.normalized()?)),
-                Err(e) => Some(Err(e)),
-            })
-            .flat_map(|r: io::Result<Record<Genotype>>| match r {
-                Ok(r) => Either::Left(r.split().map(Ok)),
-                Err(e) => Either::Right([Err(e)].into_iter()),
-            })
-            .filter_map(|r: io::Result<Record<Genotype>>| match r {
-                Ok(r) => Some(Ok(r.simplified()?)),
-                Err(e) => Some(Err(e)),
-            })
-            .map(|r| r.unwrap()) // TODO
-            .staged_sorted_by(simplified_stage_one, simplified_stage_two))
+        let sexes = self.sample_sexes(0..self.sample_names.len());
+        Ok(simplify(self.query(at)?, sexes))
+    }
+    /// Like [Self::query_simplified], with the genotypes of `samples` only, see [Self::query_samples].
+    pub fn query_simplified_samples(
+        &mut self,
+        at: &ContigRange<GRCh38Contig>,
+        samples: &[usize],
+    ) -> io::Result<impl Iterator<Item = SimplifiedRecord> + use<'_>> {
+        let sexes = self.sample_sexes(samples.iter().copied());
+        Ok(simplify(self.query_samples(at, samples)?, sexes))
     }
 }
 
+fn simplify(
+    records: impl Iterator<Item = io::Result<Record<Genotype>>>,
+    sexes: Vec<Option<Sex>>,
+) -> impl Iterator<Item = SimplifiedRecord> {
+    records
+        .filter_map(move |r: io::Result<Record<Genotype>>| match r {
+            Ok(r) => Some(Ok(r.with_sample_ploidy(&sexes).normalized()?)),
+            Err(e) => Some(Err(e)),
+        })
+        .flat_map(|r: io::Result<Record<Genotype>>| match r {
+            Ok(r) => Either::Left(r.split().map(Ok)),
+            Err(e) => Either::Right([Err(e)].into_iter()),
+        })
+        .filter_map(|r: io::Result<Record<Genotype>>| match r {
+            Ok(r) => Some(Ok(r.simplified()?)),
+            Err(e) => Some(Err(e)),
+        })
+        .map(|r| r.unwrap()) // TODO
+        .staged_sorted_by(simplified_stage_one, simplified_stage_two)
+}
+
 pub async fn load_all_simplified() -> (Vec<String>, impl Iterator<Item = SimplifiedRecord>) {
     let (sample_names, variants) = load_all().await.unwrap();
     let sample_names: Vec<_> = sample_names.into_iter().map(|s| s.to_string()).collect();
@@ -436,6 +708,21 @@ pub async fn load_contig(
     parse::parse(resource.read()?, sample_reading_function(c))
 }
 
+/// Like [load_contig], but only reads the site-level columns (no genotypes), which is much faster.
+pub async fn load_contig_sites(
+    c: GRCh38Contig,
+) -> io::Result<(Vec<String>, impl Iterator<Item = io::Result<Record<!>>>)> {
+    let resource = Genomes1000Resource::high_coverage_genotypes_contig_vcf(c)
+        .log_progress()
+        .with_global_fs_cache()
+        .ensure_cached_async()
+        .await?
+        .decompressed()
+        .buffered();
+
+    parse::parse_sites(resource.read()?)
+}
+
 pub async fn load_pedigree(resource: impl RawResource) -> io::Result<Vec<Pedigree>> {
     Ok(csv::ReaderBuilder::new()
         .delimiter(b' ')
@@ -455,6 +742,19 @@ pub async fn load_pedigree(resource: impl RawResource) -> io::Result<Vec<Pedigre
         .collect())
 }
 
+/// The sample IDs of a panel file (e.g. [Genomes1000Resource::old_phase_3_panel]).
+pub fn read_panel_samples(reader: impl BufRead) -> io::Result<BTreeSet<String>> {
+    let mut samples = BTreeSet::new();
+    for line in reader.lines() {
+        let line = line?;
+        let sample = line.split('\t').next().unwrap_or_default().trim();
+        if !sample.is_empty() && sample != "sample" {
+            samples.insert(sample.to_owned());
+        }
+    }
+    Ok(samples)
+}
+
 /// The fasta reader should be decompressed.
 /// It should also implement [Seek](std::io::Seek) if random access is needed.
 pub async fn load_grch38_reference_genome<F>(
@@ -584,3 +884,89 @@ mod boilerplate {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use resource::fs::FsCache;
+
+    use crate::{
+        DiploidGenotype, GRCh38Contig, Genomes1000Fs, Genotype, GenotypePhasing, HaploidGenotype,
+        Record,
+        pedigree::{Pedigree, Sex},
+        read_panel_samples,
+    };
+
+    #[test]
+    fn unrelated_samples() {
+        // A trio, and a sibling of the child without parents in the pedigree.
+        let pedigree = "\
+SampleID FamilyID FatherID MotherID Sex Population Superpopulation
+HG00100 HG001 0 0 1 GBR EUR
+HG00101 HG001 0 0 2 GBR EUR
+HG00102 HG001 HG00100 HG00101 2 GBR EUR
+HG00103 HG001 0 0 1 GBR EUR
+HG00200 HG002 0 0 2 GBR EUR
+";
+        let panel = "\
+sample\tpop\tsuper_pop\tgender
+HG00100\tGBR\tEUR\tmale
+HG00101\tGBR\tEUR\tfemale
+HG00200\tGBR\tEUR\tfemale
+";
+        let pedigrees: Vec<Pedigree> = csv::ReaderBuilder::new()
+            .delimiter(b' ')
+            .from_reader(pedigree.as_bytes())
+            .into_deserialize()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let fs = Genomes1000Fs {
+            sample_names: pedigrees.iter().map(|p| p.id.clone()).collect(),
+            pedigrees: pedigrees.into_iter().map(|p| (p.id.clone(), p)).collect(),
+            unrelated: read_panel_samples(panel.as_bytes()).unwrap(),
+            readers: BTreeMap::new(),
+            cache: FsCache::new("/nonexistent"),
+            rsids: None,
+        };
+        assert!(!fs.pedigree("HG00103").unwrap().has_parents());
+        assert_eq!(fs.unrelated_samples(), [0, 1, 4]);
+    }
+
+    #[test]
+    fn sample_ploidy() {
+        let diploid = |left, right| {
+            Genotype::Diploid(DiploidGenotype {
+                left,
+                phasing: GenotypePhasing::Phased,
+                right,
+            })
+        };
+        let haploid = |value| Genotype::Haploid(HaploidGenotype { value });
+        assert_eq!(diploid(1, 1).with_ploidy(1), haploid(1));
+        assert_eq!(diploid(0, 1).with_ploidy(1), Genotype::Missing);
+        assert_eq!(haploid(1).with_ploidy(2), Genotype::Missing);
+        assert_eq!(haploid(1).with_ploidy(0), Genotype::Missing);
+
+        let record = |position| Record {
+            contig: GRCh38Contig::X,
+            position,
+            id: ".".to_owned(),
+            reference_allele: vec![None],
+            alternate_alleles: vec![],
+            quality: None,
+            filter: "PASS".to_owned(),
+            info: String::new(),
+            format: "GT".to_owned(),
+            samples: vec![diploid(1, 1), diploid(1, 1), diploid(1, 1)],
+        };
+        let sexes = [Some(Sex::Male), Some(Sex::Female), None];
+
+        // PAR1, where males are diploid.
+        let par = record(1_000_000).with_sample_ploidy(&sexes);
+        assert_eq!(par.samples.iter().map(|g| g.dosage(1)).sum::<u8>(), 6);
+        let non_par = record(50_000_000).with_sample_ploidy(&sexes);
+        assert_eq!(non_par.samples[0], haploid(1));
+        assert_eq!(non_par.samples.iter().map(|g| g.dosage(1)).sum::<u8>(), 5);
+    }
+}
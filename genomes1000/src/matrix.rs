@@ -0,0 +1,437 @@
+//! Dense variants × samples dosage matrices, e.g. for PRS computation or PCA.
+//!
+//! Records are read straight from [Genomes1000Fs::query], with one row per alternate allele,
+//! which avoids building a [crate::simplified::SimplifiedRecord] per variant.
+
+use std::io;
+
+use biocore::{dna::DnaSequence, location::ContigRange, mutation::normalize::Variant};
+
+use crate::{GRCh38Contig, Genomes1000Fs, Genotype, Record};
+
+/// A value of a [DosageMatrix].
+pub trait Dosage: Copy {
+    /// The value of missing genotypes.
+    const MISSING: Self;
+    fn from_dosage(dosage: u8) -> Self;
+    /// [None] for [Self::MISSING].
+    fn value(self) -> Option<f64>;
+}
+impl Dosage for u8 {
+    const MISSING: Self = u8::MAX;
+    fn from_dosage(dosage: u8) -> Self {
+        dosage
+    }
+    fn value(self) -> Option<f64> {
+        (self != Self::MISSING).then_some(f64::from(self))
+    }
+}
+impl Dosage for f32 {
+    const MISSING: Self = f32::NAN;
+    fn from_dosage(dosage: u8) -> Self {
+        f32::from(dosage)
+    }
+    fn value(self) -> Option<f64> {
+        (!self.is_nan()).then_some(f64::from(self))
+    }
+}
+
+/// Copies of the alternate allele, see [Genomes1000Fs::dosage_matrix].
+///
+/// Other genotype sources can key the rows differently, e.g. by index into their variants.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DosageMatrix<T, V = Variant<GRCh38Contig>> {
+    /// The rows.
+    pub variants: Vec<V>,
+    /// The columns, as indices into [Genomes1000Fs::sample_names] (or the samples of the source).
+    pub samples: Vec<usize>,
+    /// Row-major, [Dosage::MISSING] for missing genotypes.
+    pub values: Vec<T>,
+}
+
+/// Filters for [DosageMatrix::standardized].
+#[derive(Debug, Clone, PartialEq)]
+pub struct StandardizeOptions {
+    /// Variants with a lower minor allele frequency are dropped.
+    pub min_maf: f64,
+    /// Variants with a higher fraction of missing genotypes are dropped.
+    pub max_missing: f64,
+    /// Drops variants in LD with a variant kept before them, if set.
+    pub ld_prune: Option<LdPrune>,
+}
+/// Greedy LD pruning, in variant order.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LdPrune {
+    /// The largest distance (in bases) between two variants checked for LD.
+    pub window: u64,
+    /// Variants with a higher r² with a kept variant are dropped.
+    pub max_r2: f64,
+}
+impl Default for StandardizeOptions {
+    fn default() -> Self {
+        Self {
+            min_maf: 0.01,
+            max_missing: 0.05,
+            ld_prune: None,
+        }
+    }
+}
+
+/// A mean-centered, variance-standardized dosage matrix, see [DosageMatrix::standardized].
+#[derive(Debug, Clone, PartialEq)]
+pub struct StandardizedMatrix {
+    /// Missing genotypes are `0.0`, i.e. imputed to the mean.
+    pub matrix: DosageMatrix<f32>,
+    /// The mean dosage of each kept variant.
+    pub means: Vec<f32>,
+    /// The standard deviation each kept variant was divided by, `sqrt(2p(1 - p))`.
+    pub scales: Vec<f32>,
+}
+
+impl<T: Dosage, V> DosageMatrix<T, V> {
+    pub fn new(samples: Vec<usize>) -> Self {
+        Self {
+            variants: vec![],
+            samples,
+            values: vec![],
+        }
+    }
+    /// Panics if the row length doesn't match the number of samples.
+    pub fn push_row(&mut self, variant: V, row: impl IntoIterator<Item = T>) {
+        let len = self.values.len();
+        self.values.extend(row);
+        assert_eq!(self.values.len() - len, self.samples.len());
+        self.variants.push(variant);
+    }
+
+    pub fn row(&self, variant: usize) -> &[T] {
+        let n = self.samples.len();
+        &self.values[variant * n..(variant + 1) * n]
+    }
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.values.chunks_exact(self.samples.len().max(1))
+    }
+    /// The dosages of a sample (by position in [Self::samples]) across all variants.
+    pub fn column(&self, sample: usize) -> impl Iterator<Item = T> + '_ {
+        assert!(sample < self.samples.len());
+        self.values
+            .iter()
+            .skip(sample)
+            .step_by(self.samples.len())
+            .copied()
+    }
+    pub fn get(&self, variant: usize, sample: usize) -> T {
+        self.values[variant * self.samples.len() + sample]
+    }
+}
+impl<T: Dosage> DosageMatrix<T> {
+    /// Centers and scales each variant (as in EIGENSTRAT), after dropping the variants
+    /// that don't pass `options`, ready for PCA.
+    ///
+    /// The means and scales are kept so other samples can be projected onto the same axes.
+    /// Expects diploid dosages, so autosomes only.
+    pub fn standardized(&self, options: &StandardizeOptions) -> StandardizedMatrix {
+        let n = self.samples.len();
+        let mut standardized = StandardizedMatrix {
+            matrix: DosageMatrix::new(self.samples.clone()),
+            means: vec![],
+            scales: vec![],
+        };
+        if n == 0 {
+            return standardized;
+        }
+
+        // Kept rows from the same contig within the pruning window, as `(position, row)`.
+        let mut recent: std::collections::VecDeque<(u64, usize)> = Default::default();
+        for (variant, v) in self.variants.iter().enumerate() {
+            let values = self.row(variant).iter().map(|d| d.value());
+            let (called, sum) = values
+                .clone()
+                .flatten()
+                .fold((0_usize, 0.0), |(c, s), d| (c + 1, s + d));
+            if called == 0 || (n - called) as f64 / n as f64 > options.max_missing {
+                continue;
+            }
+            let mean = sum / called as f64;
+            let p = mean / 2.0;
+            if p <= 0.0 || p >= 1.0 || f64::min(p, 1.0 - p) < options.min_maf {
+                continue;
+            }
+            let scale = (2.0 * p * (1.0 - p)).sqrt();
+            let row: Vec<f32> = values
+                .map(|d| d.map_or(0.0, |d| ((d - mean) / scale) as f32))
+                .collect();
+
+            if let Some(prune) = options.ld_prune {
+                let m = &standardized.matrix;
+                recent.retain(|&(at, i)| {
+                    m.variants[i].at.contig == v.at.contig && v.at.at.abs_diff(at) <= prune.window
+                });
+                let linked = recent
+                    .iter()
+                    .any(|&(_, i)| correlation(m.row(i), &row).powi(2) > prune.max_r2);
+                if linked {
+                    continue;
+                }
+                recent.push_back((v.at.at, m.variants.len()));
+            }
+
+            standardized.matrix.variants.push(v.clone());
+            standardized.matrix.values.extend(row);
+            standardized.means.push(mean as f32);
+            standardized.scales.push(scale as f32);
+        }
+        standardized
+    }
+
+    /// Adds a row with the dosage of allele `alt` of `genotypes`, or [Dosage::MISSING] throughout.
+    fn push(&mut self, variant: Variant<GRCh38Contig>, genotypes: Option<(&[Genotype], u8)>) {
+        self.variants.push(variant);
+        match genotypes {
+            Some((genotypes, alt)) => {
+                self.values
+                    .extend(self.samples.iter().map(|&i| match genotypes[i] {
+                        Genotype::Missing => T::MISSING,
+                        genotype => T::from_dosage(genotype.dosage(alt)),
+                    }))
+            }
+            None => self
+                .values
+                .extend(std::iter::repeat_n(T::MISSING, self.samples.len())),
+        }
+    }
+}
+
+impl Genomes1000Fs {
+    /// All variants starting within `ranges`, for `samples` (indices into [Self::sample_names],
+    /// e.g. from [Self::samples_in]) or all samples if [None].
+    ///
+    /// Alternate alleles that can't be resolved to a sequence are left out.
+    pub fn dosage_matrix<T: Dosage>(
+        &mut self,
+        ranges: &[ContigRange<GRCh38Contig>],
+        samples: Option<&[usize]>,
+    ) -> io::Result<DosageMatrix<T>> {
+        let mut matrix = DosageMatrix::new(self.matrix_samples(samples));
+        for range in ranges {
+            for record in self.query(range)? {
+                let record = record?;
+                if !range.at.contains(&record.at().at) {
+                    continue;
+                }
+                for (alt, variant) in alleles(&record) {
+                    matrix.push(variant, Some((&record.samples, alt)));
+                }
+            }
+        }
+        Ok(matrix)
+    }
+    /// One row per variant, in order, see [Self::dosage_matrix].
+    ///
+    /// Variants must be in the VCF representation (left-aligned, with the padding base for indels).
+    /// Those not in the VCFs get a row of [Dosage::MISSING].
+    pub fn dosage_matrix_for<T: Dosage>(
+        &mut self,
+        variants: &[Variant<GRCh38Contig>],
+        samples: Option<&[usize]>,
+    ) -> io::Result<DosageMatrix<T>> {
+        let mut matrix = DosageMatrix::new(self.matrix_samples(samples));
+        for variant in variants {
+            let range = ContigRange {
+                contig: variant.at.contig,
+                at: variant.at.at..variant.at.at + 1,
+            };
+            let mut found = None;
+            for record in self.query(&range)? {
+                let record = record?;
+                if record.at() != variant.at {
+                    continue;
+                }
+                let alt = alleles(&record)
+                    .find(|(_, v)| v == variant)
+                    .map(|(alt, _)| alt);
+                if let Some(alt) = alt {
+                    found = Some((record, alt));
+                    break;
+                }
+            }
+            let genotypes = found.as_ref().map(|(r, alt)| (&r.samples[..], *alt));
+            matrix.push(variant.clone(), genotypes);
+        }
+        Ok(matrix)
+    }
+
+    fn matrix_samples(&self, samples: Option<&[usize]>) -> Vec<usize> {
+        match samples {
+            Some(samples) => {
+                assert!(samples.iter().all(|&i| i < self.sample_names.len()));
+                samples.to_vec()
+            }
+            None => (0..self.sample_names.len()).collect(),
+        }
+    }
+}
+
+/// The alternate alleles of a record (as their index in the genotypes), as variants.
+fn alleles(record: &Record<Genotype>) -> impl Iterator<Item = (u8, Variant<GRCh38Contig>)> + '_ {
+    let reference: Option<DnaSequence> = record.reference_allele.iter().copied().try_collect();
+    record
+        .alternate_alleles
+        .iter()
+        .enumerate()
+        .filter_map(move |(i, alt)| {
+            let reference = reference.clone()?;
+            let alternate = alt.unpack(&reference)?;
+            Some((
+                u8::try_from(i + 1).unwrap(),
+                Variant {
+                    at: record.at(),
+                    reference,
+                    alternate,
+                },
+            ))
+        })
+}
+
+/// Pearson correlation of two standardized rows, `0.0` if either is constant.
+fn correlation(a: &[f32], b: &[f32]) -> f64 {
+    let dot = |a: &[f32], b: &[f32]| -> f64 {
+        a.iter()
+            .zip(b)
+            .map(|(a, b)| f64::from(*a) * f64::from(*b))
+            .sum()
+    };
+    let norm = dot(a, a) * dot(b, b);
+    if norm == 0.0 {
+        return 0.0;
+    }
+    dot(a, b) / norm.sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use biocore::{dna::DnaSequence, location::ContigPosition, mutation::normalize::Variant};
+
+    use crate::{
+        AltGenotype, DiploidGenotype, GRCh38Contig, Genotype, GenotypePhasing, HaploidGenotype,
+        Record,
+    };
+
+    use super::{DosageMatrix, LdPrune, StandardizeOptions, alleles};
+
+    #[test]
+    fn rows() {
+        let sequence = |s: &str| s.parse::<DnaSequence>().unwrap();
+        let record = Record {
+            contig: GRCh38Contig::CHR1,
+            position: 100,
+            id: ".".to_owned(),
+            reference_allele: sequence("A").into_iter().map(Some).collect(),
+            alternate_alleles: vec![
+                AltGenotype::Sequence(sequence("G")),
+                AltGenotype::INS,
+                AltGenotype::Sequence(sequence("AT")),
+            ],
+            quality: None,
+            filter: "PASS".to_owned(),
+            info: String::new(),
+            format: "GT".to_owned(),
+            samples: vec![
+                Genotype::Diploid(DiploidGenotype {
+                    left: 1,
+                    phasing: GenotypePhasing::Phased,
+                    right: 3,
+                }),
+                Genotype::Missing,
+                Genotype::Haploid(HaploidGenotype { value: 3 }),
+            ],
+        };
+
+        let alleles: Vec<_> = alleles(&record).collect();
+        assert_eq!(alleles.len(), 2);
+        assert_eq!(alleles[1].0, 3);
+        assert_eq!(
+            alleles[1].1,
+            Variant {
+                at: ContigPosition {
+                    contig: GRCh38Contig::CHR1,
+                    at: 99
+                },
+                reference: sequence("A"),
+                alternate: sequence("AT"),
+            }
+        );
+
+        let mut matrix = DosageMatrix::<u8>::new(vec![2, 0]);
+        for (alt, variant) in alleles.clone() {
+            matrix.push(variant, Some((&record.samples, alt)));
+        }
+        matrix.push(alleles[0].1.clone(), None);
+        assert_eq!(matrix.row(0), [0, 1]);
+        assert_eq!(matrix.row(1), [1, 1]);
+        assert_eq!(matrix.row(2), [u8::MAX; 2]);
+
+        let mut matrix = DosageMatrix::<f32>::new(vec![1]);
+        matrix.push(alleles[0].1.clone(), Some((&record.samples, 1)));
+        assert!(matrix.get(0, 0).is_nan());
+    }
+
+    #[test]
+    fn rows_and_columns() {
+        let mut matrix = DosageMatrix::<u8, usize>::new(vec![0, 1]);
+        matrix.push_row(3, [0, 1]);
+        matrix.push_row(5, [u8::MAX, 2]);
+
+        assert_eq!(matrix.variants, [3, 5]);
+        assert_eq!(matrix.get(1, 1), 2);
+        assert_eq!(matrix.row(1), [u8::MAX, 2]);
+        assert_eq!(matrix.column(0).collect::<Vec<_>>(), [0, u8::MAX]);
+        assert_eq!(matrix.rows().count(), 2);
+    }
+
+    #[test]
+    fn standardized() {
+        let variant = |at| Variant {
+            at: ContigPosition {
+                contig: GRCh38Contig::CHR1,
+                at,
+            },
+            reference: "A".parse::<DnaSequence>().unwrap(),
+            alternate: "G".parse::<DnaSequence>().unwrap(),
+        };
+        let mut matrix = DosageMatrix::<u8>::new(vec![0, 1, 2, 3]);
+        let rows: [[u8; 4]; 4] = [
+            [0, 1, 2, 1],
+            // The same as the first one.
+            [0, 1, 2, 1],
+            // Monomorphic.
+            [0, 0, 0, 0],
+            // Too many missing.
+            [0, u8::MAX, 2, u8::MAX],
+        ];
+        for (i, row) in rows.into_iter().enumerate() {
+            matrix.variants.push(variant(100 + i as u64));
+            matrix.values.extend(row);
+        }
+
+        let options = StandardizeOptions {
+            max_missing: 0.25,
+            ..Default::default()
+        };
+        let standardized = matrix.standardized(&options);
+        assert_eq!(standardized.matrix.variants.len(), 2);
+        assert_eq!(standardized.means, [1.0, 1.0]);
+        let row = standardized.matrix.row(0);
+        assert!((row.iter().sum::<f32>()).abs() < 1e-6);
+        assert!((row[2] - 1.0 / 0.5f32.sqrt()).abs() < 1e-6);
+
+        let pruned = matrix.standardized(&StandardizeOptions {
+            ld_prune: Some(LdPrune {
+                window: 10,
+                max_r2: 0.5,
+            }),
+            ..options
+        });
+        assert_eq!(pruned.matrix.variants, [variant(100)]);
+    }
+}
@@ -1,5 +1,7 @@
 use std::{
+    fmt,
     io::{self, BufRead},
+    marker::PhantomData,
     str,
 };
 
@@ -10,10 +12,10 @@ use crate::GRCh38Contig;
 
 use super::Record;
 
-pub(super) fn parse<S>(
+pub(super) fn parse<S, C>(
     reader: impl BufRead,
     read_sample: fn(&[u8], &[u8]) -> io::Result<S>,
-) -> io::Result<(Vec<String>, Lines<S, impl BufRead>)> {
+) -> io::Result<(Vec<String>, Lines<S, impl BufRead, C>)> {
     let mut reader = comments::skip(reader)?;
     let sample_names = read_header(&mut reader)?;
     let sample_count = sample_names.len();
@@ -25,6 +27,21 @@ pub(super) fn parse<S>(
             inner: reader,
             sample_count,
             read_sample,
+            contig: PhantomData,
+        },
+    ))
+}
+
+/// Like [parse], but skips the sample columns without looking at them.
+pub(super) fn parse_sites(reader: impl BufRead) -> io::Result<(Vec<String>, Sites<impl BufRead>)> {
+    let mut reader = comments::skip(reader)?;
+    let sample_names = read_header(&mut reader)?;
+
+    Ok((
+        sample_names,
+        Sites {
+            buf: vec![],
+            inner: reader,
         },
     ))
 }
@@ -53,17 +70,19 @@ pub(super) fn read_header(reader: &mut impl BufRead) -> Result<Vec<String>, io::
 }
 
 #[derive(Debug)]
-pub(super) struct Lines<S, B> {
+pub(super) struct Lines<S, B, C = GRCh38Contig> {
     buf: Vec<u8>,
     inner: B,
     sample_count: usize,
     read_sample: fn(&[u8], &[u8]) -> io::Result<S>,
+    contig: PhantomData<C>,
 }
-impl<S, B> Iterator for Lines<S, B>
+impl<S, B, C> Iterator for Lines<S, B, C>
 where
     B: BufRead,
+    C: FromUtf8Bytes<Err: fmt::Debug>,
 {
-    type Item = Result<Record<S>, io::Error>;
+    type Item = Result<Record<S, C>, io::Error>;
 
     fn next(&mut self) -> Option<Self::Item> {
         match read_record(
@@ -79,12 +98,114 @@ where
     }
 }
 
-pub(super) fn read_record<S>(
+/// The sample columns to read with [read_record_subset], in the order they are returned.
+#[derive(Debug, Clone)]
+pub(super) struct SampleSubset {
+    /// `(column, index in the output)`, sorted by column.
+    columns: Vec<(usize, usize)>,
+}
+impl SampleSubset {
+    /// An [io::ErrorKind::InvalidInput] error for repeated samples or samples out of range.
+    pub(super) fn new(samples: &[usize], sample_count: usize) -> io::Result<Self> {
+        let invalid = |message| io::Error::new(io::ErrorKind::InvalidInput, message);
+        let mut columns: Vec<_> = samples.iter().copied().zip(0..).collect();
+        columns.sort_unstable();
+        if let Some(w) = columns.windows(2).find(|w| w[0].0 == w[1].0) {
+            return Err(invalid(format!("Duplicate sample {}.", w[0].0)));
+        }
+        if let Some(&(c, _)) = columns.last()
+            && c >= sample_count
+        {
+            return Err(invalid(format!(
+                "Sample {c} out of range, there are {sample_count} samples."
+            )));
+        }
+        Ok(Self { columns })
+    }
+}
+
+#[derive(Debug)]
+pub(super) struct Sites<B> {
+    buf: Vec<u8>,
+    inner: B,
+}
+impl<B> Iterator for Sites<B>
+where
+    B: BufRead,
+{
+    type Item = Result<Record<!>, io::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        read_site(&mut self.buf, &mut self.inner).transpose()
+    }
+}
+
+pub(super) fn read_record<S, C: FromUtf8Bytes<Err: fmt::Debug>>(
     buf: &mut Vec<u8>,
     sample_count: usize,
     reader: &mut impl BufRead,
     read_sample: fn(&[u8], &[u8]) -> io::Result<S>,
-) -> io::Result<Option<Record<S>>> {
+) -> io::Result<Option<Record<S, C>>> {
+    let Some(mut record) = read_fixed_columns(buf, reader)? else {
+        return Ok(None);
+    };
+    record.samples = read_samples(buf, sample_count, reader, |buf| {
+        read_sample(record.format.as_bytes(), buf)
+    })?;
+    Ok(Some(record))
+}
+/// Like [read_record], but only parses the sample columns in `subset`, skipping the others.
+pub(super) fn read_record_subset<S, C: FromUtf8Bytes<Err: fmt::Debug>>(
+    buf: &mut Vec<u8>,
+    sample_count: usize,
+    subset: &SampleSubset,
+    reader: &mut impl BufRead,
+    read_sample: fn(&[u8], &[u8]) -> io::Result<S>,
+) -> io::Result<Option<Record<S, C>>> {
+    let Some(mut record) = read_fixed_columns(buf, reader)? else {
+        return Ok(None);
+    };
+
+    let mut samples: Vec<Option<S>> = (0..subset.columns.len()).map(|_| None).collect();
+    let mut column = 0;
+    for &(wanted, i) in &subset.columns {
+        for _ in column..wanted {
+            reader.skip_until(b'\t')?;
+        }
+        let last = wanted + 1 == sample_count;
+        buf.clear();
+        reader.read_until(if last { b'\n' } else { b'\t' }, buf)?;
+        let mut sample = &buf[..];
+        while let [rest @ .., b'\t' | b'\n' | b'\r'] = sample {
+            sample = rest;
+        }
+        samples[i] = Some(read_sample(record.format.as_bytes(), sample)?);
+        column = wanted + 1;
+    }
+    if column < sample_count {
+        reader.skip_until(b'\n')?;
+    }
+
+    record.samples = samples.into_iter().map(Option::unwrap).collect();
+    Ok(Some(record))
+}
+/// Reads the site-level columns of a record and skips the rest of the line.
+pub(super) fn read_site(
+    buf: &mut Vec<u8>,
+    reader: &mut impl BufRead,
+) -> io::Result<Option<Record<!>>> {
+    let Some(record) = read_fixed_columns(buf, reader)? else {
+        return Ok(None);
+    };
+    reader.skip_until(b'\n')?;
+    Ok(Some(record))
+}
+
+/// Everything up to (and including) `FORMAT`, with no samples.
+fn read_fixed_columns<S, C: FromUtf8Bytes<Err: fmt::Debug>>(
+    buf: &mut Vec<u8>,
+    reader: &mut impl BufRead,
+) -> io::Result<Option<Record<S, C>>> {
     fn take_string(buf: &mut Vec<u8>, reader: &mut impl BufRead) -> io::Result<String> {
         buf.clear();
         reader.read_until(b'\t', buf)?;
@@ -92,14 +213,14 @@ pub(super) fn read_record<S>(
         Ok(String::from_utf8(buf.to_vec()).unwrap())
     }
 
-    let contig: GRCh38Contig = {
+    let contig: C = {
         buf.clear();
         reader.read_until(b'\t', buf)?;
         if buf.is_empty() {
             return Ok(None);
         }
         let buf = &buf[..buf.len() - 1];
-        GRCh38Contig::from_bytes(buf).unwrap()
+        C::from_bytes(buf).unwrap()
     };
     let position: u64 = {
         buf.clear();
@@ -150,10 +271,6 @@ pub(super) fn read_record<S>(
     let info = take_string(buf, reader)?;
     let format = take_string(buf, reader)?;
 
-    let samples = read_samples(buf, sample_count, reader, |buf| {
-        read_sample(format.as_bytes(), buf)
-    })?;
-
     Ok(Some(Record {
         contig,
         position,
@@ -164,7 +281,7 @@ pub(super) fn read_record<S>(
         filter,
         info,
         format,
-        samples,
+        samples: vec![],
     }))
 }
 
@@ -491,3 +608,76 @@ fn utf8_error(type_: &'static str, e: str::Utf8Error) -> io::Error {
         format!("Expected {type_}, but found invalid UTF-8: {e:?}."),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Cursor};
+
+    use crate::{GRCh38Contig, Genotype, Record};
+
+    use super::{SampleSubset, read_record, read_record_subset, read_site};
+
+    #[test]
+    fn sites() {
+        let line = "chr1\t100\trs42\tA\tG,T\t.\tPASS\tAC=1\tGT\t0|1\t1|2\n";
+        let mut cursor = Cursor::new(line.repeat(2));
+        let mut buf = vec![];
+
+        let site = read_site(&mut buf, &mut cursor).unwrap().unwrap();
+        assert_eq!(site.contig, GRCh38Contig::CHR1);
+        assert_eq!(
+            (site.position, &*site.id, &*site.info),
+            (100, "rs42", "AC=1")
+        );
+        assert_eq!(site.alternate_alleles.len(), 2);
+        assert!(site.samples.is_empty());
+
+        // The next record starts right after the skipped samples.
+        let read_sample: fn(&[u8], &[u8]) -> io::Result<Genotype> =
+            |_, buf| utile::io::FromUtf8Bytes::from_bytes(buf);
+        let record: Record<Genotype> = read_record(&mut buf, 2, &mut cursor, read_sample)
+            .unwrap()
+            .unwrap();
+        assert_eq!(record.id, site.id);
+        assert_eq!(record.samples.len(), 2);
+
+        assert!(read_site(&mut buf, &mut cursor).unwrap().is_none());
+    }
+
+    #[test]
+    fn subset() {
+        let line = "chr1\t100\t.\tA\tG\t.\tPASS\t.\tGT\t0|0\t0|1\t1|0\t1|1\n";
+        let mut cursor = Cursor::new(line.repeat(2));
+        let mut buf = vec![];
+        let read_sample: fn(&[u8], &[u8]) -> io::Result<Genotype> =
+            |_, buf| utile::io::FromUtf8Bytes::from_bytes(buf);
+        let dosages = |record: Record<Genotype>| -> Vec<u8> {
+            record.samples.iter().map(|g| g.dosage(1)).collect()
+        };
+
+        let subset = SampleSubset::new(&[3, 1], 4).unwrap();
+        let record = read_record_subset(&mut buf, 4, &subset, &mut cursor, read_sample)
+            .unwrap()
+            .unwrap();
+        assert_eq!(dosages(record), [2, 1]);
+
+        // Stopping early still moves on to the next record.
+        let subset = SampleSubset::new(&[0], 4).unwrap();
+        let record = read_record_subset(&mut buf, 4, &subset, &mut cursor, read_sample)
+            .unwrap()
+            .unwrap();
+        assert_eq!(dosages(record), [0]);
+
+        assert!(
+            read_record_subset(&mut buf, 4, &subset, &mut cursor, read_sample)
+                .unwrap()
+                .is_none()
+        );
+
+        for invalid in [&[1, 3, 1][..], &[0, 4]] {
+            let error = SampleSubset::new(invalid, 4).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        }
+        assert!(SampleSubset::new(&[], 0).is_ok());
+    }
+}
@@ -1,3 +1,5 @@
+use std::{fmt, str::FromStr};
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -14,9 +16,15 @@ pub struct Pedigree {
     #[serde(rename = "Sex")]
     pub sex: Sex,
     #[serde(rename = "Population")]
-    pub population: String,
+    pub population: Population,
     #[serde(rename = "Superpopulation")]
-    pub superpopulation: String,
+    pub superpopulation: Superpopulation,
+}
+impl Pedigree {
+    /// Whether a parent is listed (`0` means unknown), i.e. the sample is the child of a trio or duo.
+    pub fn has_parents(&self) -> bool {
+        self.father_id != "0" || self.mother_id != "0"
+    }
 }
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[derive(Serialize, Deserialize)]
@@ -26,3 +34,232 @@ pub enum Sex {
     #[serde(rename = "2")]
     Female,
 }
+/// The 26 populations sampled by the 1000 Genomes Project, named by their code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Serialize, Deserialize)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum Population {
+    /// Yoruba in Ibadan, Nigeria.
+    YRI,
+    /// Luhya in Webuye, Kenya.
+    LWK,
+    /// Gambian in Western Division, The Gambia.
+    GWD,
+    /// Mende in Sierra Leone.
+    MSL,
+    /// Esan in Nigeria.
+    ESN,
+    /// Americans of African Ancestry in SW USA.
+    ASW,
+    /// African Caribbeans in Barbados.
+    ACB,
+    /// Mexican Ancestry from Los Angeles, USA.
+    MXL,
+    /// Puerto Ricans from Puerto Rico.
+    PUR,
+    /// Colombians from Medellin, Colombia.
+    CLM,
+    /// Peruvians from Lima, Peru.
+    PEL,
+    /// Han Chinese in Beijing, China.
+    CHB,
+    /// Japanese in Tokyo, Japan.
+    JPT,
+    /// Southern Han Chinese.
+    CHS,
+    /// Chinese Dai in Xishuangbanna, China.
+    CDX,
+    /// Kinh in Ho Chi Minh City, Vietnam.
+    KHV,
+    /// Utah Residents (CEPH) with Northern and Western European Ancestry.
+    CEU,
+    /// Toscani in Italia.
+    TSI,
+    /// Finnish in Finland.
+    FIN,
+    /// British in England and Scotland.
+    GBR,
+    /// Iberian Population in Spain.
+    IBS,
+    /// Gujarati Indian from Houston, Texas.
+    GIH,
+    /// Punjabi from Lahore, Pakistan.
+    PJL,
+    /// Bengali from Bangladesh.
+    BEB,
+    /// Sri Lankan Tamil from the UK.
+    STU,
+    /// Indian Telugu from the UK.
+    ITU,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Serialize, Deserialize)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum Superpopulation {
+    /// African.
+    AFR,
+    /// Admixed American.
+    AMR,
+    /// East Asian.
+    EAS,
+    /// European.
+    EUR,
+    /// South Asian.
+    SAS,
+}
+
+impl Population {
+    pub const ALL: [Self; 26] = [
+        Self::YRI,
+        Self::LWK,
+        Self::GWD,
+        Self::MSL,
+        Self::ESN,
+        Self::ASW,
+        Self::ACB,
+        Self::MXL,
+        Self::PUR,
+        Self::CLM,
+        Self::PEL,
+        Self::CHB,
+        Self::JPT,
+        Self::CHS,
+        Self::CDX,
+        Self::KHV,
+        Self::CEU,
+        Self::TSI,
+        Self::FIN,
+        Self::GBR,
+        Self::IBS,
+        Self::GIH,
+        Self::PJL,
+        Self::BEB,
+        Self::STU,
+        Self::ITU,
+    ];
+
+    pub fn superpopulation(self) -> Superpopulation {
+        match self {
+            Self::YRI | Self::LWK | Self::GWD | Self::MSL | Self::ESN | Self::ASW | Self::ACB => {
+                Superpopulation::AFR
+            }
+            Self::MXL | Self::PUR | Self::CLM | Self::PEL => Superpopulation::AMR,
+            Self::CHB | Self::JPT | Self::CHS | Self::CDX | Self::KHV => Superpopulation::EAS,
+            Self::CEU | Self::TSI | Self::FIN | Self::GBR | Self::IBS => Superpopulation::EUR,
+            Self::GIH | Self::PJL | Self::BEB | Self::STU | Self::ITU => Superpopulation::SAS,
+        }
+    }
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::YRI => "YRI",
+            Self::LWK => "LWK",
+            Self::GWD => "GWD",
+            Self::MSL => "MSL",
+            Self::ESN => "ESN",
+            Self::ASW => "ASW",
+            Self::ACB => "ACB",
+            Self::MXL => "MXL",
+            Self::PUR => "PUR",
+            Self::CLM => "CLM",
+            Self::PEL => "PEL",
+            Self::CHB => "CHB",
+            Self::JPT => "JPT",
+            Self::CHS => "CHS",
+            Self::CDX => "CDX",
+            Self::KHV => "KHV",
+            Self::CEU => "CEU",
+            Self::TSI => "TSI",
+            Self::FIN => "FIN",
+            Self::GBR => "GBR",
+            Self::IBS => "IBS",
+            Self::GIH => "GIH",
+            Self::PJL => "PJL",
+            Self::BEB => "BEB",
+            Self::STU => "STU",
+            Self::ITU => "ITU",
+        }
+    }
+}
+impl Superpopulation {
+    pub const ALL: [Self; 5] = [Self::AFR, Self::AMR, Self::EAS, Self::EUR, Self::SAS];
+
+    pub fn populations(self) -> impl Iterator<Item = Population> {
+        Population::ALL
+            .into_iter()
+            .filter(move |p| p.superpopulation() == self)
+    }
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::AFR => "AFR",
+            Self::AMR => "AMR",
+            Self::EAS => "EAS",
+            Self::EUR => "EUR",
+            Self::SAS => "SAS",
+        }
+    }
+}
+
+impl fmt::Display for Population {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+impl fmt::Display for Superpopulation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+impl FromStr for Population {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|p| p.code() == s)
+            .ok_or_else(|| s.to_owned())
+    }
+}
+impl FromStr for Superpopulation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|p| p.code() == s)
+            .ok_or_else(|| s.to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Pedigree, Population, Sex, Superpopulation};
+
+    #[test]
+    fn populations() {
+        assert_eq!(Population::ALL.len(), 26);
+        assert_eq!("GBR".parse(), Ok(Population::GBR));
+        assert_eq!(Population::GBR.superpopulation(), Superpopulation::EUR);
+        assert_eq!(Superpopulation::AMR.populations().count(), 4);
+        for superpopulation in Superpopulation::ALL {
+            assert_eq!(superpopulation.to_string().parse(), Ok(superpopulation));
+        }
+
+        let pedigrees: Vec<Pedigree> = csv::ReaderBuilder::new()
+            .delimiter(b' ')
+            .from_reader(
+                "SampleID FamilyID FatherID MotherID Sex Population Superpopulation
+HG00096 HG00096 0 0 1 GBR EUR
+NA19240 Y117 NA19239 NA19238 2 YRI AFR
+"
+                .as_bytes(),
+            )
+            .into_deserialize()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(pedigrees[0].sex, Sex::Male);
+        assert_eq!(pedigrees[0].population, Population::GBR);
+        assert_eq!(pedigrees[0].superpopulation, Superpopulation::EUR);
+        assert!(!pedigrees[0].has_parents());
+        assert!(pedigrees[1].has_parents());
+    }
+}
@@ -0,0 +1,325 @@
+//! The phase 3 release (2504 samples) on GRCh37, for data that isn't on GRCh38
+//! (e.g. Pan-UKBB), so it can be used without a liftover.
+//!
+//! Mirrors [Genomes1000Fs](crate::Genomes1000Fs), with records on [GRCh37Contig].
+//! The phase 3 VCFs only have a `GT` format field, and multi-allelic sites are mostly split
+//! over several records already.
+//!
+//! [SimplifiedRecord](crate::simplified::SimplifiedRecord) (and what builds on it, e.g. the
+//! allele counts and exports) is GRCh38 only, so [Phase3Fs::query_normalized] returns
+//! the equivalent biallelic [Record]s instead.
+
+use std::{collections::BTreeMap, io};
+
+use biocore::{location::ContigRange, vcf::IndexedVcfReader};
+use either::Either;
+use resource::{RawResourceExt, fs::FsCache};
+use utile::io::FromUtf8Bytes;
+
+use crate::{
+    Genotype, Record,
+    contig::GRCh37Contig,
+    load_pedigree, match_sample_ploidy, parse,
+    pedigree::{Pedigree, Sex},
+    source::Genomes1000Resource,
+};
+
+#[derive(Debug)]
+pub struct Phase3Fs {
+    sample_names: Vec<String>,
+    pedigrees: BTreeMap<String, Pedigree>,
+    readers: BTreeMap<GRCh37Contig, IndexedVcfReader<std::fs::File>>,
+}
+
+impl Phase3Fs {
+    pub async fn new() -> io::Result<Self> {
+        Self::new_with_cache(&FsCache::global()).await
+    }
+    pub fn sample_names(&self) -> &[String] {
+        &self.sample_names
+    }
+    /// All phase 3 samples are part of the high-coverage pedigree.
+    pub fn pedigree(&self, id: &str) -> Option<&Pedigree> {
+        self.pedigrees.get(id)
+    }
+    pub fn pedigrees(&self) -> impl Iterator<Item = &Pedigree> {
+        self.pedigrees.values()
+    }
+    /// The contigs for which a VCF is available.
+    pub fn contigs(&self) -> impl Iterator<Item = GRCh37Contig> + '_ {
+        self.readers.keys().copied()
+    }
+    pub async fn new_with_cache(cache: &FsCache) -> io::Result<Self> {
+        let mut sample_names = None;
+
+        let mut readers = BTreeMap::new();
+        for contig in GRCh37Contig::CHROMOSOMES {
+            let (Some(data), Some(index)) = (
+                Genomes1000Resource::old_phase_3_contig_vcf(contig),
+                Genomes1000Resource::old_phase_3_contig_vcf_index(contig),
+            ) else {
+                continue;
+            };
+            let data = data
+                .log_progress()
+                .with_fs_cache(cache)
+                .ensure_cached_async()
+                .await?;
+            let index = index
+                .log_progress()
+                .with_fs_cache(cache)
+                .ensure_cached_async()
+                .await?
+                .decompressed();
+
+            let reader = IndexedVcfReader::new(data.read()?, index.read()?)?;
+            let names: Vec<_> = reader.header().sample_names().clone().into_iter().collect();
+
+            // chrY and chrMT come with their own sample lists.
+            if contig != GRCh37Contig::Y && contig != GRCh37Contig::MT {
+                if let Some(sample_names) = &sample_names {
+                    assert_eq!(sample_names, &names);
+                } else {
+                    sample_names = Some(names);
+                }
+            }
+
+            readers.insert(contig, reader);
+        }
+
+        let pedigrees = load_pedigree(
+            Genomes1000Resource::high_coverage_pedigree()
+                .log_progress()
+                .with_fs_cache(cache)
+                .ensure_cached_async()
+                .await?,
+        )
+        .await?
+        .into_iter()
+        .map(|p| (p.id.clone(), p))
+        .collect();
+
+        Ok(Self {
+            sample_names: sample_names.unwrap(),
+            pedigrees,
+            readers,
+        })
+    }
+
+    /// Records on chrY and chrMT only have the samples listed in their own VCF header,
+    /// see [Self::contig_sample_names].
+    pub fn query(
+        &mut self,
+        at: &ContigRange<GRCh37Contig>,
+    ) -> io::Result<impl Iterator<Item = io::Result<Record<Genotype, GRCh37Contig>>> + use<'_>>
+    {
+        let reader = self.reader(at.contig)?;
+        let sample_count = reader.header().sample_names().len();
+
+        let mut buf = vec![];
+        Ok(reader.query_raw(at)?.map(move |r| {
+            let r = r?;
+            Ok(parse::read_record(
+                &mut buf,
+                sample_count,
+                &mut std::io::Cursor::new(r),
+                read_sample,
+            )?
+            .unwrap())
+        }))
+    }
+    /// Like [Self::query], but only parses the genotypes of `samples` (indices into
+    /// [Self::contig_sample_names]), which are returned in that order.
+    /// Repeated or out of range samples are an [io::ErrorKind::InvalidInput] error.
+    pub fn query_samples(
+        &mut self,
+        at: &ContigRange<GRCh37Contig>,
+        samples: &[usize],
+    ) -> io::Result<impl Iterator<Item = io::Result<Record<Genotype, GRCh37Contig>>> + use<'_>>
+    {
+        let reader = self.reader(at.contig)?;
+        let sample_count = reader.header().sample_names().len();
+        let subset = parse::SampleSubset::new(samples, sample_count)?;
+
+        let mut buf = vec![];
+        Ok(reader.query_raw(at)?.map(move |r| {
+            let r = r?;
+            Ok(parse::read_record_subset(
+                &mut buf,
+                sample_count,
+                &subset,
+                &mut std::io::Cursor::new(r),
+                read_sample,
+            )?
+            .unwrap())
+        }))
+    }
+    /// Like [Self::query], split into biallelic records with sequence alleles.
+    ///
+    /// On chrX and chrY, calls are matched to each sample's ploidy,
+    /// see `with_sample_ploidy` on [Record].
+    pub fn query_normalized(
+        &mut self,
+        at: &ContigRange<GRCh37Contig>,
+    ) -> io::Result<impl Iterator<Item = io::Result<Record<Genotype, GRCh37Contig>>> + use<'_>>
+    {
+        let sexes = self.contig_sample_sexes(at.contig);
+        Ok(normalize(self.query(at)?, sexes))
+    }
+    /// Like [Self::query_normalized], with the genotypes of `samples` only,
+    /// see [Self::query_samples].
+    pub fn query_normalized_samples(
+        &mut self,
+        at: &ContigRange<GRCh37Contig>,
+        samples: &[usize],
+    ) -> io::Result<impl Iterator<Item = io::Result<Record<Genotype, GRCh37Contig>>> + use<'_>>
+    {
+        let sexes = self.contig_sample_sexes(at.contig);
+        let sexes = samples
+            .iter()
+            .map(|&i| sexes.get(i).copied().flatten())
+            .collect();
+        Ok(normalize(self.query_samples(at, samples)?, sexes))
+    }
+    /// The samples of the records on `contig`, the same as [Self::sample_names]
+    /// except for chrY and chrMT.
+    pub fn contig_sample_names(&self, contig: GRCh37Contig) -> Option<Vec<String>> {
+        let reader = self.readers.get(&contig)?;
+        Some(reader.header().sample_names().iter().cloned().collect())
+    }
+
+    fn contig_sample_sexes(&self, contig: GRCh37Contig) -> Vec<Option<Sex>> {
+        self.contig_sample_names(contig)
+            .unwrap_or_default()
+            .iter()
+            .map(|name| self.pedigree(name).map(|p| p.sex))
+            .collect()
+    }
+
+    fn reader(&mut self, contig: GRCh37Contig) -> io::Result<&mut IndexedVcfReader<std::fs::File>> {
+        self.readers.get_mut(&contig).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No phase 3 VCF for contig {contig}"),
+            )
+        })
+    }
+}
+
+impl Record<Genotype, GRCh37Contig> {
+    /// Matches each call to the ploidy expected for the sample at this position
+    /// (see [Genotype::with_ploidy]), given the sex of each sample in order.
+    ///
+    /// Only affects chrX and chrY, where males are haploid outside the pseudoautosomal regions.
+    /// Samples of unknown sex are left as they are.
+    pub fn with_sample_ploidy(mut self, sexes: &[Option<Sex>]) -> Self {
+        if self.contig != GRCh37Contig::X && self.contig != GRCh37Contig::Y {
+            return self;
+        }
+        let at = self.at();
+        match_sample_ploidy(&mut self.samples, sexes, |sex| {
+            at.contig.ploidy_at(at.at, sex)
+        });
+        self
+    }
+}
+
+fn normalize(
+    records: impl Iterator<Item = io::Result<Record<Genotype, GRCh37Contig>>>,
+    sexes: Vec<Option<Sex>>,
+) -> impl Iterator<Item = io::Result<Record<Genotype, GRCh37Contig>>> {
+    records
+        .filter_map(move |r| match r {
+            Ok(r) => Some(Ok(r.with_sample_ploidy(&sexes).normalized()?)),
+            Err(e) => Some(Err(e)),
+        })
+        .flat_map(|r| match r {
+            Ok(r) => Either::Left(r.split().map(Ok)),
+            Err(e) => Either::Right([Err(e)].into_iter()),
+        })
+}
+
+pub async fn load_contig(
+    c: GRCh37Contig,
+) -> io::Result<(
+    Vec<String>,
+    impl Iterator<Item = io::Result<Record<Genotype, GRCh37Contig>>>,
+)> {
+    let resource = Genomes1000Resource::old_phase_3_contig_vcf(c)
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No phase 3 VCF for contig {c}"),
+            )
+        })?
+        .log_progress()
+        .with_global_fs_cache()
+        .ensure_cached_async()
+        .await?
+        .decompressed()
+        .buffered();
+
+    parse::parse(resource.read()?, read_sample)
+}
+
+fn read_sample(_format: &[u8], buf: &[u8]) -> io::Result<Genotype> {
+    Genotype::from_bytes(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::{Genotype, HaploidGenotype, Record, contig::GRCh37Contig, parse, pedigree::Sex};
+
+    use super::{normalize, read_sample};
+
+    #[test]
+    fn records() {
+        let line = "X\t60100\trs1\tA\tG\t100\tPASS\tAC=1\tGT\t0|1\t1\n";
+        let mut buf = vec![];
+        let record: Record<Genotype, GRCh37Contig> =
+            parse::read_record(&mut buf, 2, &mut Cursor::new(line), read_sample)
+                .unwrap()
+                .unwrap();
+        assert_eq!(record.contig, GRCh37Contig::X);
+        assert_eq!(record.at().at, 60_099);
+        assert_eq!(record.samples[0].dosage(1), 1);
+        assert_eq!(
+            record.samples[1],
+            Genotype::Haploid(HaploidGenotype { value: 1 })
+        );
+    }
+
+    #[test]
+    fn normalized() {
+        let record = |line: &str| -> Record<Genotype, GRCh37Contig> {
+            parse::read_record(&mut vec![], 3, &mut Cursor::new(line), read_sample)
+                .unwrap()
+                .unwrap()
+        };
+        let sexes = vec![Some(Sex::Male), Some(Sex::Female), None];
+        let records = [
+            // Outside PAR1 (X:60001-2699520 on GRCh37), where males are haploid.
+            record("X\t5000000\trs1\tA\tG\t100\tPASS\t.\tGT\t0|1\t1|1\t0|1\n"),
+            record("X\t5000001\trs2\tA\tG\t100\tPASS\t.\tGT\t1\t0|1\t1\n"),
+            record("X\t100000\trs3\tA\tG\t100\tPASS\t.\tGT\t1|1\t0|1\t0|0\n"),
+        ];
+        let normalized: Vec<_> = normalize(records.into_iter().map(Ok), sexes)
+            .map(|r| r.unwrap())
+            .collect();
+
+        assert_eq!(normalized.len(), 3);
+        let dosages = |r: &Record<Genotype, GRCh37Contig>| {
+            r.samples.iter().map(|g| g.dosage(1)).collect::<Vec<_>>()
+        };
+        assert_eq!(normalized[0].samples[0], Genotype::Missing);
+        assert_eq!(dosages(&normalized[0]), [0, 2, 1]);
+        assert_eq!(
+            normalized[1].samples[0],
+            Genotype::Haploid(HaploidGenotype { value: 1 })
+        );
+        assert_eq!(dosages(&normalized[1]), [1, 1, 1]);
+        assert_eq!(dosages(&normalized[2]), [2, 1, 0]);
+    }
+}
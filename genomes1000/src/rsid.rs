@@ -0,0 +1,341 @@
+//! An rsID → position index for the 1000 Genomes VCFs, see [crate::Genomes1000Fs::query_rsid].
+//!
+//! The high-coverage VCFs don't list rsIDs (their `ID` column is `.`), so the index is built
+//! from the GRCh38 dbSNP VCF ([hail::dbsnp]) by scanning it once. It is cached as a compact
+//! binary file (little-endian): the magic, a version, a table of contig names, then the entries
+//! sorted by rs number, each a `u64` rs number, a `u16` index into the contig table,
+//! and a `u32` 0-based position.
+
+use std::io::{self, BufRead, BufWriter, Write};
+
+use biocore::location::ContigPosition;
+use hail::dbsnp::{self, DbSnpResource};
+use resource::{
+    RawResource, RawResourceExt,
+    fs::{FsCache, FsCacheEntry},
+};
+use utile::io::invalid_data;
+
+use crate::GRCh38Contig;
+
+const MAGIC: &[u8; 8] = b"RSIDINDX";
+const VERSION: u32 = 1;
+const ENTRY_LEN: usize = 8 + 2 + 4;
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RsidIndex {
+    contigs: Vec<GRCh38Contig>,
+    /// Sorted by rs number, then contig and position.
+    entries: Vec<Entry>,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Entry {
+    rs: u64,
+    contig: u16,
+    at: u32,
+}
+
+/// The number of an rsID (`rs42` → `42`).
+pub fn parse_rsid(rsid: &str) -> Option<u64> {
+    rsid.strip_prefix("rs")?.parse().ok()
+}
+
+impl RsidIndex {
+    /// Scans a (decompressed) dbSNP VCF, see [DbSnpResource::grch38_vcf].
+    ///
+    /// Records off the primary assembly are skipped.
+    pub fn from_dbsnp(vcf: impl BufRead) -> io::Result<Self> {
+        let mut index = Self::default();
+        for record in dbsnp::read_vcf::<hail::contig::GRCh38Contig>(vcf) {
+            let record = record?;
+            let contig = record.at.contig.static_value();
+            let contig = GRCh38Contig::new(contig)
+                .ok_or_else(|| invalid_data(format!("Unknown dbSNP contig: {contig}")))?;
+            let at = u32::try_from(record.at.at)
+                .map_err(|_| invalid_data(format!("Invalid dbSNP position: {}", record.at.at)))?;
+            let contig = index.contig_index(contig)?;
+            index.entries.push(Entry {
+                rs: record.rsid.get(),
+                contig,
+                at,
+            });
+        }
+        index.entries.sort_unstable();
+        index.entries.dedup();
+        Ok(index)
+    }
+    /// Scans the `CHROM`, `POS`, and `ID` columns of (decompressed) VCFs that list rsIDs.
+    ///
+    /// Records list several IDs separated by `;`, anything that is not an rsID is skipped.
+    pub fn from_vcfs(vcfs: impl IntoIterator<Item = io::Result<impl BufRead>>) -> io::Result<Self> {
+        let mut index = Self::default();
+        let mut line = vec![];
+        for vcf in vcfs {
+            let mut vcf = vcf?;
+            loop {
+                line.clear();
+                if vcf.read_until(b'\n', &mut line)? == 0 {
+                    break;
+                }
+                if line.starts_with(b"#") {
+                    continue;
+                }
+                index.push_line(&line)?;
+            }
+        }
+        index.entries.sort_unstable();
+        index.entries.dedup();
+        Ok(index)
+    }
+    fn push_line(&mut self, line: &[u8]) -> io::Result<()> {
+        let mut fields = line.splitn(4, |b| *b == b'\t');
+        let (Some(contig), Some(position), Some(ids)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            return Err(invalid_data("VCF record has fewer than three columns"));
+        };
+        let ids = std::str::from_utf8(ids).map_err(invalid_data)?;
+        let mut rsids = ids.split(';').filter_map(parse_rsid).peekable();
+        if rsids.peek().is_none() {
+            return Ok(());
+        }
+
+        let contig = std::str::from_utf8(contig)
+            .ok()
+            .and_then(GRCh38Contig::new)
+            .ok_or_else(|| invalid_data(format!("Unknown contig in VCF: {contig:?}")))?;
+        let position: u32 = std::str::from_utf8(position)
+            .ok()
+            .and_then(|p| p.parse().ok())
+            .ok_or_else(|| invalid_data(format!("Invalid VCF position: {position:?}")))?;
+        let contig = self.contig_index(contig)?;
+
+        for rs in rsids {
+            self.entries.push(Entry {
+                rs,
+                contig,
+                at: position.saturating_sub(1),
+            });
+        }
+        Ok(())
+    }
+    fn contig_index(&mut self, contig: GRCh38Contig) -> io::Result<u16> {
+        let i = match self.contigs.iter().position(|c| *c == contig) {
+            Some(i) => i,
+            None => {
+                self.contigs.push(contig);
+                self.contigs.len() - 1
+            }
+        };
+        u16::try_from(i).map_err(|_| invalid_data("Too many contigs for the rsID index"))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The (0-based) positions listing `rsid`, usually at most one.
+    pub fn get(&self, rsid: &str) -> impl Iterator<Item = ContigPosition<GRCh38Contig>> + '_ {
+        let entries = match parse_rsid(rsid) {
+            Some(rs) => {
+                let start = self.entries.partition_point(|e| e.rs < rs);
+                let end = self.entries.partition_point(|e| e.rs <= rs);
+                &self.entries[start..end]
+            }
+            None => &[],
+        };
+        entries.iter().map(|e| ContigPosition {
+            contig: self.contigs[usize::from(e.contig)],
+            at: u64::from(e.at),
+        })
+    }
+
+    pub fn write(&self, mut w: impl Write) -> io::Result<()> {
+        w.write_all(MAGIC)?;
+        w.write_all(&VERSION.to_le_bytes())?;
+        w.write_all(&u16::try_from(self.contigs.len()).unwrap().to_le_bytes())?;
+        for contig in &self.contigs {
+            let name = contig.as_ref().as_bytes();
+            w.write_all(&[u8::try_from(name.len()).unwrap()])?;
+            w.write_all(name)?;
+        }
+        w.write_all(&(self.entries.len() as u64).to_le_bytes())?;
+        for e in &self.entries {
+            w.write_all(&e.rs.to_le_bytes())?;
+            w.write_all(&e.contig.to_le_bytes())?;
+            w.write_all(&e.at.to_le_bytes())?;
+        }
+        w.flush()
+    }
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        let truncated = || invalid_data("rsID index is truncated");
+        let take = |at: &mut usize, len: usize| {
+            let v = bytes
+                .get(*at..at.saturating_add(len))
+                .ok_or_else(truncated)?;
+            *at += len;
+            Ok::<_, io::Error>(v)
+        };
+
+        let mut at = 0;
+        if take(&mut at, MAGIC.len())? != MAGIC {
+            return Err(invalid_data("Not an rsID index"));
+        }
+        let version = u32::from_le_bytes(take(&mut at, 4)?.try_into().unwrap());
+        if version != VERSION {
+            return Err(invalid_data(format!(
+                "Unsupported rsID index version: {version}"
+            )));
+        }
+
+        let contig_count = u16::from_le_bytes(take(&mut at, 2)?.try_into().unwrap());
+        let mut contigs = Vec::with_capacity(contig_count.into());
+        for _ in 0..contig_count {
+            let len = take(&mut at, 1)?[0];
+            let name = take(&mut at, len.into())?;
+            let contig = std::str::from_utf8(name)
+                .ok()
+                .and_then(GRCh38Contig::new)
+                .ok_or_else(|| invalid_data(format!("Unknown contig in rsID index: {name:?}")))?;
+            contigs.push(contig);
+        }
+
+        let entry_count =
+            usize::try_from(u64::from_le_bytes(take(&mut at, 8)?.try_into().unwrap()))
+                .map_err(|_| invalid_data("rsID index is too large"))?;
+        let entries = take(&mut at, entry_count.saturating_mul(ENTRY_LEN))?
+            .chunks_exact(ENTRY_LEN)
+            .map(|e| Entry {
+                rs: u64::from_le_bytes(e[..8].try_into().unwrap()),
+                contig: u16::from_le_bytes(e[8..10].try_into().unwrap()),
+                at: u32::from_le_bytes(e[10..].try_into().unwrap()),
+            })
+            .collect::<Vec<_>>();
+
+        if entries
+            .iter()
+            .any(|e| usize::from(e.contig) >= contigs.len())
+        {
+            return Err(invalid_data("rsID index refers to an unknown contig"));
+        }
+        if !entries.is_sorted() {
+            return Err(invalid_data("rsID index entries must be sorted"));
+        }
+
+        Ok(Self { contigs, entries })
+    }
+
+    pub fn load(resource: impl RawResource) -> io::Result<Self> {
+        Self::from_bytes(&resource.read_vec()?)
+    }
+    /// Loads the index from the cache, building and storing it first if needed.
+    pub fn load_or_build(
+        entry: &FsCacheEntry,
+        build: impl FnOnce() -> io::Result<Self>,
+    ) -> io::Result<Self> {
+        if !entry.try_exists()? {
+            log::info!("[1000 Genomes][rsID] Building rsID index at {entry}");
+            let index = build()?;
+            entry.write_file_with(|file| index.write(BufWriter::new(file)))?;
+            return Ok(index);
+        }
+
+        Self::load(entry.clone())
+    }
+    /// The conventional cache location for the index of the GRCh38 dbSNP VCF.
+    pub fn cache_entry(cache: &FsCache) -> FsCacheEntry {
+        cache.entry("genomes1000/rsid/dbsnp_grch38.rsidx")
+    }
+    /// Builds the index from the GRCh38 dbSNP VCF, downloading it to `cache` if needed.
+    ///
+    /// The VCF is tens of gigabytes.
+    pub fn build_from_dbsnp(cache: &FsCache) -> io::Result<Self> {
+        let vcf = DbSnpResource::grch38_vcf()
+            .log_progress()
+            .with_fs_cache(cache)
+            .ensure_cached()?
+            .decompressed()
+            .buffered()
+            .read()?;
+        Self::from_dbsnp(vcf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use biocore::location::ContigPosition;
+
+    use super::{RsidIndex, parse_rsid};
+    use crate::GRCh38Contig;
+
+    const VCF: &str = "\
+##fileformat=VCFv4.2
+#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\tFORMAT\tHG00096
+chr1\t100\trs42\tA\tG\t.\tPASS\t.\tGT\t0|1
+chr1\t200\t.\tC\tT\t.\tPASS\t.\tGT\t0|0
+chr2\t50\trs7;esv1\tG\tA\t.\tPASS\t.\tGT\t1|1
+chrX\t10\trs42\tT\tC\t.\tPASS\t.\tGT\t0|1
+";
+
+    #[test]
+    fn round_trip() {
+        assert_eq!(parse_rsid("rs42"), Some(42));
+        assert_eq!(parse_rsid("esv1"), None);
+
+        let index = RsidIndex::from_vcfs([Ok(VCF.as_bytes())]).unwrap();
+        assert_eq!(index.len(), 3);
+        assert_eq!(
+            index.get("rs42").collect::<Vec<_>>(),
+            [
+                ContigPosition {
+                    contig: GRCh38Contig::CHR1,
+                    at: 99
+                },
+                ContigPosition {
+                    contig: GRCh38Contig::X,
+                    at: 9
+                },
+            ]
+        );
+        assert_eq!(index.get("rs7").count(), 1);
+        assert_eq!(index.get("rs8").count(), 0);
+        assert_eq!(index.get("42").count(), 0);
+
+        let mut bytes = vec![];
+        index.write(&mut bytes).unwrap();
+        assert_eq!(RsidIndex::from_bytes(&bytes).unwrap(), index);
+        assert!(RsidIndex::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn dbsnp() {
+        let vcf = "##fileformat=VCFv4.2\n\
+            ##reference=GRCh38.p14\n\
+            #CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\n\
+            NC_000001.11\t10001\trs1570391677\tT\tA,C\t.\t.\tRS=1570391677;dbSNPBuildID=154;VC=SNV\n\
+            NT_187361.1\t100\trs1\tA\tG\t.\t.\tRS=1\n\
+            NC_000023.11\t2781480\trs6655397\tG\tN,T\t.\t.\tRS=6655397;dbSNPBuildID=116;SSR=0;VC=SNV\n";
+
+        let index = RsidIndex::from_dbsnp(vcf.as_bytes()).unwrap();
+        assert_eq!(index.len(), 2);
+        assert_eq!(
+            index.get("rs1570391677").collect::<Vec<_>>(),
+            [ContigPosition {
+                contig: GRCh38Contig::CHR1,
+                at: 10000
+            }]
+        );
+        assert_eq!(
+            index.get("rs6655397").collect::<Vec<_>>(),
+            [ContigPosition {
+                contig: GRCh38Contig::X,
+                at: 2781479
+            }]
+        );
+        // Off the primary assembly.
+        assert_eq!(index.get("rs1").count(), 0);
+    }
+}
@@ -1,4 +1,17 @@
-use biocore::{dna::DnaSequence, location::ContigPosition};
+use std::{
+    fmt::Write as _,
+    io::{self, Write},
+};
+
+use biocore::{
+    dna::DnaSequence,
+    genome::Contig,
+    location::ContigPosition,
+    mutation::{
+        mnv::{self, MultiNucleotideVariant},
+        normalize::{ReferenceSequence, Variant},
+    },
+};
 
 use crate::{GRCh38Contig, Genotype};
 
@@ -15,6 +28,19 @@ pub struct SimplifiedRecord {
     pub samples: Vec<Genotype>,
 }
 
+/// Writes [SimplifiedRecord]s as a VCF with a single `GT` format field.
+///
+/// Records must be sorted (as returned by [crate::Genomes1000Fs::query_simplified]),
+/// and on one of the contigs given for the header.
+#[derive(Debug)]
+pub struct SimplifiedVcfWriter<W> {
+    writer: W,
+    contigs: Vec<GRCh38Contig>,
+    sample_count: usize,
+    last: Option<ContigPosition<GRCh38Contig>>,
+    line: String,
+}
+
 impl SimplifiedRecord {
     pub fn at(&self) -> ContigPosition<GRCh38Contig> {
         ContigPosition {
@@ -22,4 +48,219 @@ impl SimplifiedRecord {
             at: self.position - 1,
         }
     }
+    pub fn variant(&self) -> Variant<GRCh38Contig> {
+        Variant {
+            at: self.at(),
+            reference: self.reference_allele.clone(),
+            alternate: self.alternate_allele.clone(),
+        }
+    }
+}
+
+impl<W: Write> SimplifiedVcfWriter<W> {
+    /// Writes the header straight away.
+    pub fn new(
+        mut writer: W,
+        sample_names: &[String],
+        contigs: impl IntoIterator<Item = GRCh38Contig>,
+    ) -> io::Result<Self> {
+        let contigs: Vec<_> = contigs.into_iter().collect();
+
+        writeln!(writer, "##fileformat=VCFv4.2")?;
+        writeln!(
+            writer,
+            "##FILTER=<ID=PASS,Description=\"All filters passed\">"
+        )?;
+        for contig in &contigs {
+            writeln!(writer, "##contig=<ID={contig},length={}>", contig.size())?;
+        }
+        writeln!(
+            writer,
+            "##FORMAT=<ID=GT,Number=1,Type=String,Description=\"Genotype\">"
+        )?;
+        write!(
+            writer,
+            "#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\tFORMAT"
+        )?;
+        for name in sample_names {
+            write!(writer, "\t{name}")?;
+        }
+        writeln!(writer)?;
+
+        Ok(Self {
+            writer,
+            contigs,
+            sample_count: sample_names.len(),
+            last: None,
+            line: String::new(),
+        })
+    }
+
+    pub fn write_record(&mut self, record: &SimplifiedRecord) -> io::Result<()> {
+        let invalid = |e: String| io::Error::new(io::ErrorKind::InvalidInput, e);
+        let at = record.at();
+        if !self.contigs.contains(&record.contig) {
+            return Err(invalid(format!("{} is not in the header", record.contig)));
+        }
+        if self.last.as_ref().is_some_and(|last| *last > at) {
+            return Err(invalid(format!("Record at {at:?} is out of order")));
+        }
+        if record.samples.len() != self.sample_count {
+            return Err(invalid(format!(
+                "Expected {} samples, found {}",
+                self.sample_count,
+                record.samples.len()
+            )));
+        }
+        // VCF alleles can't be empty, and there's no padding base at hand to add.
+        if record.reference_allele.is_empty() || record.alternate_allele.is_empty() {
+            return Err(invalid(format!("Record at {at:?} has an empty allele")));
+        }
+
+        self.line.clear();
+        let line = &mut self.line;
+        write!(
+            line,
+            "{}\t{}\t.\t{}\t{}\t",
+            record.contig, record.position, record.reference_allele, record.alternate_allele
+        )
+        .unwrap();
+        match record.quality {
+            Some(quality) => write!(line, "{quality}").unwrap(),
+            None => line.push('.'),
+        }
+        let filter = if record.filter.is_empty() {
+            "."
+        } else {
+            &record.filter
+        };
+        write!(line, "\t{filter}\t.\tGT").unwrap();
+        for sample in &record.samples {
+            write!(line, "\t{sample}").unwrap();
+        }
+        line.push('\n');
+
+        self.writer.write_all(line.as_bytes())?;
+        self.last = Some(at);
+        Ok(())
+    }
+    pub fn write_records<'a>(
+        &mut self,
+        records: impl IntoIterator<Item = &'a SimplifiedRecord>,
+    ) -> io::Result<()> {
+        for record in records {
+            self.write_record(record)?;
+        }
+        Ok(())
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+impl<W: Write> SimplifiedVcfWriter<noodles::bgzf::io::Writer<W>> {
+    /// Writes BGZF-compressed output, which can be indexed with tabix.
+    pub fn new_bgzf(
+        writer: W,
+        sample_names: &[String],
+        contigs: impl IntoIterator<Item = GRCh38Contig>,
+    ) -> io::Result<Self> {
+        Self::new(
+            noodles::bgzf::io::Writer::new(writer),
+            sample_names,
+            contigs,
+        )
+    }
+    /// Writes the BGZF EOF marker, this must be called to produce a valid file.
+    pub fn finish(self) -> io::Result<W> {
+        self.writer.finish()
+    }
+}
+
+/// Recomposes the MNVs carried by a sample, using the phased genotypes.
+///
+/// Records must be sorted, unphased genotypes are ignored. See [mnv::group_phased]
+/// for `max_distance`.
+pub fn phased_mnvs<'a>(
+    records: impl IntoIterator<Item = &'a SimplifiedRecord>,
+    sample: usize,
+    max_distance: u64,
+    genome: &mut impl ReferenceSequence<GRCh38Contig>,
+) -> Result<Vec<MultiNucleotideVariant<GRCh38Contig>>, mnv::MnvError> {
+    let mut haplotypes: [Vec<Variant<GRCh38Contig>>; 2] = Default::default();
+    for record in records {
+        let Some(alleles) = record.samples[sample].haplotypes() else {
+            continue;
+        };
+        for (haplotype, allele) in haplotypes.iter_mut().zip(alleles) {
+            if allele == 1 {
+                haplotype.push(record.variant());
+            }
+        }
+    }
+
+    let mut mnvs = vec![];
+    for haplotype in haplotypes {
+        mnvs.extend(mnv::recompose_phased(haplotype, max_distance, genome)?);
+    }
+    mnvs.sort();
+    Ok(mnvs)
+}
+
+#[cfg(test)]
+mod tests {
+    use biocore::dna::DnaSequence;
+
+    use crate::{DiploidGenotype, GRCh38Contig, Genotype, GenotypePhasing};
+
+    use super::{SimplifiedRecord, SimplifiedVcfWriter};
+
+    fn record(position: u64, reference: &str, alternate: &str) -> SimplifiedRecord {
+        SimplifiedRecord {
+            contig: GRCh38Contig::CHR1,
+            position,
+            reference_allele: reference.parse::<DnaSequence>().unwrap(),
+            alternate_allele: alternate.parse::<DnaSequence>().unwrap(),
+            quality: None,
+            filter: "PASS".to_owned(),
+            samples: vec![
+                Genotype::Diploid(DiploidGenotype {
+                    left: 0,
+                    phasing: GenotypePhasing::Phased,
+                    right: 1,
+                }),
+                Genotype::Missing,
+            ],
+        }
+    }
+
+    #[test]
+    fn write() {
+        let names = ["A".to_owned(), "B".to_owned()];
+        let mut writer = SimplifiedVcfWriter::new(vec![], &names, [GRCh38Contig::CHR1]).unwrap();
+        writer.write_record(&record(100, "A", "G")).unwrap();
+        writer.write_record(&record(100, "A", "AT")).unwrap();
+        assert!(writer.write_record(&record(50, "A", "G")).is_err());
+        assert!(writer.write_record(&record(200, "A", "")).is_err());
+
+        let mut other = record(10, "A", "G");
+        other.contig = GRCh38Contig::CHR2;
+        assert!(writer.write_record(&other).is_err());
+
+        let vcf = String::from_utf8(writer.into_inner()).unwrap();
+        assert_eq!(
+            vcf.lines()
+                .filter(|l| !l.starts_with("##"))
+                .collect::<Vec<_>>(),
+            [
+                "#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\tFORMAT\tA\tB",
+                "chr1\t100\t.\tA\tG\t.\tPASS\t.\tGT\t0|1\t.",
+                "chr1\t100\t.\tA\tAT\t.\tPASS\t.\tGT\t0|1\t.",
+            ]
+        );
+        assert!(vcf.contains("##contig=<ID=chr1,length=248956422>\n"));
+    }
 }
@@ -1,9 +1,11 @@
-use resource::{RawResource, UrlResource};
+use resource::{MirroredResource, RawResource, UrlResource};
 use url::Url;
 
 use crate::contig::GRCh38Contig;
 
 const BASE_URL: &str = "https://ftp.1000genomes.ebi.ac.uk/";
+/// NCBI mirrors the `vol1/ftp` directory.
+const NCBI_MIRROR_URL: &str = "https://ftp-trace.ncbi.nih.gov/1000genomes/ftp/";
 const REFERENCE_GENOME: &str = "vol1/ftp/technical/reference/GRCh38_reference_genome/GRCh38_full_analysis_set_plus_decoy_hla.fa";
 
 // Picked the same files as:
@@ -154,8 +156,18 @@ impl Genomes1000Resource {
         Url::parse(&format!("{BASE_URL}{key}")).unwrap()
     }
 
-    fn url_resource(&self) -> UrlResource {
-        UrlResource::new(self.url()).unwrap()
+    /// The EBI origin, then its mirrors.
+    pub fn urls(&self) -> Vec<Url> {
+        let mut urls = vec![self.url()];
+        if let Some(path) = self.key.strip_prefix("vol1/ftp/") {
+            urls.push(Url::parse(&format!("{NCBI_MIRROR_URL}{path}")).unwrap());
+        }
+        urls
+    }
+
+    fn url_resource(&self) -> MirroredResource<UrlResource> {
+        let urls = self.urls().into_iter();
+        MirroredResource::new(urls.map(|url| UrlResource::new(url).unwrap()).collect())
     }
 }
 impl RawResource for Genomes1000Resource {
@@ -188,6 +200,12 @@ impl RawResource for Genomes1000Resource {
     async fn read_async(&self) -> std::io::Result<Self::AsyncReader> {
         self.url_resource().read_async().await
     }
+    async fn read_range_async(
+        &self,
+        range: std::ops::Range<u64>,
+    ) -> std::io::Result<Option<Self::AsyncReader>> {
+        self.url_resource().read_range_async(range).await
+    }
 }
 
 mod old {
@@ -205,6 +223,10 @@ mod old {
     const CHR_Y_NAME: &str = "ALL.chrY.phase3_integrated_v2b.20130502.genotypes.vcf.gz";
     const MT_NAME: &str = "ALL.chrMT.phase3_callmom-v0_4.20130502.genotypes.vcf.gz";
     const WGS_NAME: &str = "ALL.wgs.phase3_shapeit2_mvncall_integrated_v5c.20130502.sites.vcf.gz";
+    /// The 2504 phase 3 samples, with their population, superpopulation and sex.
+    const PANEL_NAME: &str = "integrated_call_samples_v3.20130502.ALL.panel";
+    /// The 2504 phase 3 samples, with their population, superpopulation and sex.
+    const PANEL_NAME: &str = "integrated_call_samples_v3.20130502.ALL.panel";
 
     impl Genomes1000Resource {
         pub fn old_grch37_reference_genome() -> Self {
@@ -277,6 +299,12 @@ mod old {
             })
         }
 
+        pub fn old_phase_3_panel() -> Self {
+            Self::new(format!("{CHR_BASE}/{PANEL_NAME}"))
+        }
+        pub fn old_phase_3_panel() -> Self {
+            Self::new(format!("{CHR_BASE}/{PANEL_NAME}"))
+        }
         pub fn old_phase_3_wgs_sites_vcf() -> Self {
             Self::new(format!("{CHR_BASE}/{WGS_NAME}"))
         }
@@ -0,0 +1,141 @@
+//! The three bulk downloads together, with indexed joins between them.
+
+use std::{collections::BTreeMap, io};
+
+use ids::pubmed::PubmedId;
+use jiff::civil::Date;
+
+use crate::{GwasCatalogAncestry, GwasCatalogAssociation, GwasCatalogStudy};
+
+/// Studies, associations and ancestries, joined on the study accession and PubMed ID.
+#[derive(Debug, Clone)]
+pub struct GwasCatalog {
+    studies: Vec<GwasCatalogStudy>,
+    associations: Vec<GwasCatalogAssociation>,
+    ancestries: Vec<GwasCatalogAncestry>,
+
+    /// Indices into the vectors above.
+    study_by_accession: BTreeMap<String, usize>,
+    associations_by_study: BTreeMap<String, Vec<usize>>,
+    ancestries_by_study: BTreeMap<String, Vec<usize>>,
+    studies_by_pubmed: BTreeMap<PubmedId, Vec<usize>>,
+}
+
+impl GwasCatalog {
+    pub async fn load_latest() -> io::Result<Self> {
+        Ok(Self::new(
+            GwasCatalogStudy::get_latest()
+                .await?
+                .collect::<Result<_, _>>()?,
+            GwasCatalogAssociation::get_latest()
+                .await?
+                .collect::<Result<_, _>>()?,
+            GwasCatalogAncestry::get_latest()
+                .await?
+                .collect::<Result<_, _>>()?,
+        ))
+    }
+    /// See [GwasCatalogResource::release_url](crate::GwasCatalogResource::release_url).
+    pub async fn load_release(release: Date) -> io::Result<Self> {
+        Ok(Self::new(
+            GwasCatalogStudy::get_release(release)
+                .await?
+                .collect::<Result<_, _>>()?,
+            GwasCatalogAssociation::get_release(release)
+                .await?
+                .collect::<Result<_, _>>()?,
+            GwasCatalogAncestry::get_release(release)
+                .await?
+                .collect::<Result<_, _>>()?,
+        ))
+    }
+
+    pub fn new(
+        studies: Vec<GwasCatalogStudy>,
+        associations: Vec<GwasCatalogAssociation>,
+        ancestries: Vec<GwasCatalogAncestry>,
+    ) -> Self {
+        let mut study_by_accession = BTreeMap::new();
+        let mut studies_by_pubmed: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for (i, study) in studies.iter().enumerate() {
+            study_by_accession.insert(study.study_accession.clone(), i);
+            studies_by_pubmed.entry(study.pubmedid).or_default().push(i);
+        }
+        let mut associations_by_study: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for (i, association) in associations.iter().enumerate() {
+            associations_by_study
+                .entry(association.study_accession.clone())
+                .or_default()
+                .push(i);
+        }
+        let mut ancestries_by_study: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for (i, ancestry) in ancestries.iter().enumerate() {
+            ancestries_by_study
+                .entry(ancestry.study_accession.clone())
+                .or_default()
+                .push(i);
+        }
+
+        Self {
+            studies,
+            associations,
+            ancestries,
+            study_by_accession,
+            associations_by_study,
+            ancestries_by_study,
+            studies_by_pubmed,
+        }
+    }
+
+    pub fn studies(&self) -> &[GwasCatalogStudy] {
+        &self.studies
+    }
+    pub fn associations(&self) -> &[GwasCatalogAssociation] {
+        &self.associations
+    }
+    pub fn ancestries(&self) -> &[GwasCatalogAncestry] {
+        &self.ancestries
+    }
+
+    /// By [GwasCatalogStudy::study_accession], e.g. `GCST000001`.
+    pub fn study(&self, accession: &str) -> Option<&GwasCatalogStudy> {
+        Some(&self.studies[*self.study_by_accession.get(accession)?])
+    }
+    pub fn study_associations(
+        &self,
+        accession: &str,
+    ) -> impl Iterator<Item = &GwasCatalogAssociation> {
+        Self::lookup(&self.associations_by_study, accession, &self.associations)
+    }
+    pub fn study_ancestries(&self, accession: &str) -> impl Iterator<Item = &GwasCatalogAncestry> {
+        Self::lookup(&self.ancestries_by_study, accession, &self.ancestries)
+    }
+    /// The studies of a publication, there can be several (e.g. one per trait).
+    pub fn pubmed_studies(&self, id: PubmedId) -> impl Iterator<Item = &GwasCatalogStudy> {
+        Self::lookup(&self.studies_by_pubmed, &id, &self.studies)
+    }
+    /// The study an association comes from.
+    pub fn association_study(
+        &self,
+        association: &GwasCatalogAssociation,
+    ) -> Option<&GwasCatalogStudy> {
+        self.study(&association.study_accession)
+    }
+
+    fn lookup<'a, K, Q, T>(
+        index: &'a BTreeMap<K, Vec<usize>>,
+        key: &Q,
+        items: &'a [T],
+    ) -> impl Iterator<Item = &'a T> + use<'a, K, Q, T>
+    where
+        K: Ord + std::borrow::Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        index
+            .get(key)
+            .map(|i| &i[..])
+            .unwrap_or_default()
+            .iter()
+            .map(|i| &items[*i])
+    }
+}
@@ -0,0 +1,206 @@
+//! Changes between two releases of the catalog, to follow how the evidence evolves.
+
+use std::{collections::BTreeMap, io};
+
+use jiff::civil::Date;
+use serde::{Deserialize, Serialize};
+
+use crate::{GwasCatalog, GwasCatalogAssociation, GwasCatalogStudy};
+
+/// See [CatalogDiff::new].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Deserialize)]
+pub struct CatalogDiff {
+    /// By [GwasCatalogStudy::study_accession].
+    pub studies: Changes<String>,
+    pub associations: Changes<AssociationKey>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Deserialize)]
+pub struct Changes<K> {
+    pub added: Vec<K>,
+    pub removed: Vec<K>,
+    pub changed: Vec<Change<K>>,
+}
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Deserialize)]
+pub struct Change<K> {
+    pub key: K,
+    pub fields: Vec<FieldChange>,
+}
+/// A column that differs, with the values as in the files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Deserialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: String,
+    pub new: String,
+}
+
+/// Identifies an association across releases.
+///
+/// A study can report the same variant several times (e.g. in different subgroups, see
+/// [GwasCatalogAssociation::p_value_text]), repeats are told apart by their order.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Serialize, Deserialize)]
+pub struct AssociationKey {
+    pub study_accession: String,
+    /// [GwasCatalogAssociation::strongest_snp_risk_allele].
+    pub variant: String,
+    pub p_value_text: String,
+    /// 0 unless the rest of the key is repeated.
+    pub occurrence: usize,
+}
+
+impl CatalogDiff {
+    pub fn new(old: &GwasCatalog, new: &GwasCatalog) -> Self {
+        let study_key = |study: &GwasCatalogStudy| study.study_accession.clone();
+        Self {
+            studies: Changes::new(
+                old.studies().iter().map(|s| (study_key(s), s)).collect(),
+                new.studies().iter().map(|s| (study_key(s), s)).collect(),
+            ),
+            associations: Changes::new(
+                association_keys(old.associations()),
+                association_keys(new.associations()),
+            ),
+        }
+    }
+    /// Loads both releases (cached), see [GwasCatalog::load_release].
+    pub async fn releases(old: Date, new: Date) -> io::Result<Self> {
+        let old = GwasCatalog::load_release(old).await?;
+        let new = GwasCatalog::load_release(new).await?;
+        Ok(Self::new(&old, &new))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.studies.is_empty() && self.associations.is_empty()
+    }
+}
+
+impl<K: Ord + Clone> Changes<K> {
+    fn new<T: Serialize>(old: BTreeMap<K, &T>, new: BTreeMap<K, &T>) -> Self {
+        let mut changes = Self {
+            added: vec![],
+            removed: vec![],
+            changed: vec![],
+        };
+        for (key, old_record) in &old {
+            let Some(new_record) = new.get(key) else {
+                changes.removed.push(key.clone());
+                continue;
+            };
+            let fields = field_changes(*old_record, *new_record);
+            if !fields.is_empty() {
+                changes.changed.push(Change {
+                    key: key.clone(),
+                    fields,
+                });
+            }
+        }
+        changes.added = new
+            .into_keys()
+            .filter(|key| !old.contains_key(key))
+            .collect();
+        changes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+fn association_keys(
+    associations: &[GwasCatalogAssociation],
+) -> BTreeMap<AssociationKey, &GwasCatalogAssociation> {
+    let mut keys = BTreeMap::new();
+    for association in associations {
+        let mut key = AssociationKey {
+            study_accession: association.study_accession.clone(),
+            variant: association.strongest_snp_risk_allele.clone(),
+            p_value_text: association.p_value_text.clone(),
+            occurrence: 0,
+        };
+        while keys.contains_key(&key) {
+            key.occurrence += 1;
+        }
+        keys.insert(key, association);
+    }
+    keys
+}
+
+fn field_changes<T: Serialize>(old: &T, new: &T) -> Vec<FieldChange> {
+    fields(old)
+        .into_iter()
+        .zip(fields(new))
+        .filter(|((_, old), (_, new))| old != new)
+        .map(|((field, old), (_, new))| FieldChange { field, old, new })
+        .collect()
+}
+
+/// The columns of a record, as they are written in the downloads.
+fn fields<T: Serialize>(record: &T) -> Vec<(String, String)> {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(b'\t')
+        .from_writer(vec![]);
+    writer.serialize(record).unwrap();
+    let data = writer.into_inner().unwrap();
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(b'\t')
+        .from_reader(&data[..]);
+    let header = reader.headers().unwrap().clone();
+    let values = reader.records().next().unwrap().unwrap();
+    header
+        .iter()
+        .zip(&values)
+        .map(|(field, value)| (field.to_owned(), value.to_owned()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde::Serialize;
+
+    use super::{Change, Changes, FieldChange};
+
+    #[derive(Serialize)]
+    struct Record {
+        #[serde(rename = "P-VALUE")]
+        p_value: &'static str,
+        #[serde(rename = "MAPPED_GENE")]
+        gene: &'static str,
+    }
+
+    #[test]
+    fn changes() {
+        let a = Record {
+            p_value: "1E-8",
+            gene: "APOE",
+        };
+        let b = Record {
+            p_value: "2E-9",
+            gene: "APOE",
+        };
+        let old = BTreeMap::from([(1, &a), (2, &a)]);
+        let new = BTreeMap::from([(2, &b), (3, &b)]);
+
+        let changes = Changes::new(old, new);
+        assert_eq!(changes.added, [3]);
+        assert_eq!(changes.removed, [1]);
+        assert_eq!(
+            changes.changed,
+            [Change {
+                key: 2,
+                fields: vec![FieldChange {
+                    field: "P-VALUE".to_owned(),
+                    old: "1E-8".to_owned(),
+                    new: "2E-9".to_owned(),
+                }],
+            }]
+        );
+    }
+}
@@ -1,18 +1,47 @@
+//! GWAS Catalog studies, associations and ancestries, with summary statistics.
+//!
+//! # Stability
+//!
+//! The loaders and the row types are the stable API. Rows keep public fields for the columns
+//! present in every release, and accessors for the others (e.g.
+//! [GwasCatalogStudy::submission_date]). [GwasCatalogStudy] is `#[non_exhaustive]`, so columns
+//! from new releases can be added in minor releases, and it can't be built outside this crate.
+
 use either::Either;
 use ids::pubmed::PubmedId;
 use jiff::civil::Date;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use url::Url;
 
-use biocore::location::ContigPosition;
+use biocore::{
+    genome::cytoband::Karyotype,
+    location::{ContigPosition, ContigRange},
+};
+use ensembl::contig::GRCh38Contig;
 use resource::{RawResource, RawResourceExt, UrlResource};
 use utile::io::reqwest_error;
 
+pub use self::{
+    catalog::GwasCatalog,
+    diff::{AssociationKey, CatalogDiff, Change, Changes, FieldChange},
+    snps::{RiskAllele, SnpId, Snps},
+    summary_stats::{GwasSummaryStats, SummaryStatsResource},
+};
+
+mod catalog;
+mod diff;
+mod snps;
+mod summary_stats;
+
+/// A download of the catalog, either the latest one or a dated release.
+///
+/// The cache key includes the release, so downloads of different releases don't mix.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct GwasCatalogResource {
-    url: &'static str,
+    url: Url,
     key: String,
     size: u64,
+    release: Option<Date>,
 }
 impl GwasCatalogResource {
     pub async fn get_latest_associations() -> std::io::Result<Self> {
@@ -25,6 +54,44 @@ impl GwasCatalogResource {
         get_latest_key(Self::ANCESTRY_URL).await
     }
 
+    /// The associations of a dated release, see [Self::release_url].
+    pub async fn get_release_associations(release: Date) -> std::io::Result<Self> {
+        get_release_key(release, Self::ASSOCIATIONS_RELEASE_FILE).await
+    }
+    pub async fn get_release_studies(release: Date) -> std::io::Result<Self> {
+        get_release_key(release, Self::STUDIES_RELEASE_FILE).await
+    }
+    pub async fn get_release_ancestries(release: Date) -> std::io::Result<Self> {
+        get_release_key(release, Self::ANCESTRY_RELEASE_FILE).await
+    }
+
+    /// The release the download comes from, `None` if the latest download didn't say.
+    pub fn release(&self) -> Option<Date> {
+        self.release
+    }
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// The directory of a dated release, releases are listed at
+    /// https://ftp.ebi.ac.uk/pub/databases/gwas/releases/.
+    pub fn release_url(release: Date) -> Url {
+        format!(
+            "{}{:04}/{:02}/{:02}/",
+            Self::RELEASES_URL,
+            release.year(),
+            release.month(),
+            release.day()
+        )
+        .parse()
+        .unwrap()
+    }
+
+    const RELEASES_URL: &str = "https://ftp.ebi.ac.uk/pub/databases/gwas/releases/";
+    const ASSOCIATIONS_RELEASE_FILE: &str = "gwas-catalog-associations_ontology-annotated.tsv";
+    const STUDIES_RELEASE_FILE: &str = "gwas-catalog-download-studies-v1.0.3.1.txt";
+    const ANCESTRY_RELEASE_FILE: &str = "gwas-catalog-download-ancestries-v1.0.3.1.txt";
+
     const ASSOCIATIONS_URL: &str = "https://www.ebi.ac.uk/gwas/api/search/downloads/alternative";
     const STUDIES_URL: &str = "https://www.ebi.ac.uk/gwas/api/search/downloads/studies/v1.0.3.1";
     const ANCESTRY_URL: &str =
@@ -56,7 +123,7 @@ impl RawResource for GwasCatalogResource {
     }
 
     fn read(&self) -> std::io::Result<Self::Reader> {
-        UrlResource::new(self.url).unwrap().read()
+        UrlResource::new(self.url.clone())?.read()
     }
 
     type AsyncReader = <UrlResource as RawResource>::AsyncReader;
@@ -64,7 +131,17 @@ impl RawResource for GwasCatalogResource {
         Ok(self.size)
     }
     async fn read_async(&self) -> std::io::Result<Self::AsyncReader> {
-        UrlResource::new(self.url).unwrap().read_async().await
+        UrlResource::new(self.url.clone())?.read_async().await
+    }
+
+    /// The "latest" URLs are updated in place with every release.
+    async fn validators_async(&self) -> std::io::Result<Option<resource::Validators>> {
+        UrlResource::new(self.url.clone())?.validators_async().await
+    }
+    async fn is_fresh_async(&self, cached: &resource::Validators) -> std::io::Result<Option<bool>> {
+        UrlResource::new(self.url.clone())?
+            .is_fresh_async(cached)
+            .await
     }
 }
 
@@ -197,19 +274,20 @@ pub struct GwasCatalogAssociation {
 impl GwasCatalogAssociation {
     pub async fn get_latest()
     -> Result<impl Iterator<Item = Result<Self, csv::Error>>, std::io::Error> {
-        let resource = GwasCatalogResource::get_latest_associations()
-            .await?
-            .log_progress()
-            .with_global_fs_cache()
-            .ensure_cached_async()
-            .await?
-            .buffered();
-
-        Ok(csv::ReaderBuilder::new()
-            .delimiter(b'\t')
-            .has_headers(true)
-            .from_reader(resource.read()?)
-            .into_deserialize())
+        load(GwasCatalogResource::get_latest_associations().await?).await
+    }
+    /// See [GwasCatalogResource::release_url].
+    pub async fn get_release(
+        release: Date,
+    ) -> Result<impl Iterator<Item = Result<Self, csv::Error>>, std::io::Error> {
+        load(GwasCatalogResource::get_release_associations(release).await?).await
+    }
+    /// The positions of [Self::locations_raw] on GRCh38, see [Location::position].
+    pub fn positions(&self) -> std::io::Result<Vec<ContigPosition<GRCh38Contig>>> {
+        self.locations_raw()
+            .iter()
+            .map(Location::position)
+            .collect()
     }
     pub fn locations_raw(&self) -> Vec<Location> {
         match self.locations() {
@@ -283,6 +361,7 @@ impl GwasCatalogAssociation {
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
+#[non_exhaustive]
 pub struct GwasCatalogStudy {
     //v1.0
     /// DATE ADDED TO CATALOG* +: Date a study is published in the catalog
@@ -368,19 +447,38 @@ pub struct GwasCatalogStudy {
 impl GwasCatalogStudy {
     pub async fn get_latest()
     -> Result<impl Iterator<Item = Result<Self, csv::Error>>, std::io::Error> {
-        let resource = GwasCatalogResource::get_latest_studies()
-            .await?
-            .log_progress()
-            .with_global_fs_cache()
-            .ensure_cached_async()
-            .await?
-            .buffered();
-
-        Ok(csv::ReaderBuilder::new()
-            .delimiter(b'\t')
-            .has_headers(true)
-            .from_reader(resource.read()?)
-            .into_deserialize())
+        load(GwasCatalogResource::get_latest_studies().await?).await
+    }
+    /// See [GwasCatalogResource::release_url].
+    pub async fn get_release(
+        release: Date,
+    ) -> Result<impl Iterator<Item = Result<Self, csv::Error>>, std::io::Error> {
+        load(GwasCatalogResource::get_release_studies(release).await?).await
+    }
+
+    /// Genotyping platform manufacturer and number of SNPs tested in the analysis.
+    pub fn platform_snps_passing_qc(&self) -> &str {
+        &self.platform_snps_passing_qc
+    }
+    /// The date the GWAS was submitted to the Catalog.
+    pub fn submission_date(&self) -> Option<Date> {
+        self.submission_date
+    }
+    /// Details of the statistical model used to determine association significance.
+    pub fn statistical_model(&self) -> &str {
+        &self.statistical_model
+    }
+    /// Any background trait(s) shared by all individuals in the GWAS.
+    pub fn background_trait(&self) -> &str {
+        &self.background_trait
+    }
+    /// Mapped Experimental Factor Ontology trait for the background trait(s).
+    pub fn mapped_background_trait(&self) -> &str {
+        &self.mapped_background_trait
+    }
+    /// URI of the EFO trait(s) in [Self::mapped_background_trait].
+    pub fn mapped_background_trait_uri(&self) -> &str {
+        &self.mapped_background_trait_uri
     }
 }
 
@@ -445,19 +543,13 @@ pub struct GwasCatalogAncestry {
 impl GwasCatalogAncestry {
     pub async fn get_latest()
     -> Result<impl Iterator<Item = Result<Self, csv::Error>>, std::io::Error> {
-        let resource = GwasCatalogResource::get_latest_ancestries()
-            .await?
-            .log_progress()
-            .with_global_fs_cache()
-            .ensure_cached_async()
-            .await?
-            .buffered();
-
-        Ok(csv::ReaderBuilder::new()
-            .delimiter(b'\t')
-            .has_headers(true)
-            .from_reader(resource.read()?)
-            .into_deserialize())
+        load(GwasCatalogResource::get_latest_ancestries().await?).await
+    }
+    /// See [GwasCatalogResource::release_url].
+    pub async fn get_release(
+        release: Date,
+    ) -> Result<impl Iterator<Item = Result<Self, csv::Error>>, std::io::Error> {
+        load(GwasCatalogResource::get_release_ancestries(release).await?).await
     }
 }
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -470,6 +562,43 @@ pub struct Location {
     pub loc: ContigPosition,
     pub region: String,
 }
+impl Location {
+    /// The position on GRCh38, the build the catalog currently maps to.
+    ///
+    /// Unlike [Self::loc], positions are 0-based.
+    pub fn position(&self) -> std::io::Result<ContigPosition<GRCh38Contig>> {
+        let invalid =
+            |message: String| std::io::Error::new(std::io::ErrorKind::InvalidData, message);
+        let contig = GRCh38Contig::new(&self.loc.contig)
+            .ok_or_else(|| invalid(format!("Unknown GRCh38 contig: {}", self.loc.contig)))?;
+        let at =
+            self.loc.at.checked_sub(1).ok_or_else(|| {
+                invalid(format!("Invalid 1-based position: {}:0", self.loc.contig))
+            })?;
+        Ok(ContigPosition { contig, at })
+    }
+    /// Resolves the cytogenetic [Self::region] (e.g. `6p21.33`) to a range, see [Karyotype::resolve].
+    pub fn region_range<C>(&self, karyotype: &Karyotype<C>) -> Option<ContigRange<C>>
+    where
+        C: Ord + Clone + AsRef<str>,
+    {
+        karyotype.resolve(&self.region)
+    }
+    /// Whether the position falls within the reported region (on the same chromosome),
+    /// [None] if the region cannot be resolved.
+    pub fn is_in_region<C>(&self, karyotype: &Karyotype<C>) -> Option<bool>
+    where
+        C: Ord + Clone + AsRef<str>,
+    {
+        let range = self.region_range(karyotype)?;
+        let chromosome = |contig: &str| contig.strip_prefix("chr").unwrap_or(contig).to_owned();
+        if chromosome(range.contig.as_ref()) != chromosome(&self.loc.contig) {
+            return Some(false);
+        }
+        // CHR_POS is 1-based.
+        Some(range.at.contains(&self.loc.at.saturating_sub(1)))
+    }
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum HumanContig {
@@ -611,10 +740,28 @@ fn parse_human_contig(v: &str) -> Result<HumanContig, std::io::Error> {
         "22" => Ok(HumanContig::Chr22),
         "X" => Ok(HumanContig::X),
         "Y" => Ok(HumanContig::Y),
+        "MT" | "M" => Ok(HumanContig::MT),
         _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidData, v)),
     }
 }
 
+async fn load<T: DeserializeOwned>(
+    resource: GwasCatalogResource,
+) -> Result<impl Iterator<Item = Result<T, csv::Error>>, std::io::Error> {
+    let resource = resource
+        .log_progress()
+        .with_global_fs_cache()
+        .ensure_cached_async()
+        .await?
+        .buffered();
+
+    Ok(csv::ReaderBuilder::new()
+        .delimiter(b'\t')
+        .has_headers(true)
+        .from_reader(resource.read()?)
+        .into_deserialize())
+}
+
 async fn get_latest_key(url: &'static str) -> std::io::Result<GwasCatalogResource> {
     let head = reqwest::Client::new()
         .head(url)
@@ -626,8 +773,125 @@ async fn get_latest_key(url: &'static str) -> std::io::Result<GwasCatalogResourc
     let file_size = utile::io::get_filesize_from_headers(head.headers()).unwrap();
 
     Ok(GwasCatalogResource {
-        url,
+        url: url.parse().unwrap(),
+        release: release_from_filename(&file_name),
         key: file_name,
         size: file_size,
     })
 }
+async fn get_release_key(release: Date, file: &str) -> std::io::Result<GwasCatalogResource> {
+    let url = GwasCatalogResource::release_url(release)
+        .join(file)
+        .map_err(utile::io::invalid_data)?;
+    let size = UrlResource::new(url.clone())?.size_async().await?;
+
+    Ok(GwasCatalogResource {
+        key: format!("releases/{release}/{file}"),
+        url,
+        size,
+        release: Some(release),
+    })
+}
+/// Latest downloads are named after their release, e.g.
+/// `gwas_catalog_v1.0.2-associations_e113_r2024-12-19.tsv`.
+fn release_from_filename(file_name: &str) -> Option<Date> {
+    let (_, release) = file_name
+        .rsplit_once("_r")
+        .or(file_name.rsplit_once("-r"))?;
+    release.get(..10)?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn location(contig: &str, at: u64, region: &str) -> Location {
+        Location {
+            loc: ContigPosition {
+                contig: contig.to_owned(),
+                at,
+            },
+            region: region.to_owned(),
+        }
+    }
+
+    #[test]
+    fn position() {
+        let position = location("6", 31_000_000, "6p21.33").position().unwrap();
+        assert_eq!(position.contig, GRCh38Contig::new("6").unwrap());
+        assert_eq!(position.at, 30_999_999);
+
+        let error = location("6", 0, "6p21.33").position().unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        assert!(location("chrUn", 1, "").position().is_err());
+    }
+
+    #[test]
+    fn release_from_filename() {
+        let release = |file_name| super::release_from_filename(file_name);
+        let date = |s: &str| Some(s.parse::<Date>().unwrap());
+
+        assert_eq!(
+            release("gwas_catalog_v1.0.2-associations_e113_r2024-12-19.tsv"),
+            date("2024-12-19")
+        );
+        assert_eq!(
+            release("gwas-catalog-v1.0.3.1-studies-r2025-03-26.tsv"),
+            date("2025-03-26")
+        );
+        assert_eq!(
+            release("gwas_catalog_v1.0-associations_e96_r2019-05-03.tsv"),
+            date("2019-05-03")
+        );
+        assert_eq!(release("gwas_catalog_v1.0-associations.tsv"), None);
+        assert_eq!(release("gwas_catalog_v1.0-associations_r2019-05.tsv"), None);
+    }
+
+    #[test]
+    fn is_in_region() {
+        let karyotype: Karyotype = Karyotype::read(Cursor::new(
+            "chr6\t30500000\t31100000\tp21.33\tgneg\n\
+             chr6\t31100000\t31800000\tp21.32\tgpos25\n\
+             chr7\t30500000\t31100000\tp14.3\tgneg\n",
+        ))
+        .unwrap();
+
+        assert_eq!(
+            location("6", 31_000_000, "6p21.33").is_in_region(&karyotype),
+            Some(true)
+        );
+        assert_eq!(
+            location("6", 31_500_000, "6p21.33").is_in_region(&karyotype),
+            Some(false)
+        );
+        // Same coordinates, different chromosome.
+        assert_eq!(
+            location("7", 31_000_000, "6p21.33").is_in_region(&karyotype),
+            Some(false)
+        );
+        assert_eq!(
+            location("6", 31_000_000, "6q99").is_in_region(&karyotype),
+            None
+        );
+
+        // CHR_POS is 1-based, the bands 0-based half-open.
+        assert_eq!(
+            location("6", 30_500_000, "6p21.33").is_in_region(&karyotype),
+            Some(false)
+        );
+        assert_eq!(
+            location("6", 30_500_001, "6p21.33").is_in_region(&karyotype),
+            Some(true)
+        );
+        assert_eq!(
+            location("6", 31_100_000, "6p21.33").is_in_region(&karyotype),
+            Some(true)
+        );
+        assert_eq!(
+            location("6", 31_100_001, "6p21.33").is_in_region(&karyotype),
+            Some(false)
+        );
+    }
+}
@@ -0,0 +1,169 @@
+//! Typed versions of the SNP columns of [GwasCatalogAssociation].
+
+use biocore::dna::DnaBase;
+use ids::rs::RsId;
+
+use crate::GwasCatalogAssociation;
+
+/// How the SNPs of an association relate to each other.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Snps<T> {
+    Single(T),
+    /// `;` or `,` separated: the SNPs of a haplotype, or several SNPs reported together.
+    Haplotype(Vec<T>),
+    /// ` x ` separated: an interaction between SNPs.
+    Interaction(Vec<T>),
+}
+
+/// A variant in [GwasCatalogAssociation::snps].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SnpId {
+    Rs(RsId),
+    /// Positional (e.g. `chr6:32658079`) or array-specific (e.g. `kgp4321`) identifiers.
+    Other(String),
+}
+
+/// A variant in [GwasCatalogAssociation::strongest_snp_risk_allele], e.g. `rs123-A`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RiskAllele {
+    pub snp: SnpId,
+    /// `None` if unknown (`?`), or not a single base.
+    pub allele: Option<DnaBase>,
+}
+
+impl<T> Snps<T> {
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        match self {
+            Self::Single(snp) => std::slice::from_ref(snp).iter(),
+            Self::Haplotype(snps) | Self::Interaction(snps) => snps.iter(),
+        }
+    }
+
+    /// `None` if `s` is empty.
+    fn parse(s: &str, parse: impl Fn(&str) -> T) -> Option<Self> {
+        let s = s.trim();
+        if s.is_empty() {
+            return None;
+        }
+        let split = |separators: &[&str]| -> Option<Vec<T>> {
+            let separator = separators.iter().find(|sep| s.contains(**sep))?;
+            let snps = s
+                .split(*separator)
+                .map(str::trim)
+                .filter(|snp| !snp.is_empty())
+                .map(&parse)
+                .collect();
+            Some(snps)
+        };
+        if let Some(snps) = split(&[" x "]) {
+            Some(Self::Interaction(snps))
+        } else if let Some(snps) = split(&[";", ","]) {
+            Some(Self::Haplotype(snps))
+        } else {
+            Some(Self::Single(parse(s)))
+        }
+    }
+}
+
+impl SnpId {
+    pub fn parse(s: &str) -> Self {
+        let s = s.trim();
+        match s.parse() {
+            Ok(rs_id) => Self::Rs(rs_id),
+            Err(_) => Self::Other(s.to_owned()),
+        }
+    }
+    pub fn rs_id(&self) -> Option<RsId> {
+        match self {
+            Self::Rs(rs_id) => Some(*rs_id),
+            Self::Other(_) => None,
+        }
+    }
+}
+
+impl RiskAllele {
+    /// The allele follows the last `-`, when it's made of bases or `?`.
+    pub fn parse(s: &str) -> Self {
+        let s = s.trim();
+        let split = s.rsplit_once('-').filter(|(_, allele)| {
+            !allele.is_empty() && allele.chars().all(|c| "ACGT?".contains(c))
+        });
+        match split {
+            Some((snp, allele)) => Self {
+                snp: SnpId::parse(snp),
+                allele: allele.parse().ok(),
+            },
+            None => Self {
+                snp: SnpId::parse(s),
+                allele: None,
+            },
+        }
+    }
+}
+
+impl GwasCatalogAssociation {
+    /// See [Self::snps], `None` if empty.
+    pub fn snp_ids(&self) -> Option<Snps<SnpId>> {
+        Snps::parse(&self.snps, SnpId::parse)
+    }
+    /// See [Self::strongest_snp_risk_allele], `None` if empty.
+    pub fn risk_alleles(&self) -> Option<Snps<RiskAllele>> {
+        Snps::parse(&self.strongest_snp_risk_allele, RiskAllele::parse)
+    }
+    /// Whether the SNP was merged into [Self::current_snp].
+    pub fn is_merged(&self) -> bool {
+        self.merged.trim() == "1"
+    }
+    /// See [Self::snp_id_current], which is given without the `rs` prefix.
+    pub fn current_snp(&self) -> Option<RsId> {
+        RsId::try_new(self.snp_id_current.trim().parse().ok()?).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use biocore::dna::DnaBase;
+    use ids::rs::RsId;
+
+    use super::{RiskAllele, SnpId, Snps};
+
+    #[test]
+    fn parse_snps() {
+        let rs = |id| SnpId::Rs(RsId::new(id));
+        assert_eq!(
+            Snps::parse("rs123", SnpId::parse),
+            Some(Snps::Single(rs(123)))
+        );
+        assert_eq!(
+            Snps::parse("rs1; rs2", SnpId::parse),
+            Some(Snps::Haplotype(vec![rs(1), rs(2)]))
+        );
+        assert_eq!(
+            Snps::parse("rs1 x chr6:32658079", SnpId::parse),
+            Some(Snps::Interaction(vec![
+                rs(1),
+                SnpId::Other("chr6:32658079".to_owned())
+            ]))
+        );
+        assert_eq!(Snps::parse(" ", SnpId::parse), None);
+
+        let risk = |id, allele| RiskAllele {
+            snp: rs(id),
+            allele,
+        };
+        assert_eq!(RiskAllele::parse("rs123-A"), risk(123, Some(DnaBase::A)));
+        assert_eq!(RiskAllele::parse("rs123-?"), risk(123, None));
+        assert_eq!(RiskAllele::parse("rs123-AT"), risk(123, None));
+        assert_eq!(
+            RiskAllele::parse("chr6:32658079-G").snp,
+            SnpId::Other("chr6:32658079".to_owned())
+        );
+        assert_eq!(
+            RiskAllele::parse("HLA-DRB1*15:01"),
+            RiskAllele {
+                snp: SnpId::Other("HLA-DRB1*15:01".to_owned()),
+                allele: None
+            }
+        );
+    }
+}
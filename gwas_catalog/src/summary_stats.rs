@@ -0,0 +1,228 @@
+//! Harmonised full summary statistics deposited with GWAS Catalog studies, in the GWAS-SSF format.
+//!
+//! https://www.ebi.ac.uk/gwas/docs/summary-statistics-format
+//!
+//! The harmonised files are always on [GRCh38], and live under the `harmonised` directory of the
+//! [GwasCatalogStudy::summary_stats_location].
+
+use std::{io, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use biocore::location::{
+    ContigPosition,
+    build::{BuildContig, GRCh38},
+};
+use resource::{RawResource, RawResourceExt, UrlResource};
+use utile::io::reqwest_error;
+
+use crate::GwasCatalogStudy;
+
+const FTP_BASE: &str = "https://ftp.ebi.ac.uk/pub/databases/gwas/";
+
+/// A harmonised summary statistics file, see [GwasCatalogStudy::summary_stats_resource].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SummaryStatsResource {
+    url: Url,
+}
+
+/// A row of a harmonised GWAS-SSF file, positions are on [GRCh38].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GwasSummaryStats {
+    /// `1`-`22`, `X`, `Y`, `MT`, or their numeric codes `23`-`25`.
+    pub chromosome: String,
+    /// 1-based.
+    #[serde(default, deserialize_with = "csv::invalid_option")]
+    pub base_pair_location: Option<u64>,
+    pub effect_allele: String,
+    pub other_allele: String,
+    #[serde(default, deserialize_with = "csv::invalid_option")]
+    pub beta: Option<f64>,
+    #[serde(default, deserialize_with = "csv::invalid_option")]
+    pub odds_ratio: Option<f64>,
+    #[serde(default, deserialize_with = "csv::invalid_option")]
+    pub standard_error: Option<f64>,
+    #[serde(default, deserialize_with = "csv::invalid_option")]
+    pub effect_allele_frequency: Option<f64>,
+    /// Either this or [Self::neg_log_10_p_value] is given.
+    #[serde(default, deserialize_with = "csv::invalid_option")]
+    pub p_value: Option<f64>,
+    #[serde(default, deserialize_with = "csv::invalid_option")]
+    pub neg_log_10_p_value: Option<f64>,
+    #[serde(default)]
+    pub variant_id: Option<String>,
+    #[serde(default)]
+    pub rsid: Option<String>,
+    /// How the variant was harmonised, e.g. `10` for a forward strand match.
+    #[serde(default, deserialize_with = "csv::invalid_option")]
+    pub hm_code: Option<u8>,
+}
+
+impl GwasCatalogStudy {
+    /// The harmonised summary stats of the study, `None` if none were deposited or they
+    /// haven't been harmonised yet.
+    ///
+    /// Lists the FTP directory of the study to find the file.
+    pub async fn summary_stats_resource(&self) -> io::Result<Option<SummaryStatsResource>> {
+        let Some(location) = self.summary_stats_url() else {
+            return Ok(None);
+        };
+        let directory = location
+            .join("harmonised/")
+            .map_err(utile::io::invalid_data)?;
+
+        let response = reqwest::get(directory.clone())
+            .await
+            .map_err(reqwest_error)?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let listing = response
+            .error_for_status()
+            .map_err(reqwest_error)?
+            .text()
+            .await
+            .map_err(reqwest_error)?;
+
+        let Some(file) = harmonised_file(&listing) else {
+            return Ok(None);
+        };
+        let url = directory.join(file).map_err(utile::io::invalid_data)?;
+        Ok(Some(SummaryStatsResource { url }))
+    }
+    /// Downloads (or reads from the cache) the harmonised summary stats, see
+    /// [Self::summary_stats_resource].
+    pub async fn summary_stats(
+        &self,
+    ) -> io::Result<Option<impl Iterator<Item = csv::Result<GwasSummaryStats>> + use<>>> {
+        let Some(resource) = self.summary_stats_resource().await? else {
+            return Ok(None);
+        };
+        let resource = resource
+            .log_progress()
+            .with_global_fs_cache()
+            .ensure_cached_async()
+            .await?
+            .decompressed()
+            .buffered();
+        Ok(Some(GwasSummaryStats::load(resource)?))
+    }
+
+    /// [Self::summary_stats_location] as a directory URL, over https.
+    fn summary_stats_url(&self) -> Option<Url> {
+        let location = self.summary_stats_location.trim();
+        let path = location
+            .split_once("/pub/databases/gwas/")
+            .map(|(_, path)| path)?;
+        Url::parse(&format!("{FTP_BASE}{}/", path.trim_end_matches('/'))).ok()
+    }
+}
+
+impl GwasSummaryStats {
+    pub fn load<R>(resource: R) -> io::Result<impl Iterator<Item = csv::Result<Self>> + use<R>>
+    where
+        R: RawResource,
+        <R as RawResource>::Reader: io::BufRead,
+    {
+        Ok(csv::ReaderBuilder::new()
+            .delimiter(b'\t')
+            .has_headers(true)
+            .from_reader(resource.read()?)
+            .into_deserialize())
+    }
+
+    /// The position on a [GRCh38] contig, `None` if it's missing or the contig is unknown.
+    pub fn at<C>(&self) -> Option<ContigPosition<C>>
+    where
+        C: BuildContig<Build = GRCh38> + FromStr,
+    {
+        let chromosome = match self.chromosome.trim() {
+            "23" => "X",
+            "24" => "Y",
+            "25" | "M" => "MT",
+            chromosome => chromosome,
+        };
+        let contig = [
+            chromosome.to_owned(),
+            format!("chr{chromosome}"),
+            format!("chr{}", chromosome.trim_end_matches('T')),
+        ]
+        .iter()
+        .find_map(|name| name.parse().ok())?;
+        Some(ContigPosition {
+            contig,
+            at: self.base_pair_location?.checked_sub(1)?,
+        })
+    }
+
+    /// The p-value, from [Self::p_value] or [Self::neg_log_10_p_value].
+    pub fn p(&self) -> Option<f64> {
+        self.p_value
+            .or_else(|| Some(10f64.powf(-self.neg_log_10_p_value?)))
+    }
+}
+
+impl SummaryStatsResource {
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+    fn url_resource(&self) -> UrlResource {
+        UrlResource::new(self.url.clone()).unwrap()
+    }
+}
+impl RawResource for SummaryStatsResource {
+    const NAMESPACE: &'static str = "gwas_catalog";
+
+    fn key(&self) -> String {
+        let path = self.url.path();
+        path.split_once("/pub/databases/gwas/")
+            .map_or(path, |(_, key)| key)
+            .to_owned()
+    }
+
+    fn compression(&self) -> Option<resource::Compression> {
+        // Some files are bgzipped, which reads fine as multi-member gzip.
+        Some(resource::Compression::MultiGzip)
+    }
+
+    type Reader = <UrlResource as RawResource>::Reader;
+    fn size(&self) -> io::Result<u64> {
+        self.url_resource().size()
+    }
+    fn read(&self) -> io::Result<Self::Reader> {
+        self.url_resource().read()
+    }
+
+    type AsyncReader = <UrlResource as RawResource>::AsyncReader;
+    async fn size_async(&self) -> io::Result<u64> {
+        self.url_resource().size_async().await
+    }
+    async fn read_async(&self) -> io::Result<Self::AsyncReader> {
+        self.url_resource().read_async().await
+    }
+}
+
+/// The harmonised GWAS-SSF file in a directory listing, e.g. `GCST90002357.h.tsv.gz`.
+fn harmonised_file(listing: &str) -> Option<&str> {
+    listing
+        .split("href=\"")
+        .skip(1)
+        .filter_map(|s| s.split_once('"').map(|(href, _)| href))
+        .find(|href| href.ends_with(".h.tsv.gz") && !href.contains('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::harmonised_file;
+
+    #[test]
+    fn find_harmonised_file() {
+        let listing = r#"<a href="/pub/databases/gwas/">Parent Directory</a>
+<a href="GCST90002357.h.tsv.gz-meta.yaml">GCST90002357.h.tsv.gz-meta.yaml</a>
+<a href="GCST90002357.h.tsv.gz">GCST90002357.h.tsv.gz</a>
+<a href="GCST90002357.h.tsv.gz.tbi">GCST90002357.h.tsv.gz.tbi</a>"#;
+        assert_eq!(harmonised_file(listing), Some("GCST90002357.h.tsv.gz"));
+        assert_eq!(harmonised_file("<a href=\"x.tsv\">"), None);
+    }
+}
@@ -2,13 +2,17 @@ use std::{
     borrow::Borrow,
     cmp::Ordering,
     fmt::{self, Display},
+    ops::Range,
     str::FromStr,
 };
 
 use serde::{Deserialize, Serialize, de::Unexpected};
 use utile::io::FromUtf8Bytes;
 
-use biocore::genome::Contig;
+use biocore::{
+    genome::Contig,
+    location::build::{self, BuildContig},
+};
 
 mod grch37_meta;
 mod grch38_meta;
@@ -54,6 +58,11 @@ impl GRCh38Contig {
     pub const Y: Self = Self { contig: "chrY" };
     pub const MT: Self = Self { contig: "chrM" };
 
+    /// The pseudoautosomal regions (PAR1, PAR2) of [Self::X] (0-based, half-open).
+    pub const X_PARS: [Range<u64>; 2] = [10_000..2_781_479, 155_701_382..156_030_895];
+    /// The pseudoautosomal regions (PAR1, PAR2) of [Self::Y] (0-based, half-open).
+    pub const Y_PARS: [Range<u64>; 2] = [10_000..2_781_479, 56_887_902..57_217_415];
+
     pub fn new(v: &str) -> Option<Self> {
         let contig = grch38_meta::META.get_entry(v)?.0;
         Some(Self { contig })
@@ -122,6 +131,7 @@ impl GRCh38Contig {
                 | Self::Y
         )
     }
+    /// The ploidy outside of the pseudoautosomal regions, see [Self::ploidy_at].
     pub fn ploidy(self, male: bool) -> u8 {
         match (self, male) {
             (Self::Y, true) => 1,
@@ -142,6 +152,28 @@ impl GRCh38Contig {
         self.ploidy(male) == 1
     }
 
+    /// The pseudoautosomal regions on this contig, empty unless it is [Self::X] or [Self::Y].
+    pub fn pars(self) -> &'static [Range<u64>] {
+        match self {
+            Self::X => &Self::X_PARS,
+            Self::Y => &Self::Y_PARS,
+            _ => &[],
+        }
+    }
+    pub fn is_par(self, at: u64) -> bool {
+        self.pars().iter().any(|par| par.contains(&at))
+    }
+    /// Like [Self::ploidy], but accounts for the pseudoautosomal regions.
+    ///
+    /// Males carry a copy of the PARs on both X and Y, so they are diploid there.
+    pub fn ploidy_at(self, at: u64, male: bool) -> u8 {
+        if self.is_par(at) && male {
+            2
+        } else {
+            self.ploidy(male)
+        }
+    }
+
     fn new_from_bytes(bytes: &[u8]) -> Option<Self> {
         Self::new(bytes.as_ascii()?.as_str())
     }
@@ -155,6 +187,9 @@ impl Contig for GRCh38Contig {
         self.meta().len
     }
 }
+impl BuildContig for GRCh38Contig {
+    type Build = build::GRCh38;
+}
 impl Display for GRCh38Contig {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.contig)
@@ -281,6 +316,11 @@ impl GRCh37Contig {
     pub const Y: Self = Self { contig: "Y" };
     pub const MT: Self = Self { contig: "MT" };
 
+    /// The pseudoautosomal regions (PAR1, PAR2) of [Self::X] (0-based, half-open).
+    pub const X_PARS: [Range<u64>; 2] = [60_000..2_699_520, 154_931_043..155_260_560];
+    /// The pseudoautosomal regions (PAR1, PAR2) of [Self::Y] (0-based, half-open).
+    pub const Y_PARS: [Range<u64>; 2] = [10_000..2_649_520, 59_034_049..59_363_566];
+
     pub fn new(v: &str) -> Option<Self> {
         let contig = grch37_meta::META.get_entry(v)?.0;
         Some(Self { contig })
@@ -347,6 +387,18 @@ impl GRCh37Contig {
         )
     }
 
+    /// The pseudoautosomal regions on this contig, empty unless it is [Self::X] or [Self::Y].
+    pub fn pars(self) -> &'static [Range<u64>] {
+        match self {
+            Self::X => &Self::X_PARS,
+            Self::Y => &Self::Y_PARS,
+            _ => &[],
+        }
+    }
+    pub fn is_par(self, at: u64) -> bool {
+        self.pars().iter().any(|par| par.contains(&at))
+    }
+
     fn new_from_bytes(bytes: &[u8]) -> Option<Self> {
         Self::new(bytes.as_ascii()?.as_str())
     }
@@ -360,6 +412,9 @@ impl Contig for GRCh37Contig {
         self.meta().len
     }
 }
+impl BuildContig for GRCh37Contig {
+    type Build = build::GRCh37;
+}
 impl Display for GRCh37Contig {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.contig)
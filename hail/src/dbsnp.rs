@@ -0,0 +1,284 @@
+//! The NCBI dbSNP VCF releases, to resolve rsIDs to positions and alleles.
+//!
+//! https://ftp.ncbi.nih.gov/snp/latest_release/VCF/
+
+use std::{
+    collections::{HashMap, HashSet},
+    io::{self, BufRead},
+};
+
+use biocore::{dna::DnaSequence, location::ContigPosition};
+use ids::rs::RsId;
+use resource::{RawResource, RawResourceExt, UrlResource};
+use url::Url;
+
+use crate::contig::{GRCh37Contig, GRCh38Contig};
+
+const DBSNP_BASE: &str = "https://ftp.ncbi.nih.gov/snp/latest_release/VCF";
+
+const GRCH38_VCF: &str = "GCF_000001405.40.gz";
+const GRCH37_VCF: &str = "GCF_000001405.25.gz";
+
+/// The accession of the mitochondrial genome, the nuclear chromosomes are `NC_0000NN`.
+const MITOCHONDRION: &str = "NC_012920";
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DbSnpResource {
+    key: String,
+}
+impl DbSnpResource {
+    pub fn new(key: String) -> Self {
+        Self { key }
+    }
+
+    pub fn grch38_vcf() -> Self {
+        Self::new(GRCH38_VCF.to_owned())
+    }
+    pub fn grch38_vcf_index() -> Self {
+        Self::new(format!("{GRCH38_VCF}.tbi"))
+    }
+    pub fn grch37_vcf() -> Self {
+        Self::new(GRCH37_VCF.to_owned())
+    }
+    pub fn grch37_vcf_index() -> Self {
+        Self::new(format!("{GRCH37_VCF}.tbi"))
+    }
+
+    pub fn url(&self) -> Url {
+        let key = &self.key;
+        Url::parse(&format!("{DBSNP_BASE}/{key}")).unwrap()
+    }
+
+    fn url_resource(&self) -> UrlResource {
+        UrlResource::new(self.url()).unwrap()
+    }
+}
+impl RawResource for DbSnpResource {
+    const NAMESPACE: &'static str = "dbsnp";
+
+    fn key(&self) -> String {
+        self.key.clone()
+    }
+
+    fn compression(&self) -> Option<resource::Compression> {
+        if self.key.ends_with(".tbi") {
+            None
+        } else {
+            // Block gzipped.
+            Some(resource::Compression::MultiGzip)
+        }
+    }
+
+    type Reader = <UrlResource as RawResource>::Reader;
+    fn size(&self) -> std::io::Result<u64> {
+        self.url_resource().size()
+    }
+    fn read(&self) -> std::io::Result<Self::Reader> {
+        self.url_resource().read()
+    }
+
+    type AsyncReader = <UrlResource as RawResource>::AsyncReader;
+    async fn size_async(&self) -> std::io::Result<u64> {
+        self.url_resource().size_async().await
+    }
+    async fn read_async(&self) -> std::io::Result<Self::AsyncReader> {
+        self.url_resource().read_async().await
+    }
+}
+
+/// The decompressed GRCh38 dbSNP VCF, cached compressed as it is tens of gigabytes.
+pub async fn load_grch38_dbsnp() -> io::Result<impl BufRead> {
+    load(DbSnpResource::grch38_vcf()).await
+}
+/// The decompressed GRCh37 dbSNP VCF, cached compressed as it is tens of gigabytes.
+pub async fn load_grch37_dbsnp() -> io::Result<impl BufRead> {
+    load(DbSnpResource::grch37_vcf()).await
+}
+async fn load(resource: DbSnpResource) -> io::Result<impl BufRead> {
+    let resource = resource
+        .log_progress()
+        .with_global_fs_cache()
+        .ensure_cached_async()
+        .await?;
+    resource.decompressed().buffered().read()
+}
+
+/// Contigs of a build, as named by their RefSeq accession in dbSNP.
+pub trait RefSeqContig: Sized {
+    /// `None` for unplaced scaffolds, patches and alternate loci.
+    fn from_refseq(accession: &str) -> Option<Self>;
+}
+impl RefSeqContig for GRCh38Contig {
+    fn from_refseq(accession: &str) -> Option<Self> {
+        match refseq_chromosome(accession)? {
+            Chromosome::Autosome(number) => Self::new_chr(number),
+            Chromosome::X => Some(Self::X),
+            Chromosome::Y => Some(Self::Y),
+            Chromosome::Mitochondrion => Some(Self::MT),
+        }
+    }
+}
+impl RefSeqContig for GRCh37Contig {
+    fn from_refseq(accession: &str) -> Option<Self> {
+        match refseq_chromosome(accession)? {
+            Chromosome::Autosome(number) => Self::new_chr(number),
+            Chromosome::X => Some(Self::X),
+            Chromosome::Y => Some(Self::Y),
+            Chromosome::Mitochondrion => Some(Self::MT),
+        }
+    }
+}
+
+enum Chromosome {
+    Autosome(usize),
+    X,
+    Y,
+    Mitochondrion,
+}
+/// The version suffix differs between builds (e.g. `NC_000001.11` and `NC_000001.10`).
+fn refseq_chromosome(accession: &str) -> Option<Chromosome> {
+    let (accession, _version) = accession.split_once('.').unwrap_or((accession, ""));
+    if accession == MITOCHONDRION {
+        return Some(Chromosome::Mitochondrion);
+    }
+    let number: usize = accession.strip_prefix("NC_0000")?.parse().ok()?;
+    match number {
+        1..=22 => Some(Chromosome::Autosome(number)),
+        23 => Some(Chromosome::X),
+        24 => Some(Chromosome::Y),
+        _ => None,
+    }
+}
+
+/// A dbSNP record, which may list several alternate alleles.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DbSnpRecord<C> {
+    pub rsid: RsId,
+    pub at: ContigPosition<C>,
+    pub reference: DnaSequence,
+    /// Alleles that aren't plain bases are skipped.
+    pub alternates: Vec<DnaSequence>,
+}
+
+/// The records of `reader` for the given rsIDs, see [read_vcf].
+///
+/// Merged rsIDs are reported by dbSNP under their current rsID, so they won't be resolved.
+/// If an rsID maps to several positions (e.g. in the pseudoautosomal regions), the first wins.
+pub fn resolve<C>(
+    reader: impl BufRead,
+    rsids: &HashSet<RsId>,
+) -> io::Result<HashMap<RsId, DbSnpRecord<C>>>
+where
+    C: RefSeqContig,
+{
+    let mut resolved = HashMap::new();
+    for record in read_vcf(reader) {
+        let record = record?;
+        if rsids.contains(&record.rsid) {
+            resolved.entry(record.rsid).or_insert(record);
+        }
+    }
+    Ok(resolved)
+}
+
+/// The records of a decompressed dbSNP VCF, see [DbSnpResource].
+///
+/// Records off the primary assembly, or with a non-plain reference allele, are skipped.
+pub fn read_vcf<C>(reader: impl BufRead) -> impl Iterator<Item = io::Result<DbSnpRecord<C>>>
+where
+    C: RefSeqContig,
+{
+    reader
+        .lines()
+        .map(|line| {
+            let line = line?;
+            if line.starts_with('#') || line.is_empty() {
+                return Ok(None);
+            }
+            parse_line(&line)
+        })
+        .filter_map(Result::transpose)
+}
+
+fn parse_line<C>(line: &str) -> io::Result<Option<DbSnpRecord<C>>>
+where
+    C: RefSeqContig,
+{
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Invalid dbSNP line: {line:?}"),
+        )
+    };
+
+    let fields: Vec<_> = line.splitn(6, '\t').collect();
+    let [contig, pos, id, reference, alternates, ..] = fields[..] else {
+        return Err(invalid());
+    };
+
+    let Some(contig) = C::from_refseq(contig) else {
+        return Ok(None);
+    };
+    let at = pos
+        .parse::<u64>()
+        .ok()
+        .and_then(|pos| pos.checked_sub(1))
+        .ok_or_else(invalid)?;
+    let rsid = id.parse().map_err(|_| invalid())?;
+    let Ok(reference) = reference.parse() else {
+        return Ok(None);
+    };
+    let alternates = alternates
+        .split(',')
+        .filter_map(|alternate| alternate.parse().ok())
+        .collect();
+
+    Ok(Some(DbSnpRecord {
+        rsid,
+        at: ContigPosition { contig, at },
+        reference,
+        alternates,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn test_read_vcf() {
+        let vcf = "##fileformat=VCFv4.2\n\
+            #CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\n\
+            NC_000001.11\t10001\trs1570391677\tT\tA,C\t.\t.\tRS=1570391677;dbSNPBuildID=154\n\
+            NT_187361.1\t100\trs1\tA\tG\t.\t.\tRS=1\n\
+            NC_000023.11\t2781480\trs6655397\tG\tN,T\t.\t.\tRS=6655397\n\
+            NC_012920.1\t73\trs3087742\tA\tG\t.\t.\tRS=3087742\n";
+
+        let records: Vec<DbSnpRecord<GRCh38Contig>> = read_vcf(Cursor::new(vcf))
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].rsid, RsId::new(1570391677));
+        assert_eq!(records[0].at.contig, GRCh38Contig::CHR1);
+        assert_eq!(records[0].at.at, 10000);
+        assert_eq!(records[0].alternates.len(), 2);
+        assert_eq!(records[1].at.contig, GRCh38Contig::X);
+        assert_eq!(records[1].alternates, vec!["T".parse().unwrap()]);
+        assert_eq!(records[2].at.contig, GRCh38Contig::MT);
+
+        let resolved: HashMap<_, DbSnpRecord<GRCh37Contig>> = resolve(
+            Cursor::new(vcf),
+            &HashSet::from([RsId::new(6655397), RsId::new(42)]),
+        )
+        .unwrap();
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[&RsId::new(6655397)].at.contig, GRCh37Contig::X);
+
+        assert_eq!(
+            DbSnpResource::grch38_vcf().url().as_str(),
+            "https://ftp.ncbi.nih.gov/snp/latest_release/VCF/GCF_000001405.40.gz"
+        );
+    }
+}
@@ -0,0 +1,275 @@
+//! The gnomAD v4 joint (exomes + genomes) frequency sites, as exported to VCF by Hail.
+//!
+//! https://gnomad.broadinstitute.org/downloads#v4
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    io::{self, BufRead},
+};
+
+use biocore::{dna::DnaSequence, location::ContigPosition};
+use resource::{RawResource, UrlResource};
+use url::Url;
+
+use crate::contig::GRCh38Contig;
+
+const GNOMAD_BUCKET: &str = "gcp-public-data--gnomad";
+const JOINT_SITES_BASE: &str = "release/4.1/vcf/joint";
+
+/// The INFO prefix of the joint allele frequencies, e.g. `AF_joint` and `AF_joint_nfe`.
+const JOINT_AF: &str = "AF_joint";
+/// Sex strata, which are reported alongside the genetic ancestry groups.
+const SEXES: [&str; 2] = ["XX", "XY"];
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GnomadResource {
+    key: String,
+}
+impl GnomadResource {
+    pub fn new(key: String) -> Self {
+        Self { key }
+    }
+
+    /// Only the autosomes and sex chromosomes are released.
+    pub fn joint_sites_vcf(contig: GRCh38Contig) -> Self {
+        Self::new(format!(
+            "{JOINT_SITES_BASE}/gnomad.joint.v4.1.sites.{contig}.vcf.bgz"
+        ))
+    }
+    pub fn joint_sites_vcf_index(contig: GRCh38Contig) -> Self {
+        Self::new(format!(
+            "{JOINT_SITES_BASE}/gnomad.joint.v4.1.sites.{contig}.vcf.bgz.tbi"
+        ))
+    }
+
+    pub fn url(&self) -> Url {
+        let key = &self.key;
+        Url::parse(&format!(
+            "https://storage.googleapis.com/{GNOMAD_BUCKET}/{key}"
+        ))
+        .unwrap()
+    }
+
+    fn url_resource(&self) -> UrlResource {
+        UrlResource::new(self.url()).unwrap()
+    }
+}
+impl RawResource for GnomadResource {
+    const NAMESPACE: &'static str = "gnomad";
+
+    fn key(&self) -> String {
+        self.key.clone()
+    }
+
+    fn compression(&self) -> Option<resource::Compression> {
+        if self.key.ends_with(".tbi") {
+            None
+        } else {
+            resource::Compression::infer_strict(&self.key)
+        }
+    }
+
+    type Reader = <UrlResource as RawResource>::Reader;
+    fn size(&self) -> std::io::Result<u64> {
+        self.url_resource().size()
+    }
+    fn read(&self) -> std::io::Result<Self::Reader> {
+        self.url_resource().read()
+    }
+
+    type AsyncReader = <UrlResource as RawResource>::AsyncReader;
+    async fn size_async(&self) -> std::io::Result<u64> {
+        self.url_resource().size_async().await
+    }
+    async fn read_async(&self) -> std::io::Result<Self::AsyncReader> {
+        self.url_resource().read_async().await
+    }
+}
+
+/// A single alternate allele, gnomAD sites are already split into biallelic records.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GnomadSite {
+    pub at: ContigPosition<GRCh38Contig>,
+    pub reference: DnaSequence,
+    pub alternate: DnaSequence,
+    /// Empty if the site passed all filters.
+    pub filters: Vec<String>,
+    /// `None` if no samples were called.
+    pub af: Option<f64>,
+    /// By genetic ancestry group (e.g. `nfe`, `afr`, `remaining`).
+    pub population_af: BTreeMap<String, f64>,
+}
+
+/// A position and its reference and alternate alleles, to look up frequencies by variant.
+pub type AlleleKey = (ContigPosition<GRCh38Contig>, DnaSequence, DnaSequence);
+
+impl GnomadSite {
+    pub fn is_pass(&self) -> bool {
+        self.filters.is_empty()
+    }
+    pub fn key(&self) -> AlleleKey {
+        (self.at, self.reference.clone(), self.alternate.clone())
+    }
+}
+
+/// The sites of `reader` for the given alleles, by [AlleleKey], see [read_joint_sites].
+///
+/// The file is streamed, only the requested sites are kept.
+pub fn joint_frequencies(
+    reader: impl BufRead,
+    alleles: &HashSet<AlleleKey>,
+) -> io::Result<HashMap<AlleleKey, GnomadSite>> {
+    let mut found = HashMap::new();
+    for site in read_joint_sites(reader) {
+        let site = site?;
+        let key = site.key();
+        if alleles.contains(&key) {
+            found.insert(key, site);
+        }
+    }
+    Ok(found)
+}
+
+/// The sites of a decompressed joint sites VCF, see [GnomadResource::joint_sites_vcf].
+///
+/// Alleles that aren't plain bases (e.g. `*`) are skipped.
+pub fn read_joint_sites(reader: impl BufRead) -> impl Iterator<Item = io::Result<GnomadSite>> {
+    reader
+        .lines()
+        .map(|line| {
+            let line = line?;
+            if line.starts_with('#') || line.is_empty() {
+                return Ok(None);
+            }
+            parse_line(&line)
+        })
+        .filter_map(Result::transpose)
+}
+
+fn parse_line(line: &str) -> io::Result<Option<GnomadSite>> {
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Invalid gnomAD line: {line:?}"),
+        )
+    };
+
+    let fields: Vec<_> = line.splitn(9, '\t').collect();
+    let [
+        contig,
+        pos,
+        _id,
+        reference,
+        alternate,
+        _qual,
+        filter,
+        info,
+        ..,
+    ] = fields[..]
+    else {
+        return Err(invalid());
+    };
+
+    let contig = GRCh38Contig::new(contig).ok_or_else(invalid)?;
+    let at = pos
+        .parse::<u64>()
+        .ok()
+        .and_then(|pos| pos.checked_sub(1))
+        .ok_or_else(invalid)?;
+    let (Ok(reference), Ok(alternate)) = (reference.parse(), alternate.parse()) else {
+        return Ok(None);
+    };
+    let filters = match filter {
+        "PASS" | "." => vec![],
+        filter => filter.split(';').map(str::to_owned).collect(),
+    };
+
+    let mut af = None;
+    let mut population_af = BTreeMap::new();
+    for entry in info.split(';') {
+        let Some((key, value)) = entry.split_once('=') else {
+            continue;
+        };
+        let Some(group) = key.strip_prefix(JOINT_AF) else {
+            continue;
+        };
+        if value == "." {
+            continue;
+        }
+        let value: f64 = value.parse().map_err(|_| invalid())?;
+        match group.strip_prefix('_') {
+            None if group.is_empty() => af = Some(value),
+            Some(group) if !group.contains('_') && !SEXES.contains(&group) => {
+                population_af.insert(group.to_owned(), value);
+            }
+            _ => {}
+        }
+    }
+
+    Ok(Some(GnomadSite {
+        at: ContigPosition { contig, at },
+        reference,
+        alternate,
+        filters,
+        af,
+        population_af,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn test_read_joint_sites() {
+        let vcf = "##fileformat=VCFv4.2\n\
+            #CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\n\
+            chr1\t10001\trs1570391677\tT\tC\t.\tAC0\tAC_joint=0;AF_joint=0.00000;AF_joint_XX=0.0\n\
+            chr1\t10002\t.\tA\tG\t.\tPASS\tAF_joint=0.25;AF_joint_nfe=0.5;AF_joint_nfe_XX=0.4;AF_joint_afr=0.1;AF_exomes=0.3\n\
+            chr1\t10003\t.\tA\t*\t.\tPASS\tAF_joint=0.1\n";
+        let sites: Vec<_> = read_joint_sites(Cursor::new(vcf))
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(sites.len(), 2);
+
+        assert_eq!(sites[0].at.at, 10000);
+        assert!(!sites[0].is_pass());
+        assert_eq!(sites[0].af, Some(0.));
+        assert!(sites[0].population_af.is_empty());
+
+        assert!(sites[1].is_pass());
+        assert_eq!(sites[1].af, Some(0.25));
+        assert_eq!(
+            sites[1].population_af,
+            BTreeMap::from([("afr".to_owned(), 0.1), ("nfe".to_owned(), 0.5)])
+        );
+
+        let key = |at, alternate: &str| {
+            let at = ContigPosition {
+                contig: GRCh38Contig::CHR1,
+                at,
+            };
+            (at, "A".parse().unwrap(), alternate.parse().unwrap())
+        };
+        let frequencies = joint_frequencies(
+            Cursor::new(vcf),
+            &HashSet::from([key(10001, "G"), key(10001, "T")]),
+        )
+        .unwrap();
+        assert_eq!(frequencies.len(), 1);
+        assert_eq!(frequencies[&key(10001, "G")].population_af["nfe"], 0.5);
+
+        assert_eq!(
+            GnomadResource::joint_sites_vcf(GRCh38Contig::CHR1).key(),
+            "release/4.1/vcf/joint/gnomad.joint.v4.1.sites.chr1.vcf.bgz"
+        );
+        assert_eq!(
+            GnomadResource::joint_sites_vcf(GRCh38Contig::CHR1)
+                .url()
+                .as_str(),
+            "https://storage.googleapis.com/gcp-public-data--gnomad/release/4.1/vcf/joint/gnomad.joint.v4.1.sites.chr1.vcf.bgz"
+        );
+    }
+}
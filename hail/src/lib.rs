@@ -1,6 +1,8 @@
 #![feature(ascii_char)]
 
 pub mod contig;
+pub mod dbsnp;
+pub mod gnomad;
 pub mod source;
 
 use resource::{RawResource, RawResourceExt};
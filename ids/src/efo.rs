@@ -0,0 +1,138 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Ontology trait ID as used by the Experimental Factor Ontology (e.g. 'EFO_0000305').
+///
+/// The EFO imports terms from other ontologies, so the prefix is not always 'EFO'
+/// (e.g. 'MONDO_0004975', 'HP_0000822', 'Orphanet_100').
+/// The 'EFO:0000305' form is accepted too.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EfoId(String);
+impl EfoId {
+    /// The ontology the term comes from, e.g. 'EFO' or 'MONDO'.
+    pub fn ontology(&self) -> &str {
+        self.0.split_once('_').unwrap().0
+    }
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn url(&self) -> String {
+        format!("https://www.ebi.ac.uk/efo/{self}")
+    }
+}
+
+impl fmt::Display for EfoId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+impl FromStr for EfoId {
+    type Err = EfoIdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let unexpected = || EfoIdError::UnexpectedValue(s.to_owned());
+        let (ontology, number) = s.split_once(['_', ':']).ok_or_else(unexpected)?;
+        if ontology.is_empty() || !ontology.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Err(unexpected());
+        }
+        if number.is_empty() || !number.chars().all(|c| c.is_ascii_digit()) {
+            return Err(unexpected());
+        }
+        Ok(Self(format!("{ontology}_{number}")))
+    }
+}
+
+impl Serialize for EfoId {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.0.serialize(serializer)
+    }
+}
+impl<'de> Deserialize<'de> for EfoId {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct Visitor;
+        impl<'de> serde::de::Visitor<'de> for Visitor {
+            type Value = EfoId;
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("an ontology trait ID (e.g. 'EFO_0000305')")
+            }
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                v.parse().map_err(|e| serde::de::Error::custom(e))
+            }
+            fn visit_borrowed_str<E>(self, v: &'de str) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                self.visit_str(v)
+            }
+            fn visit_string<E>(self, v: String) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                self.visit_str(&v)
+            }
+        }
+        deserializer.deserialize_str(Visitor)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum EfoIdError {
+    #[error("Expected an ontology trait ID (e.g. 'EFO_0000305'), found: '{0}'.")]
+    UnexpectedValue(String),
+}
+impl From<EfoIdError> for std::io::Error {
+    fn from(value: EfoIdError) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EfoId, EfoIdError};
+
+    #[test]
+    fn parse() {
+        let id: EfoId = "EFO_0000305".parse().unwrap();
+        assert_eq!(id.as_str(), "EFO_0000305");
+        assert_eq!(id.ontology(), "EFO");
+        assert_eq!(id.url(), "https://www.ebi.ac.uk/efo/EFO_0000305");
+
+        assert_eq!("EFO:0000305".parse::<EfoId>().unwrap(), id);
+        assert_eq!(
+            "MONDO_0004975".parse::<EfoId>().unwrap().ontology(),
+            "MONDO"
+        );
+        assert_eq!(
+            "Orphanet_100".parse::<EfoId>().unwrap().to_string(),
+            "Orphanet_100"
+        );
+
+        for invalid in [
+            "",
+            "EFO",
+            "EFO_",
+            "_0000305",
+            "EFO-0000305",
+            "EFO_00a05",
+            "EF0_0000305",
+            "0000305",
+        ] {
+            assert_eq!(
+                invalid.parse::<EfoId>(),
+                Err(EfoIdError::UnexpectedValue(invalid.to_owned())),
+                "{invalid}"
+            );
+        }
+    }
+}
@@ -1,3 +1,4 @@
+pub mod efo;
 pub mod pgs;
 pub mod pubmed;
 pub mod rs;
@@ -11,6 +11,10 @@ impl RsId {
     pub fn try_new(id: u64) -> Result<Self, RsIdError> {
         Ok(Self(NonZero::new(id).ok_or(RsIdError::Zero)?))
     }
+    /// The number of the rsID (`rs42` → `42`).
+    pub fn get(self) -> u64 {
+        self.0.get()
+    }
 }
 impl fmt::Display for RsId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
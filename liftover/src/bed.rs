@@ -0,0 +1,304 @@
+//! Lifting BED3 to BED12 records, mirroring UCSC `liftOver`.
+//!
+//! A record is lifted with the single chain that maps at least [BedLiftover::with_min_match]
+//! of its bases, and spans from the first to the last mapped base on that chain.
+//! BED12 blocks must each map whole onto the same chain. Records that can't be lifted are
+//! written to the unmapped output, each preceded by a `#reason` line as `liftOver` does.
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    io::{self, Write},
+    ops::Range,
+};
+
+use biocore::{
+    bed::{BedRecord, BedWriter},
+    genome::{ArcContig, Contig},
+    location::{
+        ContigRange,
+        orientation::{SequenceOrientation, Stranded},
+    },
+};
+use resource::RawResource;
+use utile::range::RangeLen;
+
+use crate::{Chain, Liftover};
+
+/// The default of UCSC `liftOver -minMatch`.
+const DEFAULT_MIN_MATCH: f64 = 0.95;
+
+/// Lifts BED records with a [Liftover] (which, unlike [crate::LiftoverIndexed], keeps chains apart).
+#[derive(Debug, Clone, Copy)]
+pub struct BedLiftover<'a, From = ArcContig, To = ArcContig> {
+    liftover: &'a Liftover<From, To>,
+    min_match: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum BedRejectReason {
+    /// No chain maps any base of the record.
+    DeletedInNew,
+    /// No chain maps enough bases of the record.
+    PartiallyDeletedInNew,
+    /// More than one chain maps enough bases of the record.
+    DuplicatedInNew,
+    /// A BED12 block doesn't map whole onto the chosen chain.
+    SplitInNew,
+}
+
+/// Counts of lifted and rejected records, see [BedLiftover::lift].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct BedLiftoverSummary {
+    pub lifted: usize,
+    pub rejected: BTreeMap<BedRejectReason, usize>,
+}
+
+impl<'a, From, To> BedLiftover<'a, From, To> {
+    pub fn new(liftover: &'a Liftover<From, To>) -> Self {
+        Self {
+            liftover,
+            min_match: DEFAULT_MIN_MATCH,
+        }
+    }
+    /// The minimum fraction of bases that must map, `0.95` by default.
+    pub fn with_min_match(mut self, min_match: f64) -> Self {
+        self.min_match = min_match;
+        self
+    }
+}
+impl<From, To> BedLiftover<'_, From, To>
+where
+    From: Contig + Clone,
+    To: Contig + Clone,
+{
+    /// Reads a (possibly compressed) BED file from a resource and lifts it, see [Self::lift].
+    pub fn lift_resource(
+        &self,
+        resource: impl RawResource,
+        mapped: impl Write,
+        unmapped: impl Write,
+    ) -> io::Result<BedLiftoverSummary> {
+        self.lift(biocore::bed::load::<String>(resource)?, mapped, unmapped)
+    }
+    /// Lifts all records, writing them to `mapped` or `unmapped`.
+    pub fn lift<C: AsRef<str>>(
+        &self,
+        records: impl IntoIterator<Item = io::Result<BedRecord<C>>>,
+        mapped: impl Write,
+        unmapped: impl Write,
+    ) -> io::Result<BedLiftoverSummary> {
+        let mut mapped = BedWriter::new(mapped);
+        let mut unmapped = BedWriter::new(unmapped);
+        let mut summary = BedLiftoverSummary::default();
+        for record in records {
+            let record = record?;
+            match self.lift_record(&record) {
+                Ok(lifted) => {
+                    summary.lifted += 1;
+                    mapped.write_record(&lifted)?;
+                }
+                Err(reason) => {
+                    *summary.rejected.entry(reason).or_default() += 1;
+                    writeln!(unmapped.get_mut(), "#{reason}")?;
+                    unmapped.write_record(&record)?;
+                }
+            }
+        }
+        mapped.get_mut().flush()?;
+        unmapped.get_mut().flush()?;
+        Ok(summary)
+    }
+
+    pub fn lift_record<C: AsRef<str>>(
+        &self,
+        record: &BedRecord<C>,
+    ) -> Result<BedRecord<To>, BedRejectReason> {
+        let Some(contig) = self.liftover.find_input_contig(&record.range.contig) else {
+            return Err(BedRejectReason::DeletedInNew);
+        };
+        let at = record.range.at.clone();
+        // Zero-length records (insertion points) are lifted with the base after them.
+        let query = if at.is_empty() {
+            at.start..at.start + 1
+        } else {
+            at.clone()
+        };
+
+        let mut overlapping = false;
+        let mut matching = vec![];
+        for chain in &self.liftover.chains {
+            if chain.header.t.v.contig != contig {
+                continue;
+            }
+            let mapped: u64 = segments(chain, &contig, query.clone())
+                .iter()
+                .map(|s| s.range_len())
+                .sum();
+            overlapping |= mapped > 0;
+            if mapped > 0 && mapped as f64 >= self.min_match * query.range_len() as f64 {
+                matching.push(chain);
+            }
+        }
+        let chain = match &matching[..] {
+            [] if !overlapping => return Err(BedRejectReason::DeletedInNew),
+            [] => return Err(BedRejectReason::PartiallyDeletedInNew),
+            [chain] => *chain,
+            _ => return Err(BedRejectReason::DuplicatedInNew),
+        };
+        let flipped = chain.header.t.orientation != chain.header.q.orientation;
+        let lift = |at: Range<u64>| hull(segments(chain, &contig, at));
+
+        let mut range = lift(query).unwrap();
+        if at.is_empty() {
+            let point = if flipped { range.end } else { range.start };
+            range = point..point;
+        }
+
+        let blocks = match &record.blocks {
+            None => None,
+            Some(blocks) => {
+                let mut lifted = vec![];
+                for block in blocks {
+                    let segments = segments(chain, &contig, block.clone());
+                    match &segments[..] {
+                        [s] if s.range_len() == block.range_len() => lifted.push(s.clone()),
+                        _ => return Err(BedRejectReason::SplitInNew),
+                    }
+                }
+                lifted.sort_unstable_by_key(|b| b.start);
+                if let (Some(first), Some(last)) = (lifted.first(), lifted.last()) {
+                    range = first.start..last.end;
+                }
+                Some(lifted)
+            }
+        };
+
+        let thick = record.thick.as_ref().map(|thick| {
+            let empty = range.start..range.start;
+            if thick.is_empty() {
+                return empty;
+            }
+            match lift(thick.clone()) {
+                Some(t) => t.start.max(range.start)..t.end.min(range.end),
+                None => empty,
+            }
+        });
+
+        Ok(BedRecord {
+            range: ContigRange {
+                contig: chain.header.q.v.contig.clone(),
+                at: range,
+            },
+            name: record.name.clone(),
+            score: record.score,
+            strand: record.strand.map(|s| if flipped { s.flip() } else { s }),
+            thick,
+            item_rgb: record.item_rgb,
+            blocks,
+        })
+    }
+}
+
+/// The target ranges (on the forward strand) that `at` maps to on a chain.
+fn segments<From, To>(chain: &Chain<From, To>, contig: &From, at: Range<u64>) -> Vec<Range<u64>>
+where
+    From: Contig + Clone,
+    To: Contig + Clone,
+{
+    let range = Stranded {
+        orientation: SequenceOrientation::Forward,
+        v: ContigRange {
+            contig: contig.clone(),
+            at,
+        },
+    };
+    chain
+        .map_range_raw(&range)
+        .map(|mut r| {
+            r.set_orientation(SequenceOrientation::Forward);
+            r.v.at
+        })
+        .collect()
+}
+fn hull(ranges: Vec<Range<u64>>) -> Option<Range<u64>> {
+    let start = ranges.iter().map(|r| r.start).min()?;
+    let end = ranges.iter().map(|r| r.end).max()?;
+    Some(start..end)
+}
+
+impl fmt::Display for BedRejectReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::DeletedInNew => "Deleted in new",
+            Self::PartiallyDeletedInNew => "Partially deleted in new",
+            Self::DuplicatedInNew => "Duplicated in new",
+            Self::SplitInNew => "Split in new",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use biocore::bed::BedReader;
+
+    use crate::{Liftover, tests::CHAIN};
+
+    use super::{BedLiftover, BedLiftoverSummary, BedRejectReason};
+
+    #[test]
+    fn lift() {
+        let liftover = Liftover::read(CHAIN.as_bytes()).unwrap();
+        let input = "\
+chr1\t0\t10\ta
+chr1\t15\t30\tb
+chr1\t60\t70\tc
+chr2\t2\t5\td\t0\t+
+chr1\t0\t40\te\t0\t+\t2\t38\t0\t2\t10,10,\t0,30,
+";
+        let (mut mapped, mut unmapped) = (vec![], vec![]);
+        let summary = BedLiftover::new(&liftover)
+            .lift(
+                BedReader::<_, String>::new(input.as_bytes()),
+                &mut mapped,
+                &mut unmapped,
+            )
+            .unwrap();
+        assert_eq!(
+            summary,
+            BedLiftoverSummary {
+                lifted: 2,
+                rejected: BTreeMap::from([
+                    (BedRejectReason::DeletedInNew, 1),
+                    (BedRejectReason::PartiallyDeletedInNew, 2),
+                ]),
+            }
+        );
+        assert_eq!(
+            String::from_utf8(mapped).unwrap(),
+            "chrA\t10\t20\ta\nchrB\t95\t98\td\t0\t-\n"
+        );
+        assert!(
+            String::from_utf8(unmapped)
+                .unwrap()
+                .starts_with("#Partially deleted in new\nchr1\t15\t30\tb\n#Deleted in new\n")
+        );
+
+        // 35 of the 40 bases map.
+        let (mut mapped, mut unmapped) = (vec![], vec![]);
+        BedLiftover::new(&liftover)
+            .with_min_match(0.8)
+            .lift(
+                BedReader::<_, String>::new(input.lines().last().unwrap().as_bytes()),
+                &mut mapped,
+                &mut unmapped,
+            )
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(mapped).unwrap(),
+            "chrA\t10\t45\te\t0\t+\t12\t43\t0\t2\t10,10,\t0,25,\n"
+        );
+    }
+}
@@ -3,22 +3,41 @@
 #![feature(btree_set_entry)]
 
 mod parse;
+mod serialize;
+mod validate;
+mod write;
 
+pub mod bed;
 pub mod bindings;
 pub mod sources;
+pub mod vcf;
+pub mod verify;
 
+pub use self::{
+    validate::{ChainOverlap, ChainReport, ContigCoverage},
+    write::ChainError,
+};
+
+use indicatif::{ProgressBar, ProgressStyle};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
 use std::{cmp, collections::BTreeMap, ops::Range};
 
 use utile::range::{RangeExt, RangeLen};
 
 use biocore::{
-    genome::{ArcContig, Contig},
+    genome::{
+        ArcContig, Contig,
+        alias::{ContigAliases, UnknownContigError},
+    },
     location::{
         ContigPosition, ContigRange,
+        build::BuildContig,
         orientation::{SequenceOrientation, Stranded},
     },
 };
 
+const PROGRESS_BAR_STYLE: &str = "[Liftover] Indexing {pos}/{len} chains {wide_bar} {eta}";
+
 /// https://genome.ucsc.edu/goldenPath/help/chain.html
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Liftover<From = ArcContig, To = ArcContig> {
@@ -47,6 +66,21 @@ pub struct ChainHeader<From, To> {
     pub id: u32,
 }
 pub type ChainRange<C> = Stranded<ContigRange<C>>;
+/// A mapped location on the forward strand, with the strand that the input's forward strand
+/// mapped to. [SequenceOrientation::Reverse] means the chain flips strands, so alleles and
+/// sequences need to be reverse-complemented.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct WithOrientation<T> {
+    pub orientation: SequenceOrientation,
+    pub v: T,
+}
+/// A range mapped with [Liftover::map_range_report] or [LiftoverIndexed::map_range_report].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LiftoverRangeReport<To> {
+    pub mapped: Vec<ContigRange<To>>,
+    /// The parts of the input range (on the forward strand) that fall in no chain block.
+    pub unmapped: Vec<Range<u64>>,
+}
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct AlignmentBlock {
     /// the size of the ungapped alignment
@@ -58,12 +92,25 @@ pub struct AlignmentBlock {
 }
 
 impl<From, To> Liftover<From, To> {
+    /// Builds an index for fast lookups, one contig per thread.
     pub fn indexed(&self) -> LiftoverIndexed<From, To>
     where
-        From: Contig + Ord + Clone,
-        To: Contig + Clone,
+        From: Contig + Ord + Clone + Send + Sync,
+        To: Contig + Clone + Send + Sync,
+    {
+        LiftoverIndexed::from_liftover(self, &ProgressBar::hidden())
+    }
+    /// Same as [Self::indexed], but shows a progress bar (in chains processed).
+    pub fn indexed_with_progress(&self) -> LiftoverIndexed<From, To>
+    where
+        From: Contig + Ord + Clone + Send + Sync,
+        To: Contig + Clone + Send + Sync,
     {
-        LiftoverIndexed::from_liftover(self)
+        let style = ProgressStyle::with_template(PROGRESS_BAR_STYLE).unwrap();
+        let progress = ProgressBar::new(0).with_style(style);
+        let indexed = LiftoverIndexed::from_liftover(self, &progress);
+        progress.finish();
+        indexed
     }
 
     pub fn find_input_contig(&self, contig: impl AsRef<str>) -> Option<From>
@@ -72,6 +119,56 @@ impl<From, To> Liftover<From, To> {
     {
         self.contigs.get(contig.as_ref()).cloned()
     }
+    /// Like [Self::find_input_contig], but falls back to `aliases` for unknown names.
+    pub fn resolve_input_contig(
+        &self,
+        contig: impl AsRef<str>,
+        aliases: &ContigAliases<From>,
+    ) -> Result<From, UnknownContigError>
+    where
+        From: Clone,
+    {
+        let contig = contig.as_ref();
+        self.find_input_contig(contig)
+            .or_else(|| aliases.get(contig).cloned())
+            .ok_or_else(|| UnknownContigError(contig.to_owned()))
+    }
+    /// The input contigs, e.g. to build [ContigAliases] with.
+    pub fn input_contigs(&self) -> impl Iterator<Item = &From>
+    where
+        From: AsRef<str>,
+    {
+        // Skip the aliases.
+        self.contigs
+            .iter()
+            .filter(|(name, contig)| name.as_str() == contig.as_ref())
+            .map(|(_, contig)| contig)
+    }
+
+    /// Makes [Self::find_input_contig] (and so mapping) accept every name in the synonym row
+    /// of a known input contig, e.g. `1` or `NC_000001.11` for `chr1` with
+    /// [biocore::genome::alias::HUMAN_GRCH38].
+    pub fn with_contig_synonyms<const N: usize>(self, synonyms: &[[&str; N]]) -> Self
+    where
+        From: Clone + AsRef<str>,
+    {
+        let aliases = ContigAliases::from_synonyms(synonyms, self.contigs.values().cloned());
+        self.with_contig_aliases(&aliases)
+    }
+    /// Makes [Self::find_input_contig] (and so mapping) accept every alias in `aliases`.
+    ///
+    /// Names that already resolve to an input contig are left as they are.
+    pub fn with_contig_aliases(mut self, aliases: &ContigAliases<From>) -> Self
+    where
+        From: Clone,
+    {
+        for (alias, contig) in aliases.iter() {
+            if !self.contigs.contains_key(alias) {
+                self.contigs.insert(alias.to_owned(), contig.clone());
+            }
+        }
+        self
+    }
 
     pub fn upgrade_contigs<NewFrom, NewTo>(
         self,
@@ -100,6 +197,25 @@ where
         &self,
         loc: ContigPosition<C>,
     ) -> impl Iterator<Item = ContigPosition<To>> + use<'_, From, To, C>
+    where
+        From: Clone,
+    {
+        self.map_with_orientation(loc).map(|l| l.v)
+    }
+    pub fn map_range<C: AsRef<str>>(
+        &self,
+        range: ContigRange<C>,
+    ) -> impl Iterator<Item = ContigRange<To>> + use<'_, From, To, C>
+    where
+        From: Clone,
+    {
+        self.map_range_with_orientation(range).map(|r| r.v)
+    }
+    /// Like [Self::map], but also reports whether each result came from a strand-flipping chain.
+    pub fn map_with_orientation<C: AsRef<str>>(
+        &self,
+        loc: ContigPosition<C>,
+    ) -> impl Iterator<Item = WithOrientation<ContigPosition<To>>> + use<'_, From, To, C>
     where
         From: Clone,
     {
@@ -116,17 +232,15 @@ where
             },
         };
 
-        Some(self.map_raw(loc).map(|mut r| {
-            r.set_orientation(SequenceOrientation::Forward);
-            r.v
-        }))
-        .into_iter()
-        .flatten()
+        Some(self.map_raw(loc).map(WithOrientation::from_raw_position))
+            .into_iter()
+            .flatten()
     }
-    pub fn map_range<C: AsRef<str>>(
+    /// Like [Self::map_range], see [Self::map_with_orientation].
+    pub fn map_range_with_orientation<C: AsRef<str>>(
         &self,
         range: ContigRange<C>,
-    ) -> impl Iterator<Item = ContigRange<To>> + use<'_, From, To, C>
+    ) -> impl Iterator<Item = WithOrientation<ContigRange<To>>> + use<'_, From, To, C>
     where
         From: Clone,
     {
@@ -143,13 +257,140 @@ where
             },
         };
 
-        Some(self.map_range_raw(range).map(|mut r| {
-            r.set_orientation(SequenceOrientation::Forward);
-            r.v
-        }))
+        Some(
+            self.map_range_raw(range)
+                .map(WithOrientation::from_raw_range),
+        )
+        .into_iter()
+        .flatten()
+    }
+
+    /// Like [Self::map_range], but only through the chains whose blocks cover at least
+    /// `min_match` of the range's bases, as with
+    /// [UcscLiftoverSettings::min_match](crate::bindings::ucsc::UcscLiftoverSettings::min_match).
+    pub fn map_range_with<C: AsRef<str>>(
+        &self,
+        range: ContigRange<C>,
+        min_match: f64,
+    ) -> impl Iterator<Item = ContigRange<To>> + use<'_, From, To, C>
+    where
+        From: Clone + PartialEq,
+    {
+        let Some(contig) = self.find_input_contig(&range.contig) else {
+            log::warn!("[Liftover] Unknown contig: {}", range.contig.as_ref());
+            return None.into_iter().flatten();
+        };
+        let range = Stranded {
+            orientation: SequenceOrientation::Forward,
+            v: ContigRange {
+                contig,
+                at: range.at,
+            },
+        };
+
+        let chains: Vec<_> = self
+            .chains
+            .iter()
+            .filter(|chain| chain.header.t.v.contig == range.v.contig)
+            .filter(|chain| {
+                let unmapped = chain.unmapped_parts(&range.v.contig, &range.v.at);
+                is_min_match(&unmapped, &range.v.at, min_match)
+            })
+            .collect();
+
+        Some(
+            chains
+                .into_iter()
+                .flat_map(move |chain| chain.map_range_raw(&range))
+                .map(|r| WithOrientation::from_raw_range(r).v),
+        )
         .into_iter()
         .flatten()
     }
+    /// Like [Self::map_range], but also returns the parts of the range that don't map
+    /// (e.g. because they were deleted in the output assembly).
+    pub fn map_range_report<C: AsRef<str>>(&self, range: ContigRange<C>) -> LiftoverRangeReport<To>
+    where
+        From: Clone + PartialEq,
+    {
+        let unmapped = self
+            .unmapped_parts(&range)
+            .unwrap_or_else(|| vec![range.at.clone()]);
+        LiftoverRangeReport {
+            mapped: self.map_range(range).collect(),
+            unmapped,
+        }
+    }
+    /// The parts of `range` in no chain block, or `None` if the contig is unknown.
+    fn unmapped_parts<C: AsRef<str>>(&self, range: &ContigRange<C>) -> Option<Vec<Range<u64>>>
+    where
+        From: Clone + PartialEq,
+    {
+        let contig = self.find_input_contig(&range.contig)?;
+        let covered = self
+            .iter_ranges()
+            .filter(|(from, _)| from.v.contig == contig)
+            .map(|(mut from, _)| {
+                from.set_orientation(SequenceOrientation::Forward);
+                from.v.at.intersection(range.at.clone())
+            })
+            .collect();
+        Some(gaps(covered, &range.at))
+    }
+
+    /// Like [Self::map], but resolves the contig through `aliases` if it isn't a known
+    /// input contig name, and reports unknown contigs instead of mapping to nothing.
+    pub fn map_with_aliases<C: AsRef<str>>(
+        &self,
+        loc: ContigPosition<C>,
+        aliases: &ContigAliases<From>,
+    ) -> Result<impl Iterator<Item = ContigPosition<To>> + use<'_, From, To>, UnknownContigError>
+    where
+        From: Clone,
+    {
+        let contig = self.resolve_input_contig(&loc.contig, aliases)?;
+        Ok(self.map(ContigPosition { contig, at: loc.at }))
+    }
+    /// Like [Self::map_range], see [Self::map_with_aliases].
+    pub fn map_range_with_aliases<C: AsRef<str>>(
+        &self,
+        range: ContigRange<C>,
+        aliases: &ContigAliases<From>,
+    ) -> Result<impl Iterator<Item = ContigRange<To>> + use<'_, From, To>, UnknownContigError>
+    where
+        From: Clone,
+    {
+        let contig = self.resolve_input_contig(&range.contig, aliases)?;
+        Ok(self.map_range(ContigRange {
+            contig,
+            at: range.at,
+        }))
+    }
+
+    /// Like [Self::map], but the position must be on the input [Build](biocore::location::build::Build).
+    pub fn lift<C>(
+        &self,
+        loc: ContigPosition<C>,
+    ) -> impl Iterator<Item = ContigPosition<To>> + use<'_, From, To, C>
+    where
+        From: BuildContig + Clone,
+        To: BuildContig,
+        C: BuildContig<Build = From::Build>,
+    {
+        self.map(loc)
+    }
+    /// Like [Self::map_range], but the range must be on the input [Build](biocore::location::build::Build).
+    pub fn lift_range<C>(
+        &self,
+        range: ContigRange<C>,
+    ) -> impl Iterator<Item = ContigRange<To>> + use<'_, From, To, C>
+    where
+        From: BuildContig + Clone,
+        To: BuildContig,
+        C: BuildContig<Build = From::Build>,
+    {
+        self.map_range(range)
+    }
 
     pub fn map_raw(
         &self,
@@ -325,6 +566,22 @@ impl<From, To> Liftover<From, To> {
     }
 }
 impl<From, To> Chain<From, To> {
+    /// The parts of `at` (on the forward strand of `contig`) in no block of this chain.
+    fn unmapped_parts(&self, contig: &From, at: &Range<u64>) -> Vec<Range<u64>>
+    where
+        From: Contig + Clone + PartialEq,
+        To: Clone,
+    {
+        let covered = self
+            .iter_ranges()
+            .filter(|(from, _)| &from.v.contig == contig)
+            .map(|(mut from, _)| {
+                from.set_orientation(SequenceOrientation::Forward);
+                from.v.at.intersection(at.clone())
+            })
+            .collect();
+        gaps(covered, at)
+    }
     fn iter_ranges(
         &self,
     ) -> impl Iterator<Item = (ChainRange<From>, ChainRange<To>)> + use<'_, From, To>
@@ -378,45 +635,68 @@ struct LiftoverIndexedEntry<Out> {
     range: Range<u64>,
     max: u64,
     data: ChainRange<Out>,
+    /// The index of the chain in [Liftover::chains].
+    chain: usize,
 }
 impl<From, To> LiftoverIndexed<From, To> {
-    fn from_liftover(liftover: &Liftover<From, To>) -> Self
+    fn from_liftover(liftover: &Liftover<From, To>, progress: &ProgressBar) -> Self
     where
-        From: Contig + Ord + Clone,
-        To: Contig + Clone,
+        From: Contig + Ord + Clone + Send + Sync,
+        To: Contig + Clone + Send + Sync,
     {
-        let mut chromosomes: BTreeMap<From, Vec<LiftoverIndexedEntry<To>>> = BTreeMap::new();
-        let mut contigs: BTreeMap<String, From> = BTreeMap::new();
-
-        for (mut from, mut to) in liftover.iter_ranges() {
-            assert!(!from.v.is_empty());
-            assert!(!to.v.is_empty());
-
-            if from.orientation != SequenceOrientation::Forward {
-                from = from.flip_orientation();
-                to = to.flip_orientation();
-            }
+        progress.set_length(liftover.chains.len() as u64);
+
+        // Flipping orientation doesn't change the contig, so each chain
+        // contributes to exactly one input contig and shards can be built independently.
+        let mut shards: BTreeMap<&From, Vec<(usize, &Chain<From, To>)>> = BTreeMap::new();
+        for (i, chain) in liftover.chains.iter().enumerate() {
+            shards
+                .entry(&chain.header.t.v.contig)
+                .or_default()
+                .push((i, chain));
+        }
 
-            let chr = chromosomes.entry(from.v.contig.clone()).or_default();
+        let chromosomes: BTreeMap<From, Vec<LiftoverIndexedEntry<To>>> = shards
+            .into_par_iter()
+            .map(|(contig, chains)| {
+                let mut entries = vec![];
+
+                for (i, chain) in chains {
+                    for (mut from, mut to) in chain.iter_ranges() {
+                        assert!(!from.v.is_empty());
+                        assert!(!to.v.is_empty());
+
+                        if from.orientation != SequenceOrientation::Forward {
+                            from = from.flip_orientation();
+                            to = to.flip_orientation();
+                        }
+
+                        entries.push(LiftoverIndexedEntry {
+                            range: from.v.at,
+                            max: 0,
+                            data: to,
+                            chain: i,
+                        });
+                    }
+                    progress.inc(1);
+                }
 
-            chr.push(LiftoverIndexedEntry {
-                range: from.v.at,
-                max: 0,
-                data: to,
-            });
+                entries.sort_unstable_by_key(|e| (e.range.start, e.range.end));
+                if !entries.is_empty() {
+                    entries[0].max = entries[0].range.end;
+                }
+                for i in 1..entries.len() {
+                    entries[i].max = cmp::max(entries[i - 1].max, entries[i].range.end);
+                }
 
-            contigs.insert(from.v.contig.as_ref().to_owned(), from.v.contig.clone());
-        }
+                (contig.clone(), entries)
+            })
+            .collect();
 
-        for entries in chromosomes.values_mut() {
-            entries.sort_unstable_by_key(|e| (e.range.start, e.range.end));
-            if !entries.is_empty() {
-                entries[0].max = entries[0].range.end;
-            }
-            for i in 1..entries.len() {
-                entries[i].max = cmp::max(entries[i - 1].max, entries[i].range.end);
-            }
-        }
+        let contigs = chromosomes
+            .keys()
+            .map(|c| (c.as_ref().to_owned(), c.clone()))
+            .collect();
 
         Self {
             chromosomes,
@@ -430,6 +710,56 @@ impl<From, To> LiftoverIndexed<From, To> {
     {
         self.contigs.get(contig.as_ref()).cloned()
     }
+    /// Like [Self::find_input_contig], but falls back to `aliases` for unknown names.
+    pub fn resolve_input_contig(
+        &self,
+        contig: impl AsRef<str>,
+        aliases: &ContigAliases<From>,
+    ) -> Result<From, UnknownContigError>
+    where
+        From: Clone,
+    {
+        let contig = contig.as_ref();
+        self.find_input_contig(contig)
+            .or_else(|| aliases.get(contig).cloned())
+            .ok_or_else(|| UnknownContigError(contig.to_owned()))
+    }
+    /// The input contigs, e.g. to build [ContigAliases] with.
+    pub fn input_contigs(&self) -> impl Iterator<Item = &From>
+    where
+        From: AsRef<str>,
+    {
+        // Skip the aliases.
+        self.contigs
+            .iter()
+            .filter(|(name, contig)| name.as_str() == contig.as_ref())
+            .map(|(_, contig)| contig)
+    }
+
+    /// Makes [Self::find_input_contig] (and so mapping) accept every name in the synonym row
+    /// of a known input contig, e.g. `1` or `NC_000001.11` for `chr1` with
+    /// [biocore::genome::alias::HUMAN_GRCH38].
+    pub fn with_contig_synonyms<const N: usize>(self, synonyms: &[[&str; N]]) -> Self
+    where
+        From: Clone + AsRef<str>,
+    {
+        let aliases = ContigAliases::from_synonyms(synonyms, self.contigs.values().cloned());
+        self.with_contig_aliases(&aliases)
+    }
+    /// Makes [Self::find_input_contig] (and so mapping) accept every alias in `aliases`.
+    ///
+    /// Names that already resolve to an input contig are left as they are.
+    pub fn with_contig_aliases(mut self, aliases: &ContigAliases<From>) -> Self
+    where
+        From: Clone,
+    {
+        for (alias, contig) in aliases.iter() {
+            if !self.contigs.contains_key(alias) {
+                self.contigs.insert(alias.to_owned(), contig.clone());
+            }
+        }
+        self
+    }
 }
 impl<From, To> LiftoverIndexed<From, To>
 where
@@ -440,6 +770,25 @@ where
         &self,
         loc: ContigPosition<C>,
     ) -> impl Iterator<Item = ContigPosition<To>> + use<'_, From, To, C>
+    where
+        From: Clone,
+    {
+        self.map_with_orientation(loc).map(|l| l.v)
+    }
+    pub fn map_range<C: AsRef<str>>(
+        &self,
+        range: ContigRange<C>,
+    ) -> impl Iterator<Item = ContigRange<To>> + use<'_, From, To, C>
+    where
+        From: Clone,
+    {
+        self.map_range_with_orientation(range).map(|r| r.v)
+    }
+    /// Like [Self::map], but also reports whether each result came from a strand-flipping chain.
+    pub fn map_with_orientation<C: AsRef<str>>(
+        &self,
+        loc: ContigPosition<C>,
+    ) -> impl Iterator<Item = WithOrientation<ContigPosition<To>>> + use<'_, From, To, C>
     where
         From: Clone,
     {
@@ -456,17 +805,15 @@ where
             },
         };
 
-        Some(self.map_raw(&range).map(|mut r| {
-            r.set_orientation(SequenceOrientation::Forward);
-            r.v
-        }))
-        .into_iter()
-        .flatten()
+        Some(self.map_raw(&range).map(WithOrientation::from_raw_position))
+            .into_iter()
+            .flatten()
     }
-    pub fn map_range<C: AsRef<str>>(
+    /// Like [Self::map_range], see [Self::map_with_orientation].
+    pub fn map_range_with_orientation<C: AsRef<str>>(
         &self,
         range: ContigRange<C>,
-    ) -> impl Iterator<Item = ContigRange<To>> + use<'_, From, To, C>
+    ) -> impl Iterator<Item = WithOrientation<ContigRange<To>>> + use<'_, From, To, C>
     where
         From: Clone,
     {
@@ -483,14 +830,134 @@ where
             },
         };
 
-        Some(self.map_range_raw(&range).map(|mut r| {
-            r.set_orientation(SequenceOrientation::Forward);
-            r.v
-        }))
+        Some(
+            self.map_range_raw(&range)
+                .map(WithOrientation::from_raw_range),
+        )
         .into_iter()
         .flatten()
     }
 
+    /// Like [Self::map_range], but only through the chains whose blocks cover at least
+    /// `min_match` of the range's bases, as with
+    /// [UcscLiftoverSettings::min_match](crate::bindings::ucsc::UcscLiftoverSettings::min_match).
+    pub fn map_range_with<C: AsRef<str>>(
+        &self,
+        range: ContigRange<C>,
+        min_match: f64,
+    ) -> impl Iterator<Item = ContigRange<To>> + use<'_, From, To, C>
+    where
+        From: Clone,
+    {
+        let entries = self
+            .find_input_contig(&range.contig)
+            .and_then(|contig| self.chromosomes.get(&contig));
+        let Some(entries) = entries else {
+            log::warn!("[Liftover] Unknown contig: {}", range.contig.as_ref());
+            return None.into_iter().flatten();
+        };
+        let at = range.at;
+        let entries = overlapping(entries, &at);
+
+        let mut covered: BTreeMap<usize, Vec<Range<u64>>> = BTreeMap::new();
+        for e in entries {
+            let intersected = e.range.clone().intersection(at.clone());
+            covered.entry(e.chain).or_default().push(intersected);
+        }
+        covered.retain(|_, covered| is_min_match(&gaps(covered.clone(), &at), &at, min_match));
+
+        Some(
+            entries
+                .iter()
+                .filter(move |e| covered.contains_key(&e.chain))
+                .filter_map(move |e| e.map_range(&at))
+                .map(|r| WithOrientation::from_raw_range(r).v),
+        )
+        .into_iter()
+        .flatten()
+    }
+    /// Like [Self::map_range], but also returns the parts of the range that don't map
+    /// (e.g. because they were deleted in the output assembly).
+    pub fn map_range_report<C: AsRef<str>>(&self, range: ContigRange<C>) -> LiftoverRangeReport<To>
+    where
+        From: Clone,
+    {
+        let unmapped = self
+            .unmapped_parts(&range)
+            .unwrap_or_else(|| vec![range.at.clone()]);
+        LiftoverRangeReport {
+            mapped: self.map_range(range).collect(),
+            unmapped,
+        }
+    }
+    /// The parts of `range` in no chain block, or `None` if the contig is unknown.
+    fn unmapped_parts<C: AsRef<str>>(&self, range: &ContigRange<C>) -> Option<Vec<Range<u64>>>
+    where
+        From: Clone,
+    {
+        let contig = self.find_input_contig(&range.contig)?;
+        let entries = self.chromosomes.get(&contig)?;
+        let covered = overlapping(entries, &range.at)
+            .iter()
+            .map(|e| e.range.clone().intersection(range.at.clone()))
+            .collect();
+        Some(gaps(covered, &range.at))
+    }
+
+    /// Like [Self::map], but resolves the contig through `aliases` if it isn't a known
+    /// input contig name, and reports unknown contigs instead of mapping to nothing.
+    pub fn map_with_aliases<C: AsRef<str>>(
+        &self,
+        loc: ContigPosition<C>,
+        aliases: &ContigAliases<From>,
+    ) -> Result<impl Iterator<Item = ContigPosition<To>> + use<'_, From, To>, UnknownContigError>
+    where
+        From: Clone,
+    {
+        let contig = self.resolve_input_contig(&loc.contig, aliases)?;
+        Ok(self.map(ContigPosition { contig, at: loc.at }))
+    }
+    /// Like [Self::map_range], see [Self::map_with_aliases].
+    pub fn map_range_with_aliases<C: AsRef<str>>(
+        &self,
+        range: ContigRange<C>,
+        aliases: &ContigAliases<From>,
+    ) -> Result<impl Iterator<Item = ContigRange<To>> + use<'_, From, To>, UnknownContigError>
+    where
+        From: Clone,
+    {
+        let contig = self.resolve_input_contig(&range.contig, aliases)?;
+        Ok(self.map_range(ContigRange {
+            contig,
+            at: range.at,
+        }))
+    }
+
+    /// Like [Self::map], but the position must be on the input [Build](biocore::location::build::Build).
+    pub fn lift<C>(
+        &self,
+        loc: ContigPosition<C>,
+    ) -> impl Iterator<Item = ContigPosition<To>> + use<'_, From, To, C>
+    where
+        From: BuildContig + Clone,
+        To: BuildContig,
+        C: BuildContig<Build = From::Build>,
+    {
+        self.map(loc)
+    }
+    /// Like [Self::map_range], but the range must be on the input [Build](biocore::location::build::Build).
+    pub fn lift_range<C>(
+        &self,
+        range: ContigRange<C>,
+    ) -> impl Iterator<Item = ContigRange<To>> + use<'_, From, To, C>
+    where
+        From: BuildContig + Clone,
+        To: BuildContig,
+        C: BuildContig<Build = From::Build>,
+    {
+        self.map_range(range)
+    }
+
     pub fn map_raw(
         &self,
         loc: &Stranded<ContigPosition<From>>,
@@ -558,35 +1025,8 @@ where
         #[expect(unused_variables)]
         let from = ();
 
-        // Note: `partition_point` splits by [true, true, true,|false, false]
-
-        let ranges = {
-            // We need `at.start < max`, so this is an lower bound (later ranges are still possible).
-            #[allow(clippy::nonminimal_bool)]
-            let lower_bound = ranges.partition_point(|e| !(at.start < e.max));
-            &ranges[lower_bound..] // Select slice for which `!!(at.start < e.max)` or `at.start < e.max` holds.
-        };
-        let ranges = {
-            // We need `start <= at.end`, so this is an upper bound (earlier ranges are still possible).
-            let upper_bound = ranges.partition_point(|e| e.range.start <= at.end);
-            &ranges[..upper_bound] // Select the slice for which `e.range.start <= at.end` holds.
-        };
-
-        Some(ranges.iter().filter_map(move |r| {
-            let intersected = r.range.clone().intersection(at.clone());
-            if intersected.is_empty() {
-                return None;
-            }
-
-            let shift = intersected.start - r.range.start;
-            let new = Stranded {
-                orientation: r.data.orientation,
-                v: ContigRange {
-                    contig: r.data.v.contig.clone(),
-                    at: (r.data.v.at.start + shift)
-                        ..(r.data.v.at.start + shift + intersected.range_len()),
-                },
-            };
+        Some(overlapping(ranges, &at).iter().filter_map(move |r| {
+            let new = r.map_range(&at)?;
             Some(if initially_flipped {
                 new.flip_orientation()
             } else {
@@ -597,6 +1037,92 @@ where
         .flatten()
     }
 }
+impl<Out: Clone> LiftoverIndexedEntry<Out> {
+    /// Maps the part of `at` (on the forward strand) in this block.
+    fn map_range(&self, at: &Range<u64>) -> Option<Stranded<ContigRange<Out>>> {
+        let intersected = self.range.clone().intersection(at.clone());
+        if intersected.is_empty() {
+            return None;
+        }
+
+        let shift = intersected.start - self.range.start;
+        Some(Stranded {
+            orientation: self.data.orientation,
+            v: ContigRange {
+                contig: self.data.v.contig.clone(),
+                at: (self.data.v.at.start + shift)
+                    ..(self.data.v.at.start + shift + intersected.range_len()),
+            },
+        })
+    }
+}
+
+/// The entries that may overlap `at` (sorted by start, with the running maximum end).
+fn overlapping<'a, Out>(
+    entries: &'a [LiftoverIndexedEntry<Out>],
+    at: &Range<u64>,
+) -> &'a [LiftoverIndexedEntry<Out>] {
+    // Note: `partition_point` splits by [true, true, true,|false, false]
+
+    let entries = {
+        // We need `at.start < max`, so this is an lower bound (later ranges are still possible).
+        #[allow(clippy::nonminimal_bool)]
+        let lower_bound = entries.partition_point(|e| !(at.start < e.max));
+        &entries[lower_bound..] // Select slice for which `!!(at.start < e.max)` or `at.start < e.max` holds.
+    };
+    // We need `start <= at.end`, so this is an upper bound (earlier ranges are still possible).
+    let upper_bound = entries.partition_point(|e| e.range.start <= at.end);
+    &entries[..upper_bound] // Select the slice for which `e.range.start <= at.end` holds.
+}
+/// The parts of `at` outside all of the (possibly overlapping) `covered` ranges.
+fn gaps(mut covered: Vec<Range<u64>>, at: &Range<u64>) -> Vec<Range<u64>> {
+    covered.retain(|r| !r.is_empty());
+    covered.sort_unstable_by_key(|r| r.start);
+    let mut gaps = vec![];
+    let mut end = at.start;
+    for r in covered {
+        if r.start > end {
+            gaps.push(end..r.start);
+        }
+        end = cmp::max(end, r.end);
+    }
+    if end < at.end {
+        gaps.push(end..at.end);
+    }
+    gaps
+}
+/// Whether at least `min_match` of `at` is outside of the `unmapped` parts.
+fn is_min_match(unmapped: &[Range<u64>], at: &Range<u64>, min_match: f64) -> bool {
+    let unmapped: u64 = unmapped.iter().map(|r| r.range_len()).sum();
+    (at.range_len() - unmapped) as f64 >= min_match * at.range_len() as f64
+}
+
+impl<T> WithOrientation<T> {
+    /// The chain maps the input's forward strand to the reverse strand.
+    pub fn is_flipped(&self) -> bool {
+        self.orientation.is_reverse()
+    }
+}
+impl<C: Contig> WithOrientation<ContigPosition<C>> {
+    fn from_raw_position(mut raw: Stranded<ContigPosition<C>>) -> Self {
+        let orientation = raw.orientation;
+        raw.set_orientation(SequenceOrientation::Forward);
+        Self {
+            orientation,
+            v: raw.v,
+        }
+    }
+}
+impl<C: Contig> WithOrientation<ContigRange<C>> {
+    fn from_raw_range(mut raw: Stranded<ContigRange<C>>) -> Self {
+        let orientation = raw.orientation;
+        raw.set_orientation(SequenceOrientation::Forward);
+        Self {
+            orientation,
+            v: raw.v,
+        }
+    }
+}
 
 mod boilerplate {
     use std::fmt;
@@ -739,3 +1265,132 @@ mod boilerplate {
         }
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use biocore::{
+        genome::Contig,
+        location::{ContigPosition, ContigRange, orientation::SequenceOrientation},
+    };
+
+    use super::Liftover;
+
+    /// Shared by the tests of the other modules.
+    ///
+    /// `chr1:0-50` maps to `chrA:10-55` with a 5 base deletion at `chr1:20-25`,
+    /// and `chr2:0-10` to the reverse strand of `chrB` (`chrB:90-100`).
+    pub(crate) const CHAIN: &str = "chain 100 chr1 100 + 0 50 chrA 100 + 10 55 1
+20\t5\t0
+25
+
+chain 50 chr2 100 + 0 10 chrB 100 - 0 10 2
+10
+";
+
+    #[test]
+    fn orientation() {
+        let liftover = Liftover::read(CHAIN.as_bytes()).unwrap();
+        let indexed = liftover.indexed();
+        let position = |contig: &str, at| ContigPosition {
+            contig: contig.to_owned(),
+            at,
+        };
+
+        let mapped: Vec<_> = liftover.map_with_orientation(position("chr1", 5)).collect();
+        assert_eq!(
+            mapped,
+            indexed
+                .map_with_orientation(position("chr1", 5))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(mapped.len(), 1);
+        assert_eq!(mapped[0].orientation, SequenceOrientation::Forward);
+        assert_eq!(mapped[0].v.at, 15);
+
+        let mapped: Vec<_> = liftover.map_with_orientation(position("chr2", 2)).collect();
+        assert_eq!(
+            mapped,
+            indexed
+                .map_with_orientation(position("chr2", 2))
+                .collect::<Vec<_>>()
+        );
+        assert!(mapped[0].is_flipped());
+        assert_eq!(mapped[0].v.contig.as_ref(), "chrB");
+        assert_eq!(mapped[0].v.at, 97);
+
+        let range = ContigRange {
+            contig: "chr2",
+            at: 2..5,
+        };
+        let mapped: Vec<_> = indexed.map_range_with_orientation(range).collect();
+        assert!(mapped[0].is_flipped());
+        assert_eq!(mapped[0].v.at, 95..98);
+        assert_eq!(mapped[0].v.contig.size(), 100);
+    }
+
+    #[test]
+    fn min_match() {
+        let liftover = Liftover::read(CHAIN.as_bytes()).unwrap();
+        let indexed = liftover.indexed();
+        // 10 of the 15 bases are in blocks.
+        let range = || ContigRange {
+            contig: "chr1",
+            at: 15..30,
+        };
+
+        assert_eq!(liftover.map_range_with(range(), 0.95).count(), 0);
+        assert_eq!(indexed.map_range_with(range(), 0.95).count(), 0);
+        assert_eq!(liftover.map_range_with(range(), 0.6).count(), 2);
+        assert_eq!(
+            indexed.map_range_with(range(), 0.6).collect::<Vec<_>>(),
+            indexed.map_range(range()).collect::<Vec<_>>()
+        );
+
+        // Each chain covers half of the range, which isn't enough for either on its own.
+        let split = "chain 100 chr3 100 + 0 10 chrC 100 + 0 10 3
+10
+
+chain 100 chr3 100 + 10 20 chrD 100 + 0 10 4
+10
+";
+        let liftover = Liftover::read(split.as_bytes()).unwrap();
+        let indexed = liftover.indexed();
+        let range = || ContigRange {
+            contig: "chr3",
+            at: 5..15,
+        };
+
+        assert_eq!(liftover.map_range_with(range(), 0.6).count(), 0);
+        assert_eq!(indexed.map_range_with(range(), 0.6).count(), 0);
+        let mapped: Vec<_> = liftover.map_range_with(range(), 0.5).collect();
+        assert_eq!(mapped.len(), 2);
+        assert_eq!(mapped[0].contig.as_ref(), "chrC");
+        assert_eq!(mapped[0].at, 5..10);
+        assert_eq!(mapped[1].contig.as_ref(), "chrD");
+        assert_eq!(mapped[1].at, 0..5);
+        assert_eq!(
+            indexed.map_range_with(range(), 0.5).collect::<Vec<_>>(),
+            mapped
+        );
+    }
+
+    #[test]
+    fn report() {
+        let liftover = Liftover::read(CHAIN.as_bytes()).unwrap();
+        let range = || ContigRange {
+            contig: "chr1",
+            at: 15..60,
+        };
+
+        let report = liftover.map_range_report(range());
+        assert_eq!(report, liftover.indexed().map_range_report(range()));
+        assert_eq!(report.mapped.len(), 2);
+        assert_eq!(report.unmapped, [20..25, 50..60]);
+
+        let unknown = ContigRange {
+            contig: "chr3",
+            at: 0..10,
+        };
+        assert_eq!(liftover.map_range_report(unknown).unmapped, [0..10]);
+    }
+}
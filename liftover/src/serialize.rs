@@ -0,0 +1,272 @@
+//! A compact binary encoding of [LiftoverIndexed], so the index can be cached on disk
+//! instead of being rebuilt from the chain file on every run.
+//!
+//! Layout (little-endian): magic and version, a table of every contig (name and size),
+//! then each input contig with its sorted entries, then the name lookup table.
+//! Contigs are referenced by their index in the table.
+
+use std::{
+    collections::BTreeMap,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::PathBuf,
+    sync::Arc,
+};
+
+use biocore::{
+    genome::{ArcContig, Contig},
+    location::{
+        ContigRange,
+        orientation::{SequenceOrientation, Stranded},
+    },
+};
+use resource::{
+    RawResource,
+    fs::{FsCache, FsCacheEntry},
+};
+use utile::num::TryU64;
+
+use super::{Liftover, LiftoverIndexed, LiftoverIndexedEntry};
+
+const MAGIC: &[u8; 8] = b"LIFTIDX\0";
+/// Bump on any layout change, older cache entries are then rebuilt.
+const VERSION: u32 = 2;
+
+impl LiftoverIndexed<ArcContig, ArcContig> {
+    /// Loads the index for the chain file from `cache`, building and caching it on a miss.
+    pub fn load_with_fs_cache<R: RawResource>(
+        resource: R,
+        cache: &FsCache,
+    ) -> anyhow::Result<Self> {
+        let entry = FsCacheEntry::new(
+            cache,
+            PathBuf::from("liftover_index")
+                .join(R::NAMESPACE)
+                .join(format!("{}.idx", resource.key())),
+        );
+
+        if entry.try_exists()? {
+            match Self::read(BufReader::new(entry.read()?)) {
+                Ok(index) => return Ok(index),
+                Err(e) => log::warn!("[Liftover] Rebuilding invalid cached index at {entry}: {e}"),
+            }
+        }
+
+        let index = Liftover::load(resource)?.indexed_with_progress();
+        entry.write_file_with(|file| index.write(BufWriter::new(file)))?;
+        Ok(index)
+    }
+    /// [Self::load_with_fs_cache] with the global cache.
+    pub fn load_with_global_fs_cache(resource: impl RawResource) -> anyhow::Result<Self> {
+        Self::load_with_fs_cache(resource, &FsCache::global())
+    }
+
+    pub fn write(&self, mut writer: impl Write) -> io::Result<()> {
+        let mut table: BTreeMap<&ArcContig, u32> = BTreeMap::new();
+        let mut intern = |contig| {
+            let next = u32::try_from(table.len()).unwrap();
+            *table.entry(contig).or_insert(next)
+        };
+        for (contig, entries) in &self.chromosomes {
+            intern(contig);
+            for entry in entries {
+                intern(&entry.data.v.contig);
+            }
+        }
+        let mut contigs: Vec<(&ArcContig, u32)> = table.iter().map(|(c, i)| (*c, *i)).collect();
+        contigs.sort_unstable_by_key(|(_, i)| *i);
+
+        let w = &mut writer;
+        w.write_all(MAGIC)?;
+        write_u32(w, VERSION)?;
+
+        write_len(w, contigs.len())?;
+        for (contig, _) in &contigs {
+            write_str(w, contig.as_ref())?;
+            write_u64(w, contig.size())?;
+        }
+
+        write_len(w, self.chromosomes.len())?;
+        for (contig, entries) in &self.chromosomes {
+            write_u32(w, table[contig])?;
+            write_len(w, entries.len())?;
+            for entry in entries {
+                write_u64(w, entry.range.start)?;
+                write_u64(w, entry.range.end)?;
+                write_u64(w, entry.max)?;
+                write_u32(w, table[&entry.data.v.contig])?;
+                w.write_all(&[match entry.data.orientation {
+                    SequenceOrientation::Forward => 0,
+                    SequenceOrientation::Reverse => 1,
+                }])?;
+                write_u64(w, entry.data.v.at.start)?;
+                write_u64(w, entry.data.v.at.end)?;
+                write_len(w, entry.chain)?;
+            }
+        }
+
+        // Only input contigs are indexed, the rest of the table is unused here.
+        let names: Vec<(&String, u32)> = self
+            .contigs
+            .iter()
+            .filter_map(|(name, contig)| Some((name, *table.get(contig)?)))
+            .collect();
+        write_len(w, names.len())?;
+        for (name, contig) in names {
+            write_str(w, name)?;
+            write_u32(w, contig)?;
+        }
+
+        writer.flush()
+    }
+    pub fn read(mut reader: impl Read) -> io::Result<Self> {
+        let r = &mut reader;
+
+        let mut magic = [0; 8];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("not a liftover index"));
+        }
+        let version = read_u32(r)?;
+        if version != VERSION {
+            return Err(invalid(format!(
+                "unsupported liftover index version {version}, expected {VERSION}"
+            )));
+        }
+
+        let mut contigs = vec![];
+        for _ in 0..read_u32(r)? {
+            let name = read_str(r)?;
+            let size = read_u64(r)?;
+            contigs.push(ArcContig::new(Arc::from(name), size));
+        }
+        let contig = |i: u32| {
+            contigs
+                .get(usize::try_from(i).unwrap())
+                .cloned()
+                .ok_or_else(|| invalid(format!("contig {i} out of bounds")))
+        };
+
+        let mut chromosomes = BTreeMap::new();
+        for _ in 0..read_u32(r)? {
+            let from = contig(read_u32(r)?)?;
+            let len = read_u32(r)?;
+            let mut entries = Vec::with_capacity(usize::try_from(len).unwrap());
+            for _ in 0..len {
+                let range = read_u64(r)?..read_u64(r)?;
+                let max = read_u64(r)?;
+                let to = contig(read_u32(r)?)?;
+                let mut orientation = [0];
+                r.read_exact(&mut orientation)?;
+                let orientation = match orientation[0] {
+                    0 => SequenceOrientation::Forward,
+                    1 => SequenceOrientation::Reverse,
+                    o => return Err(invalid(format!("invalid orientation {o}"))),
+                };
+                let at = read_u64(r)?..read_u64(r)?;
+                let chain = usize::try_from(read_u32(r)?).unwrap();
+                entries.push(LiftoverIndexedEntry {
+                    range,
+                    max,
+                    data: Stranded {
+                        orientation,
+                        v: ContigRange { contig: to, at },
+                    },
+                    chain,
+                });
+            }
+            chromosomes.insert(from, entries);
+        }
+
+        let mut names = BTreeMap::new();
+        for _ in 0..read_u32(r)? {
+            let name = read_str(r)?;
+            names.insert(name, contig(read_u32(r)?)?);
+        }
+
+        Ok(Self {
+            chromosomes,
+            contigs: names,
+        })
+    }
+}
+
+fn invalid(message: impl Into<String>) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Invalid liftover index: {}.", message.into()),
+    )
+}
+
+fn write_u32(writer: &mut impl Write, v: u32) -> io::Result<()> {
+    writer.write_all(&v.to_le_bytes())
+}
+fn write_u64(writer: &mut impl Write, v: u64) -> io::Result<()> {
+    writer.write_all(&v.to_le_bytes())
+}
+fn write_len(writer: &mut impl Write, len: usize) -> io::Result<()> {
+    let len = u32::try_from(len).map_err(|_| invalid("too many items"))?;
+    write_u32(writer, len)
+}
+fn write_str(writer: &mut impl Write, s: &str) -> io::Result<()> {
+    write_len(writer, s.len())?;
+    writer.write_all(s.as_bytes())
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut buf = [0; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut buf = [0; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+fn read_str(reader: &mut impl Read) -> io::Result<String> {
+    let len = read_u32(reader)?;
+    let mut buf = vec![];
+    reader.take(len.into()).read_to_end(&mut buf)?;
+    if buf.len().u64_unwrap() != u64::from(len) {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    String::from_utf8(buf).map_err(|_| invalid("contig name is not UTF-8"))
+}
+
+#[cfg(test)]
+mod tests {
+    use biocore::location::ContigPosition;
+
+    use crate::{Liftover, LiftoverIndexed};
+
+    const CHAIN: &str = "chain 100 chr1 1000 + 100 400 chrA 2000 - 500 820 1
+100\t0\t20
+200
+
+chain 50 chr2 500 + 0 100 chrB 600 + 10 110 2
+100
+";
+
+    #[test]
+    fn roundtrip() {
+        let liftover = Liftover::read(CHAIN.as_bytes()).unwrap();
+        let index = liftover.indexed().with_contig_synonyms(&[["chr1", "1"]]);
+
+        let mut buf = vec![];
+        index.write(&mut buf).unwrap();
+        let read = LiftoverIndexed::read(&*buf).unwrap();
+        assert_eq!(read, index);
+
+        let at = ContigPosition {
+            contig: "1",
+            at: 350,
+        };
+        assert_eq!(
+            read.map(at.clone()).collect::<Vec<_>>(),
+            index.map(at).collect::<Vec<_>>()
+        );
+
+        assert!(LiftoverIndexed::read(&buf[..buf.len() - 1]).is_err());
+        buf[8] = 0xFF;
+        assert!(LiftoverIndexed::read(&*buf).is_err());
+    }
+}
@@ -1,6 +1,14 @@
+use std::{collections::BTreeMap, str::FromStr};
+
 use url::Url;
 
-use resource::{Compression, RawResource, UrlResource};
+use biocore::{
+    genome::{ArcContig, Contig, alias, t2t::T2TContig},
+    location::build::{Build, BuildContig},
+};
+use resource::{Compression, FsCacheResource, RawResource, RawResourceExt, UrlResource};
+
+use crate::Liftover;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct EnsemblResource {
@@ -162,6 +170,117 @@ impl RawResource for UcscResource {
     }
 }
 
+impl Liftover<ArcContig, T2TContig> {
+    /// Loads the UCSC chains from `from` (`hg19` or `hg38`) to T2T-CHM13, cached globally.
+    pub fn load_to_t2t(from: UcscHG) -> anyhow::Result<Self> {
+        let liftover = Liftover::load(t2t_resource(from, UcscHG::Hs1)?)?;
+        for chain in &liftover.chains {
+            t2t_contig(&chain.header.q.v.contig)?;
+        }
+        Ok(liftover.upgrade_contigs(|c| c, |c| t2t_contig(&c).unwrap()))
+    }
+}
+impl Liftover<T2TContig, ArcContig> {
+    /// Loads the UCSC chains from T2T-CHM13 to `to` (`hg19` or `hg38`), cached globally.
+    pub fn load_from_t2t(to: UcscHG) -> anyhow::Result<Self> {
+        let liftover = Liftover::load(t2t_resource(UcscHG::Hs1, to)?)?;
+        for chain in &liftover.chains {
+            t2t_contig(&chain.header.t.v.contig)?;
+        }
+        Ok(liftover.upgrade_contigs(|c| t2t_contig(&c).unwrap(), |c| c))
+    }
+}
+fn t2t_resource(from: UcscHG, to: UcscHG) -> anyhow::Result<FsCacheResource<UcscResource>> {
+    use UcscHG::{Hg19, Hg38, Hs1};
+    if !matches!((from, to), (Hg19 | Hg38, Hs1) | (Hs1, Hg19 | Hg38)) {
+        anyhow::bail!("No T2T-CHM13 chains from {from} to {to}.");
+    }
+    Ok(UcscResource::new_human_liftover(from, to).with_global_fs_cache())
+}
+fn t2t_contig(contig: &ArcContig) -> anyhow::Result<T2TContig> {
+    let name = contig.as_ref();
+    let t2t = T2TContig::new(name)
+        .ok_or_else(|| anyhow::anyhow!("Unknown T2T-CHM13 contig {name:?}."))?;
+    anyhow::ensure!(
+        t2t.size() == contig.size(),
+        "T2T-CHM13 contig {name} has size {}, but the chain file states {}.",
+        t2t.size(),
+        contig.size()
+    );
+    Ok(t2t)
+}
+
+/// Fetches the UCSC chains between the builds of `From` and `To` (cached globally),
+/// with typed contigs on both sides.
+///
+/// Contigs are parsed from their UCSC name, or from the other names in its row of the build's
+/// synonym table (see [biocore::genome::alias]), so contig types using e.g. Ensembl names work.
+/// Chains on contigs the types don't know (like alternate haplotypes) are dropped.
+pub fn fetch<From, To>() -> anyhow::Result<Liftover<From, To>>
+where
+    From: BuildContig + FromStr + Clone,
+    To: BuildContig + FromStr + Clone,
+{
+    let from = ucsc_build::<From::Build>()?;
+    let to = ucsc_build::<To::Build>()?;
+    if from == to || UcscHG::is_missing(from, to) {
+        anyhow::bail!("No UCSC chains from {from} to {to}.");
+    }
+    let liftover =
+        Liftover::load(UcscResource::new_human_liftover(from, to).with_global_fs_cache())?;
+
+    let mut chains = vec![];
+    let mut contigs = BTreeMap::new();
+    let mut dropped = 0;
+    for chain in liftover.chains {
+        let t = typed_contig::<From>(from, &chain.header.t.v.contig)?;
+        let q = typed_contig::<To>(to, &chain.header.q.v.contig)?;
+        let (Some(t), Some(q)) = (t, q) else {
+            dropped += 1;
+            continue;
+        };
+        contigs.insert(chain.header.t.v.contig.as_ref().to_owned(), t.clone());
+        contigs.insert(t.as_ref().to_owned(), t.clone());
+        chains.push(chain.upgrade_contigs(|_| t.clone(), |_| q.clone()));
+    }
+    if dropped > 0 {
+        log::info!("[Liftover] Dropped {dropped} chains on unknown contigs ({from} to {to}).");
+    }
+    Ok(Liftover { chains, contigs })
+}
+fn ucsc_build<B: Build>() -> anyhow::Result<UcscHG> {
+    UcscHG::iter()
+        .find(|b| b.name() == B::UCSC_NAME)
+        .ok_or_else(|| anyhow::anyhow!("No UCSC chains for {}.", B::NAME))
+}
+/// Tries the name and its synonyms, returning `None` if `C` knows none of them.
+fn typed_contig<C>(build: UcscHG, contig: &ArcContig) -> anyhow::Result<Option<C>>
+where
+    C: Contig + FromStr,
+{
+    let name = contig.as_ref();
+    let synonyms: &[[&str; 3]] = match build {
+        UcscHG::Hg19 => alias::HUMAN_GRCH37,
+        UcscHG::Hg38 => alias::HUMAN_GRCH38,
+        UcscHG::Hs1 => alias::HUMAN_T2T_CHM13,
+        _ => &[],
+    };
+    let row = synonyms.iter().find(|row| row.contains(&name));
+    let Some(typed) = std::iter::once(name)
+        .chain(row.into_iter().flatten().copied())
+        .find_map(|name| name.parse::<C>().ok())
+    else {
+        return Ok(None);
+    };
+    anyhow::ensure!(
+        typed.size() == contig.size(),
+        "Contig {name} ({build}) has size {}, but the chain file states {}.",
+        typed.size(),
+        contig.size()
+    );
+    Ok(Some(typed))
+}
+
 mod boilerplate {
     use std::fmt;
 
@@ -522,4 +641,33 @@ mod tests {
             .collect::<BTreeSet<_>>();
         println!("\n{from:?}\n\n{to:?}");
     }
+
+    #[test]
+    fn test_fetch() {
+        use biocore::location::build::{GRCh38, T2TCHM13};
+
+        assert_eq!(ucsc_build::<GRCh38>().unwrap(), UcscHG::Hg38);
+        assert_eq!(ucsc_build::<T2TCHM13>().unwrap(), UcscHG::Hs1);
+        assert!(fetch::<T2TContig, T2TContig>().is_err());
+
+        let contig = ArcContig::new("NC_060948.1".into(), T2TContig::Y.size());
+        let typed = typed_contig::<T2TContig>(UcscHG::Hs1, &contig).unwrap();
+        assert_eq!(typed, Some(T2TContig::Y));
+        let contig = ArcContig::new("chrY_alt".into(), 10);
+        assert_eq!(
+            typed_contig::<T2TContig>(UcscHG::Hs1, &contig).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_t2t_resource() {
+        let liftover = Liftover::load_to_t2t(UcscHG::Hg38).unwrap();
+        let to = liftover
+            .chains
+            .iter()
+            .map(|c| c.header.q.v.contig)
+            .collect::<BTreeSet<_>>();
+        assert!(to.contains(&T2TContig::X));
+    }
 }
@@ -0,0 +1,152 @@
+//! Consistency checks and coverage statistics for a whole chain file.
+
+use std::{collections::BTreeMap, ops::Range};
+
+use biocore::{genome::Contig, location::orientation::SequenceOrientation};
+use utile::range::RangeLen;
+
+use super::{ChainError, Liftover};
+
+/// The result of [Liftover::validate].
+#[derive(Debug)]
+pub struct ChainReport<From> {
+    /// Chains that failed [crate::Chain::validate], which are left out of the rest of the report.
+    pub invalid: Vec<ChainError>,
+    /// Source (chain target) blocks of different chains that overlap, so map to several places.
+    pub overlaps: Vec<ChainOverlap<From>>,
+    /// Per source contig, how much is covered by chain blocks.
+    pub coverage: BTreeMap<From, ContigCoverage>,
+}
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainOverlap<From> {
+    pub contig: From,
+    /// On the forward strand.
+    pub at: Range<u64>,
+    /// The ids of the two chains.
+    pub chains: (u32, u32),
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContigCoverage {
+    pub size: u64,
+    /// Bases in at least one chain block.
+    pub covered: u64,
+}
+
+impl<From, To> Liftover<From, To>
+where
+    From: Contig + Ord + Clone,
+    To: Contig + Clone,
+{
+    /// Checks every chain (see [crate::Chain::validate]), looks for overlapping source blocks,
+    /// and measures how much of each source contig is covered.
+    ///
+    /// Corrupt or truncated chain files tend to show up as invalid chains or low coverage.
+    pub fn validate(&self) -> ChainReport<From> {
+        let mut invalid = vec![];
+        let mut blocks: BTreeMap<From, Vec<(Range<u64>, u32)>> = BTreeMap::new();
+        for chain in &self.chains {
+            if let Err(e) = chain.validate() {
+                invalid.push(e);
+                continue;
+            }
+            for (mut from, _) in chain.iter_ranges() {
+                from.set_orientation(SequenceOrientation::Forward);
+                blocks
+                    .entry(from.v.contig)
+                    .or_default()
+                    .push((from.v.at, chain.header.id));
+            }
+        }
+
+        let mut overlaps = vec![];
+        let mut coverage = BTreeMap::new();
+        for (contig, mut blocks) in blocks {
+            blocks.sort_unstable_by_key(|(at, _)| (at.start, at.end));
+
+            let mut covered = 0;
+            // The block reaching furthest so far.
+            let mut furthest: Option<(Range<u64>, u32)> = None;
+            for (at, id) in blocks {
+                match &mut furthest {
+                    Some((prev, prev_id)) if at.start < prev.end => {
+                        if *prev_id != id {
+                            overlaps.push(ChainOverlap {
+                                contig: contig.clone(),
+                                at: at.start..at.end.min(prev.end),
+                                chains: (*prev_id, id),
+                            });
+                        }
+                        covered += at.end.saturating_sub(prev.end);
+                        if at.end > prev.end {
+                            *prev = at;
+                            *prev_id = id;
+                        }
+                    }
+                    _ => {
+                        covered += at.range_len();
+                        furthest = Some((at, id));
+                    }
+                }
+            }
+
+            let size = contig.size();
+            coverage.insert(contig, ContigCoverage { size, covered });
+        }
+
+        ChainReport {
+            invalid,
+            overlaps,
+            coverage,
+        }
+    }
+}
+
+impl<From> ChainReport<From> {
+    /// No invalid chains and no overlaps.
+    pub fn is_valid(&self) -> bool {
+        self.invalid.is_empty() && self.overlaps.is_empty()
+    }
+}
+impl ContigCoverage {
+    pub fn fraction(self) -> f64 {
+        if self.size == 0 {
+            return 0.0;
+        }
+        self.covered as f64 / self.size as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Liftover;
+
+    const CHAIN: &str = "chain 100 chr1 100 + 0 50 chrA 100 + 10 55 1
+20\t5\t0
+25
+
+chain 50 chr1 100 - 40 60 chrB 100 + 0 20 2
+20
+
+chain 10 chr2 100 + 0 10 chrB 100 + 20 40 3
+10
+";
+
+    #[test]
+    fn report() {
+        let report = Liftover::read(CHAIN.as_bytes()).unwrap().validate();
+
+        // Chain 3 spans 10 target bases but 20 query bases.
+        assert_eq!(report.invalid.len(), 1);
+
+        // Chain 2 covers `chr1:40-60` on the forward strand.
+        assert_eq!(report.overlaps.len(), 1);
+        assert_eq!(report.overlaps[0].at, 40..50);
+        assert_eq!(report.overlaps[0].chains, (1, 2));
+        assert!(!report.is_valid());
+
+        let (contig, coverage) = report.coverage.first_key_value().unwrap();
+        assert_eq!(contig.as_ref(), "chr1");
+        assert_eq!((coverage.covered, coverage.size), (55, 100));
+        assert_eq!(coverage.fraction(), 0.55);
+    }
+}
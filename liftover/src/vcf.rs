@@ -0,0 +1,355 @@
+//! Lifting VCF records, alleles included, to another assembly.
+//!
+//! Records are handled as text, so every column other than `CHROM`, `POS`, `REF` and `ALT`
+//! passes through untouched. When the chain flips strand the alleles are reverse-complemented,
+//! and indels are re-anchored on the base before them (VCF anchors on the left, which is now
+//! the right). The lifted `REF` is then checked against the target reference.
+//!
+//! Records that can't be lifted are written to a separate rejects stream,
+//! unchanged except for `FILTER`, which is set to the [RejectReason].
+
+use std::{
+    collections::BTreeMap,
+    io::{self, BufRead, Write},
+};
+
+use biocore::{
+    genome::{ArcContig, Contig},
+    location::{
+        ContigRange,
+        orientation::{SequenceOrientation, Stranded},
+    },
+    mutation::normalize::ReferenceSequence,
+};
+use utile::range::RangeLen;
+
+use crate::LiftoverIndexed;
+
+/// Lifts VCF records with a [LiftoverIndexed], checking alleles against the target reference.
+pub struct VcfLiftover<'a, G, From = ArcContig, To = ArcContig> {
+    liftover: &'a LiftoverIndexed<From, To>,
+    reference: G,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(thiserror::Error)]
+pub enum RejectReason {
+    #[error("The record could not be parsed.")]
+    Malformed,
+    #[error("The record has symbolic or non-nucleotide alleles.")]
+    UnsupportedAllele,
+    #[error("The reference allele does not map to the target assembly.")]
+    Unmapped,
+    #[error("The reference allele maps to multiple locations in the target assembly.")]
+    MultipleTargets,
+    #[error("The reference allele is split across alignment blocks in the target assembly.")]
+    Fragmented,
+    #[error("The lifted reference allele does not match the target reference.")]
+    ReferenceMismatch,
+}
+
+/// Counts of lifted and rejected records, see [VcfLiftover::lift].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct VcfLiftoverSummary {
+    pub lifted: usize,
+    pub rejected: BTreeMap<RejectReason, usize>,
+}
+
+impl<'a, G, From, To> VcfLiftover<'a, G, From, To> {
+    pub fn new(liftover: &'a LiftoverIndexed<From, To>, reference: G) -> Self {
+        Self {
+            liftover,
+            reference,
+        }
+    }
+
+    pub fn into_reference(self) -> G {
+        self.reference
+    }
+}
+impl<G, From, To> VcfLiftover<'_, G, From, To>
+where
+    G: ReferenceSequence<To>,
+    From: Contig + Ord + Clone,
+    To: Contig + Clone,
+{
+    /// Lifts a whole VCF, writing lifted records to `output` and the rest to `rejects`.
+    ///
+    /// Both get the input header, but `##contig` lines (which describe the source assembly)
+    /// are only kept in `rejects`, which also gets a `##FILTER` line per [RejectReason].
+    pub fn lift(
+        &mut self,
+        mut input: impl BufRead,
+        mut output: impl Write,
+        mut rejects: impl Write,
+    ) -> io::Result<VcfLiftoverSummary> {
+        let mut summary = VcfLiftoverSummary::default();
+        let mut buf = String::new();
+        loop {
+            buf.clear();
+            if input.read_line(&mut buf)? == 0 {
+                break;
+            }
+            let line = buf.trim_end_matches(['\n', '\r']);
+
+            if line.starts_with("##contig=") {
+                writeln!(rejects, "{line}")?;
+                continue;
+            }
+            if line.starts_with("#CHROM") {
+                for reason in RejectReason::iter() {
+                    writeln!(
+                        rejects,
+                        "##FILTER=<ID={},Description=\"{}\">",
+                        reason.filter(),
+                        reason.to_string().trim_end_matches('.')
+                    )?;
+                }
+            }
+            if line.starts_with('#') || line.trim().is_empty() {
+                writeln!(output, "{line}")?;
+                writeln!(rejects, "{line}")?;
+                continue;
+            }
+
+            match self.lift_record(line)? {
+                Ok(lifted) => {
+                    summary.lifted += 1;
+                    writeln!(output, "{lifted}")?;
+                }
+                Err(reason) => {
+                    *summary.rejected.entry(reason).or_default() += 1;
+                    writeln!(rejects, "{}", with_filter(line, reason))?;
+                }
+            }
+        }
+        output.flush()?;
+        rejects.flush()?;
+        Ok(summary)
+    }
+
+    /// Lifts a single (tab-separated) data line.
+    ///
+    /// The outer error is only for failures reading the reference.
+    pub fn lift_record(&mut self, line: &str) -> io::Result<Result<String, RejectReason>> {
+        let mut fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() < 5 {
+            return Ok(Err(RejectReason::Malformed));
+        }
+        let Some(start) = fields[1].parse::<u64>().ok().and_then(|p| p.checked_sub(1)) else {
+            return Ok(Err(RejectReason::Malformed));
+        };
+        let reference = fields[3];
+        let alternates: Vec<&str> = fields[4].split(',').collect();
+        if !is_sequence(reference) || !alternates.iter().all(|a| is_sequence(a) || is_missing(a)) {
+            return Ok(Err(RejectReason::UnsupportedAllele));
+        }
+
+        let Some(contig) = self.liftover.find_input_contig(fields[0]) else {
+            return Ok(Err(RejectReason::Unmapped));
+        };
+        let len = u64::try_from(reference.len()).unwrap();
+        let source = Stranded {
+            orientation: SequenceOrientation::Forward,
+            v: ContigRange {
+                contig,
+                at: start..start + len,
+            },
+        };
+        let mut hits: Vec<_> = self.liftover.map_range_raw(&source).collect();
+        let complete = hits.iter().filter(|h| h.v.at.range_len() == len).count();
+        let mut target = match (hits.len(), complete) {
+            (0, _) => return Ok(Err(RejectReason::Unmapped)),
+            (1, 1) => hits.pop().unwrap(),
+            (_, 2..) => return Ok(Err(RejectReason::MultipleTargets)),
+            (_, _) => return Ok(Err(RejectReason::Fragmented)),
+        };
+        let flipped = target.orientation == SequenceOrientation::Reverse;
+        target.set_orientation(SequenceOrientation::Forward);
+        let ContigRange { contig, at } = target.v;
+
+        let mut start = at.start;
+        let mut reference = reference.to_owned();
+        let mut alternates: Vec<String> = alternates.into_iter().map(str::to_owned).collect();
+        if flipped {
+            reference = reverse_complement(&reference);
+            for alternate in alternates.iter_mut().filter(|a| is_sequence(a)) {
+                *alternate = reverse_complement(alternate);
+            }
+
+            let is_indel = alternates
+                .iter()
+                .any(|a| is_sequence(a) && a.len() != reference.len());
+            if is_indel {
+                let anchor = reference.as_bytes()[reference.len() - 1];
+                let anchored = alternates
+                    .iter()
+                    .filter(|a| is_sequence(a))
+                    .all(|a| a.as_bytes().last() == Some(&anchor));
+                if !anchored || start == 0 {
+                    return Ok(Err(RejectReason::UnsupportedAllele));
+                }
+
+                start -= 1;
+                let base = self.reference.fetch(&ContigRange {
+                    contig: contig.clone(),
+                    at: start..start + 1,
+                })?;
+                let base = base.encode();
+                let reanchor = |allele: &str| format!("{base}{}", &allele[..allele.len() - 1]);
+                reference = reanchor(&reference);
+                for alternate in alternates.iter_mut().filter(|a| is_sequence(a)) {
+                    *alternate = reanchor(alternate);
+                }
+            }
+        }
+
+        let found = self.reference.fetch(&ContigRange {
+            contig: contig.clone(),
+            at: start..start + len,
+        })?;
+        if !matches_reference(&reference, &found.encode()) {
+            return Ok(Err(RejectReason::ReferenceMismatch));
+        }
+
+        let position = (start + 1).to_string();
+        let alternates = alternates.join(",");
+        fields[0] = contig.as_ref();
+        fields[1] = &position;
+        fields[3] = &reference;
+        fields[4] = &alternates;
+        Ok(Ok(fields.join("\t")))
+    }
+}
+
+impl RejectReason {
+    /// The `FILTER` value used in the rejects stream.
+    pub fn filter(self) -> &'static str {
+        match self {
+            Self::Malformed => "Malformed",
+            Self::UnsupportedAllele => "UnsupportedAllele",
+            Self::Unmapped => "Unmapped",
+            Self::MultipleTargets => "MultipleTargets",
+            Self::Fragmented => "Fragmented",
+            Self::ReferenceMismatch => "ReferenceMismatch",
+        }
+    }
+    pub fn iter() -> impl Iterator<Item = Self> {
+        [
+            Self::Malformed,
+            Self::UnsupportedAllele,
+            Self::Unmapped,
+            Self::MultipleTargets,
+            Self::Fragmented,
+            Self::ReferenceMismatch,
+        ]
+        .into_iter()
+    }
+}
+
+fn with_filter(line: &str, reason: RejectReason) -> String {
+    let mut fields: Vec<&str> = line.split('\t').collect();
+    if let Some(filter) = fields.get_mut(6) {
+        *filter = reason.filter();
+    }
+    fields.join("\t")
+}
+
+fn is_sequence(allele: &str) -> bool {
+    !allele.is_empty()
+        && allele
+            .bytes()
+            .all(|b| matches!(b.to_ascii_uppercase(), b'A' | b'C' | b'G' | b'T' | b'N'))
+}
+/// `.` and the `*` spanning-deletion allele are kept as they are.
+fn is_missing(allele: &str) -> bool {
+    matches!(allele, "." | "*")
+}
+/// Case-insensitive, with `N` in the allele matching any base.
+fn matches_reference(allele: &str, found: &str) -> bool {
+    allele.len() == found.len()
+        && allele
+            .bytes()
+            .zip(found.bytes())
+            .all(|(a, f)| a.eq_ignore_ascii_case(&b'N') || a.eq_ignore_ascii_case(&f))
+}
+fn reverse_complement(allele: &str) -> String {
+    allele
+        .bytes()
+        .rev()
+        .map(|b| match b {
+            b'A' => 'T',
+            b'C' => 'G',
+            b'G' => 'C',
+            b'T' => 'A',
+            b'a' => 't',
+            b'c' => 'g',
+            b'g' => 'c',
+            b't' => 'a',
+            b => char::from(b),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use biocore::{
+        dna::DnaBase,
+        genome::{ArcContig, InMemoryGenome},
+        sequence::AsciiChar,
+    };
+
+    use crate::Liftover;
+
+    use super::{RejectReason, VcfLiftover, VcfLiftoverSummary};
+
+    // `chr1:0-10` maps to the reverse strand of `chrA:5-15`, so `chr1:x` is `chrA:14-x`.
+    const CHAIN: &str = "chain 100 chr1 20 + 0 10 chrA 20 - 5 15 1\n10\n\n";
+    const TARGET: &str = "AAAAACCGTTAGCATGGGGG";
+
+    #[test]
+    fn lift_reverse_strand() {
+        let liftover = Liftover::read(CHAIN.as_bytes()).unwrap().indexed();
+        let target = DnaBase::decode(TARGET.as_bytes().to_vec()).unwrap();
+        let genome = InMemoryGenome::new([(ArcContig::new("chrA".into(), 20), target)]);
+
+        let input = "##fileformat=VCFv4.2
+##contig=<ID=chr1,length=20>
+#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO
+chr1\t3\tsnv\tG\tA,*\t.\tPASS\t.
+chr1\t5\tdel\tTA\tT\t.\tPASS\t.
+chr1\t4\tmismatch\tA\tG\t.\tPASS\t.
+chr1\t15\tunmapped\tA\tG\t.\tPASS\t.
+";
+        let (mut output, mut rejects) = (vec![], vec![]);
+        let summary = VcfLiftover::new(&liftover, genome)
+            .lift(input.as_bytes(), &mut output, &mut rejects)
+            .unwrap();
+
+        assert_eq!(
+            summary,
+            VcfLiftoverSummary {
+                lifted: 2,
+                rejected: BTreeMap::from([
+                    (RejectReason::Unmapped, 1),
+                    (RejectReason::ReferenceMismatch, 1)
+                ]),
+            }
+        );
+        // `chr1:2` is `chrA:12` (`C`), and the deletion of `chr1:5` is the deletion of `chrA:9`.
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "##fileformat=VCFv4.2
+#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO
+chrA\t13\tsnv\tC\tT,*\t.\tPASS\t.
+chrA\t9\tdel\tTT\tT\t.\tPASS\t.
+"
+        );
+        let rejects = String::from_utf8(rejects).unwrap();
+        assert!(rejects.contains("##contig=<ID=chr1,length=20>"));
+        assert!(rejects.contains("chr1\t4\tmismatch\tA\tG\t.\tReferenceMismatch\t."));
+        assert!(rejects.contains("chr1\t15\tunmapped\tA\tG\t.\tUnmapped\t."));
+    }
+}
@@ -0,0 +1,347 @@
+//! Differential checks of [LiftoverIndexed] against the UCSC `liftOver` tool.
+//!
+//! Positions and ranges are sampled from a chain file (uniformly and around block edges),
+//! mapped with both implementations, and any disagreement is reported with both results.
+//! UCSC is always run with [UcscLiftoverSettings::loose] and `-multiple`, the closest
+//! match to the native behaviour of returning every mapped fragment.
+
+use std::{fmt, io, ops::Range, path::PathBuf};
+
+use rand::{Rng, SeedableRng, rngs::SmallRng};
+use reqwest::Client;
+
+use biocore::{
+    genome::Contig,
+    location::{ContigPosition, ContigRange, orientation::SequenceOrientation},
+};
+
+use crate::{
+    Chain, Liftover, LiftoverIndexed,
+    bindings::ucsc::{self, FailureReason, PositionFailureReason, UcscLiftoverSettings},
+    sources::UcscHG,
+};
+
+/// Where to run UCSC `liftOver`.
+#[derive(Debug, Clone)]
+pub enum UcscBackend {
+    /// A local `liftOver` binary, with the same chain file as the native liftover.
+    Cli {
+        command: PathBuf,
+        chain_file: PathBuf,
+    },
+    /// The UCSC web interface, see [UcscHG::is_available_in_online_interface].
+    Web {
+        client: Client,
+        from: UcscHG,
+        to: UcscHG,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyReport<L, E> {
+    pub checked: usize,
+    pub disagreements: Vec<Disagreement<L, E>>,
+}
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Disagreement<L, E> {
+    pub query: L,
+    /// Sorted.
+    pub native: Vec<L>,
+    /// Sorted and deduplicated.
+    pub ucsc: Result<Vec<L>, E>,
+}
+
+impl UcscBackend {
+    async fn positions(
+        &self,
+        positions: &[ContigPosition],
+    ) -> io::Result<Vec<Result<Vec<ContigPosition>, PositionFailureReason>>> {
+        let settings = UcscLiftoverSettings::loose();
+        match self {
+            Self::Cli {
+                command,
+                chain_file,
+            } => ucsc::cli::liftover_snps(positions, chain_file, command, settings).await,
+            Self::Web { client, from, to } => {
+                ucsc::web::liftover_human_snps(client, *from, positions, *to, settings).await
+            }
+        }
+    }
+    async fn ranges(
+        &self,
+        ranges: &[ContigRange],
+    ) -> io::Result<Vec<Result<Vec<ContigRange>, FailureReason>>> {
+        let settings = UcscLiftoverSettings::loose();
+        match self {
+            Self::Cli {
+                command,
+                chain_file,
+            } => ucsc::cli::liftover(ranges, chain_file, command, settings).await,
+            Self::Web { client, from, to } => {
+                ucsc::web::liftover_human(client, *from, ranges, *to, settings).await
+            }
+        }
+    }
+}
+
+/// Maps `positions` with both implementations, which must return the same set of positions.
+pub async fn verify_positions<From, To>(
+    liftover: &LiftoverIndexed<From, To>,
+    backend: &UcscBackend,
+    positions: &[ContigPosition],
+) -> io::Result<VerifyReport<ContigPosition, PositionFailureReason>>
+where
+    From: Contig + Ord + Clone,
+    To: Contig + Clone,
+{
+    let ucsc = backend.positions(positions).await?;
+
+    let mut disagreements = vec![];
+    for (query, ucsc) in positions.iter().zip(ucsc) {
+        let mut native: Vec<_> = liftover
+            .map(query.clone())
+            .map(|p| p.map_contig(|c| c.as_ref().to_owned()))
+            .collect();
+        native.sort();
+        let ucsc = ucsc.map(|mut ucsc| {
+            ucsc.sort();
+            ucsc.dedup();
+            ucsc
+        });
+
+        if ucsc.as_ref().is_ok_and(|ucsc| *ucsc == native) {
+            continue;
+        }
+        disagreements.push(Disagreement {
+            query: query.clone(),
+            native,
+            ucsc,
+        });
+    }
+
+    Ok(VerifyReport {
+        checked: positions.len(),
+        disagreements,
+    })
+}
+/// Maps `ranges` with both implementations, see [ranges_agree] for what counts as agreeing.
+pub async fn verify_ranges<From, To>(
+    liftover: &LiftoverIndexed<From, To>,
+    backend: &UcscBackend,
+    ranges: &[ContigRange],
+) -> io::Result<VerifyReport<ContigRange, FailureReason>>
+where
+    From: Contig + Ord + Clone,
+    To: Contig + Clone,
+{
+    let ucsc = backend.ranges(ranges).await?;
+
+    let mut disagreements = vec![];
+    for (query, ucsc) in ranges.iter().zip(ucsc) {
+        let mut native: Vec<_> = liftover
+            .map_range(query.clone())
+            .map(|r| r.map_contig(|c| c.as_ref().to_owned()))
+            .collect();
+        native.sort();
+        let ucsc = ucsc.map(|mut ucsc| {
+            ucsc.sort();
+            ucsc.dedup();
+            ucsc
+        });
+
+        if ucsc.as_ref().is_ok_and(|ucsc| ranges_agree(&native, ucsc)) {
+            continue;
+        }
+        disagreements.push(Disagreement {
+            query: query.clone(),
+            native,
+            ucsc,
+        });
+    }
+
+    Ok(VerifyReport {
+        checked: ranges.len(),
+        disagreements,
+    })
+}
+
+/// UCSC merges nearby fragments, so it can return fewer (larger) ranges than the native liftover.
+/// They agree if every native fragment is inside a UCSC range, and every UCSC range starts and
+/// ends where a native fragment does. A single native fragment must match exactly.
+pub fn ranges_agree(native: &[ContigRange], ucsc: &[ContigRange]) -> bool {
+    if native.len() <= 1 {
+        return native == ucsc;
+    }
+    ucsc.len() <= native.len()
+        && native
+            .iter()
+            .all(|n| ucsc.iter().any(|u| u.contains_range(n)))
+        && ucsc.iter().all(|u| {
+            native.iter().any(|n| n.at.start == u.at.start)
+                && native.iter().any(|n| n.at.end == u.at.end)
+        })
+}
+
+/// Samples `n` positions on the source contigs of the chains, half of them around block edges.
+///
+/// The same chains and seed always give the same positions.
+pub fn sample_positions<From, To>(
+    liftover: &Liftover<From, To>,
+    n: usize,
+    seed: u64,
+) -> Vec<ContigPosition>
+where
+    From: Contig + Clone,
+    To: Contig + Clone,
+{
+    let mut rng = SmallRng::seed_from_u64(seed);
+    (0..n)
+        .filter_map(|i| {
+            let (contig, at, _) = sample_point(liftover, i % 2 == 1, &mut rng)?;
+            Some(ContigPosition { contig, at })
+        })
+        .collect()
+}
+/// Samples `n` ranges of up to 1000 bases, starting as in [sample_positions].
+pub fn sample_ranges<From, To>(
+    liftover: &Liftover<From, To>,
+    n: usize,
+    seed: u64,
+) -> Vec<ContigRange>
+where
+    From: Contig + Clone,
+    To: Contig + Clone,
+{
+    let mut rng = SmallRng::seed_from_u64(seed);
+    (0..n)
+        .filter_map(|i| {
+            let (contig, start, size) = sample_point(liftover, i % 2 == 1, &mut rng)?;
+            let end = Ord::min(size, start + rng.random_range(1..1000));
+            Some(ContigRange {
+                contig,
+                at: start..end,
+            })
+        })
+        .collect()
+}
+
+/// A random position on the source contig of a random chain, either anywhere on the contig
+/// or just around one of the chain's blocks. Also returns the contig size.
+fn sample_point<From, To>(
+    liftover: &Liftover<From, To>,
+    edge: bool,
+    rng: &mut impl Rng,
+) -> Option<(String, u64, u64)>
+where
+    From: Contig + Clone,
+    To: Contig + Clone,
+{
+    if liftover.chains.is_empty() {
+        return None;
+    }
+    let chain = &liftover.chains[rng.random_range(0..liftover.chains.len())];
+    let contig = &chain.header.t.v.contig;
+    let size = contig.size();
+    if size == 0 {
+        return None;
+    }
+
+    let at = if edge {
+        let block = block_range(chain, rng);
+        let candidates = [
+            block.start.saturating_sub(1),
+            block.start,
+            block.end - 1,
+            block.end,
+        ];
+        Ord::min(candidates[rng.random_range(0..candidates.len())], size - 1)
+    } else {
+        rng.random_range(0..size)
+    };
+    Some((contig.as_ref().to_owned(), at, size))
+}
+/// A random block of the chain, on the forward strand of the source contig.
+fn block_range<From, To>(chain: &Chain<From, To>, rng: &mut impl Rng) -> Range<u64>
+where
+    From: Contig + Clone,
+    To: Contig + Clone,
+{
+    let i = rng.random_range(0..chain.blocks().count());
+    let (mut from, _) = chain.iter_ranges().nth(i).unwrap();
+    from.set_orientation(SequenceOrientation::Forward);
+    from.v.at
+}
+
+impl<L, E> VerifyReport<L, E> {
+    pub fn agrees(&self) -> bool {
+        self.disagreements.is_empty()
+    }
+}
+impl<L: fmt::Debug, E: fmt::Display> fmt::Display for VerifyReport<L, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} of {} queries disagree with UCSC liftOver.",
+            self.disagreements.len(),
+            self.checked
+        )?;
+        for disagreement in &self.disagreements {
+            writeln!(f, "{disagreement}")?;
+        }
+        Ok(())
+    }
+}
+impl<L: fmt::Debug, E: fmt::Display> fmt::Display for Disagreement<L, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Query: {:?}", self.query)?;
+        writeln!(f, "  Native: {:?}", self.native)?;
+        match &self.ucsc {
+            Ok(ucsc) => write!(f, "  UCSC:   {ucsc:?}"),
+            Err(e) => write!(f, "  UCSC:   failed ({e})"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use biocore::location::ContigRange;
+
+    use crate::{Liftover, tests::CHAIN};
+
+    use super::{ranges_agree, sample_positions, sample_ranges};
+
+    fn range(contig: &str, start: u64, end: u64) -> ContigRange {
+        ContigRange {
+            contig: contig.to_owned(),
+            at: start..end,
+        }
+    }
+
+    #[test]
+    fn agreement() {
+        let native = [range("chrA", 10, 20), range("chrA", 25, 30)];
+        assert!(ranges_agree(&native, &[range("chrA", 10, 30)]));
+        assert!(ranges_agree(&native, &native));
+        assert!(!ranges_agree(&native, &[range("chrA", 10, 31)]));
+        assert!(!ranges_agree(&native[..1], &[range("chrA", 10, 30)]));
+        assert!(ranges_agree(&[], &[]));
+    }
+
+    #[test]
+    fn sampling() {
+        let liftover = Liftover::read(CHAIN.as_bytes()).unwrap();
+
+        let positions = sample_positions(&liftover, 100, 42);
+        assert_eq!(positions.len(), 100);
+        assert!(positions.iter().all(|p| p.at < 100));
+        assert_eq!(positions, sample_positions(&liftover, 100, 42));
+
+        let ranges = sample_ranges(&liftover, 100, 42);
+        assert_eq!(ranges.len(), 100);
+        assert!(
+            ranges
+                .iter()
+                .all(|r| r.at.start < r.at.end && r.at.end <= 100)
+        );
+    }
+}
@@ -0,0 +1,200 @@
+//! Writing chains back to the UCSC chain format, after checking they are consistent.
+
+use std::{
+    collections::BTreeSet,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use biocore::genome::Contig;
+use utile::range::RangeLen;
+
+use super::{Chain, ChainRange, Liftover};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ChainError {
+    #[error("Chain {id}: {side} range {start}..{end} is outside of {contig} (size {size}).")]
+    OutOfBounds {
+        id: u32,
+        side: &'static str,
+        contig: String,
+        start: u64,
+        end: u64,
+        size: u64,
+    },
+    #[error("Chain {id}: block {block} is empty.")]
+    EmptyBlock { id: u32, block: usize },
+    #[error("Chain {id}: block {block} is followed by a negative target gap ({dt}).")]
+    NegativeGap { id: u32, block: usize, dt: i64 },
+    #[error("Chain {id}: the blocks span {found} {side} bases, but the header states {expected}.")]
+    SpanMismatch {
+        id: u32,
+        side: &'static str,
+        expected: u64,
+        found: u64,
+    },
+    #[error("Duplicate chain id {0}.")]
+    DuplicateId(u32),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+impl From<ChainError> for io::Error {
+    fn from(e: ChainError) -> Self {
+        match e {
+            ChainError::Io(e) => e,
+            e => io::Error::new(io::ErrorKind::InvalidData, e),
+        }
+    }
+}
+
+impl<From, To> Liftover<From, To>
+where
+    From: Contig,
+    To: Contig,
+{
+    /// Writes all chains in the chain format, failing before writing anything if
+    /// a chain is inconsistent (see [Chain::validate]) or chain ids are repeated.
+    pub fn write(&self, mut writer: impl Write) -> Result<(), ChainError> {
+        let mut ids = BTreeSet::new();
+        for chain in &self.chains {
+            chain.validate()?;
+            if !ids.insert(chain.header.id) {
+                return Err(ChainError::DuplicateId(chain.header.id));
+            }
+        }
+
+        for chain in &self.chains {
+            writeln!(writer, "{chain}")?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+    pub fn write_file(&self, path: impl AsRef<Path>) -> Result<(), ChainError> {
+        self.write(BufWriter::new(File::create(path)?))
+    }
+}
+impl<From, To> Liftover<From, To> {
+    /// Sets each chain's score to its number of aligned bases.
+    ///
+    /// The UCSC score also rewards matching bases and penalises gaps, which needs the sequences,
+    /// so this is only a stand-in that keeps scores meaningful after editing chains.
+    pub fn recompute_scores(&mut self) {
+        for chain in &mut self.chains {
+            chain.header.score = chain.aligned_bases();
+        }
+    }
+}
+
+impl<From, To> Chain<From, To> {
+    /// The total size of the ungapped alignment blocks.
+    pub fn aligned_bases(&self) -> u64 {
+        self.blocks().map(|b| b.size).sum()
+    }
+}
+impl<From, To> Chain<From, To>
+where
+    From: Contig,
+    To: Contig,
+{
+    /// Checks that both header ranges fit in their contigs, that blocks are not empty,
+    /// that target gaps are not negative, and that the blocks span exactly the header ranges.
+    pub fn validate(&self) -> Result<(), ChainError> {
+        let id = self.header.id;
+        check_bounds(id, "target", &self.header.t)?;
+        check_bounds(id, "query", &self.header.q)?;
+
+        let (mut t, mut q) = (0, 0);
+        for (block, b) in self.blocks().enumerate() {
+            if b.size == 0 {
+                return Err(ChainError::EmptyBlock { id, block });
+            }
+            let Ok(dt) = u64::try_from(b.dt) else {
+                return Err(ChainError::NegativeGap {
+                    id,
+                    block,
+                    dt: b.dt,
+                });
+            };
+            t += b.size + dt;
+            q += b.size + b.dq;
+        }
+
+        for (side, expected, found) in [
+            ("target", self.header.t.v.at.range_len(), t),
+            ("query", self.header.q.v.at.range_len(), q),
+        ] {
+            if expected != found {
+                return Err(ChainError::SpanMismatch {
+                    id,
+                    side,
+                    expected,
+                    found,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+fn check_bounds<C: Contig>(
+    id: u32,
+    side: &'static str,
+    range: &ChainRange<C>,
+) -> Result<(), ChainError> {
+    let contig = &range.v.contig;
+    let at = &range.v.at;
+    if at.start > at.end || at.end > contig.size() {
+        return Err(ChainError::OutOfBounds {
+            id,
+            side,
+            contig: contig.as_ref().to_owned(),
+            start: at.start,
+            end: at.end,
+            size: contig.size(),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Liftover, tests::CHAIN};
+
+    use super::ChainError;
+
+    #[test]
+    fn round_trip() {
+        let mut liftover = Liftover::read(CHAIN.as_bytes()).unwrap();
+
+        let mut written = vec![];
+        liftover.write(&mut written).unwrap();
+        // Each chain is followed by a blank line.
+        assert_eq!(
+            String::from_utf8(written.clone()).unwrap(),
+            format!("{CHAIN}\n")
+        );
+        assert_eq!(Liftover::read(&*written).unwrap(), liftover);
+
+        liftover.recompute_scores();
+        assert_eq!(liftover.chains[0].header.score, 45);
+        assert_eq!(liftover.chains[1].header.score, 10);
+    }
+
+    #[test]
+    fn inconsistent() {
+        let mut liftover = Liftover::read(CHAIN.as_bytes()).unwrap();
+        liftover.chains[0].last_block = 30;
+        assert!(matches!(
+            liftover.write(vec![]),
+            Err(ChainError::SpanMismatch { id: 1, .. })
+        ));
+
+        let mut liftover = Liftover::read(CHAIN.as_bytes()).unwrap();
+        liftover.chains[1].header.id = 1;
+        assert!(matches!(
+            liftover.write(vec![]),
+            Err(ChainError::DuplicateId(1))
+        ));
+    }
+}
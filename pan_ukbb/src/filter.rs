@@ -0,0 +1,325 @@
+//! Filters checked against the raw columns of the summary stats, so that failing rows
+//! are never deserialized.
+
+use std::{
+    collections::BTreeSet,
+    io::{self, Read},
+    ops::RangeInclusive,
+};
+
+use biocore::location::{ContigPosition, ContigRange};
+use csv::ByteRecord;
+use hail::contig::GRCh37Contig;
+use serde::{Deserialize, de::DeserializeOwned};
+use utile::serde_ext::StringDeserializer;
+
+use resource::RawResource;
+
+use crate::{Population, SummaryStats};
+
+/// Which summary stats to keep, all criteria must pass.
+///
+/// By default the statistics of the `_hq` meta-analysis are checked. With [Self::populations],
+/// the per-population statistics are used instead, and a row is kept if any population passes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SummaryStatsFilter<Contig = GRCh37Contig> {
+    min_neglog10_pval: Option<f64>,
+    populations: Option<BTreeSet<Population>>,
+    maf: Option<RangeInclusive<f64>>,
+    exclude_low_confidence: bool,
+    regions: Vec<ContigRange<Contig>>,
+}
+
+impl<Contig> Default for SummaryStatsFilter<Contig> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<Contig> SummaryStatsFilter<Contig> {
+    /// Keeps everything.
+    pub fn new() -> Self {
+        Self {
+            min_neglog10_pval: None,
+            populations: None,
+            maf: None,
+            exclude_low_confidence: false,
+            regions: vec![],
+        }
+    }
+    /// Rows with a missing p-value are dropped.
+    pub fn min_neglog10_pval(mut self, min: f64) -> Self {
+        self.min_neglog10_pval = Some(min);
+        self
+    }
+    /// Checks the statistics of these populations, e.g. [PhenotypeManifestEntry::pops_pass_qc](crate::PhenotypeManifestEntry::pops_pass_qc).
+    pub fn populations(mut self, populations: impl IntoIterator<Item = Population>) -> Self {
+        self.populations = Some(populations.into_iter().collect());
+        self
+    }
+    /// Minor allele frequency, from the allele frequency in controls for binary phenotypes.
+    /// Rows with a missing frequency are dropped.
+    pub fn maf(mut self, range: RangeInclusive<f64>) -> Self {
+        self.maf = Some(range);
+        self
+    }
+    /// Drops rows flagged as low confidence: in any population for the meta-analysis,
+    /// or in the population checked otherwise.
+    pub fn exclude_low_confidence(mut self) -> Self {
+        self.exclude_low_confidence = true;
+        self
+    }
+    /// Restricts to variants starting in `range`, can be called several times to keep any of them.
+    pub fn region(mut self, range: ContigRange<Contig>) -> Self {
+        self.regions.push(range);
+        self
+    }
+
+    pub fn load(
+        self,
+        resource: impl RawResource,
+    ) -> io::Result<impl Iterator<Item = csv::Result<SummaryStats<Contig>>>>
+    where
+        Contig: DeserializeOwned + PartialEq,
+    {
+        Ok(self.apply(resource.read()?)?)
+    }
+    pub fn apply(
+        self,
+        reader: impl Read,
+    ) -> csv::Result<impl Iterator<Item = csv::Result<SummaryStats<Contig>>>>
+    where
+        Contig: DeserializeOwned + PartialEq,
+    {
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(b'\t')
+            .has_headers(true)
+            .from_reader(reader);
+        let headers = reader.byte_headers()?.clone();
+        let predicate = Predicate::compile(self, &headers);
+
+        let mut record = ByteRecord::new();
+        Ok(std::iter::from_fn(move || {
+            loop {
+                match reader.read_byte_record(&mut record) {
+                    Ok(true) => {}
+                    Ok(false) => return None,
+                    Err(e) => return Some(Err(e)),
+                }
+                if predicate.matches(&record) {
+                    return Some(record.deserialize(Some(&headers)));
+                }
+            }
+        }))
+    }
+}
+
+impl<Contig> SummaryStats<Contig> {
+    /// Like [Self::load], but only deserializes the rows passing `filter`.
+    pub fn load_filtered(
+        resource: impl RawResource,
+        filter: SummaryStatsFilter<Contig>,
+    ) -> io::Result<impl Iterator<Item = csv::Result<Self>>>
+    where
+        Contig: DeserializeOwned + PartialEq,
+    {
+        filter.load(resource)
+    }
+}
+
+/// A [SummaryStatsFilter] with its columns resolved against the header.
+struct Predicate<Contig> {
+    filter: SummaryStatsFilter<Contig>,
+    chr: Option<usize>,
+    pos: Option<usize>,
+    /// Checked for the meta-analysis only.
+    low_confidence: Vec<usize>,
+    /// A row passes if any of these does.
+    groups: Vec<Columns>,
+}
+struct Columns {
+    neglog10_pval: Option<usize>,
+    af: Option<usize>,
+    low_confidence: Option<usize>,
+}
+
+impl<Contig> Predicate<Contig> {
+    fn compile(filter: SummaryStatsFilter<Contig>, headers: &ByteRecord) -> Self {
+        let column = |name: &str| headers.iter().position(|h| h == name.as_bytes());
+        // Binary phenotypes have frequencies in cases and controls instead.
+        let af = |suffix: &str| {
+            column(&format!("af_{suffix}")).or(column(&format!("af_controls_{suffix}")))
+        };
+
+        let (low_confidence, groups) = match &filter.populations {
+            None => (
+                Population::all()
+                    .iter()
+                    .filter_map(|p| column(&format!("low_confidence_{p}")))
+                    .collect(),
+                vec![Columns {
+                    neglog10_pval: column("neglog10_pval_meta_hq"),
+                    af: af("meta_hq"),
+                    low_confidence: None,
+                }],
+            ),
+            Some(populations) => (
+                vec![],
+                populations
+                    .iter()
+                    // Populations the phenotype wasn't GWASed in have no columns.
+                    .filter(|p| column(&format!("neglog10_pval_{p}")).is_some())
+                    .map(|p| Columns {
+                        neglog10_pval: column(&format!("neglog10_pval_{p}")),
+                        af: af(&p.to_string()),
+                        low_confidence: column(&format!("low_confidence_{p}")),
+                    })
+                    .collect(),
+            ),
+        };
+
+        Self {
+            chr: column("chr"),
+            pos: column("pos"),
+            low_confidence: if filter.exclude_low_confidence {
+                low_confidence
+            } else {
+                vec![]
+            },
+            groups,
+            filter,
+        }
+    }
+
+    fn matches(&self, record: &ByteRecord) -> bool
+    where
+        Contig: DeserializeOwned + PartialEq,
+    {
+        if !self.filter.regions.is_empty() {
+            // Rows that can't be read are kept, so deserialization reports the error.
+            let Some(at) = self.at(record) else {
+                return true;
+            };
+            let in_region = self
+                .filter
+                .regions
+                .iter()
+                .any(|r| r.contig == at.contig && r.at.contains(&at.at));
+            if !in_region {
+                return false;
+            }
+        }
+        if self.low_confidence.iter().any(|&i| flag(record, i)) {
+            return false;
+        }
+        self.groups.iter().any(|g| self.group_matches(g, record))
+    }
+    fn group_matches(&self, columns: &Columns, record: &ByteRecord) -> bool {
+        let filter = &self.filter;
+        if let Some(min) = filter.min_neglog10_pval {
+            match columns.neglog10_pval.and_then(|i| number(record, i)) {
+                Some(p) if p >= min => {}
+                _ => return false,
+            }
+        }
+        if let Some(range) = &filter.maf {
+            match columns.af.and_then(|i| number(record, i)) {
+                Some(af) if range.contains(&af.min(1.0 - af)) => {}
+                _ => return false,
+            }
+        }
+        if filter.exclude_low_confidence && columns.low_confidence.is_some_and(|i| flag(record, i))
+        {
+            return false;
+        }
+        true
+    }
+    fn at(&self, record: &ByteRecord) -> Option<ContigPosition<Contig>>
+    where
+        Contig: DeserializeOwned,
+    {
+        let chr = field(record, self.chr?)?;
+        let contig =
+            Contig::deserialize(StringDeserializer::<serde::de::value::Error>::new(chr)).ok()?;
+        let pos: u64 = field(record, self.pos?)?.parse().ok()?;
+        Some(ContigPosition {
+            contig,
+            at: pos.checked_sub(1)?,
+        })
+    }
+}
+
+fn field(record: &ByteRecord, i: usize) -> Option<&str> {
+    std::str::from_utf8(record.get(i)?).ok()
+}
+/// [None] for `NA` and empty values.
+fn number(record: &ByteRecord, i: usize) -> Option<f64> {
+    field(record, i)?.parse().ok()
+}
+fn flag(record: &ByteRecord, i: usize) -> bool {
+    matches!(record.get(i), Some(b"true" | b"True"))
+}
+
+#[cfg(test)]
+mod tests {
+    use biocore::location::ContigRange;
+    use hail::contig::GRCh37Contig;
+
+    use crate::Population;
+
+    use super::SummaryStatsFilter;
+
+    const TSV: &str = "chr\tpos\tref\talt\taf_meta_hq\tbeta_meta_hq\tse_meta_hq\tneglog10_pval_meta_hq\t\
+neglog10_pval_heterogeneity_hq\tbeta_meta\tse_meta\tneglog10_pval_meta\tneglog10_pval_heterogeneity\t\
+af_EUR\tneglog10_pval_EUR\tlow_confidence_EUR
+1\t100\tA\tG\t0.3\tNA\tNA\t9.0\tNA\tNA\tNA\tNA\tNA\t0.3\t9.0\tfalse
+1\t200\tA\tG\t0.001\tNA\tNA\t9.0\tNA\tNA\tNA\tNA\tNA\t0.001\t9.0\ttrue
+1\t300\tA\tG\t0.9\tNA\tNA\t1.0\tNA\tNA\tNA\tNA\tNA\t0.9\t8.0\tfalse
+2\t100\tA\tG\t0.5\tNA\tNA\tNA\tNA\tNA\tNA\tNA\tNA\tNA\tNA\tNA
+";
+
+    fn positions(filter: SummaryStatsFilter) -> Vec<(GRCh37Contig, u64)> {
+        filter
+            .apply(TSV.as_bytes())
+            .unwrap()
+            .map(|s| s.unwrap())
+            .map(|s| (s.chr, s.pos))
+            .collect()
+    }
+
+    #[test]
+    fn filter() {
+        assert_eq!(positions(SummaryStatsFilter::new()).len(), 4);
+        assert_eq!(
+            positions(SummaryStatsFilter::new().min_neglog10_pval(7.3)),
+            [(GRCh37Contig::CHR1, 100), (GRCh37Contig::CHR1, 200)]
+        );
+        assert_eq!(
+            positions(SummaryStatsFilter::new().maf(0.01..=0.5)),
+            [
+                (GRCh37Contig::CHR1, 100),
+                (GRCh37Contig::CHR1, 300),
+                (GRCh37Contig::CHR2, 100)
+            ]
+        );
+        assert_eq!(
+            positions(SummaryStatsFilter::new().exclude_low_confidence()).len(),
+            3
+        );
+        assert_eq!(
+            positions(
+                SummaryStatsFilter::new()
+                    .populations([Population::Eur, Population::Afr])
+                    .min_neglog10_pval(7.3)
+                    .exclude_low_confidence()
+            ),
+            [(GRCh37Contig::CHR1, 100), (GRCh37Contig::CHR1, 300)]
+        );
+        assert_eq!(
+            positions(SummaryStatsFilter::new().region(ContigRange {
+                contig: GRCh37Contig::CHR1,
+                at: 150..250,
+            })),
+            [(GRCh37Contig::CHR1, 200)]
+        );
+    }
+}
@@ -0,0 +1,175 @@
+//! The heritability manifest, with one row per phenotype and population.
+//!
+//! https://pan.ukbb.broadinstitute.org/docs/heritability
+
+use std::io::Read;
+
+use ordered_float::NotNan;
+use serde::{Deserialize, Serialize};
+
+use resource::{RawResource, RawResourceExt};
+
+use crate::{
+    Modifier, PanUKBBS3Resource, PhenoSex, PhenotypeManifestEntry, Population, TraitType, s,
+};
+
+/// The 5 fields that identify a phenotype across the manifests.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Deserialize, Serialize)]
+pub struct PhenotypeKey {
+    pub trait_type: TraitType,
+    pub phenocode: String,
+    pub pheno_sex: PhenoSex,
+    pub coding: Option<String>,
+    pub modifier: Option<Modifier>,
+}
+
+/// Columns not listed here are ignored.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Deserialize, Serialize)]
+pub struct HeritabilityManifestEntry {
+    // Phenotype ID fields, see [PhenotypeManifestEntry].
+    pub trait_type: TraitType,
+    pub phenocode: String,
+    pub pheno_sex: PhenoSex,
+    pub coding: Option<String>,
+    pub modifier: Option<Modifier>,
+    /// The population the estimates are for.
+    pub ancestry: Population,
+
+    // Final estimates, from the method picked for the population
+    // (S-LDSC for EUR, RHE-mc otherwise).
+    #[serde(rename = "estimates.final.h2_observed", with = "s::opt", default)]
+    pub h2_observed: Option<NotNan<f64>>,
+    #[serde(rename = "estimates.final.h2_observed_se", with = "s::opt", default)]
+    pub h2_observed_se: Option<NotNan<f64>>,
+    /// On the liability scale, for binary phenotypes.
+    #[serde(rename = "estimates.final.h2_liability", with = "s::opt", default)]
+    pub h2_liability: Option<NotNan<f64>>,
+    #[serde(rename = "estimates.final.h2_liability_se", with = "s::opt", default)]
+    pub h2_liability_se: Option<NotNan<f64>>,
+    #[serde(rename = "estimates.final.h2_z", with = "s::opt", default)]
+    pub h2_z: Option<NotNan<f64>>,
+
+    // Univariate LDSC estimates.
+    #[serde(rename = "estimates.ldsc.h2_observed", with = "s::opt", default)]
+    pub ldsc_h2_observed: Option<NotNan<f64>>,
+    #[serde(rename = "estimates.ldsc.h2_observed_se", with = "s::opt", default)]
+    pub ldsc_h2_observed_se: Option<NotNan<f64>>,
+    #[serde(rename = "estimates.ldsc.intercept", with = "s::opt", default)]
+    pub ldsc_intercept: Option<NotNan<f64>>,
+    #[serde(rename = "estimates.ldsc.intercept_se", with = "s::opt", default)]
+    pub ldsc_intercept_se: Option<NotNan<f64>>,
+    /// `(intercept - 1) / (mean_chi2 - 1)`, the share of inflation not due to polygenicity.
+    #[serde(rename = "estimates.ldsc.ratio", with = "s::opt", default)]
+    pub ldsc_ratio: Option<NotNan<f64>>,
+    #[serde(rename = "estimates.ldsc.lambda_gc", with = "s::opt", default)]
+    pub ldsc_lambda_gc: Option<NotNan<f64>>,
+    #[serde(rename = "estimates.ldsc.mean_chi2", with = "s::opt", default)]
+    pub ldsc_mean_chi2: Option<NotNan<f64>>,
+
+    // QC flags, which together give the phenotype_qc_{pop} field of the phenotype manifest.
+    #[serde(rename = "qcflags.GWAS_run", with = "s::opt", default)]
+    pub qc_gwas_run: Option<bool>,
+    #[serde(rename = "qcflags.ancestry_reasonable_n", with = "s::opt", default)]
+    pub qc_ancestry_reasonable_n: Option<bool>,
+    #[serde(rename = "qcflags.defined_h2", with = "s::opt", default)]
+    pub qc_defined_h2: Option<bool>,
+    #[serde(rename = "qcflags.significant_z", with = "s::opt", default)]
+    pub qc_significant_z: Option<bool>,
+    #[serde(rename = "qcflags.in_bounds_h2", with = "s::opt", default)]
+    pub qc_in_bounds_h2: Option<bool>,
+    #[serde(rename = "qcflags.normal_lambda", with = "s::opt", default)]
+    pub qc_normal_lambda: Option<bool>,
+    #[serde(rename = "qcflags.normal_ratio", with = "s::opt", default)]
+    pub qc_normal_ratio: Option<bool>,
+    #[serde(rename = "qcflags.EUR_plus_1", with = "s::opt", default)]
+    pub qc_eur_plus_1: Option<bool>,
+    #[serde(rename = "qcflags.pass_all", with = "s::opt", default)]
+    pub qc_pass_all: Option<bool>,
+}
+
+impl HeritabilityManifestEntry {
+    pub async fn load_default() -> csv::Result<Vec<Self>> {
+        let resource = PanUKBBS3Resource::heritability_manifest()
+            .log_progress()
+            .with_global_fs_cache()
+            .ensure_cached_async()
+            .await?
+            .decompressed()
+            .buffered();
+
+        Self::load(resource)
+    }
+
+    pub fn load(resource: impl RawResource) -> csv::Result<Vec<Self>> {
+        Self::from_reader(resource.read()?)
+    }
+    pub async fn load_async(resource: impl RawResource) -> csv::Result<Vec<Self>> {
+        Self::from_reader(std::io::Cursor::new(resource.read_vec_async().await?))
+    }
+    fn from_reader(reader: impl Read) -> csv::Result<Vec<Self>> {
+        csv::ReaderBuilder::new()
+            .delimiter(b'\t')
+            .has_headers(true)
+            .from_reader(reader)
+            .into_deserialize()
+            .try_collect()
+    }
+
+    pub fn key(&self) -> PhenotypeKey {
+        PhenotypeKey {
+            trait_type: self.trait_type.clone(),
+            phenocode: self.phenocode.clone(),
+            pheno_sex: self.pheno_sex.clone(),
+            coding: self.coding.clone(),
+            modifier: self.modifier.clone(),
+        }
+    }
+}
+
+impl PhenotypeManifestEntry {
+    pub fn key(&self) -> PhenotypeKey {
+        PhenotypeKey {
+            trait_type: self.trait_type.clone(),
+            phenocode: self.phenocode.clone(),
+            pheno_sex: self.pheno_sex.clone(),
+            coding: self.coding.clone(),
+            modifier: self.modifier.clone(),
+        }
+    }
+    /// The heritability estimates of this phenotype, one per population.
+    pub fn heritability<'a>(
+        &self,
+        manifest: &'a [HeritabilityManifestEntry],
+    ) -> impl Iterator<Item = &'a HeritabilityManifestEntry> {
+        let key = self.key();
+        manifest.iter().filter(move |e| e.key() == key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{PhenoSex, Population, TraitType};
+
+    use super::HeritabilityManifestEntry;
+
+    #[test]
+    fn parse() {
+        let tsv = "trait_type\tphenocode\tpheno_sex\tcoding\tmodifier\tancestry\t\
+estimates.final.h2_observed\testimates.final.h2_z\testimates.ldsc.ratio\tqcflags.pass_all\textra
+continuous\t50\tboth_sexes\t\tirnt\tEUR\t0.52\t40.1\tNA\ttrue\tx
+";
+        let entries = HeritabilityManifestEntry::from_reader(tsv.as_bytes()).unwrap();
+        let entry = &entries[0];
+        assert_eq!(entry.trait_type, TraitType::Continuous);
+        assert_eq!(entry.pheno_sex, PhenoSex::BothSexes);
+        assert_eq!(entry.coding, None);
+        assert_eq!(entry.ancestry, Population::Eur);
+        assert_eq!(entry.h2_observed.map(|v| *v), Some(0.52));
+        assert_eq!(entry.ldsc_ratio, None);
+        assert_eq!(entry.h2_liability, None);
+        assert_eq!(entry.qc_pass_all, Some(true));
+        assert_eq!(entry.key().phenocode, "50");
+    }
+}
@@ -0,0 +1,156 @@
+//! Checks of downloaded summary stats against the sizes and hashes in the phenotype manifest,
+//! so truncated downloads are caught before parsing.
+
+use std::io;
+
+use resource::{RawResource, RawResourceExt};
+
+use crate::PhenotypeManifestEntry;
+
+#[derive(Debug, thiserror::Error)]
+pub enum IntegrityError {
+    #[error("{file}: expected {expected} bytes, found {found}.")]
+    Size {
+        file: String,
+        expected: u64,
+        found: u64,
+    },
+    #[error("{file}: expected md5 {expected}, found {found}.")]
+    Md5 {
+        file: String,
+        expected: String,
+        found: String,
+    },
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+impl From<IntegrityError> for io::Error {
+    fn from(e: IntegrityError) -> Self {
+        match e {
+            IntegrityError::Io(e) => e,
+            e => io::Error::new(io::ErrorKind::InvalidData, e),
+        }
+    }
+}
+
+/// How thoroughly to check a file. Hashing reads the whole file, which takes a while
+/// for the larger phenotypes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Verification {
+    Size,
+    /// Size and md5 hash.
+    Md5,
+}
+
+impl PhenotypeManifestEntry {
+    /// Checks the (still compressed) summary stats in `resource`, usually a cached copy.
+    pub fn summary_stats_verify(
+        &self,
+        resource: &impl RawResource,
+        verification: Verification,
+    ) -> Result<(), IntegrityError> {
+        verify(
+            resource,
+            verification,
+            &self.filename,
+            u64::try_from(self.size_in_bytes).unwrap(),
+            &self.md5_hex,
+        )
+    }
+
+    /// Caches the summary stats and checks the download.
+    ///
+    /// On a mismatch the file is evicted from the cache, and downloaded once more if `retry`.
+    pub async fn summary_stats_cached_verified(
+        &self,
+        verification: Verification,
+        mut retry: bool,
+    ) -> Result<impl RawResource + use<>, IntegrityError> {
+        let resource = self
+            .summary_stats_resource()
+            .log_progress()
+            .with_global_fs_cache();
+
+        loop {
+            resource.cache_async().await?;
+            match self.summary_stats_verify(&resource, verification) {
+                Ok(()) => return Ok(resource),
+                Err(e @ (IntegrityError::Size { .. } | IntegrityError::Md5 { .. })) => {
+                    log::warn!("[Pan-UKBB] {e}");
+                    resource.invalidate_async().await?;
+                    if !retry {
+                        return Err(e);
+                    }
+                    retry = false;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+fn verify(
+    resource: &impl RawResource,
+    verification: Verification,
+    file: &str,
+    expected_size: u64,
+    expected_md5: &str,
+) -> Result<(), IntegrityError> {
+    let found = resource.size()?;
+    if found != expected_size {
+        return Err(IntegrityError::Size {
+            file: file.to_owned(),
+            expected: expected_size,
+            found,
+        });
+    }
+
+    if verification == Verification::Md5 {
+        let mut context = md5::Context::new();
+        io::copy(&mut resource.read()?, &mut context)?;
+        let found = format!("{:x}", context.compute());
+        if !found.eq_ignore_ascii_case(expected_md5) {
+            return Err(IntegrityError::Md5 {
+                file: file.to_owned(),
+                expected: expected_md5.to_owned(),
+                found,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use resource::fs::FsCache;
+
+    use super::*;
+
+    const ABC_MD5: &str = "900150983cd24fb0d6963f7d28e17f72";
+
+    #[test]
+    fn verify_size_and_md5() {
+        let (cache, _dir) = FsCache::new_temp();
+        let entry = cache.entry("abc.tsv.bgz");
+        entry.write_file(&b"abc"[..]).unwrap();
+
+        verify(&entry, Verification::Size, "abc", 3, "").unwrap();
+        verify(&entry, Verification::Md5, "abc", 3, ABC_MD5).unwrap();
+        verify(&entry, Verification::Md5, "abc", 3, &ABC_MD5.to_uppercase()).unwrap();
+
+        assert!(matches!(
+            verify(&entry, Verification::Size, "abc", 4, ABC_MD5),
+            Err(IntegrityError::Size {
+                expected: 4,
+                found: 3,
+                ..
+            })
+        ));
+        // Only checked when asked for.
+        verify(&entry, Verification::Size, "abc", 3, "00").unwrap();
+        let error = verify(&entry, Verification::Md5, "abc", 3, "00").unwrap_err();
+        assert!(matches!(&error, IntegrityError::Md5 { found, .. } if found == ABC_MD5));
+        assert_eq!(io::Error::from(error).kind(), io::ErrorKind::InvalidData);
+    }
+}
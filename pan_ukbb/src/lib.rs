@@ -1,20 +1,45 @@
 #![feature(iterator_try_collect)]
 
+pub mod filter;
+pub mod heritability;
+pub mod integrity;
+pub mod lift;
+pub mod remote;
+pub mod search;
+pub mod variant_qc;
+pub mod write;
+
 use std::{collections::BTreeSet, io, mem};
 
 use biocore::{
     dna::DnaSequence,
     location::{ContigPosition, ContigRange},
+    mutation::allele_match::Alleles,
 };
+use futures::Stream;
 use hail::contig::GRCh37Contig;
 use ordered_float::NotNan;
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use tokio::io::{AsyncBufReadExt, AsyncRead};
 use url::Url;
 
 use resource::{RawResource, RawResourceExt, UrlResource};
 
+pub use self::{
+    filter::SummaryStatsFilter,
+    heritability::{HeritabilityManifestEntry, PhenotypeKey},
+    integrity::{IntegrityError, Verification},
+    lift::SummaryStatsLiftover,
+    remote::RemoteSummaryStats,
+    search::{PhenotypeManifest, PhenotypeMatch, PhenotypeSearch},
+    variant_qc::{VariantKey, VariantQcMetrics},
+    write::SummaryStatsColumns,
+};
+
 const URL_BASE: &str = "https://pan-ukb-us-east-1.s3.amazonaws.com";
 const PHENOTYPE_MANIFEST_KEY: &str = "sumstats_release/phenotype_manifest.tsv.bgz";
+const HERITABILITY_MANIFEST_KEY: &str = "sumstats_release/h2_manifest.tsv.bgz";
+const VARIANT_QC_METRICS_KEY: &str = "sumstats_release/full_variant_qc_metrics.txt.bgz";
 
 pub struct PanUKBBS3Resource {
     pub key: String,
@@ -27,6 +52,12 @@ impl PanUKBBS3Resource {
     pub fn phenotype_manifest() -> Self {
         Self::new(PHENOTYPE_MANIFEST_KEY.to_owned())
     }
+    pub fn heritability_manifest() -> Self {
+        Self::new(HERITABILITY_MANIFEST_KEY.to_owned())
+    }
+    pub fn variant_qc_metrics() -> Self {
+        Self::new(VARIANT_QC_METRICS_KEY.to_owned())
+    }
 
     pub fn url(&self) -> Url {
         let key = &self.key;
@@ -66,6 +97,12 @@ impl RawResource for PanUKBBS3Resource {
     async fn read_async(&self) -> std::io::Result<Self::AsyncReader> {
         self.url_resource().read_async().await
     }
+    async fn read_range_async(
+        &self,
+        range: std::ops::Range<u64>,
+    ) -> std::io::Result<Option<Self::AsyncReader>> {
+        self.url_resource().read_range_async(range).await
+    }
 }
 
 /// https://pan.ukbb.broadinstitute.org/docs/per-phenotype-files
@@ -435,14 +472,23 @@ impl PhenotypeManifestEntry {
         &self,
     ) -> io::Result<impl Iterator<Item = csv::Result<SummaryStats>> + use<>> {
         SummaryStats::load(
+            self.summary_stats_cached_verified(Verification::Size, true)
+                .await?
+                .decompressed()
+                .buffered(),
+        )
+    }
+    /// Streams the summary stats straight from S3, without caching them.
+    pub async fn summary_stats_stream_default(
+        &self,
+    ) -> io::Result<impl Stream<Item = csv::Result<SummaryStats>> + use<>> {
+        SummaryStats::stream_async(
             self.summary_stats_resource()
                 .log_progress()
-                .with_global_fs_cache()
-                .ensure_cached_async()
-                .await?
                 .decompressed()
                 .buffered(),
         )
+        .await
     }
 
     pub fn summary_stats_tabix_resource(&self) -> PanUKBBS3Resource {
@@ -617,6 +663,47 @@ impl<Contig> SummaryStats<Contig> {
             .from_reader(resource.read()?)
             .into_deserialize())
     }
+    /// Like [Self::load], but reads and parses the records incrementally from
+    /// [RawResource::read_async], so the file doesn't need to be cached first.
+    pub async fn stream_async(
+        resource: impl RawResource,
+    ) -> io::Result<impl Stream<Item = csv::Result<Self>>>
+    where
+        Contig: DeserializeOwned,
+    {
+        Self::stream_from_reader(resource.read_async().await?).await
+    }
+    async fn stream_from_reader(
+        reader: impl AsyncRead,
+    ) -> io::Result<impl Stream<Item = csv::Result<Self>>>
+    where
+        Contig: DeserializeOwned,
+    {
+        let mut lines = tokio::io::BufReader::new(Box::pin(reader)).lines();
+        let Some(header) = lines.next_line().await? else {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Missing summary stats header",
+            ));
+        };
+        let header = tsv_record(&header);
+
+        // Ends after the first IO error.
+        Ok(futures::stream::unfold(
+            Some((lines, header)),
+            |state| async move {
+                let (mut lines, header) = state?;
+                match lines.next_line().await {
+                    Ok(Some(line)) => {
+                        let record = tsv_record(&line).deserialize(Some(&header));
+                        Some((record, Some((lines, header))))
+                    }
+                    Ok(None) => None,
+                    Err(e) => Some((Err(e.into()), None)),
+                }
+            },
+        ))
+    }
 
     pub fn at(&self) -> ContigPosition<Contig>
     where
@@ -627,6 +714,17 @@ impl<Contig> SummaryStats<Contig> {
             at: self.pos - 1,
         }
     }
+    /// The alternate (effect) and reference alleles, for
+    /// [AlleleMatcher](biocore::mutation::allele_match::AlleleMatcher).
+    ///
+    /// The frequency is from the meta-analysis, in controls for binary phenotypes.
+    pub fn alleles(&self) -> Alleles<'_> {
+        Alleles {
+            counted: &self.alt,
+            other: &self.ref_allele,
+            af: self.af_meta.or(self.af_controls_meta).map(|af| *af),
+        }
+    }
     pub fn at_range(&self) -> ContigRange<Contig>
     where
         Contig: Clone,
@@ -844,6 +942,11 @@ impl<Contig> SummaryStats<Contig> {
     }
 }
 
+/// A single tab-separated line, for parsing outside of a [csv::Reader].
+fn tsv_record(line: &str) -> csv::StringRecord {
+    csv::StringRecord::from(line.split('\t').collect::<Vec<_>>())
+}
+
 mod s {
     pub mod opt {
         use std::{fmt, marker::PhantomData};
@@ -1065,6 +1168,27 @@ mod tests {
 
     use super::*;
 
+    #[tokio::test]
+    async fn stream_summary_stats() {
+        use futures::StreamExt;
+
+        let mut tsv = "chr\tpos\tref\talt\tbeta_meta_hq\tse_meta_hq\tneglog10_pval_meta_hq\t\
+neglog10_pval_heterogeneity_hq\tbeta_meta\tse_meta\tneglog10_pval_meta\tneglog10_pval_heterogeneity\n"
+            .to_owned();
+        for pos in [100, 200] {
+            tsv += &format!("1\t{pos}\tA\tG\t0.5{}\n", "\tNA".repeat(7));
+        }
+        let stats: Vec<SummaryStats> = SummaryStats::stream_from_reader(tsv.as_bytes())
+            .await
+            .unwrap()
+            .map(|s| s.unwrap())
+            .collect()
+            .await;
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[1].pos, 200);
+        assert_eq!(stats[0].beta_meta_hq.map(|v| *v), Some(0.5));
+    }
+
     #[tokio::test]
     #[ignore]
     async fn test_get_latest_manifest() {
@@ -0,0 +1,256 @@
+//! Lifting summary stats to another assembly (e.g. GRCh38), alleles included.
+//!
+//! As in [VcfLiftover](liftover::vcf::VcfLiftover), alleles are reverse-complemented when the
+//! chain flips strand, and indels are then re-anchored on the base before them, which needs the
+//! target reference. With a reference, records whose alleles are swapped in the target assembly
+//! are flipped with [SummaryStats::flip_ref_alt].
+
+use std::io;
+
+use biocore::{
+    dna::DnaSequence,
+    genome::Contig,
+    location::{
+        ContigRange,
+        orientation::{SequenceOrientation, Stranded},
+    },
+    mutation::normalize::ReferenceSequence,
+};
+use liftover::{LiftoverIndexed, vcf::RejectReason};
+use utile::range::RangeLen;
+
+use crate::SummaryStats;
+
+/// See [SummaryStats::liftover_all].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SummaryStatsLiftover<From, To> {
+    pub lifted: Vec<SummaryStats<To>>,
+    pub rejected: Vec<(SummaryStats<From>, RejectReason)>,
+}
+
+impl<C> SummaryStats<C>
+where
+    C: AsRef<str> + Clone,
+{
+    /// Maps the record to the output assembly of `liftover`.
+    ///
+    /// Indels on strand-flipping chains can't be re-anchored without the reference,
+    /// see [Self::liftover_with_reference].
+    pub fn liftover<From, To>(
+        &self,
+        liftover: &LiftoverIndexed<From, To>,
+    ) -> Option<SummaryStats<To>>
+    where
+        From: Contig + Ord + Clone,
+        To: Contig + Clone,
+    {
+        self.lift(liftover, None).unwrap().ok()
+    }
+    /// Like [Self::liftover], but also checks the lifted reference allele against `genome`,
+    /// flipping the record if it matches the alternate allele instead.
+    pub fn liftover_with_reference<From, To>(
+        &self,
+        liftover: &LiftoverIndexed<From, To>,
+        genome: &mut impl ReferenceSequence<To>,
+    ) -> io::Result<Result<SummaryStats<To>, RejectReason>>
+    where
+        From: Contig + Ord + Clone,
+        To: Contig + Clone,
+    {
+        self.lift(liftover, Some(genome))
+    }
+    /// Lifts all records with [Self::liftover], keeping the ones that failed.
+    pub fn liftover_all<From, To>(
+        stats: impl IntoIterator<Item = Self>,
+        liftover: &LiftoverIndexed<From, To>,
+    ) -> SummaryStatsLiftover<C, To>
+    where
+        From: Contig + Ord + Clone,
+        To: Contig + Clone,
+    {
+        let mut result = SummaryStatsLiftover {
+            lifted: vec![],
+            rejected: vec![],
+        };
+        for stats in stats {
+            match stats.lift(liftover, None).unwrap() {
+                Ok(lifted) => result.lifted.push(lifted),
+                Err(reason) => result.rejected.push((stats, reason)),
+            }
+        }
+        result
+    }
+
+    /// The outer error is only for failures reading the reference.
+    fn lift<From, To>(
+        &self,
+        liftover: &LiftoverIndexed<From, To>,
+        mut genome: Option<&mut dyn ReferenceSequence<To>>,
+    ) -> io::Result<Result<SummaryStats<To>, RejectReason>>
+    where
+        From: Contig + Ord + Clone,
+        To: Contig + Clone,
+    {
+        let Some(contig) = liftover.find_input_contig(&self.chr) else {
+            return Ok(Err(RejectReason::Unmapped));
+        };
+        let len = u64::try_from(self.ref_allele.len()).unwrap();
+        let source = Stranded {
+            orientation: SequenceOrientation::Forward,
+            v: ContigRange {
+                contig,
+                at: self.pos - 1..self.pos - 1 + len,
+            },
+        };
+        let mut hits: Vec<_> = liftover.map_range_raw(&source).collect();
+        let complete = hits.iter().filter(|h| h.v.at.range_len() == len).count();
+        let mut target = match (hits.len(), complete) {
+            (0, _) => return Ok(Err(RejectReason::Unmapped)),
+            (1, 1) => hits.pop().unwrap(),
+            (_, 2..) => return Ok(Err(RejectReason::MultipleTargets)),
+            (_, _) => return Ok(Err(RejectReason::Fragmented)),
+        };
+        let flipped = target.orientation == SequenceOrientation::Reverse;
+        target.set_orientation(SequenceOrientation::Forward);
+        let ContigRange { contig, at } = target.v;
+
+        let mut start = at.start;
+        let mut ref_allele = self.ref_allele.clone();
+        let mut alt = self.alt.clone();
+        if flipped {
+            ref_allele = ref_allele.reverse_complement();
+            alt = alt.reverse_complement();
+
+            if ref_allele.len() != alt.len() {
+                let Some(genome) = genome.as_deref_mut() else {
+                    return Ok(Err(RejectReason::UnsupportedAllele));
+                };
+                let anchored = ref_allele.iter().last() == alt.iter().last();
+                if !anchored || start == 0 {
+                    return Ok(Err(RejectReason::UnsupportedAllele));
+                }
+
+                start -= 1;
+                let base = genome.fetch(&ContigRange {
+                    contig: contig.clone(),
+                    at: start..start + 1,
+                })?;
+                let reanchor = |allele: &DnaSequence| -> DnaSequence {
+                    base.iter()
+                        .chain(&allele[..allele.len() - 1])
+                        .copied()
+                        .collect()
+                };
+                ref_allele = reanchor(&ref_allele);
+                alt = reanchor(&alt);
+            }
+        }
+
+        let mut lifted = self.clone().map_contig(|_| contig.clone());
+        lifted.pos = start + 1;
+        lifted.ref_allele = ref_allele;
+        lifted.alt = alt;
+
+        if let Some(genome) = genome {
+            let found = genome.fetch(&ContigRange {
+                contig,
+                at: start..start + len,
+            })?;
+            if found == lifted.alt && found != lifted.ref_allele {
+                lifted.flip_ref_alt();
+            } else if found != lifted.ref_allele {
+                return Ok(Err(RejectReason::ReferenceMismatch));
+            }
+        }
+
+        Ok(Ok(lifted))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use biocore::{
+        dna::DnaBase,
+        genome::{ArcContig, InMemoryGenome},
+        sequence::AsciiChar,
+    };
+    use liftover::{Liftover, vcf::RejectReason};
+
+    use crate::SummaryStats;
+
+    // `chr1:0-10` maps to the reverse strand of `chrA:5-15`, so `chr1:x` is `chrA:14-x`.
+    const CHAIN: &str = "chain 100 chr1 20 + 0 10 chrA 20 - 5 15 1\n10\n\n";
+    const TARGET: &str = "AAAAACCGTTAGCATGGGGG";
+
+    #[test]
+    fn reverse_strand() {
+        let liftover = Liftover::read(CHAIN.as_bytes()).unwrap().indexed();
+        let target = DnaBase::decode(TARGET.as_bytes().to_vec()).unwrap();
+        let mut genome = InMemoryGenome::new([(ArcContig::new("chrA".into(), 20), target)]);
+
+        let mut tsv = "chr\tpos\tref\talt\tbeta_meta_hq\tse_meta_hq\tneglog10_pval_meta_hq\t\
+neglog10_pval_heterogeneity_hq\tbeta_meta\tse_meta\tneglog10_pval_meta\tneglog10_pval_heterogeneity\n"
+            .to_owned();
+        for (pos, ref_allele, alt) in [(3, "G", "A"), (4, "A", "C"), (5, "TA", "T"), (15, "A", "G")]
+        {
+            tsv += &format!(
+                "chr1\t{pos}\t{ref_allele}\t{alt}\t0.5{}\n",
+                "\tNA".repeat(7)
+            );
+        }
+        let stats: Vec<SummaryStats<String>> = csv::ReaderBuilder::new()
+            .delimiter(b'\t')
+            .from_reader(tsv.as_bytes())
+            .into_deserialize()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let describe = |s: &SummaryStats<ArcContig>| {
+            (
+                s.pos,
+                s.ref_allele.to_string(),
+                s.alt.to_string(),
+                s.beta_meta_hq.map(|b| *b),
+            )
+        };
+
+        let lifted = stats[0].liftover(&liftover).unwrap();
+        assert_eq!(lifted.chr.as_ref(), "chrA");
+        assert_eq!(
+            describe(&lifted),
+            (13, "C".to_owned(), "T".to_owned(), Some(0.5))
+        );
+
+        // The alleles are swapped in the target assembly.
+        let lifted = stats[1]
+            .liftover_with_reference(&liftover, &mut genome)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            describe(&lifted),
+            (12, "G".to_owned(), "T".to_owned(), Some(-0.5))
+        );
+
+        assert_eq!(stats[2].liftover(&liftover), None);
+        let lifted = stats[2]
+            .liftover_with_reference(&liftover, &mut genome)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            describe(&lifted),
+            (9, "TT".to_owned(), "T".to_owned(), Some(0.5))
+        );
+
+        let all = SummaryStats::liftover_all(stats, &liftover);
+        assert_eq!(all.lifted.len(), 2);
+        assert_eq!(
+            all.rejected
+                .iter()
+                .map(|(s, reason)| (s.pos, *reason))
+                .collect::<Vec<_>>(),
+            [
+                (5, RejectReason::UnsupportedAllele),
+                (15, RejectReason::Unmapped)
+            ]
+        );
+    }
+}
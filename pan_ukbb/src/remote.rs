@@ -0,0 +1,77 @@
+//! Region queries against the summary stats on S3, through their tabix index and HTTP range
+//! requests, so one-off lookups don't need the whole file cached.
+//!
+//! Requests are blocking.
+
+use std::io::{self, BufRead};
+
+use biocore::location::ContigRange;
+use hail::contig::GRCh37Contig;
+use noodles::{bgzf, core::region::Interval, csi::BinningIndex, tabix};
+use resource::{RawResourceExt, uri::UrlRangeReader};
+
+use crate::{PhenotypeManifestEntry, SummaryStats, tsv_record};
+
+/// Bytes fetched per request, a few bgzf blocks.
+const BLOCK_SIZE: u64 = 256 * 1024;
+
+/// See [PhenotypeManifestEntry::summary_stats_remote].
+pub struct RemoteSummaryStats {
+    index: tabix::Index,
+    reader: bgzf::io::Reader<UrlRangeReader>,
+    header: csv::StringRecord,
+}
+
+impl PhenotypeManifestEntry {
+    /// Fetches the tabix index and header of the summary stats, for [RemoteSummaryStats::query].
+    pub fn summary_stats_remote(&self) -> io::Result<RemoteSummaryStats> {
+        let index = self.summary_stats_tabix_resource().read_vec()?;
+        let index = tabix::io::Reader::new(&index[..]).read_index()?;
+
+        let data = self
+            .summary_stats_resource()
+            .url_resource()
+            .range_reader(BLOCK_SIZE)?;
+        let mut reader = bgzf::io::Reader::new(data);
+        let mut header = String::new();
+        reader.read_line(&mut header)?;
+
+        Ok(RemoteSummaryStats {
+            index,
+            reader,
+            header: tsv_record(header.trim_end()),
+        })
+    }
+}
+
+impl RemoteSummaryStats {
+    /// The summary stats starting in `range`, only the blocks covering it are downloaded.
+    pub fn query(&mut self, range: &ContigRange<GRCh37Contig>) -> io::Result<Vec<SummaryStats>> {
+        let Some(id) = self.contig_id(range.contig.as_ref()) else {
+            return Ok(vec![]);
+        };
+        let interval = Interval::try_from(range)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let chunks = self.index.query(id, interval)?;
+
+        let mut stats = vec![];
+        for line in noodles::csi::io::Query::new(&mut self.reader, chunks).lines() {
+            let record: SummaryStats = tsv_record(&line?)
+                .deserialize(Some(&self.header))
+                .map_err(io::Error::from)?;
+            // Chunks are by bin, so they can include records around the range.
+            if record.chr == range.contig && range.at.contains(&record.at().at) {
+                stats.push(record);
+            }
+        }
+        Ok(stats)
+    }
+
+    /// The index has contigs without the `chr` prefix, contigs that aren't indexed have no records.
+    fn contig_id(&self, name: &str) -> Option<usize> {
+        let names = self.index.header()?.reference_sequence_names();
+        names
+            .get_index_of(name.as_bytes())
+            .or_else(|| names.get_index_of(name.strip_prefix("chr")?.as_bytes()))
+    }
+}
@@ -0,0 +1,219 @@
+//! Finding phenotypes in the manifest by description and metadata.
+
+use std::{cmp::Reverse, collections::BTreeSet};
+
+use ordered_float::NotNan;
+use resource::RawResource;
+
+use crate::{PhenotypeManifestEntry, Population, TraitType};
+
+/// The loaded phenotype manifest.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PhenotypeManifest {
+    pub entries: Vec<PhenotypeManifestEntry>,
+}
+
+/// See [PhenotypeManifest::search].
+#[derive(Debug, Clone)]
+pub struct PhenotypeSearch<'a> {
+    manifest: &'a PhenotypeManifest,
+    text: Option<String>,
+    trait_types: BTreeSet<TraitType>,
+    category: Option<String>,
+    populations: BTreeSet<Population>,
+    passing_qc: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PhenotypeMatch<'a> {
+    /// In `(0, 1]`, 1 for an exact match of the description or phenocode.
+    pub score: NotNan<f64>,
+    pub entry: &'a PhenotypeManifestEntry,
+}
+
+impl PhenotypeManifest {
+    pub async fn load_default() -> csv::Result<Self> {
+        Ok(Self {
+            entries: PhenotypeManifestEntry::load_default().await?,
+        })
+    }
+    pub fn load(resource: impl RawResource) -> csv::Result<Self> {
+        Ok(Self {
+            entries: PhenotypeManifestEntry::load(resource)?,
+        })
+    }
+
+    /// Starts a query matching every phenotype, narrowed down with the [PhenotypeSearch] methods.
+    pub fn search(&self) -> PhenotypeSearch<'_> {
+        PhenotypeSearch {
+            manifest: self,
+            text: None,
+            trait_types: BTreeSet::new(),
+            category: None,
+            populations: BTreeSet::new(),
+            passing_qc: false,
+        }
+    }
+}
+
+impl<'a> PhenotypeSearch<'a> {
+    /// Matches the descriptions (and phenocode), by substring or by words allowing for typos.
+    pub fn text(mut self, text: &str) -> Self {
+        self.text = Some(text.to_lowercase());
+        self
+    }
+    /// Can be called several times to allow any of them.
+    pub fn trait_type(mut self, trait_type: TraitType) -> Self {
+        self.trait_types.insert(trait_type);
+        self
+    }
+    /// Case-insensitive substring of [PhenotypeManifestEntry::category].
+    pub fn category(mut self, category: &str) -> Self {
+        self.category = Some(category.to_lowercase());
+        self
+    }
+    /// Requires the phenotype to have been GWASed in `population`, can be called several times.
+    pub fn population(mut self, population: Population) -> Self {
+        self.populations.insert(population);
+        self
+    }
+    /// Checks [Self::population] against [PhenotypeManifestEntry::pops_pass_qc] instead,
+    /// and requires at least one population passing QC.
+    pub fn passing_qc(mut self) -> Self {
+        self.passing_qc = true;
+        self
+    }
+
+    /// Best matches first, ties go to the larger GWAS.
+    pub fn run(&self) -> Vec<PhenotypeMatch<'a>> {
+        let mut matches: Vec<_> = self
+            .manifest
+            .entries
+            .iter()
+            .filter_map(|entry| {
+                let score = self.score(entry)?;
+                Some(PhenotypeMatch {
+                    score: NotNan::new(score).unwrap(),
+                    entry,
+                })
+            })
+            .collect();
+        matches.sort_by_key(|m| {
+            (
+                Reverse(m.score),
+                Reverse(m.entry.n_cases_full_cohort_both_sexes),
+            )
+        });
+        matches
+    }
+
+    fn score(&self, entry: &PhenotypeManifestEntry) -> Option<f64> {
+        if !self.trait_types.is_empty() && !self.trait_types.contains(&entry.trait_type) {
+            return None;
+        }
+        if let Some(category) = &self.category {
+            let found = entry.category.as_ref()?.to_lowercase();
+            if !found.contains(category.as_str()) {
+                return None;
+            }
+        }
+        let populations = match self.passing_qc {
+            true => &entry.pops_pass_qc,
+            false => &entry.pops,
+        };
+        if !populations.is_superset(&self.populations) || populations.is_empty() {
+            return None;
+        }
+
+        let Some(text) = &self.text else {
+            return Some(1.);
+        };
+        if entry.phenocode.eq_ignore_ascii_case(text) {
+            return Some(1.);
+        }
+        let secondary = [&entry.description_more, &entry.coding_description];
+        let secondary: Vec<_> = secondary.into_iter().flatten().map(|s| &**s).collect();
+        let score = text_score(text, &entry.description, &secondary);
+        (score > 0.).then_some(score)
+    }
+}
+
+/// Scores a lowercase query against a primary and some secondary texts, 0 for no match.
+fn text_score(query: &str, primary: &str, secondary: &[&str]) -> f64 {
+    let primary = primary.to_lowercase();
+    let secondary: Vec<_> = secondary.iter().map(|s| s.to_lowercase()).collect();
+
+    if primary == query {
+        return 1.;
+    }
+    if primary.contains(query) {
+        return 0.9;
+    }
+    if secondary.iter().any(|s| s.contains(query)) {
+        return 0.7;
+    }
+
+    // Otherwise, the share of query words found, allowing for a typo in longer words.
+    let words: Vec<&str> = std::iter::once(&primary)
+        .chain(&secondary)
+        .flat_map(|s| s.split(|c: char| !c.is_alphanumeric()))
+        .filter(|w| !w.is_empty())
+        .collect();
+    let query: Vec<&str> = query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect();
+    if query.is_empty() {
+        return 0.;
+    }
+    let found: f64 = query
+        .iter()
+        .map(|q| {
+            let best = words.iter().map(|w| {
+                if w.contains(q) {
+                    1.
+                } else if q.chars().count() >= 4 && edit_distance(q, w) <= 1 {
+                    0.5
+                } else {
+                    0.
+                }
+            });
+            best.fold(0., f64::max)
+        })
+        .sum();
+    0.6 * found / query.len() as f64
+}
+
+/// Levenshtein distance.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{edit_distance, text_score};
+
+    #[test]
+    fn scores() {
+        assert_eq!(edit_distance("height", "hieght"), 2);
+        assert_eq!(edit_distance("height", "heigt"), 1);
+
+        let score = |query| text_score(query, "Standing height", &["Height in cm"]);
+        assert_eq!(score("standing height"), 1.);
+        assert_eq!(score("height"), 0.9);
+        assert_eq!(score("in cm"), 0.7);
+        assert_eq!(score("heigt standing"), 0.6 * 1.5 / 2.);
+        assert_eq!(score("blood"), 0.);
+        assert!(score("height standing") > score("standing weight"));
+    }
+}
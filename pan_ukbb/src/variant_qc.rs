@@ -0,0 +1,198 @@
+//! The full variant QC file, with imputation quality and the high-quality flag of each variant.
+//!
+//! https://pan.ukbb.broadinstitute.org/docs/qc#variant-qc
+
+use std::io::{self, Read};
+
+use biocore::{dna::DnaSequence, location::ContigPosition};
+use hail::contig::GRCh37Contig;
+use ordered_float::NotNan;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use resource::{RawResource, RawResourceExt};
+
+use crate::{PanUKBBS3Resource, SummaryStats, s};
+
+/// A variant, by position and alleles.
+pub type VariantKey<Contig = GRCh37Contig> = (ContigPosition<Contig>, DnaSequence, DnaSequence);
+
+/// Columns not listed here are ignored.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Deserialize, Serialize)]
+#[allow(non_snake_case)]
+pub struct VariantQcMetrics<Contig = GRCh37Contig> {
+    pub chrom: Contig,
+    /// 1-based, in GRCh37 coordinates.
+    pub pos: u64,
+    #[serde(rename = "ref")]
+    pub ref_allele: DnaSequence,
+    pub alt: DnaSequence,
+    #[serde(with = "s::opt", default)]
+    pub rsid: Option<String>,
+    #[serde(with = "s::opt", default)]
+    pub varid: Option<String>,
+    /// The most severe consequence (VEP).
+    #[serde(with = "s::opt", default)]
+    pub consequence: Option<String>,
+
+    /// Imputation INFO score from UK Biobank.
+    #[serde(with = "s::opt", default)]
+    pub info: Option<NotNan<f64>>,
+    #[serde(with = "s::opt", default)]
+    pub call_rate: Option<NotNan<f64>>,
+    /// Whether the variant passes the high-quality filters used for the `_hq` meta-analysis.
+    #[serde(with = "s::opt", default)]
+    pub high_quality: Option<bool>,
+
+    #[serde(with = "s::opt", default)]
+    pub af_AFR: Option<NotNan<f64>>,
+    #[serde(with = "s::opt", default)]
+    pub af_AMR: Option<NotNan<f64>>,
+    #[serde(with = "s::opt", default)]
+    pub af_CSA: Option<NotNan<f64>>,
+    #[serde(with = "s::opt", default)]
+    pub af_EAS: Option<NotNan<f64>>,
+    #[serde(with = "s::opt", default)]
+    pub af_EUR: Option<NotNan<f64>>,
+    #[serde(with = "s::opt", default)]
+    pub af_MID: Option<NotNan<f64>>,
+}
+
+impl VariantQcMetrics {
+    pub async fn load_default() -> io::Result<impl Iterator<Item = csv::Result<Self>> + use<>> {
+        Self::load(
+            PanUKBBS3Resource::variant_qc_metrics()
+                .log_progress()
+                .with_global_fs_cache()
+                .ensure_cached_async()
+                .await?
+                .decompressed()
+                .buffered(),
+        )
+    }
+}
+impl<Contig> VariantQcMetrics<Contig> {
+    pub fn load(resource: impl RawResource) -> io::Result<impl Iterator<Item = csv::Result<Self>>>
+    where
+        Contig: DeserializeOwned,
+    {
+        Ok(Self::from_reader(resource.read()?))
+    }
+    fn from_reader(reader: impl Read) -> impl Iterator<Item = csv::Result<Self>>
+    where
+        Contig: DeserializeOwned,
+    {
+        csv::ReaderBuilder::new()
+            .delimiter(b'\t')
+            .has_headers(true)
+            .from_reader(reader)
+            .into_deserialize()
+    }
+
+    pub fn at(&self) -> ContigPosition<Contig>
+    where
+        Contig: Clone,
+    {
+        ContigPosition {
+            contig: self.chrom.clone(),
+            at: self.pos - 1,
+        }
+    }
+    pub fn key(&self) -> VariantKey<Contig>
+    where
+        Contig: Clone,
+    {
+        (self.at(), self.ref_allele.clone(), self.alt.clone())
+    }
+}
+
+impl<Contig: Clone> SummaryStats<Contig> {
+    pub fn key(&self) -> VariantKey<Contig> {
+        (self.at(), self.ref_allele.clone(), self.alt.clone())
+    }
+}
+
+/// Keeps the summary stats of variants flagged [VariantQcMetrics::high_quality].
+///
+/// Both must be sorted by position (as released), and are walked in step.
+pub fn retain_high_quality<Contig: Ord + Clone>(
+    stats: impl Iterator<Item = csv::Result<SummaryStats<Contig>>>,
+    qc: impl Iterator<Item = csv::Result<VariantQcMetrics<Contig>>>,
+) -> impl Iterator<Item = csv::Result<SummaryStats<Contig>>> {
+    let mut qc = qc.peekable();
+    // The QC rows at the position of the last summary stats.
+    let mut current: Vec<VariantQcMetrics<Contig>> = vec![];
+    stats.filter_map(move |stats| {
+        let stats = match stats {
+            Ok(stats) => stats,
+            Err(e) => return Some(Err(e)),
+        };
+        let at = stats.at();
+        if current.first().is_none_or(|q| q.at() != at) {
+            current.clear();
+            loop {
+                match qc.peek() {
+                    Some(Ok(q)) if q.at() < at => drop(qc.next()),
+                    Some(Ok(q)) if q.at() == at => current.push(qc.next().unwrap().unwrap()),
+                    Some(Err(_)) => {
+                        let Some(Err(e)) = qc.next() else {
+                            unreachable!()
+                        };
+                        return Some(Err(e));
+                    }
+                    _ => break,
+                }
+            }
+        }
+        let high_quality = current.iter().any(|q| {
+            q.ref_allele == stats.ref_allele && q.alt == stats.alt && q.high_quality == Some(true)
+        });
+        high_quality.then_some(Ok(stats))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use hail::contig::GRCh37Contig;
+
+    use crate::SummaryStats;
+
+    use super::{VariantQcMetrics, retain_high_quality};
+
+    #[test]
+    fn parse_and_filter() {
+        let tsv = "chrom\tpos\tref\talt\trsid\tinfo\thigh_quality\textra
+1\t100\tA\tG\trs1\t0.95\ttrue\tx
+1\t100\tA\tT\tNA\t0.2\tfalse\tx
+1\t200\tC\tT\trs2\tNA\ttrue\tx
+";
+        let qc: Vec<VariantQcMetrics> = VariantQcMetrics::from_reader(tsv.as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(qc[0].chrom, GRCh37Contig::CHR1);
+        assert_eq!(qc[0].info.map(|v| *v), Some(0.95));
+        assert_eq!(qc[1].rsid, None);
+        assert_eq!(qc[2].high_quality, Some(true));
+        assert_eq!(qc[0].key().0.at, 99);
+
+        let mut tsv = "chr\tpos\tref\talt\tbeta_meta_hq\tse_meta_hq\tneglog10_pval_meta_hq\t\
+neglog10_pval_heterogeneity_hq\tbeta_meta\tse_meta\tneglog10_pval_meta\tneglog10_pval_heterogeneity\n"
+            .to_owned();
+        for (pos, ref_allele, alt) in [
+            (50, "A", "G"),
+            (100, "A", "G"),
+            (100, "A", "T"),
+            (200, "C", "T"),
+        ] {
+            tsv += &format!("1\t{pos}\t{ref_allele}\t{alt}{}\n", "\tNA".repeat(8));
+        }
+        let stats = csv::ReaderBuilder::new()
+            .delimiter(b'\t')
+            .from_reader(tsv.as_bytes())
+            .into_deserialize::<SummaryStats>();
+        let kept: Vec<_> = retain_high_quality(stats, qc.into_iter().map(Ok))
+            .map(|s| s.unwrap().pos)
+            .collect();
+        assert_eq!(kept, [100, 200]);
+    }
+}
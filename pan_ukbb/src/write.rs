@@ -0,0 +1,150 @@
+//! Writing summary stats back out in the released layout, for external tools (LDSC, PRS-CS, ...).
+
+use std::{collections::BTreeSet, io};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{PhenotypeManifestEntry, Population, SummaryStats, TraitType};
+
+/// The columns of a summary stats file, which depend on the phenotype.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SummaryStatsColumns {
+    /// Binary phenotypes have allele frequencies in cases and controls instead of overall.
+    pub binary: bool,
+    /// The populations the phenotype was GWASed in.
+    pub populations: BTreeSet<Population>,
+}
+
+impl SummaryStatsColumns {
+    pub fn for_phenotype(entry: &PhenotypeManifestEntry) -> Self {
+        Self {
+            binary: entry.trait_type.is_binary(),
+            populations: entry.pops.clone(),
+        }
+    }
+
+    /// In the released order.
+    pub fn names(&self) -> Vec<String> {
+        let af: &[&str] = if self.binary {
+            &["af_cases", "af_controls"]
+        } else {
+            &["af"]
+        };
+
+        let mut names: Vec<String> = ["chr", "pos", "ref", "alt"].map(String::from).into();
+        for (suffix, heterogeneity) in [
+            ("meta_hq", "neglog10_pval_heterogeneity_hq"),
+            ("meta", "neglog10_pval_heterogeneity"),
+        ] {
+            names.extend(af.iter().map(|af| format!("{af}_{suffix}")));
+            names.push(format!("beta_{suffix}"));
+            names.push(format!("se_{suffix}"));
+            names.push(format!("neglog10_pval_{suffix}"));
+            names.push(heterogeneity.to_owned());
+        }
+        for prefix in af
+            .iter()
+            .copied()
+            .chain(["beta", "se", "neglog10_pval", "low_confidence"])
+        {
+            names.extend(self.populations.iter().map(|p| format!("{prefix}_{p}")));
+        }
+        names
+    }
+}
+
+impl TraitType {
+    /// Whether the phenotype is case/control.
+    pub fn is_binary(&self) -> bool {
+        match self {
+            Self::Biomarkers | Self::Continuous => false,
+            Self::Categorical | Self::ICD10 | Self::PheCode | Self::Prescriptions => true,
+        }
+    }
+}
+
+impl<Contig: Serialize> SummaryStats<Contig> {
+    /// Writes a tab-separated file with a header, as released: missing values are `NA`,
+    /// and contigs have no `chr` prefix.
+    pub fn write_tsv<'a>(
+        writer: impl io::Write,
+        columns: &SummaryStatsColumns,
+        stats: impl IntoIterator<Item = &'a Self>,
+    ) -> csv::Result<()>
+    where
+        Self: 'a,
+    {
+        let names = columns.names();
+        let mut writer = csv::WriterBuilder::new()
+            .delimiter(b'\t')
+            .from_writer(writer);
+        writer.write_record(&names)?;
+        for stats in stats {
+            let Value::Object(values) = serde_json::to_value(stats).map_err(io::Error::from)?
+            else {
+                unreachable!()
+            };
+            writer.write_record(names.iter().map(|name| match values.get(name) {
+                None | Some(Value::Null) => "NA".to_owned(),
+                Some(Value::String(s)) if name == "chr" => {
+                    s.strip_prefix("chr").unwrap_or(s).to_owned()
+                }
+                Some(Value::String(s)) => s.clone(),
+                Some(value) => value.to_string(),
+            }))?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crate::{Population, SummaryStats};
+
+    use super::SummaryStatsColumns;
+
+    fn roundtrip(columns: &SummaryStatsColumns, row: &str) {
+        let tsv = format!("{}\n{row}\n", columns.names().join("\t"));
+        let stats: Vec<SummaryStats> = csv::ReaderBuilder::new()
+            .delimiter(b'\t')
+            .from_reader(tsv.as_bytes())
+            .into_deserialize()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        let mut written = vec![];
+        SummaryStats::write_tsv(&mut written, columns, &stats).unwrap();
+        assert_eq!(String::from_utf8(written).unwrap(), tsv);
+    }
+
+    #[test]
+    fn quantitative() {
+        let columns = SummaryStatsColumns {
+            binary: false,
+            populations: BTreeSet::from([Population::Afr, Population::Eur]),
+        };
+        assert_eq!(columns.names().len(), 4 + 5 * 2 + 5 * 2);
+        roundtrip(
+            &columns,
+            "X\t100\tA\tGT\t0.25\t-0.5\t0.1\t12.5\tNA\t0.3\t-0.4\t0.1\t1e-5\t0.5\t\
+NA\t0.3\tNA\t-0.4\tNA\t0.1\tNA\t1.5\tNA\tfalse",
+        );
+    }
+
+    #[test]
+    fn binary() {
+        let columns = SummaryStatsColumns {
+            binary: true,
+            populations: BTreeSet::from([Population::Eur]),
+        };
+        roundtrip(
+            &columns,
+            "1\t100\tA\tG\t0.25\t0.2\t-0.5\t0.1\t12.5\tNA\t0.3\t0.2\t-0.4\t0.1\t1.5\t0.5\t\
+0.3\t0.2\t-0.4\t0.1\t1.5\ttrue",
+        );
+    }
+}
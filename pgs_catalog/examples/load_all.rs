@@ -24,16 +24,19 @@ async fn main() {
         Study::load_associations_default(id)
             .await
             .unwrap()
+            .1
             .for_each(|v| drop(v.unwrap()));
 
         HarmonizedStudy::load_associations_default(id, GenomeBuild::GRCh37)
             .await
             .unwrap()
+            .1
             .for_each(|v| drop(v.unwrap()));
 
         HarmonizedStudy::load_associations_default(id, GenomeBuild::GRCh38)
             .await
             .unwrap()
+            .1
             .for_each(|v| {
                 let v = v.unwrap();
                 effect_alleles.insert(v.effect_allele.clone());
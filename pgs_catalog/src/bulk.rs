@@ -0,0 +1,150 @@
+//! Catalog-wide loading of scores.
+//!
+//! Downloads go through the global file system cache, so an interrupted run can be restarted and
+//! only parses what was already downloaded. To skip scores already processed, filter the IDs
+//! from [all_score_ids] and pass them to [load_scores].
+
+use std::io;
+
+use futures::{Stream, StreamExt};
+use resource::{RawResource, RawResourceExt};
+
+use crate::{GenomeBuild, HarmonizedStudy, PgsCatalogResource, PgsId, metadata::Score};
+
+/// The IDs of all scores in the catalog metadata, in order.
+pub async fn all_score_ids() -> io::Result<Vec<PgsId>> {
+    let mut ids: Vec<PgsId> = Score::load_all().await?.iter().map(|s| s.id).collect();
+    ids.sort();
+    Ok(ids)
+}
+
+/// Loads the harmonized studies of `ids`, at most `concurrency` at a time.
+///
+/// Results are in the order of `ids`, a failure only affects its own score.
+/// Files are parsed on the blocking thread pool, so this needs a Tokio runtime.
+pub fn load_scores(
+    ids: impl IntoIterator<Item = PgsId>,
+    build: GenomeBuild,
+    concurrency: usize,
+) -> impl Stream<Item = (PgsId, io::Result<HarmonizedStudy>)> {
+    load_with(ids, concurrency, move |id| load_score(id, build))
+}
+
+/// [load_scores] for [all_score_ids].
+pub async fn all_scores(
+    build: GenomeBuild,
+    concurrency: usize,
+) -> io::Result<impl Stream<Item = (PgsId, io::Result<HarmonizedStudy>)>> {
+    Ok(load_scores(all_score_ids().await?, build, concurrency))
+}
+
+fn load_with<F>(
+    ids: impl IntoIterator<Item = PgsId>,
+    concurrency: usize,
+    load: impl Fn(PgsId) -> F,
+) -> impl Stream<Item = (PgsId, io::Result<HarmonizedStudy>)>
+where
+    F: Future<Output = io::Result<HarmonizedStudy>>,
+{
+    futures::stream::iter(ids)
+        .map(move |id| {
+            let loading = load(id);
+            async move { (id, loading.await) }
+        })
+        .buffered(concurrency.max(1))
+}
+
+async fn load_score(id: PgsId, build: GenomeBuild) -> io::Result<HarmonizedStudy> {
+    let resource = PgsCatalogResource::HarmonizedStudy { id, build }
+        .log_progress()
+        .with_global_fs_cache()
+        .ensure_cached_async()
+        .await?
+        .decompressed()
+        .buffered();
+    parse_blocking(resource).await
+}
+
+/// Parsing a large score takes long enough to stall the other downloads if done on the runtime.
+async fn parse_blocking<R>(resource: R) -> io::Result<HarmonizedStudy>
+where
+    R: RawResource + Send + 'static,
+    <R as RawResource>::Reader: io::BufRead,
+{
+    tokio::task::spawn_blocking(move || HarmonizedStudy::load(resource))
+        .await
+        .map_err(io::Error::other)?
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+    use resource::{RawResourceExt, fs::FsCache};
+
+    use crate::{HarmonizedStudy, PgsId};
+
+    use super::{load_with, parse_blocking};
+
+    fn study_file() -> String {
+        let header: String = crate::docs::EXAMPLE_HEADER
+            .lines()
+            .filter(|line| line.starts_with('#'))
+            .map(|line| format!("{line}\n"))
+            .collect();
+        format!(
+            "{header}\
+##HARMONIZATION DETAILS
+#HmPOS_build=GRCh38
+#HmPOS_date=2022-07-29
+rsID\teffect_allele\tother_allele\teffect_weight\thm_source\thm_rsID\thm_chr\thm_pos\thm_inferOtherAllele
+rs1\tA\tG\t0.1\tENSEMBL\trs1\t1\t100\t
+rs2\tA\tG\t0.2\tENSEMBL\trs2\tX\t200\t
+"
+        )
+    }
+
+    #[tokio::test]
+    async fn parse() {
+        let (cache, _dir) = FsCache::new_temp();
+        let entry = cache.entry("PGS000079_hmPOS_GRCh38.txt");
+        entry.write_file(study_file().as_bytes()).unwrap();
+
+        let study = parse_blocking(entry.clone().buffered()).await.unwrap();
+        assert_eq!(study.pgs_info().id().to_string(), "PGS000079");
+        assert_eq!(study.harmonization_info().genome_build(), "GRCh38");
+        assert_eq!(study.associations().len(), 2);
+
+        entry.write_file(&b"#format_version=2.0\n"[..]).unwrap();
+        assert!(parse_blocking(entry.buffered()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn order_and_failures() {
+        let (cache, _dir) = FsCache::new_temp();
+        let entry = cache.entry("PGS000079_hmPOS_GRCh38.txt");
+        entry.write_file(study_file().as_bytes()).unwrap();
+
+        let ids: Vec<PgsId> = ["PGS000002", "PGS000001", "PGS000003"]
+            .iter()
+            .map(|id| id.parse().unwrap())
+            .collect();
+        let failing = ids[1];
+        let results: Vec<(PgsId, std::io::Result<HarmonizedStudy>)> =
+            load_with(ids.clone(), 2, |id| {
+                let resource = entry.clone().buffered();
+                async move {
+                    if id == failing {
+                        return Err(std::io::Error::other("Down."));
+                    }
+                    parse_blocking(resource).await
+                }
+            })
+            .collect()
+            .await;
+
+        assert_eq!(results.iter().map(|(id, _)| *id).collect::<Vec<_>>(), ids);
+        assert!(results[0].1.is_ok());
+        assert!(results[1].1.is_err());
+        assert!(results[2].1.is_ok());
+    }
+}
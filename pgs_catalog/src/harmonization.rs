@@ -0,0 +1,168 @@
+//! QC of the harmonization of a scoring file, to reject poorly harmonized scores before scoring.
+
+use std::collections::BTreeMap;
+
+use crate::{
+    HarmonizationInfo, HarmonizedSource, HarmonizedStudy, HarmonizedStudyAssociation, MatchCounts,
+};
+
+/// See [HarmonizedStudy::harmonization_report].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HarmonizationReport {
+    pub associations: usize,
+    /// Associations without a harmonized position.
+    pub unmapped: usize,
+    pub sources: BTreeMap<HarmonizedSource, usize>,
+    /// Counted from [HarmonizedStudyAssociation::match_chr], rows without the flag are left out.
+    pub match_chr: MatchCounts,
+    /// Counted from [HarmonizedStudyAssociation::match_pos], rows without the flag are left out.
+    pub match_pos: MatchCounts,
+    /// As reported in the header, see [HarmonizationInfo::match_chr].
+    pub header_match_chr: Option<MatchCounts>,
+    /// As reported in the header, see [HarmonizationInfo::match_pos].
+    pub header_match_pos: Option<MatchCounts>,
+}
+
+impl MatchCounts {
+    pub fn total(&self) -> u64 {
+        self.matching + self.not_matching
+    }
+    /// `None` if there are no counts.
+    pub fn fraction_matching(&self) -> Option<f64> {
+        match self.total() {
+            0 => None,
+            total => Some(self.matching as f64 / total as f64),
+        }
+    }
+}
+
+impl HarmonizationReport {
+    pub fn new(info: &HarmonizationInfo, associations: &[HarmonizedStudyAssociation]) -> Self {
+        let mut report = Self {
+            associations: associations.len(),
+            unmapped: 0,
+            sources: BTreeMap::new(),
+            match_chr: MatchCounts::default(),
+            match_pos: MatchCounts::default(),
+            header_match_chr: info.match_chr(),
+            header_match_pos: info.match_pos(),
+        };
+        let count = |counts: &mut MatchCounts, flag: Option<bool>| match flag {
+            Some(true) => counts.matching += 1,
+            Some(false) => counts.not_matching += 1,
+            None => {}
+        };
+        for association in associations {
+            if association.chr.is_empty() || association.pos.is_none() {
+                report.unmapped += 1;
+            }
+            *report
+                .sources
+                .entry(association.source.clone())
+                .or_default() += 1;
+            count(&mut report.match_chr, association.match_chr);
+            count(&mut report.match_pos, association.match_pos);
+        }
+        report
+    }
+
+    /// Share of the associations with a harmonized position, `None` if there are none.
+    pub fn fraction_mapped(&self) -> Option<f64> {
+        match self.associations {
+            0 => None,
+            n => Some((n - self.unmapped) as f64 / n as f64),
+        }
+    }
+
+    /// Whether the header counts, when given, agree with the per-row flags.
+    pub fn is_consistent(&self) -> bool {
+        self.header_match_chr.is_none_or(|c| c == self.match_chr)
+            && self.header_match_pos.is_none_or(|c| c == self.match_pos)
+    }
+
+    /// Whether at least `min_mapped` of the associations have a position, and at least
+    /// `min_matching` of the flagged positions match the author-reported ones.
+    ///
+    /// Files harmonized from another build have no flags, and pass the second check.
+    pub fn passes(&self, min_mapped: f64, min_matching: f64) -> bool {
+        self.fraction_mapped().unwrap_or(0.) >= min_mapped
+            && self
+                .match_pos
+                .fraction_matching()
+                .is_none_or(|f| f >= min_matching)
+    }
+}
+
+impl HarmonizedStudy {
+    pub fn harmonization_report(&self) -> HarmonizationReport {
+        HarmonizationReport::new(self.harmonization_info(), self.associations())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::{HarmonizedSource, HarmonizedStudyAssociation, MatchCounts, comments, read_file};
+
+    use super::HarmonizationReport;
+
+    fn header() -> String {
+        format!(
+            "{}{}",
+            crate::docs::EXAMPLE_HEADER,
+            "#HmPOS_build=GRCh38\n#HmPOS_date=2022-07-29\n\
+#HmPOS_match_chr={\"True\": 3, \"False\": 0}\n#HmPOS_match_pos={\"True\": 2, \"False\": 1}\n"
+        )
+    }
+
+    fn associations() -> Vec<HarmonizedStudyAssociation> {
+        let file = "\
+effect_allele\thm_source\thm_chr\thm_pos\thm_match_chr\thm_match_pos
+A\tENSEMBL\t1\t100\tTrue\tTrue
+A\tENSEMBL\t1\t200\tTrue\tFalse
+A\tAuthor-reported\t2\t300\tTrue\tTrue
+A\tENSEMBL\t\t\t\t
+";
+        read_file(file.as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn report() {
+        let header = comments::parse_header(&header()).unwrap();
+        let info = header.harmonization_info().unwrap();
+        let mut report = HarmonizationReport::new(info, &associations());
+
+        assert_eq!(report.associations, 4);
+        assert_eq!(report.unmapped, 1);
+        assert_eq!(
+            report.sources,
+            BTreeMap::from([
+                (HarmonizedSource::AuthorReported, 1),
+                (HarmonizedSource::Ensembl, 3)
+            ])
+        );
+        let counts = |matching, not_matching| MatchCounts {
+            matching,
+            not_matching,
+        };
+        assert_eq!(report.match_chr, counts(3, 0));
+        assert_eq!(report.match_pos, counts(2, 1));
+        assert!(report.is_consistent());
+
+        assert_eq!(report.fraction_mapped(), Some(0.75));
+        assert!(report.passes(0.75, 0.6));
+        assert!(!report.passes(0.8, 0.6));
+        assert!(!report.passes(0.75, 0.7));
+
+        report.header_match_pos = Some(counts(3, 0));
+        assert!(!report.is_consistent());
+
+        let empty = HarmonizationReport::new(info, &[]);
+        assert_eq!(empty.fraction_mapped(), None);
+        assert_eq!(empty.match_pos.fraction_matching(), None);
+        assert!(!empty.passes(0., 0.));
+    }
+}
@@ -1,8 +1,26 @@
+//! PGS Catalog scoring files and metadata.
+//!
+//! # Stability
+//!
+//! The loaders, the metadata accessors and the association rows are the stable API.
+//! Metadata ([Study], [HarmonizedStudy], [ScoringFileHeader], [PgsInfo], [SourceInfo] and
+//! [HarmonizationInfo]) is read through accessors, and the metadata types, association rows
+//! and enums mirroring catalog values are `#[non_exhaustive]`, so new catalog fields and values
+//! can be added in minor releases: matches need a wildcard arm, and rows can't be built
+//! outside this crate.
+
 #![feature(iterator_try_collect)]
 
 pub mod metadata;
+pub mod named_allele;
+pub mod score;
 pub mod simplified;
 
+mod bulk;
+mod harmonization;
+mod traits;
+mod variant;
+
 use std::io::{self, Read};
 
 use ordered_float::NotNan;
@@ -10,11 +28,21 @@ use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use simplified::SimplificationError;
 use url::Url;
 
-use biocore::dna::DnaSequence;
+use biocore::{
+    dna::DnaSequence,
+    mutation::{allele_match::Alleles, structural::SymbolicAllele},
+};
 use resource::{RawResource, RawResourceExt, UrlResource};
 
 pub use ids::{pgs::PgsId, rs::RsId};
 
+pub use self::{
+    bulk::{all_score_ids, all_scores, load_scores},
+    harmonization::HarmonizationReport,
+    traits::{TraitScore, find_scores_for_trait},
+    variant::{AssociationVariant, VariantError},
+};
+
 use self::simplified::SimplifiedHarmonizedStudyAssociation;
 
 const URL_BASE: &str = "https://ftp.ebi.ac.uk/pub/databases/spot/pgs";
@@ -82,6 +110,7 @@ impl RawResource for PgsCatalogResource {
 /// See [docs::HEADER_DOCS] and [docs::EXAMPLE_HEADER] and [docs::HARMONIZATION_EXTENSION].
 /// Harmonization happens to a [GenomeBuild].
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub struct HarmonizedStudy {
     /// Version of the scoring file format, e.g. '2.0'.
     format_version: String,
@@ -91,12 +120,14 @@ pub struct HarmonizedStudy {
     associations: Vec<HarmonizedStudyAssociation>,
 }
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum GenomeBuild {
     GRCh37,
     GRCh38,
 }
 /// See [docs::HEADER_DOCS] and [docs::EXAMPLE_HEADER].
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub struct Study {
     /// Version of the scoring file format, e.g. '2.0'.
     format_version: String,
@@ -104,7 +135,19 @@ pub struct Study {
     source_info: SourceInfo,
     associations: Vec<StudyAssociation>,
 }
+/// The `#` header of a scoring file, see [docs::HEADER_DOCS].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ScoringFileHeader {
+    /// Version of the scoring file format, e.g. '2.0'.
+    format_version: String,
+    pgs_info: PgsInfo,
+    source_info: SourceInfo,
+    /// Only in harmonized files, see [docs::HARMONIZATION_EXTENSION].
+    harmonization_info: Option<HarmonizationInfo>,
+}
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub struct PgsInfo {
     /// PGS identifier, e.g. 'PGS000001'
     id: PgsId,
@@ -124,6 +167,7 @@ pub struct PgsInfo {
     weight_type: WeightType,
 }
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub struct SourceInfo {
     /// PGS publication identifier, e.g. 'PGP000001'
     pgp_id: String,
@@ -131,9 +175,10 @@ pub struct SourceInfo {
     citation: String,
     /// License and terms of PGS use/distribution - refers to the EMBL-EBI Terms
     /// of Use by default
-    license: String,
+    license: Option<String>,
 }
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct HarmonizationInfo {
     /// Genome build of the harmonized file, e.g. 'GRCh38'>
     #[serde(rename = "HmPOS_build")]
@@ -141,18 +186,35 @@ pub struct HarmonizationInfo {
     /// Date of the harmonized file creation, e.g. '2022-05-26'>
     #[serde(rename = "HmPOS_date")]
     file_creation: String,
-    // /// Number of entries matching and not matching the given chromosome,
-    // /// e.g. {"True": 5210, "False": 8}>
-    // #[serde(rename = "HmPOS_match_chr")]
-    // HmPOS_match_chr: String,
-    // /// Number of entries matching and not matching the given position,
-    // /// e.g. {"True": 5210, "False": 8}>
-    // #[serde(rename = "HmPOS_match_pos")]
-    // HmPOS_match_pos: String,
+    /// Number of entries matching and not matching the given chromosome,
+    /// e.g. {"True": 5210, "False": 8}>
+    #[serde(rename = "HmPOS_match_chr")]
+    match_chr: Option<MatchCounts>,
+    /// Number of entries matching and not matching the given position,
+    /// e.g. {"True": 5210, "False": 8}>
+    #[serde(rename = "HmPOS_match_pos")]
+    match_pos: Option<MatchCounts>,
+}
+/// See [HarmonizationInfo::match_chr] and [HarmonizationInfo::match_pos].
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    Default,
+    Serialize,
+    Deserialize
+)]
+pub struct MatchCounts {
+    pub matching: u64,
+    pub not_matching: u64,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
+#[non_exhaustive]
 pub struct StudyAssociation {
     /// The SNP’s rs ID.
     /// This column also contains HLA alleles in the standard notation (e.g.
@@ -272,6 +334,7 @@ pub struct StudyAssociation {
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[derive(Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
+#[non_exhaustive]
 pub struct HarmonizedStudyAssociation {
     /// The SNP’s rs ID.
     /// This column also contains HLA alleles in the standard notation (e.g.
@@ -449,9 +512,19 @@ pub enum Allele {
     /// See [docs::DOCUMENTED_EXCEPTIONS] for exceptions.
     Other(String),
 }
+impl Allele {
+    /// Parses symbolic alleles like `<CN0>` or `<INS:ME:ALU>` out of [Allele::Other].
+    pub fn structural(&self) -> Option<SymbolicAllele> {
+        match self {
+            Allele::Other(other) => other.parse().ok(),
+            Allele::Sequence(_) | Allele::Insertion => None,
+        }
+    }
+}
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[derive(Serialize, Deserialize)]
+#[non_exhaustive]
 pub enum ImputationMethod {
     #[serde(rename = "HLA*IMP:02")]
     HLAIMP02,
@@ -463,6 +536,7 @@ pub enum ImputationMethod {
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[derive(Serialize, Deserialize)]
+#[non_exhaustive]
 pub enum HarmonizedSource {
     #[serde(rename = "Author-reported")]
     AuthorReported,
@@ -483,6 +557,7 @@ pub enum HarmonizedSource {
 /// NotReported: 1386, LogOrEafNormalized: 3
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[derive(Serialize, Deserialize)]
+#[non_exhaustive]
 pub enum WeightType {
     // Named weights
     #[serde(rename = "weights.HC132")]
@@ -562,21 +637,65 @@ pub enum WeightType {
 }
 
 impl Study {
+    /// Version of the scoring file format, e.g. '2.0'.
+    pub fn format_version(&self) -> &str {
+        &self.format_version
+    }
+    /// The score described by the file.
+    pub fn pgs_info(&self) -> &PgsInfo {
+        &self.pgs_info
+    }
+    /// The publication the score comes from.
+    pub fn source_info(&self) -> &SourceInfo {
+        &self.source_info
+    }
+    /// The variants and weights of the score, in file order.
+    pub fn associations(&self) -> &[StudyAssociation] {
+        &self.associations
+    }
+
+    pub fn load<R>(resource: R) -> io::Result<Self>
+    where
+        R: RawResource,
+        <R as RawResource>::Reader: io::BufRead,
+    {
+        let (header, associations) = Self::load_associations(resource)?;
+        Ok(Self::new(header, associations.try_collect()?))
+    }
+    pub async fn load_default(id: PgsId) -> io::Result<Self> {
+        let (header, associations) = Self::load_associations_default(id).await?;
+        Ok(Self::new(header, associations.try_collect()?))
+    }
+    fn new(header: ScoringFileHeader, associations: Vec<StudyAssociation>) -> Self {
+        Self {
+            format_version: header.format_version,
+            pgs_info: header.pgs_info,
+            source_info: header.source_info,
+            associations,
+        }
+    }
+
     pub fn load_associations<R>(
         resource: R,
-    ) -> io::Result<impl Iterator<Item = csv::Result<StudyAssociation>>>
+    ) -> io::Result<(
+        ScoringFileHeader,
+        impl Iterator<Item = csv::Result<StudyAssociation>>,
+    )>
     where
         R: RawResource,
         <R as RawResource>::Reader: io::BufRead,
     {
         let mut file = resource.read()?;
-        let _header = comments::read(&mut file)?;
-        Ok(read_file(file))
+        let header = comments::parse_header(&comments::read(&mut file)?)?;
+        Ok((header, read_file(file)))
     }
 
     pub async fn load_associations_default(
         id: PgsId,
-    ) -> io::Result<impl Iterator<Item = csv::Result<StudyAssociation>>> {
+    ) -> io::Result<(
+        ScoringFileHeader,
+        impl Iterator<Item = csv::Result<StudyAssociation>>,
+    )> {
         let resource = PgsCatalogResource::Study { id }
             .log_progress()
             .with_global_fs_cache()
@@ -590,21 +709,76 @@ impl Study {
 }
 
 impl HarmonizedStudy {
+    /// Version of the scoring file format, e.g. '2.0'.
+    pub fn format_version(&self) -> &str {
+        &self.format_version
+    }
+    /// The score described by the file.
+    pub fn pgs_info(&self) -> &PgsInfo {
+        &self.pgs_info
+    }
+    /// The publication the score comes from.
+    pub fn source_info(&self) -> &SourceInfo {
+        &self.source_info
+    }
+    /// The build and date of the harmonization, with its match counts.
+    pub fn harmonization_info(&self) -> &HarmonizationInfo {
+        &self.harmonization_info
+    }
+    /// The variants and weights of the score, in file order.
+    pub fn associations(&self) -> &[HarmonizedStudyAssociation] {
+        &self.associations
+    }
+
+    pub fn load<R>(resource: R) -> io::Result<Self>
+    where
+        R: RawResource,
+        <R as RawResource>::Reader: io::BufRead,
+    {
+        let (header, associations) = Self::load_associations(resource)?;
+        Self::new(header, associations.try_collect()?)
+    }
+    pub async fn load_default(id: PgsId, build: GenomeBuild) -> io::Result<Self> {
+        let (header, associations) = Self::load_associations_default(id, build).await?;
+        Self::new(header, associations.try_collect()?)
+    }
+    fn new(
+        header: ScoringFileHeader,
+        associations: Vec<HarmonizedStudyAssociation>,
+    ) -> io::Result<Self> {
+        Ok(Self {
+            format_version: header.format_version,
+            pgs_info: header.pgs_info,
+            source_info: header.source_info,
+            harmonization_info: header
+                .harmonization_info
+                .ok_or_else(|| utile::io::invalid_data("Missing harmonization details."))?,
+            associations,
+        })
+    }
+
+    /// [ScoringFileHeader::harmonization_info] is always present for harmonized files.
     pub fn load_associations<R>(
         resource: R,
-    ) -> io::Result<impl Iterator<Item = csv::Result<HarmonizedStudyAssociation>>>
+    ) -> io::Result<(
+        ScoringFileHeader,
+        impl Iterator<Item = csv::Result<HarmonizedStudyAssociation>>,
+    )>
     where
         R: RawResource,
         <R as RawResource>::Reader: io::BufRead,
     {
         let mut file = resource.read()?;
-        let _header = comments::read(&mut file)?;
-        Ok(read_file(file))
+        let header = comments::parse_header(&comments::read(&mut file)?)?;
+        Ok((header, read_file(file)))
     }
     pub async fn load_associations_default(
         id: PgsId,
         build: GenomeBuild,
-    ) -> io::Result<impl Iterator<Item = csv::Result<HarmonizedStudyAssociation>>> {
+    ) -> io::Result<(
+        ScoringFileHeader,
+        impl Iterator<Item = csv::Result<HarmonizedStudyAssociation>>,
+    )> {
         let resource = PgsCatalogResource::HarmonizedStudy { id, build }
             .log_progress()
             .with_global_fs_cache()
@@ -617,6 +791,94 @@ impl HarmonizedStudy {
     }
 }
 
+impl ScoringFileHeader {
+    /// Version of the scoring file format, e.g. '2.0'.
+    pub fn format_version(&self) -> &str {
+        &self.format_version
+    }
+    /// The score described by the file.
+    pub fn pgs_info(&self) -> &PgsInfo {
+        &self.pgs_info
+    }
+    /// The publication the score comes from.
+    pub fn source_info(&self) -> &SourceInfo {
+        &self.source_info
+    }
+    /// Only present in harmonized files.
+    pub fn harmonization_info(&self) -> Option<&HarmonizationInfo> {
+        self.harmonization_info.as_ref()
+    }
+}
+
+impl PgsInfo {
+    /// PGS identifier, e.g. 'PGS000001'.
+    pub fn id(&self) -> PgsId {
+        self.id
+    }
+    /// PGS name, e.g. 'PRS77_BC'.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+    /// The trait as reported by the authors, e.g. 'Breast Cancer'.
+    pub fn trait_reported(&self) -> &str {
+        &self.trait_reported
+    }
+    /// Ontology trait name, e.g. 'breast carcinoma'.
+    pub fn trait_mapped(&self) -> &str {
+        &self.trait_mapped
+    }
+    /// Ontology trait ID (EFO), e.g. 'EFO_0000305'.
+    pub fn trait_efo(&self) -> &str {
+        &self.trait_efo
+    }
+    /// Genome build of the original positions, e.g. 'GRCh38'.
+    pub fn genome_build(&self) -> &str {
+        &self.genome_build
+    }
+    /// Kept as reported, the catalog does not guarantee this is a plain integer.
+    pub fn variant_number(&self) -> &str {
+        &self.variant_number
+    }
+    /// Variant weight type, e.g. 'beta', 'OR/HR' (default 'NR').
+    pub fn weight_type(&self) -> WeightType {
+        self.weight_type
+    }
+}
+
+impl SourceInfo {
+    /// PGS publication identifier, e.g. 'PGP000001'.
+    pub fn pgp_id(&self) -> &str {
+        &self.pgp_id
+    }
+    /// Information about the publication.
+    pub fn citation(&self) -> &str {
+        &self.citation
+    }
+    /// License and terms of use, the EMBL-EBI Terms of Use if missing.
+    pub fn license(&self) -> Option<&str> {
+        self.license.as_deref()
+    }
+}
+
+impl HarmonizationInfo {
+    /// Genome build of the harmonized file, e.g. 'GRCh38'.
+    pub fn genome_build(&self) -> &str {
+        &self.genome_build
+    }
+    /// Date of the harmonized file creation, e.g. '2022-05-26'.
+    pub fn file_creation(&self) -> &str {
+        &self.file_creation
+    }
+    /// Number of entries matching and not matching the given chromosome.
+    pub fn match_chr(&self) -> Option<MatchCounts> {
+        self.match_chr
+    }
+    /// Number of entries matching and not matching the given position.
+    pub fn match_pos(&self) -> Option<MatchCounts> {
+        self.match_pos
+    }
+}
+
 impl HarmonizedStudyAssociation {
     pub fn simplified<Contig>(
         self,
@@ -624,6 +886,26 @@ impl HarmonizedStudyAssociation {
     ) -> Result<SimplifiedHarmonizedStudyAssociation<Contig>, SimplificationError> {
         SimplifiedHarmonizedStudyAssociation::new(self, contig)
     }
+    /// The effect and other alleles, for [AlleleMatcher](biocore::mutation::allele_match::AlleleMatcher).
+    ///
+    /// Falls back to the inferred other allele, `None` unless both are plain sequences.
+    pub fn alleles(&self) -> Option<Alleles<'_>> {
+        let Allele::Sequence(counted) = &self.effect_allele else {
+            return None;
+        };
+        let Some(Allele::Sequence(other)) = self
+            .other_allele
+            .as_ref()
+            .or(self.infer_other_allele.as_ref())
+        else {
+            return None;
+        };
+        Some(Alleles {
+            counted,
+            other,
+            af: self.allelefrequency_effect.map(|af| *af),
+        })
+    }
     pub fn normalised(self, std_dev: NotNan<f64>) -> Self {
         fn normalise(x: Option<NotNan<f64>>, std_dev: NotNan<f64>) -> Option<NotNan<f64>> {
             Some(x? / std_dev)
@@ -676,7 +958,19 @@ fn read_file<T: DeserializeOwned>(file: impl Read) -> impl Iterator<Item = Resul
 }
 
 pub mod comments {
-    use std::io;
+    use std::{collections::BTreeMap, io};
+
+    use serde::{
+        Deserialize,
+        de::{
+            IntoDeserializer,
+            value::{self, StrDeserializer},
+        },
+    };
+
+    use crate::{
+        HarmonizationInfo, MatchCounts, PgsInfo, ScoringFileHeader, SourceInfo, WeightType,
+    };
 
     pub fn read(reader: &mut impl io::BufRead) -> Result<String, std::io::Error> {
         let mut vec = vec![];
@@ -687,6 +981,91 @@ pub mod comments {
 
         String::from_utf8(vec).map_err(utile::io::invalid_data)
     }
+    /// Parses the header returned by [read], see [crate::docs::HEADER_DOCS].
+    pub fn parse_header(header: &str) -> io::Result<ScoringFileHeader> {
+        let fields: BTreeMap<&str, &str> = header
+            .lines()
+            .filter(|line| !line.starts_with("##"))
+            .filter_map(|line| line.strip_prefix('#')?.split_once('='))
+            .map(|(key, value)| (key.trim(), value.trim()))
+            .collect();
+        let optional = |key: &str| fields.get(key).map(|v| v.to_string());
+        let required = |key: &str| {
+            optional(key).ok_or_else(|| {
+                utile::io::invalid_data(format!("Missing `{key}` in scoring file header."))
+            })
+        };
+
+        let weight_type = match fields.get("weight_type") {
+            Some(weight_type) => {
+                let weight_type: StrDeserializer<'_, value::Error> =
+                    weight_type.into_deserializer();
+                WeightType::deserialize(weight_type).map_err(utile::io::invalid_data)?
+            }
+            None => WeightType::NotReported,
+        };
+        let harmonization_info = match fields.contains_key("HmPOS_build") {
+            true => Some(HarmonizationInfo {
+                genome_build: required("HmPOS_build")?,
+                file_creation: required("HmPOS_date")?,
+                match_chr: fields
+                    .get("HmPOS_match_chr")
+                    .map(|v| match_counts(v))
+                    .transpose()?,
+                match_pos: fields
+                    .get("HmPOS_match_pos")
+                    .map(|v| match_counts(v))
+                    .transpose()?,
+            }),
+            false => None,
+        };
+
+        Ok(ScoringFileHeader {
+            format_version: required("format_version")?,
+            pgs_info: PgsInfo {
+                id: required("pgs_id")?
+                    .parse()
+                    .map_err(utile::io::invalid_data)?,
+                name: optional("pgs_name"),
+                trait_reported: required("trait_reported")?,
+                trait_mapped: required("trait_mapped")?,
+                trait_efo: required("trait_efo")?,
+                genome_build: required("genome_build")?,
+                variant_number: required("variants_number")?,
+                weight_type,
+            },
+            source_info: SourceInfo {
+                pgp_id: required("pgp_id")?,
+                citation: required("citation")?,
+                license: optional("license"),
+            },
+            harmonization_info,
+        })
+    }
+    /// Parses e.g. `{"True": 5210, "False": 8}`, missing or null counts are 0.
+    fn match_counts(value: &str) -> io::Result<MatchCounts> {
+        let invalid = || utile::io::invalid_data(format!("Invalid match counts: {value}"));
+        let inner = value
+            .strip_prefix('{')
+            .and_then(|v| v.strip_suffix('}'))
+            .ok_or_else(invalid)?;
+
+        let mut counts = MatchCounts::default();
+        for entry in inner.split(',').filter(|e| !e.trim().is_empty()) {
+            let (key, count) = entry.split_once(':').ok_or_else(invalid)?;
+            let count = match count.trim() {
+                "null" | "None" => 0,
+                count => count.parse().map_err(|_| invalid())?,
+            };
+            match key.trim().trim_matches(['"', '\'']) {
+                "True" => counts.matching = count,
+                "False" => counts.not_matching = count,
+                _ => {}
+            }
+        }
+        Ok(counts)
+    }
+
     #[allow(dead_code)]
     pub fn skip(reader: &mut impl io::BufRead) -> Result<(), std::io::Error> {
         while let [b'#', ..] = reader.fill_buf()? {
@@ -710,6 +1089,38 @@ pub mod comments {
 
         Ok(())
     }
+
+    #[test]
+    fn test_parse_header() -> io::Result<()> {
+        let header = parse_header(crate::docs::EXAMPLE_HEADER)?;
+        assert_eq!(header.format_version(), "2.0");
+        assert_eq!(header.pgs_info().id().to_string(), "PGS000079");
+        assert_eq!(header.pgs_info().name(), Some("CC_Melanoma"));
+        assert_eq!(header.pgs_info().weight_type(), WeightType::LogOr);
+        assert_eq!(header.pgs_info().variant_number(), "24");
+        assert_eq!(header.source_info().pgp_id(), "PGP000050");
+        assert_eq!(header.source_info().license(), None);
+        assert_eq!(header.harmonization_info(), None);
+
+        let harmonized = format!(
+            "{}{}",
+            crate::docs::EXAMPLE_HEADER,
+            "##HARMONIZATION DETAILS\n#HmPOS_build=GRCh38\n#HmPOS_date=2022-07-29\n\
+#HmPOS_match_chr={\"True\": 24, \"False\": 0}\n#HmPOS_match_pos={\"True\": 23, \"False\": 1}\n"
+        );
+        let header = parse_header(&harmonized)?;
+        let info = header.harmonization_info().unwrap();
+        assert_eq!(info.genome_build(), "GRCh38");
+        assert_eq!(
+            info.match_pos(),
+            Some(MatchCounts {
+                matching: 23,
+                not_matching: 1
+            })
+        );
+
+        Ok(())
+    }
 }
 
 #[allow(dead_code)]
@@ -1078,8 +1489,10 @@ mod boilerplate {
         match s.parse() {
             Ok(rsid) => Ok(Some(rsid)),
             Err(_) if s.is_empty() => Ok(None),
-            Err(_) if OTHER_VALUES.contains(&&*s) => todo!(),
-            Err(_) => todo!(),
+            Err(_) if OTHER_VALUES.contains(&&*s) => Ok(None),
+            // HLA and other named alleles, see [crate::named_allele].
+            Err(_) if crate::named_allele::NamedAllele::parse(&s, None).is_some() => Ok(None),
+            Err(e) => Err(serde::de::Error::custom(e)),
         }
     }
 
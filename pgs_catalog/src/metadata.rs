@@ -1,4 +1,5 @@
 use std::{
+    collections::{BTreeMap, BTreeSet},
     io::Read,
     path::{Path, PathBuf},
 };
@@ -35,6 +36,162 @@ impl Metadata {
     }
 }
 
+/// [Metadata] with the cross-references between files resolved by ID, see [Metadata::index].
+#[derive(Debug, Clone)]
+pub struct MetadataIndex<'a> {
+    pub metadata: &'a Metadata,
+    scores: BTreeMap<PgsId, &'a Score>,
+    publications: BTreeMap<PgpId, &'a Publication>,
+    performance_metrics: BTreeMap<PpmId, &'a PerformanceMetric>,
+    /// One row per ancestry group.
+    sample_sets: BTreeMap<PssId, Vec<&'a EvaluationSampleSet>>,
+    efo_traits: BTreeMap<&'a str, &'a EfoTrait>,
+    cohorts: BTreeMap<&'a str, &'a Cohort>,
+    score_performance: BTreeMap<PgsId, Vec<&'a PerformanceMetric>>,
+    score_development: BTreeMap<PgsId, Vec<&'a ScoreDevelopmentSample>>,
+}
+
+/// A score with everything referencing it, see [MetadataIndex::score_record].
+#[derive(Debug, Clone)]
+pub struct ScoreRecord<'a> {
+    pub score: &'a Score,
+    pub publication: Option<&'a Publication>,
+    pub traits: Vec<&'a EfoTrait>,
+    pub development_samples: Vec<&'a ScoreDevelopmentSample>,
+    pub performance_metrics: Vec<&'a PerformanceMetric>,
+}
+
+impl Metadata {
+    pub fn index(&self) -> MetadataIndex<'_> {
+        let mut index = MetadataIndex {
+            metadata: self,
+            scores: self.scores.iter().map(|s| (s.id, s)).collect(),
+            publications: self.publications.iter().map(|p| (p.id, p)).collect(),
+            performance_metrics: self.performance_metrics.iter().map(|m| (m.id, m)).collect(),
+            sample_sets: BTreeMap::new(),
+            efo_traits: self.efo_traits.iter().map(|t| (&*t.id, t)).collect(),
+            cohorts: self.cohorts.iter().map(|c| (&*c.id, c)).collect(),
+            score_performance: BTreeMap::new(),
+            score_development: BTreeMap::new(),
+        };
+        for set in &self.evaluation_sample_sets {
+            index
+                .sample_sets
+                .entry(set.pgs_sample_set)
+                .or_default()
+                .push(set);
+        }
+        for metric in &self.performance_metrics {
+            index
+                .score_performance
+                .entry(metric.evaluated_score)
+                .or_default()
+                .push(metric);
+        }
+        for sample in &self.score_development_samples {
+            index
+                .score_development
+                .entry(sample.score_id)
+                .or_default()
+                .push(sample);
+        }
+        index
+    }
+}
+
+impl<'a> MetadataIndex<'a> {
+    pub fn score(&self, id: PgsId) -> Option<&'a Score> {
+        self.scores.get(&id).copied()
+    }
+    pub fn publication(&self, id: PgpId) -> Option<&'a Publication> {
+        self.publications.get(&id).copied()
+    }
+    pub fn performance_metric(&self, id: PpmId) -> Option<&'a PerformanceMetric> {
+        self.performance_metrics.get(&id).copied()
+    }
+    /// The rows of the sample set, one per ancestry group.
+    pub fn sample_set(&self, id: PssId) -> &[&'a EvaluationSampleSet] {
+        self.sample_sets.get(&id).map_or(&[], |s| &s[..])
+    }
+    pub fn efo_trait(&self, id: &str) -> Option<&'a EfoTrait> {
+        self.efo_traits.get(id).copied()
+    }
+    pub fn cohort(&self, id: &str) -> Option<&'a Cohort> {
+        self.cohorts.get(id).copied()
+    }
+
+    /// The metrics evaluating the score, across all publications.
+    pub fn score_performance_metrics(&self, id: PgsId) -> &[&'a PerformanceMetric] {
+        self.score_performance.get(&id).map_or(&[], |m| &m[..])
+    }
+    pub fn score_development_samples(&self, id: PgsId) -> &[&'a ScoreDevelopmentSample] {
+        self.score_development.get(&id).map_or(&[], |s| &s[..])
+    }
+    /// The mapped traits found in the bundle, in the order of [Score::mapped_traits_efo_id].
+    pub fn score_traits(&self, score: &Score) -> Vec<&'a EfoTrait> {
+        score
+            .mapped_traits_efo_id
+            .iter()
+            .filter_map(|id| self.efo_trait(id))
+            .collect()
+    }
+    /// The scores evaluated on the sample set.
+    pub fn sample_set_scores(&self, id: PssId) -> Vec<&'a Score> {
+        let ids: BTreeSet<PgsId> = self
+            .sample_set(id)
+            .iter()
+            .flat_map(|s| &s.score_ids)
+            .copied()
+            .collect();
+        ids.into_iter().filter_map(|id| self.score(id)).collect()
+    }
+    /// Cohorts not listed in the bundle are skipped.
+    pub fn cohorts(&self, ids: &[String]) -> Vec<&'a Cohort> {
+        ids.iter().filter_map(|id| self.cohort(id)).collect()
+    }
+
+    /// `None` if the score isn't in the bundle.
+    pub fn score_record(&self, id: PgsId) -> Option<ScoreRecord<'a>> {
+        let score = self.score(id)?;
+        Some(ScoreRecord {
+            score,
+            publication: self.publication(score.pgs_publication_id),
+            traits: self.score_traits(score),
+            development_samples: self.score_development_samples(id).to_vec(),
+            performance_metrics: self.score_performance_metrics(id).to_vec(),
+        })
+    }
+
+    /// References to IDs missing from the bundle, as `(referencing ID, missing ID)`.
+    ///
+    /// Empty for a consistent bundle, loading a single score only includes what it references.
+    pub fn dangling(&self) -> Vec<(String, String)> {
+        let mut dangling = vec![];
+        for score in &self.metadata.scores {
+            if self.publication(score.pgs_publication_id).is_none() {
+                dangling.push((score.id.to_string(), score.pgs_publication_id.to_string()));
+            }
+            for id in &score.mapped_traits_efo_id {
+                if self.efo_trait(id).is_none() {
+                    dangling.push((score.id.to_string(), id.clone()));
+                }
+            }
+        }
+        for metric in &self.metadata.performance_metrics {
+            if self.score(metric.evaluated_score).is_none() {
+                dangling.push((metric.id.to_string(), metric.evaluated_score.to_string()));
+            }
+            if self.sample_set(metric.pgs_sample_set).is_empty() {
+                dangling.push((metric.id.to_string(), metric.pgs_sample_set.to_string()));
+            }
+            if self.publication(metric.pgs_publication_id).is_none() {
+                dangling.push((metric.id.to_string(), metric.pgs_publication_id.to_string()));
+            }
+        }
+        dangling
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct Cohort {
@@ -633,3 +790,112 @@ mod boilerplate {
         s.trim_matches('"').to_owned()
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use ids::pgs::{PgsId, pss::PssId};
+
+    use super::{Metadata, Score, read_file};
+
+    const COHORTS: &str = "\
+Cohort ID,Cohort Name,Previous/other/additional names
+BCAC,Breast Cancer Association Consortium,
+UKB,UK Biobank,
+";
+
+    const SAMPLE_SETS: &str = "\
+PGS Sample Set (PSS),Polygenic Score (PGS) ID,Number of Individuals,Number of Cases,Number of Controls,Percent of Participants Who are Male,Sample Age,Broad Ancestry Category,\"Ancestry (e.g. French, Chinese)\",Country of Recruitment,Additional Ancestry Description,Phenotype Definitions and Methods,Followup Time,GWAS Catalog Study ID (GCST...),Source PubMed ID (PMID),Source DOI,Cohort(s),Additional Sample/Cohort Information
+PSS000001,\"PGS000001, PGS000002\",1000,500.0,500.0,,,European,,,,,,,,,BCAC|UKB,
+PSS000001,\"PGS000001, PGS000002\",200,100.0,100.0,,,East Asian,,,,,,,,,BCAC,
+";
+
+    const PERFORMANCE_METRICS: &str = "\
+PGS Performance Metric (PPM) ID,Evaluated Score,PGS Sample Set (PSS),PGS Publication (PGP) ID,Reported Trait,Covariates Included in the Model,PGS Performance: Other Relevant Information,Publication (PMID),Publication (doi),Hazard Ratio (HR),Odds Ratio (OR),Beta,Area Under the Receiver-Operating Characteristic Curve (AUROC),Concordance Statistic (C-index),Other Metric(s)
+PPM000001,PGS000001,PSS000001,PGP000001,Breast cancer,,,,,,1.55,,0.622,,
+PPM000002,PGS000002,PSS000002,PGP000001,Breast cancer,,,,,,1.4,,,,
+";
+
+    const DEVELOPMENT_SAMPLES: &str = "\
+Polygenic Score (PGS) ID,Stage of PGS Development,Number of Individuals,Number of Cases,Number of Controls,Percent of Participants Who are Male,Sample Age,Broad Ancestry Category,\"Ancestry (e.g. French, Chinese)\",Country of Recruitment,Additional Ancestry Description,Phenotype Definitions and Methods,Followup Time,GWAS Catalog Study ID (GCST...),Source PubMed ID (PMID),Source DOI,Cohort(s),Additional Sample/Cohort Information
+PGS000001,Source of Variant Associations (GWAS),79000.0,,,,,European,,,,,,,,,BCAC,
+";
+
+    const SCORES: &str = "\
+Polygenic Score (PGS) ID,PGS Name,Reported Trait,Mapped Trait(s) (EFO label),Mapped Trait(s) (EFO ID),PGS Development Method,PGS Development Details/Relevant Parameters,Original Genome Build,Number of Variants,Number of Interaction Terms,Type of Variant Weight,PGS Publication (PGP) ID,Publication (PMID),Publication (doi),Score and results match the original publication,Ancestry Distribution (%) - Source of Variant Associations (GWAS),Ancestry Distribution (%) - Score Development/Training,Ancestry Distribution (%) - PGS Evaluation,FTP link,Release Date,License/Terms of Use
+PGS000001,PRS77_BC,Breast cancer,breast carcinoma,EFO_0000305,SNP-based,,NR,77,0,beta,PGP000001,,,True,,,,https://ftp.ebi.ac.uk/pub/databases/spot/pgs/scores/PGS000001/ScoringFiles/PGS000001.txt.gz,2019-10-14,
+PGS000002,PRS313_BC,Breast cancer,breast carcinoma|other trait,EFO_0000305|EFO_9999999,SNP-based,,NR,313,0,beta,PGP000002,,,True,,,,https://ftp.ebi.ac.uk/pub/databases/spot/pgs/scores/PGS000002/ScoringFiles/PGS000002.txt.gz,2019-10-14,
+";
+
+    const EFO_TRAITS: &str = "\
+Ontology Trait ID,Ontology Trait Label,Ontology Trait Description,Ontology URL
+EFO_0000305,breast carcinoma,A carcinoma of the breast.,http://www.ebi.ac.uk/efo/EFO_0000305
+";
+
+    const PUBLICATIONS: &str = "\
+PGS Publication/Study (PGP) ID,First Author,Title,Journal Name,Publication Date,Release Date,Authors,digital object identifier (doi),PubMed ID (PMID)
+PGP000001,Mavaddat N,Prediction of breast cancer risk based on profiling with common genetic variants,J Natl Cancer Inst,2015-04-08,2019-10-14,\"Mavaddat N, Pharoah PDP, Michailidou K\",10.1093/jnci/djv036,
+";
+
+    /// Shared with the other modules' tests.
+    pub(crate) fn scores() -> Vec<Score> {
+        read_file(SCORES.as_bytes()).unwrap()
+    }
+
+    fn metadata() -> Metadata {
+        Metadata {
+            cohorts: read_file(COHORTS.as_bytes()).unwrap(),
+            evaluation_sample_sets: read_file(SAMPLE_SETS.as_bytes()).unwrap(),
+            performance_metrics: read_file(PERFORMANCE_METRICS.as_bytes()).unwrap(),
+            score_development_samples: read_file(DEVELOPMENT_SAMPLES.as_bytes()).unwrap(),
+            scores: scores(),
+            efo_traits: read_file(EFO_TRAITS.as_bytes()).unwrap(),
+            publications: read_file(PUBLICATIONS.as_bytes()).unwrap(),
+        }
+    }
+
+    #[test]
+    fn index() {
+        let metadata = metadata();
+        let index = metadata.index();
+
+        let record = index.score_record(PgsId::new(1)).unwrap();
+        assert_eq!(record.score.name, "PRS77_BC");
+        assert_eq!(record.publication.unwrap().first_author, "Mavaddat N");
+        assert_eq!(record.traits.len(), 1);
+        assert_eq!(record.traits[0].label, "breast carcinoma");
+        assert_eq!(record.development_samples.len(), 1);
+        assert_eq!(record.performance_metrics.len(), 1);
+        assert_eq!(record.performance_metrics[0].odds_ratio, "1.55");
+        assert!(index.score_record(PgsId::new(3)).is_none());
+
+        // One row per ancestry group.
+        let sample_set = index.sample_set(PssId::new(1));
+        assert_eq!(sample_set.len(), 2);
+        assert_eq!(sample_set[1].broad_ancestry_category, "East Asian");
+        let scores = index.sample_set_scores(PssId::new(1));
+        assert_eq!(
+            scores.iter().map(|s| s.id).collect::<Vec<_>>(),
+            [PgsId::new(1), PgsId::new(2)]
+        );
+        assert!(index.sample_set(PssId::new(3)).is_empty());
+
+        let cohorts = index.cohorts(&sample_set[0].cohorts);
+        assert_eq!(cohorts.len(), 2);
+        assert_eq!(cohorts[1].name, "UK Biobank");
+        assert_eq!(
+            index.cohorts(&["BCAC".to_owned(), "XYZ".to_owned()]).len(),
+            1
+        );
+
+        let score = index.score(PgsId::new(2)).unwrap();
+        assert_eq!(index.score_traits(score).len(), 1);
+        assert_eq!(
+            index.dangling(),
+            [
+                ("PGS000002".to_owned(), "PGP000002".to_owned()),
+                ("PGS000002".to_owned(), "EFO_9999999".to_owned()),
+                ("PPM000002".to_owned(), "PSS000002".to_owned()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,271 @@
+//! Associations with alleles named by gene instead of by sequence: HLA alleles, pharmacogene star
+//! alleles and APOE isoforms, see [docs::DOCUMENTED_EXCEPTIONS](crate::docs::DOCUMENTED_EXCEPTIONS).
+//!
+//! These are scored from external calls (e.g. HLA imputation) through a [NamedAlleleResolver],
+//! or skipped with [score_named_alleles] keeping track of what was left out.
+
+use std::fmt;
+
+use crate::{Allele, HarmonizedStudyAssociation};
+
+/// The classical HLA genes, which are sometimes named without the `HLA-` prefix.
+const HLA_GENES: &[&str] = &[
+    "A", "B", "C", "E", "F", "G", "DPA1", "DPB1", "DQA1", "DQB1", "DRA", "DRB1", "DRB3", "DRB4",
+    "DRB5",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum NamedAllele {
+    /// A classical HLA allele, e.g. `HLA-DQA1*01:02` has gene `DQA1` and fields `["01", "02"]`.
+    Hla { gene: String, fields: Vec<String> },
+    /// An HLA amino acid, e.g. `HLA_B_pos_116_Ser`.
+    HlaAminoAcid {
+        gene: String,
+        position: i64,
+        residue: String,
+    },
+    /// A star allele of a pharmacogene, e.g. `CYP2A6*1x2`.
+    Star { gene: String, allele: String },
+    /// APOE isoforms, e.g. `e3/e4` is `[3, 4]`.
+    Apoe(Vec<u8>),
+}
+
+/// Calls of named alleles for one sample.
+pub trait NamedAlleleResolver {
+    /// Copies (0 to 2) of `allele`, `None` if it wasn't called.
+    fn dosage(&self, allele: &NamedAllele) -> Option<f64>;
+}
+/// Resolves nothing, so all named associations are skipped.
+impl NamedAlleleResolver for () {
+    fn dosage(&self, _: &NamedAllele) -> Option<f64> {
+        None
+    }
+}
+
+/// See [score_named_alleles].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct NamedAlleleScore {
+    /// The sum of the weights times the dosages of the resolved associations.
+    pub score: f64,
+    pub resolved: usize,
+    /// With their index in the associations.
+    pub skipped: Vec<(usize, SkipReason)>,
+}
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SkipReason {
+    /// The allele name isn't in a known format.
+    Unrecognized(String),
+    /// The resolver has no call for (one of) the alleles.
+    NotCalled(Vec<NamedAllele>),
+    /// Only additive weights are supported.
+    NoWeight,
+}
+
+impl NamedAllele {
+    /// Parses a named allele, `;` separates alternatives which any count as the allele.
+    ///
+    /// Alleles without a gene (e.g. `*01:01`) take it from `locus`, usually the
+    /// [HarmonizedStudyAssociation::locus_name].
+    pub fn parse(name: &str, locus: Option<&str>) -> Option<Vec<Self>> {
+        let name = name.trim();
+        if let Some(apoe) = apoe(name) {
+            return Some(vec![apoe]);
+        }
+        name.split(';')
+            .map(|name| {
+                let name = name.trim();
+                match (name.strip_prefix('*'), locus) {
+                    (Some(allele), Some(locus)) => Self::parse_single(&format!("{locus}*{allele}")),
+                    (Some(_), None) => None,
+                    (None, _) => Self::parse_single(name),
+                }
+            })
+            .collect()
+    }
+
+    fn parse_single(name: &str) -> Option<Self> {
+        let hla = name
+            .strip_prefix("HLA-")
+            .or_else(|| name.strip_prefix("HLA_"));
+        let rest = hla.unwrap_or(name);
+
+        if let Some((gene, allele)) = rest.split_once('*') {
+            if !is_gene(gene) || allele.is_empty() {
+                return None;
+            }
+            if hla.is_none() && !HLA_GENES.contains(&gene) {
+                return Some(Self::Star {
+                    gene: gene.to_owned(),
+                    allele: allele.to_owned(),
+                });
+            }
+            return Some(Self::Hla {
+                gene: gene.to_owned(),
+                fields: hla_fields(allele)?,
+            });
+        }
+
+        hla?;
+        let parts: Vec<&str> = rest.split('_').collect();
+        let number = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+        let residue = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_alphabetic());
+        match parts[..] {
+            // e.g. `HLA_B_pos_116_Ser`
+            [gene, "pos", position, r] if residue(r) => Some(Self::HlaAminoAcid {
+                gene: gene.to_owned(),
+                position: position.parse().ok()?,
+                residue: r.to_owned(),
+            }),
+            // e.g. `HLA_A_95_30019036_V`, with the genomic position of the codon.
+            [gene, position, bp, r] if number(bp) && residue(r) => Some(Self::HlaAminoAcid {
+                gene: gene.to_owned(),
+                position: position.parse().ok()?,
+                residue: r.to_owned(),
+            }),
+            // e.g. `HLA_DRB1_03_01`
+            [gene, a, b] if number(a) && number(b) => Some(Self::Hla {
+                gene: gene.to_owned(),
+                fields: vec![a.to_owned(), b.to_owned()],
+            }),
+            _ => None,
+        }
+    }
+}
+impl fmt::Display for NamedAllele {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Hla { gene, fields } => write!(f, "HLA-{gene}*{}", fields.join(":")),
+            Self::HlaAminoAcid {
+                gene,
+                position,
+                residue,
+            } => write!(f, "HLA-{gene} {position}{residue}"),
+            Self::Star { gene, allele } => write!(f, "{gene}*{allele}"),
+            Self::Apoe(isoforms) => {
+                let isoforms: Vec<String> = isoforms.iter().map(|i| format!("e{i}")).collect();
+                write!(f, "APOE {}", isoforms.join("/"))
+            }
+        }
+    }
+}
+
+/// e.g. `e4`, `e3/e4` or `e3;e4`.
+fn apoe(name: &str) -> Option<NamedAllele> {
+    let isoforms = name
+        .split(['/', ';'])
+        .map(|isoform| match isoform.trim() {
+            "e2" => Some(2),
+            "e3" => Some(3),
+            "e4" => Some(4),
+            _ => None,
+        })
+        .collect::<Option<_>>()?;
+    Some(NamedAllele::Apoe(isoforms))
+}
+
+fn is_gene(gene: &str) -> bool {
+    gene.starts_with(|c: char| c.is_ascii_uppercase())
+        && gene.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+/// `01:02`, or the older `0102` without separators.
+fn hla_fields(allele: &str) -> Option<Vec<String>> {
+    if allele.contains(':') {
+        return Some(allele.split(':').map(String::from).collect());
+    }
+    if !allele.len().is_multiple_of(2) || !allele.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    Some(
+        allele
+            .as_bytes()
+            .chunks(2)
+            .map(|field| String::from_utf8(field.to_vec()).unwrap())
+            .collect(),
+    )
+}
+
+impl HarmonizedStudyAssociation {
+    /// The named alleles (any of which count) of associations that aren't positional,
+    /// [None] for plain variants.
+    ///
+    /// The name is either the effect allele or, for presence/absence alleles, the rsID column.
+    /// Names that aren't recognized are returned as the error.
+    pub fn named_allele(&self) -> Option<Result<Vec<NamedAllele>, String>> {
+        let name = match &self.effect_allele {
+            Allele::Other(other) if !other.starts_with('<') && other != "P" => other,
+            _ => self
+                .rs_id
+                .as_ref()
+                .filter(|id| id.parse::<ids::rs::RsId>().is_err() && !id.is_empty())?,
+        };
+        let locus = self.locus_name.as_deref().map(str::trim);
+        Some(NamedAllele::parse(name, locus).ok_or_else(|| name.clone()))
+    }
+}
+
+/// Scores the named associations in `associations` with `resolver`, for one sample.
+/// Positional associations are ignored.
+pub fn score_named_alleles(
+    associations: &[HarmonizedStudyAssociation],
+    resolver: &impl NamedAlleleResolver,
+) -> NamedAlleleScore {
+    let mut score = NamedAlleleScore::default();
+    for (i, association) in associations.iter().enumerate() {
+        let alleles = match association.named_allele() {
+            None => continue,
+            Some(Ok(alleles)) => alleles,
+            Some(Err(name)) => {
+                score.skipped.push((i, SkipReason::Unrecognized(name)));
+                continue;
+            }
+        };
+        let Some(weight) = association.effect_weight else {
+            score.skipped.push((i, SkipReason::NoWeight));
+            continue;
+        };
+        let dosage: Option<f64> = alleles.iter().map(|a| resolver.dosage(a)).sum();
+        match dosage {
+            Some(dosage) => {
+                score.score += *weight * dosage.min(2.);
+                score.resolved += 1;
+            }
+            None => score.skipped.push((i, SkipReason::NotCalled(alleles))),
+        }
+    }
+    score
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NamedAllele;
+
+    #[test]
+    fn parse() {
+        let parse = |name: &str, locus: Option<&str>| -> Vec<String> {
+            NamedAllele::parse(name, locus)
+                .unwrap_or_default()
+                .iter()
+                .map(|a| a.to_string())
+                .collect()
+        };
+        assert_eq!(parse("HLA-A*02:01", None), ["HLA-A*02:01"]);
+        assert_eq!(parse("HLA_DQA1*01:02", None), ["HLA-DQA1*01:02"]);
+        assert_eq!(parse("HLA-DQA1*0102", None), ["HLA-DQA1*01:02"]);
+        assert_eq!(parse("B*27:05", None), ["HLA-B*27:05"]);
+        assert_eq!(parse("HLA_DRB1_03_01", None), ["HLA-DRB1*03:01"]);
+        assert_eq!(parse("HLA_B_pos_116_Ser", None), ["HLA-B 116Ser"]);
+        assert_eq!(parse("HLA_A_95_30019036_V", None), ["HLA-A 95V"]);
+        assert_eq!(parse("CYP2A6*1x2", None), ["CYP2A6*1x2"]);
+        assert_eq!(parse("e3/e4", None), ["APOE e3/e4"]);
+        assert_eq!(
+            parse("*03:01; *03:04", Some("HLA-DRB1")),
+            ["HLA-DRB1*03:01", "HLA-DRB1*03:04"]
+        );
+        assert_eq!(parse("*4", Some("CYP2A6")), ["CYP2A6*4"]);
+
+        assert!(parse("*01:01", None).is_empty());
+        assert!(parse("HLA_DRB1_11_13_71_74_IV", None).is_empty());
+        assert!(parse("DQ2.5/DQ8", None).is_empty());
+    }
+}
@@ -0,0 +1,277 @@
+//! Ancestry adjustment of raw scores, as in the PGS Catalog Calculator.
+//!
+//! Samples are projected onto principal components of a reference panel (e.g. 1000 Genomes),
+//! and the part of the score predicted by ancestry is regressed out, using reference samples
+//! scored the same way:
+//! - [Adjustment::Mean] fits the score on the PCs, and divides the residual by the residual
+//!   standard deviation of the reference (`Z_norm1`).
+//! - [Adjustment::MeanVariance] also fits the squared residuals on the PCs, with a gamma GLM
+//!   with a log link, so the spread can vary with ancestry too (`Z_norm2`).
+
+/// Variant loadings of reference principal components, to project new samples onto them.
+///
+/// Dosages are standardized with the reference mean and `sqrt(2p(1 - p))` before projecting,
+/// as when the PCs were computed. Projected PCs shrink towards 0 compared to the reference
+/// samples, more so for the later components, so only the first few are worth using.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PcaProjection {
+    /// Reference mean dosage of each variant.
+    pub means: Vec<f64>,
+    /// Reference standard deviation of each variant.
+    pub scales: Vec<f64>,
+    /// Row-major, variants × components.
+    pub loadings: Vec<f64>,
+}
+
+/// How much of the ancestry signal to remove, see the [module docs](self).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Adjustment {
+    Mean,
+    MeanVariance,
+}
+
+/// Fitted on reference samples, see [AncestryModel::fit].
+#[derive(Debug, Clone, PartialEq)]
+pub struct AncestryModel {
+    adjustment: Adjustment,
+    /// Intercept first, then one per PC.
+    mean: Vec<f64>,
+    /// For [Adjustment::Mean].
+    residual_sd: f64,
+    /// For [Adjustment::MeanVariance], of the log of the residual variance.
+    variance: Vec<f64>,
+    /// Adjusted scores of the reference samples, sorted, for percentiles.
+    reference: Vec<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AdjustedScore {
+    /// The ancestry-adjusted z-score.
+    pub z: f64,
+    /// Share of reference samples with a lower adjusted score, in `[0, 100]`.
+    pub percentile: f64,
+}
+
+impl PcaProjection {
+    pub fn components(&self) -> usize {
+        match self.means.len() {
+            0 => 0,
+            n => self.loadings.len() / n,
+        }
+    }
+    /// The PCs of a sample from its dosages, in the order of [Self::means].
+    /// Missing dosages are imputed to the reference mean.
+    pub fn project(&self, dosages: &[Option<f64>]) -> Vec<f64> {
+        assert_eq!(dosages.len(), self.means.len());
+        let k = self.components();
+        let mut pcs = vec![0.; k];
+        for (v, dosage) in dosages.iter().enumerate() {
+            let Some(dosage) = dosage else { continue };
+            if self.scales[v] == 0. {
+                continue;
+            }
+            let x = (dosage - self.means[v]) / self.scales[v];
+            for (pc, loading) in pcs.iter_mut().zip(&self.loadings[v * k..(v + 1) * k]) {
+                *pc += x * loading;
+            }
+        }
+        pcs
+    }
+}
+
+impl AncestryModel {
+    /// Fits the model on the PCs and raw scores of reference samples.
+    ///
+    /// `None` if there are too few samples for the number of PCs, or the PCs are collinear.
+    pub fn fit(adjustment: Adjustment, pcs: &[Vec<f64>], scores: &[f64]) -> Option<Self> {
+        assert_eq!(pcs.len(), scores.len());
+        let k = pcs.first()?.len();
+        if pcs.len() <= k + 1 {
+            return None;
+        }
+        let design: Vec<Vec<f64>> = pcs.iter().map(|pcs| row(pcs)).collect();
+
+        let mean = least_squares(&design, scores, &vec![1.; scores.len()])?;
+        let residuals: Vec<f64> = design
+            .iter()
+            .zip(scores)
+            .map(|(x, y)| y - dot(x, &mean))
+            .collect();
+        let residual_sd = (residuals.iter().map(|r| r * r).sum::<f64>()
+            / (residuals.len() - k - 1) as f64)
+            .sqrt();
+
+        let variance = match adjustment {
+            Adjustment::Mean => vec![],
+            Adjustment::MeanVariance => {
+                let squared: Vec<f64> = residuals.iter().map(|r| (r * r).max(1e-12)).collect();
+                gamma_log_glm(&design, &squared)?
+            }
+        };
+
+        let mut model = Self {
+            adjustment,
+            mean,
+            residual_sd,
+            variance,
+            reference: vec![],
+        };
+        let mut reference: Vec<f64> = pcs
+            .iter()
+            .zip(scores)
+            .map(|(pcs, score)| model.z(pcs, *score))
+            .collect();
+        reference.sort_by(f64::total_cmp);
+        model.reference = reference;
+        Some(model)
+    }
+
+    pub fn adjustment(&self) -> Adjustment {
+        self.adjustment
+    }
+
+    /// Adjusts the raw score of a sample with the given PCs.
+    pub fn adjust(&self, pcs: &[f64], score: f64) -> AdjustedScore {
+        let z = self.z(pcs, score);
+        let below = self.reference.partition_point(|r| *r < z);
+        AdjustedScore {
+            z,
+            percentile: 100. * below as f64 / self.reference.len() as f64,
+        }
+    }
+
+    fn z(&self, pcs: &[f64], score: f64) -> f64 {
+        assert_eq!(pcs.len() + 1, self.mean.len());
+        let x = row(pcs);
+        let residual = score - dot(&x, &self.mean);
+        match self.adjustment {
+            Adjustment::Mean => residual / self.residual_sd,
+            Adjustment::MeanVariance => residual / dot(&x, &self.variance).exp().sqrt(),
+        }
+    }
+}
+
+/// The design matrix row of a sample, with the intercept.
+fn row(pcs: &[f64]) -> Vec<f64> {
+    std::iter::once(1.).chain(pcs.iter().copied()).collect()
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+/// Weighted least squares, through the normal equations.
+fn least_squares(x: &[Vec<f64>], y: &[f64], weights: &[f64]) -> Option<Vec<f64>> {
+    let p = x[0].len();
+    let mut a = vec![vec![0.; p + 1]; p];
+    for ((x, y), w) in x.iter().zip(y).zip(weights) {
+        for i in 0..p {
+            for j in 0..p {
+                a[i][j] += w * x[i] * x[j];
+            }
+            a[i][p] += w * x[i] * y;
+        }
+    }
+    solve(a)
+}
+
+/// Gaussian elimination with partial pivoting of an augmented matrix.
+fn solve(mut a: Vec<Vec<f64>>) -> Option<Vec<f64>> {
+    let n = a.len();
+    for col in 0..n {
+        let pivot = (col..n).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+        if a[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        a.swap(col, pivot);
+        for i in col + 1..n {
+            let factor = a[i][col] / a[col][col];
+            let (above, below) = a.split_at_mut(i);
+            for (x, pivot) in below[0][col..].iter_mut().zip(&above[col][col..]) {
+                *x -= factor * pivot;
+            }
+        }
+    }
+    let mut solution = vec![0.; n];
+    for i in (0..n).rev() {
+        let rest: f64 = (i + 1..n).map(|j| a[i][j] * solution[j]).sum();
+        solution[i] = (a[i][n] - rest) / a[i][i];
+    }
+    Some(solution)
+}
+
+/// Coefficients of a gamma GLM with a log link, by iteratively reweighted least squares.
+/// The working weights of this family are all 1.
+fn gamma_log_glm(x: &[Vec<f64>], y: &[f64]) -> Option<Vec<f64>> {
+    let ones = vec![1.; y.len()];
+    let log_y: Vec<f64> = y.iter().map(|y| y.ln()).collect();
+    let mut beta = least_squares(x, &log_y, &ones)?;
+    for _ in 0..50 {
+        let working: Vec<f64> = x
+            .iter()
+            .zip(y)
+            .map(|(x, y)| {
+                let eta = dot(x, &beta);
+                eta + (y - eta.exp()) / eta.exp()
+            })
+            .collect();
+        let next = least_squares(x, &working, &ones)?;
+        let change = next
+            .iter()
+            .zip(&beta)
+            .map(|(a, b)| (a - b).abs())
+            .fold(0., f64::max);
+        beta = next;
+        if change < 1e-10 {
+            break;
+        }
+    }
+    Some(beta)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Adjustment, AncestryModel, PcaProjection};
+
+    #[test]
+    fn projection() {
+        let projection = PcaProjection {
+            means: vec![1., 0.5],
+            scales: vec![0.5, 0.25],
+            loadings: vec![1., 0., 0., 1.],
+        };
+        assert_eq!(projection.components(), 2);
+        assert_eq!(projection.project(&[Some(2.), None]), [2., 0.]);
+    }
+
+    #[test]
+    fn adjust() {
+        // Two ancestry groups along PC1, the second with a higher and wider score distribution.
+        let noise = [-1.5, -0.5, 0.5, 1.5];
+        let mut pcs = vec![];
+        let mut scores = vec![];
+        for (pc1, (mean, sd)) in [(-1., (0., 1.)), (1., (4., 2.))] {
+            for (i, noise) in noise.iter().cycle().take(40).enumerate() {
+                pcs.push(vec![pc1, (i % 5) as f64 / 10.]);
+                scores.push(mean + sd * noise);
+            }
+        }
+
+        let model = AncestryModel::fit(Adjustment::Mean, &pcs, &scores).unwrap();
+        // The same distance from the group mean gets the same z.
+        let low = model.adjust(&[-1., 0.], 1.5);
+        let high = model.adjust(&[1., 0.], 5.5);
+        assert!((low.z - high.z).abs() < 1e-6);
+        assert!(model.adjust(&[1., 0.], 4.).z.abs() < 1e-6);
+
+        // Scaled by the spread of each group instead.
+        let model = AncestryModel::fit(Adjustment::MeanVariance, &pcs, &scores).unwrap();
+        let low = model.adjust(&[-1., 0.], 1.5);
+        let high = model.adjust(&[1., 0.], 7.);
+        assert!((low.z - high.z).abs() < 1e-3);
+        assert!(low.z > 0.);
+        assert!((50. ..100.).contains(&low.percentile));
+
+        assert!(AncestryModel::fit(Adjustment::Mean, &pcs[..3], &scores[..3]).is_none());
+    }
+}
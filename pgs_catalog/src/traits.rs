@@ -0,0 +1,123 @@
+//! Finding scores by the ontology traits they are mapped to.
+
+use std::{collections::BTreeSet, io};
+
+use ids::{efo::EfoId, pgs::pgp::PgpId, pubmed::PubmedId};
+use resource::{RawResourceExt, UrlResource};
+use serde::Deserialize;
+
+use crate::{PgsId, metadata::Score};
+
+const REST_BASE: &str = "https://www.pgscatalog.org/rest";
+
+/// A score found by [find_scores_for_trait].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TraitScore {
+    pub id: PgsId,
+    pub name: String,
+    pub reported_trait: String,
+    /// The mapped trait that matched, the queried trait or one of its descendants.
+    pub matched_trait: EfoId,
+    pub publication: PgpId,
+    pub pmid: Option<PubmedId>,
+    pub release_date: String,
+}
+
+/// The scores mapped to `efo`, by the catalog metadata.
+///
+/// With `include_children`, also the scores mapped to descendants of `efo` in the ontology,
+/// which are listed by the catalog's REST API.
+pub async fn find_scores_for_trait(
+    efo: EfoId,
+    include_children: bool,
+) -> io::Result<Vec<TraitScore>> {
+    let mut traits = BTreeSet::from([efo.clone()]);
+    if include_children {
+        traits.extend(child_traits(&efo).await?);
+    }
+    Ok(scores_for_traits(&Score::load_all().await?, &traits))
+}
+
+fn scores_for_traits(scores: &[Score], traits: &BTreeSet<EfoId>) -> Vec<TraitScore> {
+    scores
+        .iter()
+        .filter_map(|score| {
+            let matched_trait = score
+                .mapped_traits_efo_id
+                .iter()
+                .filter_map(|id| id.parse::<EfoId>().ok())
+                .find(|id| traits.contains(id))?;
+            Some(TraitScore {
+                id: score.id,
+                name: score.name.clone(),
+                reported_trait: score.reported_trait.clone(),
+                matched_trait,
+                publication: score.pgs_publication_id,
+                pmid: score.publication_pmid,
+                release_date: score.release_date.clone(),
+            })
+        })
+        .collect()
+}
+
+/// All descendants of `efo` with scores, not cached as the ontology mapping is updated with
+/// each release.
+async fn child_traits(efo: &EfoId) -> io::Result<Vec<EfoId>> {
+    #[derive(Deserialize)]
+    struct Trait {
+        child_traits: Vec<ChildTrait>,
+    }
+    #[derive(Deserialize)]
+    struct ChildTrait {
+        id: EfoId,
+    }
+
+    let url = format!("{REST_BASE}/trait/{efo}?include_children=1");
+    let body = UrlResource::new(url)?.read_vec_async().await?;
+    let found: Trait = serde_json::from_slice(&body)?;
+    Ok(found.child_traits.into_iter().map(|t| t.id).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use ids::efo::EfoId;
+
+    use crate::{PgsId, metadata::tests::scores};
+
+    use super::scores_for_traits;
+
+    fn matches(traits: &[&str]) -> Vec<(PgsId, String)> {
+        let traits: BTreeSet<EfoId> = traits.iter().map(|t| t.parse().unwrap()).collect();
+        scores_for_traits(&scores(), &traits)
+            .into_iter()
+            .map(|s| (s.id, s.matched_trait.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn scores_for_traits_matches_any_mapped_trait() {
+        assert_eq!(
+            matches(&["EFO_0000305"]),
+            [
+                (PgsId::new(1), "EFO_0000305".to_owned()),
+                (PgsId::new(2), "EFO_0000305".to_owned())
+            ]
+        );
+        assert_eq!(
+            matches(&["EFO:9999999"]),
+            [(PgsId::new(2), "EFO_9999999".to_owned())]
+        );
+        assert!(matches(&["MONDO_0004975"]).is_empty());
+        assert!(matches(&[]).is_empty());
+
+        let score =
+            &scores_for_traits(&scores(), &BTreeSet::from(["EFO_0000305".parse().unwrap()]))[0];
+        assert_eq!(score.name, "PRS77_BC");
+        assert_eq!(score.reported_trait, "Breast cancer");
+        assert_eq!(score.publication.to_string(), "PGP000001");
+        assert_eq!(score.pmid, None);
+        assert_eq!(score.release_date, "2019-10-14");
+    }
+}
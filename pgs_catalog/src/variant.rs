@@ -0,0 +1,223 @@
+//! Typed positions and alleles of harmonized associations.
+
+use std::{io, str::FromStr};
+
+use biocore::{
+    dna::DnaSequence,
+    location::{
+        ContigPosition,
+        build::{Build, BuildContig},
+    },
+};
+
+use crate::{Allele, GenomeBuild, HarmonizedStudyAssociation};
+
+/// See [HarmonizedStudyAssociation::to_variant].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AssociationVariant<C> {
+    pub at: ContigPosition<C>,
+    pub effect_allele: DnaSequence,
+    /// The reported or inferred other alleles, inference can give several candidates.
+    /// Empty if unknown.
+    pub other_alleles: Vec<DnaSequence>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum VariantError {
+    #[error("Expected contigs of {expected}, the associations are harmonized to {found}.")]
+    BuildMismatch {
+        expected: &'static str,
+        found: GenomeBuild,
+    },
+    #[error("The association has no harmonized chromosome.")]
+    NoChromosome,
+    #[error("The association has no harmonized position.")]
+    NoPosition,
+    #[error("Harmonized positions are 1-based, found 0.")]
+    ZeroPosition,
+    #[error("Unknown contig for the build: '{0}'.")]
+    UnknownContig(String),
+    #[error("Haplotype and diplotype associations span several variants.")]
+    Haplotype,
+    #[error("HLA alleles have no genomic position: '{0}'.")]
+    Hla(String),
+    #[error("The effect allele is empty.")]
+    EmptyEffectAllele,
+    #[error("Unsupported effect allele: {0:?}.")]
+    EffectAllele(Allele),
+    #[error("Unsupported other allele: {0:?}.")]
+    OtherAllele(Allele),
+}
+impl From<VariantError> for io::Error {
+    fn from(e: VariantError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, e)
+    }
+}
+
+impl HarmonizedStudyAssociation {
+    /// The harmonized position and alleles, on the contigs `C` of `build`.
+    ///
+    /// The reported other allele takes precedence over the inferred ones, as in
+    /// [Self::alleles].
+    ///
+    /// The associations don't know which build they were harmonized to, so `build` is checked
+    /// against the build of `C` to catch mixing up files.
+    pub fn to_variant<C>(&self, build: GenomeBuild) -> Result<AssociationVariant<C>, VariantError>
+    where
+        C: BuildContig + FromStr,
+    {
+        let expected = <C::Build as Build>::NAME;
+        if expected != build.to_string() {
+            return Err(VariantError::BuildMismatch {
+                expected,
+                found: build,
+            });
+        }
+        if self.is_haplotype == Some(true) || self.is_diplotype == Some(true) {
+            return Err(VariantError::Haplotype);
+        }
+
+        let effect_allele = match &self.effect_allele {
+            Allele::Sequence(sequence) if sequence.is_empty() => {
+                return Err(VariantError::EmptyEffectAllele);
+            }
+            Allele::Sequence(sequence) => sequence.clone(),
+            Allele::Other(other) if other.starts_with('*') || self.imputation_method.is_some() => {
+                return Err(VariantError::Hla(other.clone()));
+            }
+            allele => return Err(VariantError::EffectAllele(allele.clone())),
+        };
+        let other_alleles = match self
+            .other_allele
+            .as_ref()
+            .or(self.infer_other_allele.as_ref())
+        {
+            None => vec![],
+            Some(Allele::Sequence(sequence)) => vec![sequence.clone()],
+            Some(allele @ Allele::Other(other)) => other
+                .split('/')
+                .map(|a| a.parse().ok())
+                .collect::<Option<_>>()
+                .ok_or_else(|| VariantError::OtherAllele(allele.clone()))?,
+            Some(allele @ Allele::Insertion) => {
+                return Err(VariantError::OtherAllele(allele.clone()));
+            }
+        };
+
+        if self.chr.is_empty() {
+            return Err(VariantError::NoChromosome);
+        }
+        let contig =
+            parse_contig(&self.chr).ok_or_else(|| VariantError::UnknownContig(self.chr.clone()))?;
+        let pos = self.pos.ok_or(VariantError::NoPosition)?;
+        let at = pos.checked_sub(1).ok_or(VariantError::ZeroPosition)?;
+
+        Ok(AssociationVariant {
+            at: ContigPosition { contig, at },
+            effect_allele,
+            other_alleles,
+        })
+    }
+}
+
+/// Harmonized chromosomes have no `chr` prefix, whatever the build.
+fn parse_contig<C: FromStr>(chr: &str) -> Option<C> {
+    let bare = chr.strip_prefix("chr").unwrap_or(chr);
+    let mut candidates = vec![bare.to_owned(), format!("chr{bare}")];
+    if matches!(bare, "M" | "MT") {
+        candidates.extend(["MT", "chrM"].map(String::from));
+    }
+    candidates.iter().find_map(|c| c.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use biocore::{
+        genome::Contig,
+        location::{ContigPosition, build::BuildContig},
+    };
+
+    use crate::{GenomeBuild, HarmonizedStudyAssociation, read_file};
+
+    use super::VariantError;
+
+    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    struct Chr(String);
+    impl AsRef<str> for Chr {
+        fn as_ref(&self) -> &str {
+            &self.0
+        }
+    }
+    impl Contig for Chr {
+        fn size(&self) -> u64 {
+            u64::MAX
+        }
+    }
+    impl BuildContig for Chr {
+        type Build = biocore::location::build::GRCh38;
+    }
+    impl FromStr for Chr {
+        type Err = ();
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "chr1" | "chrX" => Ok(Self(s.to_owned())),
+                _ => Err(()),
+            }
+        }
+    }
+
+    fn associations() -> Vec<HarmonizedStudyAssociation> {
+        let file = "\
+rsID\teffect_allele\tother_allele\teffect_weight\thm_source\thm_rsID\thm_chr\thm_pos\thm_inferOtherAllele
+rs1\tA\tG\t0.1\tENSEMBL\trs1\t1\t100\tC
+rs2\tA\t\t0.1\tENSEMBL\trs2\tX\t200\tC/G
+rs3\tA\tG\t0.1\tENSEMBL\trs3\t1\t0\t
+rs4\tA\tG\t0.1\tENSEMBL\trs4\t1\t\t
+rs5\tA\tG\t0.1\tENSEMBL\trs5\t\t\t
+";
+        read_file(file.as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn to_variant() {
+        let associations = associations();
+        let variant = |i: usize| associations[i].to_variant::<Chr>(GenomeBuild::GRCh38);
+
+        // The reported other allele wins over the inferred one, as in `alleles`.
+        let reported = variant(0).unwrap();
+        assert_eq!(
+            reported.at,
+            ContigPosition {
+                contig: Chr("chr1".to_owned()),
+                at: 99
+            }
+        );
+        assert_eq!(reported.effect_allele.to_string(), "A");
+        assert_eq!(reported.other_alleles.len(), 1);
+        assert_eq!(reported.other_alleles[0].to_string(), "G");
+        assert_eq!(associations[0].alleles().unwrap().other.to_string(), "G");
+
+        let inferred = variant(1).unwrap();
+        assert_eq!(inferred.at.contig, Chr("chrX".to_owned()));
+        assert_eq!(
+            inferred
+                .other_alleles
+                .iter()
+                .map(|a| a.to_string())
+                .collect::<Vec<_>>(),
+            ["C", "G"]
+        );
+
+        assert_eq!(variant(2), Err(VariantError::ZeroPosition));
+        assert_eq!(variant(3), Err(VariantError::NoPosition));
+        assert_eq!(variant(4), Err(VariantError::NoChromosome));
+        assert!(matches!(
+            associations[0].to_variant::<Chr>(GenomeBuild::GRCh37),
+            Err(VariantError::BuildMismatch { .. })
+        ));
+    }
+}
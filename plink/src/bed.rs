@@ -0,0 +1,130 @@
+//! Variant-major `.bed` genotype matrices, 2 bits per sample.
+
+use std::io::{self, Read, Seek, SeekFrom};
+
+use genomes1000::{DiploidGenotype, Genotype, GenotypePhasing};
+use utile::num::TryU64;
+
+use crate::PlinkError;
+
+/// The magic number, followed by the variant-major mode byte.
+pub const MAGIC: [u8; 2] = [0x6c, 0x1b];
+const VARIANT_MAJOR: u8 = 0x01;
+
+#[derive(Debug)]
+pub struct BedReader<R> {
+    reader: R,
+    samples: usize,
+    buf: Vec<u8>,
+}
+
+impl<R: Read> BedReader<R> {
+    pub fn new(mut reader: R, samples: usize) -> io::Result<Self> {
+        let mut header = [0; 3];
+        reader.read_exact(&mut header)?;
+        if header[..2] != MAGIC {
+            return Err(PlinkError::InvalidMagic.into());
+        }
+        if header[2] != VARIANT_MAJOR {
+            return Err(PlinkError::UnsupportedMode(header[2]).into());
+        }
+        Ok(Self {
+            reader,
+            samples,
+            buf: vec![0; samples.div_ceil(4)],
+        })
+    }
+
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    /// Reads the next variant, [None] at the end of the file.
+    pub fn read_dosages(&mut self) -> io::Result<Option<Vec<Option<u8>>>> {
+        match self.reader.read_exact(&mut self.buf) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        }
+        let dosages = (0..self.samples)
+            .map(|i| dosage(self.buf[i / 4] >> (2 * (i % 4))))
+            .collect();
+        Ok(Some(dosages))
+    }
+    pub fn read_genotypes(&mut self) -> io::Result<Option<Vec<Genotype>>> {
+        let dosages = self.read_dosages()?;
+        Ok(dosages.map(|d| d.into_iter().map(genotype).collect()))
+    }
+}
+impl<R: Read + Seek> BedReader<R> {
+    /// Moves to the start of the given variant.
+    pub fn seek_variant(&mut self, variant: usize) -> io::Result<()> {
+        let offset = 3 + (variant * self.buf.len()).u64_unwrap();
+        self.reader.seek(SeekFrom::Start(offset))?;
+        Ok(())
+    }
+}
+impl<R: Read> Iterator for BedReader<R> {
+    type Item = io::Result<Vec<Option<u8>>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_dosages().transpose()
+    }
+}
+
+/// Decodes a 2-bit genotype code (low bits) into a count of A1 alleles.
+pub fn dosage(code: u8) -> Option<u8> {
+    match code & 0b11 {
+        0b00 => Some(2),
+        0b01 => None,
+        0b10 => Some(1),
+        0b11 => Some(0),
+        _ => unreachable!(),
+    }
+}
+/// Unphased, with A2 as `0` and A1 as `1`.
+pub fn genotype(dosage: Option<u8>) -> Genotype {
+    let diploid = |left, right| {
+        Genotype::Diploid(DiploidGenotype {
+            left,
+            phasing: GenotypePhasing::Unphased,
+            right,
+        })
+    };
+    match dosage {
+        None => Genotype::Missing,
+        Some(0) => diploid(0, 0),
+        Some(1) => diploid(0, 1),
+        Some(_) => diploid(1, 1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::BedReader;
+
+    #[test]
+    fn test_decode() {
+        // 5 samples, 2 variants: 2 bytes each, first sample in the low bits.
+        let bed = [0x6c, 0x1b, 0x01, 0b11_10_01_00, 0b00, 0b00_00_00_11, 0b11];
+
+        let mut reader = BedReader::new(Cursor::new(bed), 5).unwrap();
+        let variants = (&mut reader).try_collect::<Vec<_>>().unwrap();
+        assert_eq!(
+            variants,
+            [
+                vec![Some(2), None, Some(1), Some(0), Some(2)],
+                vec![Some(0), Some(2), Some(2), Some(2), Some(0)],
+            ]
+        );
+
+        reader.seek_variant(1).unwrap();
+        let genotypes = reader.read_genotypes().unwrap().unwrap();
+        assert_eq!(genotypes[0].dosage(1), 0);
+        assert_eq!(genotypes[1].dosage(1), 2);
+
+        assert!(BedReader::new(Cursor::new([0x6c, 0x1b, 0x00]), 5).is_err());
+    }
+}
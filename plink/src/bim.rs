@@ -0,0 +1,174 @@
+//! `.bim` variant files: `chrom id cM position A1 A2`.
+
+use std::{
+    io::{self, BufRead},
+    marker::PhantomData,
+    str::FromStr,
+};
+
+use biocore::{genome::alias::ContigAliases, location::ContigPosition};
+
+use crate::PlinkError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BimRecord<C = String> {
+    /// 0-based (the file is 1-based).
+    pub at: ContigPosition<C>,
+    /// [None] if `.`.
+    pub id: Option<String>,
+    /// Genetic distance (cM), `0` if unknown.
+    pub centimorgans: f64,
+    /// The counted allele, usually the minor/alternate one.
+    pub allele_1: String,
+    /// Usually the major/reference allele.
+    pub allele_2: String,
+}
+
+pub struct BimReader<R, C = String> {
+    reader: R,
+    buf: String,
+    line: usize,
+    _contig: PhantomData<fn() -> C>,
+}
+
+impl<C> BimRecord<C> {
+    pub fn map_contig<NewContig>(self, f: impl FnOnce(C) -> NewContig) -> BimRecord<NewContig> {
+        BimRecord {
+            at: self.at.map_contig(f),
+            id: self.id,
+            centimorgans: self.centimorgans,
+            allele_1: self.allele_1,
+            allele_2: self.allele_2,
+        }
+    }
+}
+
+impl<R: BufRead, C> BimReader<R, C> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buf: String::new(),
+            line: 0,
+            _contig: PhantomData,
+        }
+    }
+}
+impl<R: BufRead> BimReader<R, String> {
+    /// Resolves contig names through `aliases` (see also [chromosome_name]),
+    /// unknown contigs are reported as errors.
+    pub fn with_aliases<C: Clone>(
+        self,
+        aliases: &ContigAliases<C>,
+    ) -> impl Iterator<Item = io::Result<BimRecord<C>>> + use<'_, R, C> {
+        self.map(|record| {
+            let record = record?;
+            let name = &record.at.contig;
+            let contig = aliases
+                .resolve(name)
+                .or_else(|_| aliases.resolve(chromosome_name(name)))?;
+            Ok(record.map_contig(|_| contig))
+        })
+    }
+}
+impl<R, C> Iterator for BimReader<R, C>
+where
+    R: BufRead,
+    C: FromStr,
+{
+    type Item = io::Result<BimRecord<C>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.buf.clear();
+            match self.reader.read_line(&mut self.buf) {
+                Ok(0) => return None,
+                Ok(_) => {}
+                Err(e) => return Some(Err(e)),
+            }
+            self.line += 1;
+
+            if self.buf.trim().is_empty() {
+                continue;
+            }
+            return Some(parse_record(&self.buf, self.line).map_err(Into::into));
+        }
+    }
+}
+
+/// Translates PLINK's numeric codes for non-autosomes (`23` to `26`).
+///
+/// `XY` (the pseudo-autosomal regions) is reported as `X`.
+pub fn chromosome_name(code: &str) -> &str {
+    match code {
+        "23" | "25" | "XY" => "X",
+        "24" => "Y",
+        "26" | "M" => "MT",
+        code => code,
+    }
+}
+
+fn parse_record<C: FromStr>(line: &str, line_number: usize) -> Result<BimRecord<C>, PlinkError> {
+    let columns: Vec<&str> = line.split_ascii_whitespace().collect();
+    let [contig, id, centimorgans, position, allele_1, allele_2] = columns[..] else {
+        return Err(PlinkError::ColumnCount {
+            line: line_number,
+            expected: 6,
+            found: columns.len(),
+        });
+    };
+
+    let invalid = |column: usize| PlinkError::InvalidField {
+        line: line_number,
+        column: column + 1,
+        value: columns[column].to_owned(),
+    };
+
+    let contig = C::from_str(contig)
+        .or_else(|_| C::from_str(chromosome_name(contig)))
+        .map_err(|_| PlinkError::InvalidContig {
+            line: line_number,
+            value: contig.to_owned(),
+        })?;
+    let position: u64 = position.parse().map_err(|_| invalid(3))?;
+    if position == 0 {
+        return Err(invalid(3));
+    }
+
+    Ok(BimRecord {
+        at: ContigPosition {
+            contig,
+            at: position - 1,
+        },
+        id: (id != ".").then(|| id.to_owned()),
+        centimorgans: centimorgans.parse().map_err(|_| invalid(2))?,
+        allele_1: allele_1.to_owned(),
+        allele_2: allele_2.to_owned(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use biocore::genome::alias::ContigAliases;
+
+    use super::BimReader;
+
+    #[test]
+    fn test_aliases() {
+        let bim = "1\trs1\t0\t100\tA\tG\n23\t.\t0.5\t200\tT\tC\n";
+        let aliases = ContigAliases::human_grch38(["chr1", "chrX"]);
+
+        let records = BimReader::new(Cursor::new(bim))
+            .with_aliases(&aliases)
+            .try_collect::<Vec<_>>()
+            .unwrap();
+
+        assert_eq!(records[0].at.contig, "chr1");
+        assert_eq!(records[0].at.at, 99);
+        assert_eq!(records[0].id.as_deref(), Some("rs1"));
+        assert_eq!(records[1].at.contig, "chrX");
+        assert_eq!(records[1].id, None);
+        assert_eq!(records[1].allele_1, "T");
+    }
+}
@@ -0,0 +1,131 @@
+//! `.fam` sample files: `FID IID father mother sex phenotype`.
+
+use std::io::{self, BufRead};
+
+use genomes1000::pedigree::Sex;
+
+use crate::PlinkError;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FamRecord {
+    pub family_id: String,
+    pub id: String,
+    /// [None] if `0` (not in the dataset).
+    pub father_id: Option<String>,
+    /// [None] if `0` (not in the dataset).
+    pub mother_id: Option<String>,
+    /// [None] if unknown (`0`).
+    pub sex: Option<Sex>,
+    /// Kept as text, as it can be a case/control code or a quantitative value.
+    /// [None] if missing (`-9` or `0`).
+    pub phenotype: Option<String>,
+}
+
+pub struct FamReader<R> {
+    reader: R,
+    buf: String,
+    line: usize,
+}
+
+impl<R: BufRead> FamReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buf: String::new(),
+            line: 0,
+        }
+    }
+}
+impl<R: BufRead> Iterator for FamReader<R> {
+    type Item = io::Result<FamRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.buf.clear();
+            match self.reader.read_line(&mut self.buf) {
+                Ok(0) => return None,
+                Ok(_) => {}
+                Err(e) => return Some(Err(e)),
+            }
+            self.line += 1;
+
+            if self.buf.trim().is_empty() {
+                continue;
+            }
+            return Some(parse_record(&self.buf, self.line).map_err(Into::into));
+        }
+    }
+}
+
+fn parse_record(line: &str, line_number: usize) -> Result<FamRecord, PlinkError> {
+    let columns: Vec<&str> = line.split_ascii_whitespace().collect();
+    let [family_id, id, father_id, mother_id, sex, phenotype] = columns[..] else {
+        return Err(PlinkError::ColumnCount {
+            line: line_number,
+            expected: 6,
+            found: columns.len(),
+        });
+    };
+
+    let parent = |id: &str| (id != "0").then(|| id.to_owned());
+    let sex = match sex {
+        "1" => Some(Sex::Male),
+        "2" => Some(Sex::Female),
+        "0" | "-9" => None,
+        _ => {
+            return Err(PlinkError::InvalidField {
+                line: line_number,
+                column: 5,
+                value: sex.to_owned(),
+            });
+        }
+    };
+
+    Ok(FamRecord {
+        family_id: family_id.to_owned(),
+        id: id.to_owned(),
+        father_id: parent(father_id),
+        mother_id: parent(mother_id),
+        sex,
+        phenotype: (!matches!(phenotype, "-9" | "0")).then(|| phenotype.to_owned()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use genomes1000::pedigree::Sex;
+
+    use super::{FamReader, FamRecord};
+
+    #[test]
+    fn test_parse() {
+        let fam = "FAM1 NA1 0 0 1 -9\nFAM1 NA2 NA1 NA3 2 1.5\n\nFAM2 NA4 0 0 0 2\n";
+        let records = FamReader::new(Cursor::new(fam))
+            .try_collect::<Vec<_>>()
+            .unwrap();
+
+        assert_eq!(records.len(), 3);
+        assert_eq!(
+            records[1],
+            FamRecord {
+                family_id: "FAM1".to_owned(),
+                id: "NA2".to_owned(),
+                father_id: Some("NA1".to_owned()),
+                mother_id: Some("NA3".to_owned()),
+                sex: Some(Sex::Female),
+                phenotype: Some("1.5".to_owned()),
+            }
+        );
+        assert_eq!(records[0].father_id, None);
+        assert_eq!(records[0].sex, Some(Sex::Male));
+        assert_eq!(records[0].phenotype, None);
+        assert_eq!(records[2].sex, None);
+
+        let mut reader = FamReader::new(Cursor::new("FAM1 NA1 0 0 1\n"));
+        assert!(reader.next().unwrap().is_err());
+        let mut reader = FamReader::new(Cursor::new("FAM1 NA1 0 0 X -9\n"));
+        assert!(reader.next().unwrap().is_err());
+    }
+}
@@ -0,0 +1,336 @@
+//! PLINK 1 binary filesets (`.bed`, `.bim`, `.fam`) and PLINK 2 filesets (`.pgen`, `.pvar`, `.psam`).
+//!
+//! PLINK 1 genotypes are exposed as ALT dosages (copies of the `.bim` A1 allele), or as
+//! [genomes1000::Genotype]s with the A2 allele as reference (`0`) and A1 as alternate (`1`).
+//! PLINK 2 dosages count copies of the (first) `.pvar` ALT allele.
+//! Both can be loaded into a [DosageMatrix], with rows keyed by variant index.
+
+#![feature(iterator_try_collect)]
+
+pub mod bed;
+pub mod bim;
+pub mod fam;
+pub mod pgen;
+pub mod psam;
+pub mod pvar;
+
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, Read, Seek},
+    path::Path,
+    str::FromStr,
+};
+
+use biocore::{genome::alias::ContigAliases, location::ContigRange};
+use genomes1000::{Genotype, matrix::Dosage};
+
+use self::{
+    bed::BedReader, bim::BimRecord, fam::FamRecord, pgen::PgenReader, psam::PsamRecord,
+    pvar::PvarRecord,
+};
+
+pub use genomes1000::matrix::DosageMatrix;
+
+/// A `.bed` file with its `.bim` variants and `.fam` samples, kept in memory.
+#[derive(Debug)]
+pub struct Plink1<R, C = String> {
+    samples: Vec<FamRecord>,
+    variants: Vec<BimRecord<C>>,
+    bed: BedReader<R>,
+}
+
+/// A `.pgen` file with its `.pvar` variants and `.psam` samples, kept in memory.
+#[derive(Debug)]
+pub struct Plink2<R, C = String> {
+    samples: Vec<PsamRecord>,
+    variants: Vec<PvarRecord<C>>,
+    pgen: PgenReader<R>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum PlinkError {
+    #[error("Not a PLINK .bed/.pgen file (wrong magic number).")]
+    InvalidMagic,
+    #[error("Unsupported storage mode {0:#04x}.")]
+    UnsupportedMode(u8),
+    #[error("Missing header line.")]
+    MissingHeader,
+    #[error("Line {line}: missing column {column}.")]
+    MissingColumn { line: usize, column: &'static str },
+    #[error("Line {line}: expected {expected} columns, found {found}.")]
+    ColumnCount {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    #[error("Line {line}: invalid contig {value:?}.")]
+    InvalidContig { line: usize, value: String },
+    #[error("Line {line}: invalid value {value:?} in column {column}.")]
+    InvalidField {
+        line: usize,
+        column: usize,
+        value: String,
+    },
+    #[error("Variant {0} is out of bounds.")]
+    VariantOutOfBounds(usize),
+    #[error("Variant {variant}: unsupported .pgen record type {vrtype:#04x}.")]
+    UnsupportedRecord { variant: usize, vrtype: u8 },
+    #[error("Variant {0}: truncated or malformed .pgen record.")]
+    InvalidRecord(usize),
+}
+impl From<PlinkError> for io::Error {
+    fn from(e: PlinkError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, e)
+    }
+}
+
+impl<C: FromStr> Plink1<BufReader<File>, C> {
+    /// Opens `{prefix}.bed`, `{prefix}.bim` and `{prefix}.fam`.
+    pub fn open(prefix: impl AsRef<Path>) -> io::Result<Self> {
+        let open = |extension| open_with_extension(prefix.as_ref(), extension);
+        Self::new(
+            BufReader::new(open("bed")?),
+            BufReader::new(open("bim")?),
+            BufReader::new(open("fam")?),
+        )
+    }
+}
+impl<R: Read, C: FromStr> Plink1<R, C> {
+    pub fn new(bed: R, bim: impl BufRead, fam: impl BufRead) -> io::Result<Self> {
+        let samples = fam::FamReader::new(fam).try_collect::<Vec<_>>()?;
+        let variants = bim::BimReader::new(bim).try_collect::<Vec<_>>()?;
+        let bed = BedReader::new(bed, samples.len())?;
+        Ok(Self {
+            samples,
+            variants,
+            bed,
+        })
+    }
+}
+impl<R: Read, C: Clone> Plink1<R, C> {
+    /// Resolves contigs through `aliases`, see [bim::BimReader::with_aliases].
+    pub fn new_with_aliases(
+        bed: R,
+        bim: impl BufRead,
+        fam: impl BufRead,
+        aliases: &ContigAliases<C>,
+    ) -> io::Result<Self> {
+        let samples = fam::FamReader::new(fam).try_collect::<Vec<_>>()?;
+        let variants = bim::BimReader::new(bim)
+            .with_aliases(aliases)
+            .try_collect::<Vec<_>>()?;
+        let bed = BedReader::new(bed, samples.len())?;
+        Ok(Self {
+            samples,
+            variants,
+            bed,
+        })
+    }
+}
+impl<R, C> Plink1<R, C> {
+    pub fn samples(&self) -> &[FamRecord] {
+        &self.samples
+    }
+    pub fn variants(&self) -> &[BimRecord<C>] {
+        &self.variants
+    }
+}
+impl<R: Read, C> Plink1<R, C> {
+    /// All variants in file order, with a dosage per sample ([None] if missing).
+    ///
+    /// Reads from the current position of the `.bed` file, so call it on a fresh reader.
+    pub fn dosages(
+        &mut self,
+    ) -> impl Iterator<Item = io::Result<(&BimRecord<C>, Vec<Option<u8>>)>> + '_ {
+        let Self { variants, bed, .. } = self;
+        let variants = &*variants;
+        variants
+            .iter()
+            .map(move |variant| Ok((variant, bed.read_dosages()?.ok_or_else(truncated)?)))
+    }
+}
+impl<R: Read + Seek, C> Plink1<R, C> {
+    /// The dosage of each sample at a variant (by index in [Self::variants]).
+    pub fn variant_dosages(&mut self, variant: usize) -> io::Result<Vec<Option<u8>>> {
+        if variant >= self.variants.len() {
+            return Err(PlinkError::VariantOutOfBounds(variant).into());
+        }
+        self.bed.seek_variant(variant)?;
+        self.bed.read_dosages()?.ok_or_else(truncated)
+    }
+    pub fn variant_genotypes(&mut self, variant: usize) -> io::Result<Vec<Genotype>> {
+        let dosages = self.variant_dosages(variant)?;
+        Ok(dosages.into_iter().map(bed::genotype).collect())
+    }
+    /// Variants in the range, with their dosages.
+    pub fn query(
+        &mut self,
+        range: &ContigRange<C>,
+    ) -> io::Result<Vec<(&BimRecord<C>, Vec<Option<u8>>)>>
+    where
+        C: PartialEq,
+    {
+        let indices: Vec<usize> = (0..self.variants.len())
+            .filter(|&i| range.contains(&self.variants[i].at))
+            .collect();
+        let mut dosages = Vec::with_capacity(indices.len());
+        for &i in &indices {
+            dosages.push(self.variant_dosages(i)?);
+        }
+        Ok(indices
+            .into_iter()
+            .map(|i| &self.variants[i])
+            .zip(dosages)
+            .collect())
+    }
+    /// The dosages of the given variants (by index in [Self::variants]), in order.
+    ///
+    /// The columns are all samples, in the order of [Self::samples].
+    pub fn dosage_matrix<T: Dosage>(
+        &mut self,
+        variants: impl IntoIterator<Item = usize>,
+    ) -> io::Result<DosageMatrix<T, usize>> {
+        let mut matrix = DosageMatrix::new((0..self.samples.len()).collect());
+        for variant in variants {
+            let dosages = self.variant_dosages(variant)?;
+            let row = dosages
+                .into_iter()
+                .map(|d| d.map_or(T::MISSING, T::from_dosage));
+            matrix.push_row(variant, row);
+        }
+        Ok(matrix)
+    }
+}
+
+impl<C: FromStr> Plink2<BufReader<File>, C> {
+    /// Opens `{prefix}.pgen`, `{prefix}.pvar` and `{prefix}.psam`.
+    pub fn open(prefix: impl AsRef<Path>) -> io::Result<Self> {
+        let open = |extension| open_with_extension(prefix.as_ref(), extension);
+        Self::new(
+            BufReader::new(open("pgen")?),
+            BufReader::new(open("pvar")?),
+            BufReader::new(open("psam")?),
+        )
+    }
+}
+impl<R: Read, C: FromStr> Plink2<R, C> {
+    pub fn new(pgen: R, pvar: impl BufRead, psam: impl BufRead) -> io::Result<Self> {
+        let samples = psam::PsamReader::new(psam).try_collect::<Vec<_>>()?;
+        let variants = pvar::PvarReader::new(pvar).try_collect::<Vec<_>>()?;
+        Self::from_parts(pgen, samples, variants)
+    }
+}
+impl<R: Read, C: Clone> Plink2<R, C> {
+    /// Resolves contigs through `aliases`, see [pvar::PvarReader::with_aliases].
+    pub fn new_with_aliases(
+        pgen: R,
+        pvar: impl BufRead,
+        psam: impl BufRead,
+        aliases: &ContigAliases<C>,
+    ) -> io::Result<Self> {
+        let samples = psam::PsamReader::new(psam).try_collect::<Vec<_>>()?;
+        let variants = pvar::PvarReader::new(pvar)
+            .with_aliases(aliases)
+            .try_collect::<Vec<_>>()?;
+        Self::from_parts(pgen, samples, variants)
+    }
+}
+impl<R: Read, C> Plink2<R, C> {
+    fn from_parts(
+        pgen: R,
+        samples: Vec<PsamRecord>,
+        variants: Vec<PvarRecord<C>>,
+    ) -> io::Result<Self> {
+        let pgen = PgenReader::new(pgen, samples.len())?;
+        if pgen.samples() != samples.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "The .pgen file has {} samples, but the .psam file has {}.",
+                    pgen.samples(),
+                    samples.len()
+                ),
+            ));
+        }
+        Ok(Self {
+            samples,
+            variants,
+            pgen,
+        })
+    }
+}
+impl<R, C> Plink2<R, C> {
+    pub fn samples(&self) -> &[PsamRecord] {
+        &self.samples
+    }
+    pub fn variants(&self) -> &[PvarRecord<C>] {
+        &self.variants
+    }
+    /// Whether the `.pgen` file stores dosages, rather than only hardcalls.
+    pub fn has_dosages(&self) -> bool {
+        self.pgen.has_dosages()
+    }
+}
+impl<R: Read + Seek, C> Plink2<R, C> {
+    /// The ALT dosage of each sample at a variant (by index in [Self::variants]).
+    pub fn variant_dosages(&mut self, variant: usize) -> io::Result<Vec<Option<f32>>> {
+        if variant >= self.variants.len() {
+            return Err(PlinkError::VariantOutOfBounds(variant).into());
+        }
+        self.pgen.seek_variant(variant)?;
+        self.pgen.read_dosages()?.ok_or_else(truncated)
+    }
+    /// Variants in the range, with their dosages.
+    pub fn query(
+        &mut self,
+        range: &ContigRange<C>,
+    ) -> io::Result<Vec<(&PvarRecord<C>, Vec<Option<f32>>)>>
+    where
+        C: PartialEq,
+    {
+        let indices: Vec<usize> = (0..self.variants.len())
+            .filter(|&i| range.contains(&self.variants[i].at))
+            .collect();
+        let mut dosages = Vec::with_capacity(indices.len());
+        for &i in &indices {
+            dosages.push(self.variant_dosages(i)?);
+        }
+        Ok(indices
+            .into_iter()
+            .map(|i| &self.variants[i])
+            .zip(dosages)
+            .collect())
+    }
+    /// The dosages of the given variants (by index in [Self::variants]), in order.
+    ///
+    /// The columns are all samples, in the order of [Self::samples].
+    pub fn dosage_matrix(
+        &mut self,
+        variants: impl IntoIterator<Item = usize>,
+    ) -> io::Result<DosageMatrix<f32, usize>> {
+        let mut matrix = DosageMatrix::new((0..self.samples.len()).collect());
+        for variant in variants {
+            let dosages = self.variant_dosages(variant)?;
+            matrix.push_row(
+                variant,
+                dosages
+                    .into_iter()
+                    .map(|d| d.unwrap_or(<f32 as Dosage>::MISSING)),
+            );
+        }
+        Ok(matrix)
+    }
+}
+
+fn open_with_extension(prefix: &Path, extension: &str) -> io::Result<File> {
+    let mut path = prefix.as_os_str().to_owned();
+    path.push(format!(".{extension}"));
+    File::open(path)
+}
+
+fn truncated() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::UnexpectedEof,
+        "The genotype file has fewer variants than the variant file.",
+    )
+}
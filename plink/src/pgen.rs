@@ -0,0 +1,728 @@
+//! PLINK 2 `.pgen` genotype matrices.
+//!
+//! Supports the fixed-width storage modes (PLINK 1 `.bed` layout `0x01`, hardcalls `0x02` and
+//! hardcalls with dosages `0x03`) and the variable-width mode `0x10` written by `--make-pgen`,
+//! including LD-compressed records and dosage tracks.
+//! Multiallelic records (see [PlinkError::UnsupportedRecord]) and files with a separate `.pgi`
+//! index (mode `0x11`) are not supported.
+
+use std::io::{self, Read, Seek, SeekFrom};
+
+use utile::num::TryU64;
+
+use crate::{PlinkError, bed};
+
+const BED: u8 = 0x01;
+const HARDCALLS: u8 = 0x02;
+const DOSAGES: u8 = 0x03;
+const VARIABLE: u8 = 0x10;
+
+/// Dosages are stored as `u16`s, where this is one copy of the ALT allele.
+const DOSAGE_SCALE: f32 = 16384.0;
+const MISSING_DOSAGE: u16 = 0xFFFF;
+const MISSING_HARDCALL: u8 = 3;
+
+/// Variable-width files index their records in blocks of this many variants.
+const BLOCK: usize = 1 << 16;
+
+#[derive(Debug)]
+pub struct PgenReader<R> {
+    reader: R,
+    mode: u8,
+    variants: Option<usize>,
+    samples: usize,
+    /// The records of variable-width files.
+    index: Vec<RecordIndex>,
+    /// The next variant of variable-width files, and the position of the reader.
+    next: usize,
+    position: u64,
+    buf: Vec<u8>,
+    /// The 2-bit hardcall of each sample in the last record.
+    hardcalls: Vec<u8>,
+    /// The explicit dosage of each sample in the last record, if any.
+    dosages: Vec<Option<u16>>,
+    /// The last record without LD compression, and its hardcalls.
+    ldbase: Option<(usize, Vec<u8>)>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct RecordIndex {
+    position: u64,
+    len: usize,
+    vrtype: u8,
+    /// The record the LD-compressed ones are stored against.
+    ldbase: Option<usize>,
+}
+
+impl<R: Read> PgenReader<R> {
+    /// `samples` is only used by `.bed`-layout files, the other modes store it in the header.
+    pub fn new(mut reader: R, samples: usize) -> io::Result<Self> {
+        let mut header = [0; 3];
+        reader.read_exact(&mut header)?;
+        if header[..2] != bed::MAGIC {
+            return Err(PlinkError::InvalidMagic.into());
+        }
+
+        let mode = header[2];
+        let (variants, samples) = match mode {
+            BED => (None, samples),
+            HARDCALLS | DOSAGES | VARIABLE => {
+                let mut counts = [0; 8];
+                reader.read_exact(&mut counts)?;
+                let count = |bytes: &[u8]| {
+                    usize::try_from(u32::from_le_bytes(bytes.try_into().unwrap())).unwrap()
+                };
+                (Some(count(&counts[..4])), count(&counts[4..]))
+            }
+            mode => return Err(PlinkError::UnsupportedMode(mode).into()),
+        };
+
+        let (index, position) = match mode {
+            VARIABLE => read_index(&mut reader, variants.unwrap())?,
+            BED => (vec![], 3),
+            _ => (vec![], 11),
+        };
+
+        let hardcalls = samples.div_ceil(4);
+        let record = match mode {
+            DOSAGES => hardcalls + 2 * samples,
+            _ => hardcalls,
+        };
+        Ok(Self {
+            reader,
+            mode,
+            variants,
+            samples,
+            index,
+            next: 0,
+            position,
+            buf: vec![0; record],
+            hardcalls: vec![],
+            dosages: vec![],
+            ldbase: None,
+        })
+    }
+
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+    /// [None] for `.bed`-layout files, which don't store it.
+    pub fn variants(&self) -> Option<usize> {
+        self.variants
+    }
+    /// Whether any record has dosages, rather than only hardcalls.
+    pub fn has_dosages(&self) -> bool {
+        match self.mode {
+            VARIABLE => self.index.iter().any(|r| r.vrtype & 0x60 != 0),
+            mode => mode == DOSAGES,
+        }
+    }
+
+    /// Reads the next variant as ALT dosages (`0.0..=2.0`), [None] at the end of the file.
+    ///
+    /// Samples without a dosage get their hardcall.
+    pub fn read_dosages(&mut self) -> io::Result<Option<Vec<Option<f32>>>> {
+        if !self.read_record()? {
+            return Ok(None);
+        }
+        let dosages = self
+            .hardcalls
+            .iter()
+            .zip(&self.dosages)
+            .map(|(&code, dosage)| match *dosage {
+                Some(MISSING_DOSAGE) => None,
+                Some(dosage) => Some(f32::from(dosage) / DOSAGE_SCALE),
+                None => hardcall(code).map(f32::from),
+            })
+            .collect();
+        Ok(Some(dosages))
+    }
+    /// Reads the next variant as hardcall ALT counts, [None] at the end of the file.
+    pub fn read_hardcalls(&mut self) -> io::Result<Option<Vec<Option<u8>>>> {
+        if !self.read_record()? {
+            return Ok(None);
+        }
+        Ok(Some(self.hardcalls.iter().map(|&c| hardcall(c)).collect()))
+    }
+
+    fn read_record(&mut self) -> io::Result<bool> {
+        if self.mode == VARIABLE {
+            return self.read_variable_record();
+        }
+
+        match self.reader.read_exact(&mut self.buf) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(false),
+            Err(e) => return Err(e),
+        }
+        self.hardcalls = (0..self.samples)
+            .map(|i| {
+                let code = two_bits(&self.buf, i);
+                match self.mode {
+                    BED => bed::dosage(code).unwrap_or(MISSING_HARDCALL),
+                    _ => code,
+                }
+            })
+            .collect();
+        self.dosages = match self.mode {
+            DOSAGES => self.buf[self.samples.div_ceil(4)..]
+                .chunks_exact(2)
+                .map(|bytes| Some(u16::from_le_bytes([bytes[0], bytes[1]])))
+                .collect(),
+            _ => vec![None; self.samples],
+        };
+        Ok(true)
+    }
+    fn read_variable_record(&mut self) -> io::Result<bool> {
+        let variant = self.next;
+        let Some(&record) = self.index.get(variant) else {
+            return Ok(false);
+        };
+        // Records follow the index, with nothing in between in files written by PLINK.
+        let Some(gap) = record.position.checked_sub(self.position) else {
+            return Err(PlinkError::InvalidRecord(variant).into());
+        };
+        io::copy(&mut (&mut self.reader).take(gap), &mut io::sink())?;
+        self.buf.resize(record.len, 0);
+        self.reader.read_exact(&mut self.buf)?;
+        self.position = record.position + record.len.u64_unwrap();
+        self.next += 1;
+
+        let ldbase = match (record.ldbase, &self.ldbase) {
+            (None, _) => None,
+            (Some(base), Some((loaded, hardcalls))) if base == *loaded => Some(&hardcalls[..]),
+            (Some(_), _) => return Err(PlinkError::InvalidRecord(variant).into()),
+        };
+        let (hardcalls, dosages) = decode_record(&self.buf, record.vrtype, self.samples, ldbase)
+            .map_err(|e| match e {
+                DecodeError::Invalid => PlinkError::InvalidRecord(variant),
+                DecodeError::Unsupported => PlinkError::UnsupportedRecord {
+                    variant,
+                    vrtype: record.vrtype,
+                },
+            })?;
+        if record.ldbase.is_none() {
+            self.ldbase = Some((variant, hardcalls.clone()));
+        }
+        self.hardcalls = hardcalls;
+        self.dosages = dosages;
+        Ok(true)
+    }
+}
+impl<R: Read + Seek> PgenReader<R> {
+    /// Moves to the start of the given variant.
+    pub fn seek_variant(&mut self, variant: usize) -> io::Result<()> {
+        if self.mode != VARIABLE {
+            let header = match self.mode {
+                BED => 3,
+                _ => 11,
+            };
+            let offset = header + (variant * self.buf.len()).u64_unwrap();
+            self.reader.seek(SeekFrom::Start(offset))?;
+            return Ok(());
+        }
+
+        let Some(record) = self.index.get(variant) else {
+            self.next = variant;
+            return Ok(());
+        };
+        // LD-compressed records need the record they were compressed against.
+        if let Some(base) = record.ldbase
+            && self
+                .ldbase
+                .as_ref()
+                .is_none_or(|(loaded, _)| *loaded != base)
+        {
+            self.seek_record(base)?;
+            self.read_variable_record()?;
+        }
+        self.seek_record(variant)
+    }
+    fn seek_record(&mut self, variant: usize) -> io::Result<()> {
+        let position = self.index[variant].position;
+        self.reader.seek(SeekFrom::Start(position))?;
+        self.position = position;
+        self.next = variant;
+        Ok(())
+    }
+}
+
+/// Decodes a 2-bit `.pgen` hardcall (hom REF, het, hom ALT, missing) into an ALT count.
+pub fn hardcall(code: u8) -> Option<u8> {
+    match code & 0b11 {
+        MISSING_HARDCALL => None,
+        count => Some(count),
+    }
+}
+
+/// Reads the record types and lengths following the header of a variable-width file,
+/// returning them with the position after the index.
+fn read_index(reader: &mut impl Read, variants: usize) -> io::Result<(Vec<RecordIndex>, u64)> {
+    let control = read_bytes(reader, 1)?[0];
+    // The low bits give the size of the record types (4 or 8 bits) and lengths (1-4 bytes).
+    let (wide_types, len_bytes) = match control & 0x0F {
+        c @ 0..=3 => (false, usize::from(c) + 1),
+        c @ 8..=11 => (true, usize::from(c) - 7),
+        _ => return Err(PlinkError::UnsupportedMode(VARIABLE).into()),
+    };
+    let allele_count_bytes = usize::from((control >> 4) & 0b11);
+    let explicit_nonref_flags = control >> 6 == 3;
+
+    let blocks = variants.div_ceil(BLOCK);
+    let starts: Vec<u64> = read_bytes(reader, 8 * blocks)?
+        .chunks_exact(8)
+        .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+        .collect();
+    let mut position = 12 + 8 * blocks;
+
+    let mut index = Vec::with_capacity(variants);
+    let mut ldbase = None;
+    for (block, start) in starts.into_iter().enumerate() {
+        let count = BLOCK.min(variants - block * BLOCK);
+        let types = match wide_types {
+            true => read_bytes(reader, count)?,
+            false => {
+                let packed = read_bytes(reader, count.div_ceil(2))?;
+                position += packed.len();
+                (0..count)
+                    .map(|i| (packed[i / 2] >> (4 * (i % 2))) & 0x0F)
+                    .collect()
+            }
+        };
+        if wide_types {
+            position += count;
+        }
+        let lens = read_bytes(reader, count * len_bytes)?;
+        // ALT allele counts and provisional REF flags, not needed for biallelic genotypes.
+        let skipped = count * allele_count_bytes
+            + if explicit_nonref_flags {
+                count.div_ceil(8)
+            } else {
+                0
+            };
+        read_bytes(reader, skipped)?;
+        position += lens.len() + skipped;
+
+        let mut record_position = start;
+        for (i, (vrtype, len)) in types
+            .into_iter()
+            .zip(lens.chunks_exact(len_bytes))
+            .enumerate()
+        {
+            let len = len
+                .iter()
+                .rev()
+                .fold(0, |len, &byte| (len << 8) | usize::from(byte));
+            let is_ld = matches!(vrtype & 0b111, 2 | 3);
+            if !is_ld {
+                ldbase = Some(block * BLOCK + i);
+            }
+            index.push(RecordIndex {
+                position: record_position,
+                len,
+                vrtype,
+                ldbase: if is_ld { ldbase } else { None },
+            });
+            record_position += len.u64_unwrap();
+        }
+    }
+    Ok((index, position.u64_unwrap()))
+}
+fn read_bytes(reader: &mut impl Read, len: usize) -> io::Result<Vec<u8>> {
+    let mut buf = vec![0; len];
+    reader.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+enum DecodeError {
+    Invalid,
+    Unsupported,
+}
+
+/// Decodes the hardcalls and explicit dosages of a variable-width record.
+fn decode_record(
+    record: &[u8],
+    vrtype: u8,
+    samples: usize,
+    ldbase: Option<&[u8]>,
+) -> Result<(Vec<u8>, Vec<Option<u16>>), DecodeError> {
+    // Multiallelic hardcalls.
+    if vrtype & 0x08 != 0 {
+        return Err(DecodeError::Unsupported);
+    }
+    let mut bytes = Bytes(record);
+
+    let hardcalls = match vrtype & 0b111 {
+        0 => {
+            let genotypes = bytes.take(samples.div_ceil(4))?;
+            (0..samples).map(|i| two_bits(genotypes, i)).collect()
+        }
+        // The two most common hardcalls as a bitarray, the others as a list.
+        1 => {
+            let code = bytes.u8()?;
+            let (base, delta) = (code / 4, code & 0b11);
+            if base + delta > MISSING_HARDCALL {
+                return Err(DecodeError::Invalid);
+            }
+            let bits = bytes.take(samples.div_ceil(8))?;
+            let mut hardcalls: Vec<u8> = (0..samples)
+                .map(|i| base + delta * ((bits[i / 8] >> (i % 8)) & 1))
+                .collect();
+            bytes.apply_difflist(&mut hardcalls)?;
+            hardcalls
+        }
+        // Differences from the LD base, inverted (REF and ALT swapped) for 3.
+        ld @ (2 | 3) => {
+            let mut hardcalls = ldbase.ok_or(DecodeError::Invalid)?.to_vec();
+            bytes.apply_difflist(&mut hardcalls)?;
+            if ld == 3 {
+                for code in &mut hardcalls {
+                    if *code != 1 && *code != MISSING_HARDCALL {
+                        *code = 2 - *code;
+                    }
+                }
+            }
+            hardcalls
+        }
+        // Differences from all hom REF (4) or all missing (7).
+        sparse @ (4 | 7) => {
+            let base = if sparse == 4 { 0 } else { MISSING_HARDCALL };
+            let mut hardcalls = vec![base; samples];
+            bytes.apply_difflist(&mut hardcalls)?;
+            hardcalls
+        }
+        _ => return Err(DecodeError::Unsupported),
+    };
+
+    // Phase of the hets, a flag for whether all are phased, then one or two bitarrays.
+    if vrtype & 0x10 != 0 {
+        let hets = hardcalls.iter().filter(|&&c| c == 1).count();
+        let flags = bytes.take(1 + hets / 8)?;
+        if flags[0] & 1 == 1 {
+            let phased: u32 = flags.iter().map(|b| b.count_ones()).sum::<u32>() - 1;
+            bytes.take(usize::try_from(phased).unwrap().div_ceil(8))?;
+        }
+    }
+
+    let mut dosages = vec![None; samples];
+    match vrtype & 0x60 {
+        0 => {}
+        // A dosage for every sample, where missing means the hardcall is used.
+        0x40 => {
+            for dosage in &mut dosages {
+                *dosage = Some(bytes.u16()?).filter(|&d| d != MISSING_DOSAGE);
+            }
+        }
+        // The samples with a dosage as a list (0x20) or bitarray (0x60), then their dosages.
+        list => {
+            let with_dosage: Vec<usize> = match list {
+                0x20 => bytes
+                    .difflist(samples, false)?
+                    .into_iter()
+                    .map(|(i, _)| i)
+                    .collect(),
+                _ => {
+                    let bits = bytes.take(samples.div_ceil(8))?;
+                    (0..samples)
+                        .filter(|i| (bits[i / 8] >> (i % 8)) & 1 == 1)
+                        .collect()
+                }
+            };
+            for sample in with_dosage {
+                dosages[sample] = Some(bytes.u16()?);
+            }
+        }
+    }
+
+    Ok((hardcalls, dosages))
+}
+
+struct Bytes<'a>(&'a [u8]);
+impl<'a> Bytes<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+        let (taken, rest) = self.0.split_at_checked(len).ok_or(DecodeError::Invalid)?;
+        self.0 = rest;
+        Ok(taken)
+    }
+    fn u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.take(1)?[0])
+    }
+    fn u16(&mut self) -> Result<u16, DecodeError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+    /// Little-endian, `len` bytes.
+    fn uint(&mut self, len: usize) -> Result<usize, DecodeError> {
+        let bytes = self.take(len)?;
+        Ok(bytes
+            .iter()
+            .rev()
+            .fold(0, |value, &byte| (value << 8) | usize::from(byte)))
+    }
+    /// LEB128.
+    fn varint(&mut self) -> Result<usize, DecodeError> {
+        let mut value = 0;
+        for shift in (0..32).step_by(7) {
+            let byte = self.u8()?;
+            value |= usize::from(byte & 0x7F) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(DecodeError::Invalid)
+    }
+
+    /// A sorted list of samples, with a 2-bit hardcall each if `with_hardcalls`.
+    ///
+    /// Samples are in groups of 64, stored as the first sample of each group, the size of each
+    /// group but the last (only needed for random access), the hardcalls, and then the
+    /// differences from the previous sample within each group.
+    fn difflist(
+        &mut self,
+        samples: usize,
+        with_hardcalls: bool,
+    ) -> Result<Vec<(usize, u8)>, DecodeError> {
+        let len = self.varint()?;
+        if len == 0 {
+            return Ok(vec![]);
+        }
+        if len > samples {
+            return Err(DecodeError::Invalid);
+        }
+        let groups = len.div_ceil(64);
+        let id_bytes = usize::try_from(usize::BITS - samples.leading_zeros())
+            .unwrap()
+            .div_ceil(8);
+        let starts: Vec<usize> = (0..groups).map(|_| self.uint(id_bytes)).try_collect()?;
+        self.take(groups - 1)?;
+        let hardcalls = match with_hardcalls {
+            true => Some(self.take(len.div_ceil(4))?),
+            false => None,
+        };
+
+        let mut list = Vec::with_capacity(len);
+        for (group, start) in starts.into_iter().enumerate() {
+            let mut sample = start;
+            for i in group * 64..len.min((group + 1) * 64) {
+                if i != group * 64 {
+                    sample += self.varint()?;
+                }
+                if sample >= samples {
+                    return Err(DecodeError::Invalid);
+                }
+                list.push((sample, hardcalls.map_or(0, |h| two_bits(h, i))));
+            }
+        }
+        Ok(list)
+    }
+    fn apply_difflist(&mut self, hardcalls: &mut [u8]) -> Result<(), DecodeError> {
+        for (sample, hardcall) in self.difflist(hardcalls.len(), true)? {
+            hardcalls[sample] = hardcall;
+        }
+        Ok(())
+    }
+}
+
+fn two_bits(bytes: &[u8], i: usize) -> u8 {
+    (bytes[i / 4] >> (2 * (i % 4))) & 0b11
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Cursor};
+
+    use super::{PgenReader, PlinkError};
+
+    #[test]
+    fn test_fixed_width_dosages() {
+        let mut pgen = vec![0x6c, 0x1b, 0x03];
+        pgen.extend(2u32.to_le_bytes()); // Variants.
+        pgen.extend(3u32.to_le_bytes()); // Samples.
+        for (hardcalls, dosages) in [
+            (0b10_01_00, [0, 16384, 32768]),
+            (0b11_00_01, [8192, 0, 0xFFFF]),
+        ] {
+            pgen.push(hardcalls);
+            for dosage in dosages {
+                pgen.extend(u16::to_le_bytes(dosage));
+            }
+        }
+
+        let mut reader = PgenReader::new(Cursor::new(pgen), 0).unwrap();
+        assert_eq!((reader.variants(), reader.samples()), (Some(2), 3));
+        assert!(reader.has_dosages());
+
+        assert_eq!(
+            reader.read_dosages().unwrap().unwrap(),
+            [Some(0.0), Some(1.0), Some(2.0)]
+        );
+        assert_eq!(
+            reader.read_dosages().unwrap().unwrap(),
+            [Some(0.5), Some(0.0), None]
+        );
+        assert_eq!(reader.read_dosages().unwrap(), None);
+
+        reader.seek_variant(1).unwrap();
+        assert_eq!(
+            reader.read_hardcalls().unwrap().unwrap(),
+            [Some(1), Some(0), None]
+        );
+    }
+
+    /// A variable-width file, with 1-byte record lengths and 8-bit or 4-bit record types.
+    fn variable_width(samples: u32, wide_types: bool, records: &[(u8, Vec<u8>)]) -> Vec<u8> {
+        let mut pgen = vec![0x6c, 0x1b, 0x10];
+        pgen.extend(u32::try_from(records.len()).unwrap().to_le_bytes());
+        pgen.extend(samples.to_le_bytes());
+        pgen.push(if wide_types { 8 } else { 0 });
+
+        let types: Vec<u8> = match wide_types {
+            true => records.iter().map(|(vrtype, _)| *vrtype).collect(),
+            false => records
+                .chunks(2)
+                .map(|pair| pair.iter().rev().fold(0, |byte, (t, _)| (byte << 4) | t))
+                .collect(),
+        };
+        let start = pgen.len() + 8 + types.len() + records.len();
+        pgen.extend(u64::try_from(start).unwrap().to_le_bytes());
+        pgen.extend(types);
+        pgen.extend(records.iter().map(|(_, r)| u8::try_from(r.len()).unwrap()));
+        for (_, record) in records {
+            pgen.extend(record);
+        }
+        pgen
+    }
+    fn pack(hardcalls: &[u8]) -> Vec<u8> {
+        hardcalls
+            .chunks(4)
+            .map(|chunk| chunk.iter().rev().fold(0, |byte, h| (byte << 2) | h))
+            .collect()
+    }
+    /// A single group, so for fewer than 64 samples.
+    fn difflist(entries: &[(u8, u8)], with_hardcalls: bool) -> Vec<u8> {
+        let mut list = vec![u8::try_from(entries.len()).unwrap()];
+        if let Some((first, _)) = entries.first() {
+            list.push(*first);
+            if with_hardcalls {
+                list.extend(pack(&entries.iter().map(|(_, h)| *h).collect::<Vec<_>>()));
+            }
+            list.extend(entries.windows(2).map(|pair| pair[1].0 - pair[0].0));
+        }
+        list
+    }
+
+    #[test]
+    fn test_variable_width() {
+        let dosages =
+            |values: &[u16]| -> Vec<u8> { values.iter().flat_map(|v| v.to_le_bytes()).collect() };
+        let records = [
+            // Plain hardcalls.
+            (0x00, pack(&[0, 1, 2, 3, 0])),
+            // Differences from hom REF.
+            (0x04, difflist(&[(1, 1), (4, 2)], true)),
+            // LD-compressed against the previous record.
+            (0x02, difflist(&[(0, 2)], true)),
+            // LD-compressed and inverted, with dosages for a list of samples.
+            (
+                0x23,
+                [
+                    difflist(&[(2, 3)], true),
+                    difflist(&[(1, 0), (3, 0)], false),
+                    dosages(&[8192, 24576]),
+                ]
+                .concat(),
+            ),
+            // Hom REF and het as bits, hom ALT as a difference, with dosages for all samples.
+            (
+                0x41,
+                [
+                    vec![0x01, 0b10010],
+                    difflist(&[(2, 2)], true),
+                    dosages(&[0, 0xFFFF, 32768, 4096, 16384]),
+                ]
+                .concat(),
+            ),
+            // Differences from missing, with phased hets and dosages for a bitarray of samples.
+            (
+                0x77,
+                [
+                    difflist(&[(0, 1), (1, 1), (2, 0)], true),
+                    vec![0b110, 0b01000],
+                    dosages(&[16384]),
+                ]
+                .concat(),
+            ),
+            // Multiallelic.
+            (0x08, pack(&[0, 1, 2, 3, 0])),
+        ];
+
+        let mut reader =
+            PgenReader::new(Cursor::new(variable_width(5, true, &records)), 0).unwrap();
+        assert_eq!((reader.variants(), reader.samples()), (Some(7), 5));
+        assert!(reader.has_dosages());
+
+        let mut read = || reader.read_dosages().unwrap().unwrap();
+        assert_eq!(read(), [Some(0.), Some(1.), Some(2.), None, Some(0.)]);
+        assert_eq!(read(), [Some(0.), Some(1.), Some(0.), Some(0.), Some(2.)]);
+        assert_eq!(read(), [Some(2.), Some(1.), Some(0.), Some(0.), Some(2.)]);
+        let inverted = [Some(2.), Some(0.5), None, Some(1.5), Some(0.)];
+        assert_eq!(read(), inverted);
+        assert_eq!(read(), [Some(0.), Some(1.), Some(2.), Some(0.25), Some(1.)]);
+        assert_eq!(read(), [Some(1.), Some(1.), Some(0.), Some(1.), None]);
+
+        let error = reader.read_dosages().unwrap_err();
+        let error = error
+            .into_inner()
+            .unwrap()
+            .downcast::<PlinkError>()
+            .unwrap();
+        assert_eq!(
+            *error,
+            PlinkError::UnsupportedRecord {
+                variant: 6,
+                vrtype: 0x08
+            }
+        );
+
+        // Seeking to an LD-compressed record loads the record it is compressed against.
+        reader.seek_variant(3).unwrap();
+        assert_eq!(reader.read_dosages().unwrap().unwrap(), inverted);
+        reader.seek_variant(2).unwrap();
+        assert_eq!(
+            reader.read_hardcalls().unwrap().unwrap(),
+            [Some(2), Some(1), Some(0), Some(0), Some(2)]
+        );
+        reader.seek_variant(7).unwrap();
+        assert_eq!(reader.read_hardcalls().unwrap(), None);
+
+        let records = [
+            (0x00, pack(&[2, 2, 1])),
+            (0x04, difflist(&[(1, 3)], true)),
+            (0x07, difflist(&[], true)),
+        ];
+        let mut reader =
+            PgenReader::new(Cursor::new(variable_width(3, false, &records)), 0).unwrap();
+        assert!(!reader.has_dosages());
+        let mut read = || reader.read_hardcalls().unwrap();
+        assert_eq!(read(), Some(vec![Some(2), Some(2), Some(1)]));
+        assert_eq!(read(), Some(vec![Some(0), None, Some(0)]));
+        assert_eq!(read(), Some(vec![None, None, None]));
+        assert_eq!(read(), None);
+    }
+
+    #[test]
+    fn test_separate_index_is_unsupported() {
+        let mut pgen = vec![0x6c, 0x1b, 0x11];
+        pgen.extend(2u32.to_le_bytes()); // Variants.
+        pgen.extend(3u32.to_le_bytes()); // Samples.
+
+        let error = PgenReader::new(Cursor::new(pgen), 3).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        let error = error
+            .into_inner()
+            .unwrap()
+            .downcast::<PlinkError>()
+            .unwrap();
+        assert_eq!(*error, PlinkError::UnsupportedMode(0x11));
+    }
+}
@@ -0,0 +1,134 @@
+//! `.psam` sample files, with a `#IID` or `#FID IID` header line.
+
+use std::io::{self, BufRead};
+
+use genomes1000::pedigree::Sex;
+
+use crate::PlinkError;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PsamRecord {
+    pub family_id: Option<String>,
+    pub id: String,
+    /// [None] if unknown or if there is no `SEX` column.
+    pub sex: Option<Sex>,
+}
+
+pub struct PsamReader<R> {
+    reader: R,
+    buf: String,
+    line: usize,
+    /// Indices of the `FID`, `IID` and `SEX` columns.
+    columns: Option<(Option<usize>, usize, Option<usize>)>,
+}
+
+impl<R: BufRead> PsamReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buf: String::new(),
+            line: 0,
+            columns: None,
+        }
+    }
+}
+impl<R: BufRead> Iterator for PsamReader<R> {
+    type Item = io::Result<PsamRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.buf.clear();
+            match self.reader.read_line(&mut self.buf) {
+                Ok(0) => return None,
+                Ok(_) => {}
+                Err(e) => return Some(Err(e)),
+            }
+            self.line += 1;
+
+            let line = self.buf.trim_end_matches(['\n', '\r']);
+            if line.trim().is_empty() || line.starts_with("##") {
+                continue;
+            }
+            if line.starts_with("#FID") || line.starts_with("#IID") {
+                let names: Vec<&str> = line[1..].split_ascii_whitespace().collect();
+                let find = |name| names.iter().position(|n| *n == name);
+                let Some(id) = find("IID") else {
+                    return Some(Err(PlinkError::MissingColumn {
+                        line: self.line,
+                        column: "IID",
+                    }
+                    .into()));
+                };
+                self.columns = Some((find("FID"), id, find("SEX")));
+                continue;
+            }
+
+            let Some(columns) = self.columns else {
+                return Some(Err(PlinkError::MissingHeader.into()));
+            };
+            return Some(parse_record(line, self.line, columns).map_err(Into::into));
+        }
+    }
+}
+
+fn parse_record(
+    line: &str,
+    line_number: usize,
+    (family_id, id, sex): (Option<usize>, usize, Option<usize>),
+) -> Result<PsamRecord, PlinkError> {
+    let columns: Vec<&str> = line.split_ascii_whitespace().collect();
+    let get = |column: usize| {
+        columns.get(column).copied().ok_or(PlinkError::ColumnCount {
+            line: line_number,
+            expected: column + 1,
+            found: columns.len(),
+        })
+    };
+
+    let sex = match sex.map(get).transpose()? {
+        Some("1" | "M" | "m") => Some(Sex::Male),
+        Some("2" | "F" | "f") => Some(Sex::Female),
+        _ => None,
+    };
+
+    Ok(PsamRecord {
+        family_id: family_id.map(get).transpose()?.map(str::to_owned),
+        id: get(id)?.to_owned(),
+        sex,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use genomes1000::pedigree::Sex;
+
+    use super::PsamReader;
+
+    #[test]
+    fn test_parse() {
+        let psam = "## Comment.\n#FID\tIID\tSEX\tPHENO1\nFAM1\tNA1\t2\t1\nFAM1\tNA2\tNA\t2\n";
+        let records = PsamReader::new(Cursor::new(psam))
+            .try_collect::<Vec<_>>()
+            .unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].family_id.as_deref(), Some("FAM1"));
+        assert_eq!(records[0].id, "NA1");
+        assert_eq!(records[0].sex, Some(Sex::Female));
+        assert_eq!(records[1].sex, None);
+
+        // Without FID or SEX columns.
+        let records = PsamReader::new(Cursor::new("#IID\tPHENO1\nNA1\t1\n"))
+            .try_collect::<Vec<_>>()
+            .unwrap();
+        assert_eq!(records[0].family_id, None);
+        assert_eq!(records[0].id, "NA1");
+        assert_eq!(records[0].sex, None);
+
+        let mut reader = PsamReader::new(Cursor::new("NA1\t1\n"));
+        assert!(reader.next().unwrap().is_err());
+        let mut reader = PsamReader::new(Cursor::new("#FID\tSEX\nFAM1\t1\n"));
+        assert!(reader.next().unwrap().is_err());
+    }
+}
@@ -0,0 +1,169 @@
+//! `.pvar` variant files: VCF-like, with a `#CHROM POS ID REF ALT ...` header line.
+
+use std::{
+    io::{self, BufRead},
+    marker::PhantomData,
+    str::FromStr,
+};
+
+use biocore::{genome::alias::ContigAliases, location::ContigPosition};
+
+use crate::{PlinkError, bim::chromosome_name};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PvarRecord<C = String> {
+    /// 0-based (the file is 1-based).
+    pub at: ContigPosition<C>,
+    /// [None] if `.`.
+    pub id: Option<String>,
+    pub reference: String,
+    /// Empty if `.`.
+    pub alternate: Vec<String>,
+}
+
+pub struct PvarReader<R, C = String> {
+    reader: R,
+    buf: String,
+    line: usize,
+    /// Indices of the `#CHROM`, `POS`, `ID`, `REF` and `ALT` columns.
+    columns: Option<[usize; 5]>,
+    _contig: PhantomData<fn() -> C>,
+}
+
+impl<C> PvarRecord<C> {
+    pub fn map_contig<NewContig>(self, f: impl FnOnce(C) -> NewContig) -> PvarRecord<NewContig> {
+        PvarRecord {
+            at: self.at.map_contig(f),
+            id: self.id,
+            reference: self.reference,
+            alternate: self.alternate,
+        }
+    }
+}
+
+impl<R: BufRead, C> PvarReader<R, C> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buf: String::new(),
+            line: 0,
+            columns: None,
+            _contig: PhantomData,
+        }
+    }
+}
+impl<R: BufRead> PvarReader<R, String> {
+    /// Resolves contig names through `aliases` (see also [chromosome_name]),
+    /// unknown contigs are reported as errors.
+    pub fn with_aliases<C: Clone>(
+        self,
+        aliases: &ContigAliases<C>,
+    ) -> impl Iterator<Item = io::Result<PvarRecord<C>>> + use<'_, R, C> {
+        self.map(|record| {
+            let record = record?;
+            let name = &record.at.contig;
+            let contig = aliases
+                .resolve(name)
+                .or_else(|_| aliases.resolve(chromosome_name(name)))?;
+            Ok(record.map_contig(|_| contig))
+        })
+    }
+}
+impl<R, C> Iterator for PvarReader<R, C>
+where
+    R: BufRead,
+    C: FromStr,
+{
+    type Item = io::Result<PvarRecord<C>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.buf.clear();
+            match self.reader.read_line(&mut self.buf) {
+                Ok(0) => return None,
+                Ok(_) => {}
+                Err(e) => return Some(Err(e)),
+            }
+            self.line += 1;
+
+            let line = self.buf.trim_end_matches(['\n', '\r']);
+            if line.trim().is_empty() || line.starts_with("##") {
+                continue;
+            }
+            if line.starts_with("#CHROM") {
+                match parse_header(line, self.line) {
+                    Ok(columns) => self.columns = Some(columns),
+                    Err(e) => return Some(Err(e.into())),
+                }
+                continue;
+            }
+
+            let Some(columns) = self.columns else {
+                return Some(Err(PlinkError::MissingHeader.into()));
+            };
+            return Some(parse_record(line, self.line, columns).map_err(Into::into));
+        }
+    }
+}
+
+fn parse_header(line: &str, line_number: usize) -> Result<[usize; 5], PlinkError> {
+    let names: Vec<&str> = line.split_ascii_whitespace().collect();
+    let find = |name: &'static str| {
+        names
+            .iter()
+            .position(|n| *n == name)
+            .ok_or(PlinkError::MissingColumn {
+                line: line_number,
+                column: name,
+            })
+    };
+    Ok([
+        find("#CHROM")?,
+        find("POS")?,
+        find("ID")?,
+        find("REF")?,
+        find("ALT")?,
+    ])
+}
+
+fn parse_record<C: FromStr>(
+    line: &str,
+    line_number: usize,
+    [contig, position, id, reference, alternate]: [usize; 5],
+) -> Result<PvarRecord<C>, PlinkError> {
+    let columns: Vec<&str> = line.split_ascii_whitespace().collect();
+    let get = |column: usize| {
+        columns.get(column).copied().ok_or(PlinkError::ColumnCount {
+            line: line_number,
+            expected: column + 1,
+            found: columns.len(),
+        })
+    };
+    let invalid = |column: usize| PlinkError::InvalidField {
+        line: line_number,
+        column: column + 1,
+        value: columns[column].to_owned(),
+    };
+
+    let contig_name = get(contig)?;
+    let contig = C::from_str(contig_name)
+        .or_else(|_| C::from_str(chromosome_name(contig_name)))
+        .map_err(|_| PlinkError::InvalidContig {
+            line: line_number,
+            value: contig_name.to_owned(),
+        })?;
+    let at: u64 = get(position)?.parse().map_err(|_| invalid(position))?;
+    let at = at.checked_sub(1).ok_or_else(|| invalid(position))?;
+    let id = get(id)?;
+    let alternate = get(alternate)?;
+
+    Ok(PvarRecord {
+        at: ContigPosition { contig, at },
+        id: (id != ".").then(|| id.to_owned()),
+        reference: get(reference)?.to_owned(),
+        alternate: match alternate {
+            "." => vec![],
+            alternate => alternate.split(',').map(str::to_owned).collect(),
+        },
+    })
+}
@@ -0,0 +1,222 @@
+//! Guides for adenine (ABE) and cytosine (CBE) base editors.
+//!
+//! Base editors deaminate bases in a window of the protospacer without nicking both strands,
+//! which makes them a better fit than prime editing for many transitions, at the cost of also
+//! editing any other ("bystander") target base in the window.
+
+use std::ops::RangeInclusive;
+
+use biocore::{
+    dna::DnaBase,
+    genome::Contig,
+    location::{
+        ContigPosition, ContigRange,
+        orientation::{SequenceOrientation, Stranded},
+    },
+};
+use serde::{Deserialize, Serialize};
+use utile::{num::TryUsize, regex_ext::find_iter_overlapping};
+
+use crate::{
+    Pam,
+    edit::{Edit, cached_regex},
+    editor::Editor,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize)]
+pub enum Deaminase {
+    /// A•T → G•C.
+    Adenine,
+    /// C•G → T•A.
+    Cytosine,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize)]
+pub struct BaseEditor {
+    pub deaminase: Deaminase,
+    /// The Cas9 the deaminase is fused to, [Editor::nick_distance] and [Editor::scaffold] are
+    /// only used for the guide.
+    pub editor: Editor,
+    /// The protospacer positions that get edited, 1-based from the PAM-distal end.
+    pub window: RangeInclusive<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize)]
+pub struct BaseEditDesign<C> {
+    pub editor: BaseEditor,
+    pub pam: Pam<C>,
+    /// The edited base, on the strand of the PAM.
+    pub target: Stranded<ContigPosition<C>>,
+    /// Other bases in the window that would be edited too, on the strand of the PAM.
+    pub bystanders: Vec<Stranded<ContigPosition<C>>>,
+}
+
+impl Deaminase {
+    /// The (original, edited) base on the strand of the PAM.
+    pub fn conversion(self) -> (DnaBase, DnaBase) {
+        match self {
+            Self::Adenine => (DnaBase::A, DnaBase::G),
+            Self::Cytosine => (DnaBase::C, DnaBase::T),
+        }
+    }
+}
+
+impl BaseEditor {
+    /// ABE8e (Richter et al. 2020).
+    pub fn abe8e() -> Self {
+        Self {
+            deaminase: Deaminase::Adenine,
+            editor: Editor::sp_cas9(),
+            window: 4..=8,
+        }
+    }
+    /// ABE7.10 (Gaudelli et al. 2017).
+    pub fn abe7_10() -> Self {
+        Self {
+            deaminase: Deaminase::Adenine,
+            editor: Editor::sp_cas9(),
+            window: 4..=7,
+        }
+    }
+    /// BE4max (Koblan et al. 2018).
+    pub fn be4max() -> Self {
+        Self {
+            deaminase: Deaminase::Cytosine,
+            editor: Editor::sp_cas9(),
+            window: 4..=8,
+        }
+    }
+
+    /// All guides placing the edit in the window, on either strand.
+    ///
+    /// Empty if the edit isn't a single base conversion this editor can make.
+    pub fn designs<C>(&self, edit: &Edit<C>) -> Vec<BaseEditDesign<C>>
+    where
+        C: Contig + Clone,
+    {
+        let edit_range = edit.edit_range_in_original();
+        if edit_range.v.at.end - edit_range.v.at.start != 1
+            || edit.edited_len() != edit.original_len()
+        {
+            return vec![];
+        }
+        let (from, to) = self.deaminase.conversion();
+        let spacer_size = self.editor.spacer_size;
+        let regex = cached_regex(&self.editor.pam_pattern);
+
+        let mut designs = vec![];
+        for orientation in [SequenceOrientation::Forward, SequenceOrientation::Reverse] {
+            let target = edit_range.clone().into_orientation(orientation);
+            if edit.get_original(target.clone()).first() != Some(&from)
+                || edit
+                    .get_edited(edit.edit_range_in_edited().into_orientation(orientation))
+                    .first()
+                    != Some(&to)
+            {
+                continue;
+            }
+            let target = target.into_start();
+
+            let original = match orientation {
+                SequenceOrientation::Forward => edit.original(),
+                SequenceOrientation::Reverse => edit.original().reverse_complement(),
+            };
+            for pam in find_iter_overlapping(&regex, &original.encode()) {
+                let Some(spacer_start) = pam.start.checked_sub(spacer_size) else {
+                    continue;
+                };
+                let position =
+                    |at: u64| (spacer_start <= at && at < pam.start).then(|| at - spacer_start + 1);
+                if !position(target.v.at).is_some_and(|p| self.window.contains(&p)) {
+                    continue;
+                }
+
+                let bystanders = (spacer_start..pam.start)
+                    .filter(|&at| at != target.v.at)
+                    .filter(|&at| position(at).is_some_and(|p| self.window.contains(&p)))
+                    .filter(|&at| original[at.usize_unwrap()] == from)
+                    .map(|at| Stranded {
+                        orientation,
+                        v: ContigPosition {
+                            contig: edit.original_contig(),
+                            at,
+                        },
+                    })
+                    .collect();
+                designs.push(BaseEditDesign {
+                    editor: self.clone(),
+                    pam: Stranded {
+                        orientation,
+                        v: ContigRange {
+                            contig: edit.original_contig(),
+                            at: pam,
+                        },
+                    },
+                    target: target.clone(),
+                    bystanders,
+                });
+            }
+        }
+        designs
+    }
+}
+
+impl<C> BaseEditDesign<C>
+where
+    C: Contig + Clone,
+{
+    pub fn spacer(&self) -> Stranded<ContigRange<C>> {
+        let start = self.pam.v.at.start - self.editor.editor.spacer_size;
+        Stranded {
+            orientation: self.pam.orientation,
+            v: ContigRange {
+                contig: self.pam.v.contig.clone(),
+                at: start..self.pam.v.at.start,
+            },
+        }
+    }
+    /// The position of the target in the protospacer, 1-based from the PAM-distal end.
+    pub fn target_position(&self) -> u64 {
+        self.target.v.at - self.spacer().v.at.start + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base_edit_designs() {
+        // The A→G edit is at protospacer position 5 of the forward NGG PAM, with a bystander A
+        // at position 7.
+        let edit = Edit::parse("GTCAGTCAGTCTCTC(A/G)CATCTCAGTCAGTCTTGGCAGTCAGTC").unwrap();
+
+        let designs = BaseEditor::abe8e().designs(&edit);
+        assert_eq!(designs.len(), 1);
+        let design = &designs[0];
+        assert!(design.pam.orientation.is_forward());
+        assert_eq!(design.target_position(), 5);
+        assert_eq!(edit.get_original(design.spacer()).len(), 20);
+        let bystanders: Vec<_> = design.bystanders.iter().map(|b| b.v.at).collect();
+        assert_eq!(bystanders, vec![17]);
+
+        // Cytosine base editors can't make this edit, and a narrower window drops it.
+        assert!(BaseEditor::be4max().designs(&edit).is_empty());
+        let narrow = BaseEditor {
+            window: 6..=7,
+            ..BaseEditor::abe8e()
+        };
+        assert!(narrow.designs(&edit).is_empty());
+
+        // On the reverse strand, G→A is a C→T edit.
+        let edit = Edit::parse("GACTGACTGCCAAGACTGACTGAGAAT(G/A)TAGAGACTGACTGAC").unwrap();
+        let designs = BaseEditor::be4max().designs(&edit);
+        assert_eq!(designs.len(), 1);
+        assert!(designs[0].pam.orientation.is_reverse());
+        assert_eq!(designs[0].target_position(), 5);
+        assert!(designs[0].bystanders.is_empty());
+    }
+}
@@ -0,0 +1,269 @@
+//! Designing many edits at once, e.g. from a VCF of the variants to introduce.
+
+use std::io::{self, BufRead};
+
+use biocore::{
+    dna::DnaSequence,
+    genome::ArcContig,
+    location::{ContigPosition, ContigRange},
+    mutation::normalize::{NormalizationError, ReferenceSequence, Variant},
+};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use utile::num::TryU64;
+
+use crate::{
+    Design,
+    design_spec::DesignSpec,
+    edit::Edit,
+    scoring::{self, Scorer},
+};
+
+#[derive(Debug, Clone)]
+pub struct BatchSpec {
+    pub design: DesignSpec,
+    /// Reference bases fetched on each side of the variant, they need to fit the PAM, spacer and
+    /// templates of the designs.
+    pub flank: u64,
+    /// Keep only the best designs of each variant, or all if `None`.
+    pub max_designs: Option<usize>,
+}
+impl Default for BatchSpec {
+    fn default() -> Self {
+        Self {
+            design: DesignSpec::default(),
+            flank: 60,
+            max_designs: Some(10),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct VariantDesigns {
+    pub variant: Variant,
+    /// The reference bases the [Edit] was built from, see [BatchSpec::edit].
+    pub window: ContigRange<String>,
+    /// Best first, see [scoring::rank].
+    pub designs: Vec<(Design<ArcContig>, f64)>,
+}
+
+impl BatchSpec {
+    /// See [read_vcf] and [Self::design_variants].
+    pub fn design_vcf(
+        &self,
+        vcf: impl BufRead,
+        genome: &mut impl ReferenceSequence<String>,
+        scorer: &(impl Scorer + Sync),
+    ) -> io::Result<Vec<Result<VariantDesigns, NormalizationError>>> {
+        let variants = read_vcf(vcf).collect::<io::Result<Vec<_>>>()?;
+        Ok(self.design_variants(variants, genome, scorer))
+    }
+    /// Ranked designs for each variant, in the same order.
+    ///
+    /// The reference is read sequentially, the designs are then computed in parallel.
+    pub fn design_variants(
+        &self,
+        variants: impl IntoIterator<Item = Variant>,
+        genome: &mut impl ReferenceSequence<String>,
+        scorer: &(impl Scorer + Sync),
+    ) -> Vec<Result<VariantDesigns, NormalizationError>> {
+        let edits: Vec<_> = variants
+            .into_iter()
+            .map(|variant| {
+                let (window, edit) = self.edit(&variant, genome)?;
+                Ok((variant, window, edit))
+            })
+            .collect();
+
+        edits
+            .into_par_iter()
+            .map(|edit| {
+                let (variant, window, edit) = edit?;
+                Ok(VariantDesigns {
+                    variant,
+                    window,
+                    designs: self.designs(edit, scorer),
+                })
+            })
+            .collect()
+    }
+
+    /// The [Edit] introducing the variant, with [Self::flank] reference bases on each side.
+    ///
+    /// Fails if the reference allele doesn't match the genome, or if the flanks run past the
+    /// end of the contig.
+    pub fn edit(
+        &self,
+        variant: &Variant,
+        genome: &mut impl ReferenceSequence<String>,
+    ) -> Result<(ContigRange<String>, Edit<ArcContig>), NormalizationError> {
+        variant.validate(genome)?;
+
+        let contig = &variant.at.contig;
+        let start = variant.at.at;
+        let end = start + variant.reference.len().u64_unwrap();
+        let window = ContigRange {
+            contig: contig.clone(),
+            at: start.saturating_sub(self.flank)..end + self.flank,
+        };
+        let before = genome.fetch(&ContigRange {
+            contig: contig.clone(),
+            at: window.at.start..start,
+        })?;
+        let after = genome.fetch(&ContigRange {
+            contig: contig.clone(),
+            at: end..window.at.end,
+        })?;
+
+        let name = format!("{contig}:{}-{}", window.at.start + 1, window.at.end);
+        let edit = Edit::new(
+            &name,
+            before,
+            variant.reference.clone(),
+            variant.alternate.clone(),
+            after,
+        );
+        Ok((window, edit))
+    }
+
+    fn designs(
+        &self,
+        edit: Edit<ArcContig>,
+        scorer: &impl Scorer,
+    ) -> Vec<(Design<ArcContig>, f64)> {
+        let designs = edit
+            .pams(&self.design.editor)
+            .into_iter()
+            .filter_map(|pam| self.design.clone().designs(edit.clone(), pam))
+            .flatten();
+        let mut ranked = scoring::rank(designs, scorer);
+        if let Some(max_designs) = self.max_designs {
+            ranked.truncate(max_designs);
+        }
+        ranked
+    }
+}
+
+/// The variants of a VCF, one per alternate allele.
+///
+/// Only the `CHROM`, `POS`, `REF` and `ALT` columns are read. Symbolic alleles (e.g. `<DEL>`)
+/// can't be designed for, and are skipped with a warning.
+pub fn read_vcf(reader: impl BufRead) -> impl Iterator<Item = io::Result<Variant>> {
+    reader
+        .lines()
+        .map(|line| {
+            let line = line?;
+            if line.starts_with('#') || line.trim().is_empty() {
+                return Ok(vec![]);
+            }
+            parse_vcf_line(&line)
+        })
+        .flat_map(|variants| match variants {
+            Ok(variants) => variants.into_iter().map(Ok).collect(),
+            Err(e) => vec![Err(e)],
+        })
+}
+
+fn parse_vcf_line(line: &str) -> io::Result<Vec<Variant>> {
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Invalid VCF line: {line:?}"),
+        )
+    };
+
+    let mut fields = line.split('\t');
+    let (Some(contig), Some(pos), Some(_id), Some(reference), Some(alternates)) = (
+        fields.next(),
+        fields.next(),
+        fields.next(),
+        fields.next(),
+        fields.next(),
+    ) else {
+        return Err(invalid());
+    };
+
+    let at = pos
+        .parse::<u64>()
+        .ok()
+        .and_then(|pos| pos.checked_sub(1))
+        .ok_or_else(invalid)?;
+    let reference: DnaSequence = reference
+        .to_ascii_uppercase()
+        .parse()
+        .map_err(|_| invalid())?;
+
+    let variants = alternates
+        .split(',')
+        .filter_map(|alternate| match alternate.to_ascii_uppercase().parse() {
+            Ok(alternate) => Some(Variant {
+                at: ContigPosition {
+                    contig: contig.to_owned(),
+                    at,
+                },
+                reference: reference.clone(),
+                alternate,
+            }),
+            Err(_) => {
+                log::warn!(
+                    "[primeedit] Skipping unsupported allele {alternate:?} at {contig}:{pos}."
+                );
+                None
+            }
+        })
+        .collect();
+    Ok(variants)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use biocore::{dna::DnaBase, genome::InMemoryGenome, sequence::AsciiChar};
+
+    use super::*;
+
+    use crate::scoring::HeuristicScorer;
+
+    #[test]
+    fn test_design_vcf() {
+        let sequence = "GATTACAGATTACA\
+            ACGTTGCAGCTAGGCATCGATCGATGGAATCAAGCTAGCATCGATCGACTAGCA\
+            GATTACAGATTACA";
+        let mut genome = InMemoryGenome::new([(
+            "chr1".to_owned(),
+            DnaBase::decode(sequence.as_bytes().to_vec()).unwrap(),
+        )]);
+        let vcf = "##fileformat=VCFv4.2\n\
+            #CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\n\
+            chr1\t45\t.\tC\tT,<DEL>\t.\t.\t.\n\
+            chr1\t45\t.\tG\tT\t.\t.\t.\n";
+
+        let spec = BatchSpec {
+            design: DesignSpec {
+                avoid_rtt_cytosine: false,
+                ..DesignSpec::default()
+            },
+            flank: 30,
+            max_designs: Some(3),
+        };
+        let results = spec
+            .design_vcf(Cursor::new(vcf), &mut genome, &HeuristicScorer::default())
+            .unwrap();
+        assert_eq!(results.len(), 2);
+
+        let designs = results[0].as_ref().unwrap();
+        assert_eq!(designs.window.at, 14..75);
+        assert!(!designs.designs.is_empty());
+        assert!(designs.designs.len() <= 3);
+        for (design, _) in &designs.designs {
+            let edit = &design.edit;
+            assert_eq!(edit.original_contig().as_ref(), "chr1:15-75");
+            assert_eq!(edit.edit_range_in_original().v.at, 30..31);
+        }
+
+        assert!(matches!(
+            results[1],
+            Err(NormalizationError::ReferenceMismatch { .. })
+        ));
+    }
+}
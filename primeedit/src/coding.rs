@@ -0,0 +1,245 @@
+//! Silent mutations that follow the coding sequence of an annotated transcript, rather than
+//! assuming the whole edit sequence is coding from [Edit::translation_frame_start].
+
+use std::ops::Range;
+
+use biocore::{
+    annotation::Transcript,
+    consequence::CodonTable,
+    dna::DnaBase,
+    genome::Contig,
+    location::{
+        ContigRange,
+        orientation::{SequenceOrientation, Stranded},
+    },
+    mutation::SilentMutation,
+};
+use utile::{
+    num::{TryI64, TryU64},
+    range::RangeExt,
+};
+
+use crate::edit::Edit;
+
+/// Exonic bases next to an intron that are part of the splice region, see
+/// [Consequence::SpliceRegion](biocore::consequence::Consequence::SpliceRegion).
+const SPLICE_REGION: u64 = 3;
+
+impl<C> Edit<C>
+where
+    C: Contig + Clone,
+{
+    /// Restricts [Self::original_silent_mutations] and [Self::edited_silent_mutations] to
+    /// synonymous codon changes in the coding sequence of `transcript`.
+    ///
+    /// `offset` is the position on the transcript's contig of the first original base.
+    ///
+    /// Codons are skipped if they span an exon boundary, touch a splice region, overlap the
+    /// edit, or are downstream of a frameshifting edit.
+    pub fn with_transcript<G>(
+        mut self,
+        transcript: &Transcript<G>,
+        offset: u64,
+        codons: &CodonTable,
+    ) -> Self {
+        self.coding_silent_mutations = Some(self.cds_silent_mutations(transcript, offset, codons));
+        self
+    }
+
+    fn cds_silent_mutations<G>(
+        &self,
+        transcript: &Transcript<G>,
+        offset: u64,
+        codons: &CodonTable,
+    ) -> Vec<SilentMutation<C, DnaBase>> {
+        let Some(strand) = transcript.strand else {
+            log::warn!("[primeedit] Transcript {} has no strand.", transcript.id);
+            return vec![];
+        };
+        let len = self.original_len().u64_unwrap();
+        let edit = self.edit_range_in_original().into_forward().v.at;
+        let frameshift = (self.edited_len().i64_unwrap() - len.i64_unwrap()) % 3 != 0;
+
+        let mut mutations: Vec<_> = coding_codons(transcript, strand)
+            .into_iter()
+            .filter_map(|codon| {
+                let codon = codon.start.checked_sub(offset)?..codon.end.checked_sub(offset)?;
+                (codon.end <= len).then_some(codon)
+            })
+            .filter(|codon| {
+                let (upstream, downstream) = match strand {
+                    SequenceOrientation::Forward => {
+                        (codon.end <= edit.start, edit.end <= codon.start)
+                    }
+                    SequenceOrientation::Reverse => {
+                        (edit.end <= codon.start, codon.end <= edit.start)
+                    }
+                };
+                upstream || (downstream && !frameshift)
+            })
+            .flat_map(|codon| {
+                let at = Stranded {
+                    orientation: SequenceOrientation::Forward,
+                    v: ContigRange {
+                        contig: self.original_contig(),
+                        at: codon,
+                    },
+                }
+                .into_orientation(strand);
+                let original: [DnaBase; 3] = self
+                    .get_original(at.clone())
+                    .to_vec()
+                    .try_into()
+                    .expect("codon");
+                let at = at.into_start();
+                synonyms(codons, original).map(move |edited| SilentMutation {
+                    at: at.clone(),
+                    original,
+                    edited,
+                })
+            })
+            .collect();
+        mutations.sort_by_key(|m| (m.at_range().into_forward().v.at.start, m.edited));
+        mutations
+    }
+}
+
+/// The complete codons of each CDS segment, on the transcript's contig.
+fn coding_codons<G>(transcript: &Transcript<G>, strand: SequenceOrientation) -> Vec<Range<u64>> {
+    let mut codons = vec![];
+    for segment in &transcript.cds {
+        let phase = u64::from(segment.phase);
+        let Range { start, end } = segment.at.clone();
+        if strand.is_forward() {
+            let mut at = start + phase;
+            while at + 3 <= end {
+                codons.push(at..at + 3);
+                at += 3;
+            }
+        } else {
+            let mut at = end.saturating_sub(phase);
+            while start + 3 <= at {
+                codons.push(at - 3..at);
+                at -= 3;
+            }
+        }
+    }
+    codons.retain(|codon| !in_splice_region(transcript, codon));
+    codons
+}
+
+fn in_splice_region<G>(transcript: &Transcript<G>, codon: &Range<u64>) -> bool {
+    let last = transcript.exons.len().saturating_sub(1);
+    transcript.exons.iter().enumerate().any(|(i, exon)| {
+        let acceptor = exon.start..exon.start + SPLICE_REGION;
+        let donor = exon.end.saturating_sub(SPLICE_REGION)..exon.end;
+        (i > 0 && codon.overlaps(&acceptor)) || (i < last && codon.overlaps(&donor))
+    })
+}
+
+/// Other codons for the same amino acid, stop codons are left alone.
+fn synonyms(
+    codons: &CodonTable,
+    codon: [DnaBase; 3],
+) -> impl Iterator<Item = [DnaBase; 3]> + use<'_> {
+    let amino_acid = *codons.get(&codon);
+    codons
+        .iter()
+        .filter(move |(c, a)| amino_acid.is_some() && **c != codon && **a == amino_acid)
+        .map(|(c, _)| *c)
+}
+
+#[cfg(test)]
+mod tests {
+    use biocore::{
+        aminoacid::codons::map::STANDARD, annotation::CdsSegment, dna::DnaSequence,
+        genome::ArcContig,
+    };
+
+    use super::*;
+
+    const SEQUENCE: &str = "ATGGCTGCAAAATTTCTGCCCAGTCGATGG";
+
+    fn transcript(strand: SequenceOrientation) -> Transcript {
+        Transcript {
+            id: "T1".to_owned(),
+            name: None,
+            biotype: None,
+            range: ContigRange {
+                contig: "chr1".to_owned(),
+                at: 90..200,
+            },
+            strand: Some(strand),
+            exons: vec![90..130, 140..200],
+            cds: vec![
+                CdsSegment {
+                    at: 100..130,
+                    phase: 0,
+                },
+                CdsSegment {
+                    at: 140..170,
+                    phase: 0,
+                },
+            ],
+        }
+    }
+    /// Replaces the base at `at`, `SEQUENCE` starting at position 100 of the contig.
+    fn edit(at: usize, original: &str, edited: &str) -> Edit<ArcContig> {
+        let seq = |s: &str| s.parse::<DnaSequence>().unwrap();
+        Edit::new(
+            "chr1:101-130",
+            seq(&SEQUENCE[..at]),
+            seq(original),
+            seq(edited),
+            seq(&SEQUENCE[at + original.len()..]),
+        )
+    }
+
+    #[test]
+    fn test_coding_silent_mutations() {
+        let substitution = edit(13, "T", "C").with_transcript(
+            &transcript(SequenceOrientation::Forward),
+            100,
+            &STANDARD,
+        );
+        let mutations: Vec<_> = substitution.original_silent_mutations().unwrap().collect();
+        assert!(!mutations.is_empty());
+        for m in &mutations {
+            let start = m.at.v.at;
+            assert!(m.at.orientation.is_forward());
+            assert_eq!(start % 3, 0);
+            assert_ne!(start, 12, "overlaps the edit");
+            assert_ne!(start, 27, "splice region");
+            assert_eq!(STANDARD.get(&m.original), STANDARD.get(&m.edited));
+        }
+        let lysine: Vec<_> = mutations.iter().filter(|m| m.at.v.at == 9).collect();
+        assert_eq!(lysine.len(), 1);
+        assert_eq!(lysine[0].edited, [DnaBase::A, DnaBase::A, DnaBase::G]);
+        assert_eq!(
+            substitution.edited_silent_mutations().unwrap().count(),
+            mutations.len()
+        );
+
+        // Past a frameshift, only the upstream codons are left.
+        let frameshift = edit(13, "T", "TA").with_transcript(
+            &transcript(SequenceOrientation::Forward),
+            100,
+            &STANDARD,
+        );
+        let mutations: Vec<_> = frameshift.edited_silent_mutations().unwrap().collect();
+        assert!(!mutations.is_empty());
+        assert!(mutations.iter().all(|m| m.at.v.at < 12));
+
+        // On the reverse strand, codons are read from the reverse complement.
+        let reverse = edit(13, "T", "C").with_transcript(
+            &transcript(SequenceOrientation::Reverse),
+            100,
+            &STANDARD,
+        );
+        for m in reverse.original_silent_mutations().unwrap() {
+            assert!(m.at.orientation.is_reverse());
+            assert_eq!(reverse.get_original(m.at_range()).to_vec(), m.original);
+            assert_eq!(STANDARD.get(&m.original), STANDARD.get(&m.edited));
+        }
+    }
+}
@@ -2,6 +2,7 @@ use std::{iter, ops::Range};
 
 use biocore::{
     dna::DnaBase,
+    enzyme::RestrictionEnzyme,
     genome::{Contig, EditedContig},
     location::orientation::Stranded,
 };
@@ -60,6 +61,16 @@ pub struct DesignSpec {
     ///
     /// This is the minimum size of the edit (the distance between the first and last edited bases).
     pub minimum_edit_size: Option<u64>,
+    /// Melting temperature range of the primer binding site, in °C, see [Design::pbs_tm].
+    ///
+    /// The PBS Tm predicts editing efficiency better than its length, which alone accepts many
+    /// weakly binding primers.
+    #[serde(default)]
+    pub pbs_tm_range: Option<Range<f64>>,
+    /// Only keep designs that create or destroy a site of one of these enzymes, so that edited
+    /// clones can be screened by RFLP, see [Design::restriction_sites].
+    #[serde(default)]
+    pub require_restriction_site_change: Option<Vec<RestrictionEnzyme>>,
 }
 impl Default for DesignSpec {
     fn default() -> Self {
@@ -72,6 +83,8 @@ impl Default for DesignSpec {
             avoid_rtt_cytosine: true,
             require_seed_or_pam_disruption: false,
             minimum_edit_size: None,
+            pbs_tm_range: None,
+            require_restriction_site_change: None,
         }
     }
 }
@@ -132,6 +145,8 @@ impl DesignSpec {
             avoid_rtt_cytosine,
             require_seed_or_pam_disruption,
             minimum_edit_size,
+            pbs_tm_range,
+            require_restriction_site_change,
         } = self.clone();
 
         if !edit.pams(&self.editor).contains(&pam) {
@@ -216,6 +231,16 @@ impl DesignSpec {
                 minimum_edit_size
                     .is_none_or(|min| min <= design.full_edited_range_in_edited().v.len())
             })
+            .filter(move |design| {
+                pbs_tm_range
+                    .as_ref()
+                    .is_none_or(|range| range.contains(&design.pbs_tm().expect("sizing")))
+            })
+            .filter(move |design| {
+                require_restriction_site_change
+                    .as_ref()
+                    .is_none_or(|enzymes| !design.restriction_sites(enzymes).is_empty())
+            })
             .inspect(move |design| {
                 #[cfg(debug_assertions)]
                 design.assert_valid();
@@ -271,6 +296,8 @@ mod tests {
             avoid_rtt_cytosine: false,
             require_seed_or_pam_disruption: false,
             minimum_edit_size: None,
+            pbs_tm_range: None,
+            require_restriction_site_change: None,
         };
 
         let edit =
@@ -389,4 +416,29 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_pbs_tm_range_filter() {
+        let spec = DesignSpec {
+            avoid_rtt_cytosine: false,
+            ..DesignSpec::default()
+        };
+        let spec_tm = DesignSpec {
+            pbs_tm_range: Some(20. ..40.),
+            ..spec.clone()
+        };
+
+        let edit = make_edit("ACGTTGCAGCTAGGCATCGATCGATGGAAT(C/T)AAGCTAGCATCGATCGACTAGCA");
+        let count = |spec: &DesignSpec| {
+            edit.pams(&spec.editor)
+                .into_iter()
+                .filter_map(|pam| spec.clone().designs(edit.clone(), pam))
+                .flatten()
+                .inspect(|design| design.assert_compliant(spec))
+                .count()
+        };
+        let all = count(&spec);
+        let filtered = count(&spec_tm);
+        assert!(0 < filtered && filtered < all);
+    }
 }
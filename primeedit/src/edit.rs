@@ -1,5 +1,6 @@
 use std::{
     collections::BTreeMap,
+    iter,
     ops::Range,
     sync::{Arc, LazyLock, Mutex},
 };
@@ -35,54 +36,67 @@ pub struct Edit<C> {
 
     /// On the sense/RNA-like strand (as opposed to the antisense/template strand).
     translation_frame_start: Option<Stranded<ContigPosition<C>>>,
+    /// See [Self::with_transcript], takes precedence over [Self::translation_frame_start].
+    pub(crate) coding_silent_mutations: Option<Vec<SilentMutation<C, DnaBase>>>,
 }
 impl Edit<ArcContig> {
     pub fn parse(input: &str) -> Option<Self> {
         let (start, rest) = input.split_once('(')?;
-        let (mut original, rest) = rest.split_once('/')?;
-        let (mut edited, end) = rest.split_once(')')?;
-
-        let mut start = start.to_owned();
-        let mut end = end.to_owned();
-
-        while let Some(o) = original.chars().next()
-            && let Some(e) = edited.chars().next()
-            && o == e
-        {
-            original = original.strip_prefix(o).unwrap();
-            edited = edited.strip_prefix(e).unwrap();
-            start = format!("{start}{o}");
-        }
-
-        while let Some(o) = original.chars().next_back()
-            && let Some(e) = edited.chars().next_back()
-            && o == e
-        {
-            original = original.strip_suffix(o).unwrap();
-            edited = edited.strip_suffix(e).unwrap();
-            end = format!("{e}{end}");
-        }
+        let (original, rest) = rest.split_once('/')?;
+        let (edited, end) = rest.split_once(')')?;
 
-        let start: DnaSequence = start.parse().ok()?;
-        let original: DnaSequence = original.parse().ok()?;
-        let edited: DnaSequence = edited.parse().ok()?;
-        let end: DnaSequence = end.parse().ok()?;
+        Some(Self::new(
+            "original",
+            start.parse().ok()?,
+            original.parse().ok()?,
+            edited.parse().ok()?,
+            end.parse().ok()?,
+        ))
+    }
+    /// Replaces `original` with `edited`, between the `start` and `end` flanks.
+    ///
+    /// Bases shared at either end of `original` and `edited` (e.g. the VCF anchor base of indels)
+    /// are moved to the flanks.
+    pub fn new(
+        name: &str,
+        start: DnaSequence,
+        original: DnaSequence,
+        edited: DnaSequence,
+        end: DnaSequence,
+    ) -> Self {
+        let mut start = start.to_vec();
+        let mut original = original.to_vec();
+        let mut edited = edited.to_vec();
+        let mut end = end.to_vec();
+
+        let prefix = iter::zip(&original, &edited)
+            .take_while(|(o, e)| o == e)
+            .count();
+        start.extend(original.drain(..prefix));
+        edited.drain(..prefix);
+
+        let suffix = iter::zip(original.iter().rev(), edited.iter().rev())
+            .take_while(|(o, e)| o == e)
+            .count();
+        end.splice(..0, original.drain(original.len() - suffix..));
+        edited.truncate(edited.len() - suffix);
 
         let contig = ArcContig::from_contig(ContigRef::new(
-            "original",
+            name,
             (start.len() + original.len() + end.len()).u64_unwrap(),
         ));
 
-        Some(Self {
+        Self {
             contig,
 
-            start,
-            original,
-            edited,
-            end,
+            start: DnaSequence::new(start),
+            original: DnaSequence::new(original),
+            edited: DnaSequence::new(edited),
+            end: DnaSequence::new(end),
 
             translation_frame_start: None,
-        })
+            coding_silent_mutations: None,
+        }
     }
     pub fn with_translation_frame_start(
         mut self,
@@ -114,6 +128,7 @@ where
             edited: _,
             end,
             translation_frame_start: _,
+            coding_silent_mutations: _,
         } = self;
         format!("{start}{original}{end}").parse().unwrap()
     }
@@ -125,6 +140,7 @@ where
             edited: _,
             end: _,
             translation_frame_start: _,
+            coding_silent_mutations: _,
         } = self;
         let range = start.len()..start.len() + original.len();
         let range = u64::try_from(range.start).unwrap()..u64::try_from(range.end).unwrap();
@@ -164,6 +180,7 @@ where
             edited,
             end,
             translation_frame_start: _,
+            coding_silent_mutations: _,
         } = self;
         format!("{start}{edited}{end}").parse().unwrap()
     }
@@ -185,6 +202,7 @@ where
             edited,
             end: _,
             translation_frame_start: _,
+            coding_silent_mutations: _,
         } = self;
         let range = start.len()..start.len() + edited.len();
         range.start.u64_unwrap()..range.end.u64_unwrap()
@@ -419,6 +437,9 @@ where
     pub fn original_silent_mutations(
         &self,
     ) -> Option<impl Iterator<Item = SilentMutation<C, DnaBase>>> {
+        if let Some(mutations) = &self.coding_silent_mutations {
+            return Some(mutations.clone().into_iter());
+        }
         Some(
             self.original()
                 .silent_mutations(self.translation_frame_start.clone()?)
@@ -430,6 +451,18 @@ where
     pub fn edited_silent_mutations(
         &self,
     ) -> Option<impl Iterator<Item = SilentMutation<EditedContig<C>, DnaBase>>> {
+        if let Some(mutations) = &self.coding_silent_mutations {
+            let edited_contig = self.edited_contig();
+            let mutations = mutations.iter().map(|m| SilentMutation {
+                at: edited_contig
+                    .clone()
+                    .liftover(m.at.clone())
+                    .expect("coding silent mutations do not overlap the edit"),
+                original: m.original,
+                edited: m.edited,
+            });
+            return Some(mutations.collect::<Vec<_>>().into_iter());
+        }
         Some(
             self.edited()
                 .silent_mutations(self.translation_frame_start_edited()?)
@@ -7,11 +7,17 @@ use utile::num::TryU64;
 const DEFAULT_SCAFFOLD: &str =
     "GTTTTAGAGCTAGAAATAGCAAGTTAAAATAAGGCTAGTCCGTTATCAACTTGAAAAAGTGGCACCGAGTCGGTGC";
 
+/// The parts of a prime editor that matter for design.
+///
+/// Custom editors can be loaded with [Self::from_json] or [Self::from_toml]. The PAM pattern and
+/// scaffold are required, the nick distance and spacer size default to the SpCas9 values.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[derive(Serialize, Deserialize)]
 pub struct Editor {
     pub pam_pattern: IupacDnaSequence,
+    #[serde(default = "default_nick_distance")]
     pub nick_distance: u64,
+    #[serde(default = "default_spacer_size")]
     pub spacer_size: u64,
     pub scaffold: DnaSequence,
 }
@@ -19,12 +25,137 @@ impl Editor {
     pub fn sp_cas9() -> Self {
         Self {
             pam_pattern: "NGG".parse().unwrap(),
-            nick_distance: 3,
-            spacer_size: 20,
+            nick_distance: default_nick_distance(),
+            spacer_size: default_spacer_size(),
             scaffold: DEFAULT_SCAFFOLD.parse().unwrap(),
         }
     }
+    /// SpCas9-NG (Nishimasu et al. 2018), recognising NG PAMs, as in PE2-NG and PEmax-NG.
+    pub fn sp_cas9_ng() -> Self {
+        Self {
+            pam_pattern: "NGN".parse().unwrap(),
+            ..Self::sp_cas9()
+        }
+    }
+    /// The near-PAMless SpRY (Walton et al. 2020), which strongly prefers NRN over NYN PAMs.
+    pub fn sp_ry() -> Self {
+        Self {
+            pam_pattern: "NRN".parse().unwrap(),
+            ..Self::sp_cas9()
+        }
+    }
+
+    /// Published editors, by name.
+    ///
+    /// Editors that only differ in their Cas9 or reverse transcriptase mutations (e.g. PE2 and
+    /// PEmax) are designed for in the same way.
+    pub fn library() -> Vec<(&'static str, Self)> {
+        vec![
+            // https://pmc.ncbi.nlm.nih.gov/articles/PMC6907074/
+            ("PE2", Self::sp_cas9()),
+            // Chen et al. 2021.
+            ("PEmax", Self::sp_cas9()),
+            ("PE2-NG", Self::sp_cas9_ng()),
+            ("PEmax-NG", Self::sp_cas9_ng()),
+            ("PE2-SpRY", Self::sp_ry()),
+            ("PEmax-SpRY", Self::sp_ry()),
+        ]
+    }
+    /// See [Self::library], the name is case-insensitive.
+    pub fn named(name: &str) -> Option<Self> {
+        Self::library()
+            .into_iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, editor)| editor)
+    }
+    /// Either the name of a [library](Self::library) editor (e.g. `"PEmax"`), or a custom editor
+    /// (e.g. `{ "pam_pattern": "NNGRRT", "spacer_size": 21, "scaffold": "GTTTTAGAGC..." }`).
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Definition {
+            Named(String),
+            Custom(Editor),
+        }
+        match serde_json::from_str(json)? {
+            Definition::Named(name) => Self::named(&name)
+                .ok_or_else(|| serde::de::Error::custom(format!("Unknown editor {name:?}."))),
+            Definition::Custom(editor) => Ok(editor),
+        }
+    }
+    /// Like [Self::from_json], a library editor is given as `name = "PEmax"`.
+    pub fn from_toml(toml: &str) -> Result<Self, toml::de::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Definition {
+            Named { name: String },
+            Custom(Editor),
+        }
+        match toml::from_str(toml)? {
+            Definition::Named { name } => Self::named(&name)
+                .ok_or_else(|| serde::de::Error::custom(format!("Unknown editor {name:?}."))),
+            Definition::Custom(editor) => Ok(editor),
+        }
+    }
+
     pub fn pam_size(&self) -> u64 {
         self.pam_pattern.len().u64_unwrap()
     }
 }
+
+fn default_nick_distance() -> u64 {
+    3
+}
+fn default_spacer_size() -> u64 {
+    20
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_json() {
+        assert_eq!(Editor::from_json(r#""pemax""#).unwrap(), Editor::sp_cas9());
+        assert!(Editor::from_json(r#""PE9""#).is_err());
+
+        let editor = Editor::from_json(&format!(
+            r#"{{ "pam_pattern": "NNGRRT", "spacer_size": 21, "scaffold": "{DEFAULT_SCAFFOLD}" }}"#
+        ))
+        .unwrap();
+        assert_eq!(editor.pam_size(), 6);
+        assert_eq!(editor.spacer_size, 21);
+        assert_eq!(editor.nick_distance, 3);
+        assert_eq!(editor.scaffold, Editor::sp_cas9().scaffold);
+
+        // No silent SpCas9 scaffold for other Cas9s.
+        assert!(Editor::from_json(r#"{ "pam_pattern": "NNGRRT", "spacer_size": 21 }"#).is_err());
+    }
+
+    #[test]
+    fn test_from_toml() {
+        assert_eq!(
+            Editor::from_toml(r#"name = "PEmax""#).unwrap(),
+            Editor::sp_cas9()
+        );
+        assert!(Editor::from_toml(r#"name = "PE9""#).is_err());
+
+        let editor = Editor::from_toml(
+            r#"
+pam_pattern = "NNGRRT"
+spacer_size = 21
+scaffold = "GTTTTAGAGCTA"
+"#,
+        )
+        .unwrap();
+        assert_eq!(editor.pam_size(), 6);
+        assert_eq!(editor.spacer_size, 21);
+        assert_eq!(editor.nick_distance, 3);
+        assert_eq!(
+            editor.scaffold,
+            "GTTTTAGAGCTA".parse::<DnaSequence>().unwrap()
+        );
+
+        assert!(Editor::from_toml(r#"pam_pattern = "NNGRRT""#).is_err());
+    }
+}
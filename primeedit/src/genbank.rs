@@ -0,0 +1,261 @@
+//! Annotated GenBank records of a [Design], for viewing in sequence editors.
+//!
+//! See https://www.ncbi.nlm.nih.gov/genbank/samplerecord/ for the format.
+
+use std::{fmt::Write, ops::Range};
+
+use crate::Design;
+use biocore::{
+    genome::Contig,
+    location::{
+        ContigPosition, ContigRange,
+        orientation::{SequenceOrientation, Stranded},
+    },
+};
+
+/// Column of the feature qualifiers.
+const QUALIFIER_INDENT: usize = 21;
+
+struct Feature {
+    key: &'static str,
+    location: String,
+    qualifiers: Vec<(&'static str, String)>,
+}
+
+impl<C> Design<C>
+where
+    C: Contig + Clone,
+{
+    /// A GenBank record of the original sequence, named after its contig, with features for the
+    /// spacer, PAM, nick, PBS, RTT, edit, and silent mutations.
+    ///
+    /// The RTT and silent mutations are mapped back from the edited sequence.
+    ///
+    /// `None` if the design doesn't fit in the edit sequence.
+    pub fn to_genbank(&self) -> Option<String> {
+        let edit = &self.edit;
+        let original = edit.original();
+        let edit_in_original = edit.edit_range_in_original().v.at;
+        let edit_in_edited = edit.edit_range_in_edited().v.at;
+        let to_original = |at| to_original(at, &edit_in_original, &edit_in_edited);
+
+        let mut features = vec![
+            Feature::label("misc_feature", location(self.spacer()?), "spacer"),
+            Feature::label("misc_feature", location(self.pam.clone()), "PAM"),
+            Feature::label("misc_feature", location(nick(self.nick()?)), "nick"),
+            Feature::label("primer_bind", location(self.primer_binding_site()?), "PBS"),
+        ];
+
+        let nick_in_edited = self.nick_in_edited()?;
+        let rtt = Stranded {
+            orientation: self.pam.orientation,
+            v: ContigRange {
+                contig: edit.edited_contig(),
+                at: nick_in_edited.v.at
+                    ..self.full_edited_range_in_edited().v.at.end + self.rtt_template_homology,
+            },
+        };
+        let orientation = rtt.orientation;
+        let at = rtt.into_forward().v.at;
+        let rtt = Stranded {
+            orientation: SequenceOrientation::Forward,
+            v: ContigRange {
+                contig: edit.original_contig(),
+                at: to_original(at),
+            },
+        }
+        .into_orientation(orientation);
+        features.push(Feature::label("misc_feature", location(rtt), "RTT"));
+
+        let mut replace = edit.get_edited(edit.edit_range_in_edited()).encode();
+        replace.make_ascii_lowercase();
+        let mut edit_feature =
+            Feature::label("variation", location(edit.edit_range_in_original()), "edit");
+        edit_feature.qualifiers.push(("replace", replace));
+        features.push(edit_feature);
+
+        let mut edited = edit.edited();
+        for m in &self.distruptions {
+            m.apply(&mut edited);
+        }
+        for m in &self.distruptions {
+            let at = m.affected_range().into_forward().v;
+            let mut replace = edited[at.clone()].to_owned().encode();
+            replace.make_ascii_lowercase();
+            let range = Stranded {
+                orientation: SequenceOrientation::Forward,
+                v: ContigRange {
+                    contig: edit.original_contig(),
+                    at: to_original(at.at),
+                },
+            };
+            let mut feature = Feature::label("variation", location(range), "silent mutation");
+            feature.qualifiers.push(("replace", replace));
+            features.push(feature);
+        }
+
+        let mut name: String = edit
+            .original_contig()
+            .as_ref()
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || "._-".contains(c) {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        if name.is_empty() {
+            name = "design".to_owned();
+        }
+
+        let mut record = String::new();
+        let len = original.len();
+        writeln!(
+            record,
+            "LOCUS       {name:<16} {len:>11} bp    DNA     linear   SYN"
+        )
+        .unwrap();
+        writeln!(record, "DEFINITION  Prime editing design for {name}.").unwrap();
+        writeln!(record, "FEATURES             Location/Qualifiers").unwrap();
+        writeln!(record, "     {:<16}1..{len}", "source").unwrap();
+        for Feature {
+            key,
+            location,
+            qualifiers,
+        } in features
+        {
+            writeln!(record, "     {key:<16}{location}").unwrap();
+            for (qualifier, value) in qualifiers {
+                writeln!(record, "{:QUALIFIER_INDENT$}/{qualifier}=\"{value}\"", "").unwrap();
+            }
+        }
+        writeln!(record, "ORIGIN").unwrap();
+        let mut sequence = original.encode();
+        sequence.make_ascii_lowercase();
+        for (i, line) in sequence.as_bytes().chunks(60).enumerate() {
+            write!(record, "{:>9}", i * 60 + 1).unwrap();
+            for block in line.chunks(10) {
+                write!(record, " {}", std::str::from_utf8(block).unwrap()).unwrap();
+            }
+            writeln!(record).unwrap();
+        }
+        writeln!(record, "//").unwrap();
+        Some(record)
+    }
+}
+
+impl Feature {
+    fn label(key: &'static str, location: String, label: &str) -> Self {
+        Self {
+            key,
+            location,
+            qualifiers: vec![("label", label.to_owned())],
+        }
+    }
+}
+
+/// A GenBank location, 1-based and inclusive, empty ranges are a site between two bases.
+fn location<C: Contig>(range: Stranded<ContigRange<C>>) -> String {
+    let orientation = range.orientation;
+    let at = range.into_forward().v.at;
+    let span = if at.is_empty() {
+        format!("{}^{}", at.start, at.start + 1)
+    } else if at.end - at.start == 1 {
+        format!("{}", at.end)
+    } else {
+        format!("{}..{}", at.start + 1, at.end)
+    };
+    if orientation.is_forward() {
+        span
+    } else {
+        format!("complement({span})")
+    }
+}
+
+/// Maps a range in the edited sequence back to the original one.
+///
+/// Positions outside of the edit are shifted by the difference in length, range bounds
+/// inside of it are widened to the whole edit.
+fn to_original(
+    at: Range<u64>,
+    edit_in_original: &Range<u64>,
+    edit_in_edited: &Range<u64>,
+) -> Range<u64> {
+    let shift = |at: u64| at - edit_in_edited.end + edit_in_original.end;
+    let start = if at.start <= edit_in_edited.start {
+        at.start
+    } else if edit_in_edited.end <= at.start {
+        shift(at.start)
+    } else {
+        edit_in_original.start
+    };
+    let end = if at.end <= edit_in_edited.start {
+        at.end
+    } else if edit_in_edited.end <= at.end {
+        shift(at.end)
+    } else {
+        edit_in_original.end
+    };
+    start..end
+}
+
+/// The nick as an empty range, between the two bases it separates.
+fn nick<C>(nick: Stranded<ContigPosition<C>>) -> Stranded<ContigRange<C>> {
+    nick.map_value(|p| ContigRange {
+        contig: p.contig,
+        at: p.at..p.at,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{design_spec::DesignSpec, edit::Edit};
+
+    use super::to_original;
+
+    #[test]
+    fn test_to_genbank() {
+        let spec = DesignSpec::default();
+        let edit =
+            Edit::parse("ACGTTGCAGCTAGGCATCGATCGATGGAAT(C/T)AAGCTAGCATCGATCGACTAGCA").unwrap();
+        let design = edit
+            .pams(&spec.editor)
+            .into_iter()
+            .filter_map(|pam| spec.clone().designs(edit.clone(), pam))
+            .flatten()
+            .next()
+            .unwrap();
+
+        let record = design.to_genbank().unwrap();
+        let lines: Vec<_> = record.lines().collect();
+        assert!(lines[0].starts_with("LOCUS "));
+        assert!(lines[0].contains(" 54 bp "));
+        assert_eq!(lines.last(), Some(&"//"));
+        for label in ["spacer", "PAM", "nick", "PBS", "RTT", "edit"] {
+            assert!(record.contains(&format!("/label=\"{label}\"")), "{label}");
+        }
+        assert!(record.contains("     variation       31\n"));
+        assert!(record.contains("/replace=\"t\""));
+        assert!(record.contains("        1 acgttgcagc tagg"));
+    }
+
+    #[test]
+    fn test_to_original() {
+        // A 2 base deletion at 10..12, and a 3 base insertion at 10.
+        let deletion = |at| to_original(at, &(10..12), &(10..10));
+        let insertion = |at| to_original(at, &(10..10), &(10..13));
+
+        // Ending right at the edit.
+        assert_eq!(deletion(5..10), 5..10);
+        assert_eq!(insertion(5..10), 5..10);
+        // Starting right after it.
+        assert_eq!(deletion(10..15), 10..17);
+        assert_eq!(insertion(13..15), 10..12);
+        // Overlapping it.
+        assert_eq!(deletion(5..15), 5..17);
+        assert_eq!(insertion(11..12), 10..10);
+        assert_eq!(insertion(5..11), 5..10);
+    }
+}
@@ -1,6 +1,17 @@
+pub mod baseedit;
+pub mod batch;
+mod coding;
 pub mod design_spec;
 pub mod edit;
 pub mod editor;
+mod genbank;
+pub mod offtarget;
+pub mod oligo;
+pub mod pipeline;
+pub mod restriction;
+pub mod scoring;
+pub mod tm;
+pub mod twin;
 
 use std::{collections::HashSet, hash::Hash, ops::Range};
 
@@ -306,6 +317,8 @@ where
             avoid_rtt_cytosine,
             require_seed_or_pam_disruption,
             minimum_edit_size,
+            pbs_tm_range,
+            require_restriction_site_change,
         } = spec;
         {
             let Self {
@@ -348,6 +361,12 @@ where
                 true
             }
             && minimum_edit_size.is_none_or(|min| min <= self.full_edited_range_in_edited().v.len())
+            && pbs_tm_range
+                .as_ref()
+                .is_none_or(|range| range.contains(&self.pbs_tm().expect("sizing")))
+            && require_restriction_site_change
+                .as_ref()
+                .is_none_or(|enzymes| !self.restriction_sites(enzymes).is_empty())
     }
     fn is_in_range(&self) -> bool {
         self.spacer().is_some()
@@ -390,6 +409,8 @@ where
             avoid_rtt_cytosine,
             require_seed_or_pam_disruption,
             minimum_edit_size,
+            pbs_tm_range,
+            require_restriction_site_change,
         } = spec;
         {
             let Self {
@@ -427,6 +448,12 @@ where
         if let Some(minimum_edit_size) = minimum_edit_size {
             assert!(*minimum_edit_size <= self.full_edited_range_in_edited().v.len());
         }
+        if let Some(pbs_tm_range) = pbs_tm_range {
+            assert!(pbs_tm_range.contains(&self.pbs_tm().expect("sizing")));
+        }
+        if let Some(enzymes) = require_restriction_site_change {
+            assert!(!self.restriction_sites(enzymes).is_empty());
+        }
     }
     fn assert_in_range(&self) {
         assert!(self.spacer().is_some());
@@ -0,0 +1,359 @@
+//! Off-target sites of a spacer, and their MIT and CFD specificity scores.
+//!
+//! The search is a plain scan for PAMs followed by a protospacer with few enough mismatches, it
+//! is meant for loci or small genomes rather than whole human chromosomes.
+
+use std::ops::Range;
+
+use biocore::{
+    dna::{Complement, DnaBase, DnaSequence},
+    genome::Contig,
+    location::orientation::SequenceOrientation,
+};
+use serde::{Deserialize, Serialize};
+use utile::{
+    num::{TryI64, TryU64, TryUsize},
+    regex_ext::find_iter_overlapping,
+};
+
+use crate::{Design, edit::cached_regex, editor::Editor};
+
+/// Mismatch weights by protospacer position, PAM-distal first (Hsu et al. 2013).
+const MIT_WEIGHTS: [f64; 20] = [
+    0., 0., 0.014, 0., 0., 0.395, 0.317, 0., 0.389, 0.079, 0.445, 0.508, 0.613, 0.851, 0.732,
+    0.828, 0.615, 0.804, 0.685, 0.583,
+];
+
+/// The pairs of [CFD_MISMATCHES], as spacer base (`T` for the RNA `U`) and target strand DNA base.
+pub const CFD_MISMATCH_PAIRS: [(DnaBase, DnaBase); 12] = [
+    (DnaBase::A, DnaBase::A),
+    (DnaBase::A, DnaBase::C),
+    (DnaBase::A, DnaBase::G),
+    (DnaBase::C, DnaBase::A),
+    (DnaBase::C, DnaBase::C),
+    (DnaBase::C, DnaBase::T),
+    (DnaBase::G, DnaBase::A),
+    (DnaBase::G, DnaBase::G),
+    (DnaBase::G, DnaBase::T),
+    (DnaBase::T, DnaBase::C),
+    (DnaBase::T, DnaBase::G),
+    (DnaBase::T, DnaBase::T),
+];
+/// CFD activity of a single mismatch, for each of [CFD_MISMATCH_PAIRS] by position, PAM-distal
+/// first (Doench et al. 2016, supplementary table 19).
+#[rustfmt::skip]
+pub const CFD_MISMATCHES: [[f64; 20]; 12] = [
+    // rA:dA
+    [
+        1., 0.727272727, 0.705882353, 0.636363636, 0.363636364,
+        0.714285714, 0.4375, 0.428571429, 0.6, 0.882352941,
+        0.307692308, 0.333333333, 0.3, 0.533333333, 0.2,
+        0., 0.133333333, 0.5, 0.538461538, 0.6,
+    ],
+    // rA:dC
+    [
+        1., 0.8, 0.611111111, 0.625, 0.72,
+        0.714285714, 0.705882353, 0.733333333, 0.666666667, 0.555555556,
+        0.65, 0.722222222, 0.652173913, 0.466666667, 0.65,
+        0.192307692, 0.176470588, 0.4, 0.375, 0.764705882,
+    ],
+    // rA:dG
+    [
+        0.857142857, 0.785714286, 0.428571429, 0.352941176, 0.5,
+        0.454545455, 0.4375, 0.428571429, 0.571428571, 0.333333333,
+        0.4, 0.263157895, 0.210526316, 0.214285714, 0.272727273,
+        0., 0.176470588, 0.19047619, 0.206896552, 0.227272727,
+    ],
+    // rC:dA
+    [
+        1., 0.727272727, 0.866666667, 0.842105263, 0.571428571,
+        0.928571429, 0.8125, 0.875, 0.875, 0.941176471,
+        0.307692308, 0.538461538, 0.7, 0.733333333, 0.066666667,
+        0.307692308, 0.466666667, 0.642857143, 0.461538462, 0.3,
+    ],
+    // rC:dC
+    [
+        0.913043478, 0.695652174, 0.5, 0.5, 0.6,
+        0.5, 0.470588235, 0.642857143, 0.619047619, 0.388888889,
+        0.25, 0.444444444, 0.136363636, 0., 0.05,
+        0.153846154, 0.058823529, 0.133333333, 0.125, 0.058823529,
+    ],
+    // rC:dT
+    [
+        1., 0.8, 0.65, 0.764705882, 0.5,
+        0.9375, 0.8125, 0.6875, 0.636363636, 0.8125,
+        0.642857143, 0.684210526, 0.65, 0.5, 0.071428571,
+        0.428571429, 0.647058824, 0.6, 0.533333333, 0.,
+    ],
+    // rG:dA
+    [
+        0.857142857, 0.9, 0.5, 0.5, 0.6,
+        0.428571429, 0.375, 0.428571429, 0.333333333, 0.25,
+        0.4, 0.352941176, 0.111111111, 0.214285714, 0.266666667,
+        0., 0.142857143, 0., 0.448275862, 0.153846154,
+    ],
+    // rG:dG
+    [
+        0.714285714, 0.692307692, 0.384615385, 0.529411765, 0.4375,
+        0.8, 0.6875, 0.571428571, 0.538461538, 0.4,
+        0.428571429, 0.529411765, 0.421052632, 0.428571429, 0.272727273,
+        0., 0.176470588, 0.235294118, 0.421052632, 0.5,
+    ],
+    // rG:dT
+    [
+        0.9, 0.846153846, 0.75, 0.9, 0.866666667,
+        1., 0.9375, 1., 0.692307692, 0.933333333,
+        1., 0.933333333, 0.923076923, 0.75, 0.941176471,
+        0.666666667, 0.933333333, 0.692307692, 0.714285714, 0.9375,
+    ],
+    // rU:dC
+    [
+        0.956521739, 0.84, 0.5, 0.625, 0.8,
+        0.75, 0.571428571, 0.875, 0.857142857, 0.5,
+        0.375, 0.5, 0.55, 0.409090909, 0.05,
+        0.538461538, 0.588235294, 0.5, 0.409090909, 0.5,
+    ],
+    // rU:dG
+    [
+        0.9, 0.84, 0.947368421, 0.8, 0.923076923,
+        0.916666667, 0.8125, 0.875, 0.928571429, 0.642857143,
+        0.785714286, 0.692307692, 0.9, 0.6875, 0.909090909,
+        0.6875, 0.8125, 0.8, 0.5, 0.5,
+    ],
+    // rU:dT
+    [
+        1., 0.846153846, 0.578947368, 0.7, 0.8,
+        0.933333333, 0.9375, 0.8, 0.75, 0.8125,
+        0.75, 0.714285714, 0.384615385, 0.35, 0.222222222,
+        0., 0.176470588, 0., 0.25, 0.15,
+    ],
+];
+/// CFD activity by the last two bases of the PAM, any other PAM scores `0` (Doench et al. 2016).
+pub const CFD_PAMS: [([DnaBase; 2], f64); 7] = [
+    ([DnaBase::G, DnaBase::G], 1.),
+    ([DnaBase::A, DnaBase::G], 0.259259259),
+    ([DnaBase::C, DnaBase::G], 0.107142857),
+    ([DnaBase::G, DnaBase::A], 0.069444444),
+    ([DnaBase::T, DnaBase::G], 0.038961039),
+    ([DnaBase::G, DnaBase::C], 0.022222222),
+    ([DnaBase::G, DnaBase::T], 0.016129032),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize)]
+pub struct OffTarget {
+    pub orientation: SequenceOrientation,
+    /// The protospacer and PAM, in forward coordinates of the searched sequence.
+    pub at: Range<u64>,
+    /// On the strand of the PAM.
+    pub protospacer: DnaSequence,
+    pub pam: DnaSequence,
+}
+
+pub trait OffTargetScorer {
+    /// The predicted cutting activity at `hit` relative to a perfect match, in `[0, 1]`.
+    fn score(&self, spacer: &[DnaBase], hit: &OffTarget) -> f64;
+}
+
+/// The MIT (Hsu et al. 2013) score, from the positions and spread of the mismatches.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize)]
+pub struct Mit;
+
+/// The Cutting Frequency Determination score (Doench et al. 2016), see [CFD_MISMATCHES] and
+/// [CFD_PAMS].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize)]
+pub struct Cfd;
+
+impl OffTarget {
+    /// 0-based mismatching positions from the PAM-distal end.
+    pub fn mismatches(&self, spacer: &[DnaBase]) -> Vec<usize> {
+        assert_eq!(spacer.len(), self.protospacer.len());
+        spacer
+            .iter()
+            .zip(self.protospacer.iter())
+            .enumerate()
+            .filter(|(_, (a, b))| a != b)
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
+
+/// The sites of `sequence`, on either strand, with a PAM of `editor` and at most `max_mismatches`
+/// to `spacer` (without any forced 5' G).
+pub fn search(
+    spacer: &[DnaBase],
+    editor: &Editor,
+    sequence: &[DnaBase],
+    max_mismatches: usize,
+) -> Vec<OffTarget> {
+    let regex = cached_regex(&editor.pam_pattern);
+    let spacer_size = spacer.len();
+    let len = sequence.len();
+
+    let mut hits = vec![];
+    for orientation in [SequenceOrientation::Forward, SequenceOrientation::Reverse] {
+        let strand: DnaSequence = match orientation {
+            SequenceOrientation::Forward => sequence.iter().copied().collect(),
+            SequenceOrientation::Reverse => sequence.iter().rev().map(|b| b.complement()).collect(),
+        };
+        for pam in find_iter_overlapping(&regex, &strand.encode()) {
+            let pam = pam.start.usize_unwrap()..pam.end.usize_unwrap();
+            let Some(start) = pam.start.checked_sub(spacer_size) else {
+                continue;
+            };
+            let protospacer = &strand[start..pam.start];
+            let mismatches = spacer.iter().zip(protospacer).filter(|(a, b)| a != b);
+            if mismatches.count() > max_mismatches {
+                continue;
+            }
+            let at = match orientation {
+                SequenceOrientation::Forward => start..pam.end,
+                SequenceOrientation::Reverse => len - pam.end..len - start,
+            };
+            hits.push(OffTarget {
+                orientation,
+                at: at.start.u64_unwrap()..at.end.u64_unwrap(),
+                protospacer: protospacer.iter().copied().collect(),
+                pam: strand[pam].iter().copied().collect(),
+            });
+        }
+    }
+    hits.sort_by_key(|hit| (hit.at.start, hit.orientation));
+    hits
+}
+
+/// The aggregate specificity of a guide, from `100` (no off-targets) down to `0`, as popularised
+/// by the MIT design tool and CRISPOR: `100 / (1 + Σ scores)`.
+pub fn specificity(scores: impl IntoIterator<Item = f64>) -> f64 {
+    100. / (1. + scores.into_iter().sum::<f64>())
+}
+
+impl<C> Design<C>
+where
+    C: Contig + Clone,
+{
+    /// The [specificity] of the spacer against the off-targets in `sequence`.
+    ///
+    /// One perfect match is assumed to be the on-target and is skipped, any other counts.
+    /// `None` if the design doesn't fit in the edit sequence.
+    pub fn specificity(
+        &self,
+        sequence: &[DnaBase],
+        max_mismatches: usize,
+        scorer: &impl OffTargetScorer,
+    ) -> Option<f64> {
+        let spacer = self.edit.get_original(self.spacer()?);
+        let mut on_target = false;
+        let scores = search(&spacer, &self.editor, sequence, max_mismatches)
+            .into_iter()
+            .filter(|hit| {
+                let skip = !on_target && hit.protospacer == spacer;
+                on_target |= skip;
+                !skip
+            })
+            .map(|hit| scorer.score(&spacer, &hit))
+            .collect::<Vec<_>>();
+        Some(specificity(scores))
+    }
+}
+
+impl OffTargetScorer for Mit {
+    fn score(&self, spacer: &[DnaBase], hit: &OffTarget) -> f64 {
+        let mismatches = hit.mismatches(spacer);
+        let n = mismatches.len();
+        if n == 0 {
+            return 1.;
+        }
+        // Align to the PAM-proximal end, for spacers that aren't 20 bases long.
+        let offset = MIT_WEIGHTS.len().i64_unwrap() - spacer.len().i64_unwrap();
+        let weights: f64 = mismatches
+            .iter()
+            .map(|&i| {
+                let i = i.i64_unwrap() + offset;
+                let weight = usize::try_from(i).map_or(0., |i| MIT_WEIGHTS[i]);
+                1. - weight
+            })
+            .product();
+        let mean_distance = if n < 2 {
+            19.
+        } else {
+            (mismatches[n - 1] - mismatches[0]) as f64 / (n - 1) as f64
+        };
+        let spread = 1. / ((19. - mean_distance) / 19. * 4. + 1.);
+        weights * spread / (n * n) as f64
+    }
+}
+
+impl OffTargetScorer for Cfd {
+    fn score(&self, spacer: &[DnaBase], hit: &OffTarget) -> f64 {
+        // Align to the PAM-proximal end, for spacers that aren't 20 bases long.
+        let offset = 20 - spacer.len().i64_unwrap();
+        let mismatches: f64 = hit
+            .mismatches(spacer)
+            .into_iter()
+            .map(|i| {
+                let pair = (spacer[i], hit.protospacer[i].complement());
+                let pair = CFD_MISMATCH_PAIRS.iter().position(|p| *p == pair);
+                let position = usize::try_from(i.i64_unwrap() + offset).ok();
+                match (pair, position) {
+                    (Some(pair), Some(position)) => CFD_MISMATCHES[pair][position],
+                    _ => 1.,
+                }
+            })
+            .product();
+        let pam: &[DnaBase] = &hit.pam[hit.pam.len().saturating_sub(2)..];
+        let pam = CFD_PAMS.iter().find(|(bases, _)| bases.as_slice() == pam);
+        mismatches * pam.map_or(0., |(_, score)| *score)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seq(s: &str) -> DnaSequence {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_search_and_score() {
+        let spacer = seq("GACTTCAGTACGATCGTACG");
+        // The on-target, a reverse strand hit with a PAM-proximal mismatch, and an unrelated PAM.
+        let one_mismatch = seq("GACTTCAGTACGATCGTACC").reverse_complement();
+        let sequence = seq(&format!(
+            "TT{spacer}TGGAAAACCT{one_mismatch}AAAAGGAAAAAAAAAAAAAAAAAAAAAAAA",
+            one_mismatch = one_mismatch.encode()
+        ));
+
+        let hits = search(&spacer, &Editor::sp_cas9(), &sequence, 2);
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].at, 2..25);
+        assert_eq!(hits[0].protospacer, spacer);
+        assert!(hits[1].orientation.is_reverse());
+        assert_eq!(hits[1].mismatches(&spacer), vec![19]);
+        assert_eq!(hits[1].pam, seq("AGG"));
+
+        assert_eq!(Mit.score(&spacer, &hits[0]), 1.);
+        let mit = Mit.score(&spacer, &hits[1]);
+        assert!((mit - (1. - 0.583)).abs() < 1e-9);
+
+        assert_eq!(Cfd.score(&spacer, &hits[0]), 1.);
+        // rG:dG,20 with an NGG PAM.
+        assert_eq!(Cfd.score(&spacer, &hits[1]), 0.5);
+
+        // A perfect protospacer next to an NAG PAM, and with two more mismatches.
+        let mut nag = OffTarget {
+            pam: seq("TAG"),
+            ..hits[0].clone()
+        };
+        assert!((Cfd.score(&spacer, &nag) - 0.259259259).abs() < 1e-9);
+        // rG:dT,1 and rA:dC,13
+        nag.protospacer = seq("AACTTCAGTACGGTCGTACG");
+        assert!((Cfd.score(&spacer, &nag) - 0.9 * 0.652173913 * 0.259259259).abs() < 1e-9);
+
+        assert_eq!(specificity([]), 100.);
+        assert!((specificity([mit]) - 100. / (1. + mit)).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,285 @@
+//! Orderable oligos to clone a [Design] into an expression vector.
+//!
+//! The Golden Gate layout follows the pegRNA cloning protocol of
+//! https://pmc.ncbi.nlm.nih.gov/articles/PMC6907074/ (pU6-pegRNA-GG-acceptor, Addgene #132777):
+//! three annealed oligo pairs for the spacer, scaffold and 3' extension.
+
+use std::io;
+
+use biocore::{
+    dna::{DnaBase, DnaSequence},
+    genome::Contig,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::Design;
+
+/// A single-stranded oligo, 5' → 3'.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize)]
+pub struct Oligo {
+    pub name: String,
+    pub sequence: DnaSequence,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize)]
+pub struct OligoSpec {
+    pub assembly: Assembly,
+    /// Prepend a G to spacers that don't already start with one, for U6 transcription.
+    ///
+    /// An alternative to [Design::force_5_prime_g], which replaces the first base instead.
+    pub prepend_g: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize)]
+pub enum Assembly {
+    /// Annealed top/bottom pairs for the spacer, scaffold and 3' extension.
+    ///
+    /// The scaffold is split after its 5th and before its last 4 bases, which serve as the
+    /// overhangs between the fragments.
+    GoldenGate {
+        /// The overhang of the vector before the spacer, on the top strand.
+        five_prime: DnaSequence,
+        /// The overhang of the vector after the 3' extension, on the top strand.
+        three_prime: DnaSequence,
+    },
+    /// The whole guide as a single fragment, with homology arms matching the ends of the
+    /// linearised vector.
+    Gibson {
+        left_homology: DnaSequence,
+        right_homology: DnaSequence,
+    },
+}
+
+impl Default for OligoSpec {
+    fn default() -> Self {
+        Self {
+            assembly: Assembly::golden_gate(),
+            prepend_g: true,
+        }
+    }
+}
+impl Assembly {
+    /// The overhangs of pU6-pegRNA-GG-acceptor digested with BsaI.
+    pub fn golden_gate() -> Self {
+        Self::GoldenGate {
+            five_prime: "CACC".parse().unwrap(),
+            three_prime: "TTTT".parse().unwrap(),
+        }
+    }
+}
+
+impl<C> Design<C>
+where
+    C: Contig + Clone,
+{
+    /// The oligos to order for this design, named after `name`.
+    ///
+    /// `None` if the design doesn't fit in the edit sequence, or if the scaffold is too short
+    /// to be split into Golden Gate oligos.
+    pub fn oligos(&self, name: &str, spec: &OligoSpec) -> Option<Vec<Oligo>> {
+        let mut spacer = self.spacer_sequence()?;
+        if spec.prepend_g && spacer.first() != Some(&DnaBase::G) {
+            spacer = concat(&[&[DnaBase::G], &spacer]);
+        }
+        let scaffold = &self.editor.scaffold;
+        let extension = concat(&[
+            &self.reverse_transcriptase_template()?,
+            &self.primer_sequence()?,
+        ]);
+
+        let oligo = |suffix: &str, sequence: DnaSequence| Oligo {
+            name: format!("{name}_{suffix}"),
+            sequence,
+        };
+        let oligos = match &spec.assembly {
+            Assembly::GoldenGate {
+                five_prime,
+                three_prime,
+            } => {
+                let split_5 = 5;
+                let split_3 = scaffold.len().checked_sub(4)?;
+                if split_5 + 4 > split_3 {
+                    return None;
+                }
+                vec![
+                    oligo(
+                        "spacer_top",
+                        concat(&[five_prime, &spacer, &scaffold[..split_5]]),
+                    ),
+                    oligo(
+                        "spacer_bottom",
+                        concat(&[&spacer, &scaffold[..split_5 + 4]]).reverse_complement(),
+                    ),
+                    oligo("scaffold_top", concat(&[&scaffold[split_5..split_3]])),
+                    oligo(
+                        "scaffold_bottom",
+                        concat(&[&scaffold[split_5 + 4..]]).reverse_complement(),
+                    ),
+                    oligo("extension_top", concat(&[&scaffold[split_3..], &extension])),
+                    oligo(
+                        "extension_bottom",
+                        concat(&[&extension, three_prime]).reverse_complement(),
+                    ),
+                ]
+            }
+            Assembly::Gibson {
+                left_homology,
+                right_homology,
+            } => vec![oligo(
+                "gibson",
+                concat(&[left_homology, &spacer, scaffold, &extension, right_homology]),
+            )],
+        };
+        Some(oligos)
+    }
+}
+
+#[derive(Serialize)]
+struct PlateRow<'a> {
+    #[serde(rename = "Plate")]
+    plate: usize,
+    #[serde(rename = "Well Position")]
+    well: String,
+    #[serde(rename = "Name")]
+    name: &'a str,
+    #[serde(rename = "Sequence")]
+    sequence: String,
+}
+
+/// Writes a 96-well plate layout, filling each plate column by column (A1, B1, …, H1, A2, …)
+/// and starting a new plate every 96 oligos.
+pub fn write_plate_csv<'a>(
+    writer: impl io::Write,
+    oligos: impl IntoIterator<Item = &'a Oligo>,
+) -> csv::Result<()> {
+    let mut writer = csv::Writer::from_writer(writer);
+    for (i, oligo) in oligos.into_iter().enumerate() {
+        writer.serialize(PlateRow {
+            plate: i / 96 + 1,
+            well: well(i % 96),
+            name: &oligo.name,
+            sequence: oligo.sequence.encode(),
+        })?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// The name of the `i`th well of a 96-well plate, column by column.
+fn well(i: usize) -> String {
+    let row = char::from(b'A' + (i % 8) as u8);
+    format!("{row}{}", i / 8 + 1)
+}
+
+fn concat(parts: &[&[DnaBase]]) -> DnaSequence {
+    parts.iter().flat_map(|part| part.iter().copied()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{design_spec::DesignSpec, edit::Edit};
+
+    #[test]
+    fn test_golden_gate() {
+        let spec = DesignSpec::default();
+        let edit =
+            Edit::parse("ACGTTGCAGCTAGGCATCGATCGATGGAAT(C/T)AAGCTAGCATCGATCGACTAGCA").unwrap();
+        let design = edit
+            .pams(&spec.editor)
+            .into_iter()
+            .filter_map(|pam| spec.clone().designs(edit.clone(), pam))
+            .flatten()
+            .next()
+            .unwrap();
+
+        let oligos = design.oligos("peg1", &OligoSpec::default()).unwrap();
+        let [
+            spacer_top,
+            spacer_bottom,
+            scaffold_top,
+            scaffold_bottom,
+            extension_top,
+            extension_bottom,
+        ] = &oligos[..]
+        else {
+            panic!("expected three pairs");
+        };
+        assert_eq!(spacer_top.name, "peg1_spacer_top");
+
+        // The top strands, without the vector overhangs, are the full guide.
+        let top = concat(&[
+            &spacer_top.sequence[4..],
+            &scaffold_top.sequence,
+            &extension_top.sequence,
+        ]);
+        assert_eq!(top, design.full_guide_sequence().unwrap());
+
+        // Each pair anneals, leaving 4 base overhangs.
+        for (top, bottom) in [
+            (spacer_top, spacer_bottom),
+            (scaffold_top, scaffold_bottom),
+            (extension_top, extension_bottom),
+        ] {
+            let top = &top.sequence;
+            let bottom = bottom.sequence.clone().reverse_complement();
+            assert_eq!(top[4..], bottom[..bottom.len() - 4]);
+        }
+        assert_eq!(
+            spacer_top.sequence[..4],
+            "CACC".parse::<DnaSequence>().unwrap()[..]
+        );
+    }
+
+    #[test]
+    fn test_short_scaffold() {
+        let spec = DesignSpec::default();
+        let edit =
+            Edit::parse("ACGTTGCAGCTAGGCATCGATCGATGGAAT(C/T)AAGCTAGCATCGATCGACTAGCA").unwrap();
+        let mut design = edit
+            .pams(&spec.editor)
+            .into_iter()
+            .filter_map(|pam| spec.clone().designs(edit.clone(), pam))
+            .flatten()
+            .next()
+            .unwrap();
+
+        let gibson = OligoSpec {
+            assembly: Assembly::Gibson {
+                left_homology: "ACGT".parse().unwrap(),
+                right_homology: "TGCA".parse().unwrap(),
+            },
+            prepend_g: true,
+        };
+        for scaffold in ["GTTTTAGAGCTA", "GTT"] {
+            design.editor.scaffold = scaffold.parse().unwrap();
+            assert!(design.oligos("peg1", &OligoSpec::default()).is_none());
+            assert!(design.oligos("peg1", &gibson).is_some(), "{scaffold}");
+        }
+    }
+
+    #[test]
+    fn test_plate_csv() {
+        assert_eq!(well(0), "A1");
+        assert_eq!(well(9), "B2");
+        assert_eq!(well(95), "H12");
+
+        let oligos: Vec<_> = (0..97)
+            .map(|i| Oligo {
+                name: format!("oligo{i}"),
+                sequence: "ACGT".parse().unwrap(),
+            })
+            .collect();
+        let mut csv = vec![];
+        write_plate_csv(&mut csv, &oligos).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines[0], "Plate,Well Position,Name,Sequence");
+        assert_eq!(lines[1], "1,A1,oligo0,ACGT");
+        assert_eq!(lines[97], "2,A1,oligo96,ACGT");
+    }
+}
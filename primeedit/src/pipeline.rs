@@ -0,0 +1,243 @@
+//! Enumerating, filtering, scoring and ranking all the candidate [Design]s of an [Edit].
+
+use std::{collections::HashSet, fmt, hash::Hash};
+
+use biocore::genome::Contig;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Design,
+    design_spec::DesignSpec,
+    edit::Edit,
+    scoring::{HeuristicScorer, Scorer},
+};
+
+/// Like [DesignSpec::designs] over all PAMs, but keeping track of why candidates were dropped.
+#[derive(Debug, Clone)]
+pub struct DesignPipeline<S = HeuristicScorer> {
+    pub spec: DesignSpec,
+    pub scorer: S,
+    /// Keep only the best designs, or all if `None`.
+    pub top_k: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PipelineOutput<C> {
+    /// Best first, see [Scorer].
+    pub designs: Vec<(Design<C>, f64)>,
+    /// In the order they were enumerated, excluding those past [DesignPipeline::top_k].
+    pub rejected: Vec<(Design<C>, Rejection)>,
+}
+
+/// Why a candidate was dropped, the first failing check is reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize)]
+pub enum Rejection {
+    /// The spacer, PBS or RTT run past the edit sequence.
+    OutOfRange,
+    /// See [DesignSpec::avoid_poly_u].
+    PolyU,
+    /// See [DesignSpec::avoid_rtt_cytosine].
+    RttCytosine,
+    /// See [DesignSpec::require_seed_or_pam_disruption].
+    NoSeedOrPamDisruption,
+    /// See [DesignSpec::minimum_edit_size].
+    EditTooSmall,
+    /// See [DesignSpec::pbs_tm_range].
+    PbsTm,
+    /// See [DesignSpec::require_restriction_site_change].
+    NoRestrictionSiteChange,
+    /// The scorer couldn't compute its features.
+    Unscorable,
+    /// Same pegRNA as a better scoring design.
+    Duplicate,
+}
+
+impl Default for DesignPipeline {
+    fn default() -> Self {
+        Self {
+            spec: DesignSpec::default(),
+            scorer: HeuristicScorer::default(),
+            top_k: Some(10),
+        }
+    }
+}
+
+impl<S: Scorer> DesignPipeline<S> {
+    pub fn run<C>(&self, edit: &Edit<C>) -> PipelineOutput<C>
+    where
+        C: Contig + Clone + Hash,
+    {
+        let mut rejected = vec![];
+        let mut ranked = vec![];
+        for design in self.candidates(edit) {
+            let score = self
+                .check(&design)
+                .and_then(|()| design.score(&self.scorer).ok_or(Rejection::Unscorable));
+            match score {
+                Ok(score) => ranked.push((design, score)),
+                Err(reason) => rejected.push((design, reason)),
+            }
+        }
+        ranked.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+        let mut seen = HashSet::new();
+        let mut designs = vec![];
+        for (design, score) in ranked {
+            let guide = design.full_guide_sequence().expect("sizing");
+            if seen.insert(guide) {
+                designs.push((design, score));
+            } else {
+                rejected.push((design, Rejection::Duplicate));
+            }
+        }
+        if let Some(top_k) = self.top_k {
+            designs.truncate(top_k);
+        }
+
+        PipelineOutput { designs, rejected }
+    }
+
+    /// Every combination of PAM, primer size, RTT homology and 5' G forcing allowed by the spec.
+    fn candidates<C>(&self, edit: &Edit<C>) -> impl Iterator<Item = Design<C>> + use<'_, C, S>
+    where
+        C: Contig + Clone,
+    {
+        let spec = &self.spec;
+        let force_5_prime_g: &[bool] = match spec.force_5_prime_g {
+            Some(true) => &[true],
+            Some(false) => &[false],
+            None => &[false, true],
+        };
+        let edit = edit.clone();
+        edit.pams(&spec.editor).into_iter().flat_map(move |pam| {
+            let edit = edit.clone();
+            spec.primer_size_range.clone().flat_map(move |primer_size| {
+                let (edit, pam) = (edit.clone(), pam.clone());
+                spec.rtt_template_homology_range
+                    .clone()
+                    .flat_map(move |rtt_template_homology| {
+                        let (edit, pam) = (edit.clone(), pam.clone());
+                        force_5_prime_g.iter().map(move |&force_5_prime_g| Design {
+                            edit: edit.clone(),
+                            editor: spec.editor.clone(),
+                            pam: pam.clone(),
+                            force_5_prime_g,
+                            primer_size,
+                            rtt_template_homology,
+                            distruptions: vec![],
+                        })
+                    })
+            })
+        })
+    }
+
+    /// The checks of [Design::is_compliant], in order.
+    fn check<C>(&self, design: &Design<C>) -> Result<(), Rejection>
+    where
+        C: Contig + Clone + Hash,
+    {
+        let spec = &self.spec;
+        if !design.is_in_range() {
+            return Err(Rejection::OutOfRange);
+        }
+        if spec.avoid_poly_u && design.has_poly_u().expect("sizing") {
+            return Err(Rejection::PolyU);
+        }
+        if spec.avoid_rtt_cytosine && design.has_rtt_cytosine().expect("sizing") {
+            return Err(Rejection::RttCytosine);
+        }
+        if spec.require_seed_or_pam_disruption
+            && !design.distrupts_seed().expect("sizing")
+            && !design.distrupts_pam().expect("sizing")
+        {
+            return Err(Rejection::NoSeedOrPamDisruption);
+        }
+        if spec
+            .minimum_edit_size
+            .is_some_and(|min| design.full_edited_range_in_edited().v.len() < min)
+        {
+            return Err(Rejection::EditTooSmall);
+        }
+        if let Some(range) = &spec.pbs_tm_range
+            && !range.contains(&design.pbs_tm().expect("sizing"))
+        {
+            return Err(Rejection::PbsTm);
+        }
+        if let Some(enzymes) = &spec.require_restriction_site_change
+            && design.restriction_sites(enzymes).is_empty()
+        {
+            return Err(Rejection::NoRestrictionSiteChange);
+        }
+        debug_assert!(design.is_compliant(spec));
+        Ok(())
+    }
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            Self::OutOfRange => "the spacer, PBS or RTT don't fit in the edit sequence",
+            Self::PolyU => "the pegRNA contains a poly(U) tract",
+            Self::RttCytosine => "the 3' extension starts with a C",
+            Self::NoSeedOrPamDisruption => "the edit disrupts neither the seed nor the PAM",
+            Self::EditTooSmall => "the edit is smaller than the minimum edit size",
+            Self::PbsTm => "the PBS melting temperature is out of range",
+            Self::NoRestrictionSiteChange => "no restriction site is created or destroyed",
+            Self::Unscorable => "the design couldn't be scored",
+            Self::Duplicate => "the pegRNA is the same as a better scoring design",
+        };
+        f.write_str(reason)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pipeline() {
+        let edit =
+            Edit::parse("ACGTTGCAGCTAGGCATCGATCGATGGAAT(C/T)AAGCTAGCATCGATCGACTAGCA").unwrap();
+        let pipeline = DesignPipeline {
+            top_k: Some(5),
+            ..DesignPipeline::default()
+        };
+        let output = pipeline.run(&edit);
+
+        assert_eq!(output.designs.len(), 5);
+        assert!(output.designs.windows(2).all(|w| w[0].1 >= w[1].1));
+        for (design, _) in &output.designs {
+            design.assert_compliant(&pipeline.spec);
+        }
+        for (design, reason) in &output.rejected {
+            assert!(*reason == Rejection::Duplicate || !design.is_compliant(&pipeline.spec));
+        }
+        assert!(
+            output
+                .rejected
+                .iter()
+                .any(|(_, reason)| *reason == Rejection::RttCytosine)
+        );
+
+        // Without a top-k, the compliant designs match those of the spec.
+        let pipeline = DesignPipeline {
+            top_k: None,
+            ..pipeline
+        };
+        let expected: HashSet<_> = edit
+            .pams(&pipeline.spec.editor)
+            .into_iter()
+            .filter_map(|pam| pipeline.spec.clone().designs(edit.clone(), pam))
+            .flatten()
+            .map(|design| design.full_guide_sequence().unwrap())
+            .collect();
+        let output = pipeline.run(&edit);
+        let found: HashSet<_> = output
+            .designs
+            .iter()
+            .map(|(design, _)| design.full_guide_sequence().unwrap())
+            .collect();
+        assert_eq!(found, expected);
+    }
+}
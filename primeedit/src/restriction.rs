@@ -0,0 +1,86 @@
+//! Restriction sites created or destroyed by a [Design], to screen clones by RFLP.
+
+use biocore::{enzyme::RestrictionEnzyme, genome::Contig};
+use serde::{Deserialize, Serialize};
+
+use crate::Design;
+
+/// The enzymes whose number of recognition sites differs between the original and edited
+/// sequences, so that digesting an amplicon of the locus tells them apart.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize)]
+pub struct RestrictionSites {
+    /// Enzymes with more sites in the edited sequence.
+    pub created: Vec<RestrictionEnzyme>,
+    /// Enzymes with fewer sites in the edited sequence.
+    pub destroyed: Vec<RestrictionEnzyme>,
+}
+
+impl<C> Design<C>
+where
+    C: Contig + Clone,
+{
+    /// The changes in restriction sites, including those from [Self::distruptions], see
+    /// [RestrictionEnzyme::common] for a default table.
+    pub fn restriction_sites(&self, enzymes: &[RestrictionEnzyme]) -> RestrictionSites {
+        let original = self.edit.original();
+        let mut edited = self.edit.edited();
+        for m in &self.distruptions {
+            m.apply(&mut edited);
+        }
+
+        let mut sites = RestrictionSites::default();
+        for enzyme in enzymes {
+            let before = enzyme.find(&original).len();
+            let after = enzyme.find(&edited).len();
+            if before < after {
+                sites.created.push(enzyme.clone());
+            } else if after < before {
+                sites.destroyed.push(enzyme.clone());
+            }
+        }
+        sites
+    }
+}
+
+impl RestrictionSites {
+    pub fn is_empty(&self) -> bool {
+        self.created.is_empty() && self.destroyed.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{design_spec::DesignSpec, edit::Edit};
+
+    #[test]
+    fn test_restriction_sites() {
+        let spec = DesignSpec {
+            avoid_rtt_cytosine: false,
+            ..DesignSpec::default()
+        };
+        // C→A creates an EcoRV site (GATATC) and destroys a DpnII site (GATC).
+        let edit =
+            Edit::parse("ACGTTGCAGCTAGGCATCGATCGATGGAAGAT(C/A)TCAAGCTAGCATCGACTAGCA").unwrap();
+        let design = edit
+            .pams(&spec.editor)
+            .into_iter()
+            .filter_map(|pam| spec.clone().designs(edit.clone(), pam))
+            .flatten()
+            .next()
+            .unwrap();
+
+        let sites = design.restriction_sites(&RestrictionEnzyme::common());
+        let names = |enzymes: &[RestrictionEnzyme]| -> Vec<String> {
+            enzymes.iter().map(|e| e.name.clone()).collect()
+        };
+        assert!(names(&sites.created).contains(&"EcoRV".to_owned()));
+        assert!(names(&sites.destroyed).contains(&"DpnII".to_owned()));
+        assert!(!sites.is_empty());
+
+        let none = design.restriction_sites(&[RestrictionEnzyme::named("NotI").unwrap()]);
+        assert!(none.is_empty());
+    }
+}
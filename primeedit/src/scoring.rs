@@ -0,0 +1,269 @@
+//! Ranking of [Design]s, rather than only filtering them with [Design::is_compliant].
+//!
+//! The default [HeuristicScorer] follows published design guidelines, other models (e.g. trained
+//! on screening data) can be plugged in through [Scorer], or loaded as a [LinearModel].
+
+use std::{collections::BTreeMap, ops::RangeInclusive};
+
+use biocore::{dna::DnaBase, genome::Contig};
+use serde::{Deserialize, Serialize};
+use utile::num::{TryI64, TryU64};
+
+use crate::Design;
+
+/// The properties of a [Design] that are known to affect editing efficiency.
+#[derive(Debug, Clone, PartialEq)]
+#[derive(Serialize, Deserialize)]
+pub struct Features {
+    /// See [Design::primer_size].
+    pub pbs_length: u64,
+    /// Share of G and C in the primer binding site.
+    pub pbs_gc: f64,
+    /// Length of the [Design::reverse_transcriptase_template].
+    pub rtt_length: u64,
+    /// Bases from the nick to the start of the edit, on the strand of the PAM.
+    pub nick_to_edit: i64,
+    /// The first base of the 3' extension, see [Design::has_rtt_cytosine].
+    pub rtt_first_base: DnaBase,
+    /// Share of G and C in the spacer (before forcing a 5' G).
+    pub spacer_gc: f64,
+}
+
+pub trait Scorer {
+    /// Higher is better.
+    fn score(&self, features: &Features) -> f64;
+}
+impl<F: Fn(&Features) -> f64> Scorer for F {
+    fn score(&self, features: &Features) -> f64 {
+        self(features)
+    }
+}
+
+/// Penalises each feature by how far it falls outside of its optimal range, so that a perfect
+/// design scores `0` and the rest are negative.
+#[derive(Debug, Clone, PartialEq)]
+#[derive(Serialize, Deserialize)]
+pub struct HeuristicScorer {
+    /// "a PBS of 13 nt and an RT template of 10–16 nt"
+    /// – https://pmc.ncbi.nlm.nih.gov/articles/PMC6907074/
+    pub pbs_length: Preference,
+    /// "PBS sequences with ~40–60% GC content generally perform well"
+    /// – https://pmc.ncbi.nlm.nih.gov/articles/PMC9799714/
+    pub pbs_gc: Preference,
+    pub rtt_length: Preference,
+    /// Editing efficiency drops the further the edit is from the nick.
+    pub nick_to_edit: Preference,
+    /// See [DesignSpec::avoid_rtt_cytosine](crate::design_spec::DesignSpec::avoid_rtt_cytosine).
+    pub rtt_first_base_c: f64,
+    pub spacer_gc: Preference,
+}
+/// An optimal range for a feature, and the penalty for each unit outside of it.
+#[derive(Debug, Clone, PartialEq)]
+#[derive(Serialize, Deserialize)]
+pub struct Preference {
+    pub optimum: RangeInclusive<f64>,
+    pub weight: f64,
+}
+
+/// A linear model over [Features::values], e.g. exported from an externally trained model.
+///
+/// Missing weights are treated as `0`.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[derive(Serialize, Deserialize)]
+pub struct LinearModel {
+    pub intercept: f64,
+    pub weights: BTreeMap<String, f64>,
+}
+
+impl<C> Design<C>
+where
+    C: Contig + Clone,
+{
+    /// `None` if the design doesn't fit in the edit sequence.
+    pub fn features(&self) -> Option<Features> {
+        let rtt_template = self.reverse_transcriptase_template()?;
+        let spacer = self.edit.get_original(self.spacer()?);
+        Some(Features {
+            pbs_length: self.primer_size,
+            pbs_gc: gc_content(&self.primer_sequence()?)?,
+            rtt_length: rtt_template.len().u64_unwrap(),
+            nick_to_edit: self.edit.distance_from_edit(self.pam.clone())
+                + self.editor.nick_distance.i64_unwrap(),
+            rtt_first_base: *rtt_template.first()?,
+            spacer_gc: gc_content(&spacer)?,
+        })
+    }
+    pub fn score(&self, scorer: &impl Scorer) -> Option<f64> {
+        Some(scorer.score(&self.features()?))
+    }
+}
+
+/// Sorts the designs from best to worst, dropping those that can't be scored.
+pub fn rank<C>(
+    designs: impl IntoIterator<Item = Design<C>>,
+    scorer: &impl Scorer,
+) -> Vec<(Design<C>, f64)>
+where
+    C: Contig + Clone,
+{
+    let mut ranked: Vec<_> = designs
+        .into_iter()
+        .filter_map(|design| {
+            let score = design.score(scorer)?;
+            Some((design, score))
+        })
+        .collect();
+    ranked.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+    ranked
+}
+
+impl Features {
+    /// Named numeric values, as used by [LinearModel].
+    ///
+    /// [Self::rtt_first_base] is one-hot encoded, e.g. as `rtt_first_base_C`.
+    pub fn values(&self) -> Vec<(String, f64)> {
+        let Self {
+            pbs_length,
+            pbs_gc,
+            rtt_length,
+            nick_to_edit,
+            rtt_first_base,
+            spacer_gc,
+        } = self;
+        let mut values = vec![
+            ("pbs_length".to_owned(), *pbs_length as f64),
+            ("pbs_gc".to_owned(), *pbs_gc),
+            ("rtt_length".to_owned(), *rtt_length as f64),
+            ("nick_to_edit".to_owned(), *nick_to_edit as f64),
+            ("spacer_gc".to_owned(), *spacer_gc),
+        ];
+        for base in [DnaBase::A, DnaBase::C, DnaBase::G, DnaBase::T] {
+            let value = if base == *rtt_first_base { 1. } else { 0. };
+            values.push((format!("rtt_first_base_{base}"), value));
+        }
+        values
+    }
+}
+
+impl Default for HeuristicScorer {
+    fn default() -> Self {
+        Self {
+            pbs_length: Preference::new(10. ..=13., 0.5),
+            pbs_gc: Preference::new(0.4..=0.6, 10.),
+            rtt_length: Preference::new(10. ..=16., 0.25),
+            nick_to_edit: Preference::new(0. ..=6., 0.5),
+            rtt_first_base_c: 2.,
+            spacer_gc: Preference::new(0.4..=0.7, 5.),
+        }
+    }
+}
+impl Scorer for HeuristicScorer {
+    fn score(&self, features: &Features) -> f64 {
+        let Self {
+            pbs_length,
+            pbs_gc,
+            rtt_length,
+            nick_to_edit,
+            rtt_first_base_c,
+            spacer_gc,
+        } = self;
+        let rtt_cytosine = if features.rtt_first_base == DnaBase::C {
+            *rtt_first_base_c
+        } else {
+            0.
+        };
+        -(pbs_length.penalty(features.pbs_length as f64)
+            + pbs_gc.penalty(features.pbs_gc)
+            + rtt_length.penalty(features.rtt_length as f64)
+            + nick_to_edit.penalty(features.nick_to_edit as f64)
+            + rtt_cytosine
+            + spacer_gc.penalty(features.spacer_gc))
+    }
+}
+
+impl Preference {
+    pub fn new(optimum: RangeInclusive<f64>, weight: f64) -> Self {
+        Self { optimum, weight }
+    }
+    pub fn penalty(&self, value: f64) -> f64 {
+        let distance = if value < *self.optimum.start() {
+            self.optimum.start() - value
+        } else if *self.optimum.end() < value {
+            value - self.optimum.end()
+        } else {
+            0.
+        };
+        distance * self.weight
+    }
+}
+
+impl Scorer for LinearModel {
+    fn score(&self, features: &Features) -> f64 {
+        self.intercept
+            + features
+                .values()
+                .into_iter()
+                .map(|(name, value)| self.weights.get(&name).unwrap_or(&0.) * value)
+                .sum::<f64>()
+    }
+}
+
+/// Share of G and C, `None` if empty.
+pub(crate) fn gc_content(sequence: &[DnaBase]) -> Option<f64> {
+    if sequence.is_empty() {
+        return None;
+    }
+    let gc = sequence
+        .iter()
+        .filter(|b| matches!(b, DnaBase::G | DnaBase::C))
+        .count();
+    Some(gc as f64 / sequence.len() as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{design_spec::DesignSpec, edit::Edit};
+
+    #[test]
+    fn test_rank() {
+        let spec = DesignSpec {
+            avoid_rtt_cytosine: false,
+            ..DesignSpec::default()
+        };
+        let edit =
+            Edit::parse("ACGTTGCAGCTAGGCATCGATCGATGGAAT(C/T)AAGCTAGCATCGATCGACTAGCA").unwrap();
+        let designs: Vec<_> = edit
+            .pams(&spec.editor)
+            .into_iter()
+            .filter_map(|pam| spec.clone().designs(edit.clone(), pam))
+            .flatten()
+            .collect();
+        assert!(!designs.is_empty());
+
+        let ranked = rank(designs.clone(), &HeuristicScorer::default());
+        assert_eq!(designs.len(), ranked.len());
+        assert!(ranked.windows(2).all(|w| w[0].1 >= w[1].1));
+        assert!(ranked.iter().all(|(_, score)| *score <= 0.));
+
+        // A model preferring longer primers puts them first.
+        let model = LinearModel {
+            intercept: 0.,
+            weights: BTreeMap::from([("pbs_length".to_owned(), 1.)]),
+        };
+        let ranked = rank(designs, &model);
+        let longest = ranked.iter().map(|(d, _)| d.primer_size).max().unwrap();
+        assert_eq!(ranked[0].0.primer_size, longest);
+    }
+
+    #[test]
+    fn test_penalty() {
+        let preference = Preference::new(10. ..=13., 0.5);
+        assert_eq!(preference.penalty(12.), 0.);
+        assert_eq!(preference.penalty(8.), 1.);
+        assert_eq!(preference.penalty(15.), 1.);
+        assert_eq!(gc_content(&[DnaBase::G, DnaBase::A]), Some(0.5));
+        assert_eq!(gc_content(&[]), None);
+    }
+}
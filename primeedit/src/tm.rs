@@ -0,0 +1,137 @@
+//! Melting temperatures of short duplexes, with the nearest-neighbour model.
+//!
+//! Uses the unified parameters of SantaLucia 1998 (PNAS 95:1460), including its salt correction.
+
+use biocore::{
+    dna::{Complement, DnaBase},
+    genome::Contig,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::Design;
+
+/// Gas constant, in cal/(K·mol).
+const R: f64 = 1.987;
+const KELVIN: f64 = 273.15;
+
+#[derive(Debug, Clone, PartialEq)]
+#[derive(Serialize, Deserialize)]
+pub struct Conditions {
+    /// Monovalent cation concentration, in mol/L.
+    pub sodium: f64,
+    /// Total strand concentration, in mol/L.
+    pub strand_concentration: f64,
+}
+impl Default for Conditions {
+    /// 50 mM Na⁺ and 250 nM of oligo, as is common for primer design.
+    fn default() -> Self {
+        Self {
+            sodium: 0.05,
+            strand_concentration: 250e-9,
+        }
+    }
+}
+
+impl<C> Design<C>
+where
+    C: Contig + Clone,
+{
+    /// The melting temperature of the primer binding site, in °C, under default [Conditions].
+    pub fn pbs_tm(&self) -> Option<f64> {
+        melting_temperature(&self.primer_sequence()?, &Conditions::default())
+    }
+}
+
+/// The melting temperature, in °C, of `sequence` bound to its perfect complement.
+///
+/// `None` for sequences shorter than two bases.
+pub fn melting_temperature(sequence: &[DnaBase], conditions: &Conditions) -> Option<f64> {
+    if sequence.len() < 2 {
+        return None;
+    }
+
+    let (mut enthalpy, mut entropy) = (0., 0.);
+    for terminal in [sequence[0], sequence[sequence.len() - 1]] {
+        let (h, s) = initiation(terminal);
+        enthalpy += h;
+        entropy += s;
+    }
+    for pair in sequence.windows(2) {
+        let (h, s) = nearest_neighbour(pair[0], pair[1]);
+        enthalpy += h;
+        entropy += s;
+    }
+
+    let self_complementary = sequence
+        .iter()
+        .zip(sequence.iter().rev())
+        .all(|(a, b)| *a == b.complement());
+    let x = if self_complementary {
+        entropy += -1.4;
+        1.
+    } else {
+        4.
+    };
+
+    let phosphates = (sequence.len() - 1) as f64;
+    entropy += 0.368 * phosphates * conditions.sodium.ln();
+
+    let tm = enthalpy * 1000. / (entropy + R * (conditions.strand_concentration / x).ln());
+    Some(tm - KELVIN)
+}
+
+/// (ΔH° in kcal/mol, ΔS° in cal/(K·mol)) for the terminal base pair.
+fn initiation(base: DnaBase) -> (f64, f64) {
+    match base {
+        DnaBase::G | DnaBase::C => (0.1, -2.8),
+        DnaBase::A | DnaBase::T => (2.3, 4.1),
+    }
+}
+
+/// (ΔH° in kcal/mol, ΔS° in cal/(K·mol)) for the stack `5'-ab-3'`.
+fn nearest_neighbour(a: DnaBase, b: DnaBase) -> (f64, f64) {
+    use DnaBase::*;
+    match (a, b) {
+        (A, A) | (T, T) => (-7.9, -22.2),
+        (A, T) => (-7.2, -20.4),
+        (T, A) => (-7.2, -21.3),
+        (C, A) | (T, G) => (-8.5, -22.7),
+        (G, T) | (A, C) => (-8.4, -22.4),
+        (C, T) | (A, G) => (-7.8, -21.0),
+        (G, A) | (T, C) => (-8.2, -22.2),
+        (C, G) => (-10.6, -27.2),
+        (G, C) => (-9.8, -24.4),
+        (G, G) | (C, C) => (-8.0, -19.9),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use biocore::dna::DnaSequence;
+
+    use super::*;
+
+    fn tm(sequence: &str) -> f64 {
+        let sequence: DnaSequence = sequence.parse().unwrap();
+        melting_temperature(&sequence, &Conditions::default()).unwrap()
+    }
+
+    #[test]
+    fn test_melting_temperature() {
+        assert!(
+            (tm("AGCTAGCATCGAT") - 38.7).abs() < 0.1,
+            "{}",
+            tm("AGCTAGCATCGAT")
+        );
+        assert!(tm("GCGCGGCCGCGC") > tm("ATATTAATATAT"));
+        assert!(tm("AGCTAGCATCGATCG") > tm("AGCTAGCATCGAT"));
+
+        let high_salt = Conditions {
+            sodium: 1.,
+            ..Conditions::default()
+        };
+        let sequence: DnaSequence = "AGCTAGCATCGAT".parse().unwrap();
+        assert!(melting_temperature(&sequence, &high_salt).unwrap() > tm("AGCTAGCATCGAT"));
+        assert_eq!(melting_temperature(&sequence[..1], &high_salt), None);
+    }
+}
@@ -0,0 +1,395 @@
+//! twinPE: two pegRNAs nicking opposite strands on either side of the edit, whose 3' flaps are
+//! complementary to each other instead of to the original sequence.
+//!
+//! The flaps anneal and replace all the sequence between the nicks, which allows larger
+//! replacements and insertions than a single pegRNA.
+//! – https://pmc.ncbi.nlm.nih.gov/articles/PMC9035044/
+
+use std::{hash::Hash, ops::Range};
+
+use biocore::{
+    dna::{DnaBase, DnaSequence, DnaSequenceSlice},
+    genome::Contig,
+    location::{ContigRange, orientation::Stranded},
+};
+use serde::{Deserialize, Serialize};
+use utile::num::{TryI64, TryU64, TryUsize};
+
+use crate::{Pam, edit::Edit, editor::Editor, scoring::gc_content};
+
+/// A pair of pegRNAs, see the [module docs](self).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize)]
+pub struct TwinDesign<C> {
+    pub edit: Edit<C>,
+    pub editor: Editor,
+    /// On the forward strand, nicking 5' of the edit.
+    pub forward_pam: Pam<C>,
+    /// On the reverse strand, nicking 3' of the edit.
+    pub reverse_pam: Pam<C>,
+    /// See [DesignSpec::force_5_prime_g](crate::design_spec::DesignSpec::force_5_prime_g),
+    /// applied to both spacers.
+    pub force_5_prime_g: bool,
+    /// See [DesignSpec::primer_size_range](crate::design_spec::DesignSpec::primer_size_range),
+    /// the same for both guides.
+    pub primer_size: u64,
+}
+
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct TwinDesignSpec {
+    pub editor: Editor,
+    /// See [DesignSpec::force_5_prime_g](crate::design_spec::DesignSpec::force_5_prime_g).
+    pub force_5_prime_g: Option<bool>,
+    /// See [DesignSpec::primer_size_range](crate::design_spec::DesignSpec::primer_size_range).
+    pub primer_size_range: Range<u64>,
+    /// The number of original bases between the two nicks, all of which are replaced.
+    pub nick_distance_range: Range<u64>,
+    /// The length of the flaps, which is also how many bases they anneal over.
+    pub flap_size_range: Range<u64>,
+    /// Share of G and C in the flaps, low GC flaps anneal poorly.
+    pub flap_gc_range: Range<f64>,
+    /// See [DesignSpec::avoid_poly_u](crate::design_spec::DesignSpec::avoid_poly_u).
+    pub avoid_poly_u: bool,
+    /// See [DesignSpec::avoid_rtt_cytosine](crate::design_spec::DesignSpec::avoid_rtt_cytosine).
+    pub avoid_rtt_cytosine: bool,
+}
+impl Default for TwinDesignSpec {
+    fn default() -> Self {
+        Self {
+            editor: Editor::sp_cas9(),
+            force_5_prime_g: Some(true),
+            primer_size_range: 8..15,
+            nick_distance_range: 20..200,
+            flap_size_range: 20..120,
+            flap_gc_range: 0.3..0.7,
+            avoid_poly_u: true,
+            avoid_rtt_cytosine: true,
+        }
+    }
+}
+
+impl<C> TwinDesign<C>
+where
+    C: Contig + Clone,
+{
+    /// The nicks, as positions between bases on the forward strand of the original sequence.
+    /// The bases in between are replaced by the [Self::flap].
+    pub fn nicks(&self) -> Option<Range<u64>> {
+        let forward = self.edit.nick(&self.editor, self.forward_pam.clone())?;
+        let reverse = self.edit.nick(&self.editor, self.reverse_pam.clone())?;
+        let len = self.edit.original_len().u64_unwrap();
+        Some(forward.v.at..len.checked_sub(reverse.v.at)?)
+    }
+    /// The nicks in the edited sequence, see [Self::nicks].
+    pub fn nicks_in_edited(&self) -> Option<Range<u64>> {
+        let nicks = self.nicks()?;
+        let edit = self.edit.edit_range_in_original().v.at;
+        if edit.start < nicks.start || nicks.end < edit.end {
+            return None;
+        }
+        let growth = self.edit.edited_len().i64_unwrap() - self.edit.original_len().i64_unwrap();
+        let end = (nicks.end.i64_unwrap() + growth).u64_unwrap();
+        Some(nicks.start..end)
+    }
+
+    /// The new sequence between the nicks, on the forward strand.
+    ///
+    /// This is the 3' flap of the forward pegRNA, the reverse pegRNA makes its reverse complement.
+    pub fn flap(&self) -> Option<DnaSequence> {
+        let nicks = self.nicks_in_edited()?;
+        let nicks = nicks.start.usize_unwrap()..nicks.end.usize_unwrap();
+        Some(self.edit.edited().get_range(nicks)?.to_owned())
+    }
+    pub fn flap_gc_content(&self) -> Option<f64> {
+        gc_content(&self.flap()?)
+    }
+    /// Whether the flaps reverse transcribed from the two templates anneal to each other,
+    /// see [flaps_anneal].
+    pub fn flaps_anneal(&self) -> Option<bool> {
+        let [forward, reverse] = self.reverse_transcriptase_templates()?;
+        // The flaps are the reverse complement of the templates.
+        Some(flaps_anneal(
+            &forward.reverse_complement(),
+            &reverse.reverse_complement(),
+        ))
+    }
+
+    /// Forward then reverse.
+    pub fn spacers(&self) -> Option<[Stranded<ContigRange<C>>; 2]> {
+        Some([
+            self.edit.spacer(&self.editor, self.forward_pam.clone())?,
+            self.edit.spacer(&self.editor, self.reverse_pam.clone())?,
+        ])
+    }
+    /// Forward then reverse, with the first base forced to G if [Self::force_5_prime_g] is set.
+    pub fn spacer_sequences(&self) -> Option<[DnaSequence; 2]> {
+        Some(self.spacers()?.map(|spacer| {
+            let mut spacer = self.edit.get_original(spacer);
+            if self.force_5_prime_g {
+                spacer[0] = DnaBase::G;
+            }
+            spacer
+        }))
+    }
+    /// Forward then reverse.
+    pub fn primer_sequences(&self) -> Option<[DnaSequence; 2]> {
+        Some(
+            [
+                self.edit
+                    .primer(&self.editor, self.forward_pam.clone(), self.primer_size)?,
+                self.edit
+                    .primer(&self.editor, self.reverse_pam.clone(), self.primer_size)?,
+            ]
+            .map(|primer| self.edit.get_original(primer)),
+        )
+    }
+    /// Forward then reverse, each is the reverse complement of the flap of its pegRNA.
+    pub fn reverse_transcriptase_templates(&self) -> Option<[DnaSequence; 2]> {
+        let flap = self.flap()?;
+        Some([flap.reverse_complement(), flap])
+    }
+    /// Forward then reverse, see [Design::full_guide_sequence](crate::Design::full_guide_sequence).
+    pub fn full_guide_sequences(&self) -> Option<[DnaSequence; 2]> {
+        let [spacer_f, spacer_r] = self.spacer_sequences()?;
+        let [rtt_f, rtt_r] = self.reverse_transcriptase_templates()?;
+        let [primer_f, primer_r] = self.primer_sequences()?;
+        let guide = |mut spacer: DnaSequence, mut rtt: DnaSequence, mut primer: DnaSequence| {
+            spacer.append(&mut self.editor.scaffold.clone());
+            spacer.append(&mut rtt);
+            spacer.append(&mut primer);
+            spacer
+        };
+        Some([
+            guide(spacer_f, rtt_f, primer_f),
+            guide(spacer_r, rtt_r, primer_r),
+        ])
+    }
+
+    /// See [Design::has_poly_u](crate::Design::has_poly_u), for either guide.
+    pub fn has_poly_u(&self) -> Option<bool> {
+        const PATTERN: [DnaBase; 4] = [DnaBase::T; 4];
+
+        let spacers = self.spacer_sequences()?;
+        let rtts = self.reverse_transcriptase_templates()?;
+        let primers = self.primer_sequences()?;
+        Some((0..2).any(|i| {
+            let mut rest = rtts[i].clone();
+            rest.append(&mut primers[i].clone());
+            spacers[i].contains(&PATTERN) || rest.contains(&PATTERN)
+        }))
+    }
+    /// See [Design::has_rtt_cytosine](crate::Design::has_rtt_cytosine), for either guide.
+    pub fn has_rtt_cytosine(&self) -> Option<bool> {
+        let rtts = self.reverse_transcriptase_templates()?;
+        Some(rtts.iter().any(|rtt| rtt.first() == Some(&DnaBase::C)))
+    }
+
+    fn is_in_range(&self) -> bool {
+        self.spacers().is_some() && self.primer_sequences().is_some() && self.flap().is_some()
+    }
+    fn unforced_spacer_sequences(&self) -> Option<[DnaSequence; 2]> {
+        Some(self.spacers()?.map(|spacer| self.edit.get_original(spacer)))
+    }
+}
+impl<C> TwinDesign<C>
+where
+    C: Contig + Clone + Hash + PartialEq,
+{
+    pub fn is_valid(&self) -> bool {
+        let pams = self.edit.pams(&self.editor);
+        self.forward_pam.orientation.is_forward()
+            && self.reverse_pam.orientation.is_reverse()
+            && pams.contains(&self.forward_pam)
+            && pams.contains(&self.reverse_pam)
+            && self.is_in_range()
+            && self.flaps_anneal() == Some(true)
+    }
+    pub fn is_compliant(&self, spec: &TwinDesignSpec) -> bool {
+        let TwinDesignSpec {
+            editor,
+            force_5_prime_g,
+            primer_size_range,
+            nick_distance_range,
+            flap_size_range,
+            flap_gc_range,
+            avoid_poly_u,
+            avoid_rtt_cytosine,
+        } = spec;
+        self.is_valid()
+            && editor == &self.editor
+            && force_5_prime_g.is_none_or(|force| {
+                let already_g = self
+                    .unforced_spacer_sequences()
+                    .is_some_and(|s| s.iter().all(|s| s[0] == DnaBase::G));
+                force == self.force_5_prime_g || already_g
+            })
+            && primer_size_range.contains(&self.primer_size)
+            && self
+                .nicks()
+                .is_some_and(|nicks| nick_distance_range.contains(&(nicks.end - nicks.start)))
+            && self
+                .flap()
+                .is_some_and(|flap| flap_size_range.contains(&flap.len().u64_unwrap()))
+            && self
+                .flap_gc_content()
+                .is_some_and(|gc| flap_gc_range.contains(&gc))
+            && !(*avoid_poly_u && self.has_poly_u().expect("sizing"))
+            && !(*avoid_rtt_cytosine && self.has_rtt_cytosine().expect("sizing"))
+    }
+}
+
+/// The fewest consecutive complementary bases over which two flaps (or a flap and itself) anneal.
+pub const MIN_FLAP_OVERLAP: usize = 10;
+
+/// Whether two 3' flaps, each 5' to 3' on its own strand, are complementary over at least
+/// [MIN_FLAP_OVERLAP] consecutive bases, and neither is self-complementary over as many.
+///
+/// A self-complementary flap folds onto itself (or a copy of itself) instead of its partner.
+pub fn flaps_anneal(forward_flap: &DnaSequenceSlice, reverse_flap: &DnaSequenceSlice) -> bool {
+    let complementary =
+        |a: &DnaSequenceSlice, b: &DnaSequenceSlice| longest_common_run(a, &b.reverse_complement());
+    complementary(forward_flap, reverse_flap) >= MIN_FLAP_OVERLAP
+        && complementary(forward_flap, forward_flap) < MIN_FLAP_OVERLAP
+        && complementary(reverse_flap, reverse_flap) < MIN_FLAP_OVERLAP
+}
+/// The length of the longest run of bases shared by `a` and `b`.
+fn longest_common_run(a: &[DnaBase], b: &[DnaBase]) -> usize {
+    let mut longest = 0;
+    // Run lengths ending at the previous base of `a`, for each base of `b`.
+    let mut previous = vec![0; b.len() + 1];
+    for &base_a in a {
+        let mut current = vec![0; b.len() + 1];
+        for (j, &base_b) in b.iter().enumerate() {
+            if base_a == base_b {
+                current[j + 1] = previous[j] + 1;
+                longest = longest.max(current[j + 1]);
+            }
+        }
+        previous = current;
+    }
+    longest
+}
+
+impl TwinDesignSpec {
+    /// All compliant pairs of PAMs and sizings for the edit.
+    pub fn designs<C>(self, edit: Edit<C>) -> impl Iterator<Item = TwinDesign<C>> + use<C>
+    where
+        C: Contig + Clone + Hash,
+    {
+        let pams = edit.pams(&self.editor);
+        let (forward, reverse): (Vec<_>, Vec<_>) = pams
+            .into_iter()
+            .partition(|pam| pam.orientation.is_forward());
+
+        let mut designs = vec![];
+        for forward_pam in &forward {
+            for reverse_pam in &reverse {
+                for primer_size in self.primer_size_range.clone() {
+                    let design = TwinDesign {
+                        edit: edit.clone(),
+                        editor: self.editor.clone(),
+                        forward_pam: forward_pam.clone(),
+                        reverse_pam: reverse_pam.clone(),
+                        force_5_prime_g: false,
+                        primer_size,
+                    };
+                    if !design.is_in_range() {
+                        continue;
+                    }
+                    let already_g = design
+                        .unforced_spacer_sequences()
+                        .expect("sizing")
+                        .iter()
+                        .all(|s| s[0] == DnaBase::G);
+                    let forced = TwinDesign {
+                        force_5_prime_g: true,
+                        ..design.clone()
+                    };
+                    match self.force_5_prime_g {
+                        _ if already_g => designs.push(design),
+                        Some(true) => designs.push(forced),
+                        Some(false) => designs.push(design),
+                        None => designs.extend([design, forced]),
+                    }
+                }
+            }
+        }
+        designs
+            .into_iter()
+            .filter(move |design| design.is_compliant(&self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use biocore::genome::ArcContig;
+
+    use super::*;
+
+    fn spec() -> TwinDesignSpec {
+        TwinDesignSpec {
+            nick_distance_range: 0..100,
+            flap_size_range: 0..100,
+            flap_gc_range: 0.0..1.0,
+            avoid_poly_u: false,
+            avoid_rtt_cytosine: false,
+            ..TwinDesignSpec::default()
+        }
+    }
+
+    #[test]
+    fn test_flaps() {
+        let edit: Edit<ArcContig> =
+            Edit::parse("AAAAAAAAAAAAAAAAAAAAAAAATGGAA(C/GTTG)AACCAAAAAAAAAAAAAAAAAAAAAAAA")
+                .unwrap();
+
+        let designs: Vec<_> = spec().designs(edit.clone()).collect();
+        assert!(!designs.is_empty());
+        for design in designs {
+            design.assert_flaps();
+
+            let flap = design.flap().unwrap();
+            assert!(flap.to_string().contains("GTTG"));
+            let nicks = design.nicks().unwrap();
+            assert_eq!(
+                flap.len(),
+                (nicks.end - nicks.start).usize_unwrap() + edit.edited_len() - edit.original_len()
+            );
+        }
+
+        let mut spec = spec();
+        spec.flap_gc_range = 0.9..1.0;
+        assert_eq!(spec.designs(edit).count(), 0);
+    }
+
+    #[test]
+    fn test_flaps_anneal() {
+        let parse = |s: &str| -> DnaSequence { s.parse().unwrap() };
+        let flap = parse("ATGGAAGTTGAACCAAAAAC");
+        assert!(flaps_anneal(&flap, &flap.reverse_complement()));
+
+        // Unrelated flaps.
+        assert!(!flaps_anneal(&flap, &parse("CCGTACGGATCTTGCAGTCA")));
+        // Complementary over too few bases.
+        assert!(!flaps_anneal(&flap, &parse("TTTTGGTTCTTGCAGTCAGT")));
+        // Complementary, but each flap is its own reverse complement too.
+        let palindrome = parse("ACGTTGCAATGCATTGCAACGT");
+        assert_eq!(palindrome, palindrome.reverse_complement());
+        assert!(!flaps_anneal(&palindrome, &palindrome.reverse_complement()));
+    }
+
+    impl TwinDesign<ArcContig> {
+        fn assert_flaps(&self) {
+            let [forward, reverse] = self.reverse_transcriptase_templates().unwrap();
+            assert_eq!(forward, reverse.reverse_complement());
+            assert_eq!(forward.reverse_complement(), self.flap().unwrap());
+            assert!(flaps_anneal(
+                &self.flap().unwrap(),
+                &reverse.reverse_complement()
+            ));
+            assert_eq!(self.flaps_anneal(), Some(true));
+            assert!(self.is_valid());
+        }
+    }
+}
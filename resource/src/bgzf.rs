@@ -0,0 +1,232 @@
+//! BGZF (blocked gzip), the compression of tabix-indexed files, BAM and BCF.
+//!
+//! Any BGZF file decompresses as [Compression::MultiGzip](crate::Compression::MultiGzip), but
+//! seeking to the offsets in a tabix, BAI or CSI index requires reading it block by block, see
+//! [RawResourceExt::read_bgzf](crate::RawResourceExt::read_bgzf) and the [noodles::bgzf] [Reader].
+//! Files are written with its [Writer], or with an [IndexingWriter] to also write a `.gzi` index
+//! (as `bgzip -i` does), which is read with [RawResourceExt::read_gzi](crate::RawResourceExt::read_gzi).
+//!
+//! https://samtools.github.io/hts-specs/SAMv1.pdf (section 4.1)
+
+use std::io::{self, Write};
+
+pub use noodles::bgzf::{
+    VirtualPosition, gzi,
+    io::{Reader, Writer},
+};
+
+/// The most data a block holds, as in htslib and [Writer].
+pub const MAX_BLOCK_SIZE: usize = 0xff00;
+
+/// A [Writer] that ends blocks after a given amount of data, recording where each one starts.
+pub struct IndexingWriter<W: Write> {
+    inner: Writer<W>,
+    block_size: usize,
+    /// The data in the current block.
+    buffered: usize,
+    /// The data in the finished blocks.
+    uncompressed: u64,
+    /// The (compressed, uncompressed) offsets of the start of each block but the first.
+    blocks: Vec<(u64, u64)>,
+}
+
+impl<W: Write> IndexingWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner: Writer::new(inner),
+            block_size: MAX_BLOCK_SIZE,
+            buffered: 0,
+            uncompressed: 0,
+            blocks: vec![],
+        }
+    }
+    /// Clamped to `1..=MAX_BLOCK_SIZE`.
+    pub fn with_block_size(self, block_size: usize) -> Self {
+        Self {
+            block_size: block_size.clamp(1, MAX_BLOCK_SIZE),
+            ..self
+        }
+    }
+
+    /// The most data written to each block, smaller blocks are only written on [Write::flush].
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+    /// The position of the next byte written, e.g. to build a tabix index.
+    pub fn virtual_position(&self) -> VirtualPosition {
+        self.inner.virtual_position()
+    }
+    /// The (compressed, uncompressed) offsets of the start of each block but the first, as in
+    /// a `.gzi` index.
+    pub fn blocks(&self) -> &[(u64, u64)] {
+        &self.blocks
+    }
+
+    /// Writes the `.gzi` index of the blocks so far.
+    pub fn write_gzi(&self, mut writer: impl Write) -> io::Result<()> {
+        writer.write_all(&u64::try_from(self.blocks.len()).unwrap().to_le_bytes())?;
+        for (compressed, uncompressed) in &self.blocks {
+            writer.write_all(&compressed.to_le_bytes())?;
+            writer.write_all(&uncompressed.to_le_bytes())?;
+        }
+        Ok(())
+    }
+    /// Writes the remaining data and the EOF marker, returning the inner writer and the
+    /// [Self::blocks].
+    pub fn finish(self) -> io::Result<(W, Vec<(u64, u64)>)> {
+        Ok((self.inner.finish()?, self.blocks))
+    }
+
+    fn end_block(&mut self) -> io::Result<()> {
+        if self.buffered == 0 {
+            return Ok(());
+        }
+        // Flushing the noodles writer compresses its buffer into a block.
+        self.inner.flush()?;
+        self.uncompressed += u64::try_from(self.buffered).unwrap();
+        self.buffered = 0;
+        Ok(())
+    }
+}
+impl<W: Write> Write for IndexingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.buffered == 0 && self.uncompressed > 0 {
+            let compressed = self.inner.virtual_position().compressed();
+            self.blocks.push((compressed, self.uncompressed));
+        }
+        let len = buf.len().min(self.block_size - self.buffered);
+        self.inner.write_all(&buf[..len])?;
+        self.buffered += len;
+        if self.buffered == self.block_size {
+            self.end_block()?;
+        }
+        Ok(len)
+    }
+    /// Ends the current block early.
+    fn flush(&mut self) -> io::Result<()> {
+        self.end_block()?;
+        self.inner.get_mut().flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, Cursor, Read, Write};
+
+    use crate::{RawResourceExt, fs::FsCache};
+
+    use super::*;
+
+    /// Writes each of `blocks` in its own BGZF block, also returning the `.gzi` entries.
+    fn bgzf(blocks: &[&str]) -> (Vec<u8>, Vec<(u64, u64)>) {
+        let mut writer = IndexingWriter::new(vec![]);
+        for block in blocks {
+            writer.write_all(block.as_bytes()).unwrap();
+            writer.flush().unwrap();
+        }
+        writer.finish().unwrap()
+    }
+
+    #[test]
+    fn test_read_bgzf() {
+        let (file, gzi) = bgzf(&["line 1\nline 2\n", "line 3\n"]);
+        let second_start = gzi[0].0;
+        let (cache, _dir) = FsCache::new_temp();
+        let entry = cache.entry("data.bgz");
+        entry.write_file(&file[..]).unwrap();
+
+        let mut reader = entry.read_bgzf().unwrap();
+        let mut lines = vec![];
+        let mut line = String::new();
+        while reader.read_line(&mut line).unwrap() > 0 {
+            lines.push(std::mem::take(&mut line));
+        }
+        assert_eq!(lines, ["line 1\n", "line 2\n", "line 3\n"]);
+
+        let position = VirtualPosition::try_from((0, 7)).unwrap();
+        reader.seek(position).unwrap();
+        assert_eq!(reader.virtual_position(), position);
+        let mut rest = String::new();
+        reader.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "line 2\nline 3\n");
+
+        reader
+            .seek(VirtualPosition::try_from((second_start, 0)).unwrap())
+            .unwrap();
+        line.clear();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!(line, "line 3\n");
+
+        // The decompressed data matches a plain multi-member gzip decoder.
+        let mut multi = String::new();
+        flate2::read::MultiGzDecoder::new(&file[..])
+            .read_to_string(&mut multi)
+            .unwrap();
+        assert_eq!(multi, "line 1\nline 2\nline 3\n");
+    }
+
+    #[test]
+    fn test_read_gzi() {
+        let mut writer = IndexingWriter::new(vec![]);
+        for block in ["line 1\nline 2\n", "line 3\n", "line 4\n"] {
+            writer.write_all(block.as_bytes()).unwrap();
+            writer.flush().unwrap();
+        }
+        let mut gzi = vec![];
+        writer.write_gzi(&mut gzi).unwrap();
+        let (file, _) = writer.finish().unwrap();
+        let (cache, _dir) = FsCache::new_temp();
+        cache.entry("data.bgz").write_file(&file[..]).unwrap();
+        cache.entry("data.bgz.gzi").write_file(&gzi[..]).unwrap();
+
+        let index = cache.entry("data.bgz.gzi").read_gzi().unwrap();
+        let mut reader = cache.entry("data.bgz").read_bgzf().unwrap();
+        let mut line = String::new();
+        for (offset, expected) in [(21, "line 4\n"), (7, "line 2\n"), (14, "line 3\n")] {
+            reader
+                .seek_by_uncompressed_position(&index, offset)
+                .unwrap();
+            line.clear();
+            reader.read_line(&mut line).unwrap();
+            assert_eq!(line, expected);
+        }
+    }
+
+    #[test]
+    fn test_indexing_writer_block_size() {
+        let data: String = (0..100).map(|i| format!("line {i:02}\n")).collect();
+        let mut writer = IndexingWriter::new(vec![]).with_block_size(100);
+        assert_eq!(writer.block_size(), 100);
+        writer.write_all(data.as_bytes()).unwrap();
+        let mut gzi = vec![];
+        writer.write_gzi(&mut gzi).unwrap();
+        let (file, blocks) = writer.finish().unwrap();
+
+        assert_eq!(blocks.len(), data.len().div_ceil(100) - 1);
+        let starts: Vec<u64> = blocks
+            .iter()
+            .map(|&(_, uncompressed)| uncompressed)
+            .collect();
+        assert_eq!(starts, (1..8).map(|i| i * 100).collect::<Vec<_>>());
+
+        let index = gzi::io::Reader::new(&gzi[..]).read_index().unwrap();
+        let mut reader = Reader::new(Cursor::new(file));
+        let mut all = String::new();
+        reader.read_to_string(&mut all).unwrap();
+        assert_eq!(all, data);
+
+        let mut line = String::new();
+        for i in [50, 3, 99] {
+            let offset = data.find(&format!("line {i:02}")).unwrap();
+            reader
+                .seek_by_uncompressed_position(&index, u64::try_from(offset).unwrap())
+                .unwrap();
+            line.clear();
+            reader.read_line(&mut line).unwrap();
+            assert_eq!(line, format!("line {i:02}\n"));
+        }
+    }
+}
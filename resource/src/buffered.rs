@@ -1,4 +1,6 @@
-use super::{Compression, RawResource};
+use std::ops::Range;
+
+use super::{Compression, RawResource, Validators};
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct BufferedResource<R> {
@@ -33,4 +35,17 @@ impl<R: RawResource> RawResource for BufferedResource<R> {
     async fn read_async(&self) -> std::io::Result<Self::AsyncReader> {
         Ok(tokio::io::BufReader::new(self.resource.read_async().await?))
     }
+    async fn read_range_async(
+        &self,
+        range: Range<u64>,
+    ) -> std::io::Result<Option<Self::AsyncReader>> {
+        let reader = self.resource.read_range_async(range).await?;
+        Ok(reader.map(tokio::io::BufReader::new))
+    }
+    async fn validators_async(&self) -> std::io::Result<Option<Validators>> {
+        self.resource.validators_async().await
+    }
+    async fn is_fresh_async(&self, cached: &Validators) -> std::io::Result<Option<bool>> {
+        self.resource.is_fresh_async(cached).await
+    }
 }
@@ -1,15 +1,61 @@
-use std::{fmt, path::PathBuf};
+use std::{fmt, ops::Range, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    Compression, RawResource, RawResourceExt, ResourceRef,
+    Compression, RawResource, RawResourceExt, ResourceRef, Validators,
     fs::{FsCache, FsCacheEntry},
 };
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct FsCacheResource<R> {
+    cache: FsCache,
     entry: FsCacheEntry,
     resource: R,
 }
+
+/// The error (wrapped in an [std::io::Error]) of a cache miss while offline, see
+/// [FsCache::with_offline] and [FsCacheResource::with_offline].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{entry} is not cached and would be downloaded, but the cache is offline")]
+pub struct WouldDownload {
+    pub entry: FsCacheEntry,
+}
+impl WouldDownload {
+    pub fn from_io(error: &std::io::Error) -> Option<&Self> {
+        error.get_ref()?.downcast_ref()
+    }
+}
+
+/// How to split a download into concurrent ranged reads, see
+/// [FsCacheResource::cache_segmented_async].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SegmentedDownload {
+    /// In bytes, each segment is held in memory until written.
+    pub segment_size: u64,
+    /// The maximum number of segments downloaded at once.
+    pub connections: usize,
+    /// The expected hex-encoded SHA-256 of the whole file, if known.
+    pub sha256: Option<String>,
+}
+impl Default for SegmentedDownload {
+    fn default() -> Self {
+        Self {
+            segment_size: 16 * 1024 * 1024,
+            connections: 8,
+            sha256: None,
+        }
+    }
+}
+impl SegmentedDownload {
+    fn segments(&self, size: u64) -> Vec<Range<u64>> {
+        let segment_size = self.segment_size.max(1);
+        (0..size)
+            .step_by(usize::try_from(segment_size).unwrap())
+            .map(|start| start..(start + segment_size).min(size))
+            .collect()
+    }
+}
 impl<R> fmt::Display for FsCacheResource<R> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.entry)
@@ -21,11 +67,41 @@ impl<R> FsCacheResource<R> {
         R: RawResource,
     {
         Self {
+            cache: cache.clone(),
             entry: FsCacheEntry::new(cache, PathBuf::from(R::NAMESPACE).join(resource.key())),
             resource,
         }
     }
 
+    /// Overrides whether the cache is offline, for this resource only.
+    pub fn with_offline(self, offline: bool) -> Self {
+        Self {
+            cache: self.cache.with_offline(offline),
+            ..self
+        }
+    }
+
+    /// Fails with [WouldDownload] if the cache is offline.
+    fn check_online(&self) -> std::io::Result<()> {
+        if !self.cache.is_offline() {
+            return Ok(());
+        }
+        Err(std::io::Error::other(WouldDownload {
+            entry: self.entry.clone(),
+        }))
+    }
+
+    /// Keeps the cache within its quota after a new entry, see [FsCache::evict].
+    fn enforce_quota(&self) {
+        match self.cache.evict_except(Some(&self.entry)) {
+            Ok(evicted) if !evicted.is_empty() => {
+                log::info!("Evicted {} files to fit {self}", evicted.len());
+            }
+            Ok(_) => {}
+            Err(e) => log::warn!("Failed to enforce the cache quota: {e}"),
+        }
+    }
+
     pub fn try_exists(&self) -> std::io::Result<bool> {
         self.entry.try_exists()
     }
@@ -88,12 +164,304 @@ impl<R> FsCacheResource<R> {
         panic!("FsCacheResource is not supported on wasm32");
     }
 
+    /// Like [Self::ensure_cached_async], see [Self::cache_segmented_async].
+    #[cfg(not(target_arch = "wasm32"))] // TODO
+    pub async fn ensure_cached_segmented_async(
+        self,
+        options: &SegmentedDownload,
+    ) -> std::io::Result<Self>
+    where
+        R: RawResource,
+    {
+        self.cache_segmented_async(options).await?;
+        Ok(self)
+    }
+    /// Like [Self::cache_async], but downloading segments of the file concurrently.
+    ///
+    /// Each segment and the whole file are checked against the expected size (and hash, if
+    /// given) before the entry is created. Falls back to a single stream if the size isn't
+    /// known or the resource can't be read in parts, the entry is then removed again if the hash
+    /// doesn't match.
+    #[cfg(not(target_arch = "wasm32"))] // TODO
+    pub async fn cache_segmented_async(
+        &self,
+        options: &SegmentedDownload,
+    ) -> std::io::Result<FsCacheEntry>
+    where
+        R: RawResource,
+    {
+        if self.try_exists_async().await? {
+            return Ok(self.entry.clone());
+        }
+        self.check_online()?;
+        let Ok(size) = self.resource.size_async().await else {
+            return self.cache_single_stream_async(options).await;
+        };
+
+        let segments = options.segments(size);
+        log::info!(
+            "Cache miss at {self}, downloading {} segments",
+            segments.len()
+        );
+        let result = self
+            .entry
+            .write_file_with_async(async |path| {
+                self.download_segments(path, size, segments, options).await
+            })
+            .await;
+        match result {
+            Ok(()) => {
+                log::info!("Retrieved {self}");
+                self.store_validators().await;
+                self.enforce_quota();
+                Ok(self.entry.clone())
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::Unsupported => {
+                log::warn!("{e}, downloading {self} as a single stream");
+                self.cache_single_stream_async(options).await
+            }
+            Err(e) => Err(e),
+        }
+    }
+    /// The fallback of [Self::cache_segmented_async], still checking the hash (if given).
+    #[cfg(not(target_arch = "wasm32"))] // TODO
+    async fn cache_single_stream_async(
+        &self,
+        options: &SegmentedDownload,
+    ) -> std::io::Result<FsCacheEntry>
+    where
+        R: RawResource,
+    {
+        let entry = self.cache_async().await?;
+        if let Err(e) = verify_sha256(entry.as_ref(), options.sha256.as_deref()).await {
+            self.invalidate_async().await?;
+            return Err(e);
+        }
+        Ok(entry)
+    }
+    #[cfg(not(target_arch = "wasm32"))] // TODO
+    async fn download_segments(
+        &self,
+        path: &std::path::Path,
+        size: u64,
+        segments: Vec<Range<u64>>,
+        options: &SegmentedDownload,
+    ) -> std::io::Result<()>
+    where
+        R: RawResource,
+    {
+        use futures::{StreamExt, TryStreamExt};
+        use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+        use utile::io::read_ext::AsyncReadInto;
+
+        let mut file = tokio::fs::File::create(path).await?;
+        file.set_len(size).await?;
+
+        let mut downloads = futures::stream::iter(segments)
+            .map(|range| async move {
+                let Some(reader) = self.resource.read_range_async(range.clone()).await? else {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::Unsupported,
+                        format!("{self} can't be read in parts"),
+                    ));
+                };
+                let data = reader.read_into_vec().await?;
+                if u64::try_from(data.len()).unwrap() != range.end - range.start {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!(
+                            "Expected {} bytes for {range:?} of {self}, got {}.",
+                            range.end - range.start,
+                            data.len()
+                        ),
+                    ));
+                }
+                Ok((range.start, data))
+            })
+            .buffer_unordered(options.connections.max(1));
+        while let Some((start, data)) = downloads.try_next().await? {
+            file.seek(std::io::SeekFrom::Start(start)).await?;
+            file.write_all(&data).await?;
+        }
+        file.sync_all().await?;
+
+        verify(path, size, options.sha256.as_deref()).await
+    }
+
+    /// Downloads to `<entry>.part`, checkpointing the progress in `<entry>.part.json` so that an
+    /// interrupted download resumes where it left off, if the resource can be read in parts.
+    ///
+    /// The `.part` file is locked for the whole download. If another download of the entry (in
+    /// this process or another) holds it, this one goes to a private temporary file instead.
+    #[cfg(not(target_arch = "wasm32"))] // TODO
+    async fn download_resumable_async(&self) -> std::io::Result<()>
+    where
+        R: RawResource,
+    {
+        use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+
+        let part = self.entry.sibling(".part");
+        let metadata = self.entry.sibling(".part.json");
+        let size = self.resource.size_async().await.ok();
+
+        let directory = part.as_ref().parent().unwrap().to_owned();
+        tokio::fs::create_dir_all(&directory).await?;
+        let (file, path, temp) = match lock_part(&part)? {
+            Some(file) => (file, part.as_ref().to_owned(), None),
+            None => {
+                log::info!("{part} is locked by another download, downloading {self} separately");
+                let (file, temp) = tempfile::Builder::new()
+                    .prefix(".part-")
+                    .tempfile_in(&directory)?
+                    .into_parts();
+                (file, temp.to_path_buf(), Some(temp))
+            }
+        };
+        let resumable = temp.is_none();
+
+        let checkpoint = match (size, read_json::<PartMetadata>(&metadata).await) {
+            (Some(size), Some(m)) if resumable && m.size == size && m.offset < size => {
+                // Bytes past the last checkpoint may not have been flushed.
+                m.offset.min(file.metadata()?.len())
+            }
+            _ => 0,
+        };
+        let mut offset = 0;
+        let mut reader = None;
+        if checkpoint > 0
+            && let Some(size) = size
+        {
+            reader = self.resource.read_range_async(checkpoint..size).await?;
+            if reader.is_some() {
+                log::info!("Resuming {self} from byte {checkpoint}");
+                offset = checkpoint;
+            }
+        }
+        let reader = match reader {
+            Some(reader) => reader,
+            None => self.resource.read_async().await?,
+        };
+        let mut reader = std::pin::pin!(reader);
+
+        let mut file = tokio::fs::File::from_std(file);
+        file.set_len(offset).await?;
+        file.seek(std::io::SeekFrom::Start(offset)).await?;
+
+        let mut buffer = vec![0; 64 * 1024];
+        let mut checkpoint = offset;
+        loop {
+            let n = reader.read(&mut buffer).await?;
+            if n == 0 {
+                break;
+            }
+            file.write_all(&buffer[..n]).await?;
+            offset += u64::try_from(n).unwrap();
+
+            if resumable
+                && let Some(size) = size
+                && offset - checkpoint >= CHECKPOINT_BYTES
+            {
+                file.sync_data().await?;
+                write_json(&metadata, &PartMetadata { size, offset }).await?;
+                checkpoint = offset;
+            }
+        }
+        file.sync_all().await?;
+
+        match size {
+            Some(size) if offset < size => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    format!("Expected {size} bytes for {self}, got {offset} so far."),
+                ));
+            }
+            Some(size) if offset > size => {
+                if resumable {
+                    tokio::fs::remove_file(&metadata).await?;
+                }
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("Expected {size} bytes for {self}, got {offset}."),
+                ));
+            }
+            _ => {}
+        }
+
+        // Still holding the lock, so no other download starts writing to the `.part` file.
+        crate::fs::rename_or_copy_async(&path, &self.entry).await?;
+        drop(file);
+        if resumable {
+            remove_if_exists_async(&metadata).await?;
+        }
+        Ok(())
+    }
+
     pub fn invalidate(&self) -> std::io::Result<()> {
-        self.entry.invalidate()
+        self.entry.invalidate()?;
+        match std::fs::remove_file(self.validators_entry()) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
     }
     #[cfg(not(target_arch = "wasm32"))] // TODO
     pub async fn invalidate_async(&self) -> std::io::Result<()> {
-        self.entry.invalidate_async().await
+        self.entry.invalidate_async().await?;
+        remove_if_exists_async(&self.validators_entry()).await
+    }
+
+    /// Like [Self::ensure_cached_async], but re-downloading the entry if it changed upstream,
+    /// see [Self::revalidate_async].
+    #[cfg(not(target_arch = "wasm32"))] // TODO
+    pub async fn ensure_revalidated_async(self) -> std::io::Result<Self>
+    where
+        R: RawResource,
+    {
+        self.revalidate_async().await?;
+        self.ensure_cached_async().await
+    }
+    /// Checks a cached entry against the source (with a conditional request if supported),
+    /// invalidating it if it is stale. Returns whether it was invalidated.
+    ///
+    /// Entries cached without [Validators] are stale if the source has some, and kept if it
+    /// doesn't. Offline, entries are always kept.
+    #[cfg(not(target_arch = "wasm32"))] // TODO
+    pub async fn revalidate_async(&self) -> std::io::Result<bool>
+    where
+        R: RawResource,
+    {
+        if self.cache.is_offline() || !self.try_exists_async().await? {
+            return Ok(false);
+        }
+        let fresh = match read_json::<Validators>(&self.validators_entry()).await {
+            Some(cached) => self.resource.is_fresh_async(&cached).await?,
+            None => self.resource.validators_async().await?.map(|_| false),
+        };
+        if fresh != Some(false) {
+            return Ok(false);
+        }
+        log::info!("Cache entry {self} is stale");
+        self.invalidate_async().await?;
+        Ok(true)
+    }
+
+    fn validators_entry(&self) -> FsCacheEntry {
+        self.entry.sibling(".validators.json")
+    }
+    /// Records the version of a new entry, for [Self::revalidate_async].
+    #[cfg(not(target_arch = "wasm32"))] // TODO
+    async fn store_validators(&self)
+    where
+        R: RawResource,
+    {
+        let result = async {
+            match self.resource.validators_async().await? {
+                Some(validators) => write_json(&self.validators_entry(), &validators).await,
+                None => Ok(()),
+            }
+        };
+        if let Err(e) = result.await {
+            log::warn!("Failed to store the validators of {self}: {e}");
+        }
     }
     #[cfg(target_arch = "wasm32")] // TODO
     pub async fn invalidate_async(&self) -> std::io::Result<()> {
@@ -115,6 +483,7 @@ impl<R: RawResource> RawResource for FsCacheResource<R> {
         if let Ok(size) = self.entry.size() {
             Ok(size)
         } else {
+            self.check_online()?;
             self.resource.size()
         }
     }
@@ -123,6 +492,7 @@ impl<R: RawResource> RawResource for FsCacheResource<R> {
             log::info!("Cache hit at {self}");
             return self.entry.read();
         }
+        self.check_online()?;
 
         log::info!("Cache miss at {self} from {self}");
 
@@ -130,6 +500,7 @@ impl<R: RawResource> RawResource for FsCacheResource<R> {
             .write_file(ResourceRef::new(&self.resource).buffered().read()?)?;
 
         log::info!("Retrieved {self}");
+        self.enforce_quota();
 
         self.entry.read()
     }
@@ -141,6 +512,7 @@ impl<R: RawResource> RawResource for FsCacheResource<R> {
         if let Ok(size) = self.entry.size_async().await {
             Ok(size)
         } else {
+            self.check_online()?;
             self.resource.size_async().await
         }
     }
@@ -150,19 +522,15 @@ impl<R: RawResource> RawResource for FsCacheResource<R> {
             log::info!("Cache hit at {self}");
             return self.entry.read_async().await;
         }
+        self.check_online()?;
 
         log::info!("Cache miss at {self} from {self}");
 
-        self.entry
-            .write_file_async(
-                ResourceRef::new(&self.resource)
-                    .buffered()
-                    .read_async()
-                    .await?,
-            )
-            .await?;
+        self.download_resumable_async().await?;
 
         log::info!("Retrieved {self}");
+        self.store_validators().await;
+        self.enforce_quota();
 
         self.entry.read_async().await
     }
@@ -177,3 +545,347 @@ impl<R: RawResource> RawResource for FsCacheResource<R> {
         panic!("FsCacheResource is not supported on wasm32");
     }
 }
+
+/// The progress of a download to `<entry>.part`, see [FsCacheResource::cache_async].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Serialize, Deserialize)]
+struct PartMetadata {
+    /// Of the complete file.
+    size: u64,
+    /// The bytes before this are on disk.
+    offset: u64,
+}
+/// How often the progress of a download is persisted.
+const CHECKPOINT_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Opens `part` for writing with an exclusive lock, `None` if another download holds it.
+#[cfg(not(target_arch = "wasm32"))] // TODO
+fn lock_part(part: &FsCacheEntry) -> std::io::Result<Option<std::fs::File>> {
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(part)?;
+    match file.try_lock() {
+        Ok(()) => Ok(Some(file)),
+        Err(std::fs::TryLockError::WouldBlock) => Ok(None),
+        Err(std::fs::TryLockError::Error(e)) => Err(e),
+    }
+}
+
+/// `None` if missing or unreadable.
+#[cfg(not(target_arch = "wasm32"))] // TODO
+async fn read_json<T: serde::de::DeserializeOwned>(entry: &FsCacheEntry) -> Option<T> {
+    let data = tokio::fs::read(entry).await.ok()?;
+    serde_json::from_slice(&data).ok()
+}
+#[cfg(not(target_arch = "wasm32"))] // TODO
+async fn write_json(entry: &FsCacheEntry, value: &impl Serialize) -> std::io::Result<()> {
+    tokio::fs::write(entry, serde_json::to_vec(value)?).await
+}
+#[cfg(not(target_arch = "wasm32"))] // TODO
+async fn remove_if_exists_async(entry: &FsCacheEntry) -> std::io::Result<()> {
+    match tokio::fs::remove_file(entry).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Checks the size and, if given, the hex-encoded SHA-256 of the file at `path`.
+#[cfg(not(target_arch = "wasm32"))] // TODO
+async fn verify(path: &std::path::Path, size: u64, sha256: Option<&str>) -> std::io::Result<()> {
+    let actual = tokio::fs::metadata(path).await?.len();
+    if actual != size {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Expected {size} bytes, downloaded {actual}."),
+        ));
+    }
+    verify_sha256(path, sha256).await
+}
+/// Checks the hex-encoded SHA-256 of the file at `path`, if given.
+#[cfg(not(target_arch = "wasm32"))] // TODO
+async fn verify_sha256(path: &std::path::Path, sha256: Option<&str>) -> std::io::Result<()> {
+    use sha2::{Digest, Sha256};
+    use tokio::io::AsyncReadExt;
+
+    let Some(expected) = sha256 else {
+        return Ok(());
+    };
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; 1 << 20];
+    loop {
+        let n = file.read(&mut buffer).await?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+    }
+    let actual: String = hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    if !actual.eq_ignore_ascii_case(expected) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Expected SHA-256 {expected}, downloaded {actual}."),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Supports ranged reads (unless `unranged`), which are recorded, and versioning through
+    /// its ETag.
+    #[derive(Default)]
+    struct MemoryResource {
+        data: Vec<u8>,
+        unranged: bool,
+        ranges: std::sync::Mutex<Vec<Range<u64>>>,
+        etag: std::sync::Mutex<String>,
+    }
+    impl RawResource for MemoryResource {
+        const NAMESPACE: &'static str = "memory";
+        fn key(&self) -> String {
+            "data".to_owned()
+        }
+        fn compression(&self) -> Option<Compression> {
+            None
+        }
+
+        type Reader = std::io::Cursor<Vec<u8>>;
+        fn size(&self) -> std::io::Result<u64> {
+            Ok(self.data.len().try_into().unwrap())
+        }
+        fn read(&self) -> std::io::Result<Self::Reader> {
+            Ok(std::io::Cursor::new(self.data.clone()))
+        }
+
+        type AsyncReader = std::io::Cursor<Vec<u8>>;
+        async fn size_async(&self) -> std::io::Result<u64> {
+            self.size()
+        }
+        async fn read_async(&self) -> std::io::Result<Self::AsyncReader> {
+            self.read()
+        }
+        async fn read_range_async(
+            &self,
+            range: Range<u64>,
+        ) -> std::io::Result<Option<Self::AsyncReader>> {
+            if self.unranged {
+                return Ok(None);
+            }
+            self.ranges.lock().unwrap().push(range.clone());
+            let range = usize::try_from(range.start).unwrap()..usize::try_from(range.end).unwrap();
+            Ok(Some(std::io::Cursor::new(self.data[range].to_vec())))
+        }
+        async fn validators_async(&self) -> std::io::Result<Option<Validators>> {
+            Ok(Some(Validators {
+                etag: Some(self.etag.lock().unwrap().clone()),
+                size: Some(self.size()?),
+                ..Validators::default()
+            }))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resume() {
+        let (cache, _dir) = FsCache::new_temp();
+        let data: Vec<u8> = (0..100).collect();
+        let resource = FsCacheResource::new(
+            &cache,
+            MemoryResource {
+                data: data.clone(),
+                ..MemoryResource::default()
+            },
+        );
+
+        // An interrupted download, with more bytes on disk than were checkpointed.
+        let part = resource.entry.sibling(".part");
+        part.write_file(&data[..40]).unwrap();
+        let metadata = resource.entry.sibling(".part.json");
+        write_json(
+            &metadata,
+            &PartMetadata {
+                size: 100,
+                offset: 30,
+            },
+        )
+        .await
+        .unwrap();
+
+        resource.cache_async().await.unwrap();
+        assert_eq!(*resource.resource.ranges.lock().unwrap(), vec![30..100]);
+        assert_eq!(resource.read_vec().unwrap(), data);
+        assert!(!part.try_exists().unwrap());
+        assert!(!metadata.try_exists().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_downloads() {
+        let (cache, _dir) = FsCache::new_temp();
+        let data: Vec<u8> = (0..100).collect();
+        let resource = || {
+            FsCacheResource::new(
+                &cache,
+                MemoryResource {
+                    data: data.clone(),
+                    ..MemoryResource::default()
+                },
+            )
+        };
+
+        // Another download holds the `.part` file, which is left alone.
+        let first = resource();
+        let part = first.entry.sibling(".part");
+        part.write_file(&data[..40]).unwrap();
+        let locked = lock_part(&part).unwrap().unwrap();
+        assert!(lock_part(&part).unwrap().is_none());
+        first.cache_async().await.unwrap();
+        assert_eq!(first.read_vec().unwrap(), data);
+        assert_eq!(part.read_vec().unwrap(), data[..40]);
+        drop(locked);
+
+        let second = resource();
+        second.invalidate().unwrap();
+        let third = resource();
+        let (a, b) = tokio::join!(second.cache_async(), third.cache_async());
+        a.unwrap();
+        b.unwrap();
+        assert_eq!(second.read_vec().unwrap(), data);
+    }
+
+    #[tokio::test]
+    async fn test_revalidate() {
+        let (cache, _dir) = FsCache::new_temp();
+        let resource = FsCacheResource::new(
+            &cache,
+            MemoryResource {
+                data: vec![1, 2, 3],
+                ..MemoryResource::default()
+            },
+        );
+        assert!(!resource.revalidate_async().await.unwrap());
+
+        let resource = resource.ensure_cached_async().await.unwrap();
+        assert!(resource.validators_entry().try_exists().unwrap());
+        assert!(!resource.revalidate_async().await.unwrap());
+
+        *resource.resource.etag.lock().unwrap() = "v2".to_owned();
+        assert!(resource.revalidate_async().await.unwrap());
+        assert!(!resource.try_exists().unwrap());
+
+        let resource = resource.ensure_revalidated_async().await.unwrap();
+        assert!(resource.try_exists().unwrap());
+        assert!(!resource.revalidate_async().await.unwrap());
+
+        let cached = Validators {
+            etag: Some("a".to_owned()),
+            size: Some(3),
+            ..Validators::default()
+        };
+        let current = Validators {
+            last_modified: Some("yesterday".to_owned()),
+            ..cached.clone()
+        };
+        assert_eq!(cached.matches(&current), Some(true));
+        let resized = Validators {
+            size: Some(4),
+            ..cached.clone()
+        };
+        assert_eq!(cached.matches(&resized), Some(false));
+        assert_eq!(cached.matches(&Validators::default()), None);
+    }
+
+    #[tokio::test]
+    async fn test_offline() {
+        let (cache, _dir) = FsCache::new_temp();
+        let resource = FsCacheResource::new(
+            &cache.with_offline(true),
+            MemoryResource {
+                data: vec![1, 2, 3],
+                ..MemoryResource::default()
+            },
+        );
+        let error = resource.read_async().await.unwrap_err();
+        assert_eq!(
+            WouldDownload::from_io(&error),
+            Some(&WouldDownload {
+                entry: resource.entry.clone()
+            })
+        );
+        assert!(WouldDownload::from_io(&resource.size().unwrap_err()).is_some());
+
+        let resource = resource
+            .with_offline(false)
+            .ensure_cached_async()
+            .await
+            .unwrap();
+        let resource = resource.with_offline(true);
+        assert_eq!(resource.read_vec_async().await.unwrap(), vec![1, 2, 3]);
+        *resource.resource.etag.lock().unwrap() = "v2".to_owned();
+        assert!(!resource.revalidate_async().await.unwrap());
+    }
+
+    #[test]
+    fn test_segments() {
+        let options = SegmentedDownload {
+            segment_size: 4,
+            ..SegmentedDownload::default()
+        };
+        assert_eq!(options.segments(10), vec![0..4, 4..8, 8..10]);
+        assert_eq!(options.segments(8), vec![0..4, 4..8]);
+        assert!(options.segments(0).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_verify() {
+        let (cache, _dir) = FsCache::new_temp();
+        let entry = cache.entry("file");
+        entry.write_file(&b"abc"[..]).unwrap();
+
+        let sha256 = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        verify(entry.as_ref(), 3, Some(sha256)).await.unwrap();
+        verify(entry.as_ref(), 3, None).await.unwrap();
+        assert!(verify(entry.as_ref(), 4, None).await.is_err());
+        assert!(
+            verify(entry.as_ref(), 3, Some(&sha256.replace('b', 'c')))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_single_stream_verify() {
+        let (cache, _dir) = FsCache::new_temp();
+        let resource = FsCacheResource::new(
+            &cache,
+            MemoryResource {
+                data: b"abc".to_vec(),
+                unranged: true,
+                ..MemoryResource::default()
+            },
+        );
+        let sha256 = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+        let wrong = SegmentedDownload {
+            sha256: Some(sha256.replace('b', 'c')),
+            ..SegmentedDownload::default()
+        };
+        let error = resource.cache_segmented_async(&wrong).await.unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        assert!(!resource.try_exists_async().await.unwrap());
+
+        let right = SegmentedDownload {
+            sha256: Some(sha256.to_owned()),
+            ..SegmentedDownload::default()
+        };
+        resource.cache_segmented_async(&right).await.unwrap();
+        assert_eq!(resource.read_vec_async().await.unwrap(), b"abc");
+    }
+}
@@ -7,7 +7,7 @@ use std::{
 
 use pin_project::pin_project;
 
-use super::{Compression, RawResource, RawResourceExt, ResourceRef};
+use super::{Compression, RawResource, RawResourceExt, ResourceRef, Validators};
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct DecompressedResource<R> {
@@ -89,7 +89,16 @@ impl<R: RawResource> RawResource for DecompressedResource<R> {
                         .await?,
                 ),
             )),
-            Some(Compression::MultiGzip) => todo!(),
+            Some(Compression::MultiGzip) => {
+                let mut decoder = async_compression::tokio::bufread::GzipDecoder::new(
+                    ResourceRef::new(&self.resource)
+                        .buffered()
+                        .read_async()
+                        .await?,
+                );
+                decoder.multiple_members(true);
+                Ok(AsyncDecompressedReader::MultiGzip(decoder))
+            }
             Some(Compression::Brotli) => Ok(AsyncDecompressedReader::Brotli(
                 async_compression::tokio::bufread::BrotliDecoder::new(
                     ResourceRef::new(&self.resource)
@@ -100,6 +109,14 @@ impl<R: RawResource> RawResource for DecompressedResource<R> {
             )),
         }
     }
+
+    /// The versions of the compressed and decompressed files are the same.
+    async fn validators_async(&self) -> std::io::Result<Option<Validators>> {
+        self.resource.validators_async().await
+    }
+    async fn is_fresh_async(&self, cached: &Validators) -> std::io::Result<Option<bool>> {
+        self.resource.is_fresh_async(cached).await
+    }
 }
 
 pub enum DecompressedReader<R: std::io::Read> {
@@ -125,8 +142,7 @@ impl<R: std::io::Read> std::io::Read for DecompressedReader<R> {
 pub enum AsyncDecompressedReader<R> {
     None(#[pin] R),
     Gzip(#[pin] async_compression::tokio::bufread::GzipDecoder<tokio::io::BufReader<R>>),
-    // // TODO: async multi-gz?
-    // MultiGzip(#[pin] async_compression::tokio::bufread::GzipDecoder<tokio::io::BufReader<R>>),
+    MultiGzip(#[pin] async_compression::tokio::bufread::GzipDecoder<tokio::io::BufReader<R>>),
     Brotli(#[pin] async_compression::tokio::bufread::BrotliDecoder<tokio::io::BufReader<R>>),
 }
 impl<R: tokio::io::AsyncRead> tokio::io::AsyncRead for AsyncDecompressedReader<R> {
@@ -138,7 +154,7 @@ impl<R: tokio::io::AsyncRead> tokio::io::AsyncRead for AsyncDecompressedReader<R
         match self.project() {
             AsyncDecompressedReaderProj::None(reader) => reader.poll_read(cx, buf),
             AsyncDecompressedReaderProj::Gzip(decoder) => decoder.poll_read(cx, buf),
-            // AsyncDecompressedReaderProj::MultiGzip(decoder) => decoder.poll_read(cx, buf),
+            AsyncDecompressedReaderProj::MultiGzip(decoder) => decoder.poll_read(cx, buf),
             AsyncDecompressedReaderProj::Brotli(decoder) => decoder.poll_read(cx, buf),
         }
     }
@@ -232,6 +248,14 @@ impl<R: RawResource> RawResource for CompressedResource<R> {
             )),
         }
     }
+
+    /// The versions of the compressed and decompressed files are the same.
+    async fn validators_async(&self) -> std::io::Result<Option<Validators>> {
+        self.resource.validators_async().await
+    }
+    async fn is_fresh_async(&self, cached: &Validators) -> std::io::Result<Option<bool>> {
+        self.resource.is_fresh_async(cached).await
+    }
 }
 pub enum CompressedReader<R: std::io::Read> {
     Gzip(flate2::bufread::GzEncoder<std::io::BufReader<R>>),
@@ -290,3 +314,30 @@ mod boilerplate {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use crate::fs::FsCache;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_multi_gzip_async() {
+        let member = |data: &[u8]| {
+            let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+            encoder.write_all(data).unwrap();
+            encoder.finish().unwrap()
+        };
+        let (cache, _dir) = FsCache::new_temp();
+        let entry = cache.entry("data.gz");
+        let file = [member(b"first\n"), member(b"second\n")].concat();
+        entry.write_file(&file[..]).unwrap();
+
+        let resource = entry.decompressed_with(Compression::MultiGzip);
+        let data = resource.read_string_async().await.unwrap();
+        assert_eq!(data, "first\nsecond\n");
+        assert_eq!(resource.read_string().unwrap(), data);
+    }
+}
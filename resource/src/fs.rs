@@ -1,7 +1,12 @@
 use std::{
+    collections::{BTreeMap, HashMap},
     fmt, io,
     path::{Path, PathBuf},
-    sync::LazyLock,
+    sync::{
+        LazyLock, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, SystemTime},
 };
 
 use directories::ProjectDirs;
@@ -10,25 +15,117 @@ use utile::io::not_found_error;
 
 use crate::RawResource;
 
+/// The environment variable setting the [CacheQuota::total] of [FsCache::global], in bytes.
+const GLOBAL_QUOTA_VAR: &str = "BIO_DATA_CACHE_QUOTA";
+/// The environment variable making [FsCache::global] offline when set to `1` or `true`.
+const GLOBAL_OFFLINE_VAR: &str = "BIO_DATA_OFFLINE";
+/// The marker of a pinned entry, see [FsCacheEntry::pin].
+const PINNED_SUFFIX: &str = ".pinned";
+/// How long [FsCache::evict_except] trusts the [USAGE] of a cache, as other processes (and
+/// writes outside of [crate::cached]) also add to it.
+const USAGE_TTL: Duration = Duration::from_secs(60);
+
+/// See [FsCache::set_global_offline].
+static GLOBAL_OFFLINE: LazyLock<AtomicBool> = LazyLock::new(|| {
+    let offline = std::env::var(GLOBAL_OFFLINE_VAR).is_ok_and(|var| var == "1" || var == "true");
+    AtomicBool::new(offline)
+});
+/// The usage of each root cache as of its last scan, plus the entries cached since, so that
+/// [FsCache::evict_except] only walks the cache when it may be over its quota.
+static USAGE: LazyLock<Mutex<HashMap<PathBuf, (CacheUsage, SystemTime)>>> =
+    LazyLock::new(Default::default);
+
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct FsCache {
     path: PathBuf,
+    /// The cache the quota applies to, see [Self::subfolder].
+    root: PathBuf,
+    quota: CacheQuota,
+    /// Cache misses fail instead of downloading, see [crate::cached::WouldDownload].
+    offline: bool,
+}
+
+/// Size limits enforced by evicting the least recently accessed files, see [FsCache::evict].
+///
+/// Namespaces are relative to the root cache, also for [FsCache::subfolder]s.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CacheQuota {
+    /// In bytes, for the whole cache.
+    pub total: Option<u64>,
+    /// In bytes, by namespace (the top-level folder, e.g. `pan_ukbb`).
+    pub namespaces: BTreeMap<String, u64>,
+}
+
+/// In bytes, including partial downloads.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CacheUsage {
+    pub total: u64,
+    pub namespaces: BTreeMap<String, u64>,
+}
+
+/// An entry in the cache, see [FsCache::entries].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheEntryInfo {
+    pub entry: FsCacheEntry,
+    /// The top-level folder, e.g. `pan_ukbb`.
+    pub namespace: String,
+    /// The path within the namespace, `/`-separated.
+    pub key: String,
+    /// In bytes.
+    pub size: u64,
+    /// Reading an entry counts as accessing it.
+    pub accessed: SystemTime,
+    /// See [FsCacheEntry::pin].
+    pub pinned: bool,
+}
+
+/// A file in the cache, see [FsCache::files].
+#[derive(Debug, Clone)]
+struct CachedFile {
+    path: PathBuf,
+    namespace: String,
+    size: u64,
+    accessed: SystemTime,
 }
+
 impl FsCache {
+    /// With the quota in `BIO_DATA_CACHE_QUOTA`, if set, and offline if `BIO_DATA_OFFLINE` is
+    /// set (or see [Self::set_global_offline]).
     pub fn global() -> Self {
         static PROJECT_DIRS: LazyLock<ProjectDirs> = LazyLock::new(|| {
             let cache = directories::ProjectDirs::from("", "bio_data", "bio_data").unwrap();
             log::info!("Using global cache at {}", cache.cache_dir().display());
             cache
         });
+        static QUOTA: LazyLock<CacheQuota> = LazyLock::new(|| {
+            let total = std::env::var(GLOBAL_QUOTA_VAR).ok().map(|quota| {
+                quota
+                    .parse()
+                    .unwrap_or_else(|_| panic!("Invalid {GLOBAL_QUOTA_VAR}: {quota:?}"))
+            });
+            CacheQuota {
+                total,
+                ..CacheQuota::default()
+            }
+        });
 
         Self::new(PROJECT_DIRS.cache_dir())
+            .with_quota(QUOTA.clone())
+            .with_offline(GLOBAL_OFFLINE.load(Ordering::Relaxed))
+    }
+    /// Whether [Self::global] caches obtained from now on are offline, e.g. on compute nodes
+    /// without network access.
+    pub fn set_global_offline(offline: bool) {
+        GLOBAL_OFFLINE.store(offline, Ordering::Relaxed);
     }
 
     pub fn new(path: impl AsRef<Path>) -> Self {
         assert!(path.as_ref().is_absolute(), "{}", path.as_ref().display());
         Self {
             path: path.as_ref().to_path_buf(),
+            root: path.as_ref().to_path_buf(),
+            quota: CacheQuota::default(),
+            offline: false,
         }
     }
     pub fn new_temp() -> (Self, tempfile::TempDir) {
@@ -36,20 +133,219 @@ impl FsCache {
             .suffix("bio_data")
             .tempdir()
             .unwrap();
-        (
-            Self {
-                path: temp.path().to_path_buf(),
-            },
-            temp,
-        )
+        (Self::new(temp.path()), temp)
+    }
+
+    pub fn with_quota(self, quota: CacheQuota) -> Self {
+        Self { quota, ..self }
+    }
+    pub fn quota(&self) -> &CacheQuota {
+        &self.quota
+    }
+    pub fn with_offline(self, offline: bool) -> Self {
+        Self { offline, ..self }
+    }
+    pub fn is_offline(&self) -> bool {
+        self.offline
     }
 
     pub fn entry(&self, key: impl AsRef<Path>) -> FsCacheEntry {
         FsCacheEntry::new(self, key)
     }
 
+    /// With the same offline setting, and sharing the quota of the root cache: usage and
+    /// eviction cover the whole root cache.
     pub fn subfolder(&self, key: impl AsRef<Path>) -> Self {
-        Self::new(self.path.join(key))
+        Self {
+            path: self.path.join(key),
+            ..self.clone()
+        }
+    }
+
+    /// Of the root cache, see [Self::subfolder].
+    pub fn usage(&self) -> io::Result<CacheUsage> {
+        let mut usage = CacheUsage::default();
+        for file in self.files(&self.root)? {
+            usage.add(&file.namespace, file.size);
+        }
+        Ok(usage)
+    }
+
+    /// Deletes the least recently accessed files until the root cache is within its quota,
+    /// returning the deleted paths. Partial downloads and pinned entries are never deleted, and
+    /// metadata goes with its entry.
+    pub fn evict(&self) -> io::Result<Vec<PathBuf>> {
+        self.evict_files(None)
+    }
+    /// Like [Self::evict], but keeping `entry` (e.g. because it was just cached).
+    ///
+    /// Only walks the cache if it may be over its quota, going by the usage found by the last
+    /// walk and the entries added since.
+    pub fn evict_except(&self, entry: Option<&FsCacheEntry>) -> io::Result<Vec<PathBuf>> {
+        if self.quota == CacheQuota::default() {
+            return Ok(vec![]);
+        }
+        if let Some(entry) = entry {
+            let size = std::fs::metadata(entry).map_or(0, |m| m.len());
+            let mut usages = USAGE.lock().unwrap();
+            if let Some((usage, scanned)) = usages.get_mut(&self.root)
+                && scanned.elapsed().is_ok_and(|elapsed| elapsed < USAGE_TTL)
+            {
+                let namespace = self.namespace(&entry.path);
+                usage.add(&namespace, size);
+                if !self.quota.is_exceeded(usage, &namespace) {
+                    return Ok(vec![]);
+                }
+            }
+        }
+        self.evict_files(entry)
+    }
+    fn evict_files(&self, entry: Option<&FsCacheEntry>) -> io::Result<Vec<PathBuf>> {
+        let scanned = SystemTime::now();
+        let mut files = self.files(&self.root)?;
+        files.sort_by_key(|file| file.accessed);
+        let mut usage = CacheUsage::default();
+        for file in &files {
+            usage.add(&file.namespace, file.size);
+        }
+
+        let mut evicted = vec![];
+        for file in files {
+            if !self.quota.is_exceeded(&usage, &file.namespace)
+                || entry.is_some_and(|entry| entry.path == file.path)
+                || is_sidecar(&file.path)
+                || sidecar_path(&file.path, PINNED_SUFFIX).exists()
+            {
+                continue;
+            }
+            remove_with_metadata(&file.path)?;
+            usage.total -= file.size;
+            *usage.namespaces.get_mut(&file.namespace).unwrap() -= file.size;
+            evicted.push(file.path);
+        }
+        USAGE
+            .lock()
+            .unwrap()
+            .insert(self.root.clone(), (usage, scanned));
+        Ok(evicted)
+    }
+
+    /// The entries in the cache, without partial downloads and metadata, sorted by path.
+    ///
+    /// Namespaces and keys are relative to the root cache, see [Self::subfolder].
+    pub fn entries(&self) -> io::Result<Vec<CacheEntryInfo>> {
+        let mut entries: Vec<_> = self
+            .files(&self.path)?
+            .into_iter()
+            .filter(|file| !is_sidecar(&file.path))
+            .map(|file| {
+                let key = file
+                    .path
+                    .strip_prefix(&self.root)
+                    .unwrap()
+                    .components()
+                    .skip(1)
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                let entry = FsCacheEntry { path: file.path };
+                CacheEntryInfo {
+                    pinned: entry.is_pinned(),
+                    entry,
+                    namespace: file.namespace,
+                    key,
+                    size: file.size,
+                    accessed: file.accessed,
+                }
+            })
+            .collect();
+        entries.sort_by(|a, b| a.entry.cmp(&b.entry));
+        Ok(entries)
+    }
+    pub fn namespace_entries(&self, namespace: &str) -> io::Result<Vec<CacheEntryInfo>> {
+        let mut entries = self.entries()?;
+        entries.retain(|entry| entry.namespace == namespace);
+        Ok(entries)
+    }
+
+    /// Deletes the entries for which `f` is true, with their metadata, returning them.
+    /// Pinned entries are kept.
+    pub fn remove_where(
+        &self,
+        mut f: impl FnMut(&CacheEntryInfo) -> bool,
+    ) -> io::Result<Vec<CacheEntryInfo>> {
+        let mut removed = vec![];
+        for entry in self.entries()? {
+            if entry.pinned || !f(&entry) {
+                continue;
+            }
+            remove_with_metadata(&entry.entry.path)?;
+            removed.push(entry);
+        }
+        Ok(removed)
+    }
+    /// Deletes the entries whose `<namespace>/<key>` matches `pattern`, see [Self::remove_where].
+    pub fn remove_matching(&self, pattern: &regex::Regex) -> io::Result<Vec<CacheEntryInfo>> {
+        self.remove_where(|entry| pattern.is_match(&format!("{}/{}", entry.namespace, entry.key)))
+    }
+    /// Deletes the entries not accessed for `age`, see [Self::remove_where].
+    pub fn remove_older_than(&self, age: Duration) -> io::Result<Vec<CacheEntryInfo>> {
+        let Some(cutoff) = SystemTime::now().checked_sub(age) else {
+            return Ok(vec![]);
+        };
+        self.remove_where(|entry| entry.accessed < cutoff)
+    }
+
+    /// All files under `folder`, with their namespace.
+    fn files(&self, folder: &Path) -> io::Result<Vec<CachedFile>> {
+        let mut files = vec![];
+        let mut folders = vec![folder.to_owned()];
+        while let Some(folder) = folders.pop() {
+            let entries = match std::fs::read_dir(&folder) {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            };
+            for entry in entries {
+                let entry = entry?;
+                let metadata = entry.metadata()?;
+                if metadata.is_dir() {
+                    folders.push(entry.path());
+                    continue;
+                }
+                let path = entry.path();
+                files.push(CachedFile {
+                    namespace: self.namespace(&path),
+                    path,
+                    size: metadata.len(),
+                    accessed: metadata.modified()?,
+                });
+            }
+        }
+        Ok(files)
+    }
+    /// The top-level folder of the root cache `path` is in.
+    fn namespace(&self, path: &Path) -> String {
+        path.strip_prefix(&self.root)
+            .ok()
+            .and_then(|path| path.components().next())
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+}
+impl CacheQuota {
+    fn is_exceeded(&self, usage: &CacheUsage, namespace: &str) -> bool {
+        self.total.is_some_and(|quota| usage.total > quota)
+            || self
+                .namespaces
+                .get(namespace)
+                .is_some_and(|&quota| usage.namespaces.get(namespace).copied().unwrap_or(0) > quota)
+    }
+}
+impl CacheUsage {
+    fn add(&mut self, namespace: &str, size: u64) {
+        self.total += size;
+        *self.namespaces.entry(namespace.to_owned()).or_default() += size;
     }
 }
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -74,9 +370,32 @@ impl FsCacheEntry {
         Self { path }
     }
 
+    /// The entry at the same path with `suffix` appended, e.g. `.part`.
+    pub(crate) fn sibling(&self, suffix: &str) -> Self {
+        let mut path = self.path.clone().into_os_string();
+        path.push(suffix);
+        Self { path: path.into() }
+    }
+
     pub fn try_exists(&self) -> std::io::Result<bool> {
         self.as_ref().try_exists()
     }
+
+    /// Protects the entry from eviction and removal (but not invalidation), until unpinned.
+    pub fn pin(&self) -> std::io::Result<()> {
+        let pin = sidecar_path(&self.path, PINNED_SUFFIX);
+        std::fs::create_dir_all(self.path.parent().unwrap())?;
+        std::fs::write(pin, [])
+    }
+    pub fn unpin(&self) -> std::io::Result<()> {
+        match std::fs::remove_file(sidecar_path(&self.path, PINNED_SUFFIX)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+    pub fn is_pinned(&self) -> bool {
+        sidecar_path(&self.path, PINNED_SUFFIX).exists()
+    }
     #[cfg(not(target_arch = "wasm32"))] // TODO
     pub async fn try_exists_async(&self) -> std::io::Result<bool> {
         tokio::fs::try_exists(&self).await
@@ -123,6 +442,25 @@ impl FsCacheEntry {
         Ok(())
     }
 
+    /// Like [Self::write_file_with], `f` writes to the temporary file at the given path.
+    #[cfg(not(target_arch = "wasm32"))] // TODO
+    pub async fn write_file_with_async(
+        &self,
+        f: impl AsyncFnOnce(&Path) -> std::io::Result<()>,
+    ) -> std::io::Result<()> {
+        tokio::fs::create_dir_all(self.path.parent().unwrap()).await?;
+
+        let tmp_file = tempfile::Builder::new()
+            .prefix("tempfile_")
+            .suffix("_utile")
+            .tempfile_in(self.path.parent().unwrap())?;
+        f(tmp_file.path()).await?;
+
+        rename_or_copy_async(tmp_file, &self).await?;
+
+        Ok(())
+    }
+
     pub fn write_json<T: serde::Serialize>(&self, data: &T) -> std::io::Result<()> {
         self.write_file_with(|file| Ok(serde_json::to_writer(file, data)?))
     }
@@ -157,8 +495,11 @@ impl RawResource for FsCacheEntry {
     fn size(&self) -> std::io::Result<u64> {
         std::fs::metadata(self).map(|m| m.len())
     }
+    /// Marks the entry as accessed, see [FsCache::evict].
     fn read(&self) -> std::io::Result<Self::Reader> {
-        std::fs::File::open(self).map_err(|e| not_found_error(e, self))
+        let file = std::fs::File::open(self).map_err(|e| not_found_error(e, self))?;
+        touch(&file);
+        Ok(file)
     }
 
     #[cfg(not(target_arch = "wasm32"))] // TODO
@@ -168,10 +509,14 @@ impl RawResource for FsCacheEntry {
         tokio::fs::metadata(self).await.map(|m| m.len())
     }
     #[cfg(not(target_arch = "wasm32"))] // TODO
+    /// Marks the entry as accessed, see [FsCache::evict].
     async fn read_async(&self) -> std::io::Result<Self::AsyncReader> {
-        tokio::fs::File::open(self)
+        let file = tokio::fs::File::open(self)
             .await
-            .map_err(|e| not_found_error(e, self))
+            .map_err(|e| not_found_error(e, self))?;
+        let file = file.into_std().await;
+        touch(&file);
+        Ok(tokio::fs::File::from_std(file))
     }
     #[cfg(target_arch = "wasm32")]
     type AsyncReader = std::io::Cursor<&'static [u8]>;
@@ -185,7 +530,37 @@ impl RawResource for FsCacheEntry {
     }
 }
 
-// Add these new helper functions
+/// The modification time doubles as the access time, which is often not updated by the OS.
+fn touch(file: &std::fs::File) {
+    if let Err(e) = file.set_modified(SystemTime::now()) {
+        log::debug!("Failed to update the access time of a cache entry: {e}");
+    }
+}
+/// Partial downloads and metadata, see [FsCacheEntry::sibling].
+fn is_sidecar(path: &Path) -> bool {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    name.ends_with(".part")
+        || name.ends_with(".part.json")
+        || name.ends_with(".validators.json")
+        || name.ends_with(PINNED_SUFFIX)
+        || name.starts_with("tempfile_")
+}
+fn sidecar_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(suffix);
+    path.into()
+}
+/// Removes an entry and its validators, which would otherwise outlive it.
+fn remove_with_metadata(path: &Path) -> io::Result<()> {
+    for path in [path.to_owned(), sidecar_path(path, ".validators.json")] {
+        match std::fs::remove_file(path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
 fn rename_or_copy(from: impl AsRef<Path>, to: impl AsRef<Path>) -> std::io::Result<()> {
     match std::fs::rename(from.as_ref(), to.as_ref()) {
         Ok(()) => Ok(()),
@@ -199,7 +574,10 @@ fn rename_or_copy(from: impl AsRef<Path>, to: impl AsRef<Path>) -> std::io::Resu
 }
 
 #[cfg(not(target_arch = "wasm32"))] // TODO
-async fn rename_or_copy_async(from: impl AsRef<Path>, to: impl AsRef<Path>) -> std::io::Result<()> {
+pub(crate) async fn rename_or_copy_async(
+    from: impl AsRef<Path>,
+    to: impl AsRef<Path>,
+) -> std::io::Result<()> {
     match tokio::fs::rename(from.as_ref(), to.as_ref()).await {
         Ok(()) => Ok(()),
         Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
@@ -210,3 +588,147 @@ async fn rename_or_copy_async(from: impl AsRef<Path>, to: impl AsRef<Path>) -> s
         Err(e) => Err(e),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subfolder() {
+        let (cache, dir) = FsCache::new_temp();
+        let quota = CacheQuota {
+            total: Some(100),
+            ..CacheQuota::default()
+        };
+        let cache = cache.with_quota(quota.clone()).with_offline(true);
+
+        let subfolder = cache.subfolder("sub");
+        assert_eq!(subfolder.path, dir.path().join("sub"));
+        assert_eq!(subfolder.quota(), &quota);
+        assert!(subfolder.is_offline());
+
+        // The quota covers the root cache, with namespaces relative to it.
+        let write = |cache: &FsCache, key: &str, age: u64| {
+            let entry = cache.entry(key);
+            entry.write_file(&[0; 60][..]).unwrap();
+            let file = std::fs::File::options().write(true).open(&entry).unwrap();
+            file.set_modified(SystemTime::now() - Duration::from_secs(age))
+                .unwrap();
+            entry
+        };
+        let outside = write(&cache, "other/data", 200);
+        let inside = write(&subfolder, "data", 100);
+        assert_eq!(subfolder.usage().unwrap().total, 120);
+        let entries = subfolder.entries().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!((&*entries[0].namespace, &*entries[0].key), ("sub", "data"));
+
+        assert_eq!(subfolder.evict().unwrap(), [outside.path]);
+        assert!(inside.try_exists().unwrap());
+    }
+
+    #[test]
+    fn test_evict_except_usage() {
+        let (cache, _dir) = FsCache::new_temp();
+        let cache = cache.with_quota(CacheQuota {
+            total: Some(100),
+            ..CacheQuota::default()
+        });
+        let write = |key: &str, size: usize| {
+            let entry = cache.entry(key);
+            entry.write_file(&vec![0; size][..]).unwrap();
+            entry
+        };
+
+        let first = write("a/first", 40);
+        assert!(cache.evict_except(Some(&first)).unwrap().is_empty());
+        // Not seen by the last walk, so only found by an explicit eviction.
+        write("a/unseen", 50);
+        let second = write("a/second", 40);
+        assert!(cache.evict_except(Some(&second)).unwrap().is_empty());
+        // The entries added since the last walk are counted.
+        let third = write("a/third", 40);
+        assert_eq!(cache.evict_except(Some(&third)).unwrap().len(), 2);
+        assert!(cache.usage().unwrap().total <= 100);
+    }
+
+    #[test]
+    fn test_quota() {
+        let (cache, _dir) = FsCache::new_temp();
+        let write = |key: &str, size: usize, age: u64| {
+            let entry = cache.entry(key);
+            entry.write_file(&vec![0; size][..]).unwrap();
+            let file = std::fs::File::options().write(true).open(&entry).unwrap();
+            file.set_modified(SystemTime::now() - Duration::from_secs(age))
+                .unwrap();
+            entry
+        };
+        let old = write("a/old", 10, 300);
+        let new = write("a/new", 10, 100);
+        let other = write("b/other", 10, 200);
+        let part = write("b/download.part", 10, 400);
+
+        let usage = cache.usage().unwrap();
+        assert_eq!(usage.total, 40);
+        assert_eq!(usage.namespaces["a"], 20);
+
+        // Nothing to do without a quota.
+        assert!(cache.evict().unwrap().is_empty());
+
+        // Reading an entry makes it the most recently accessed.
+        old.read().unwrap();
+        let cache = cache.with_quota(CacheQuota {
+            total: Some(25),
+            namespaces: BTreeMap::from([("a".to_owned(), 15)]),
+        });
+        let evicted = cache.evict().unwrap();
+        assert_eq!(evicted, vec![other.path.clone(), new.path.clone()]);
+        assert!(old.try_exists().unwrap());
+        assert!(part.try_exists().unwrap());
+
+        let usage = cache.usage().unwrap();
+        assert_eq!(usage.total, 20);
+        assert_eq!(usage.namespaces["a"], 10);
+    }
+
+    #[test]
+    fn test_entries() {
+        let (cache, _dir) = FsCache::new_temp();
+        for key in ["a/x/1.txt", "a/2.txt", "b/3.txt", "b/3.txt.part"] {
+            cache.entry(key).write_file(&b"data"[..]).unwrap();
+        }
+        cache.entry("a/2.txt").pin().unwrap();
+
+        let entries = cache.entries().unwrap();
+        let keys: Vec<_> = entries
+            .iter()
+            .map(|e| (e.namespace.as_str(), e.key.as_str(), e.pinned))
+            .collect();
+        assert_eq!(
+            keys,
+            [
+                ("a", "2.txt", true),
+                ("a", "x/1.txt", false),
+                ("b", "3.txt", false)
+            ]
+        );
+        assert_eq!(entries[0].size, 4);
+        assert_eq!(cache.namespace_entries("b").unwrap().len(), 1);
+
+        let removed = cache
+            .remove_matching(&regex::Regex::new(r"^a/").unwrap())
+            .unwrap();
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].key, "x/1.txt");
+        assert!(
+            cache
+                .remove_older_than(Duration::from_secs(60))
+                .unwrap()
+                .is_empty()
+        );
+
+        cache.entry("a/2.txt").unpin().unwrap();
+        assert_eq!(cache.remove_where(|_| true).unwrap().len(), 2);
+        assert!(cache.entries().unwrap().is_empty());
+    }
+}
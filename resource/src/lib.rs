@@ -1,17 +1,23 @@
 #![feature(impl_trait_in_assoc_type)]
 #![expect(async_fn_in_trait)] // TODO
 
+pub mod bgzf;
 pub mod buffered;
 pub mod cached;
 pub mod compression;
 pub mod fs;
 pub mod iter;
+pub mod mirror;
 pub mod progress;
+pub mod range;
+pub mod rate_limit;
+pub mod s3;
 pub mod uri;
 
 use std::{
     fmt::Debug,
     io::{self, Read},
+    ops::Range,
     pin::pin,
 };
 
@@ -25,11 +31,13 @@ use utile::io::read_ext::AsyncReadInto;
 pub use self::{
     buffered::BufferedResource,
     compression::{CompressedResource, DecompressedResource},
+    mirror::MirroredResource,
     progress::ProgressResource,
+    s3::S3Resource,
     uri::UrlResource,
 };
 
-pub use self::cached::FsCacheResource;
+pub use self::cached::{FsCacheResource, SegmentedDownload, WouldDownload};
 
 type JsonStreamDeserializer<R, T> =
     StreamDeserializer<'static, serde_json::de::IoRead<io::BufReader<R>>, T>;
@@ -47,6 +55,26 @@ pub trait RawResource {
     type AsyncReader: tokio::io::AsyncRead;
     async fn size_async(&self) -> io::Result<u64>;
     async fn read_async(&self) -> io::Result<Self::AsyncReader>;
+
+    /// The bytes in `range`, or `None` if the resource can't be read in parts.
+    ///
+    /// Used to parallelise downloads, see [FsCacheResource::cache_segmented_async].
+    async fn read_range_async(&self, range: Range<u64>) -> io::Result<Option<Self::AsyncReader>> {
+        let _ = range;
+        Ok(None)
+    }
+
+    /// The current [Validators] of the source, or `None` if it has none.
+    async fn validators_async(&self) -> io::Result<Option<Validators>> {
+        Ok(None)
+    }
+    /// Whether a copy with the `cached` validators is still current, or `None` if unknown.
+    ///
+    /// Sources can override this with a conditional request.
+    async fn is_fresh_async(&self, cached: &Validators) -> io::Result<Option<bool>> {
+        let current = self.validators_async().await?;
+        Ok(current.and_then(|current| cached.matches(&current)))
+    }
 }
 pub trait RawResourceExt: RawResource + Sized {
     fn buffered(self) -> BufferedResource<Self> {
@@ -60,6 +88,10 @@ pub trait RawResourceExt: RawResource + Sized {
         FsCacheResource::new(&crate::fs::FsCache::global(), self)
     }
 
+    fn with_mirrors(self, mirrors: impl IntoIterator<Item = Self>) -> MirroredResource<Self> {
+        MirroredResource::new(std::iter::once(self).chain(mirrors).collect())
+    }
+
     fn log_progress(self) -> ProgressResource<Self> {
         ProgressResource::new(self)
     }
@@ -78,6 +110,17 @@ pub trait RawResourceExt: RawResource + Sized {
         CompressedResource::new(self, compression)
     }
 
+    /// For BGZF files read from a seekable source (e.g. a cache entry or a range reader), to
+    /// seek to the virtual positions of an index.
+    fn read_bgzf(&self) -> io::Result<bgzf::Reader<Self::Reader>> {
+        Ok(bgzf::Reader::new(ResourceRef::new(self).read()?))
+    }
+    /// The `.gzi` index of a BGZF file, to seek by decompressed offset with
+    /// [bgzf::Reader::seek_by_uncompressed_position].
+    fn read_gzi(&self) -> io::Result<bgzf::gzi::Index> {
+        bgzf::gzi::io::Reader::new(ResourceRef::new(self).read()?).read_index()
+    }
+
     fn read_vec(&self) -> io::Result<Vec<u8>> {
         let mut reader = ResourceRef::new(self).read()?;
         let mut data = Vec::new();
@@ -171,6 +214,35 @@ impl Compression {
     }
 }
 
+/// What identifies a version of a remote file, to detect when a cached copy is stale.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct Validators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub size: Option<u64>,
+}
+impl Validators {
+    /// Whether `current` is the same version, or `None` if they can't be compared.
+    ///
+    /// ETags are preferred, then the last modification date, and a size change always means a
+    /// new version.
+    pub fn matches(&self, current: &Self) -> Option<bool> {
+        if let (Some(a), Some(b)) = (self.size, current.size)
+            && a != b
+        {
+            return Some(false);
+        }
+        if let (Some(a), Some(b)) = (&self.etag, &current.etag) {
+            return Some(a == b);
+        }
+        if let (Some(a), Some(b)) = (&self.last_modified, &current.last_modified) {
+            return Some(a == b);
+        }
+        None
+    }
+}
+
 /// Just a helper struct to avoid a blanket `impl RawResource for &R`
 /// or requiring a `Clone` bound in some places.
 /// (The blanket impl would allow the builder api to take a reference
@@ -207,4 +279,13 @@ impl<'a, R: RawResource> RawResource for ResourceRef<'a, R> {
     async fn read_async(&self) -> io::Result<Self::AsyncReader> {
         R::read_async(self.resource).await
     }
+    async fn read_range_async(&self, range: Range<u64>) -> io::Result<Option<Self::AsyncReader>> {
+        R::read_range_async(self.resource, range).await
+    }
+    async fn validators_async(&self) -> io::Result<Option<Validators>> {
+        R::validators_async(self.resource).await
+    }
+    async fn is_fresh_async(&self, cached: &Validators) -> io::Result<Option<bool>> {
+        R::is_fresh_async(self.resource, cached).await
+    }
 }
@@ -0,0 +1,183 @@
+//! Resources available from several origins, to survive the outage of any single one.
+
+use std::{io, ops::Range, time::Duration};
+
+use web_time::Instant;
+
+use super::{Compression, RawResource, Validators};
+
+/// Reads from the first candidate that succeeds, see [Self::fastest_first_async].
+///
+/// The namespace, key and compression are those of the first (primary) candidate, so the cache
+/// entry doesn't depend on which mirror was used.
+/// Mirrors rarely share ETags, so validators may report a change after failing over.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MirroredResource<R> {
+    candidates: Vec<R>,
+    /// The order in which candidates are tried.
+    order: Vec<usize>,
+}
+impl<R: RawResource> MirroredResource<R> {
+    /// Panics if there are no candidates.
+    pub fn new(candidates: Vec<R>) -> Self {
+        assert!(
+            !candidates.is_empty(),
+            "A mirrored resource needs a candidate."
+        );
+        let order = (0..candidates.len()).collect();
+        Self { candidates, order }
+    }
+    pub fn with_mirror(mut self, mirror: R) -> Self {
+        self.order.push(self.candidates.len());
+        self.candidates.push(mirror);
+        self
+    }
+
+    pub fn primary(&self) -> &R {
+        &self.candidates[0]
+    }
+    /// In the order they are tried.
+    pub fn candidates(&self) -> impl Iterator<Item = &R> {
+        self.order.iter().map(|&i| &self.candidates[i])
+    }
+
+    /// Tries the candidates by how quickly they answer a size request, those that fail last.
+    pub async fn fastest_first_async(mut self) -> Self {
+        let latencies = futures::future::join_all(self.candidates.iter().map(async |candidate| {
+            let start = Instant::now();
+            match candidate.size_async().await {
+                Ok(_) => start.elapsed(),
+                Err(_) => Duration::MAX,
+            }
+        }))
+        .await;
+        self.order.sort_by_key(|&i| latencies[i]);
+        log::info!(
+            "[Mirror] Using {} for {}.",
+            self.candidates[self.order[0]].key(),
+            self.key()
+        );
+        self
+    }
+
+    fn first_ok<T>(&self, mut f: impl FnMut(&R) -> io::Result<T>) -> io::Result<T> {
+        let mut error = None;
+        for candidate in self.candidates() {
+            match f(candidate) {
+                Ok(ok) => return Ok(ok),
+                Err(e) => {
+                    log::warn!(
+                        "[Mirror] {} failed, trying the next mirror: {e}",
+                        candidate.key()
+                    );
+                    error = Some(e);
+                }
+            }
+        }
+        Err(error.unwrap())
+    }
+    async fn first_ok_async<T>(
+        &self,
+        mut f: impl AsyncFnMut(&R) -> io::Result<T>,
+    ) -> io::Result<T> {
+        let mut error = None;
+        for candidate in self.candidates() {
+            match f(candidate).await {
+                Ok(ok) => return Ok(ok),
+                Err(e) => {
+                    log::warn!(
+                        "[Mirror] {} failed, trying the next mirror: {e}",
+                        candidate.key()
+                    );
+                    error = Some(e);
+                }
+            }
+        }
+        Err(error.unwrap())
+    }
+}
+impl<R: RawResource> RawResource for MirroredResource<R> {
+    const NAMESPACE: &'static str = R::NAMESPACE;
+    fn key(&self) -> String {
+        self.primary().key()
+    }
+    fn compression(&self) -> Option<Compression> {
+        self.primary().compression()
+    }
+
+    type Reader = R::Reader;
+    fn size(&self) -> io::Result<u64> {
+        self.first_ok(R::size)
+    }
+    fn read(&self) -> io::Result<Self::Reader> {
+        self.first_ok(R::read)
+    }
+
+    type AsyncReader = R::AsyncReader;
+    async fn size_async(&self) -> io::Result<u64> {
+        self.first_ok_async(async |c| c.size_async().await).await
+    }
+    async fn read_async(&self) -> io::Result<Self::AsyncReader> {
+        self.first_ok_async(async |c| c.read_async().await).await
+    }
+    async fn read_range_async(&self, range: Range<u64>) -> io::Result<Option<Self::AsyncReader>> {
+        self.first_ok_async(async |c| c.read_range_async(range.clone()).await)
+            .await
+    }
+    async fn validators_async(&self) -> io::Result<Option<Validators>> {
+        self.first_ok_async(async |c| c.validators_async().await)
+            .await
+    }
+    async fn is_fresh_async(&self, cached: &Validators) -> io::Result<Option<bool>> {
+        self.first_ok_async(async |c| c.is_fresh_async(cached).await)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fails unless it has data.
+    struct Origin(Option<&'static [u8]>);
+    impl RawResource for Origin {
+        const NAMESPACE: &'static str = "origin";
+        fn key(&self) -> String {
+            format!("{:?}", self.0)
+        }
+        fn compression(&self) -> Option<Compression> {
+            None
+        }
+
+        type Reader = &'static [u8];
+        fn size(&self) -> io::Result<u64> {
+            Ok(self.read()?.len().try_into().unwrap())
+        }
+        fn read(&self) -> io::Result<Self::Reader> {
+            self.0.ok_or_else(|| io::Error::other("Down."))
+        }
+
+        type AsyncReader = &'static [u8];
+        async fn size_async(&self) -> io::Result<u64> {
+            self.size()
+        }
+        async fn read_async(&self) -> io::Result<Self::AsyncReader> {
+            self.read()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failover() {
+        let resource = MirroredResource::new(vec![Origin(None)]).with_mirror(Origin(Some(b"data")));
+        assert_eq!(resource.key(), "None");
+        assert_eq!(resource.read().unwrap(), b"data");
+        assert_eq!(resource.size_async().await.unwrap(), 4);
+
+        let resource = resource.fastest_first_async().await;
+        assert!(resource.candidates().next().unwrap().0.is_some());
+        assert_eq!(resource.key(), "None");
+
+        let down = MirroredResource::new(vec![Origin(None), Origin(None)]);
+        assert!(down.read_async().await.is_err());
+    }
+}
@@ -1,8 +1,8 @@
-use std::pin::Pin;
+use std::{ops::Range, pin::Pin};
 
 use indicatif::ProgressStyle;
 
-use super::{Compression, RawResource};
+use super::{Compression, RawResource, Validators};
 
 const PROGRESS_BAR_STYLE: &str =
     "{spinner} {bytes} ({percent}%) of {total_bytes} | {bytes_per_sec} {wide_bar} {eta}";
@@ -55,4 +55,25 @@ impl<R: RawResource> RawResource for ProgressResource<R> {
         .with_style(style)
         .wrap_async_read(Box::pin(reader)))
     }
+    async fn read_range_async(
+        &self,
+        range: Range<u64>,
+    ) -> std::io::Result<Option<Self::AsyncReader>> {
+        let style = ProgressStyle::with_template(PROGRESS_BAR_STYLE).unwrap();
+        let size = range.end - range.start;
+        let Some(reader) = self.resource.read_range_async(range).await? else {
+            return Ok(None);
+        };
+        Ok(Some(
+            indicatif::ProgressBar::new(size)
+                .with_style(style)
+                .wrap_async_read(Box::pin(reader)),
+        ))
+    }
+    async fn validators_async(&self) -> std::io::Result<Option<Validators>> {
+        self.resource.validators_async().await
+    }
+    async fn is_fresh_async(&self, cached: &Validators) -> std::io::Result<Option<bool>> {
+        self.resource.is_fresh_async(cached).await
+    }
 }
@@ -0,0 +1,132 @@
+//! Seekable readers over remote files, fetching a block at a time, see [RangeReader].
+
+use std::{
+    io::{self, Read, Seek, SeekFrom},
+    ops::Range,
+};
+
+use bytes::Bytes;
+
+/// A source that can be read in parts, e.g. with HTTP range requests.
+pub trait RangeFetch {
+    /// The bytes in `range`, which is within the source.
+    fn fetch_range(&self, range: Range<u64>) -> io::Result<Bytes>;
+}
+
+/// A seekable reader fetching `block_size` bytes at a time, for reading small parts of large
+/// files (e.g. through an index) without downloading them.
+#[derive(Debug)]
+pub struct RangeReader<F> {
+    source: F,
+    size: u64,
+    position: u64,
+    block_size: u64,
+    block_start: u64,
+    block: Bytes,
+}
+impl<F> RangeReader<F> {
+    /// `size` is the size of the whole source.
+    pub fn new(source: F, size: u64, block_size: u64) -> Self {
+        Self {
+            source,
+            size,
+            position: 0,
+            block_size: block_size.max(1),
+            block_start: 0,
+            block: Bytes::new(),
+        }
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+    pub fn source(&self) -> &F {
+        &self.source
+    }
+}
+impl<F: RangeFetch> Read for RangeReader<F> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position >= self.size || buf.is_empty() {
+            return Ok(0);
+        }
+        let block_end = self.block_start + u64::try_from(self.block.len()).unwrap();
+        if !(self.block_start..block_end).contains(&self.position) {
+            let end = (self.position + self.block_size).min(self.size);
+            self.block = self.source.fetch_range(self.position..end)?;
+            self.block_start = self.position;
+            if self.block.is_empty() {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+        }
+
+        let offset = usize::try_from(self.position - self.block_start).unwrap();
+        let available = &self.block[offset..];
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.position += u64::try_from(n).unwrap();
+        Ok(n)
+    }
+}
+impl<F> Seek for RangeReader<F> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.size.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        };
+        let Some(position) = position else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Invalid seek to a negative or overflowing position.",
+            ));
+        };
+        self.position = position;
+        Ok(position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, io::BufRead};
+
+    use super::*;
+
+    /// Records the ranges fetched.
+    struct Source {
+        data: Bytes,
+        fetched: RefCell<Vec<Range<u64>>>,
+    }
+    impl RangeFetch for Source {
+        fn fetch_range(&self, range: Range<u64>) -> io::Result<Bytes> {
+            self.fetched.borrow_mut().push(range.clone());
+            let range = usize::try_from(range.start).unwrap()..usize::try_from(range.end).unwrap();
+            Ok(self.data.slice(range))
+        }
+    }
+
+    #[test]
+    fn test_range_reader() {
+        let source = Source {
+            data: Bytes::from_static(b"line 1\nline 2\nline 3\n"),
+            fetched: RefCell::default(),
+        };
+        let mut reader = io::BufReader::new(RangeReader::new(source, 21, 8));
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!(line, "line 1\n");
+
+        reader.seek(SeekFrom::End(-7)).unwrap();
+        let mut rest = String::new();
+        reader.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "line 3\n");
+
+        reader.seek(SeekFrom::Start(10)).unwrap();
+        line.clear();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!(line, "e 2\n");
+        assert!(reader.seek(SeekFrom::Current(-100)).is_err());
+
+        let fetched = reader.into_inner().source.fetched.into_inner();
+        assert_eq!(fetched, [0..8, 14..21, 10..18]);
+    }
+}
@@ -0,0 +1,154 @@
+//! Per-host request rate limits, shared by every resource in the process.
+//!
+//! Requests to a limited host wait for a token from its bucket, see [set_limit].
+//! [UrlResource](crate::UrlResource) and [S3Resource](crate::S3Resource) requests go through
+//! [acquire_async] (or [acquire]), other clients should too.
+//! Hosts with published limits are limited by default:
+//! - `rest.ensembl.org`: 15 requests per second.
+//! - `eutils.ncbi.nlm.nih.gov`: 3 requests per second (without an API key).
+
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex},
+    time::Duration,
+};
+
+use url::Url;
+use web_time::Instant;
+
+static BUCKETS: LazyLock<Mutex<HashMap<String, TokenBucket>>> = LazyLock::new(|| {
+    let defaults = [
+        ("rest.ensembl.org", RateLimit::per_second(15.)),
+        ("eutils.ncbi.nlm.nih.gov", RateLimit::per_second(3.)),
+    ];
+    let buckets = defaults
+        .into_iter()
+        .map(|(host, limit)| (host.to_owned(), TokenBucket::new(limit)))
+        .collect();
+    Mutex::new(buckets)
+});
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    pub requests_per_second: f64,
+    /// How many requests can be made at once after a pause.
+    pub burst: u32,
+}
+impl RateLimit {
+    /// With a burst of one second worth of requests.
+    pub fn per_second(requests_per_second: f64) -> Self {
+        Self {
+            requests_per_second,
+            burst: (requests_per_second.ceil() as u32).max(1),
+        }
+    }
+    pub fn with_burst(self, burst: u32) -> Self {
+        Self {
+            burst: burst.max(1),
+            ..self
+        }
+    }
+}
+
+/// Limits requests to `host` (e.g. `rest.ensembl.org`), or removes its limit if `None`.
+pub fn set_limit(host: &str, limit: Option<RateLimit>) {
+    let mut buckets = BUCKETS.lock().unwrap();
+    match limit {
+        Some(limit) => {
+            buckets.insert(host.to_owned(), TokenBucket::new(limit));
+        }
+        None => {
+            buckets.remove(host);
+        }
+    }
+}
+pub fn limit(host: &str) -> Option<RateLimit> {
+    let buckets = BUCKETS.lock().unwrap();
+    buckets.get(host).map(|bucket| bucket.limit)
+}
+
+/// Waits until a request to `url` is allowed.
+pub async fn acquire_async(url: &Url) {
+    let wait = reserve(url);
+    if !wait.is_zero() {
+        log::debug!("[RateLimit] Waiting {wait:?} before requesting {url}.");
+        utile::time::sleep(wait).await;
+    }
+}
+/// Blocks until a request to `url` is allowed.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn acquire(url: &Url) {
+    let wait = reserve(url);
+    if !wait.is_zero() {
+        log::debug!("[RateLimit] Waiting {wait:?} before requesting {url}.");
+        std::thread::sleep(wait);
+    }
+}
+
+fn reserve(url: &Url) -> Duration {
+    let Some(host) = url.host_str() else {
+        return Duration::ZERO;
+    };
+    let mut buckets = BUCKETS.lock().unwrap();
+    match buckets.get_mut(host) {
+        Some(bucket) => bucket.take(Instant::now()),
+        None => Duration::ZERO,
+    }
+}
+
+#[derive(Debug, Clone)]
+struct TokenBucket {
+    limit: RateLimit,
+    /// Negative when requests are already waiting for tokens.
+    tokens: f64,
+    updated: Instant,
+}
+impl TokenBucket {
+    fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            tokens: limit.burst.into(),
+            updated: Instant::now(),
+        }
+    }
+    /// Takes a token, returning how long to wait before it is available.
+    fn take(&mut self, now: Instant) -> Duration {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        let rate = self.limit.requests_per_second;
+        self.tokens = (self.tokens + elapsed * rate).min(self.limit.burst.into());
+        self.updated = now.max(self.updated);
+
+        self.tokens -= 1.;
+        if self.tokens >= 0. {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / rate)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_bucket() {
+        let mut bucket = TokenBucket::new(RateLimit::per_second(2.).with_burst(2));
+        let start = bucket.updated;
+        assert_eq!(bucket.take(start), Duration::ZERO);
+        assert_eq!(bucket.take(start), Duration::ZERO);
+        // Queued behind each other.
+        assert_eq!(bucket.take(start), Duration::from_millis(500));
+        assert_eq!(bucket.take(start), Duration::from_secs(1));
+
+        // Refills up to the burst.
+        let later = start + Duration::from_secs(10);
+        assert_eq!(bucket.take(later), Duration::ZERO);
+        assert_eq!(bucket.take(later), Duration::ZERO);
+        assert_eq!(bucket.take(later), Duration::from_millis(500));
+
+        let url = Url::parse("https://unlimited.example.com/data").unwrap();
+        assert_eq!(reserve(&url), Duration::ZERO);
+        assert_eq!(limit("rest.ensembl.org"), Some(RateLimit::per_second(15.)));
+    }
+}
@@ -1,4 +1,4 @@
-use std::{fmt, sync::LazyLock};
+use std::{fmt, ops::Range, sync::LazyLock};
 
 use bytes::Bytes;
 use futures::{Stream, TryStreamExt};
@@ -7,7 +7,10 @@ use url::Url;
 
 use utile::io::{get_filesize_from_headers, reqwest_error};
 
-use super::{Compression, RawResource};
+use super::{
+    Compression, RawResource, Validators,
+    range::{RangeFetch, RangeReader},
+};
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct UrlResource(Url);
@@ -46,6 +49,7 @@ impl UrlResource {
 
     #[cfg(not(target_arch = "wasm32"))]
     pub fn exists(&self) -> reqwest::Result<bool> {
+        crate::rate_limit::acquire(&self.0);
         let response = reqwest::blocking::Client::new()
             .head(self.0.clone())
             .send()?;
@@ -57,6 +61,7 @@ impl UrlResource {
     }
 
     pub async fn exists_async(&self) -> reqwest::Result<bool> {
+        crate::rate_limit::acquire_async(&self.0).await;
         let response = reqwest::Client::new().head(self.0.clone()).send().await?;
         Ok(response.status() == reqwest::StatusCode::OK)
     }
@@ -92,6 +97,7 @@ impl RawResource for UrlResource {
     type Reader = reqwest::blocking::Response;
     #[cfg(not(target_arch = "wasm32"))]
     fn size(&self) -> std::io::Result<u64> {
+        crate::rate_limit::acquire(&self.0);
         let response = reqwest::blocking::Client::new()
             .head(self.0.clone())
             .send()
@@ -111,6 +117,7 @@ impl RawResource for UrlResource {
         log::info!("Downloading {self}");
         static CLIENT: LazyLock<reqwest::blocking::Client> =
             LazyLock::new(reqwest::blocking::Client::new);
+        crate::rate_limit::acquire(&self.0);
         let response = CLIENT
             .get(self.0.clone())
             .send()
@@ -146,6 +153,7 @@ impl RawResource for UrlResource {
         tokio_util::io::StreamReader<impl Stream<Item = std::io::Result<Bytes>>, Bytes>;
     async fn size_async(&self) -> std::io::Result<u64> {
         static CLIENT: LazyLock<reqwest::Client> = LazyLock::new(reqwest::Client::new);
+        crate::rate_limit::acquire_async(&self.0).await;
         let response = CLIENT
             .head(self.0.clone())
             .send()
@@ -162,6 +170,7 @@ impl RawResource for UrlResource {
         }
     }
     async fn read_async(&self) -> std::io::Result<Self::AsyncReader> {
+        crate::rate_limit::acquire_async(&self.0).await;
         let response = reqwest::Client::new()
             .get(self.0.clone())
             .send()
@@ -177,8 +186,136 @@ impl RawResource for UrlResource {
             )));
         }
 
-        let stream = response.bytes_stream().map_err(std::io::Error::other);
-        Ok(tokio_util::io::StreamReader::new(stream))
+        Ok(stream_reader(response))
+    }
+    async fn read_range_async(
+        &self,
+        range: Range<u64>,
+    ) -> std::io::Result<Option<Self::AsyncReader>> {
+        static CLIENT: LazyLock<reqwest::Client> = LazyLock::new(reqwest::Client::new);
+        let Some(last) = range.end.checked_sub(1) else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Empty range.",
+            ));
+        };
+        crate::rate_limit::acquire_async(&self.0).await;
+        let response = CLIENT
+            .get(self.0.clone())
+            .header(
+                reqwest::header::RANGE,
+                format!("bytes={}-{last}", range.start),
+            )
+            .send()
+            .await
+            .map_err(reqwest_error)?
+            .error_for_status()
+            .map_err(reqwest_error)?;
+        if response.status() != reqwest::StatusCode::PARTIAL_CONTENT {
+            return Ok(None);
+        }
+        Ok(Some(stream_reader(response)))
+    }
+
+    async fn validators_async(&self) -> std::io::Result<Option<Validators>> {
+        static CLIENT: LazyLock<reqwest::Client> = LazyLock::new(reqwest::Client::new);
+        crate::rate_limit::acquire_async(&self.0).await;
+        let response = CLIENT
+            .head(self.0.clone())
+            .send()
+            .await
+            .map_err(reqwest_error)?
+            .error_for_status()
+            .map_err(reqwest_error)?;
+        Ok(Some(validators(response.headers())))
+    }
+    /// With a conditional `HEAD` request, falling back to comparing the validators if the
+    /// server ignores the conditions.
+    async fn is_fresh_async(&self, cached: &Validators) -> std::io::Result<Option<bool>> {
+        static CLIENT: LazyLock<reqwest::Client> = LazyLock::new(reqwest::Client::new);
+        crate::rate_limit::acquire_async(&self.0).await;
+        let mut request = CLIENT.head(self.0.clone());
+        if let Some(etag) = &cached.etag {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &cached.last_modified {
+            request = request.header(reqwest::header::IF_MODIFIED_SINCE, last_modified);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(reqwest_error)?
+            .error_for_status()
+            .map_err(reqwest_error)?;
+        if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+            return Ok(Some(true));
+        }
+        Ok(cached.matches(&validators(response.headers())))
+    }
+}
+
+pub(crate) fn validators(headers: &reqwest::header::HeaderMap) -> Validators {
+    let header = |name: reqwest::header::HeaderName| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_owned)
+    };
+    Validators {
+        etag: header(reqwest::header::ETAG),
+        last_modified: header(reqwest::header::LAST_MODIFIED),
+        size: get_filesize_from_headers(headers),
+    }
+}
+
+pub(crate) fn stream_reader(
+    response: reqwest::Response,
+) -> tokio_util::io::StreamReader<impl Stream<Item = std::io::Result<Bytes>> + use<>, Bytes> {
+    let stream = response.bytes_stream().map_err(std::io::Error::other);
+    tokio_util::io::StreamReader::new(stream)
+}
+
+/// A seekable reader over a remote file, fetching a block at a time with HTTP range requests,
+/// see [RangeReader].
+#[cfg(not(target_arch = "wasm32"))]
+pub type UrlRangeReader = RangeReader<UrlResource>;
+#[cfg(not(target_arch = "wasm32"))]
+impl UrlResource {
+    /// See [UrlRangeReader], `block_size` is the number of bytes requested at once.
+    pub fn range_reader(&self, block_size: u64) -> std::io::Result<UrlRangeReader> {
+        Ok(RangeReader::new(self.clone(), self.size()?, block_size))
+    }
+}
+#[cfg(not(target_arch = "wasm32"))]
+impl RangeFetch for UrlResource {
+    fn fetch_range(&self, range: Range<u64>) -> std::io::Result<Bytes> {
+        static CLIENT: LazyLock<reqwest::blocking::Client> =
+            LazyLock::new(reqwest::blocking::Client::new);
+
+        log::debug!(
+            "Fetching bytes {}..{} of {}",
+            range.start,
+            range.end,
+            self.0
+        );
+        crate::rate_limit::acquire(&self.0);
+        let response = CLIENT
+            .get(self.0.clone())
+            .header(
+                reqwest::header::RANGE,
+                format!("bytes={}-{}", range.start, range.end - 1),
+            )
+            .send()
+            .map_err(reqwest_error)?
+            .error_for_status()
+            .map_err(reqwest_error)?;
+        if response.status() != reqwest::StatusCode::PARTIAL_CONTENT {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                format!("Range requests are not supported by {}.", self.0),
+            ));
+        }
+        response.bytes().map_err(reqwest_error)
     }
 }
 
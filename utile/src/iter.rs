@@ -1,4 +1,13 @@
-use std::{cmp::Ordering, collections::VecDeque, iter::Peekable, ops::Range};
+use std::{
+    cmp::Ordering,
+    collections::VecDeque,
+    fs::File,
+    io::{self, BufReader, BufWriter, Write},
+    iter::Peekable,
+    ops::Range,
+};
+
+use serde::{Serialize, de::DeserializeOwned};
 
 pub trait IteratorExt: Iterator + Sized {
     fn spliced(
@@ -20,6 +29,62 @@ pub trait IteratorExt: Iterator + Sized {
     {
         StagedSortedIterator::new(self, s1, s2)
     }
+
+    /// Sort an iterator too large for memory, by spilling sorted runs of `run_size` items to
+    /// temporary files and merging them. The sort is stable.
+    ///
+    /// The whole iterator is consumed (and spilled) here, if it fits in a single run it is
+    /// sorted in memory instead.
+    fn external_sorted_by<F>(
+        self,
+        run_size: usize,
+        compare: F,
+    ) -> io::Result<ExternalSortedIterator<Self::Item, F>>
+    where
+        Self::Item: Serialize + DeserializeOwned,
+        F: FnMut(&Self::Item, &Self::Item) -> Ordering,
+    {
+        ExternalSortedIterator::new(self, run_size, compare)
+    }
+
+    /// Like [Iterator::map], but mapping chunks of `chunk_size` items on the rayon thread pool.
+    /// The order is preserved, so this can feed [Self::staged_sorted_by].
+    #[cfg(not(target_arch = "wasm32"))]
+    fn par_map<F, O>(
+        self,
+        chunk_size: usize,
+        f: F,
+    ) -> ParFilterMap<Self, impl Fn(Self::Item) -> Option<O> + Sync + Send, O>
+    where
+        Self::Item: Send,
+        F: Fn(Self::Item) -> O + Sync + Send,
+        O: Send,
+    {
+        ParFilterMap::new(self, chunk_size, move |item| Some(f(item)))
+    }
+    /// Like [Iterator::filter], see [Self::par_map].
+    #[cfg(not(target_arch = "wasm32"))]
+    fn par_filter<F>(
+        self,
+        chunk_size: usize,
+        f: F,
+    ) -> ParFilterMap<Self, impl Fn(Self::Item) -> Option<Self::Item> + Sync + Send, Self::Item>
+    where
+        Self::Item: Send,
+        F: Fn(&Self::Item) -> bool + Sync + Send,
+    {
+        ParFilterMap::new(self, chunk_size, move |item| f(&item).then_some(item))
+    }
+    /// Like [Iterator::filter_map], see [Self::par_map].
+    #[cfg(not(target_arch = "wasm32"))]
+    fn par_filter_map<F, O>(self, chunk_size: usize, f: F) -> ParFilterMap<Self, F, O>
+    where
+        Self::Item: Send,
+        F: Fn(Self::Item) -> Option<O> + Sync + Send,
+        O: Send,
+    {
+        ParFilterMap::new(self, chunk_size, f)
+    }
 }
 impl<T: Iterator> IteratorExt for T {}
 
@@ -198,3 +263,314 @@ where
         }
     }
 }
+
+/// The most runs merged at once, as each is an open file while merging.
+const MAX_MERGED_RUNS: usize = 64;
+
+pub struct ExternalSortedIterator<T, F> {
+    compare: F,
+    /// If everything fit in a single run.
+    memory: std::vec::IntoIter<T>,
+    merge: Merge<T>,
+}
+/// A sorted run spilled to a temporary file, deleted on drop, and only opened once read.
+struct SortedRun {
+    path: tempfile::TempPath,
+    reader: Option<BufReader<File>>,
+    remaining: usize,
+}
+/// Merges up to [MAX_MERGED_RUNS] sorted runs.
+struct Merge<T> {
+    runs: Vec<SortedRun>,
+    /// The next item of each run.
+    heads: Vec<Option<T>>,
+    /// The runs with items left, as a binary min-heap by head and then by run, so that earlier
+    /// runs win ties and the sort is stable. [std::collections::BinaryHeap] can't take the
+    /// comparison function.
+    heap: Vec<usize>,
+    /// A failed read, reported after the item that preceded it.
+    error: Option<io::Error>,
+}
+impl<T, F> ExternalSortedIterator<T, F>
+where
+    T: Serialize + DeserializeOwned,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    pub fn new(iter: impl Iterator<Item = T>, run_size: usize, mut compare: F) -> io::Result<Self> {
+        let mut iter = iter.peekable();
+        let mut runs = vec![];
+        loop {
+            let mut run: Vec<T> = iter.by_ref().take(run_size.max(1)).collect();
+            if run.is_empty() {
+                break;
+            }
+            run.sort_by(&mut compare);
+            if runs.is_empty() && iter.peek().is_none() {
+                return Ok(Self {
+                    merge: Merge::new(vec![], &mut compare)?,
+                    memory: run.into_iter(),
+                    compare,
+                });
+            }
+            runs.push(SortedRun::write(run.iter().map(Ok))?);
+        }
+
+        // Merge consecutive runs into longer ones until few enough are left, which keeps the
+        // sort stable.
+        while runs.len() > MAX_MERGED_RUNS {
+            let mut merged = Vec::with_capacity(runs.len().div_ceil(MAX_MERGED_RUNS));
+            let mut runs_left = runs.into_iter();
+            loop {
+                let group: Vec<_> = runs_left.by_ref().take(MAX_MERGED_RUNS).collect();
+                match group.len() {
+                    0 => break,
+                    1 => merged.extend(group),
+                    _ => {
+                        let mut merge = Merge::new(group, &mut compare)?;
+                        let items = std::iter::from_fn(|| merge.next(&mut compare));
+                        merged.push(SortedRun::write(items)?);
+                    }
+                }
+            }
+            runs = merged;
+        }
+
+        Ok(Self {
+            memory: vec![].into_iter(),
+            merge: Merge::new(runs, &mut compare)?,
+            compare,
+        })
+    }
+}
+impl<T, F> Iterator for ExternalSortedIterator<T, F>
+where
+    T: DeserializeOwned,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    type Item = io::Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(item) = self.memory.next() {
+            return Some(Ok(item));
+        }
+        self.merge.next(&mut self.compare)
+    }
+}
+impl<T: DeserializeOwned> Merge<T> {
+    fn new(
+        mut runs: Vec<SortedRun>,
+        compare: &mut impl FnMut(&T, &T) -> Ordering,
+    ) -> io::Result<Self> {
+        let heads = runs
+            .iter_mut()
+            .map(SortedRun::next)
+            .collect::<io::Result<_>>()?;
+        let mut merge = Self {
+            runs,
+            heads,
+            heap: vec![],
+            error: None,
+        };
+        for run in 0..merge.runs.len() {
+            if merge.heads[run].is_some() {
+                merge.heap.push(run);
+                merge.sift_up(merge.heap.len() - 1, compare);
+            }
+        }
+        Ok(merge)
+    }
+
+    fn next(&mut self, compare: &mut impl FnMut(&T, &T) -> Ordering) -> Option<io::Result<T>> {
+        if let Some(error) = self.error.take() {
+            self.heap.clear();
+            return Some(Err(error));
+        }
+
+        let &run = self.heap.first()?;
+        let item = self.heads[run].take().unwrap();
+        match self.runs[run].next() {
+            Ok(Some(head)) => self.heads[run] = Some(head),
+            Ok(None) => {
+                self.heap.swap_remove(0);
+            }
+            Err(e) => self.error = Some(e),
+        }
+        if self.error.is_none() {
+            self.sift_down(0, compare);
+        }
+        Some(Ok(item))
+    }
+
+    /// Whether the run at `a` in the heap comes before the one at `b`.
+    fn is_before(&self, a: usize, b: usize, compare: &mut impl FnMut(&T, &T) -> Ordering) -> bool {
+        let (a, b) = (self.heap[a], self.heap[b]);
+        let head = |run: usize| self.heads[run].as_ref().unwrap();
+        compare(head(a), head(b)).then(a.cmp(&b)).is_lt()
+    }
+    fn sift_up(&mut self, mut i: usize, compare: &mut impl FnMut(&T, &T) -> Ordering) {
+        while i > 0 {
+            let parent = (i - 1) / 2;
+            if !self.is_before(i, parent, compare) {
+                break;
+            }
+            self.heap.swap(i, parent);
+            i = parent;
+        }
+    }
+    fn sift_down(&mut self, mut i: usize, compare: &mut impl FnMut(&T, &T) -> Ordering) {
+        loop {
+            let mut first = i;
+            for child in [2 * i + 1, 2 * i + 2] {
+                if child < self.heap.len() && self.is_before(child, first, compare) {
+                    first = child;
+                }
+            }
+            if first == i {
+                break;
+            }
+            self.heap.swap(i, first);
+            i = first;
+        }
+    }
+}
+impl SortedRun {
+    fn write<T: Serialize>(items: impl Iterator<Item = io::Result<T>>) -> io::Result<Self> {
+        let file = tempfile::NamedTempFile::new()?;
+        let mut writer = BufWriter::new(file.as_file());
+        let mut remaining = 0;
+        for item in items {
+            rmp_serde::encode::write_named(&mut writer, &item?).map_err(io::Error::other)?;
+            remaining += 1;
+        }
+        writer.flush()?;
+        drop(writer);
+        Ok(Self {
+            path: file.into_temp_path(),
+            reader: None,
+            remaining,
+        })
+    }
+    fn next<T: DeserializeOwned>(&mut self) -> io::Result<Option<T>> {
+        if self.remaining == 0 {
+            self.reader = None;
+            return Ok(None);
+        }
+        if self.reader.is_none() {
+            self.reader = Some(BufReader::new(File::open(&self.path)?));
+        }
+        self.remaining -= 1;
+        rmp_serde::from_read(self.reader.as_mut().unwrap())
+            .map(Some)
+            .map_err(io::Error::other)
+    }
+}
+
+/// Chunks are collected from the base iterator on the calling thread, then processed in
+/// parallel and yielded in order, see [IteratorExt::par_map].
+#[cfg(not(target_arch = "wasm32"))]
+pub struct ParFilterMap<Base, F, O> {
+    base: Base,
+    chunk_size: usize,
+    f: F,
+    processed: std::vec::IntoIter<O>,
+}
+#[cfg(not(target_arch = "wasm32"))]
+impl<Base, F, O> ParFilterMap<Base, F, O>
+where
+    Base: Iterator<Item: Send>,
+    F: Fn(Base::Item) -> Option<O> + Sync + Send,
+    O: Send,
+{
+    pub fn new(base: Base, chunk_size: usize, f: F) -> Self {
+        Self {
+            base,
+            chunk_size: chunk_size.max(1),
+            f,
+            processed: vec![].into_iter(),
+        }
+    }
+}
+#[cfg(not(target_arch = "wasm32"))]
+impl<Base, F, O> Iterator for ParFilterMap<Base, F, O>
+where
+    Base: Iterator<Item: Send>,
+    F: Fn(Base::Item) -> Option<O> + Sync + Send,
+    O: Send,
+{
+    type Item = O;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+        loop {
+            if let Some(item) = self.processed.next() {
+                return Some(item);
+            }
+            let chunk: Vec<_> = self.base.by_ref().take(self.chunk_size).collect();
+            if chunk.is_empty() {
+                return None;
+            }
+            let processed: Vec<_> = chunk.into_par_iter().filter_map(&self.f).collect();
+            self.processed = processed.into_iter();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_external_sorted_by() {
+        // Pseudo-random keys, with the original index to check stability.
+        let items: Vec<(u64, usize)> = (0..1000).map(|i| ((i * 7919) % 101, i as usize)).collect();
+        let mut expected = items.clone();
+        expected.sort_by_key(|(key, _)| *key);
+
+        let sorted: Vec<_> = items
+            .iter()
+            .copied()
+            .external_sorted_by(64, |a, b| a.0.cmp(&b.0))
+            .unwrap()
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(sorted, expected);
+
+        // More runs than are merged at once.
+        let sorted: Vec<_> = items
+            .iter()
+            .copied()
+            .external_sorted_by(4, |a, b| a.0.cmp(&b.0))
+            .unwrap()
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(sorted, expected);
+
+        // In memory.
+        let sorted: Vec<_> = items
+            .into_iter()
+            .external_sorted_by(1000, |a, b| a.0.cmp(&b.0))
+            .unwrap()
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(sorted, expected);
+
+        let empty = std::iter::empty::<u8>().external_sorted_by(10, Ord::cmp);
+        assert_eq!(empty.unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_par_map() {
+        let squares: Vec<_> = (0..1000u64).par_map(64, |i| i * i).collect();
+        assert_eq!(squares, (0..1000u64).map(|i| i * i).collect::<Vec<_>>());
+
+        let even: Vec<_> = (0..1000u64).par_filter(7, |i| i % 2 == 0).collect();
+        assert_eq!(even, (0..1000u64).step_by(2).collect::<Vec<_>>());
+
+        let parsed: Vec<u8> = ["1", "x", "3"]
+            .into_iter()
+            .par_filter_map(2, |s| s.parse().ok())
+            .collect();
+        assert_eq!(parsed, [1, 3]);
+    }
+}
@@ -164,6 +164,281 @@ where
     }
 }
 
+/// The records of a JSON lines reader, blank lines are skipped.
+pub fn from_reader<T: serde::de::DeserializeOwned>(
+    reader: impl std::io::BufRead,
+) -> impl Iterator<Item = std::io::Result<T>> {
+    reader.lines().filter_map(|line| match line {
+        Ok(line) if line.trim().is_empty() => None,
+        Ok(line) => Some(serde_json::from_str(&line).map_err(std::io::Error::from)),
+        Err(e) => Some(Err(e)),
+    })
+}
+/// Like [from_reader], without blocking the async runtime.
+pub fn from_async_reader<T: serde::de::DeserializeOwned>(
+    reader: impl tokio::io::AsyncBufRead + Unpin,
+) -> impl futures::Stream<Item = std::io::Result<T>> {
+    use tokio::io::AsyncBufReadExt;
+
+    let lines = reader.lines();
+    futures::stream::try_unfold(lines, async |mut lines| {
+        loop {
+            let Some(line) = lines.next_line().await? else {
+                return Ok(None);
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record = serde_json::from_str(&line).map_err(std::io::Error::from)?;
+            return Ok::<_, std::io::Error>(Some((record, lines)));
+        }
+    })
+}
+
+/// JSON lines files, optionally compressed and appended to over time (e.g. to persist
+/// intermediate records of a long analysis).
+#[cfg(not(target_arch = "wasm32"))]
+pub mod file {
+    use std::{
+        fs::File,
+        io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write},
+        path::{Path, PathBuf},
+        pin::Pin,
+    };
+
+    use futures::Stream;
+    use serde::{Serialize, de::DeserializeOwned};
+    use tokio::io::{AsyncBufRead, AsyncWriteExt};
+
+    /// Inferred from the extension of the file, see [JsonLinesCompression::infer].
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum JsonLinesCompression {
+        None,
+        /// `.gz`
+        Gzip,
+        /// `.zst`
+        Zstd,
+    }
+    impl JsonLinesCompression {
+        pub fn infer(path: &Path) -> Self {
+            match path.extension().and_then(|extension| extension.to_str()) {
+                Some("gz") => Self::Gzip,
+                Some("zst") => Self::Zstd,
+                _ => Self::None,
+            }
+        }
+    }
+
+    /// Reads a (possibly compressed) JSON lines file, see [JsonLinesWriter].
+    pub fn read<T: DeserializeOwned>(
+        path: impl AsRef<Path>,
+    ) -> io::Result<impl Iterator<Item = io::Result<T>>> {
+        let path = path.as_ref();
+        let file = BufReader::new(File::open(path)?);
+        // Both decoders read all the concatenated members/frames.
+        let reader: Box<dyn BufRead> = match JsonLinesCompression::infer(path) {
+            JsonLinesCompression::None => Box::new(file),
+            JsonLinesCompression::Gzip => {
+                Box::new(BufReader::new(flate2::bufread::MultiGzDecoder::new(file)))
+            }
+            JsonLinesCompression::Zstd => Box::new(BufReader::new(
+                zstd::stream::read::Decoder::with_buffer(file)?,
+            )),
+        };
+        Ok(super::from_reader(reader))
+    }
+    /// Like [read], without blocking the async runtime.
+    pub async fn read_async<T: DeserializeOwned>(
+        path: impl AsRef<Path>,
+    ) -> io::Result<impl Stream<Item = io::Result<T>>> {
+        use async_compression::tokio::bufread::{GzipDecoder, ZstdDecoder};
+
+        let path = path.as_ref();
+        let file = tokio::io::BufReader::new(tokio::fs::File::open(path).await?);
+        let reader: Pin<Box<dyn AsyncBufRead + Send>> = match JsonLinesCompression::infer(path) {
+            JsonLinesCompression::None => Box::pin(file),
+            JsonLinesCompression::Gzip => {
+                let mut decoder = GzipDecoder::new(file);
+                decoder.multiple_members(true);
+                Box::pin(tokio::io::BufReader::new(decoder))
+            }
+            JsonLinesCompression::Zstd => {
+                let mut decoder = ZstdDecoder::new(file);
+                decoder.multiple_members(true);
+                Box::pin(tokio::io::BufReader::new(decoder))
+            }
+        };
+        Ok(super::from_async_reader(reader))
+    }
+
+    /// Buffers records until flushed, then writes them at once, as a single gzip member or
+    /// zstd frame if compressed. A crash can at most lose the records since the last flush.
+    ///
+    /// Flushes on drop, but errors are only logged there.
+    #[derive(Debug)]
+    pub struct JsonLinesWriter {
+        path: PathBuf,
+        compression: JsonLinesCompression,
+        file: File,
+        buffer: Vec<u8>,
+    }
+    impl JsonLinesWriter {
+        /// Truncates any existing file.
+        pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+            let file = File::create(path.as_ref())?;
+            Ok(Self::new(path.as_ref(), file))
+        }
+        /// Appends to the file, creating it if needed.
+        ///
+        /// A partial last line of an uncompressed file, or a partial last gzip member or zstd
+        /// frame of a compressed one (e.g. from a crash while writing), is removed first.
+        /// Compressed files are decompressed once to find it.
+        pub fn append(path: impl AsRef<Path>) -> io::Result<Self> {
+            let mut file = File::options()
+                .create(true)
+                .read(true)
+                .append(true)
+                .open(path.as_ref())?;
+            match JsonLinesCompression::infer(path.as_ref()) {
+                JsonLinesCompression::None => truncate_partial_line(&mut file)?,
+                compression => truncate_partial_member(&mut file, compression)?,
+            }
+            Ok(Self::new(path.as_ref(), file))
+        }
+        fn new(path: &Path, file: File) -> Self {
+            Self {
+                path: path.to_owned(),
+                compression: JsonLinesCompression::infer(path),
+                file,
+                buffer: vec![],
+            }
+        }
+
+        pub fn path(&self) -> &Path {
+            &self.path
+        }
+
+        /// Buffers the record, see [Self::flush].
+        pub fn write<T: Serialize>(&mut self, record: &T) -> io::Result<()> {
+            serde_json::to_writer(&mut self.buffer, record)?;
+            self.buffer.push(b'\n');
+            Ok(())
+        }
+        /// The number of bytes waiting to be flushed, e.g. to flush periodically.
+        pub fn buffered(&self) -> usize {
+            self.buffer.len()
+        }
+
+        /// Writes the buffered records and syncs them to disk.
+        pub fn flush(&mut self) -> io::Result<()> {
+            let Some(data) = self.take_encoded()? else {
+                return Ok(());
+            };
+            self.file.write_all(&data)?;
+            self.file.sync_data()
+        }
+        /// Like [Self::flush], without blocking the async runtime on IO.
+        pub async fn flush_async(&mut self) -> io::Result<()> {
+            let Some(data) = self.take_encoded()? else {
+                return Ok(());
+            };
+            let mut file = tokio::fs::File::from_std(self.file.try_clone()?);
+            file.write_all(&data).await?;
+            file.flush().await?;
+            file.sync_data().await
+        }
+
+        fn take_encoded(&mut self) -> io::Result<Option<Vec<u8>>> {
+            if self.buffer.is_empty() {
+                return Ok(None);
+            }
+            let data = std::mem::take(&mut self.buffer);
+            let data = match self.compression {
+                JsonLinesCompression::None => data,
+                JsonLinesCompression::Gzip => {
+                    let mut encoder =
+                        flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+                    encoder.write_all(&data)?;
+                    encoder.finish()?
+                }
+                JsonLinesCompression::Zstd => zstd::stream::encode_all(&data[..], 0)?,
+            };
+            Ok(Some(data))
+        }
+    }
+    impl Drop for JsonLinesWriter {
+        fn drop(&mut self) {
+            if let Err(e) = self.flush() {
+                log::error!("Failed to flush {}: {e}", self.path.display());
+            }
+        }
+    }
+
+    /// Truncates `file` after its last newline.
+    fn truncate_partial_line(file: &mut File) -> io::Result<()> {
+        const BLOCK: u64 = 64 * 1024;
+
+        let len = file.metadata()?.len();
+        let mut end = len;
+        let mut block = vec![];
+        while end > 0 {
+            let start = end.saturating_sub(BLOCK);
+            file.seek(SeekFrom::Start(start))?;
+            block.clear();
+            Read::take(&mut *file, end - start).read_to_end(&mut block)?;
+            if let Some(i) = block.iter().rposition(|&b| b == b'\n') {
+                end = start + u64::try_from(i).unwrap() + 1;
+                break;
+            }
+            end = start;
+        }
+        if end < len {
+            log::warn!("Removing a partial line of {} bytes.", len - end);
+            file.set_len(end)?;
+        }
+        Ok(())
+    }
+    /// Truncates `file` after its last complete gzip member or zstd frame, if the one after it
+    /// runs to the end of the file. Corrupt data followed by more data is returned as an error.
+    fn truncate_partial_member(
+        file: &mut File,
+        compression: JsonLinesCompression,
+    ) -> io::Result<()> {
+        // How the decoders report a partial (or corrupt) member, rather than an IO error.
+        let is_partial = |e: &io::Error| {
+            use io::ErrorKind::*;
+            matches!(e.kind(), UnexpectedEof | InvalidData | Other)
+        };
+
+        let len = file.metadata()?.len();
+        file.seek(SeekFrom::Start(0))?;
+        let mut reader = BufReader::new(&mut *file);
+        let mut end = 0;
+        while !reader.fill_buf()?.is_empty() {
+            let decoded = match compression {
+                JsonLinesCompression::None => unreachable!(),
+                JsonLinesCompression::Gzip => io::copy(
+                    &mut flate2::bufread::GzDecoder::new(&mut reader),
+                    &mut io::sink(),
+                ),
+                JsonLinesCompression::Zstd => zstd::stream::read::Decoder::with_buffer(&mut reader)
+                    .and_then(|decoder| io::copy(&mut decoder.single_frame(), &mut io::sink())),
+            };
+            match decoded {
+                Ok(_) => end = reader.stream_position()?,
+                Err(e) if is_partial(&e) && reader.fill_buf()?.is_empty() => break,
+                Err(e) => return Err(e),
+            }
+        }
+        drop(reader);
+        if end < len {
+            log::warn!("Removing a partial member of {} bytes.", len - end);
+            file.set_len(end)?;
+        }
+        Ok(())
+    }
+}
+
 mod boilerplate {
     use super::*;
 
@@ -188,3 +463,94 @@ mod boilerplate {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+
+    use super::file::*;
+
+    #[tokio::test]
+    async fn test_file() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["records.jsonl", "records.jsonl.gz", "records.jsonl.zst"] {
+            let path = dir.path().join(name);
+
+            let mut writer = JsonLinesWriter::create(&path).unwrap();
+            writer.write(&(1, "a")).unwrap();
+            writer.flush().unwrap();
+            writer.write(&(2, "b")).unwrap();
+            writer.flush_async().await.unwrap();
+            drop(writer);
+
+            let mut writer = JsonLinesWriter::append(&path).unwrap();
+            writer.write(&(3, "c")).unwrap();
+            drop(writer);
+
+            let expected = vec![
+                (1, "a".to_owned()),
+                (2, "b".to_owned()),
+                (3, "c".to_owned()),
+            ];
+            let records: Vec<(u64, String)> =
+                read(&path).unwrap().collect::<Result<_, _>>().unwrap();
+            assert_eq!(records, expected, "{name}");
+            let records: Vec<(u64, String)> = read_async(&path)
+                .await
+                .unwrap()
+                .try_collect()
+                .await
+                .unwrap();
+            assert_eq!(records, expected, "{name}");
+        }
+
+        // An interrupted write.
+        let path = dir.path().join("partial.jsonl");
+        std::fs::write(&path, "[1]\n[2]\n[3").unwrap();
+        let mut writer = JsonLinesWriter::append(&path).unwrap();
+        writer.write(&[4]).unwrap();
+        drop(writer);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "[1]\n[2]\n[4]\n");
+
+        // An interrupted compressed write.
+        for name in ["partial.jsonl.gz", "partial.jsonl.zst"] {
+            let path = dir.path().join(name);
+            let mut writer = JsonLinesWriter::create(&path).unwrap();
+            writer.write(&[1]).unwrap();
+            writer.flush().unwrap();
+            let complete = std::fs::metadata(&path).unwrap().len();
+            writer.write(&[2]).unwrap();
+            writer.write(&[3]).unwrap();
+            drop(writer);
+            let file = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+            let len = file.metadata().unwrap().len();
+            file.set_len(complete + (len - complete) / 2).unwrap();
+            drop(file);
+
+            let mut writer = JsonLinesWriter::append(&path).unwrap();
+            writer.write(&[4]).unwrap();
+            drop(writer);
+            let records: Vec<[u64; 1]> = read(&path).unwrap().collect::<Result<_, _>>().unwrap();
+            assert_eq!(records, [[1], [4]], "{name}");
+        }
+
+        // A corrupt member followed by complete ones is not removed.
+        for name in ["corrupt.jsonl.gz", "corrupt.jsonl.zst"] {
+            let path = dir.path().join(name);
+            let mut writer = JsonLinesWriter::create(&path).unwrap();
+            writer.write(&[1]).unwrap();
+            writer.flush().unwrap();
+            let corrupt = std::fs::metadata(&path).unwrap().len();
+            writer.write(&[2]).unwrap();
+            writer.flush().unwrap();
+            writer.write(&[3]).unwrap();
+            drop(writer);
+            let mut data = std::fs::read(&path).unwrap();
+            data[usize::try_from(corrupt).unwrap()] ^= 0xFF;
+            std::fs::write(&path, &data).unwrap();
+
+            assert!(JsonLinesWriter::append(&path).is_err(), "{name}");
+            assert_eq!(std::fs::read(&path).unwrap(), data, "{name}");
+        }
+    }
+}